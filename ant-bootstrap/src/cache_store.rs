@@ -7,8 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    craft_valid_multiaddr, multiaddr_get_peer_id, BootstrapAddr, BootstrapAddresses,
-    BootstrapCacheConfig, Error, PeersArgs, Result,
+    craft_valid_multiaddr, multiaddr_get_peer_id, sort_by_quality, BootstrapAddr,
+    BootstrapAddresses, BootstrapCacheConfig, Error, PeersArgs, Result,
 };
 use atomic_write_file::AtomicWriteFile;
 use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
@@ -81,10 +81,8 @@ impl CacheData {
 
         self.peers.values_mut().for_each(|bootstrap_addresses| {
             if bootstrap_addresses.0.len() > cfg.max_addrs_per_peer {
-                // sort by lowest failure rate first
-                bootstrap_addresses
-                    .0
-                    .sort_by_key(|addr| addr.failure_rate() as u64);
+                // sort by best quality first
+                sort_by_quality(&mut bootstrap_addresses.0);
                 bootstrap_addresses.0.truncate(cfg.max_addrs_per_peer);
             }
         });
@@ -245,7 +243,7 @@ impl BootstrapCacheStore {
     }
 
     /// Get a list containing single addr per peer. We use the least faulty addr for each peer.
-    /// This list is sorted by the failure rate of the addr.
+    /// This list is sorted by the quality score of the addr, the best first.
     pub fn get_sorted_addrs(&self) -> impl Iterator<Item = &Multiaddr> {
         let mut addrs = self
             .data
@@ -254,7 +252,7 @@ impl BootstrapCacheStore {
             .flat_map(|bootstrap_addresses| bootstrap_addresses.get_least_faulty())
             .collect::<Vec<_>>();
 
-        addrs.sort_by_key(|addr| addr.failure_rate() as u64);
+        sort_by_quality(&mut addrs);
 
         addrs.into_iter().map(|addr| &addr.addr)
     }
@@ -433,6 +431,35 @@ mod tests {
         assert_eq!(peers[0].addr, good_addr);
     }
 
+    #[tokio::test]
+    async fn test_sorted_addrs_prefer_the_reliable_peers() {
+        let (mut store, _) = create_test_store().await;
+        let new_addr: Multiaddr =
+            "/ip4/127.0.0.1/tcp/8080/p2p/12D3KooWRBhwfeP2Y4TCx1SM6s9rUoHhR5STiGwxBhgFRcw3UERE"
+                .parse()
+                .unwrap();
+        let reliable_addr: Multiaddr =
+            "/ip4/127.0.0.1/tcp/8081/p2p/12D3KooWD2aV1f3qkhggzEFaJ24CEFYkSdZF5RKoMLpU6CwExYV5"
+                .parse()
+                .unwrap();
+        let faulty_addr: Multiaddr =
+            "/ip4/127.0.0.1/tcp/8082/p2p/12D3KooWLFDDYDHPWEBCBwGtKLQi3D6FUyLZdTMA8dAvfNHHnwUa"
+                .parse()
+                .unwrap();
+
+        store.add_addr(faulty_addr.clone());
+        store.add_addr(new_addr.clone());
+        store.add_addr(reliable_addr.clone());
+        for _ in 0..3 {
+            store.update_addr_status(&reliable_addr, true);
+            store.update_addr_status(&faulty_addr, false);
+        }
+        store.update_addr_status(&faulty_addr, true);
+
+        let addrs = store.get_sorted_addrs().cloned().collect::<Vec<_>>();
+        assert_eq!(addrs, vec![reliable_addr, new_addr, faulty_addr]);
+    }
+
     #[tokio::test]
     async fn test_peer_not_removed_if_successful() {
         let (mut store, _) = create_test_store().await;
@@ -6,9 +6,14 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{cache_store::CacheData, craft_valid_multiaddr_from_str, BootstrapAddr, Error, Result};
+use crate::{
+    cache_store::CacheData,
+    craft_valid_multiaddr_from_str,
+    signed_contacts::{network_contacts_public_key, SignedContacts, NETWORK_CONTACTS_PK_ENV},
+    BootstrapAddr, Error, Result,
+};
 use futures::stream::{self, StreamExt};
-use libp2p::Multiaddr;
+use libp2p::{identity::ed25519, Multiaddr};
 use reqwest::Client;
 use std::time::Duration;
 use url::Url;
@@ -41,6 +46,11 @@ pub struct ContactsFetcher {
     request_client: Client,
     /// Ignore PeerId in the multiaddr if not present. This is only useful for fetching nat detection contacts
     ignore_peer_id: bool,
+    /// The key the contacts must be signed with. If not set, the contacts are refused unless the
+    /// verification is skipped.
+    verification_key: Option<ed25519::PublicKey>,
+    /// Accept the contacts without verifying their signature, e.g. for a private network.
+    skip_verification: bool,
 }
 
 impl ContactsFetcher {
@@ -64,6 +74,8 @@ impl ContactsFetcher {
             endpoints,
             request_client,
             ignore_peer_id: false,
+            verification_key: network_contacts_public_key()?,
+            skip_verification: false,
         })
     }
 
@@ -91,6 +103,18 @@ impl ContactsFetcher {
         self.ignore_peer_id = ignore_peer_id;
     }
 
+    /// Set the key the contacts must be signed with. Defaults to the network contacts key, see
+    /// [`network_contacts_public_key`].
+    pub fn set_verification_key(&mut self, verification_key: ed25519::PublicKey) {
+        self.verification_key = Some(verification_key);
+    }
+
+    /// Accept the contacts without verifying their signature, e.g. for a private network whose
+    /// contacts are not signed by the network key.
+    pub fn skip_verification(&mut self, skip_verification: bool) {
+        self.skip_verification = skip_verification;
+    }
+
     /// Fetch the list of bootstrap addresses from all configured endpoints
     pub async fn fetch_bootstrap_addresses(&self) -> Result<Vec<BootstrapAddr>> {
         Ok(self
@@ -108,6 +132,17 @@ impl ContactsFetcher {
             self.endpoints.len(),
             self.endpoints
         );
+        let verification_key = match (&self.verification_key, self.skip_verification) {
+            (_, true) => {
+                warn!("Not verifying the signature of the contacts, as asked");
+                None
+            }
+            (Some(key), false) => Some(key),
+            (None, false) => {
+                error!("No network contacts key to verify the contacts with, set {NETWORK_CONTACTS_PK_ENV} or skip the verification");
+                return Err(Error::MissingContactsPublicKey);
+            }
+        };
         let mut bootstrap_addresses = Vec::new();
 
         let mut fetches = stream::iter(self.endpoints.clone())
@@ -121,6 +156,7 @@ impl ContactsFetcher {
                         self.request_client.clone(),
                         &endpoint,
                         self.ignore_peer_id,
+                        verification_key,
                     )
                     .await,
                     endpoint,
@@ -169,6 +205,7 @@ impl ContactsFetcher {
         request_client: Client,
         endpoint: &Url,
         ignore_peer_id: bool,
+        verification_key: Option<&ed25519::PublicKey>,
     ) -> Result<Vec<Multiaddr>> {
         info!("Fetching peers from endpoint: {endpoint}");
         let mut retries = 0;
@@ -181,8 +218,16 @@ impl ContactsFetcher {
                     if response.status().is_success() {
                         let text = response.text().await?;
 
-                        match Self::try_parse_response(&text, ignore_peer_id) {
+                        match Self::try_parse_response(&text, ignore_peer_id, verification_key) {
                             Ok(addrs) => break addrs,
+                            // Fetching again won't make the contacts trustworthy
+                            Err(
+                                err
+                                @ (Error::UnsignedContacts | Error::InvalidContactsSignature(_)),
+                            ) => {
+                                error!("Refusing the contacts from {endpoint}: {err}");
+                                return Err(err);
+                            }
                             Err(err) => {
                                 warn!("Failed to parse response with err: {err:?}");
                                 retries += 1;
@@ -228,8 +273,20 @@ impl ContactsFetcher {
         Ok(bootstrap_addresses)
     }
 
-    /// Try to parse a response from a endpoint
-    fn try_parse_response(response: &str, ignore_peer_id: bool) -> Result<Vec<Multiaddr>> {
+    /// Try to parse a response from a endpoint, once its signature is verified, if a key is provided
+    fn try_parse_response(
+        response: &str,
+        ignore_peer_id: bool,
+        verification_key: Option<&ed25519::PublicKey>,
+    ) -> Result<Vec<Multiaddr>> {
+        let signed = serde_json::from_str::<SignedContacts>(response);
+        let response = match (&signed, verification_key) {
+            (Ok(signed), Some(key)) => signed.verify(key)?,
+            (Ok(signed), None) => signed.contacts.as_str(),
+            (Err(_), Some(_)) => return Err(Error::UnsignedContacts),
+            (Err(_), None) => response,
+        };
+
         match serde_json::from_str::<CacheData>(response) {
             Ok(json_endpoints) => {
                 info!(
@@ -300,6 +357,7 @@ mod tests {
             .await;
 
         let mut fetcher = ContactsFetcher::new().unwrap();
+        fetcher.skip_verification(true);
         fetcher.endpoints = vec![mock_server.uri().parse().unwrap()];
 
         let addrs = fetcher.fetch_bootstrap_addresses().await.unwrap();
@@ -339,6 +397,7 @@ mod tests {
             .await;
 
         let mut fetcher = ContactsFetcher::new().unwrap();
+        fetcher.skip_verification(true);
         fetcher.endpoints = vec![
             mock_server1.uri().parse().unwrap(),
             mock_server2.uri().parse().unwrap(),
@@ -369,6 +428,7 @@ mod tests {
             .await;
 
         let mut fetcher = ContactsFetcher::new().unwrap();
+        fetcher.skip_verification(true);
         fetcher.endpoints = vec![mock_server.uri().parse().unwrap()];
 
         let addrs = fetcher.fetch_bootstrap_addresses().await.unwrap();
@@ -392,6 +452,7 @@ mod tests {
             .await;
 
         let mut fetcher = ContactsFetcher::new().unwrap();
+        fetcher.skip_verification(true);
         fetcher.endpoints = vec![mock_server.uri().parse().unwrap()];
 
         let addrs = fetcher.fetch_bootstrap_addresses().await.unwrap();
@@ -404,6 +465,70 @@ mod tests {
         assert_eq!(addrs[0].addr, addr);
     }
 
+    #[tokio::test]
+    async fn test_only_signed_contacts_are_used_if_a_key_is_set() {
+        let contacts =
+            "/ip4/127.0.0.1/tcp/8080/p2p/12D3KooWD2aV1f3qkhggzEFaJ24CEFYkSdZF5RKoMLpU6CwExYV5";
+        let network_key = ed25519::Keypair::generate();
+        let signed = SignedContacts::sign(&network_key, contacts.to_string());
+
+        let signed_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(serde_json::to_string(&signed).unwrap()),
+            )
+            .mount(&signed_server)
+            .await;
+        let unsigned_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(contacts))
+            .mount(&unsigned_server)
+            .await;
+
+        let mut fetcher =
+            ContactsFetcher::with_endpoints(vec![unsigned_server.uri().parse().unwrap()]).unwrap();
+        fetcher.set_verification_key(network_key.public());
+        assert!(fetcher.fetch_addrs().await.unwrap().is_empty());
+
+        fetcher.endpoints = vec![signed_server.uri().parse().unwrap()];
+        let addrs = fetcher.fetch_addrs().await.unwrap();
+        assert_eq!(addrs, vec![contacts.parse::<Multiaddr>().unwrap()]);
+
+        fetcher.set_verification_key(ed25519::Keypair::generate().public());
+        assert!(fetcher.fetch_addrs().await.unwrap().is_empty());
+
+        fetcher.endpoints = vec![unsigned_server.uri().parse().unwrap()];
+        fetcher.skip_verification(true);
+        let addrs = fetcher.fetch_addrs().await.unwrap();
+        assert_eq!(addrs, vec![contacts.parse::<Multiaddr>().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn test_contacts_are_refused_without_a_key_unless_skipping_the_verification() {
+        let contacts =
+            "/ip4/127.0.0.1/tcp/8080/p2p/12D3KooWD2aV1f3qkhggzEFaJ24CEFYkSdZF5RKoMLpU6CwExYV5";
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(contacts))
+            .mount(&mock_server)
+            .await;
+
+        let mut fetcher =
+            ContactsFetcher::with_endpoints(vec![mock_server.uri().parse().unwrap()]).unwrap();
+        fetcher.verification_key = None;
+        assert!(matches!(
+            fetcher.fetch_addrs().await,
+            Err(Error::MissingContactsPublicKey)
+        ));
+
+        fetcher.skip_verification(true);
+        let addrs = fetcher.fetch_addrs().await.unwrap();
+        assert_eq!(addrs, vec![contacts.parse::<Multiaddr>().unwrap()]);
+    }
+
     #[tokio::test]
     async fn test_custom_endpoints() {
         let endpoints = vec!["http://example.com".parse().unwrap()];
@@ -0,0 +1,171 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{craft_valid_multiaddr_from_str, BootstrapAddr, Error, Result};
+use futures::stream::{self, StreamExt};
+use hickory_resolver::TokioAsyncResolver;
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+
+/// The prefix of the SRV records of a seed domain, e.g. `_autonomi._udp.seeds.example.com`.
+const SRV_PREFIX: &str = "_autonomi._udp";
+
+/// The prefix of the multiaddr in a TXT record, following the libp2p `dnsaddr` convention.
+const TXT_ADDR_PREFIX: &str = "dnsaddr=";
+
+/// The prefix of the peer id in the TXT record of an SRV target.
+const TXT_PEER_ID_PREFIX: &str = "peer_id=";
+
+/// The number of seed domains resolved at once.
+const MAX_CONCURRENT_LOOKUPS: usize = 3;
+
+/// Resolves the bootstrap addresses published in the DNS records of seed domains.
+///
+/// Each seed domain can publish:
+/// - TXT records of full multiaddrs, e.g. `dnsaddr=/ip4/1.2.3.4/udp/1200/quic-v1/p2p/12D3Koo...`
+/// - SRV records at `_autonomi._udp.<domain>`, whose targets have a `peer_id=12D3Koo...` TXT record.
+///   The QUIC address is then built from the IPs of the target and the port of the record.
+///
+/// Unlike the network contacts file, DNS records are cached by the resolvers on the way.
+pub struct DnsSeedResolver {
+    domains: Vec<String>,
+    max_addrs: usize,
+}
+
+impl DnsSeedResolver {
+    pub fn new(domains: Vec<String>) -> Self {
+        Self {
+            domains,
+            max_addrs: usize::MAX,
+        }
+    }
+
+    /// Set the number of addrs to resolve, at most.
+    pub fn set_max_addrs(&mut self, max_addrs: usize) {
+        self.max_addrs = max_addrs;
+    }
+
+    /// Resolve the addrs of all the seed domains. Fails if none of them resolved to any addr.
+    pub async fn resolve_bootstrap_addresses(&self) -> Result<Vec<BootstrapAddr>> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf().map_err(|err| {
+            error!("Failed to read the system DNS config: {err}");
+            Error::FailedToResolveDnsSeeds
+        })?;
+
+        let mut addrs: Vec<Multiaddr> = stream::iter(&self.domains)
+            .map(|domain| resolve_domain(&resolver, domain))
+            .buffer_unordered(MAX_CONCURRENT_LOOKUPS)
+            .flat_map(stream::iter)
+            .collect()
+            .await;
+        addrs.dedup();
+        addrs.truncate(self.max_addrs);
+
+        if addrs.is_empty() {
+            warn!(
+                "No bootstrap addrs found in the DNS seeds {:?}",
+                self.domains
+            );
+            return Err(Error::FailedToResolveDnsSeeds);
+        }
+        info!(
+            "Resolved {} bootstrap addrs from the DNS seeds {:?}",
+            addrs.len(),
+            self.domains
+        );
+        Ok(addrs.into_iter().map(BootstrapAddr::new).collect())
+    }
+}
+
+/// The addrs of the TXT and SRV records of the domain, if any.
+async fn resolve_domain(resolver: &TokioAsyncResolver, domain: &str) -> Vec<Multiaddr> {
+    let mut addrs = vec![];
+
+    match resolver.txt_lookup(domain).await {
+        Ok(lookup) => {
+            addrs.extend(
+                lookup
+                    .iter()
+                    .filter_map(|txt| parse_txt_addr(&txt.to_string())),
+            );
+        }
+        Err(err) => debug!("No TXT record resolved for the DNS seed {domain}: {err}"),
+    }
+
+    let srv_name = format!("{SRV_PREFIX}.{domain}");
+    match resolver.srv_lookup(srv_name.as_str()).await {
+        Ok(lookup) => {
+            for srv in lookup.iter() {
+                let target = srv.target().to_utf8();
+                let peer_id = match resolver.txt_lookup(target.as_str()).await {
+                    Ok(lookup) => lookup
+                        .iter()
+                        .find_map(|txt| parse_txt_peer_id(&txt.to_string())),
+                    Err(err) => {
+                        debug!("No TXT record resolved for the SRV target {target}: {err}");
+                        None
+                    }
+                };
+                let Some(peer_id) = peer_id else {
+                    debug!("No peer id found for the SRV target {target}, skipping it");
+                    continue;
+                };
+                match resolver.lookup_ip(target.as_str()).await {
+                    Ok(ips) => addrs.extend(ips.iter().map(|ip| {
+                        Multiaddr::from(ip)
+                            .with(Protocol::Udp(srv.port()))
+                            .with(Protocol::QuicV1)
+                            .with(Protocol::P2p(peer_id))
+                    })),
+                    Err(err) => debug!("Failed to resolve the SRV target {target}: {err}"),
+                }
+            }
+        }
+        Err(err) => debug!("No SRV record resolved for {srv_name}: {err}"),
+    }
+
+    addrs
+}
+
+/// The multiaddr of a TXT record, with or without the `dnsaddr=` prefix.
+fn parse_txt_addr(txt: &str) -> Option<Multiaddr> {
+    let txt = txt.trim();
+    let addr = txt.strip_prefix(TXT_ADDR_PREFIX).unwrap_or(txt);
+    craft_valid_multiaddr_from_str(addr, false)
+}
+
+/// The peer id of a `peer_id=` TXT record.
+fn parse_txt_peer_id(txt: &str) -> Option<PeerId> {
+    txt.trim().strip_prefix(TXT_PEER_ID_PREFIX)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn txt_records_are_parsed_into_bootstrap_addrs() {
+        let peer_id = "12D3KooWRBhwfeP2Y4TCx1SM6s9rUoHhR5STiGwxBhgFRcw3UERE";
+        let addr = format!("/ip4/203.0.113.7/udp/1200/quic-v1/p2p/{peer_id}");
+
+        assert_eq!(
+            parse_txt_addr(&format!("dnsaddr={addr}")).map(|addr| addr.to_string()),
+            Some(addr.clone())
+        );
+        assert_eq!(
+            parse_txt_addr(&addr).map(|addr| addr.to_string()),
+            Some(addr)
+        );
+        assert_eq!(parse_txt_addr("v=spf1 -all"), None);
+
+        assert_eq!(
+            parse_txt_peer_id(&format!("peer_id={peer_id}")).map(|peer_id| peer_id.to_string()),
+            Some(peer_id.to_string())
+        );
+        assert_eq!(parse_txt_peer_id(peer_id), None);
+    }
+}
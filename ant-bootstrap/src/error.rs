@@ -20,6 +20,16 @@ pub enum Error {
     InvalidBootstrapCacheDir,
     #[error("Could not obtain bootstrap addresses from {0} after {1} retries")]
     FailedToObtainAddrsFromUrl(String, usize),
+    #[error("Could not resolve any bootstrap addresses from the DNS seeds")]
+    FailedToResolveDnsSeeds,
+    #[error("The network contacts are not signed")]
+    UnsignedContacts,
+    #[error("Invalid signature of the network contacts: {0}")]
+    InvalidContactsSignature(String),
+    #[error("No network contacts public key to verify the network contacts with")]
+    MissingContactsPublicKey,
+    #[error("Invalid network contacts public key: {0}")]
+    InvalidContactsPublicKey(String),
     #[error("Failed to parse Url")]
     FailedToParseUrl,
     #[error("IO error: {0}")]
@@ -6,11 +6,13 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::DnsSeedResolver;
 use crate::{
     config::cache_file_name,
     craft_valid_multiaddr, craft_valid_multiaddr_from_str,
     error::{Error, Result},
-    BootstrapAddr, BootstrapCacheConfig, BootstrapCacheStore, ContactsFetcher,
+    sort_by_quality, BootstrapAddr, BootstrapCacheConfig, BootstrapCacheStore, ContactsFetcher,
 };
 use clap::Args;
 use libp2p::Multiaddr;
@@ -53,6 +55,26 @@ pub struct PeersArgs {
     /// a bootstrap cache JSON file.
     #[clap(long, conflicts_with = "first", value_delimiter = ',')]
     pub network_contacts_url: Vec<String>,
+    /// Specify the domain(s) to resolve the bootstrap addrs from, through DNS.
+    ///
+    /// The TXT records of a domain can hold multiaddrs, optionally prefixed with `dnsaddr=`. Its
+    /// `_autonomi._udp` SRV records can point to hosts with a `peer_id=<peer id>` TXT record.
+    #[clap(
+        long = "dns-seed",
+        value_name = "domain",
+        conflicts_with = "first",
+        value_delimiter = ','
+    )]
+    #[serde(default)]
+    pub dns_seeds: Vec<String>,
+    /// Set to use the network contacts without verifying they are signed with the network key.
+    ///
+    /// This is meant for private networks, whose contacts files are not signed by the network key.
+    /// Without it, the contacts are refused if no network key is set through `NETWORK_CONTACTS_PK`,
+    /// at build time or at runtime.
+    #[clap(long, default_value = "false")]
+    #[serde(default)]
+    pub skip_contacts_verification: bool,
     /// Set to indicate this is a local network. You could also set the `local` feature flag to set this to true.
     ///
     /// This would use mDNS for peer discovery.
@@ -79,7 +101,7 @@ pub struct PeersArgs {
 }
 
 impl PeersArgs {
-    /// Get bootstrap peers sorted by the failure rate. The peer with the lowest failure rate will be
+    /// Get bootstrap peers sorted by the quality score. The peer most likely to be reachable will be
     /// the first in the list.
     pub async fn get_addrs(
         &self,
@@ -94,8 +116,10 @@ impl PeersArgs {
             .collect())
     }
 
-    /// Get bootstrap peers sorted by the failure rate. The peer with the lowest failure rate will be
+    /// Get bootstrap peers sorted by the quality score. The peer most likely to be reachable will be
     /// the first in the list.
+    ///
+    /// The cached peers are preferred, and are used on their own if the network contacts can't be fetched.
     pub async fn get_bootstrap_addr(
         &self,
         config: Option<BootstrapCacheConfig>,
@@ -134,7 +158,7 @@ impl PeersArgs {
 
         if let Some(count) = count {
             if bootstrap_addresses.len() >= count {
-                bootstrap_addresses.sort_by_key(|addr| addr.failure_rate() as u64);
+                sort_by_quality(&mut bootstrap_addresses);
                 bootstrap_addresses.truncate(count);
                 info!("Returning early as enough bootstrap addresses are found");
                 return Ok(bootstrap_addresses);
@@ -158,13 +182,13 @@ impl PeersArgs {
                         addrs
                             .0
                             .into_iter()
-                            .min_by_key(|addr| addr.failure_rate() as u64)
+                            .max_by(|a, b| a.quality_score().total_cmp(&b.quality_score()))
                     });
                     bootstrap_addresses.extend(from_cache);
 
                     if let Some(count) = count {
                         if bootstrap_addresses.len() >= count {
-                            bootstrap_addresses.sort_by_key(|addr| addr.failure_rate() as u64);
+                            sort_by_quality(&mut bootstrap_addresses);
                             bootstrap_addresses.truncate(count);
                             info!("Returning early as enough bootstrap addresses are found");
                             return Ok(bootstrap_addresses);
@@ -188,15 +212,56 @@ impl PeersArgs {
                 .map(|url| url.parse::<Url>().map_err(|_| Error::FailedToParseUrl))
                 .collect::<Result<Vec<Url>>>()?;
             let mut contacts_fetcher = ContactsFetcher::with_endpoints(addrs)?;
+            contacts_fetcher.skip_verification(self.skip_contacts_verification);
             if let Some(count) = count {
                 contacts_fetcher.set_max_addrs(count);
             }
-            let addrs = contacts_fetcher.fetch_bootstrap_addresses().await?;
-            bootstrap_addresses.extend(addrs);
+            match contacts_fetcher.fetch_bootstrap_addresses().await {
+                Ok(addrs) => bootstrap_addresses.extend(addrs),
+                Err(err) if !bootstrap_addresses.is_empty() => {
+                    warn!("Failed to fetch the network contacts, using the cached peers: {err}");
+                }
+                Err(err) => return Err(err),
+            }
+
+            if let Some(count) = count {
+                if bootstrap_addresses.len() >= count {
+                    sort_by_quality(&mut bootstrap_addresses);
+                    bootstrap_addresses.truncate(count);
+                    info!("Returning early as enough bootstrap addresses are found");
+                    return Ok(bootstrap_addresses);
+                }
+            }
+        }
+
+        // If we have DNS seeds, resolve addrs from their records.
+        if !self.dns_seeds.is_empty() {
+            info!(
+                "Resolving bootstrap addresses from DNS seeds: {:?}",
+                self.dns_seeds
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let mut resolver = DnsSeedResolver::new(self.dns_seeds.clone());
+                if let Some(count) = count {
+                    resolver.set_max_addrs(count);
+                }
+                match resolver.resolve_bootstrap_addresses().await {
+                    Ok(addrs) => bootstrap_addresses.extend(addrs),
+                    Err(err) if !bootstrap_addresses.is_empty() => {
+                        warn!(
+                            "Failed to resolve the DNS seeds, using the peers found so far: {err}"
+                        );
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            warn!("DNS seeds can't be resolved from a browser, skipping them");
 
             if let Some(count) = count {
                 if bootstrap_addresses.len() >= count {
-                    bootstrap_addresses.sort_by_key(|addr| addr.failure_rate() as u64);
+                    sort_by_quality(&mut bootstrap_addresses);
                     bootstrap_addresses.truncate(count);
                     info!("Returning early as enough bootstrap addresses are found");
                     return Ok(bootstrap_addresses);
@@ -206,15 +271,21 @@ impl PeersArgs {
 
         if !self.disable_mainnet_contacts {
             let mut contacts_fetcher = ContactsFetcher::with_mainnet_endpoints()?;
+            contacts_fetcher.skip_verification(self.skip_contacts_verification);
             if let Some(count) = count {
                 contacts_fetcher.set_max_addrs(count);
             }
-            let addrs = contacts_fetcher.fetch_bootstrap_addresses().await?;
-            bootstrap_addresses.extend(addrs);
+            match contacts_fetcher.fetch_bootstrap_addresses().await {
+                Ok(addrs) => bootstrap_addresses.extend(addrs),
+                Err(err) if !bootstrap_addresses.is_empty() => {
+                    warn!("Failed to fetch the mainnet contacts, using the cached peers: {err}");
+                }
+                Err(err) => return Err(err),
+            }
         }
 
         if !bootstrap_addresses.is_empty() {
-            bootstrap_addresses.sort_by_key(|addr| addr.failure_rate() as u64);
+            sort_by_quality(&mut bootstrap_addresses);
             if let Some(count) = count {
                 bootstrap_addresses.truncate(count);
             }
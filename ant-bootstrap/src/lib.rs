@@ -16,7 +16,7 @@
 //! - Shared Cache: System-wide cache file accessible by both nodes and clients
 //! - Concurrent Access: File locking for safe multi-process access
 //! - Atomic Operations: Safe cache updates using atomic file operations
-//! - Initial Peer Discovery: Fallback web endpoints for new/stale cache scenarios
+//! - Initial Peer Discovery: Fallback web endpoints and DNS seeds for new/stale cache scenarios
 
 #[macro_use]
 extern crate tracing;
@@ -24,20 +24,26 @@ extern crate tracing;
 mod cache_store;
 pub mod config;
 pub mod contacts;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dns_seeds;
 pub mod error;
 mod initial_peers;
+pub mod signed_contacts;
 
 use ant_protocol::version::{get_network_id, get_truncate_version_str};
 use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::{borrow::Borrow, time::SystemTime};
 use thiserror::Error;
 
 pub use cache_store::BootstrapCacheStore;
 pub use config::BootstrapCacheConfig;
 pub use contacts::ContactsFetcher;
+#[cfg(not(target_arch = "wasm32"))]
+pub use dns_seeds::DnsSeedResolver;
 pub use error::{Error, Result};
 pub use initial_peers::{PeersArgs, ANT_PEERS_ENV};
+pub use signed_contacts::{SignedContacts, NETWORK_CONTACTS_PK_ENV};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Set of addresses for a particular PeerId
@@ -64,8 +70,11 @@ impl BootstrapAddresses {
             .find(|bootstrap_addr| &bootstrap_addr.addr == addr)
     }
 
+    /// The addr with the best quality score, see [`BootstrapAddr::quality_score`].
     pub fn get_least_faulty(&self) -> Option<&BootstrapAddr> {
-        self.0.iter().min_by_key(|addr| addr.failure_rate() as u64)
+        self.0
+            .iter()
+            .max_by(|a, b| a.quality_score().total_cmp(&b.quality_score()))
     }
 
     pub fn remove_addr(&mut self, addr: &Multiaddr) {
@@ -137,8 +146,8 @@ impl BootstrapAddr {
                 self.success_count = 1;
                 self.failure_count = 0;
             }
+            self.last_seen = SystemTime::now();
         }
-        self.last_seen = SystemTime::now();
         if !success {
             if let Some(new_value) = self.failure_count.checked_add(1) {
                 self.failure_count = new_value;
@@ -176,15 +185,30 @@ impl BootstrapAddr {
         trace!("Successfully synced BootstrapAddr: {self:?}");
     }
 
-    fn failure_rate(&self) -> f64 {
-        if self.success_count + self.failure_count == 0 {
-            0.0
-        } else {
-            self.failure_count as f64 / (self.success_count + self.failure_count) as f64
-        }
+    /// The likelihood of connecting to this addr, from 0 to 1.
+    ///
+    /// This is the success rate of the connections, starting at 0.5 for a new addr, which halves
+    /// for each day since the last successful connection, as peers move or go offline.
+    pub fn quality_score(&self) -> f64 {
+        let attempts = self.success_count as f64 + self.failure_count as f64;
+        let success_rate = (self.success_count as f64 + 1.0) / (attempts + 2.0);
+        let days_since_seen = SystemTime::now()
+            .duration_since(self.last_seen)
+            .map(|duration| duration.as_secs_f64() / (24.0 * 60.0 * 60.0))
+            .unwrap_or_default();
+        success_rate * 0.5_f64.powf(days_since_seen)
     }
 }
 
+/// Sort the addrs by their quality score, the best first.
+pub(crate) fn sort_by_quality<T: Borrow<BootstrapAddr>>(addrs: &mut [T]) {
+    addrs.sort_by(|a, b| {
+        b.borrow()
+            .quality_score()
+            .total_cmp(&a.borrow().quality_score())
+    });
+}
+
 /// Craft a proper address to avoid any ill formed addresses
 ///
 /// ignore_peer_id is only used for nat-detection contact list
@@ -0,0 +1,90 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Error, Result};
+use libp2p::identity::ed25519;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable to set the hex encoded public key the network contacts are signed with.
+pub const NETWORK_CONTACTS_PK_ENV: &str = "NETWORK_CONTACTS_PK";
+const NETWORK_CONTACTS_PK_BUILD_TIME_VAL: Option<&str> = option_env!("NETWORK_CONTACTS_PK");
+
+/// A network contacts file, signed with the network contacts key.
+///
+/// The `contacts` hold the content of an unsigned file, i.e. a multiaddr per line or a bootstrap
+/// cache JSON, so that the signature is checked against the exact bytes that get parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedContacts {
+    pub contacts: String,
+    /// The hex encoded ed25519 signature of the contacts
+    pub signature: String,
+}
+
+impl SignedContacts {
+    pub fn sign(keypair: &ed25519::Keypair, contacts: String) -> Self {
+        let signature = hex::encode(keypair.sign(contacts.as_bytes()));
+        Self {
+            contacts,
+            signature,
+        }
+    }
+
+    /// The contacts, if they were signed with the key.
+    pub fn verify(&self, public_key: &ed25519::PublicKey) -> Result<&str> {
+        let signature = hex::decode(&self.signature)
+            .map_err(|_| Error::InvalidContactsSignature(self.signature.clone()))?;
+        if public_key.verify(self.contacts.as_bytes(), &signature) {
+            Ok(&self.contacts)
+        } else {
+            Err(Error::InvalidContactsSignature(self.signature.clone()))
+        }
+    }
+}
+
+/// The key the network contacts are signed with, from the `NETWORK_CONTACTS_PK` env var at runtime
+/// or else at build time.
+pub fn network_contacts_public_key() -> Result<Option<ed25519::PublicKey>> {
+    let Some(key_hex) = std::env::var(NETWORK_CONTACTS_PK_ENV)
+        .ok()
+        .or_else(|| NETWORK_CONTACTS_PK_BUILD_TIME_VAL.map(|key| key.to_string()))
+    else {
+        return Ok(None);
+    };
+    parse_public_key(&key_hex).map(Some)
+}
+
+/// Parse a hex encoded ed25519 public key.
+pub fn parse_public_key(key_hex: &str) -> Result<ed25519::PublicKey> {
+    hex::decode(key_hex.trim())
+        .ok()
+        .and_then(|bytes| ed25519::PublicKey::try_from_bytes(&bytes).ok())
+        .ok_or_else(|| Error::InvalidContactsPublicKey(key_hex.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contacts_are_only_used_if_signed_with_the_network_key() -> Result<()> {
+        let network_key = ed25519::Keypair::generate();
+        let contacts =
+            "/ip4/127.0.0.1/udp/8080/quic-v1/p2p/12D3KooWRBhwfeP2Y4TCx1SM6s9rUoHhR5STiGwxBhgFRcw3UERE";
+        let mut signed = SignedContacts::sign(&network_key, contacts.to_string());
+
+        let public_key = parse_public_key(&hex::encode(network_key.public().to_bytes()))?;
+        assert_eq!(signed.verify(&public_key)?, contacts);
+
+        let other_key = ed25519::Keypair::generate().public();
+        assert!(signed.verify(&other_key).is_err());
+
+        signed.contacts.push_str("\n/ip4/10.0.0.1/udp/8080/quic-v1");
+        assert!(signed.verify(&public_key).is_err());
+        Ok(())
+    }
+}
@@ -46,6 +46,8 @@ async fn test_multiaddr_format_parsing() -> Result<(), Box<dyn std::error::Error
             first: false,
             addrs: vec![addr.clone()],
             network_contacts_url: vec![],
+            dns_seeds: vec![],
+            skip_contacts_verification: false,
             local: false,
             disable_mainnet_contacts: true,
             ignore_cache: true,
@@ -84,6 +86,8 @@ async fn test_network_contacts_format() -> Result<(), Box<dyn std::error::Error>
         first: false,
         addrs: vec![],
         network_contacts_url: vec![format!("{}/peers", mock_server.uri()).parse()?],
+        dns_seeds: vec![],
+        skip_contacts_verification: true,
         local: false,
         disable_mainnet_contacts: true,
         ignore_cache: true,
@@ -32,6 +32,8 @@ async fn test_first_flag() -> Result<(), Box<dyn std::error::Error>> {
         first: true,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -58,6 +60,8 @@ async fn test_peer_argument() -> Result<(), Box<dyn std::error::Error>> {
         first: false,
         addrs: vec![peer_addr.clone()],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: true,
         ignore_cache: false,
@@ -93,6 +97,8 @@ async fn test_network_contacts_fallback() -> Result<(), Box<dyn std::error::Erro
         first: false,
         addrs: vec![],
         network_contacts_url: vec![format!("{}/peers", mock_server.uri()).parse()?],
+        dns_seeds: vec![],
+        skip_contacts_verification: true,
         local: false,
         disable_mainnet_contacts: true,
         ignore_cache: true,
@@ -124,6 +130,8 @@ async fn test_local_mode() -> Result<(), Box<dyn std::error::Error>> {
         first: false,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: true,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -160,6 +168,8 @@ async fn test_test_network_peers() -> Result<(), Box<dyn std::error::Error>> {
         first: false,
         addrs: vec![peer_addr.clone()],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: true,
         ignore_cache: false,
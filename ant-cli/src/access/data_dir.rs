@@ -6,17 +6,19 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::profile::{get_client_base_dir_path, selected_profile};
 use color_eyre::{
-    eyre::{eyre, Context, Result},
+    eyre::{Context, Result},
     Section,
 };
 use std::path::PathBuf;
 
+/// The data dir of the selected profile, or the client data dir without one.
 pub fn get_client_data_dir_path() -> Result<PathBuf> {
-    let mut home_dirs = dirs_next::data_dir()
-        .ok_or_else(|| eyre!("Failed to obtain data dir, your OS might not be supported."))?;
-    home_dirs.push("autonomi");
-    home_dirs.push("client");
+    let home_dirs = match selected_profile() {
+        Some(selected) => selected.data_dir.clone(),
+        None => get_client_base_dir_path()?,
+    };
     std::fs::create_dir_all(home_dirs.as_path())
         .wrap_err("Failed to create data dir")
         .with_suggestion(|| {
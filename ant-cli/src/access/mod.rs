@@ -9,4 +9,5 @@
 pub mod data_dir;
 pub mod keys;
 pub mod network;
+pub mod profile;
 pub mod user_data;
@@ -0,0 +1,195 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_bootstrap::PeersArgs;
+use color_eyre::{
+    eyre::{eyre, Context, Result},
+    Section,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The environment variable to select a profile with, when `--profile` isn't given.
+pub const PROFILE_ENV: &str = "AUTONOMI_PROFILE";
+
+const PROFILES_FILENAME: &str = "profiles.json";
+
+/// A named network to connect to, with its own state, see `ant profile`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    /// How to find the peers of the network, unless others are given on the command line.
+    pub peers: PeersArgs,
+    /// The ID of the network, unless another is given on the command line.
+    pub network_id: Option<u8>,
+    /// Where the wallets are kept, `wallets` in the data dir of the profile by default.
+    pub wallet_dir: Option<PathBuf>,
+    /// Where the keys, the user data and the upload sessions are kept, `profiles/<name>` in the client data dir by
+    /// default.
+    pub data_dir: Option<PathBuf>,
+}
+
+/// The profile the CLI was run with, its directories resolved.
+#[derive(Debug)]
+pub struct SelectedProfile {
+    pub name: String,
+    pub profile: NetworkProfile,
+    pub data_dir: PathBuf,
+    pub wallet_dir: PathBuf,
+}
+
+static SELECTED_PROFILE: OnceLock<SelectedProfile> = OnceLock::new();
+
+/// The dir all the profiles are kept in, along with the state of the CLI run without one.
+pub fn get_client_base_dir_path() -> Result<PathBuf> {
+    let mut home_dirs = dirs_next::data_dir()
+        .ok_or_else(|| eyre!("Failed to obtain data dir, your OS might not be supported."))?;
+    home_dirs.push("autonomi");
+    home_dirs.push("client");
+    Ok(home_dirs)
+}
+
+fn profiles_path() -> Result<PathBuf> {
+    Ok(get_client_base_dir_path()?.join(PROFILES_FILENAME))
+}
+
+fn read_profiles(path: &Path) -> Result<BTreeMap<String, NetworkProfile>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let bytes = std::fs::read(path).wrap_err("Failed to read the profiles")?;
+    serde_json::from_slice(&bytes)
+        .wrap_err("Failed to parse the profiles")
+        .with_suggestion(|| format!("make sure the profiles file is valid JSON: {path:?}"))
+}
+
+fn write_profiles(path: &Path, profiles: &BTreeMap<String, NetworkProfile>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).wrap_err("Failed to create data dir")?;
+    }
+    let bytes = serde_json::to_vec_pretty(profiles)?;
+    std::fs::write(path, bytes).wrap_err("Failed to write the profiles")
+}
+
+pub fn load_profiles() -> Result<BTreeMap<String, NetworkProfile>> {
+    read_profiles(&profiles_path()?)
+}
+
+pub fn save_profile(name: &str, profile: NetworkProfile) -> Result<()> {
+    let path = profiles_path()?;
+    let mut profiles = read_profiles(&path)?;
+    let _ = profiles.insert(name.to_string(), profile);
+    write_profiles(&path, &profiles)
+}
+
+/// Remove the profile, returning whether there was one. Its state is left on disk.
+pub fn remove_profile(name: &str) -> Result<bool> {
+    let path = profiles_path()?;
+    let mut profiles = read_profiles(&path)?;
+    if profiles.remove(name).is_none() {
+        return Ok(false);
+    }
+    write_profiles(&path, &profiles)?;
+    Ok(true)
+}
+
+impl NetworkProfile {
+    /// The directories of the profile of the given name, kept apart from the other profiles unless set otherwise.
+    fn resolve(self, name: &str, base_dir: &Path) -> SelectedProfile {
+        let data_dir = self
+            .data_dir
+            .clone()
+            .unwrap_or_else(|| base_dir.join("profiles").join(name));
+        let wallet_dir = self
+            .wallet_dir
+            .clone()
+            .unwrap_or_else(|| data_dir.join("wallets"));
+        let mut profile = self;
+        // Networks sharing an ID would share the bootstrap cache otherwise.
+        if profile.peers.bootstrap_cache_dir.is_none() {
+            profile.peers.bootstrap_cache_dir = Some(data_dir.join("bootstrap_cache"));
+        }
+        SelectedProfile {
+            name: name.to_string(),
+            profile,
+            data_dir,
+            wallet_dir,
+        }
+    }
+}
+
+/// Select the profile of the given name for the rest of the run.
+pub fn select_profile(name: &str) -> Result<&'static SelectedProfile> {
+    let profile = load_profiles()?
+        .remove(name)
+        .ok_or_else(|| eyre!("No profile named {name:?}"))
+        .with_suggestion(|| {
+            "create it with `ant profile create`, or list them with `ant profile list`"
+        })?;
+    let selected = profile.resolve(name, &get_client_base_dir_path()?);
+    info!(
+        "Using the profile {name:?}, with its data in {:?}",
+        selected.data_dir
+    );
+    Ok(SELECTED_PROFILE.get_or_init(|| selected))
+}
+
+pub fn selected_profile() -> Option<&'static SelectedProfile> {
+    SELECTED_PROFILE.get()
+}
+
+/// The peers options of the selected profile, unless others were given on the command line.
+pub fn peers_args(peers: PeersArgs) -> PeersArgs {
+    match selected_profile() {
+        Some(selected) if peers == PeersArgs::default() => selected.profile.peers.clone(),
+        _ => peers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_keep_their_state_apart() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dir = temp_dir.path().to_path_buf();
+        let path = dir.join(PROFILES_FILENAME);
+        let testnet = NetworkProfile {
+            peers: PeersArgs {
+                addrs: vec!["/ip4/10.0.0.1/udp/1200/quic-v1".parse()?],
+                disable_mainnet_contacts: true,
+                ..Default::default()
+            },
+            network_id: Some(2),
+            ..Default::default()
+        };
+        let mut profiles = BTreeMap::new();
+        let _ = profiles.insert("testnet".to_string(), testnet.clone());
+        write_profiles(&path, &profiles)?;
+        assert_eq!(read_profiles(&path)?, profiles);
+
+        let selected = testnet.resolve("testnet", &dir);
+        assert_eq!(selected.data_dir, dir.join("profiles").join("testnet"));
+        assert_eq!(selected.wallet_dir, selected.data_dir.join("wallets"));
+        assert_eq!(
+            selected.profile.peers.bootstrap_cache_dir,
+            Some(selected.data_dir.join("bootstrap_cache"))
+        );
+
+        let shared_wallets = NetworkProfile {
+            wallet_dir: Some(dir.join("wallets")),
+            ..Default::default()
+        }
+        .resolve("local", &dir);
+        assert_eq!(shared_wallets.wallet_dir, dir.join("wallets"));
+        assert_ne!(shared_wallets.data_dir, selected.data_dir);
+        Ok(())
+    }
+}
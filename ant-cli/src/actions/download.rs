@@ -18,6 +18,7 @@ use color_eyre::{
     eyre::{eyre, Context, Result},
     Section,
 };
+use serde_json::json;
 use std::path::PathBuf;
 
 pub async fn download(addr: &str, dest_path: &str, client: &mut Client) -> Result<()> {
@@ -51,27 +52,25 @@ async fn download_private(
     let mut all_errs = vec![];
     for (path, access, _meta) in archive.iter() {
         progress_bar.println(format!("Fetching file: {path:?}..."));
-        let bytes = match client.data_get(access.clone()).await {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                let err = format!("Failed to fetch file {path:?}: {e}");
-                all_errs.push(err);
-                continue;
-            }
-        };
-
-        let path = PathBuf::from(dest_path).join(path);
-        let here = PathBuf::from(".");
-        let parent = path.parent().unwrap_or_else(|| &here);
-        std::fs::create_dir_all(parent)?;
-        std::fs::write(path, bytes)?;
+        // Interrupted downloads resume from the chunks already written on the next attempt.
+        let file_path = PathBuf::from(dest_path).join(path);
+        if let Err(e) = client.file_download(access.clone(), file_path).await {
+            let err = format!("Failed to fetch file {path:?}: {e}");
+            all_errs.push(err);
+            continue;
+        }
         progress_bar.clone().inc(1);
     }
     progress_bar.finish_and_clear();
 
     if all_errs.is_empty() {
         info!("Successfully downloaded private data with local address: {addr}");
-        println!("Successfully downloaded private data with local address: {addr}");
+        status!("Successfully downloaded private data with local address: {addr}");
+        crate::output::json_result(json!({
+            "address": addr,
+            "destination": dest_path,
+            "files_downloaded": archive.iter().count(),
+        }));
         Ok(())
     } else {
         let err_no = all_errs.len();
@@ -97,27 +96,25 @@ async fn download_public(
     let mut all_errs = vec![];
     for (path, addr, _meta) in archive.iter() {
         progress_bar.println(format!("Fetching file: {path:?}..."));
-        let bytes = match client.data_get_public(*addr).await {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                let err = format!("Failed to fetch file {path:?}: {e}");
-                all_errs.push(err);
-                continue;
-            }
-        };
-
-        let path = PathBuf::from(dest_path).join(path);
-        let here = PathBuf::from(".");
-        let parent = path.parent().unwrap_or_else(|| &here);
-        std::fs::create_dir_all(parent)?;
-        std::fs::write(path, bytes)?;
+        // Interrupted downloads resume from the chunks already written on the next attempt.
+        let file_path = PathBuf::from(dest_path).join(path);
+        if let Err(e) = client.file_download_public(*addr, file_path).await {
+            let err = format!("Failed to fetch file {path:?}: {e}");
+            all_errs.push(err);
+            continue;
+        }
         progress_bar.clone().inc(1);
     }
     progress_bar.finish_and_clear();
 
     if all_errs.is_empty() {
         info!("Successfully downloaded data at: {addr}");
-        println!("Successfully downloaded data at: {addr}");
+        status!("Successfully downloaded data at: {addr}");
+        crate::output::json_result(json!({
+            "address": addr.to_string(),
+            "destination": dest_path,
+            "files_downloaded": archive.iter().count(),
+        }));
         Ok(())
     } else {
         let err_no = all_errs.len();
@@ -6,7 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod daemon;
 mod file;
+mod gateway;
+#[cfg(all(feature = "fuse", unix))]
+mod mount;
+mod profile;
 mod register;
 mod vault;
 mod wallet;
@@ -14,6 +19,8 @@ mod wallet;
 use crate::opt::Opt;
 use clap::Subcommand;
 use color_eyre::Result;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
 
 #[derive(Subcommand, Debug)]
 pub enum SubCmd {
@@ -40,6 +47,62 @@ pub enum SubCmd {
         #[command(subcommand)]
         command: WalletCmd,
     },
+
+    /// Operations related to the network profiles, to switch between networks with `--profile`.
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCmd,
+    },
+
+    /// Run a daemon keeping a client connected to the network, serving its operations over gRPC.
+    ///
+    /// The local applications share the connection, the wallet and the register key of the daemon instead of each
+    /// bootstrapping their own client. Anyone able to connect to the daemon can spend from its wallet, so it only
+    /// listens on a loopback address unless its callers are required to present a token.
+    Daemon {
+        /// The address to listen on for RPCs.
+        #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+        address: IpAddr,
+        /// The port to listen on for RPCs.
+        #[arg(long, default_value_t = daemon::CLIENT_DAEMON_DEFAULT_PORT)]
+        port: u16,
+        /// Require the callers to present the token read from this file, as an `authorization: Bearer TOKEN`
+        /// header. Required to listen on another address than a loopback one.
+        #[arg(long)]
+        rpc_token_file: Option<PathBuf>,
+        /// The directory the files are uploaded from and downloaded to, the paths of the directory RPCs being
+        /// relative to it. The directory RPCs are refused without one.
+        #[arg(long)]
+        files_dir: Option<PathBuf>,
+    },
+
+    /// Serve the public data of the network over HTTP, for browsers and other HTTP clients.
+    ///
+    /// `GET /<data address>` serves the data, or the listing of its files if it's a public archive, and
+    /// `GET /<archive address>/<path>` serves a file of a public archive. The gateway is read only.
+    Gateway {
+        /// The address to listen on for HTTP requests.
+        #[arg(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+        address: IpAddr,
+        /// The port to listen on for HTTP requests.
+        #[arg(long, default_value_t = gateway::GATEWAY_DEFAULT_PORT)]
+        port: u16,
+        /// The size of the in-memory cache of the fetched chunks, in MiB.
+        #[arg(long, default_value_t = gateway::GATEWAY_DEFAULT_CACHE_SIZE_MB)]
+        cache_size: usize,
+    },
+
+    /// Mount a public archive read-only through FUSE, until unmounted.
+    ///
+    /// The folders and files of the archive are listed without fetching them, the files being fetched from the
+    /// network as they're read.
+    #[cfg(all(feature = "fuse", unix))]
+    Mount {
+        /// The address of the public archive.
+        addr: String,
+        /// The empty directory to mount the archive at.
+        mountpoint: PathBuf,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -50,6 +113,12 @@ pub enum FileCmd {
         file: String,
     },
 
+    /// Estimate the cost to upload a file, with how it splits between the nodes storing it, without paying.
+    Estimate {
+        /// The file or directory to estimate the cost for.
+        file: String,
+    },
+
     /// Upload a file and pay for it. Data on the Network is private by default.
     Upload {
         /// The file to upload.
@@ -67,8 +136,56 @@ pub enum FileCmd {
         dest_file: String,
     },
 
+    /// Sync a directory with the archive it was uploaded as, uploading only its new and changed files.
+    ///
+    /// The files removed from the directory are left out of the new archive.
+    Sync {
+        /// The directory to sync.
+        dir: String,
+        /// The address of the archive the directory was uploaded as.
+        addr: String,
+    },
+
+    /// Check that the chunks of an archive are still stored by enough nodes, paying for and uploading again the
+    /// ones that aren't.
+    Repair {
+        /// The address of the archive to repair.
+        addr: String,
+        /// The number of nodes each chunk has to be stored by.
+        #[arg(long, default_value_t = autonomi::client::files::repair::DEFAULT_MIN_REPLICAS)]
+        min_replicas: usize,
+    },
+
+    /// Push a copy of the chunks of an archive to the archiver nodes, which hold them beyond their close group, for
+    /// `file repair` to restore the chunks lost by their close group from.
+    PushToArchivers {
+        /// The address of the archive to push.
+        addr: String,
+        /// The number of archivers each chunk is pushed to.
+        #[arg(long, default_value_t = autonomi::client::files::repair::DEFAULT_ARCHIVE_COPIES)]
+        copies: usize,
+    },
+
     /// List previous uploads
     List,
+
+    /// List the uploads in progress, tracked locally to resume them without paying again.
+    Ls,
+
+    /// Show the progress of the uploads of a file, or of the files within a directory.
+    Status {
+        /// The file or directory that was uploaded.
+        path: String,
+    },
+
+    /// Stop tracking the uploads of a file, or of the files within a directory, reclaiming the disk they use.
+    Forget {
+        /// The file or directory that was uploaded.
+        path: String,
+        /// Forget the uploads even if they hold payments for chunks not stored yet. These payments are lost.
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -151,10 +268,42 @@ pub enum VaultCmd {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum ProfileCmd {
+    /// Save the network options given, e.g. `ant --peer <multiaddr> --network-id 2 profile create testnet`.
+    ///
+    /// The profile keeps its own wallets, keys and user data, in the dirs given or in a dir of its own.
+    Create {
+        /// The name of the profile.
+        name: String,
+        /// The dir to keep the wallets of the profile in.
+        #[arg(long)]
+        wallet_dir: Option<PathBuf>,
+        /// The dir to keep the keys, the user data and the upload sessions of the profile in.
+        #[arg(long)]
+        data_dir: Option<PathBuf>,
+        /// Replace the profile if it exists.
+        #[arg(long)]
+        overwrite: bool,
+    },
+
+    /// List the profiles.
+    List,
+
+    /// Remove a profile. Its wallets and data are left on disk.
+    Remove {
+        /// The name of the profile.
+        name: String,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 pub enum WalletCmd {
     /// Create a wallet.
     Create {
+        /// Derive the wallet from a new 24-word mnemonic, to back it up as a seed phrase.
+        #[clap(long, action)]
+        mnemonic: bool,
         /// Optional flag to not add a password.
         #[clap(long, action)]
         no_password: bool,
@@ -165,8 +314,18 @@ pub enum WalletCmd {
 
     /// Import an existing wallet.
     Import {
-        /// Hex-encoded private key.
-        private_key: String,
+        /// Hex-encoded private key, or the mnemonic phrase when `--mnemonic` is set.
+        #[clap(required_unless_present = "file")]
+        private_key: Option<String>,
+        /// Import the wallet from a file created with `wallet export --output`.
+        #[clap(long, conflicts_with_all = ["private_key", "mnemonic"])]
+        file: Option<PathBuf>,
+        /// Restore the wallet from a BIP39 mnemonic phrase instead of a private key.
+        #[clap(long, action)]
+        mnemonic: bool,
+        /// The derivation path of the wallet key in the mnemonic.
+        #[clap(long, requires = "mnemonic", default_value = autonomi::mnemonic::DEFAULT_DERIVATION_PATH)]
+        derivation_path: String,
         /// Optional flag to not add a password.
         #[clap(long, action)]
         no_password: bool,
@@ -175,25 +334,58 @@ pub enum WalletCmd {
         password: Option<String>,
     },
 
-    /// Print the private key of a wallet.
-    Export,
+    /// Print the private key of a wallet, or write the wallet to a file to import it elsewhere.
+    Export {
+        /// Write the wallet to this file instead of printing its private key.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+        /// Optional flag to not encrypt the wallet file.
+        #[clap(long, action, requires = "output")]
+        no_password: bool,
+        /// Optional password to encrypt the wallet file with.
+        #[clap(long, short, requires = "output")]
+        password: Option<String>,
+    },
+
+    /// Encrypt an unencrypted wallet with a password.
+    Encrypt {
+        /// Password to encrypt the wallet with.
+        #[clap(long, short)]
+        password: Option<String>,
+    },
 
     /// Check the balance of the wallet.
-    Balance,
+    Balance {
+        /// Check the balance of this address instead, without loading any wallet.
+        #[clap(long)]
+        address: Option<String>,
+    },
 }
 
 pub async fn handle_subcommand(opt: Opt) -> Result<()> {
-    let peers = crate::access::network::get_peers(opt.peers);
+    let peers =
+        crate::access::network::get_peers(crate::access::profile::peers_args(opt.peers.clone()));
     let cmd = opt.command;
 
     match cmd {
         Some(SubCmd::File { command }) => match command {
             FileCmd::Cost { file } => file::cost(&file, peers.await?).await,
+            FileCmd::Estimate { file } => file::estimate(&file, peers.await?).await,
             FileCmd::Upload { file, public } => file::upload(&file, public, peers.await?).await,
             FileCmd::Download { addr, dest_file } => {
                 file::download(&addr, &dest_file, peers.await?).await
             }
+            FileCmd::Sync { dir, addr } => file::sync(&dir, &addr, peers.await?).await,
+            FileCmd::Repair { addr, min_replicas } => {
+                file::repair(&addr, min_replicas, peers.await?).await
+            }
+            FileCmd::PushToArchivers { addr, copies } => {
+                file::push_to_archivers(&addr, copies, peers.await?).await
+            }
             FileCmd::List => file::list(),
+            FileCmd::Ls => file::ls(),
+            FileCmd::Status { path } => file::status(&path),
+            FileCmd::Forget { path, force } => file::forget(&path, force),
         },
         Some(SubCmd::Register { command }) => match command {
             RegisterCmd::GenerateKey { overwrite } => register::generate_key(overwrite),
@@ -219,17 +411,68 @@ pub async fn handle_subcommand(opt: Opt) -> Result<()> {
         },
         Some(SubCmd::Wallet { command }) => match command {
             WalletCmd::Create {
+                mnemonic,
                 no_password,
                 password,
-            } => wallet::create(no_password, password),
+            } => wallet::create(mnemonic, no_password, password),
             WalletCmd::Import {
                 private_key,
+                file,
+                mnemonic,
+                derivation_path,
                 no_password,
                 password,
-            } => wallet::import(private_key, no_password, password),
-            WalletCmd::Export => wallet::export(),
-            WalletCmd::Balance => wallet::balance().await,
+            } => {
+                let private_key = match (file, private_key) {
+                    (Some(file), _) => wallet::private_key_from_file(&file)?,
+                    (None, Some(phrase)) if mnemonic => {
+                        wallet::private_key_from_mnemonic(&phrase, &derivation_path)?
+                    }
+                    (None, Some(private_key)) => private_key,
+                    (None, None) => unreachable!("clap requires a private key or a file"),
+                };
+                wallet::import(private_key, no_password, password)
+            }
+            WalletCmd::Export {
+                output,
+                no_password,
+                password,
+            } => wallet::export(output, no_password, password),
+            WalletCmd::Encrypt { password } => wallet::encrypt(password),
+            WalletCmd::Balance { address } => wallet::balance(address).await,
+        },
+        Some(SubCmd::Profile { command }) => match command {
+            ProfileCmd::Create {
+                name,
+                wallet_dir,
+                data_dir,
+                overwrite,
+            } => profile::create(
+                &name,
+                opt.peers,
+                opt.network_id,
+                wallet_dir,
+                data_dir,
+                overwrite,
+            ),
+            ProfileCmd::List => profile::list(),
+            ProfileCmd::Remove { name } => profile::remove(&name),
         },
+        Some(SubCmd::Daemon {
+            address,
+            port,
+            rpc_token_file,
+            files_dir,
+        }) => daemon::run(address, port, rpc_token_file, files_dir, peers.await?).await,
+        Some(SubCmd::Gateway {
+            address,
+            port,
+            cache_size,
+        }) => gateway::run(address, port, cache_size, peers.await?).await,
+        #[cfg(all(feature = "fuse", unix))]
+        Some(SubCmd::Mount { addr, mountpoint }) => {
+            mount::mount(&addr, mountpoint, peers.await?).await
+        }
         None => Ok(()),
     }
 }
@@ -0,0 +1,485 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#![allow(deprecated)]
+
+use crate::wallet::load_wallet;
+use ant_protocol::antclient_proto::{
+    ant_client_server::{AntClient, AntClientServer},
+    DataGetPublicRequest, DataPiece, DataPutPublicResponse, DirDownloadPublicRequest,
+    DirDownloadPublicResponse, DirUploadPublicRequest, DirUploadPublicResponse,
+    RegisterCreateRequest, RegisterCreateResponse, RegisterGetRequest, RegisterGetResponse,
+    RegisterUpdateRequest, RegisterUpdateResponse, WalletBalanceRequest, WalletBalanceResponse,
+};
+use ant_protocol::node_rpc::{rpc_auth_matches, RPC_AUTH_METADATA_KEY};
+use autonomi::client::address::{addr_to_str, str_to_addr};
+use autonomi::client::data::DataAddr;
+use autonomi::client::registers::{RegisterAddress, RegisterPermissions, RegisterSecretKey};
+use autonomi::{Client, Multiaddr, Wallet};
+use color_eyre::eyre::{eyre, Context, Result};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Server, Code, Request, Response, Status, Streaming};
+
+/// The default port the daemon listens on for RPCs.
+pub const CLIENT_DAEMON_DEFAULT_PORT: u16 = 12600;
+
+/// The max size of the pieces the data is streamed out in, well within the default max gRPC message size.
+const DATA_PIECE_SIZE: usize = 1024 * 1024;
+
+/// The max size of the data put in a single RPC, as the daemon holds all of it in memory to upload it.
+const MAX_DATA_PUT_SIZE: usize = 256 * 1024 * 1024;
+
+struct AntClientDaemon {
+    client: Client,
+    wallet: Wallet,
+    register_key: Option<RegisterSecretKey>,
+    /// The directory the files are uploaded from and downloaded to, the directory RPCs being refused without one
+    files_dir: Option<PathBuf>,
+}
+
+impl AntClientDaemon {
+    fn register_key(&self) -> Result<&RegisterSecretKey, Status> {
+        self.register_key.as_ref().ok_or_else(|| {
+            Status::new(
+                Code::FailedPrecondition,
+                "The daemon was started without a register key".to_string(),
+            )
+        })
+    }
+
+    /// The path on the host of the daemon of a path relative to its files directory, refusing the ones out of it.
+    fn path_in_files_dir(&self, path: &str) -> Result<PathBuf, Status> {
+        let files_dir = self.files_dir.as_deref().ok_or_else(|| {
+            Status::new(
+                Code::FailedPrecondition,
+                "The daemon was started without a files directory".to_string(),
+            )
+        })?;
+        confine_to_dir(files_dir, Path::new(path))
+    }
+}
+
+/// Join the relative path to the directory, refusing the paths escaping it, through `..` or a symlink.
+fn confine_to_dir(dir: &Path, path: &Path) -> Result<PathBuf, Status> {
+    let is_relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_relative {
+        return Err(Status::new(
+            Code::InvalidArgument,
+            format!("The path {path:?} must be relative to the files directory of the daemon"),
+        ));
+    }
+    let joined = dir.join(path);
+    // The path may not exist yet, as a download destination, the nearest existing ancestor
+    // is then the one resolved, for a symlinked parent not to lead the new files out of the dir.
+    // A dangling symlink is an existing ancestor which can't be resolved, and is refused.
+    let is_in_dir = joined
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .and_then(|ancestor| ancestor.canonicalize().ok())
+        .is_some_and(|canonical| canonical.starts_with(dir));
+    if !is_in_dir {
+        return Err(Status::new(
+            Code::PermissionDenied,
+            format!("The path {path:?} is out of the files directory of the daemon"),
+        ));
+    }
+    Ok(joined)
+}
+
+/// Check the RPC request carries the token of the daemon, if it requires one.
+fn authorize(token: Option<&str>, request: Request<()>) -> Result<Request<()>, Status> {
+    let Some(token) = token else {
+        return Ok(request);
+    };
+    let presented = request
+        .metadata()
+        .get(RPC_AUTH_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if rpc_auth_matches(presented, token) {
+        Ok(request)
+    } else {
+        warn!(
+            "Rejected an unauthenticated RPC request from {:?}",
+            request.remote_addr()
+        );
+        Err(Status::new(
+            Code::Unauthenticated,
+            "A valid RPC token is required",
+        ))
+    }
+}
+
+fn parse_register_address(address: &str) -> Result<RegisterAddress, Status> {
+    RegisterAddress::from_hex(address).map_err(|err| {
+        Status::new(
+            Code::InvalidArgument,
+            format!("Failed to parse register address: {err}"),
+        )
+    })
+}
+
+fn parse_data_address(address: &str) -> Result<DataAddr, Status> {
+    str_to_addr(address).map_err(|err| {
+        Status::new(
+            Code::InvalidArgument,
+            format!("Failed to parse address: {err}"),
+        )
+    })
+}
+
+#[tonic::async_trait]
+impl AntClient for AntClientDaemon {
+    async fn wallet_balance(
+        &self,
+        request: Request<WalletBalanceRequest>,
+    ) -> Result<Response<WalletBalanceResponse>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let tokens = self.wallet.balance_of_tokens().await.map_err(|err| {
+            Status::new(Code::Internal, format!("Failed to get the balance: {err}"))
+        })?;
+        let gas = self.wallet.balance_of_gas_tokens().await.map_err(|err| {
+            Status::new(
+                Code::Internal,
+                format!("Failed to get the gas balance: {err}"),
+            )
+        })?;
+
+        Ok(Response::new(WalletBalanceResponse {
+            address: format!("{:?}", self.wallet.address()),
+            tokens: tokens.to_string(),
+            gas: gas.to_string(),
+        }))
+    }
+
+    async fn data_put_public(
+        &self,
+        request: Request<Streaming<DataPiece>>,
+    ) -> Result<Response<DataPutPublicResponse>, Status> {
+        debug!("RPC request received: DataPutPublic");
+
+        let mut pieces = request.into_inner();
+        let mut data = Vec::new();
+        while let Some(piece) = pieces.message().await? {
+            if data.len() + piece.data.len() > MAX_DATA_PUT_SIZE {
+                return Err(Status::new(
+                    Code::ResourceExhausted,
+                    format!("The data is larger than the max of {MAX_DATA_PUT_SIZE} bytes, upload it as files instead"),
+                ));
+            }
+            data.extend_from_slice(&piece.data);
+        }
+        info!("Uploading {} bytes of public data", data.len());
+
+        let addr = self
+            .client
+            .data_put_public(data.into(), (&self.wallet).into())
+            .await
+            .map_err(|err| Status::new(Code::Internal, format!("Failed to put data: {err}")))?;
+
+        Ok(Response::new(DataPutPublicResponse {
+            address: addr_to_str(addr),
+        }))
+    }
+
+    type DataGetPublicStream = ReceiverStream<Result<DataPiece, Status>>;
+
+    async fn data_get_public(
+        &self,
+        request: Request<DataGetPublicRequest>,
+    ) -> Result<Response<Self::DataGetPublicStream>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let addr = parse_data_address(&request.get_ref().address)?;
+        let data = self
+            .client
+            .data_get_public(addr)
+            .await
+            .map_err(|err| Status::new(Code::Internal, format!("Failed to get data: {err}")))?;
+
+        let (pieces_tx, pieces_rx) = mpsc::channel(4);
+        let _handle = tokio::spawn(async move {
+            let mut offset = 0;
+            while offset < data.len() {
+                let end = usize::min(offset + DATA_PIECE_SIZE, data.len());
+                let piece = DataPiece {
+                    data: data.slice(offset..end).to_vec(),
+                };
+                if let Err(err) = pieces_tx.send(Ok(piece)).await {
+                    debug!("Dropping the data stream to the RPC client: {err}");
+                    break;
+                }
+                offset = end;
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(pieces_rx)))
+    }
+
+    async fn dir_upload_public(
+        &self,
+        request: Request<DirUploadPublicRequest>,
+    ) -> Result<Response<DirUploadPublicResponse>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let path = self.path_in_files_dir(&request.into_inner().path)?;
+        let archive_addr = self
+            .client
+            .dir_and_archive_upload_public(path.clone(), &self.wallet)
+            .await
+            .map_err(|err| Status::new(Code::Internal, format!("Failed to upload: {err}")))?;
+        let address = addr_to_str(archive_addr);
+
+        if let Err(err) = crate::user_data::write_local_public_file_archive(
+            address.clone(),
+            &path.to_string_lossy(),
+        ) {
+            warn!("Failed to save the uploaded archive to local user data: {err}");
+        }
+
+        Ok(Response::new(DirUploadPublicResponse { address }))
+    }
+
+    async fn dir_download_public(
+        &self,
+        request: Request<DirDownloadPublicRequest>,
+    ) -> Result<Response<DirDownloadPublicResponse>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let request = request.into_inner();
+        let archive_addr = parse_data_address(&request.address)?;
+        let dest = self.path_in_files_dir(&request.dest)?;
+        self.client
+            .dir_download_public(archive_addr, dest)
+            .await
+            .map_err(|err| Status::new(Code::Internal, format!("Failed to download: {err}")))?;
+
+        Ok(Response::new(DirDownloadPublicResponse {}))
+    }
+
+    async fn register_create(
+        &self,
+        request: Request<RegisterCreateRequest>,
+    ) -> Result<Response<RegisterCreateResponse>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let request = request.into_inner();
+        let register_key = self.register_key()?.clone();
+        let permissions = if request.public {
+            RegisterPermissions::new_anyone_can_write()
+        } else {
+            RegisterPermissions::new_with([register_key.public_key()])
+        };
+        let register = self
+            .client
+            .register_create_with_permissions(
+                Some(request.value.into()),
+                &request.name,
+                register_key,
+                permissions,
+                &self.wallet,
+            )
+            .await
+            .map_err(|err| {
+                Status::new(Code::Internal, format!("Failed to create register: {err}"))
+            })?;
+        let address = *register.address();
+
+        if let Err(err) = crate::user_data::write_local_register(&address, &request.name) {
+            warn!("Failed to save the register to local user data: {err}");
+        }
+
+        Ok(Response::new(RegisterCreateResponse {
+            address: address.to_hex(),
+        }))
+    }
+
+    async fn register_get(
+        &self,
+        request: Request<RegisterGetRequest>,
+    ) -> Result<Response<RegisterGetResponse>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let address = parse_register_address(&request.get_ref().address)?;
+        let register =
+            self.client.register_get(address).await.map_err(|err| {
+                Status::new(Code::Internal, format!("Failed to get register: {err}"))
+            })?;
+        let values = self
+            .client
+            .register_values(&register)
+            .await
+            .map_err(|err| {
+                Status::new(
+                    Code::Internal,
+                    format!("Failed to get register values: {err}"),
+                )
+            })?;
+
+        Ok(Response::new(RegisterGetResponse {
+            values: values.into_iter().map(|value| value.to_vec()).collect(),
+        }))
+    }
+
+    async fn register_update(
+        &self,
+        request: Request<RegisterUpdateRequest>,
+    ) -> Result<Response<RegisterUpdateResponse>, Status> {
+        debug!("RPC request received: {:?}", request.get_ref());
+
+        let request = request.into_inner();
+        let address = parse_register_address(&request.address)?;
+        let register_key = self.register_key()?.clone();
+        let register =
+            self.client.register_get(address).await.map_err(|err| {
+                Status::new(Code::Internal, format!("Failed to get register: {err}"))
+            })?;
+        self.client
+            .register_update_with_wallet(register, request.value.into(), register_key, &self.wallet)
+            .await
+            .map_err(|err| {
+                Status::new(Code::Internal, format!("Failed to update register: {err}"))
+            })?;
+
+        Ok(Response::new(RegisterUpdateResponse {}))
+    }
+}
+
+/// Connect to the network and serve the client operations to the local applications until stopped.
+///
+/// Without a token file, the daemon only listens on a loopback address.
+pub async fn run(
+    address: IpAddr,
+    port: u16,
+    rpc_token_file: Option<PathBuf>,
+    files_dir: Option<PathBuf>,
+    peers: Vec<Multiaddr>,
+) -> Result<()> {
+    let token = match rpc_token_file {
+        Some(path) => {
+            let token = std::fs::read_to_string(&path)
+                .wrap_err(format!("Failed to read the RPC token file {path:?}"))?
+                .trim()
+                .to_string();
+            if token.is_empty() {
+                return Err(eyre!("The RPC token file {path:?} is empty"));
+            }
+            Some(token)
+        }
+        None if !address.is_loopback() => {
+            return Err(eyre!(
+                "The daemon spends from the wallet, it requires an --rpc-token-file to listen on {address}"
+            ));
+        }
+        None => None,
+    };
+    let files_dir = files_dir
+        .map(|dir| {
+            dir.canonicalize()
+                .wrap_err(format!("Failed to open the files directory {dir:?}"))
+        })
+        .transpose()?;
+
+    let wallet = load_wallet()?;
+    let register_key = match crate::keys::get_register_signing_key() {
+        Ok(key) => Some(key),
+        Err(err) => {
+            warn!("Serving without a register key, registers can't be created nor updated: {err}");
+            status!("No register key found, registers can't be created nor updated");
+            None
+        }
+    };
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    let addr = SocketAddr::new(address, port);
+    info!("Client daemon listening for RPCs on {addr}");
+    status!("Client daemon listening for RPCs on {addr}");
+    if token.is_none() {
+        warn!(
+            "Anyone able to connect to {addr} can spend from the wallet {:?}",
+            wallet.address()
+        );
+    }
+
+    let daemon = AntClientDaemon {
+        client,
+        wallet,
+        register_key,
+        files_dir,
+    };
+    Server::builder()
+        .add_service(AntClientServer::with_interceptor(daemon, move |request| {
+            authorize(token.as_deref(), request)
+        }))
+        .serve(addr)
+        .await
+        .wrap_err("The client daemon failed")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::node_rpc::rpc_auth_metadata_value;
+
+    #[test]
+    fn paths_out_of_the_files_dir_are_refused() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let files_dir = dir.path().canonicalize().expect("canonical dir");
+
+        assert_eq!(
+            confine_to_dir(&files_dir, Path::new("photos/cat.png")).expect("path in the dir"),
+            files_dir.join("photos/cat.png")
+        );
+        assert!(confine_to_dir(&files_dir, Path::new("/etc/passwd")).is_err());
+        assert!(confine_to_dir(&files_dir, Path::new("../outside")).is_err());
+        assert!(confine_to_dir(&files_dir, Path::new("photos/../../outside")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn new_paths_under_a_symlink_out_of_the_files_dir_are_refused() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let files_dir = dir.path().join("files");
+        std::fs::create_dir(&files_dir).expect("files dir");
+        let files_dir = files_dir.canonicalize().expect("canonical dir");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&outside).expect("outside dir");
+        std::os::unix::fs::symlink(&outside, files_dir.join("link")).expect("symlink");
+
+        assert!(confine_to_dir(&files_dir, Path::new("link")).is_err());
+        assert!(confine_to_dir(&files_dir, Path::new("link/new_file")).is_err());
+        assert!(confine_to_dir(&files_dir, Path::new("link/new_dir/new_file")).is_err());
+        assert!(confine_to_dir(&files_dir, Path::new("new_dir/new_file")).is_ok());
+
+        std::os::unix::fs::symlink(outside.join("missing"), files_dir.join("dangling"))
+            .expect("dangling symlink");
+        assert!(confine_to_dir(&files_dir, Path::new("dangling")).is_err());
+    }
+
+    #[test]
+    fn requests_require_the_token_when_set() {
+        assert!(authorize(Some("secret"), Request::new(())).is_err());
+
+        let mut request = Request::new(());
+        let _ = request.metadata_mut().insert(
+            RPC_AUTH_METADATA_KEY,
+            rpc_auth_metadata_value("secret")
+                .parse()
+                .expect("metadata value"),
+        );
+        assert!(authorize(Some("secret"), request).is_ok());
+        assert!(authorize(None, Request::new(())).is_ok());
+    }
+}
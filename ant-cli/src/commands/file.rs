@@ -6,28 +6,90 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::data_dir::get_client_data_dir_path;
 use crate::utils::collect_upload_summary;
 use crate::wallet::load_wallet;
-use autonomi::client::address::addr_to_str;
+use autonomi::client::address::{addr_to_str, str_to_addr};
+use autonomi::client::files::repair::RepairTarget;
+use autonomi::client::files::upload_session::{ChunkUploadStatus, UploadSession};
 use autonomi::Multiaddr;
+use color_eyre::eyre::eyre;
 use color_eyre::eyre::Context;
 use color_eyre::eyre::Result;
 use color_eyre::Section;
-use std::path::PathBuf;
+use prettytable::{Cell, Row, Table};
+use serde_json::json;
+use std::path::{Path, PathBuf};
 
 pub async fn cost(file: &str, peers: Vec<Multiaddr>) -> Result<()> {
     let client = crate::actions::connect_to_network(peers).await?;
 
-    println!("Getting upload cost...");
+    status!("Getting upload cost...");
     info!("Calculating cost for file: {file}");
     let cost = client
         .file_cost(&PathBuf::from(file))
         .await
         .wrap_err("Failed to calculate cost for file")?;
 
-    println!("Estimate cost to upload file: {file}");
-    println!("Total cost: {cost}");
+    status!("Estimate cost to upload file: {file}");
+    status!("Total cost: {cost}");
     info!("Total cost: {cost} for file: {file}");
+    crate::output::json_result(json!({
+        "file": file,
+        "cost": cost.to_string(),
+    }));
+    Ok(())
+}
+
+pub async fn estimate(file: &str, peers: Vec<Multiaddr>) -> Result<()> {
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    status!("Getting store quotes for {file}...");
+    info!("Estimating cost for file: {file}");
+    let estimate = client
+        .estimate_cost(Path::new(file))
+        .await
+        .wrap_err("Failed to estimate the cost of the file")?;
+    info!("Cost estimate for file {file}: {estimate:?}");
+
+    let mut payees: Vec<_> = estimate.payees.iter().collect();
+    payees.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    status!("Estimate cost to upload file: {file}");
+    status!("Total cost: {} AttoTokens", estimate.total);
+    status!("Chunks to pay for: {}", estimate.chunks_to_pay);
+    status!(
+        "Chunks already on the network: {}",
+        estimate.chunks_already_stored
+    );
+    if !payees.is_empty() {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Node rewards address"),
+            Cell::new("AttoTokens"),
+        ]));
+        for (address, amount) in &payees {
+            table.add_row(Row::new(vec![
+                Cell::new(&address.to_string()),
+                Cell::new(&amount.to_string()),
+            ]));
+        }
+        crate::output::print_table(&table);
+    }
+
+    crate::output::json_result(json!({
+        "file": file,
+        "cost": estimate.total.to_string(),
+        "chunks_to_pay": estimate.chunks_to_pay,
+        "chunks_already_stored": estimate.chunks_already_stored,
+        "payees": payees
+            .iter()
+            .map(|(address, amount)| json!({
+                "rewards_address": address.to_string(),
+                "cost": amount.to_string(),
+            }))
+            .collect::<Vec<_>>(),
+    }));
     Ok(())
 }
 
@@ -37,7 +99,7 @@ pub async fn upload(file: &str, public: bool, peers: Vec<Multiaddr>) -> Result<(
     let event_receiver = client.enable_client_events();
     let (upload_summary_thread, upload_completed_tx) = collect_upload_summary(event_receiver);
 
-    println!("Uploading data to network...");
+    status!("Uploading data to network...");
     info!(
         "Uploading {} file: {file}",
         if public { "public" } else { "private" }
@@ -59,10 +121,18 @@ pub async fn upload(file: &str, public: bool, peers: Vec<Multiaddr>) -> Result<(
         local_addr = addr_to_str(xor_name);
         local_addr.clone()
     } else {
+        // The progress of each file is tracked under the data dir, to resume an interrupted upload with `file ls`
+        // showing it in the meantime. The absolute path finds the sessions back from any working dir.
+        let sessions_dir = get_client_data_dir_path()?;
+        let dir_path = std::fs::canonicalize(&dir_path).unwrap_or(dir_path);
+        let archive = client
+            .dir_upload_with_sessions(dir_path, &wallet, &sessions_dir)
+            .await
+            .wrap_err("Failed to upload dir")?;
         let private_data_access = client
-            .dir_and_archive_upload(dir_path, &wallet)
+            .archive_put(&archive, (&wallet).into())
             .await
-            .wrap_err("Failed to upload dir and archive")?;
+            .wrap_err("Failed to upload archive")?;
 
         local_addr = private_data_access.address();
         private_data_access.to_hex()
@@ -77,13 +147,13 @@ pub async fn upload(file: &str, public: bool, peers: Vec<Multiaddr>) -> Result<(
     // get summary
     let summary = upload_summary_thread.await?;
     if summary.record_count == 0 {
-        println!("All chunks already exist on the network.");
+        status!("All chunks already exist on the network.");
     } else {
-        println!("Successfully uploaded: {file}");
-        println!("At address: {local_addr}");
+        status!("Successfully uploaded: {file}");
+        status!("At address: {local_addr}");
         info!("Successfully uploaded: {file} at address: {local_addr}");
-        println!("Number of chunks uploaded: {}", summary.record_count);
-        println!("Total cost: {} AttoTokens", summary.tokens_spent);
+        status!("Number of chunks uploaded: {}", summary.record_count);
+        status!("Total cost: {} AttoTokens", summary.tokens_spent);
     }
     info!("Summary for upload of file {file} at {local_addr:?}: {summary:?}");
 
@@ -91,13 +161,21 @@ pub async fn upload(file: &str, public: bool, peers: Vec<Multiaddr>) -> Result<(
     let writer = if public {
         crate::user_data::write_local_public_file_archive(archive, &name)
     } else {
-        crate::user_data::write_local_private_file_archive(archive, local_addr, &name)
+        crate::user_data::write_local_private_file_archive(archive, local_addr.clone(), &name)
     };
     writer
         .wrap_err("Failed to save file to local user data")
         .with_suggestion(|| "Local user data saves the file address above to disk, without it you need to keep track of the address yourself")?;
     info!("Saved file to local user data");
 
+    crate::output::json_result(json!({
+        "file": file,
+        "address": local_addr,
+        "public": public,
+        "chunks_uploaded": summary.record_count,
+        "cost": summary.tokens_spent.to_string(),
+    }));
+
     Ok(())
 }
 
@@ -106,34 +184,365 @@ pub async fn download(addr: &str, dest_path: &str, peers: Vec<Multiaddr>) -> Res
     crate::actions::download(addr, dest_path, &mut client).await
 }
 
+pub async fn sync(dir: &str, addr: &str, peers: Vec<Multiaddr>) -> Result<()> {
+    let public_address = str_to_addr(addr).ok();
+    let private_address = crate::user_data::get_local_private_archive_access(addr)
+        .inspect_err(|e| error!("Failed to get private archive access: {e}"))
+        .ok();
+    if public_address.is_none() && private_address.is_none() {
+        return Err(eyre!("Failed to parse archive address {addr}"))
+            .with_suggestion(|| "Try the `file list` command to get addresses you have access to");
+    }
+
+    let wallet = load_wallet()?;
+    let mut client = crate::actions::connect_to_network(peers).await?;
+    let event_receiver = client.enable_client_events();
+    let (upload_summary_thread, upload_completed_tx) = collect_upload_summary(event_receiver);
+
+    status!("Syncing {dir} with the archive at {addr}...");
+    info!("Syncing {dir} with the archive at {addr}");
+    let dir_path = PathBuf::from(dir);
+    let name = dir_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or(dir.to_string());
+
+    let (local_addr, sync_summary) = match (public_address, private_address) {
+        (Some(public_address), _) => {
+            let (new_addr, sync_summary) = client
+                .dir_sync_public(dir_path, public_address, &wallet)
+                .await
+                .wrap_err("Failed to sync directory")?;
+            let local_addr = addr_to_str(new_addr);
+            if !sync_summary.is_empty() {
+                crate::user_data::write_local_public_file_archive(local_addr.clone(), &name)
+                    .wrap_err("Failed to save the synced archive to local user data")?;
+            }
+            (local_addr, sync_summary)
+        }
+        (None, Some(private_address)) => {
+            let (new_access, sync_summary) = client
+                .dir_sync(dir_path, private_address, &wallet)
+                .await
+                .wrap_err("Failed to sync directory")?;
+            let local_addr = new_access.address();
+            if !sync_summary.is_empty() {
+                crate::user_data::write_local_private_file_archive(
+                    new_access.to_hex(),
+                    local_addr.clone(),
+                    &name,
+                )
+                .wrap_err("Failed to save the synced archive to local user data")?;
+            }
+            (local_addr, sync_summary)
+        }
+        (None, None) => unreachable!("one of the addresses was checked to be valid"),
+    };
+
+    if let Err(e) = upload_completed_tx.send(()) {
+        error!("Failed to send upload completed event: {e:?}");
+        eprintln!("Failed to send upload completed event: {e:?}");
+    }
+    let summary = upload_summary_thread.await?;
+    info!("Summary for the sync of {dir} to {local_addr}: {sync_summary:?}, {summary:?}");
+
+    if sync_summary.is_empty() {
+        status!("✅ {dir} is already in sync with the archive at {local_addr}");
+    } else {
+        status!("✅ Synced {dir} to the archive at: {local_addr}");
+        print_changes("Added", &sync_summary.added);
+        print_changes("Changed", &sync_summary.changed);
+        print_changes("Removed", &sync_summary.removed);
+        status!("Unchanged: {} file(s)", sync_summary.unchanged);
+        status!("Number of chunks uploaded: {}", summary.record_count);
+        status!("Total cost: {} AttoTokens", summary.tokens_spent);
+    }
+    crate::output::json_result(json!({
+        "dir": dir,
+        "address": local_addr,
+        "added": sync_summary.added,
+        "changed": sync_summary.changed,
+        "removed": sync_summary.removed,
+        "unchanged": sync_summary.unchanged,
+        "chunks_uploaded": summary.record_count,
+        "cost": summary.tokens_spent.to_string(),
+    }));
+    Ok(())
+}
+
+fn repair_target(addr: &str) -> Result<RepairTarget> {
+    match str_to_addr(addr) {
+        Ok(public_address) => Ok(RepairTarget::PublicArchive(public_address)),
+        Err(_) => crate::user_data::get_local_private_archive_access(addr)
+            .map(RepairTarget::Archive)
+            .map_err(|_| eyre!("Failed to parse archive address {addr}"))
+            .with_suggestion(|| "Try the `file list` command to get addresses you have access to"),
+    }
+}
+
+pub async fn repair(addr: &str, min_replicas: usize, peers: Vec<Multiaddr>) -> Result<()> {
+    let target = repair_target(addr)?;
+    let wallet = load_wallet()?;
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    status!("Checking the chunks of the archive at {addr}...");
+    info!("Repairing the archive at {addr}, with at least {min_replicas} replicas per chunk");
+    let report = client
+        .repair(target, min_replicas, &wallet)
+        .await
+        .wrap_err("Failed to repair the archive")?;
+    info!("Repair of the archive at {addr}: {report:?}");
+
+    status!("Chunks checked: {}", report.checked);
+    status!("Stored by enough nodes: {}", report.healthy);
+    status!("Uploaded again: {}", report.repaired.len());
+    for (chunk, replicas) in &report.repaired {
+        status!("  {} (stored by {replicas} node(s))", addr_to_str(*chunk));
+    }
+    if !report.repaired.is_empty() {
+        status!("Total cost: {} AttoTokens", report.cost);
+    }
+    if !report.lost.is_empty() {
+        status!(
+            "❌ Lost: {} chunk(s), not stored by any node. Upload the files again to restore them:",
+            report.lost.len()
+        );
+        for chunk in &report.lost {
+            status!("  {}", addr_to_str(*chunk));
+        }
+    }
+    if !report.failed.is_empty() {
+        status!(
+            "❌ Failed: {} chunk(s), run the repair again:",
+            report.failed.len()
+        );
+        for (chunk, err) in &report.failed {
+            status!("  {}: {err}", addr_to_str(*chunk));
+        }
+    }
+    if report.is_healthy() {
+        status!("✅ The archive at {addr} is stored by enough nodes");
+    }
+    crate::output::json_result(json!({
+        "address": addr,
+        "checked": report.checked,
+        "healthy": report.healthy,
+        "repaired": report.repaired.iter().map(|(chunk, replicas)| json!({
+            "chunk": addr_to_str(*chunk),
+            "replicas": replicas,
+        })).collect::<Vec<_>>(),
+        "lost": report.lost.iter().map(|chunk| addr_to_str(*chunk)).collect::<Vec<_>>(),
+        "failed": report.failed.iter().map(|(chunk, err)| json!({
+            "chunk": addr_to_str(*chunk),
+            "error": err,
+        })).collect::<Vec<_>>(),
+        "cost": report.cost.to_string(),
+    }));
+    Ok(())
+}
+
+pub async fn push_to_archivers(addr: &str, copies: usize, peers: Vec<Multiaddr>) -> Result<()> {
+    let target = repair_target(addr)?;
+    let client = crate::actions::connect_to_network(peers).await?;
+
+    status!("Pushing the chunks of the archive at {addr} to {copies} archiver(s) each...");
+    let pushed = client
+        .push_to_archivers(target, copies)
+        .await
+        .wrap_err("Failed to push the archive to archivers")?;
+
+    status!("✅ Pushed {pushed} chunk(s) of the archive at {addr} to archivers");
+    crate::output::json_result(json!({
+        "address": addr,
+        "chunks_pushed": pushed,
+    }));
+    Ok(())
+}
+
+fn print_changes(kind: &str, paths: &[PathBuf]) {
+    status!("{kind}: {} file(s)", paths.len());
+    for path in paths {
+        status!("  {}", path.display());
+    }
+}
+
 pub fn list() -> Result<()> {
     // get public file archives
-    println!("Retrieving local user data...");
+    status!("Retrieving local user data...");
     let file_archives = crate::user_data::get_local_public_file_archives()
         .wrap_err("Failed to get local public file archives")?;
+    let public_json: Vec<_> = file_archives
+        .iter()
+        .map(|(addr, name)| json!({ "name": name, "address": addr_to_str(*addr) }))
+        .collect();
 
-    println!(
+    status!(
         "✅ You have {} public file archive(s):",
         file_archives.len()
     );
     for (addr, name) in file_archives {
-        println!("{}: {}", name, addr_to_str(addr));
+        status!("{}: {}", name, addr_to_str(addr));
     }
 
     // get private file archives
-    println!();
+    status!();
     let private_file_archives = crate::user_data::get_local_private_file_archives()
         .wrap_err("Failed to get local private file archives")?;
+    let private_json: Vec<_> = private_file_archives
+        .iter()
+        .map(|(addr, name)| json!({ "name": name, "address": addr.address() }))
+        .collect();
 
-    println!(
+    status!(
         "✅ You have {} private file archive(s):",
         private_file_archives.len()
     );
     for (addr, name) in private_file_archives {
-        println!("{}: {}", name, addr.address());
+        status!("{}: {}", name, addr.address());
     }
 
-    println!();
-    println!("> Note that private data addresses are not network addresses, they are only used for referring to private data client side.");
+    status!();
+    status!("> Note that private data addresses are not network addresses, they are only used for referring to private data client side.");
+    crate::output::json_result(json!({
+        "public_archives": public_json,
+        "private_archives": private_json,
+    }));
     Ok(())
 }
+
+pub fn ls() -> Result<()> {
+    let sessions = UploadSession::list(&get_client_data_dir_path()?)
+        .wrap_err("Failed to read the tracked uploads")?;
+    status!("✅ You have {} upload(s) in progress:", sessions.len());
+    for session in &sessions {
+        let progress = UploadProgress::of(session);
+        status!(
+            "{}: {}/{} chunk(s) stored, {} paid for",
+            session.source().display(),
+            progress.stored,
+            progress.total(),
+            progress.paid
+        );
+    }
+    if !sessions.is_empty() {
+        status!();
+        status!("> Upload the files again to resume their uploads, or use `file forget` to stop tracking them.");
+    }
+    crate::output::json_result(json!({
+        "uploads": sessions.iter().map(upload_json).collect::<Vec<_>>(),
+    }));
+    Ok(())
+}
+
+pub fn status(path: &str) -> Result<()> {
+    let sessions = sessions_of(path)?;
+    if sessions.is_empty() {
+        status!("No upload in progress for {path}");
+        status!("> Completed uploads are not tracked, use `file list` to get their addresses.");
+    }
+    for session in &sessions {
+        let progress = UploadProgress::of(session);
+        status!("{}:", session.source().display());
+        if session.is_complete() {
+            status!("  ✅ All {} chunk(s) stored and verified", progress.total());
+        } else {
+            status!("  Stored and verified: {} chunk(s)", progress.stored);
+            status!("  Paid for, not stored yet: {} chunk(s)", progress.paid);
+            status!("  Not paid for yet: {} chunk(s)", progress.unpaid);
+        }
+    }
+    crate::output::json_result(json!({
+        "uploads": sessions.iter().map(upload_json).collect::<Vec<_>>(),
+    }));
+    Ok(())
+}
+
+pub fn forget(path: &str, force: bool) -> Result<()> {
+    let sessions_dir = get_client_data_dir_path()?;
+    let sessions = sessions_of(path)?;
+    let paid: usize = sessions
+        .iter()
+        .map(|session| UploadProgress::of(session).paid)
+        .sum();
+    if paid > 0 && !force {
+        return Err(eyre!(
+            "The uploads of {path} hold payments for {paid} chunk(s) not stored yet"
+        ))
+        .with_suggestion(|| "Upload the files again to store these chunks without paying again")
+        .with_suggestion(|| {
+            "Run the command with the --force flag to forget them anyway, losing these payments"
+        });
+    }
+
+    let mut reclaimed = 0;
+    for session in &sessions {
+        reclaimed += session.size_on_disk(&sessions_dir);
+        session.remove(&sessions_dir).wrap_err(format!(
+            "Failed to forget the upload of {:?}",
+            session.source()
+        ))?;
+        info!(
+            "Forgot the upload session {} of {:?}",
+            session.id(),
+            session.source()
+        );
+    }
+    status!(
+        "✅ Forgot {} upload(s) of {path}, reclaiming {reclaimed} bytes",
+        sessions.len()
+    );
+    crate::output::json_result(json!({
+        "forgotten": sessions.iter().map(|session| session.source()).collect::<Vec<_>>(),
+        "reclaimed_bytes": reclaimed,
+    }));
+    Ok(())
+}
+
+/// The upload sessions of the file at the path, or of the files within the dir at the path.
+fn sessions_of(path: &str) -> Result<Vec<UploadSession>> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let sessions = UploadSession::list(&get_client_data_dir_path()?)
+        .wrap_err("Failed to read the tracked uploads")?;
+    Ok(sessions
+        .into_iter()
+        .filter(|session| session.source().starts_with(&path))
+        .collect())
+}
+
+/// The number of chunks of an upload session per status.
+#[derive(Default)]
+struct UploadProgress {
+    stored: usize,
+    paid: usize,
+    unpaid: usize,
+}
+
+impl UploadProgress {
+    fn of(session: &UploadSession) -> Self {
+        let mut progress = Self::default();
+        for status in session.chunks().values() {
+            match status {
+                ChunkUploadStatus::Stored => progress.stored += 1,
+                ChunkUploadStatus::Paid => progress.paid += 1,
+                ChunkUploadStatus::Unpaid => progress.unpaid += 1,
+            }
+        }
+        progress
+    }
+
+    fn total(&self) -> usize {
+        self.stored + self.paid + self.unpaid
+    }
+}
+
+fn upload_json(session: &UploadSession) -> serde_json::Value {
+    let progress = UploadProgress::of(session);
+    json!({
+        "file": session.source(),
+        "session": session.id(),
+        "complete": session.is_complete(),
+        "chunks_stored": progress.stored,
+        "chunks_paid": progress.paid,
+        "chunks_unpaid": progress.unpaid,
+    })
+}
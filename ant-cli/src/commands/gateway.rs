@@ -0,0 +1,831 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use autonomi::client::address::str_to_addr;
+use autonomi::client::data::{DataAddr, GetError};
+use autonomi::client::files::archive_public::PublicArchive;
+use autonomi::{Bytes, Client, Multiaddr};
+use color_eyre::eyre::{Context, Result};
+use hyper::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::StreamExt;
+
+/// The default port the gateway serves the network data on.
+pub const GATEWAY_DEFAULT_PORT: u16 = 8080;
+
+/// The default size of the in-memory chunk cache, in MiB.
+pub const GATEWAY_DEFAULT_CACHE_SIZE_MB: usize = 256;
+
+/// Data up to this size is fetched in full before answering, to check whether it's an archive to browse.
+const ARCHIVE_PROBE_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Data bigger than this is streamed through without being cached, so one big file doesn't flush the cache.
+const MAX_CACHED_DATA_SIZE: usize = 32 * 1024 * 1024;
+
+/// The decrypted chunks of the most recently fetched data, evicted oldest first once over capacity.
+struct ChunkCache {
+    entries: HashMap<DataAddr, Arc<Vec<Bytes>>>,
+    order: VecDeque<DataAddr>,
+    size: usize,
+    capacity: usize,
+}
+
+impl ChunkCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            size: 0,
+            capacity,
+        }
+    }
+
+    fn get(&self, addr: &DataAddr) -> Option<Arc<Vec<Bytes>>> {
+        self.entries.get(addr).cloned()
+    }
+
+    fn insert(&mut self, addr: DataAddr, chunks: Vec<Bytes>) {
+        let size: usize = chunks.iter().map(Bytes::len).sum();
+        if size > self.capacity || self.entries.contains_key(&addr) {
+            return;
+        }
+
+        while self.size + size > self.capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.size -= evicted.iter().map(Bytes::len).sum::<usize>();
+            }
+        }
+
+        self.size += size;
+        self.order.push_back(addr);
+        let _ = self.entries.insert(addr, Arc::new(chunks));
+    }
+}
+
+/// What to answer to the `Range` header of a request for data of a known size.
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// No (usable) range was asked for, the whole data is served.
+    Full,
+    /// The inclusive range of bytes to serve.
+    Partial(u64, u64),
+    /// The range lies outside of the data.
+    Unsatisfiable,
+}
+
+/// Parse a single range `Range` header against the size of the data. Multiple ranges aren't supported and, like any
+/// malformed header, get the whole data served.
+fn parse_range(header: Option<&str>, size: u64) -> ByteRange {
+    let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let (start, end) = if start.is_empty() {
+        // a suffix range, the last `end` bytes
+        let Ok(suffix) = end.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        if suffix == 0 {
+            return ByteRange::Unsatisfiable;
+        }
+        (size.saturating_sub(suffix), size.saturating_sub(1))
+    } else {
+        let Ok(start) = start.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        let end = if end.is_empty() {
+            size.saturating_sub(1)
+        } else {
+            match end.parse::<u64>() {
+                Ok(end) => end.min(size.saturating_sub(1)),
+                Err(_) => return ByteRange::Full,
+            }
+        };
+        (start, end)
+    };
+
+    if size == 0 || start >= size || start > end {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end)
+}
+
+/// The part of the piece of data starting at `offset` that falls within the inclusive `range`, if any.
+fn slice_piece(piece: &Bytes, offset: u64, range: Option<(u64, u64)>) -> Option<Bytes> {
+    let Some((start, end)) = range else {
+        return Some(piece.clone());
+    };
+    let piece_end = offset + piece.len() as u64;
+    if piece_end <= start || offset > end {
+        return None;
+    }
+    let from = start.saturating_sub(offset) as usize;
+    let to = (end + 1).min(piece_end).saturating_sub(offset) as usize;
+    Some(piece.slice(from..to))
+}
+
+/// The part of the piece of data starting at `offset` from the byte at `from` on, if any.
+fn skip_to(piece: Bytes, offset: u64, from: u64) -> Option<Bytes> {
+    let skipped = from.saturating_sub(offset);
+    if skipped >= piece.len() as u64 {
+        return None;
+    }
+    Some(piece.slice(skipped as usize..))
+}
+
+/// The content type of a file going by its extension.
+fn content_type_from_path(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let content_type = match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" | "rs" | "toml" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "wasm" => "application/wasm",
+        _ => return None,
+    };
+    Some(content_type)
+}
+
+/// The content type of data going by its first bytes, for data served without a file name.
+fn sniff_content_type(head: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"\0asm", "application/wasm"),
+    ];
+    if let Some((_, content_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return content_type;
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" {
+        match &head[8..12] {
+            b"WEBP" => return "image/webp",
+            b"WAVE" => return "audio/wav",
+            _ => {}
+        }
+    }
+    if head.len() >= 8 && &head[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+
+    // a chunk boundary might split a multi byte character, so only the valid prefix has to be text
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&head[..err.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return "application/octet-stream",
+    };
+    let start = text.trim_start().as_bytes();
+    let starts_with = |prefix: &[u8]| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+    };
+    if starts_with(b"<!doctype html>") || starts_with(b"<html") {
+        "text/html; charset=utf-8"
+    } else {
+        "text/plain; charset=utf-8"
+    }
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = input
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn html_escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn text_response(status: StatusCode, text: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(text))
+        .unwrap_or_default()
+}
+
+fn fetch_error_response(err: GetError) -> Response<Body> {
+    text_response(
+        StatusCode::BAD_GATEWAY,
+        format!("Failed to fetch the data from the network: {err}"),
+    )
+}
+
+/// An HTML page listing the files of the archive under the given folder, linking to each of them.
+fn archive_listing(archive_addr: &str, archive: &PublicArchive, folder: &str) -> Response<Body> {
+    let mut files: Vec<_> = archive
+        .iter()
+        .map(|(path, _, meta)| (path.to_string_lossy().into_owned(), meta.size))
+        .filter(|(path, _)| path.starts_with(folder))
+        .collect();
+    files.sort();
+
+    let title = html_escape(&format!("/{archive_addr}/{folder}"));
+    let mut page = format!("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<ul>\n");
+    for (path, size) in files {
+        page.push_str(&format!(
+            "<li><a href=\"/{archive_addr}/{}\">{}</a> ({size} bytes)</li>\n",
+            percent_encode_path(&path),
+            html_escape(&path),
+        ));
+    }
+    page.push_str("</ul>\n</body>\n</html>\n");
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(page))
+        .unwrap_or_default()
+}
+
+/// A body streaming the (range of the) data, starting with the pieces already received, which start at `offset`.
+fn stream_body(
+    received: Vec<Bytes>,
+    mut pieces: mpsc::Receiver<Result<Bytes, GetError>>,
+    mut offset: u64,
+    range: Option<(u64, u64)>,
+) -> Body {
+    let (mut sender, body) = Body::channel();
+    let _handle = tokio::spawn(async move {
+        for piece in received {
+            if let Some(piece) = slice_piece(&piece, offset, range) {
+                if sender.send_data(piece).await.is_err() {
+                    return;
+                }
+            }
+            offset += piece.len() as u64;
+        }
+        while let Some(piece) = pieces.recv().await {
+            let piece = match piece {
+                Ok(piece) => piece,
+                Err(err) => {
+                    warn!("Failed to fetch the data being served: {err}");
+                    sender.abort();
+                    return;
+                }
+            };
+            if matches!(range, Some((_, end)) if offset > end) {
+                return;
+            }
+            if let Some(piece) = slice_piece(&piece, offset, range) {
+                if let Err(err) = sender.send_data(piece).await {
+                    debug!("Dropping the data stream to the HTTP client: {err}");
+                    return;
+                }
+            }
+            offset += piece.len() as u64;
+        }
+    });
+    body
+}
+
+/// Answer with the data of the given size, honouring the `Range` header. The received pieces, then the ones still
+/// to be received, start at `offset`, which can't be past the start of the range.
+fn data_response(
+    received: Vec<Bytes>,
+    pieces: mpsc::Receiver<Result<Bytes, GetError>>,
+    offset: u64,
+    size: u64,
+    content_type: &str,
+    range_header: Option<&str>,
+) -> Response<Body> {
+    let builder = Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(ACCEPT_RANGES, "bytes");
+    let response = match parse_range(range_header, size) {
+        ByteRange::Full => builder
+            .header(CONTENT_LENGTH, size)
+            .body(stream_body(received, pieces, offset, None)),
+        ByteRange::Partial(start, end) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(CONTENT_RANGE, format!("bytes {start}-{end}/{size}"))
+            .header(CONTENT_LENGTH, end - start + 1)
+            .body(stream_body(received, pieces, offset, Some((start, end)))),
+        ByteRange::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(CONTENT_RANGE, format!("bytes */{size}"))
+            .body(Body::empty()),
+    };
+    response.unwrap_or_default()
+}
+
+struct Gateway {
+    client: Client,
+    cache: Mutex<ChunkCache>,
+}
+
+impl Gateway {
+    /// Fetch the data from the byte at `from` on in the background, from the cache if it's there, sending its pieces
+    /// in order. Only the chunks from there are fetched from the network.
+    fn fetch(
+        self: &Arc<Self>,
+        addr: DataAddr,
+        from: u64,
+    ) -> mpsc::Receiver<Result<Bytes, GetError>> {
+        let (pieces_tx, pieces_rx) = mpsc::channel(4);
+        let gateway = Arc::clone(self);
+        let _handle =
+            tokio::spawn(async move { gateway.forward_data(addr, from, pieces_tx).await });
+        pieces_rx
+    }
+
+    async fn forward_data(
+        &self,
+        addr: DataAddr,
+        from: u64,
+        pieces_tx: mpsc::Sender<Result<Bytes, GetError>>,
+    ) {
+        let cached = self.cache.lock().await.get(&addr);
+        if let Some(chunks) = cached {
+            debug!("Serving {addr:?} from the chunk cache");
+            let mut offset = 0;
+            for chunk in chunks.iter() {
+                let piece = skip_to(chunk.clone(), offset, from);
+                offset += chunk.len() as u64;
+                let Some(piece) = piece else {
+                    continue;
+                };
+                if pieces_tx.send(Ok(piece)).await.is_err() {
+                    return;
+                }
+            }
+            return;
+        }
+
+        let (mut offset, stream) = match self.client.data_stream_public_from(addr, from).await {
+            Ok(stream) => stream,
+            Err(err) => {
+                let _ = pieces_tx.send(Err(err)).await;
+                return;
+            }
+        };
+        tokio::pin!(stream);
+
+        // only the whole data is cached
+        let mut to_cache = (offset == 0).then(Vec::new);
+        let mut to_cache_size = 0;
+        while let Some(piece) = stream.next().await {
+            let piece = match piece {
+                Ok(piece) => piece,
+                Err(err) => {
+                    let _ = pieces_tx.send(Err(err)).await;
+                    return;
+                }
+            };
+            if let Some(chunks) = to_cache.as_mut() {
+                to_cache_size += piece.len();
+                if to_cache_size > MAX_CACHED_DATA_SIZE {
+                    to_cache = None;
+                } else {
+                    chunks.push(piece.clone());
+                }
+            }
+            let piece_offset = offset;
+            offset += piece.len() as u64;
+            let Some(piece) = skip_to(piece, piece_offset, from) else {
+                continue;
+            };
+            if pieces_tx.send(Ok(piece)).await.is_err() {
+                // the HTTP client went away, don't cache the partial data
+                return;
+            }
+        }
+
+        if let Some(chunks) = to_cache {
+            self.cache.lock().await.insert(addr, chunks);
+        }
+    }
+
+    async fn handle(self: Arc<Self>, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::GET {
+            return text_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Only GET requests are served".to_string(),
+            );
+        }
+
+        let path = percent_decode(request.uri().path());
+        let range_header = request
+            .headers()
+            .get(RANGE)
+            .and_then(|range| range.to_str().ok());
+        let (addr, file_path) = match path.trim_start_matches('/').split_once('/') {
+            Some((addr, file_path)) => (addr, file_path),
+            None => (path.trim_start_matches('/'), ""),
+        };
+        if addr.is_empty() {
+            return text_response(
+                StatusCode::OK,
+                "Autonomi gateway: GET /<data address> for public data, GET /<archive address>/<path> for the files of a public archive\n".to_string(),
+            );
+        }
+        let data_addr = match str_to_addr(addr) {
+            Ok(data_addr) => data_addr,
+            Err(err) => {
+                return text_response(
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid data address {addr:?}: {err}"),
+                )
+            }
+        };
+
+        if file_path.is_empty() {
+            self.serve_data(addr, data_addr, range_header).await
+        } else {
+            self.serve_archive_path(addr, data_addr, file_path, range_header)
+                .await
+        }
+    }
+
+    /// Serve the data at the address, or the listing of its files if it's an archive.
+    async fn serve_data(
+        self: &Arc<Self>,
+        addr: &str,
+        data_addr: DataAddr,
+        range_header: Option<&str>,
+    ) -> Response<Body> {
+        let mut pieces = self.fetch(data_addr, 0);
+        let (received, complete) = match probe_archive(&mut pieces).await {
+            Ok(probed) => probed,
+            Err(err) => return fetch_error_response(err),
+        };
+
+        let head = received.first().cloned().unwrap_or_default();
+        if !complete {
+            // too big to be an archive, and with its size unknown ranges can't be served
+            return Response::builder()
+                .header(CONTENT_TYPE, sniff_content_type(&head))
+                .body(stream_body(received, pieces, 0, None))
+                .unwrap_or_default();
+        }
+
+        let data: Bytes = received.concat().into();
+        if let Ok(archive) = PublicArchive::from_bytes(data.clone()) {
+            return archive_listing(addr, &archive, "");
+        }
+        data_response(
+            received,
+            pieces,
+            0,
+            data.len() as u64,
+            sniff_content_type(&data),
+            range_header,
+        )
+    }
+
+    /// Serve the file at the path of the archive, or the listing of the files under it if it's a folder.
+    async fn serve_archive_path(
+        self: &Arc<Self>,
+        addr: &str,
+        archive_addr: DataAddr,
+        file_path: &str,
+        range_header: Option<&str>,
+    ) -> Response<Body> {
+        let mut pieces = self.fetch(archive_addr, 0);
+        let archive = match probe_archive(&mut pieces).await {
+            Ok((received, true)) => PublicArchive::from_bytes(received.concat().into()).ok(),
+            // too big to be an archive
+            Ok((_, false)) => None,
+            Err(err) => return fetch_error_response(err),
+        };
+        let Some(archive) = archive else {
+            return text_response(StatusCode::NOT_FOUND, format!("{addr} is not an archive"));
+        };
+
+        let path = Path::new(file_path);
+        if let Some((data_addr, meta)) = archive.map().get(path) {
+            let content_type = content_type_from_path(path).unwrap_or("application/octet-stream");
+            // only the chunks from the start of the range on are fetched
+            let from = match parse_range(range_header, meta.size) {
+                ByteRange::Partial(start, _) => start,
+                ByteRange::Full | ByteRange::Unsatisfiable => 0,
+            };
+            let pieces = self.fetch(*data_addr, from);
+            return data_response(
+                Vec::new(),
+                pieces,
+                from,
+                meta.size,
+                content_type,
+                range_header,
+            );
+        }
+
+        let folder = format!("{}/", file_path.trim_end_matches('/'));
+        if archive
+            .iter()
+            .any(|(path, _, _)| path.to_string_lossy().starts_with(&folder))
+        {
+            return archive_listing(addr, &archive, &folder);
+        }
+        text_response(
+            StatusCode::NOT_FOUND,
+            format!("No {file_path:?} in the archive {addr}"),
+        )
+    }
+}
+
+/// Receive the pieces of the data up to `ARCHIVE_PROBE_LIMIT`, for an archive to be told apart without fetching
+/// all of a big file. Returns the received pieces, and whether they're all of the data.
+async fn probe_archive(
+    pieces: &mut mpsc::Receiver<Result<Bytes, GetError>>,
+) -> Result<(Vec<Bytes>, bool), GetError> {
+    let mut received = Vec::new();
+    let mut received_size = 0;
+    while received_size <= ARCHIVE_PROBE_LIMIT {
+        match pieces.recv().await {
+            Some(piece) => {
+                let piece = piece?;
+                received_size += piece.len();
+                received.push(piece);
+            }
+            None => return Ok((received, true)),
+        }
+    }
+    Ok((received, false))
+}
+
+/// Connect to the network and serve its public data over HTTP until stopped.
+pub async fn run(
+    address: IpAddr,
+    port: u16,
+    cache_size_mb: usize,
+    peers: Vec<Multiaddr>,
+) -> Result<()> {
+    let client = crate::actions::connect_to_network(peers).await?;
+    let gateway = Arc::new(Gateway {
+        client,
+        cache: Mutex::new(ChunkCache::new(cache_size_mb * 1024 * 1024)),
+    });
+
+    let make_service = make_service_fn(move |_conn| {
+        let gateway = Arc::clone(&gateway);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let gateway = Arc::clone(&gateway);
+                async move { Ok::<_, Infallible>(gateway.handle(request).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&SocketAddr::new(address, port))
+        .wrap_err("Failed to bind the gateway address")?
+        .serve(make_service);
+    info!(
+        "Gateway serving the network data on http://{}",
+        server.local_addr()
+    );
+    status!(
+        "Gateway serving the network data on http://{}",
+        server.local_addr()
+    );
+    server.await.wrap_err("The gateway failed")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_header_is_parsed_against_the_size() {
+        assert_eq!(parse_range(None, 100), ByteRange::Full);
+        assert_eq!(
+            parse_range(Some("bytes=0-9"), 100),
+            ByteRange::Partial(0, 9)
+        );
+        assert_eq!(
+            parse_range(Some("bytes=90-"), 100),
+            ByteRange::Partial(90, 99)
+        );
+        assert_eq!(
+            parse_range(Some("bytes=-10"), 100),
+            ByteRange::Partial(90, 99)
+        );
+        assert_eq!(
+            parse_range(Some("bytes=50-500"), 100),
+            ByteRange::Partial(50, 99)
+        );
+        assert_eq!(
+            parse_range(Some("bytes=100-"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            parse_range(Some("bytes=9-0"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(parse_range(Some("bytes=0-9,20-29"), 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("items=0-9"), 100), ByteRange::Full);
+    }
+
+    #[tokio::test]
+    async fn only_the_head_of_big_data_is_probed_for_an_archive() {
+        let (pieces_tx, mut pieces) = mpsc::channel(4);
+        let _handle = tokio::spawn(async move {
+            let piece = Bytes::from(vec![0u8; 1024 * 1024]);
+            while pieces_tx.send(Ok(piece.clone())).await.is_ok() {}
+        });
+        let (received, complete) = probe_archive(&mut pieces).await.expect("probe to succeed");
+        assert!(!complete);
+        assert_eq!(received.len(), ARCHIVE_PROBE_LIMIT / (1024 * 1024) + 1);
+
+        let (pieces_tx, mut pieces) = mpsc::channel(4);
+        pieces_tx
+            .send(Ok(Bytes::from_static(b"small")))
+            .await
+            .expect("piece to be sent");
+        drop(pieces_tx);
+        let (received, complete) = probe_archive(&mut pieces).await.expect("probe to succeed");
+        assert!(complete);
+        assert_eq!(received, vec![Bytes::from_static(b"small")]);
+    }
+
+    #[test]
+    fn pieces_are_sliced_to_the_range() {
+        let piece = Bytes::from_static(b"0123456789");
+        assert_eq!(slice_piece(&piece, 0, None), Some(piece.clone()));
+        assert_eq!(
+            slice_piece(&piece, 10, Some((12, 14))),
+            Some(Bytes::from_static(b"234"))
+        );
+        assert_eq!(
+            slice_piece(&piece, 10, Some((5, 12))),
+            Some(Bytes::from_static(b"012"))
+        );
+        assert_eq!(
+            slice_piece(&piece, 10, Some((18, 30))),
+            Some(Bytes::from_static(b"89"))
+        );
+        assert_eq!(slice_piece(&piece, 10, Some((0, 9))), None);
+        assert_eq!(slice_piece(&piece, 10, Some((20, 30))), None);
+
+        assert_eq!(skip_to(piece.clone(), 10, 0), Some(piece.clone()));
+        assert_eq!(
+            skip_to(piece.clone(), 10, 17),
+            Some(Bytes::from_static(b"789"))
+        );
+        assert_eq!(skip_to(piece, 10, 20), None);
+    }
+
+    #[test]
+    fn content_type_is_detected() {
+        assert_eq!(
+            content_type_from_path(Path::new("site/index.HTML")),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(
+            content_type_from_path(Path::new("photo.jpg")),
+            Some("image/jpeg")
+        );
+        assert_eq!(content_type_from_path(Path::new("unknown.bin")), None);
+        assert_eq!(content_type_from_path(Path::new("README")), None);
+
+        assert_eq!(sniff_content_type(b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(sniff_content_type(b"\0\0\0\x20ftypisom"), "video/mp4");
+        assert_eq!(
+            sniff_content_type(b"  <!DOCTYPE html><html>"),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(
+            sniff_content_type(b"<html></html>"),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(sniff_content_type(b"<HTML>"), "text/html; charset=utf-8");
+        assert_eq!(
+            sniff_content_type("<!DOCTYPE htmé".as_bytes()),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            sniff_content_type(b"hello \xc3"),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(
+            sniff_content_type(b"\xff\xfe\0\x01"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn paths_are_percent_coded() {
+        assert_eq!(percent_decode("/a%20b/c%2Fd%zz"), "/a b/c/d%zz");
+        assert_eq!(
+            percent_encode_path("photos/my cat.jpg"),
+            "photos/my%20cat.jpg"
+        );
+        assert_eq!(
+            percent_decode(&percent_encode_path("dir/ü &.txt")),
+            "dir/ü &.txt"
+        );
+    }
+
+    #[test]
+    fn chunk_cache_evicts_the_oldest_data() {
+        let addrs: Vec<DataAddr> = (0..3)
+            .map(|_| DataAddr::random(&mut rand::thread_rng()))
+            .collect();
+        let mut cache = ChunkCache::new(10);
+
+        cache.insert(addrs[0], vec![Bytes::from_static(b"0123")]);
+        cache.insert(
+            addrs[1],
+            vec![Bytes::from_static(b"01"), Bytes::from_static(b"23")],
+        );
+        assert!(cache.get(&addrs[0]).is_some());
+        assert_eq!(cache.size, 8);
+
+        cache.insert(addrs[2], vec![Bytes::from_static(b"0123")]);
+        assert!(cache.get(&addrs[0]).is_none());
+        assert!(cache.get(&addrs[1]).is_some());
+        assert!(cache.get(&addrs[2]).is_some());
+        assert_eq!(cache.size, 8);
+
+        // too big to be cached at all
+        cache.insert(addrs[0], vec![Bytes::from_static(b"0123456789abc")]);
+        assert!(cache.get(&addrs[0]).is_none());
+        assert_eq!(cache.size, 8);
+    }
+}
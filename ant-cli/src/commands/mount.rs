@@ -0,0 +1,519 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use autonomi::client::address::str_to_addr;
+use autonomi::client::data::{DataAddr, GetError};
+use autonomi::client::files::archive::Metadata;
+use autonomi::client::files::archive_public::PublicArchive;
+use autonomi::{Bytes, Client, Multiaddr};
+use color_eyre::eyre::{Context, Result};
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    ReplyOpen, Request, FUSE_ROOT_ID,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+
+/// How long the kernel caches the attributes and entries, which never change as the archive is immutable.
+const ATTR_TTL: Duration = Duration::from_secs(3600);
+
+/// The max bytes of the files kept in memory, the least recently read ones being dropped first.
+const CONTENT_CACHE_SIZE: usize = 256 * 1024 * 1024;
+
+/// The max bytes kept of a file being read, the ones before the latest read being dropped first. Reading before
+/// them fetches the file again from its start.
+const MAX_FILE_WINDOW: usize = 64 * 1024 * 1024;
+
+/// An entry of the mounted archive, its inode being its index in the tree plus one.
+#[derive(Debug)]
+enum Node {
+    Dir {
+        parent: u64,
+        children: BTreeMap<OsString, u64>,
+    },
+    File {
+        addr: DataAddr,
+        meta: Metadata,
+    },
+}
+
+/// The folders and files of an archive, as inodes.
+#[derive(Debug)]
+struct ArchiveTree {
+    nodes: Vec<Node>,
+    /// The owner of the entries, the one of the mountpoint
+    uid: u32,
+    gid: u32,
+}
+
+impl ArchiveTree {
+    fn new(archive: &PublicArchive, uid: u32, gid: u32) -> Self {
+        let mut tree = Self {
+            nodes: vec![Node::Dir {
+                parent: FUSE_ROOT_ID,
+                children: BTreeMap::new(),
+            }],
+            uid,
+            gid,
+        };
+        for (path, addr, meta) in archive.iter() {
+            let names: Vec<&OsStr> = path
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name),
+                    _ => None,
+                })
+                .collect();
+            let Some((file_name, dir_names)) = names.split_last() else {
+                continue;
+            };
+            let mut parent = FUSE_ROOT_ID;
+            for name in dir_names {
+                parent = tree.dir_child(parent, name);
+            }
+            let ino = tree.nodes.len() as u64 + 1;
+            if let Some(Node::Dir { children, .. }) = tree.node_mut(parent) {
+                if children.contains_key(*file_name) {
+                    warn!("Skipping {path:?}, clashing with a folder of the archive");
+                    continue;
+                }
+                let _ = children.insert(file_name.to_os_string(), ino);
+            }
+            tree.nodes.push(Node::File {
+                addr: *addr,
+                meta: meta.clone(),
+            });
+        }
+        tree
+    }
+
+    /// The inode of the folder in the parent one, created if need be.
+    fn dir_child(&mut self, parent: u64, name: &OsStr) -> u64 {
+        let ino = self.nodes.len() as u64 + 1;
+        let Some(Node::Dir { children, .. }) = self.node_mut(parent) else {
+            return parent;
+        };
+        if let Some(child) = children.get(name) {
+            return *child;
+        }
+        let _ = children.insert(name.to_os_string(), ino);
+        self.nodes.push(Node::Dir {
+            parent,
+            children: BTreeMap::new(),
+        });
+        ino
+    }
+
+    fn node(&self, ino: u64) -> Option<&Node> {
+        self.nodes.get(usize::try_from(ino).ok()?.checked_sub(1)?)
+    }
+
+    fn node_mut(&mut self, ino: u64) -> Option<&mut Node> {
+        self.nodes
+            .get_mut(usize::try_from(ino).ok()?.checked_sub(1)?)
+    }
+
+    fn lookup(&self, parent: u64, name: &OsStr) -> Option<u64> {
+        match self.node(parent)? {
+            Node::Dir { children, .. } => children.get(name).copied(),
+            Node::File { .. } => None,
+        }
+    }
+
+    fn attr(&self, ino: u64) -> Option<FileAttr> {
+        let (kind, perm, size, mtime) = match self.node(ino)? {
+            Node::Dir { .. } => (FileType::Directory, 0o555, 0, UNIX_EPOCH),
+            Node::File { meta, .. } => (
+                FileType::RegularFile,
+                0o444,
+                meta.size,
+                UNIX_EPOCH + Duration::from_secs(meta.modified),
+            ),
+        };
+        Some(FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink: if kind == FileType::Directory { 2 } else { 1 },
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 512,
+            flags: 0,
+        })
+    }
+}
+
+/// The fetched bytes of a file, fetching more of them on demand.
+struct FileContent {
+    /// The pieces kept, starting at `start` in the file
+    pieces: VecDeque<Bytes>,
+    start: usize,
+    /// The offset in the file of the end of the pieces kept
+    end: usize,
+    incoming: Option<mpsc::Receiver<Result<Bytes, GetError>>>,
+}
+
+impl FileContent {
+    fn new(incoming: mpsc::Receiver<Result<Bytes, GetError>>) -> Self {
+        Self {
+            pieces: VecDeque::new(),
+            start: 0,
+            end: 0,
+            incoming: Some(incoming),
+        }
+    }
+
+    fn kept_size(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Read up to `len` bytes at the offset, waiting for the pieces not fetched yet. `None` when the offset was
+    /// dropped already, for the file to be fetched again.
+    fn read_at(
+        &mut self,
+        offset: usize,
+        len: usize,
+        runtime: &Handle,
+    ) -> Option<Result<Vec<u8>, GetError>> {
+        if offset < self.start {
+            return None;
+        }
+        while self.end < offset + len {
+            let Some(incoming) = self.incoming.as_mut() else {
+                break;
+            };
+            match runtime.block_on(incoming.recv()) {
+                Some(Ok(piece)) => {
+                    self.end += piece.len();
+                    self.pieces.push_back(piece);
+                }
+                Some(Err(err)) => {
+                    self.incoming = None;
+                    return Some(Err(err));
+                }
+                None => self.incoming = None,
+            }
+        }
+
+        let mut data = Vec::with_capacity(len);
+        let mut piece_start = self.start;
+        for piece in &self.pieces {
+            let piece_end = piece_start + piece.len();
+            if piece_end > offset && piece_start < offset + len {
+                let from = offset.saturating_sub(piece_start);
+                let to = usize::min(piece.len(), offset + len - piece_start);
+                data.extend_from_slice(&piece[from..to]);
+            }
+            piece_start = piece_end;
+        }
+
+        // Drop the pieces before this read, for a big file not to be held in memory as a whole
+        while self.kept_size() > MAX_FILE_WINDOW {
+            match self.pieces.front() {
+                Some(piece) if self.start + piece.len() <= offset => {
+                    self.start += piece.len();
+                    let _ = self.pieces.pop_front();
+                }
+                _ => break,
+            }
+        }
+        Some(Ok(data))
+    }
+}
+
+/// Serves a public archive read-only, fetching the files from the network as they're read.
+struct ArchiveFs {
+    client: Client,
+    runtime: Handle,
+    tree: ArchiveTree,
+    contents: HashMap<u64, FileContent>,
+    /// The inodes of the contents, least recently read first
+    order: VecDeque<u64>,
+}
+
+impl ArchiveFs {
+    /// Fetch the data in the background, sending its pieces in order.
+    fn fetch(&self, addr: DataAddr) -> mpsc::Receiver<Result<Bytes, GetError>> {
+        let (pieces_tx, pieces_rx) = mpsc::channel(4);
+        let client = self.client.clone();
+        let _handle = self.runtime.spawn(async move {
+            let stream = match client.data_stream_public(addr).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    let _ = pieces_tx.send(Err(err)).await;
+                    return;
+                }
+            };
+            tokio::pin!(stream);
+            while let Some(piece) = stream.next().await {
+                let is_err = piece.is_err();
+                if pieces_tx.send(piece).await.is_err() || is_err {
+                    return;
+                }
+            }
+        });
+        pieces_rx
+    }
+
+    /// Read the file at the offset, `None` when it failed to be fetched.
+    fn read_file(
+        &mut self,
+        ino: u64,
+        addr: DataAddr,
+        offset: usize,
+        len: usize,
+    ) -> Option<Vec<u8>> {
+        self.order.retain(|cached| *cached != ino);
+        self.order.push_back(ino);
+        loop {
+            if !self.contents.contains_key(&ino) {
+                let content = FileContent::new(self.fetch(addr));
+                let _ = self.contents.insert(ino, content);
+            }
+            let read = self
+                .contents
+                .get_mut(&ino)
+                .and_then(|content| content.read_at(offset, len, &self.runtime));
+            match read {
+                Some(Ok(data)) => {
+                    self.evict_contents(ino);
+                    return Some(data);
+                }
+                Some(Err(err)) => {
+                    error!("Failed to read {addr:?} at {offset}: {err}");
+                    let _ = self.contents.remove(&ino);
+                    return None;
+                }
+                None => {
+                    debug!("Fetching {addr:?} again to read it at {offset}");
+                    let _ = self.contents.remove(&ino);
+                }
+            }
+        }
+    }
+
+    /// Drop the least recently read contents over the cache size, but the one just read.
+    fn evict_contents(&mut self, current: u64) {
+        let mut size: usize = self.contents.values().map(FileContent::kept_size).sum();
+        while size > CONTENT_CACHE_SIZE {
+            let Some(oldest) = self.order.front().copied() else {
+                break;
+            };
+            if oldest == current {
+                break;
+            }
+            let _ = self.order.pop_front();
+            if let Some(content) = self.contents.remove(&oldest) {
+                size -= content.kept_size();
+            }
+        }
+    }
+}
+
+impl Filesystem for ArchiveFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        match self
+            .tree
+            .lookup(parent, name)
+            .and_then(|ino| self.tree.attr(ino))
+        {
+            Some(attr) => reply.entry(&ATTR_TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.tree.attr(ino) {
+            Some(attr) => reply.attr(&ATTR_TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            return reply.error(libc::EROFS);
+        }
+        match self.tree.node(ino) {
+            Some(Node::File { .. }) => reply.opened(0, 0),
+            Some(Node::Dir { .. }) => reply.error(libc::EISDIR),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let addr = match self.tree.node(ino) {
+            Some(Node::File { addr, .. }) => *addr,
+            Some(Node::Dir { .. }) => return reply.error(libc::EISDIR),
+            None => return reply.error(libc::ENOENT),
+        };
+        let Ok(offset) = usize::try_from(offset) else {
+            return reply.error(libc::EINVAL);
+        };
+        match self.read_file(ino, addr, offset, size as usize) {
+            Some(data) => reply.data(&data),
+            None => reply.error(libc::EIO),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let (parent, children) = match self.tree.node(ino) {
+            Some(Node::Dir { parent, children }) => (*parent, children),
+            Some(Node::File { .. }) => return reply.error(libc::ENOTDIR),
+            None => return reply.error(libc::ENOENT),
+        };
+        let entries = [
+            (ino, FileType::Directory, OsStr::new(".")),
+            (parent, FileType::Directory, OsStr::new("..")),
+        ]
+        .into_iter()
+        .chain(children.iter().map(|(name, child)| {
+            let kind = match self.tree.node(*child) {
+                Some(Node::Dir { .. }) => FileType::Directory,
+                _ => FileType::RegularFile,
+            };
+            (*child, kind, name.as_os_str())
+        }));
+        for (index, (child, kind, name)) in entries.enumerate().skip(offset.max(0) as usize) {
+            if reply.add(child, index as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mount the public archive at the address read-only, until unmounted.
+pub async fn mount(addr: &str, mountpoint: PathBuf, peers: Vec<Multiaddr>) -> Result<()> {
+    let archive_addr = str_to_addr(addr).wrap_err("Failed to parse the archive address")?;
+    let client = crate::actions::connect_to_network(peers).await?;
+    let archive = client
+        .archive_get_public(archive_addr)
+        .await
+        .wrap_err("Failed to fetch the archive")?;
+
+    let owner = std::fs::metadata(&mountpoint)
+        .wrap_err_with(|| format!("Failed to read the mountpoint {mountpoint:?}"))?;
+    let fs = ArchiveFs {
+        client,
+        runtime: Handle::current(),
+        tree: ArchiveTree::new(&archive, owner.uid(), owner.gid()),
+        contents: HashMap::new(),
+        order: VecDeque::new(),
+    };
+    status!("Mounting {addr} read-only at {mountpoint:?}, until unmounted");
+    info!("Mounting the archive {addr} at {mountpoint:?}");
+    // The file system blocks on fetching the files, so it's served off the runtime threads
+    let options = [
+        MountOption::RO,
+        MountOption::FSName(format!("autonomi:{addr}")),
+        MountOption::Subtype("autonomi".to_string()),
+    ];
+    tokio::task::spawn_blocking(move || fuser::mount2(fs, &mountpoint, &options))
+        .await
+        .wrap_err("The mount panicked")?
+        .wrap_err("Failed to mount the archive")?;
+    status!("Unmounted {addr}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(paths: &[&str]) -> PublicArchive {
+        let mut archive = PublicArchive::new();
+        for (index, path) in paths.iter().enumerate() {
+            let addr = DataAddr::from_content(path.as_bytes());
+            archive.add_file(
+                PathBuf::from(path),
+                addr,
+                Metadata::new_with_size(index as u64 + 1),
+            );
+        }
+        archive
+    }
+
+    #[test]
+    fn archive_folders_are_mapped_to_inodes() {
+        let tree = ArchiveTree::new(
+            &archive(&["site/index.html", "site/img/cat.png", "README"]),
+            1000,
+            1000,
+        );
+
+        let site = tree
+            .lookup(FUSE_ROOT_ID, OsStr::new("site"))
+            .expect("site folder");
+        let img = tree.lookup(site, OsStr::new("img")).expect("img folder");
+        let cat = tree.lookup(img, OsStr::new("cat.png")).expect("cat file");
+        assert!(tree.lookup(FUSE_ROOT_ID, OsStr::new("cat.png")).is_none());
+
+        let attr = tree.attr(cat).expect("cat attributes");
+        assert_eq!(attr.kind, FileType::RegularFile);
+        assert_eq!(attr.size, 2);
+        assert_eq!(attr.perm, 0o444);
+        assert_eq!(
+            tree.attr(img).expect("img attributes").kind,
+            FileType::Directory
+        );
+        assert!(tree.attr(tree.nodes.len() as u64 + 1).is_none());
+        assert!(tree.attr(0).is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn file_content_is_read_as_its_pieces_arrive() {
+        let runtime = Handle::current();
+        let (pieces_tx, pieces_rx) = mpsc::channel(4);
+        let _handle = tokio::spawn(async move {
+            for piece in [&b"0123"[..], b"4567", b"89"] {
+                let _ = pieces_tx.send(Ok(Bytes::from_static(piece))).await;
+            }
+        });
+
+        let reads = tokio::task::spawn_blocking(move || {
+            let mut content = FileContent::new(pieces_rx);
+            let first = content.read_at(2, 4, &runtime);
+            let rest = content.read_at(6, 100, &runtime);
+            (first, rest)
+        })
+        .await
+        .expect("reads to complete");
+        assert_eq!(reads.0.expect("in range").expect("read"), b"2345");
+        assert_eq!(reads.1.expect("in range").expect("read"), b"6789");
+    }
+}
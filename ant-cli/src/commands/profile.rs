@@ -0,0 +1,80 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::access::profile::{
+    load_profiles, remove_profile, save_profile, selected_profile, NetworkProfile,
+};
+use ant_bootstrap::PeersArgs;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde_json::json;
+use std::path::PathBuf;
+
+pub fn create(
+    name: &str,
+    peers: PeersArgs,
+    network_id: Option<u8>,
+    wallet_dir: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    overwrite: bool,
+) -> Result<()> {
+    if !overwrite && load_profiles()?.contains_key(name) {
+        return Err(eyre!(
+            "The profile {name:?} already exists, use --overwrite to replace it"
+        ));
+    }
+    let profile = NetworkProfile {
+        peers,
+        network_id,
+        wallet_dir,
+        data_dir,
+    };
+    save_profile(name, profile)?;
+    status!("✅ Profile {name:?} saved, select it with `--profile {name}`");
+    crate::output::json_result(json!({ "profile": name }));
+    Ok(())
+}
+
+pub fn list() -> Result<()> {
+    let profiles = load_profiles()?;
+    let selected = selected_profile().map(|selected| selected.name.as_str());
+    status!("✅ You have {} profile(s):", profiles.len());
+    for (name, profile) in &profiles {
+        let network_id = profile
+            .network_id
+            .map_or_else(|| "default".to_string(), |id| id.to_string());
+        let marker = if selected == Some(name.as_str()) {
+            " (selected)"
+        } else {
+            ""
+        };
+        status!("{name}{marker}: network ID {network_id}");
+    }
+    crate::output::json_result(json!({
+        "profiles": profiles
+            .iter()
+            .map(|(name, profile)| json!({
+                "name": name,
+                "network_id": profile.network_id,
+                "wallet_dir": profile.wallet_dir,
+                "data_dir": profile.data_dir,
+                "selected": selected == Some(name.as_str()),
+            }))
+            .collect::<Vec<_>>(),
+    }));
+    Ok(())
+}
+
+pub fn remove(name: &str) -> Result<()> {
+    if !remove_profile(name)? {
+        return Err(eyre!("No profile named {name:?}"));
+    }
+    status!("✅ Profile {name:?} removed, its data was left on disk");
+    crate::output::json_result(json!({ "profile": name }));
+    Ok(())
+}
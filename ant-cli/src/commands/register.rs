@@ -13,12 +13,14 @@ use crate::wallet::load_wallet;
 use autonomi::client::registers::RegisterAddress;
 use autonomi::client::registers::RegisterPermissions;
 use autonomi::client::registers::RegisterSecretKey;
+use autonomi::client::registers::MAX_REG_ENTRY_SIZE;
 use autonomi::Client;
 use autonomi::Multiaddr;
 use color_eyre::eyre::eyre;
 use color_eyre::eyre::Context;
 use color_eyre::eyre::Result;
 use color_eyre::Section;
+use serde_json::json;
 
 pub fn generate_key(overwrite: bool) -> Result<()> {
     // check if the key already exists
@@ -35,7 +37,8 @@ pub fn generate_key(overwrite: bool) -> Result<()> {
     let path = crate::keys::create_register_signing_key_file(key)
         .wrap_err("Failed to create new register key")?;
     info!("Created new register key at: {path:?}");
-    println!("✅ Created new register key at: {}", path.display());
+    status!("✅ Created new register key at: {}", path.display());
+    crate::output::json_result(json!({ "key_path": path }));
     Ok(())
 }
 
@@ -49,7 +52,11 @@ pub async fn cost(name: &str, peers: Vec<Multiaddr>) -> Result<()> {
         .await
         .wrap_err("Failed to get cost for register")?;
     info!("Estimated cost to create a register with name {name}: {cost}");
-    println!("✅ The estimated cost to create a register with name {name} is: {cost}");
+    status!("✅ The estimated cost to create a register with name {name} is: {cost}");
+    crate::output::json_result(json!({
+        "name": name,
+        "cost": cost.to_string(),
+    }));
     Ok(())
 }
 
@@ -61,10 +68,10 @@ pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>
     let event_receiver = client.enable_client_events();
     let (upload_summary_thread, upload_completed_tx) = collect_upload_summary(event_receiver);
 
-    println!("Creating register with name: {name}");
+    status!("Creating register with name: {name}");
     info!("Creating register with name: {name}");
     let register = if public {
-        println!("With public write access");
+        status!("With public write access");
         info!("With public write access");
         let permissions = RegisterPermissions::new_anyone_can_write();
         client
@@ -78,7 +85,7 @@ pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>
             .await
             .wrap_err("Failed to create register")?
     } else {
-        println!("With private write access");
+        status!("With private write access");
         info!("With private write access");
         client
             .register_create(
@@ -100,14 +107,14 @@ pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>
 
     let summary = upload_summary_thread.await?;
     if summary.record_count == 0 {
-        println!("✅ The register already exists on the network at address: {address}.");
-        println!("No tokens were spent.");
+        status!("✅ The register already exists on the network at address: {address}.");
+        status!("No tokens were spent.");
     } else {
-        println!("✅ Register created at address: {address}");
-        println!("With name: {name}");
-        println!("And initial value: [{value}]");
+        status!("✅ Register created at address: {address}");
+        status!("With name: {name}");
+        status!("And initial value: [{value}]");
         info!("Register created at address: {address} with name: {name}");
-        println!("Total cost: {} AttoTokens", summary.tokens_spent);
+        status!("Total cost: {} AttoTokens", summary.tokens_spent);
     }
     info!("Summary of register creation: {summary:?}");
 
@@ -116,6 +123,13 @@ pub async fn create(name: &str, value: &str, public: bool, peers: Vec<Multiaddr>
         .with_suggestion(|| "Local user data saves the register address above to disk, without it you need to keep track of the address yourself")?;
     info!("Saved register to local user data");
 
+    crate::output::json_result(json!({
+        "address": address.to_hex(),
+        "name": name,
+        "value": value,
+        "public": public,
+        "cost": summary.tokens_spent.to_string(),
+    }));
     Ok(())
 }
 
@@ -134,26 +148,45 @@ pub async fn edit(address: String, name: bool, value: &str, peers: Vec<Multiaddr
             })?
     };
 
-    println!("Getting register at address: {address}");
+    status!("Getting register at address: {address}");
     info!("Getting register at address: {address}");
     let register = client
         .register_get(address)
         .await
         .wrap_err(format!("Failed to get register at address: {address}"))?;
 
-    println!("Found register at address: {address}");
-    println!("Updating register with new value: {value}");
+    status!("Found register at address: {address}");
+    status!("Updating register with new value: {value}");
     info!("Updating register at address: {address} with new value: {value}");
 
-    client
-        .register_update(register, value.as_bytes().to_vec().into(), register_key)
-        .await
-        .wrap_err(format!("Failed to update register at address: {address}"))?;
+    if value.len() > MAX_REG_ENTRY_SIZE {
+        status!("The value is too big for a register entry, storing it as data");
+        info!("Storing the value of {} bytes as data", value.len());
+        let wallet = load_wallet()?;
+        client
+            .register_update_with_wallet(
+                register,
+                value.as_bytes().to_vec().into(),
+                register_key,
+                &wallet,
+            )
+            .await
+            .wrap_err(format!("Failed to update register at address: {address}"))?;
+    } else {
+        client
+            .register_update(register, value.as_bytes().to_vec().into(), register_key)
+            .await
+            .wrap_err(format!("Failed to update register at address: {address}"))?;
+    }
 
-    println!("✅ Successfully updated register");
-    println!("With value: [{value}]");
+    status!("✅ Successfully updated register");
+    status!("With value: [{value}]");
     info!("Successfully updated register at address: {address}");
 
+    crate::output::json_result(json!({
+        "address": address.to_hex(),
+        "value": value,
+    }));
     Ok(())
 }
 
@@ -172,34 +205,49 @@ pub async fn get(address: String, name: bool, peers: Vec<Multiaddr>) -> Result<(
             })?
     };
 
-    println!("Getting register at address: {address}");
+    status!("Getting register at address: {address}");
     info!("Getting register at address: {address}");
     let register = client
         .register_get(address)
         .await
         .wrap_err(format!("Failed to get register at address: {address}"))?;
-    let values = register.values();
+    let values = client.register_values(&register).await.wrap_err(format!(
+        "Failed to get the values of register at address: {address}"
+    ))?;
 
-    println!("✅ Register found at address: {address}");
+    status!("✅ Register found at address: {address}");
     info!("Register found at address: {address}");
     match values.as_slice() {
-        [one] => println!("With value: [{:?}]", String::from_utf8_lossy(one)),
+        [one] => status!("With value: [{:?}]", String::from_utf8_lossy(one)),
         _ => {
-            println!("With multiple concurrent values:");
+            status!("With multiple concurrent values:");
             for value in values.iter() {
-                println!("[{:?}]", String::from_utf8_lossy(value));
+                status!("[{:?}]", String::from_utf8_lossy(value));
             }
         }
     }
+    crate::output::json_result(json!({
+        "address": address.to_hex(),
+        "values": values
+            .iter()
+            .map(|value| String::from_utf8_lossy(value))
+            .collect::<Vec<_>>(),
+    }));
     Ok(())
 }
 
 pub fn list() -> Result<()> {
-    println!("Retrieving local user data...");
+    status!("Retrieving local user data...");
     let registers = crate::user_data::get_local_registers()?;
-    println!("✅ You have {} register(s):", registers.len());
-    for (addr, name) in registers {
-        println!("{}: {}", name, addr.to_hex());
+    status!("✅ You have {} register(s):", registers.len());
+    for (addr, name) in &registers {
+        status!("{}: {}", name, addr.to_hex());
     }
+    crate::output::json_result(json!({
+        "registers": registers
+            .iter()
+            .map(|(addr, name)| json!({ "name": name, "address": addr.to_hex() }))
+            .collect::<Vec<_>>(),
+    }));
     Ok(())
 }
@@ -11,19 +11,21 @@ use autonomi::Multiaddr;
 use color_eyre::eyre::Context;
 use color_eyre::eyre::Result;
 use color_eyre::Section;
+use serde_json::json;
 
 pub async fn cost(peers: Vec<Multiaddr>) -> Result<()> {
     let client = crate::actions::connect_to_network(peers).await?;
     let vault_sk = crate::keys::get_vault_secret_key()?;
 
-    println!("Getting cost to create a new vault...");
+    status!("Getting cost to create a new vault...");
     let total_cost = client.vault_cost(&vault_sk).await?;
 
     if total_cost.is_zero() {
-        println!("Vault already exists, modifying an existing vault is free");
+        status!("Vault already exists, modifying an existing vault is free");
     } else {
-        println!("Cost to create a new vault: {total_cost} AttoTokens");
+        status!("Cost to create a new vault: {total_cost} AttoTokens");
     }
+    crate::output::json_result(json!({ "cost": total_cost.to_string() }));
     Ok(())
 }
 
@@ -32,28 +34,34 @@ pub async fn create(peers: Vec<Multiaddr>) -> Result<()> {
     let wallet = load_wallet()?;
     let vault_sk = crate::keys::get_vault_secret_key()?;
 
-    println!("Retrieving local user data...");
+    status!("Retrieving local user data...");
     let local_user_data = crate::user_data::get_local_user_data()?;
     let file_archives_len = local_user_data.file_archives.len();
     let private_file_archives_len = local_user_data.private_file_archives.len();
     let registers_len = local_user_data.registers.len();
 
-    println!("Pushing to network vault...");
+    status!("Pushing to network vault...");
     let total_cost = client
         .put_user_data_to_vault(&vault_sk, wallet.into(), local_user_data)
         .await?;
 
     if total_cost.is_zero() {
-        println!("✅ Successfully pushed user data to existing vault");
+        status!("✅ Successfully pushed user data to existing vault");
     } else {
-        println!("✅ Successfully created new vault containing local user data");
+        status!("✅ Successfully created new vault containing local user data");
     }
 
-    println!("Total cost: {total_cost} AttoTokens");
-    println!("Vault contains:");
-    println!("{file_archives_len} public file archive(s)");
-    println!("{private_file_archives_len} private file archive(s)");
-    println!("{registers_len} register(s)");
+    status!("Total cost: {total_cost} AttoTokens");
+    status!("Vault contains:");
+    status!("{file_archives_len} public file archive(s)");
+    status!("{private_file_archives_len} private file archive(s)");
+    status!("{registers_len} register(s)");
+    crate::output::json_result(json!({
+        "cost": total_cost.to_string(),
+        "public_archives": file_archives_len,
+        "private_archives": private_file_archives_len,
+        "registers": registers_len,
+    }));
     Ok(())
 }
 
@@ -62,7 +70,7 @@ pub async fn sync(peers: Vec<Multiaddr>, force: bool) -> Result<()> {
     let vault_sk = crate::keys::get_vault_secret_key()?;
     let wallet = load_wallet()?;
 
-    println!("Fetching vault from network...");
+    status!("Fetching vault from network...");
     let net_user_data = client
         .get_user_data_from_vault(&vault_sk)
         .await
@@ -70,13 +78,13 @@ pub async fn sync(peers: Vec<Multiaddr>, force: bool) -> Result<()> {
         .with_suggestion(|| "Make sure you have already created a vault on the network")?;
 
     if force {
-        println!("The force flag was provided, overwriting user data in the vault with local user data...");
+        status!("The force flag was provided, overwriting user data in the vault with local user data...");
     } else {
-        println!("Syncing vault with local user data...");
+        status!("Syncing vault with local user data...");
         crate::user_data::write_local_user_data(&net_user_data)?;
     }
 
-    println!("Pushing local user data to network vault...");
+    status!("Pushing local user data to network vault...");
     let local_user_data = crate::user_data::get_local_user_data()?;
     let file_archives_len = local_user_data.file_archives.len();
     let private_file_archives_len = local_user_data.private_file_archives.len();
@@ -85,11 +93,16 @@ pub async fn sync(peers: Vec<Multiaddr>, force: bool) -> Result<()> {
         .put_user_data_to_vault(&vault_sk, wallet.into(), local_user_data)
         .await?;
 
-    println!("✅ Successfully synced vault");
-    println!("Vault contains:");
-    println!("{file_archives_len} public file archive(s)");
-    println!("{private_file_archives_len} private file archive(s)");
-    println!("{registers_len} register(s)");
+    status!("✅ Successfully synced vault");
+    status!("Vault contains:");
+    status!("{file_archives_len} public file archive(s)");
+    status!("{private_file_archives_len} private file archive(s)");
+    status!("{registers_len} register(s)");
+    crate::output::json_result(json!({
+        "public_archives": file_archives_len,
+        "private_archives": private_file_archives_len,
+        "registers": registers_len,
+    }));
     Ok(())
 }
 
@@ -97,17 +110,22 @@ pub async fn load(peers: Vec<Multiaddr>) -> Result<()> {
     let client = crate::actions::connect_to_network(peers).await?;
     let vault_sk = crate::keys::get_vault_secret_key()?;
 
-    println!("Retrieving vault from network...");
+    status!("Retrieving vault from network...");
     let user_data = client.get_user_data_from_vault(&vault_sk).await?;
-    println!("Writing user data to disk...");
+    status!("Writing user data to disk...");
     crate::user_data::write_local_user_data(&user_data)?;
 
-    println!("✅ Successfully loaded vault with:");
-    println!("{} public file archive(s)", user_data.file_archives.len());
-    println!(
+    status!("✅ Successfully loaded vault with:");
+    status!("{} public file archive(s)", user_data.file_archives.len());
+    status!(
         "{} private file archive(s)",
         user_data.private_file_archives.len()
     );
-    println!("{} register(s)", user_data.registers.len());
+    status!("{} register(s)", user_data.registers.len());
+    crate::output::json_result(json!({
+        "public_archives": user_data.file_archives.len(),
+        "private_archives": user_data.private_file_archives.len(),
+        "registers": user_data.registers.len(),
+    }));
     Ok(())
 }
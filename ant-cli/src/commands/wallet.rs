@@ -6,20 +6,39 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::wallet::fs::{select_wallet, select_wallet_private_key, store_private_key};
-use crate::wallet::input::request_password;
+use crate::wallet::fs::{
+    encrypt_stored_private_key, select_wallet, select_wallet_address, select_wallet_private_key,
+    store_private_key,
+};
+use crate::wallet::input::{get_password_input, request_password};
+use crate::wallet::portable::PortableWallet;
 use crate::wallet::DUMMY_NETWORK;
-use autonomi::Wallet;
+use autonomi::mnemonic::{
+    private_key_from_mnemonic as derive_private_key, random_mnemonic, DEFAULT_DERIVATION_PATH,
+};
+use autonomi::{get_evm_network_from_env, Wallet, WatchOnlyWallet};
 use color_eyre::eyre::eyre;
 use color_eyre::Result;
 use prettytable::{Cell, Row, Table};
+use serde_json::json;
+use std::path::{Path, PathBuf};
 
 const WALLET_PASSWORD_REQUIRED: bool = false;
+const MNEMONIC_WORD_COUNT: usize = 24;
 
-pub fn create(no_password: bool, password: Option<String>) -> Result<()> {
+pub fn create(mnemonic: bool, no_password: bool, password: Option<String>) -> Result<()> {
     let maybe_encryption_password = maybe_request_password(no_password, password)?;
 
-    let wallet_private_key = Wallet::random_private_key();
+    let mut mnemonic_phrase = None;
+    let wallet_private_key = if mnemonic {
+        let mnemonic = random_mnemonic(MNEMONIC_WORD_COUNT)?;
+        status!("Wallet mnemonic (write it down and keep it safe): {mnemonic}");
+        let private_key = derive_private_key(&mnemonic.to_string(), "", DEFAULT_DERIVATION_PATH)?;
+        mnemonic_phrase = Some(mnemonic.to_string());
+        private_key
+    } else {
+        Wallet::random_private_key()
+    };
 
     let wallet_address = Wallet::new_from_private_key(DUMMY_NETWORK, &wallet_private_key)
         .expect("Infallible")
@@ -29,10 +48,16 @@ pub fn create(no_password: bool, password: Option<String>) -> Result<()> {
     // Save the private key file
     let file_path = store_private_key(&wallet_private_key, maybe_encryption_password)?;
 
-    println!("Wallet address: {wallet_address}");
-    println!("Wallet private key: {wallet_private_key}");
-    println!("Stored wallet in: {file_path:?}");
+    status!("Wallet address: {wallet_address}");
+    status!("Wallet private key: {wallet_private_key}");
+    status!("Stored wallet in: {file_path:?}");
 
+    crate::output::json_result(json!({
+        "address": wallet_address,
+        "private_key": wallet_private_key,
+        "mnemonic": mnemonic_phrase,
+        "path": file_path,
+    }));
     Ok(())
 }
 
@@ -60,13 +85,34 @@ pub fn import(
     // Save the private key file
     let file_path = store_private_key(&wallet_private_key, maybe_encryption_password)?;
 
-    println!("Wallet address: {wallet_address}");
-    println!("Stored wallet in: {file_path:?}");
+    status!("Wallet address: {wallet_address}");
+    status!("Stored wallet in: {file_path:?}");
 
+    crate::output::json_result(json!({
+        "address": wallet_address,
+        "path": file_path,
+    }));
     Ok(())
 }
 
-pub fn export() -> Result<()> {
+/// Derive the private key of the wallet at the derivation path of the mnemonic phrase.
+pub fn private_key_from_mnemonic(phrase: &str, derivation_path: &str) -> Result<String> {
+    derive_private_key(phrase, "", derivation_path)
+        .map_err(|err| eyre!("Could not restore the wallet from the mnemonic: {err}"))
+}
+
+/// Read the private key of a wallet file written by [`export`], prompting for its password if it is encrypted.
+pub fn private_key_from_file(path: &Path) -> Result<String> {
+    let wallet = PortableWallet::read_from_file(path)?;
+    status!("Importing wallet: {}", wallet.address());
+    let password = wallet
+        .is_encrypted()
+        .then(|| get_password_input("Enter password to decrypt wallet file:"));
+
+    Ok(wallet.private_key(password.as_deref())?)
+}
+
+pub fn export(output: Option<PathBuf>, no_password: bool, password: Option<String>) -> Result<()> {
     let wallet_private_key = select_wallet_private_key()?;
 
     let wallet_address = Wallet::new_from_private_key(DUMMY_NETWORK, &wallet_private_key)
@@ -74,19 +120,66 @@ pub fn export() -> Result<()> {
         .address()
         .to_string();
 
-    println!("Wallet address: {wallet_address}");
-    println!("Wallet private key: {wallet_private_key}");
+    status!("Wallet address: {wallet_address}");
+
+    match output {
+        Some(path) => {
+            let maybe_encryption_password = maybe_request_password(no_password, password)?;
+            PortableWallet::new(&wallet_private_key, maybe_encryption_password.as_deref())?
+                .write_to_file(&path)?;
+            status!("Exported wallet to: {path:?}");
+            crate::output::json_result(json!({
+                "address": wallet_address,
+                "path": path,
+            }));
+        }
+        None => {
+            status!("Wallet private key: {wallet_private_key}");
+            crate::output::json_result(json!({
+                "address": wallet_address,
+                "private_key": wallet_private_key,
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn encrypt(password: Option<String>) -> Result<()> {
+    let wallet_address = select_wallet_address()?;
+
+    let password = match password {
+        Some(password) => password,
+        None => request_password(true).ok_or_else(|| eyre!("A password is required"))?,
+    };
+
+    let file_path = encrypt_stored_private_key(&wallet_address, password)?;
 
+    status!("Wallet address: {wallet_address}");
+    status!("Stored encrypted wallet in: {file_path:?}");
+
+    crate::output::json_result(json!({
+        "address": wallet_address,
+        "path": file_path,
+    }));
     Ok(())
 }
 
-pub async fn balance() -> Result<()> {
-    let wallet = select_wallet()?;
+pub async fn balance(address: Option<String>) -> Result<()> {
+    let wallet = match address {
+        Some(address) => {
+            let address = address
+                .parse()
+                .map_err(|_| eyre!("Please provide a valid wallet address in hex format"))?;
+            WatchOnlyWallet::new(get_evm_network_from_env()?, address)
+        }
+        None => select_wallet()?.watch_only(),
+    };
 
     let token_balance = wallet.balance_of_tokens().await?;
     let gas_balance = wallet.balance_of_gas_tokens().await?;
 
-    println!("Wallet balances: {}", wallet.address());
+    status!("Wallet balances: {}", wallet.address());
 
     let mut table = Table::new();
 
@@ -100,8 +193,13 @@ pub async fn balance() -> Result<()> {
         Cell::new(&gas_balance.to_string()),
     ]));
 
-    table.printstd();
+    crate::output::print_table(&table);
 
+    crate::output::json_result(json!({
+        "address": wallet.address().to_string(),
+        "token_balance": token_balance.to_string(),
+        "gas_balance": gas_balance.to_string(),
+    }));
     Ok(())
 }
 
@@ -9,6 +9,9 @@
 #[macro_use]
 extern crate tracing;
 
+#[macro_use]
+mod output;
+
 mod access;
 mod actions;
 mod commands;
@@ -35,7 +38,19 @@ use tracing::Level;
 async fn main() -> Result<()> {
     color_eyre::install().expect("Failed to initialise error handler");
     let opt = Opt::parse();
-    if let Some(network_id) = opt.network_id {
+    output::set_json(opt.json);
+    let profile = match opt.profile.clone() {
+        Some(name) => Some(name),
+        None => std::env::var(access::profile::PROFILE_ENV).ok(),
+    };
+    let profile = profile
+        .as_deref()
+        .map(access::profile::select_profile)
+        .transpose()?;
+    let network_id = opt
+        .network_id
+        .or_else(|| profile.and_then(|selected| selected.profile.network_id));
+    if let Some(network_id) = network_id {
         ant_protocol::version::set_network_id(network_id);
     }
 
@@ -80,7 +95,9 @@ async fn main() -> Result<()> {
     let version = ant_build_info::git_info();
     info!("autonomi client built with git version: {version}");
 
-    commands::handle_subcommand(opt).await?;
+    commands::handle_subcommand(opt)
+        .await
+        .inspect_err(output::json_error)?;
 
     Ok(())
 }
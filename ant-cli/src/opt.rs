@@ -27,6 +27,13 @@ pub(crate) struct Opt {
     #[clap(long = "timeout", global = true, value_parser = |t: &str| -> Result<Duration> { Ok(t.parse().map(Duration::from_secs)?) })]
     pub connection_timeout: Option<Duration>,
 
+    /// Print the result of the command as JSON on stdout, for scripts to parse it.
+    ///
+    /// The progress and the human readable results are printed to stderr instead. Failures are printed as
+    /// `{"error": "..."}`.
+    #[clap(long, global = true)]
+    pub json: bool,
+
     /// Print the crate version.
     #[clap(long)]
     pub crate_version: bool,
@@ -68,6 +75,15 @@ pub(crate) struct Opt {
     #[command(flatten)]
     pub(crate) peers: PeersArgs,
 
+    /// The network profile to use, see `ant profile`.
+    ///
+    /// The profile tells the peers and the network ID to use, unless given on the command line, and its own
+    /// dirs to keep the wallets, the keys and the user data in.
+    ///
+    /// Alternatively, the `AUTONOMI_PROFILE` environment variable can tell the profile.
+    #[clap(long, global = true, verbatim_doc_comment)]
+    pub profile: Option<String>,
+
     /// Print the package version.
     #[cfg(not(feature = "nightly"))]
     #[clap(long)]
@@ -0,0 +1,82 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use color_eyre::eyre::Report;
+use prettytable::Table;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the global `--json` flag.
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Print a line for humans, e.g. progress or a result.
+///
+/// With `--json`, it's printed to stderr instead, for stdout to only hold the JSON result of the command.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub(crate) fn set_json(enabled: bool) {
+    JSON_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn is_json() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Print a table for humans, to stderr with `--json` like the `status!` lines.
+pub(crate) fn print_table(table: &Table) {
+    if is_json() {
+        if let Err(err) = table.print(&mut std::io::stderr()) {
+            warn!("Failed to print table: {err}");
+        }
+    } else {
+        table.printstd();
+    }
+}
+
+/// Print the result of the command as a single line of JSON on stdout, with `--json` only.
+pub(crate) fn json_result(result: Value) {
+    if is_json() {
+        println!("{result}");
+    }
+}
+
+/// Print the error the command failed with, with `--json` only.
+pub(crate) fn json_error(err: &Report) {
+    json_result(error_json(err));
+}
+
+fn error_json(err: &Report) -> Value {
+    json!({
+        "error": format!("{err:#}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color_eyre::eyre::{eyre, WrapErr};
+
+    #[test]
+    fn errors_hold_their_whole_chain() {
+        let err = Err::<(), _>(eyre!("peer unreachable"))
+            .wrap_err("Failed to upload file")
+            .expect_err("error was wrapped");
+        assert_eq!(
+            error_json(&err),
+            json!({ "error": "Failed to upload file: peer unreachable" })
+        );
+    }
+}
@@ -7,11 +7,12 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use autonomi::client::{Amount, ClientEvent, UploadSummary};
+use tokio::sync::broadcast::error::RecvError;
 
 /// Collects upload summary from the event receiver.
 /// Send a signal to the returned sender to stop collecting and to return the result via the join handle.
 pub fn collect_upload_summary(
-    mut event_receiver: tokio::sync::mpsc::Receiver<ClientEvent>,
+    mut event_receiver: tokio::sync::broadcast::Receiver<ClientEvent>,
 ) -> (
     tokio::task::JoinHandle<UploadSummary>,
     tokio::sync::oneshot::Sender<()>,
@@ -25,11 +26,15 @@ pub fn collect_upload_summary(
             tokio::select! {
                 event = event_receiver.recv() => {
                     match event {
-                        Some(ClientEvent::UploadComplete(upload_summary)) => {
+                        Ok(ClientEvent::UploadComplete(upload_summary)) => {
                             tokens_spent += upload_summary.tokens_spent;
                             record_count += upload_summary.record_count;
                         }
-                        None => break,
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(missed)) => {
+                            warn!("Upload summary missed {missed} client events");
+                        }
+                        Err(RecvError::Closed) => break,
                     }
                 }
                 _ = &mut upload_completed_rx => break,
@@ -38,11 +43,9 @@ pub fn collect_upload_summary(
 
         // try to drain the event receiver in case there are any more events
         while let Ok(event) = event_receiver.try_recv() {
-            match event {
-                ClientEvent::UploadComplete(upload_summary) => {
-                    tokens_spent += upload_summary.tokens_spent;
-                    record_count += upload_summary.record_count;
-                }
+            if let ClientEvent::UploadComplete(upload_summary) = event {
+                tokens_spent += upload_summary.tokens_spent;
+                record_count += upload_summary.record_count;
             }
         }
 
@@ -28,4 +28,12 @@ pub enum Error {
     NoWalletsFound,
     #[error("Invalid wallet selection input")]
     InvalidSelection,
+    #[error("Wallet file is invalid: {0}")]
+    InvalidWalletFile(String),
+    #[error("Wallet file version {0} is not supported, please upgrade")]
+    UnsupportedWalletFileVersion(u32),
+    #[error("Wallet {0} is already encrypted")]
+    AlreadyEncrypted(String),
+    #[error("Failed to remove the unencrypted private key file: {0}")]
+    FailedToRemovePrivateKey(String),
 }
@@ -25,10 +25,16 @@ pub static SELECTED_WALLET_ADDRESS: OnceLock<String> = OnceLock::new();
 
 /// Creates the wallets folder if it is missing and returns the folder path.
 pub(crate) fn get_client_wallet_dir_path() -> Result<PathBuf, Error> {
-    let mut home_dirs = dirs_next::data_dir().ok_or(Error::WalletsFolderNotFound)?;
-    home_dirs.push("autonomi");
-    home_dirs.push("client");
-    home_dirs.push("wallets");
+    let home_dirs = match crate::access::profile::selected_profile() {
+        Some(selected) => selected.wallet_dir.clone(),
+        None => {
+            let mut home_dirs = dirs_next::data_dir().ok_or(Error::WalletsFolderNotFound)?;
+            home_dirs.push("autonomi");
+            home_dirs.push("client");
+            home_dirs.push("wallets");
+            home_dirs
+        }
+    };
 
     std::fs::create_dir_all(home_dirs.as_path()).map_err(|_| Error::FailedToCreateWalletsFolder)?;
 
@@ -109,6 +115,27 @@ pub(crate) fn load_private_key(wallet_address: &str) -> Result<String, Error> {
     }
 }
 
+/// Encrypts the unencrypted private key file of a wallet with the password, removing the plain text file.
+pub(crate) fn encrypt_stored_private_key(
+    wallet_address: &str,
+    encryption_password: String,
+) -> Result<OsString, Error> {
+    let wallets_folder = get_client_wallet_dir_path()?;
+    let plain_file_path = wallets_folder.join(wallet_address);
+
+    if !plain_file_path.exists() {
+        return Err(Error::AlreadyEncrypted(wallet_address.to_string()));
+    }
+
+    let private_key = load_private_key(wallet_address)?;
+    let file_path = store_private_key(&private_key, Some(encryption_password))?;
+
+    std::fs::remove_file(plain_file_path)
+        .map_err(|err| Error::FailedToRemovePrivateKey(err.to_string()))?;
+
+    Ok(file_path)
+}
+
 pub(crate) fn load_wallet_from_address(wallet_address: &str) -> Result<Wallet, Error> {
     let network = get_evm_network_from_env().expect("Could not load EVM network from environment");
     let private_key = load_private_key(wallet_address)?;
@@ -169,7 +196,7 @@ fn get_wallet_selection(wallet_files: Vec<String>) -> Result<String, Error> {
 }
 
 fn list_wallets(wallet_files: &[String]) {
-    println!("Wallets:");
+    status!("Wallets:");
 
     let mut table = Table::new();
 
@@ -189,7 +216,7 @@ fn list_wallets(wallet_files: &[String]) {
         ]));
     }
 
-    table.printstd();
+    crate::output::print_table(&table);
 }
 
 fn get_wallet_files(wallets_folder: &PathBuf) -> Result<Vec<String>, Error> {
@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 pub(crate) fn get_wallet_selection_input(prompt: &str) -> String {
-    println!("{prompt}");
+    status!("{prompt}");
 
     let mut buffer = String::new();
     let stdin = std::io::stdin();
@@ -34,7 +34,7 @@ pub(crate) fn confirm_password(password: &str) -> bool {
         if get_password_input("Repeat password: ") == password {
             return true;
         }
-        println!("Passwords do not match.");
+        status!("Passwords do not match.");
     }
 
     false
@@ -52,7 +52,7 @@ pub(crate) fn request_password(required: bool) -> Option<String> {
 
         if password.is_empty() {
             if required {
-                println!("Password is required.");
+                status!("Password is required.");
                 continue;
             }
 
@@ -63,6 +63,6 @@ pub(crate) fn request_password(required: bool) -> Option<String> {
             return Some(password);
         }
 
-        println!("Please set a new password.");
+        status!("Please set a new password.");
     }
 }
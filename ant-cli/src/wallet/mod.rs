@@ -14,6 +14,7 @@ pub(crate) mod encryption;
 pub(crate) mod error;
 pub(crate) mod fs;
 pub(crate) mod input;
+pub(crate) mod portable;
 
 pub const DUMMY_NETWORK: Network = Network::ArbitrumSepolia;
 
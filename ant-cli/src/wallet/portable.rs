@@ -0,0 +1,129 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::wallet::encryption::{decrypt_private_key, encrypt_private_key};
+use crate::wallet::error::Error;
+use crate::wallet::DUMMY_NETWORK;
+use autonomi::Wallet;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version of the wallet file format, bumped on incompatible changes.
+const WALLET_FILE_VERSION: u32 = 1;
+
+/// A wallet in a single file, to move it between machines.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PortableWallet {
+    version: u32,
+    address: String,
+    /// The hex-encoded private key, encrypted with a password if `encrypted` is set.
+    private_key: String,
+    encrypted: bool,
+}
+
+impl PortableWallet {
+    /// Wrap the private key, encrypting it when a password is given.
+    pub(crate) fn new(private_key: &str, password: Option<&str>) -> Result<Self, Error> {
+        let address = wallet_address(private_key)?;
+        let (private_key, encrypted) = match password {
+            Some(password) => (encrypt_private_key(private_key, password)?, true),
+            None => (private_key.to_string(), false),
+        };
+
+        Ok(Self {
+            version: WALLET_FILE_VERSION,
+            address,
+            private_key,
+            encrypted,
+        })
+    }
+
+    pub(crate) fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub(crate) fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// The private key of the wallet. The password is needed if the wallet is encrypted.
+    pub(crate) fn private_key(&self, password: Option<&str>) -> Result<String, Error> {
+        let private_key = match (self.encrypted, password) {
+            (true, Some(password)) => decrypt_private_key(&self.private_key, password)?,
+            (true, None) => {
+                return Err(Error::FailedToDecryptKey(
+                    "A password is required to decrypt the wallet".to_string(),
+                ))
+            }
+            (false, _) => self.private_key.clone(),
+        };
+
+        if wallet_address(&private_key)? != self.address {
+            return Err(Error::InvalidWalletFile(
+                "The private key does not match the wallet address".to_string(),
+            ));
+        }
+        Ok(private_key)
+    }
+
+    pub(crate) fn write_to_file(&self, path: &Path) -> Result<(), Error> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|err| Error::InvalidWalletFile(err.to_string()))?;
+        std::fs::write(path, contents)
+            .map_err(|err| Error::FailedToStorePrivateKey(err.to_string()))
+    }
+
+    pub(crate) fn read_from_file(path: &Path) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path).map_err(|_| Error::PrivateKeyFileNotFound)?;
+        let wallet: Self = serde_json::from_str(&contents)
+            .map_err(|err| Error::InvalidWalletFile(err.to_string()))?;
+
+        if wallet.version > WALLET_FILE_VERSION {
+            return Err(Error::UnsupportedWalletFileVersion(wallet.version));
+        }
+        Ok(wallet)
+    }
+}
+
+fn wallet_address(private_key: &str) -> Result<String, Error> {
+    Ok(Wallet::new_from_private_key(DUMMY_NETWORK, private_key)
+        .map_err(|_| Error::InvalidPrivateKey)?
+        .address()
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_portable_wallet_file_roundtrip() {
+        let private_key = Wallet::random_private_key();
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+        for password in [None, Some("password123")] {
+            let path = dir.path().join("wallet.json");
+            let wallet = PortableWallet::new(&private_key, password).unwrap();
+            wallet.write_to_file(&path).unwrap();
+
+            let imported = PortableWallet::read_from_file(&path).unwrap();
+            assert_eq!(imported, wallet);
+            assert_eq!(imported.is_encrypted(), password.is_some());
+            assert_eq!(imported.private_key(password).unwrap(), private_key);
+        }
+    }
+
+    #[test]
+    fn test_encrypted_portable_wallet_needs_the_password() {
+        let private_key = Wallet::random_private_key();
+        let wallet = PortableWallet::new(&private_key, Some("password123")).unwrap();
+
+        assert!(wallet.private_key(None).is_err());
+        assert!(wallet.private_key(Some("wrong password")).is_err());
+    }
+}
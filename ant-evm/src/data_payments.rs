@@ -14,6 +14,7 @@ use evmlib::{
 };
 use libp2p::{identity::PublicKey, PeerId};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
 pub use std::time::SystemTime;
 #[cfg(target_arch = "wasm32")]
@@ -58,6 +59,13 @@ impl ProofOfPayment {
             .collect()
     }
 
+    /// Hash of the proof, to refer to the payment without carrying all of its quotes
+    pub fn hash(&self) -> Result<XorName, EvmError> {
+        let bytes =
+            rmp_serde::to_vec(self).map_err(|err| EvmError::ProofSerialization(err.to_string()))?;
+        Ok(XorName::from_content(&bytes))
+    }
+
     /// returns the list of payees
     pub fn payees(&self) -> Vec<PeerId> {
         self.peer_quotes
@@ -131,6 +139,9 @@ pub struct PaymentQuote {
     /// the node's signature for the quote
     #[debug(skip)]
     pub signature: Vec<u8>,
+    /// when the quote expires, signed along with the rest, `QUOTE_EXPIRATION_SECS` after its timestamp if not set
+    #[serde(default)]
+    pub expires_at: Option<SystemTime>,
 }
 
 impl PaymentQuote {
@@ -143,6 +154,7 @@ impl PaymentQuote {
             rewards_address: dummy_address(),
             pub_key: vec![],
             signature: vec![],
+            expires_at: None,
         }
     }
 
@@ -159,6 +171,7 @@ impl PaymentQuote {
         timestamp: SystemTime,
         quoting_metrics: &QuotingMetrics,
         rewards_address: &RewardsAddress,
+        expires_at: Option<SystemTime>,
     ) -> Vec<u8> {
         let mut bytes = xorname.to_vec();
         bytes.extend_from_slice(
@@ -171,6 +184,16 @@ impl PaymentQuote {
         let serialised_quoting_metrics = rmp_serde::to_vec(quoting_metrics).unwrap_or_default();
         bytes.extend_from_slice(&serialised_quoting_metrics);
         bytes.extend_from_slice(rewards_address.as_slice());
+        // Left out when not set, for the quotes without an expiry to be signed as they used to.
+        if let Some(expires_at) = expires_at {
+            bytes.extend_from_slice(
+                &expires_at
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .expect("Unix epoch to be in the past")
+                    .as_secs()
+                    .to_le_bytes(),
+            );
+        }
         bytes
     }
 
@@ -181,6 +204,7 @@ impl PaymentQuote {
             self.timestamp,
             &self.quoting_metrics,
             &self.rewards_address,
+            self.expires_at,
         )
     }
 
@@ -220,15 +244,35 @@ impl PaymentQuote {
         true
     }
 
+    /// Whether the quote is scoped to its payee rather than to its content, for any of the records the node is
+    /// responsible for until it expires. The nodes sign an expiry for their quotes to be reused that way, the quotes
+    /// without one only pay for their content.
+    pub fn is_payee_scoped(&self) -> bool {
+        self.expires_at.is_some()
+    }
+
+    /// Whether the quote pays for storing the given content, being either quoted for it or scoped to its payee
+    pub fn covers(&self, content: &XorName) -> bool {
+        self.content == *content || self.is_payee_scoped()
+    }
+
     /// Returns true if the quote has expired
     pub fn has_expired(&self) -> bool {
-        let now = SystemTime::now();
+        !self.is_valid_for(Duration::ZERO)
+    }
+
+    /// Returns true if the quote is still valid in `duration` from now
+    pub fn is_valid_for(&self, duration: Duration) -> bool {
+        let now = SystemTime::now() + duration;
 
+        if let Some(expires_at) = self.expires_at {
+            return now.duration_since(self.timestamp).is_ok() && now <= expires_at;
+        }
         let dur_s = match now.duration_since(self.timestamp) {
             Ok(dur) => dur.as_secs(),
-            Err(_) => return true,
+            Err(_) => return false,
         };
-        dur_s > QUOTE_EXPIRATION_SECS
+        dur_s <= QUOTE_EXPIRATION_SECS
     }
 
     /// test utility to create a dummy quote
@@ -240,6 +284,7 @@ impl PaymentQuote {
             pub_key: vec![],
             signature: vec![],
             rewards_address: dummy_address(),
+            expires_at: None,
         }
     }
 
@@ -371,6 +416,44 @@ mod tests {
         assert!(!quote.check_is_signed_by_claimed_peer(false_peer));
     }
 
+    #[test]
+    fn test_signed_expiry() {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+
+        let mut quote = PaymentQuote::zero();
+        quote.expires_at = Some(quote.timestamp + Duration::from_secs(60));
+        quote.pub_key = keypair.public().encode_protobuf();
+        quote.signature = keypair.sign(&quote.bytes_for_sig()).expect("quote to sign");
+        assert!(quote.check_is_signed_by_claimed_peer(peer_id));
+        assert!(!quote.has_expired());
+        assert!(quote.is_valid_for(Duration::from_secs(30)));
+        assert!(!quote.is_valid_for(Duration::from_secs(120)));
+
+        // The expiry can't be extended without the node's signature
+        quote.expires_at = Some(quote.timestamp + Duration::from_secs(3600));
+        assert!(!quote.check_is_signed_by_claimed_peer(peer_id));
+
+        // Without an expiry, the quote is valid for `QUOTE_EXPIRATION_SECS`
+        quote.expires_at = None;
+        assert!(quote.is_valid_for(Duration::from_secs(QUOTE_EXPIRATION_SECS - 60)));
+        assert!(!quote.is_valid_for(Duration::from_secs(QUOTE_EXPIRATION_SECS + 60)));
+    }
+
+    #[test]
+    fn only_payee_scoped_quotes_cover_other_contents() {
+        let content = XorName::from_content(b"content");
+        let other = XorName::from_content(b"other content");
+
+        let mut quote = PaymentQuote::test_dummy(content);
+        assert!(quote.covers(&content));
+        assert!(!quote.covers(&other));
+
+        quote.expires_at = Some(quote.timestamp + Duration::from_secs(60));
+        assert!(quote.is_payee_scoped());
+        assert!(quote.covers(&other));
+    }
+
     #[test]
     fn test_historical_verify() {
         let mut old_quote = PaymentQuote::zero();
@@ -29,4 +29,6 @@ pub enum EvmError {
     NotEnoughBalance(AttoTokens, AttoTokens),
     #[error("Invalid quote public key")]
     InvalidQuotePublicKey,
+    #[error("Failed to serialize the proof of payment: {0}")]
+    ProofSerialization(String),
 }
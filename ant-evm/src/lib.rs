@@ -18,11 +18,13 @@ pub use evmlib::contract::payment_vault;
 pub use evmlib::cryptography;
 #[cfg(feature = "external-signer")]
 pub use evmlib::external_signer;
+pub use evmlib::mnemonic;
 pub use evmlib::utils;
 pub use evmlib::utils::get_evm_network_from_env;
 pub use evmlib::utils::{DATA_PAYMENTS_ADDRESS, PAYMENT_TOKEN_ADDRESS, RPC_URL};
 pub use evmlib::wallet::Error as EvmWalletError;
 pub use evmlib::wallet::Wallet as EvmWallet;
+pub use evmlib::wallet::WatchOnlyWallet as EvmWatchOnlyWallet;
 pub use evmlib::CustomNetwork;
 pub use evmlib::Network as EvmNetwork;
 
@@ -36,6 +36,15 @@ const ALL_ANT_LOGS: &str = "all";
 // Trace at nodes, clients, debug at networking layer
 const VERBOSE_ANT_LOGS: &str = "v";
 
+/// Where and under which name the traces are exported, see [`crate::LogBuilder::otlp_endpoint`].
+#[cfg(feature = "otlp")]
+#[derive(Debug, Default)]
+pub(crate) struct OtlpConfig {
+    pub(crate) endpoint: Option<String>,
+    pub(crate) service_name: Option<String>,
+    pub(crate) resource_attributes: Vec<(&'static str, String)>,
+}
+
 /// Handle that implements functions to change the log level on the fly.
 pub struct ReloadHandle(pub(crate) Handle<Box<dyn Filter<Registry> + Send + Sync>, Registry>);
 
@@ -128,7 +137,7 @@ impl TracingLayers {
             LogOutputDest::Path(path) => {
                 std::fs::create_dir_all(path)?;
                 if print_updates_to_stdout {
-                    println!("Logging to directory: {path:?}");
+                    eprintln!("Logging to directory: {path:?}");
                 }
 
                 // the number of normal files
@@ -188,6 +197,7 @@ impl TracingLayers {
     pub(crate) fn otlp_layer(
         &mut self,
         default_logging_targets: Vec<(String, Level)>,
+        config: OtlpConfig,
     ) -> Result<()> {
         use opentelemetry::{
             sdk::{trace, Resource},
@@ -197,23 +207,40 @@ impl TracingLayers {
         use opentelemetry_semantic_conventions::resource::{SERVICE_INSTANCE_ID, SERVICE_NAME};
         use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
-        let service_name = std::env::var("OTLP_SERVICE_NAME").unwrap_or_else(|_| {
-            let random_node_name: String = thread_rng()
-                .sample_iter(&Alphanumeric)
-                .take(10)
-                .map(char::from)
-                .collect();
-            random_node_name
-        });
+        let service_name = config
+            .service_name
+            .or_else(|| std::env::var("OTLP_SERVICE_NAME").ok())
+            .unwrap_or_else(|| {
+                let random_node_name: String = thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(10)
+                    .map(char::from)
+                    .collect();
+                random_node_name
+            });
         println!("The opentelemetry traces are logged under the name: {service_name}");
 
+        let mut exporter = opentelemetry_otlp::new_exporter().tonic().with_env();
+        if let Some(endpoint) = config.endpoint {
+            println!("Exporting the opentelemetry traces to {endpoint}");
+            exporter = exporter.with_endpoint(endpoint);
+        }
+
+        let mut resource = vec![
+            KeyValue::new(SERVICE_NAME, service_name),
+            KeyValue::new(SERVICE_INSTANCE_ID, std::process::id().to_string()),
+        ];
+        resource.extend(
+            config
+                .resource_attributes
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value)),
+        );
+
         let tracer = opentelemetry_otlp::new_pipeline()
             .tracing()
-            .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_env())
-            .with_trace_config(trace::config().with_resource(Resource::new(vec![
-                KeyValue::new(SERVICE_NAME, service_name),
-                KeyValue::new(SERVICE_INSTANCE_ID, std::process::id().to_string()),
-            ])))
+            .with_exporter(exporter)
+            .with_trace_config(trace::config().with_resource(Resource::new(resource)))
             .install_batch(opentelemetry::runtime::Tokio)?;
 
         let targets = match std::env::var("ANT_LOG_OTLP") {
@@ -107,6 +107,8 @@ pub struct LogBuilder {
     max_archived_log_files: Option<usize>,
     /// Setting this would print the ant_logging related updates to stdout.
     print_updates_to_stdout: bool,
+    #[cfg(feature = "otlp")]
+    otlp: layers::OtlpConfig,
 }
 
 impl LogBuilder {
@@ -122,6 +124,8 @@ impl LogBuilder {
             max_log_files: None,
             max_archived_log_files: None,
             print_updates_to_stdout: true,
+            #[cfg(feature = "otlp")]
+            otlp: Default::default(),
         }
     }
 
@@ -150,6 +154,28 @@ impl LogBuilder {
         self.print_updates_to_stdout = print;
     }
 
+    /// The OTLP (gRPC) endpoint to export the traces to, e.g. `http://localhost:4317`.
+    ///
+    /// Overrides the `OTEL_EXPORTER_OTLP_ENDPOINT` env variable. The traces are exported only if either is set.
+    #[cfg(feature = "otlp")]
+    pub fn otlp_endpoint(&mut self, endpoint: String) {
+        self.otlp.endpoint = Some(endpoint);
+    }
+
+    /// The service name the traces are exported under.
+    ///
+    /// Overrides the `OTLP_SERVICE_NAME` env variable. A random name is used if neither is set.
+    #[cfg(feature = "otlp")]
+    pub fn otlp_service_name(&mut self, service_name: String) {
+        self.otlp.service_name = Some(service_name);
+    }
+
+    /// Add a resource attribute to the exported traces, e.g. the peer id, to tell the nodes apart.
+    #[cfg(feature = "otlp")]
+    pub fn otlp_resource_attribute(&mut self, key: &'static str, value: String) {
+        self.otlp.resource_attributes.push((key, value));
+    }
+
     /// Inits node logging, returning the NonBlocking guard if present.
     /// This guard should be held for the life of the program.
     ///
@@ -168,12 +194,15 @@ impl LogBuilder {
 
         #[cfg(feature = "otlp")]
         {
-            match std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
-                Ok(_) => layers.otlp_layer(self.default_logging_targets)?,
-                Err(_) => println!(
-                "The OTLP feature is enabled but the OTEL_EXPORTER_OTLP_ENDPOINT variable is not \
-                set, so traces will not be submitted."
-            ),
+            let endpoint_set = self.otlp.endpoint.is_some()
+                || std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_ok();
+            if endpoint_set {
+                layers.otlp_layer(self.default_logging_targets, self.otlp)?;
+            } else {
+                println!(
+                    "The OTLP feature is enabled but neither an OTLP endpoint nor the \
+                    OTEL_EXPORTER_OTLP_ENDPOINT variable is set, so traces will not be submitted."
+                );
             }
         }
 
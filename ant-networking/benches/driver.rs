@@ -0,0 +1,183 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_networking::{
+    bench::{GetRecordAccumulation, ReplicationFetching},
+    GetRecordCfg,
+};
+use ant_protocol::{
+    storage::{try_serialize_record, Chunk, RecordKind, RecordType, Scratchpad},
+    NetworkAddress, CLOSE_GROUP_SIZE,
+};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use libp2p::{
+    kad::{Quorum, Record},
+    PeerId,
+};
+use rand::{thread_rng, Rng};
+use std::{collections::HashMap, num::NonZeroUsize};
+
+const SAMPLE_SIZE: usize = 50;
+
+/// The number of keys each holder notifies of in the replication benches.
+const KEYS_PER_HOLDER: usize = 500;
+
+fn get_record_cfg(get_quorum: Quorum) -> GetRecordCfg {
+    GetRecordCfg {
+        get_quorum,
+        retry_strategy: None,
+        target_record: None,
+        expected_holders: Default::default(),
+        is_register: false,
+    }
+}
+
+fn chunk_record(size: usize) -> Record {
+    let mut content = vec![0u8; size];
+    thread_rng().fill(&mut content[..]);
+    let chunk = Chunk::new(Bytes::from(content));
+    let value = try_serialize_record(&chunk, RecordKind::Chunk)
+        .expect("Chunk to serialize")
+        .to_vec();
+    Record::new(
+        NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+        value,
+    )
+}
+
+fn scratchpad_record(owner: &bls::SecretKey, updates: u64) -> Record {
+    let mut scratchpad = Scratchpad::new(owner.public_key(), 0);
+    for _ in 0..updates {
+        let _ = scratchpad.update_and_sign(Bytes::from(vec![7u8; 64 * 1024]), owner);
+    }
+    let value = try_serialize_record(&scratchpad, RecordKind::Scratchpad)
+        .expect("Scratchpad to serialize")
+        .to_vec();
+    Record::new(
+        NetworkAddress::from_scratchpad_address(*scratchpad.address()).to_record_key(),
+        value,
+    )
+}
+
+fn get_record_accumulation(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create the tokio runtime");
+    let _guard = runtime.enter();
+    let mut accumulation =
+        GetRecordAccumulation::new(CLOSE_GROUP_SIZE).expect("Failed to build the driver");
+
+    let mut group = c.benchmark_group("get_record_accumulation");
+    group.sample_size(SAMPLE_SIZE);
+
+    // A chunk verifies against its key, so the query completes on its first copy.
+    let chunk = chunk_record(1024 * 1024);
+    group.throughput(Throughput::Bytes(chunk.value.len() as u64));
+    group.bench_function("chunk_first_copy", |b| {
+        b.iter(|| {
+            accumulation
+                .accumulate(
+                    std::slice::from_ref(&chunk),
+                    get_record_cfg(Quorum::Majority),
+                )
+                .expect("Failed to accumulate")
+                .expect("Chunk to be found")
+        })
+    });
+
+    // A scratchpad has to be seen from every holder for `Quorum::All`.
+    let owner = bls::SecretKey::random();
+    let scratchpad = scratchpad_record(&owner, 1);
+    let copies = vec![scratchpad.clone(); CLOSE_GROUP_SIZE];
+    group.throughput(Throughput::Elements(copies.len() as u64));
+    group.bench_function("scratchpad_all_copies", |b| {
+        b.iter(|| {
+            accumulation
+                .accumulate(&copies, get_record_cfg(Quorum::All))
+                .expect("Failed to accumulate")
+                .expect("Scratchpad to be found")
+        })
+    });
+
+    // Half of the holders hold a newer version, splitting the record.
+    let newer = scratchpad_record(&owner, 2);
+    let split_copies: Vec<_> = (0..CLOSE_GROUP_SIZE)
+        .map(|index| {
+            if index % 2 == 0 {
+                scratchpad.clone()
+            } else {
+                newer.clone()
+            }
+        })
+        .collect();
+    let quorum = Quorum::N(NonZeroUsize::new(CLOSE_GROUP_SIZE).expect("non zero"));
+    group.bench_function("scratchpad_split", |b| {
+        b.iter(|| {
+            let result = accumulation
+                .accumulate(&split_copies, get_record_cfg(quorum))
+                .expect("Failed to accumulate");
+            assert!(result.is_err(), "A split record to be reported");
+        })
+    });
+
+    group.finish();
+}
+
+fn replication_fetcher(c: &mut Criterion) {
+    let self_peer_id = PeerId::random();
+    let holders: Vec<_> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+    let keys: Vec<_> = (0..KEYS_PER_HOLDER)
+        .map(|_| {
+            let addr = NetworkAddress::from_peer(PeerId::random());
+            (addr, RecordType::Chunk)
+        })
+        .collect();
+    let locally_stored = HashMap::new();
+
+    let mut group = c.benchmark_group("replication_fetcher");
+    group.sample_size(SAMPLE_SIZE);
+    group.throughput(Throughput::Elements(
+        (KEYS_PER_HOLDER * holders.len()) as u64,
+    ));
+
+    // Every holder notifies of the same keys, as the close group does on churn.
+    group.bench_function("add_keys", |b| {
+        b.iter_batched(
+            || ReplicationFetching::new(self_peer_id),
+            |mut fetching| {
+                for holder in holders.iter() {
+                    let _ = fetching.add_keys(*holder, keys.clone(), &locally_stored);
+                }
+                fetching
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Drains the queue, marking each key fetched as soon as it's handed out.
+    group.bench_function("add_and_fetch_all", |b| {
+        b.iter_batched(
+            || ReplicationFetching::new(self_peer_id),
+            |mut fetching| {
+                let mut to_fetch = vec![];
+                for holder in holders.iter() {
+                    to_fetch.extend(fetching.add_keys(*holder, keys.clone(), &locally_stored));
+                }
+                while let Some((_holder, key)) = to_fetch.pop() {
+                    to_fetch.extend(fetching.fetched(key, RecordType::Chunk));
+                }
+                fetching
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, get_record_accumulation, replication_fetcher);
+criterion_main!(benches);
@@ -0,0 +1,306 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::record_store::RecordCategory;
+use libp2p::{kad::RecordKey, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{sync_channel, SyncSender, TrySendError},
+    thread::JoinHandle,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// File name of the audit log of the record mutations, in the root dir of the node.
+pub const AUDIT_LOG_FILENAME: &str = "record_audit.jsonl";
+
+/// The max entries waiting to be written, the ones beyond being dropped rather than holding up the store.
+const AUDIT_LOG_QUEUE_SIZE: usize = 4096;
+
+/// What was done to a record of the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Stored as put by a client or by a peer of its close group.
+    Put,
+    /// Stored as fetched through replication.
+    Replicate,
+    /// Removed to stay within the limits of the store.
+    Evict,
+    /// Removed as expired, as beyond our responsible range, or as held enough by its close group we're out of.
+    Prune,
+    /// Removed for another reason, e.g. failing to be written to disk.
+    Remove,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Put => "put",
+            Self::Replicate => "replicate",
+            Self::Evict => "evict",
+            Self::Prune => "prune",
+            Self::Remove => "remove",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Where a record written to the store comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordSource {
+    /// Put to us by the peer the request came from, or by ourselves.
+    Put(Option<PeerId>),
+    /// Fetched through replication, from the holder unless fetched from the network.
+    Replication(Option<PeerId>),
+}
+
+/// A line of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the record was mutated, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub action: AuditAction,
+    /// The record key, in hex.
+    pub key: String,
+    /// The type of the record, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_type: Option<String>,
+    /// The peer the record was stored from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Why the record was removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl AuditEntry {
+    pub(crate) fn new(
+        action: AuditAction,
+        key: &RecordKey,
+        category: Option<RecordCategory>,
+    ) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since_epoch| since_epoch.as_secs())
+                .unwrap_or_default(),
+            action,
+            key: hex::encode(key.as_ref()),
+            record_type: category.map(|category| category.to_string()),
+            source: None,
+            reason: None,
+        }
+    }
+
+    pub(crate) fn written(
+        key: &RecordKey,
+        category: Option<RecordCategory>,
+        source: RecordSource,
+    ) -> Self {
+        let (action, peer) = match source {
+            RecordSource::Put(peer) => (AuditAction::Put, peer),
+            RecordSource::Replication(peer) => (AuditAction::Replicate, peer),
+        };
+        Self {
+            source: peer.map(|peer| peer.to_string()),
+            ..Self::new(action, key, category)
+        }
+    }
+
+    pub(crate) fn with_reason(self, reason: &str) -> Self {
+        Self {
+            reason: Some(reason.to_string()),
+            ..self
+        }
+    }
+}
+
+/// The file the older half of the log is moved to once the log is full.
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = OsString::from(path.as_os_str());
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// An append-only log of the records stored and removed, one JSON entry per line, for the operators to find out
+/// what happened to a record.
+///
+/// The log is capped at `max_bytes` overall: once the file takes half of it, it's moved aside, replacing the
+/// previous one, and a new file is started. The entries are written on a thread of their own, for the store not
+/// to wait on the disk.
+#[derive(Debug)]
+pub(crate) struct AuditLog {
+    path: PathBuf,
+    entries: Option<SyncSender<AuditEntry>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl AuditLog {
+    pub(crate) fn open(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let mut file = AuditFile::open(path.clone(), max_bytes)?;
+        let (entries, queued) = sync_channel::<AuditEntry>(AUDIT_LOG_QUEUE_SIZE);
+        let writer = std::thread::Builder::new()
+            .name("record-audit-log".to_string())
+            .spawn(move || {
+                for entry in queued {
+                    if let Err(err) = file.append(&entry) {
+                        warn!("Failed to append to the audit log {:?}: {err:?}", file.path);
+                    }
+                }
+            })?;
+        Ok(Self {
+            path,
+            entries: Some(entries),
+            writer: Some(writer),
+        })
+    }
+
+    /// Queue the entry to be appended, logging rather than returning the errors, for the store to carry on
+    /// regardless.
+    pub(crate) fn append(&mut self, entry: AuditEntry) {
+        let Some(entries) = &self.entries else {
+            return;
+        };
+        match entries.try_send(entry) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!(
+                    "Dropped an entry of the audit log {:?}, as too many are queued",
+                    self.path
+                );
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!(
+                    "Failed to append to the audit log {:?}, its writer is gone",
+                    self.path
+                );
+            }
+        }
+    }
+}
+
+impl Drop for AuditLog {
+    /// Write the queued entries before closing the log.
+    fn drop(&mut self) {
+        drop(self.entries.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// The file of the audit log, written by its writer thread.
+#[derive(Debug)]
+struct AuditFile {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    size: u64,
+}
+
+impl AuditFile {
+    fn open(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            file,
+            size,
+        })
+    }
+
+    fn append(&mut self, entry: &AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes / 2 {
+            fs::rename(&self.path, rotated_path(&self.path))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.size = 0;
+        }
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// The last `count` entries of the audit log at `path`, oldest first. An empty log is returned if there is none,
+/// and the lines that don't parse are skipped.
+pub fn tail_audit_log(path: &Path, count: usize) -> io::Result<Vec<AuditEntry>> {
+    let mut entries = VecDeque::with_capacity(count.min(1024));
+    for path in [rotated_path(path), path.to_path_buf()] {
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        for line in BufReader::new(file).lines() {
+            let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) else {
+                continue;
+            };
+            if entries.len() == count {
+                let _ = entries.pop_front();
+            }
+            if count > 0 {
+                entries.push_back(entry);
+            }
+        }
+    }
+    Ok(entries.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_log_stays_within_its_cap_and_tails_the_latest_entries() -> eyre::Result<()> {
+        let dir = assert_fs::TempDir::new()?;
+        let path = dir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(path.clone(), 2048)?;
+        let keys: Vec<_> = (0..100u8).map(|i| RecordKey::new(&[i; 32])).collect();
+        let peer = PeerId::random();
+        for key in &keys {
+            log.append(AuditEntry::written(
+                key,
+                Some(RecordCategory::Chunk),
+                RecordSource::Replication(Some(peer)),
+            ));
+        }
+        log.append(AuditEntry::new(AuditAction::Evict, &keys[0], None).with_reason("quota"));
+        // Closing the log writes the queued entries.
+        drop(log);
+
+        let size = |path: &Path| fs::metadata(path).map(|metadata| metadata.len());
+        assert!(size(&path)? + size(&rotated_path(&path))? <= 2048);
+
+        let tail = tail_audit_log(&path, 3)?;
+        assert_eq!(tail.len(), 3);
+        assert_eq!(tail[0].key, hex::encode([98u8; 32]));
+        assert_eq!(tail[1].action, AuditAction::Replicate);
+        assert_eq!(tail[1].source, Some(peer.to_string()));
+        assert_eq!(tail[1].record_type.as_deref(), Some("chunk"));
+        assert_eq!(tail[2].action, AuditAction::Evict);
+        assert_eq!(tail[2].reason.as_deref(), Some("quota"));
+
+        // The entries from before the rotation are tailed as well.
+        let tail = tail_audit_log(&path, 1000)?;
+        assert!(tail.len() > 4 && tail.len() < keys.len());
+        assert!(tail_audit_log(&dir.path().join("none"), 10)?.is_empty());
+        Ok(())
+    }
+}
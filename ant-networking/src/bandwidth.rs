@@ -0,0 +1,487 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use futures::{AsyncRead, AsyncWrite};
+use libp2p::{
+    core::muxing::{StreamMuxer, StreamMuxerEvent},
+    PeerId,
+};
+#[cfg(feature = "open-metrics")]
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{ready, Context, Poll},
+};
+
+/// Max number of peers accounted for individually. Beyond that, the peers without a live
+/// stream and the least bandwidth are only accounted for in the totals per protocol.
+const MAX_TRACKED_PEERS: usize = 1000;
+
+/// The protocol the bytes of the streams closed before their protocol was negotiated are accounted to.
+const UNKNOWN_PROTOCOL: &str = "unknown";
+
+/// The max bytes of a stream looked at for the protocol negotiated on it.
+const MAX_NEGOTIATION_BYTES: usize = 1024;
+
+/// The header of the multistream-select negotiation, and its rejection of a proposed protocol.
+const MULTISTREAM_HEADER: &[u8] = b"/multistream/1.0.0";
+const MULTISTREAM_NA: &[u8] = b"na";
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct BandwidthLabels {
+    pub(crate) protocol: String,
+    pub(crate) direction: BandwidthDirection,
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+pub(crate) enum BandwidthDirection {
+    Sent,
+    Received,
+}
+
+/// The bytes sent to and received from a peer over the streams of a protocol, e.g. `/ipfs/kad/1.0.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerBandwidth {
+    pub peer_id: PeerId,
+    pub protocol: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl PeerBandwidth {
+    pub fn total(&self) -> u64 {
+        self.bytes_sent.saturating_add(self.bytes_received)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Counters {
+    fn load(&self) -> (u64, u64) {
+        (
+            self.sent.load(Ordering::Relaxed),
+            self.received.load(Ordering::Relaxed),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Accounts {
+    peers: HashMap<(PeerId, String), Arc<Counters>>,
+    /// The bytes of the peers no longer accounted for individually, per protocol
+    evicted: BTreeMap<String, (u64, u64)>,
+}
+
+impl Accounts {
+    /// Stop accounting individually for the peers without a live connection and the least bandwidth.
+    fn evict(&mut self, count: usize) {
+        let mut idle: Vec<_> = self
+            .peers
+            .iter()
+            // Only referenced by us, i.e. no live stream
+            .filter(|(_key, counters)| Arc::strong_count(counters) == 1)
+            .map(|(key, counters)| {
+                let (sent, received) = counters.load();
+                (sent.saturating_add(received), key.clone())
+            })
+            .collect();
+        idle.sort();
+
+        for (_total, key) in idle.into_iter().take(count) {
+            if let Some(counters) = self.peers.remove(&key) {
+                let (sent, received) = counters.load();
+                let evicted = self.evicted.entry(key.1).or_default();
+                evicted.0 = evicted.0.saturating_add(sent);
+                evicted.1 = evicted.1.saturating_add(received);
+            }
+        }
+    }
+}
+
+/// Accounts the bytes sent and received per peer and per protocol negotiated on the streams, e.g. kad,
+/// request/response, gossipsub or identify.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BandwidthTracker {
+    accounts: Arc<Mutex<Accounts>>,
+}
+
+impl BandwidthTracker {
+    /// Account for the bytes of the streams of a new connection to the peer.
+    pub(crate) fn wrap<M>(&self, peer_id: PeerId, muxer: M) -> CountingMuxer<M> {
+        CountingMuxer {
+            inner: muxer,
+            tracker: self.clone(),
+            peer_id,
+        }
+    }
+
+    /// The counters of the peer's streams of the protocol.
+    fn counters(&self, peer_id: PeerId, protocol: String) -> Arc<Counters> {
+        match self.accounts.lock() {
+            Ok(mut accounts) => {
+                if accounts.peers.len() >= MAX_TRACKED_PEERS {
+                    accounts.evict(MAX_TRACKED_PEERS / 10);
+                }
+                Arc::clone(accounts.peers.entry((peer_id, protocol)).or_default())
+            }
+            Err(err) => {
+                error!("Bandwidth accounts lock is poisoned: {err}");
+                Arc::default()
+            }
+        }
+    }
+
+    /// The bandwidth used per peer and protocol, highest first.
+    pub(crate) fn usage(&self) -> Vec<PeerBandwidth> {
+        let Ok(accounts) = self.accounts.lock() else {
+            return vec![];
+        };
+        let mut usage: Vec<_> = accounts
+            .peers
+            .iter()
+            .map(|((peer_id, protocol), counters)| {
+                let (bytes_sent, bytes_received) = counters.load();
+                PeerBandwidth {
+                    peer_id: *peer_id,
+                    protocol: protocol.clone(),
+                    bytes_sent,
+                    bytes_received,
+                }
+            })
+            .collect();
+        usage.sort_by_key(|bandwidth| std::cmp::Reverse(bandwidth.total()));
+        usage
+    }
+
+    /// The bytes sent and received per protocol, over all peers.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn totals_per_protocol(&self) -> BTreeMap<String, (u64, u64)> {
+        let Ok(accounts) = self.accounts.lock() else {
+            return BTreeMap::new();
+        };
+        let mut totals = accounts.evicted.clone();
+        for ((_peer_id, protocol), counters) in accounts.peers.iter() {
+            let (sent, received) = counters.load();
+            let total = totals.entry(protocol.clone()).or_default();
+            total.0 = total.0.saturating_add(sent);
+            total.1 = total.1.saturating_add(received);
+        }
+        totals
+    }
+}
+
+/// What the multistream-select messages of a stream tell of its protocol.
+#[derive(Debug, PartialEq, Eq)]
+enum Negotiation {
+    /// More bytes are needed
+    Pending,
+    Agreed(String),
+    /// Not a multistream-select negotiation
+    Failed,
+}
+
+/// The protocol confirmed by the listener of a stream, out of the bytes it sent: length-prefixed messages
+/// being the multistream header, then `na` for each protocol rejected, then the echo of the one agreed on.
+fn negotiated_protocol(mut bytes: &[u8]) -> Negotiation {
+    loop {
+        let Some((len, prefix_len)) = decode_uvarint(bytes) else {
+            return Negotiation::Pending;
+        };
+        let Some(message) = bytes.get(prefix_len..prefix_len.saturating_add(len)) else {
+            return Negotiation::Pending;
+        };
+        bytes = &bytes[prefix_len + len..];
+        let Some(message) = message.strip_suffix(b"\n") else {
+            return Negotiation::Failed;
+        };
+        if message == MULTISTREAM_HEADER || message == MULTISTREAM_NA {
+            continue;
+        }
+        return match std::str::from_utf8(message) {
+            Ok(protocol) if protocol.starts_with('/') => Negotiation::Agreed(protocol.to_string()),
+            _ => Negotiation::Failed,
+        };
+    }
+}
+
+/// The unsigned varint at the start of the bytes, with its length.
+fn decode_uvarint(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    for (index, byte) in bytes.iter().enumerate().take(4) {
+        value |= usize::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+/// A muxer counting the bytes of all its substreams, per negotiated protocol.
+pub(crate) struct CountingMuxer<M> {
+    inner: M,
+    tracker: BandwidthTracker,
+    peer_id: PeerId,
+}
+
+impl<M> CountingMuxer<M> {
+    fn counting<S>(&self, inner: S, is_inbound: bool) -> CountingStream<S> {
+        CountingStream {
+            inner,
+            tracker: self.tracker.clone(),
+            peer_id: self.peer_id,
+            is_inbound,
+            account: StreamAccount::Negotiating {
+                listener_bytes: Vec::new(),
+                sent: 0,
+                received: 0,
+            },
+        }
+    }
+}
+
+impl<M> StreamMuxer for CountingMuxer<M>
+where
+    M: StreamMuxer + Unpin,
+    M::Substream: Unpin,
+{
+    type Substream = CountingStream<M::Substream>;
+    type Error = M::Error;
+
+    fn poll_inbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_inbound(cx))?;
+        Poll::Ready(Ok(this.counting(inner, true)))
+    }
+
+    fn poll_outbound(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.get_mut();
+        let inner = ready!(Pin::new(&mut this.inner).poll_outbound(cx))?;
+        Poll::Ready(Ok(this.counting(inner, false)))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll(cx)
+    }
+}
+
+/// Where the bytes of a stream are accounted to.
+enum StreamAccount {
+    /// The protocol isn't known yet, the bytes are held until it is
+    Negotiating {
+        /// The bytes sent by the listener of the stream so far, confirming the protocol
+        listener_bytes: Vec<u8>,
+        sent: u64,
+        received: u64,
+    },
+    Negotiated(Arc<Counters>),
+}
+
+/// A substream counting the bytes read from and written to it, accounted to the protocol negotiated on it.
+pub(crate) struct CountingStream<S> {
+    inner: S,
+    tracker: BandwidthTracker,
+    peer_id: PeerId,
+    /// Whether we're the listener of the stream, i.e. the one confirming the protocol
+    is_inbound: bool,
+    account: StreamAccount,
+}
+
+impl<S> CountingStream<S> {
+    fn account(&mut self, bytes: &[u8], is_sent: bool) {
+        let protocol = match &mut self.account {
+            StreamAccount::Negotiated(counters) => {
+                let counter = if is_sent {
+                    &counters.sent
+                } else {
+                    &counters.received
+                };
+                let _ = counter.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+                return;
+            }
+            StreamAccount::Negotiating {
+                listener_bytes,
+                sent,
+                received,
+            } => {
+                if is_sent {
+                    *sent = sent.saturating_add(bytes.len() as u64);
+                } else {
+                    *received = received.saturating_add(bytes.len() as u64);
+                }
+                // The listener writes the protocol agreed on, which the dialer reads
+                if is_sent != self.is_inbound {
+                    return;
+                }
+                let wanted = MAX_NEGOTIATION_BYTES.saturating_sub(listener_bytes.len());
+                listener_bytes.extend_from_slice(&bytes[..bytes.len().min(wanted)]);
+                match negotiated_protocol(listener_bytes) {
+                    Negotiation::Agreed(protocol) => protocol,
+                    Negotiation::Pending if listener_bytes.len() < MAX_NEGOTIATION_BYTES => return,
+                    Negotiation::Pending | Negotiation::Failed => UNKNOWN_PROTOCOL.to_string(),
+                }
+            }
+        };
+        self.settle(protocol);
+    }
+
+    /// Account the bytes held so far to the protocol, and the next ones as they come.
+    fn settle(&mut self, protocol: String) {
+        let counters = self.tracker.counters(self.peer_id, protocol);
+        if let StreamAccount::Negotiating { sent, received, .. } = &self.account {
+            let _ = counters.sent.fetch_add(*sent, Ordering::Relaxed);
+            let _ = counters.received.fetch_add(*received, Ordering::Relaxed);
+        }
+        self.account = StreamAccount::Negotiated(counters);
+    }
+}
+
+impl<S> Drop for CountingStream<S> {
+    fn drop(&mut self) {
+        if let StreamAccount::Negotiating { sent, received, .. } = &self.account {
+            if *sent > 0 || *received > 0 {
+                self.settle(UNKNOWN_PROTOCOL.to_string());
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let read = ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.account(&buf[..read], false);
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.account(&buf[..written], true);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{io::Cursor, AsyncReadExt, AsyncWriteExt};
+
+    /// The length-prefixed multistream-select messages.
+    fn messages(messages: &[&str]) -> Vec<u8> {
+        let mut bytes = vec![];
+        for message in messages {
+            bytes.push(message.len() as u8 + 1);
+            bytes.extend_from_slice(message.as_bytes());
+            bytes.push(b'\n');
+        }
+        bytes
+    }
+
+    #[test]
+    fn the_protocol_echoed_by_the_listener_is_the_negotiated_one() {
+        let header = "/multistream/1.0.0";
+        let confirmed = messages(&[header, "na", "/ipfs/kad/1.0.0"]);
+        assert_eq!(
+            negotiated_protocol(&confirmed),
+            Negotiation::Agreed("/ipfs/kad/1.0.0".to_string())
+        );
+        assert_eq!(
+            negotiated_protocol(&confirmed[..confirmed.len() - 3]),
+            Negotiation::Pending
+        );
+        assert_eq!(
+            negotiated_protocol(&messages(&[header])),
+            Negotiation::Pending
+        );
+        assert_eq!(negotiated_protocol(b"\x03abc"), Negotiation::Failed);
+    }
+
+    #[tokio::test]
+    async fn bytes_are_accounted_per_peer_and_protocol() -> eyre::Result<()> {
+        let tracker = BandwidthTracker::default();
+        let (peer, other_peer) = (PeerId::random(), PeerId::random());
+        let muxer = tracker.wrap(peer, ());
+
+        // The peer proposes a protocol, then sends its request
+        let mut proposal = messages(&["/multistream/1.0.0", "/autonomi/kad/1.0.0"]);
+        let proposal_len = proposal.len();
+        proposal.extend_from_slice(&[0u8; 16]);
+        let mut stream = muxer.counting(Cursor::new(proposal), true);
+        let mut buf = vec![0u8; proposal_len + 16];
+        stream.read_exact(&mut buf).await?;
+        // We confirm it, then respond
+        let confirmation = messages(&["/multistream/1.0.0", "/autonomi/kad/1.0.0"]);
+        stream.write_all(&confirmation).await?;
+        stream.write_all(&[1u8; 100]).await?;
+
+        // A stream closed before negotiating its protocol
+        let mut other_stream = tracker
+            .wrap(other_peer, ())
+            .counting(Cursor::new(vec![0u8; 8]), false);
+        other_stream.read_exact(&mut [0u8; 8]).await?;
+        drop(other_stream);
+
+        let usage = tracker.usage();
+        assert_eq!(
+            usage[0],
+            PeerBandwidth {
+                peer_id: peer,
+                protocol: "/autonomi/kad/1.0.0".to_string(),
+                bytes_sent: confirmation.len() as u64 + 100,
+                bytes_received: proposal_len as u64 + 16,
+            }
+        );
+        assert_eq!(usage[1].peer_id, other_peer);
+        assert_eq!(usage[1].protocol, UNKNOWN_PROTOCOL);
+        assert_eq!(usage[1].total(), 8);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Entry points into the hot paths of the `SwarmDriver` for the benches, driving them with synthetic events rather
+//! than through a network. Only built with the `bench` feature.
+
+use crate::{
+    driver::NetworkBuilder, error::Result, replication_fetcher::ReplicationFetcher, GetRecordCfg,
+    GetRecordError, GetRecordOk, Network, NetworkEvent, SwarmDriver,
+};
+use ant_protocol::{storage::RecordType, NetworkAddress};
+use libp2p::{
+    identity::Keypair,
+    kad::{self, PeerRecord, ProgressStep, QueryId, QueryResult, QueryStats, Record, RecordKey},
+    PeerId,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    num::NonZeroUsize,
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// Accumulates the copies of a record the way a GetRecord query does, feeding them to a client `SwarmDriver` as
+/// the kad events of its query.
+///
+/// Has to be created within a tokio runtime.
+pub struct GetRecordAccumulation {
+    driver: SwarmDriver,
+    query_id: QueryId,
+    peers: Vec<PeerId>,
+    _network: Network,
+    _events: mpsc::Receiver<NetworkEvent>,
+}
+
+impl GetRecordAccumulation {
+    /// A driver able to accumulate the copies from up to `peer_count` peers.
+    pub fn new(peer_count: usize) -> Result<Self> {
+        let (network, events, mut driver) =
+            NetworkBuilder::new(Keypair::generate_ed25519(), true).build_client()?;
+        // The query is never polled, so a single one stands in for all of the accumulations.
+        let query_id = driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_record(RecordKey::new(&[0; 32]));
+        Ok(Self {
+            driver,
+            query_id,
+            peers: (0..peer_count).map(|_| PeerId::random()).collect(),
+            _network: network,
+            _events: events,
+        })
+    }
+
+    /// Feed the copies to the query, each from a different peer, until it completes, finishing it if the copies run
+    /// out first.
+    pub fn accumulate(
+        &mut self,
+        copies: &[Record],
+        cfg: GetRecordCfg,
+    ) -> Result<std::result::Result<GetRecordOk, GetRecordError>> {
+        let key = copies
+            .first()
+            .map(|record| record.key.clone())
+            .unwrap_or_else(|| RecordKey::new(&[0; 32]));
+        let (sender, mut receiver) = oneshot::channel();
+        let _ = self
+            .driver
+            .pending_get_record
+            .insert(self.query_id, (key, vec![sender], Default::default(), cfg));
+
+        for (index, (record, peer)) in copies.iter().zip(self.peers.iter()).enumerate() {
+            self.driver.handle_kad_event(self.progressed(
+                kad::GetRecordOk::FoundRecord(PeerRecord {
+                    peer: Some(*peer),
+                    record: record.clone(),
+                }),
+                index + 1,
+                false,
+            ))?;
+            if let Ok(result) = receiver.try_recv() {
+                return Ok(result);
+            }
+        }
+
+        self.driver.handle_kad_event(self.progressed(
+            kad::GetRecordOk::FinishedWithNoAdditionalRecord {
+                cache_candidates: BTreeMap::new(),
+            },
+            copies.len() + 1,
+            true,
+        ))?;
+        Ok(receiver
+            .try_recv()
+            .unwrap_or(Err(GetRecordError::RecordNotFound)))
+    }
+
+    fn progressed(&self, result: kad::GetRecordOk, count: usize, last: bool) -> kad::Event {
+        kad::Event::OutboundQueryProgressed {
+            id: self.query_id,
+            result: QueryResult::GetRecord(Ok(result)),
+            stats: QueryStats::empty(),
+            step: ProgressStep {
+                count: NonZeroUsize::new(count).unwrap_or(NonZeroUsize::MIN),
+                last,
+            },
+        }
+    }
+}
+
+/// Queues the keys to replicate the way a node does when its peers notify it of them.
+pub struct ReplicationFetching {
+    fetcher: ReplicationFetcher,
+    _events: mpsc::Receiver<NetworkEvent>,
+}
+
+impl ReplicationFetching {
+    pub fn new(self_peer_id: PeerId) -> Self {
+        let (event_sender, events) = mpsc::channel(1);
+        Self {
+            fetcher: ReplicationFetcher::new(self_peer_id, event_sender),
+            _events: events,
+        }
+    }
+
+    /// Add the keys `holder` notified us of, returning the keys to fetch next.
+    pub fn add_keys(
+        &mut self,
+        holder: PeerId,
+        incoming_keys: Vec<(NetworkAddress, RecordType)>,
+        locally_stored_keys: &HashMap<RecordKey, (NetworkAddress, RecordType)>,
+    ) -> Vec<(PeerId, RecordKey)> {
+        self.fetcher
+            .add_keys(holder, incoming_keys, locally_stored_keys)
+    }
+
+    /// Mark the fetched key as stored, returning the keys to fetch next.
+    pub fn fetched(&mut self, key: RecordKey, record_type: RecordType) -> Vec<(PeerId, RecordKey)> {
+        self.fetcher.notify_about_new_put(key, record_type)
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Faults injected on purpose, to exercise the bad node detection and the retries of the clients. Only built with
+//! the `chaos` feature.
+
+use ant_protocol::{
+    error::Error as ProtocolError,
+    messages::{Query, QueryResponse, Request, Response},
+    storage::{RecordHeader, RecordKind},
+    NetworkAddress,
+};
+use libp2p::kad::Record;
+use rand::Rng;
+use std::{env, time::Duration};
+
+/// The percentage, 0 to 100, of the inbound requests to drop without a response.
+pub const CHAOS_DROP_REQUESTS_PERCENT_ENV: &str = "ANT_CHAOS_DROP_REQUESTS_PERCENT";
+/// How long, in milliseconds, to hold back the keys to fetch through replication.
+pub const CHAOS_REPLICATION_DELAY_MS_ENV: &str = "ANT_CHAOS_REPLICATION_DELAY_MS";
+/// Whether to serve the chunks corrupted, `true` or `1` to enable.
+pub const CHAOS_CORRUPT_CHUNKS_ENV: &str = "ANT_CHAOS_CORRUPT_CHUNKS";
+/// Whether to refuse to quote for storing records, `true` or `1` to enable.
+pub const CHAOS_REFUSE_QUOTES_ENV: &str = "ANT_CHAOS_REFUSE_QUOTES";
+
+/// The faults a node injects. The default injects none.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChaosConfig {
+    /// The percentage, 0 to 100, of the inbound requests dropped without a response.
+    pub drop_requests_percent: u8,
+    /// How long the keys to fetch through replication are held back for.
+    pub replication_delay: Duration,
+    /// Serve the chunks with a byte flipped, failing their verification.
+    pub corrupt_chunks: bool,
+    /// Answer the store quote requests with an error.
+    pub refuse_quotes: bool,
+}
+
+/// What to do with an inbound request.
+#[derive(Debug)]
+pub(crate) enum ChaosAction {
+    Handle,
+    Drop,
+    Respond(Box<Response>),
+}
+
+impl ChaosConfig {
+    /// The faults to inject according to the `ANT_CHAOS_*` env variables.
+    pub fn from_env() -> Self {
+        let flag = |name: &str| {
+            env::var(name).is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        };
+        let number = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+
+        Self {
+            drop_requests_percent: number(CHAOS_DROP_REQUESTS_PERCENT_ENV)
+                .map(|percent| percent.min(100) as u8)
+                .unwrap_or_default(),
+            replication_delay: Duration::from_millis(
+                number(CHAOS_REPLICATION_DELAY_MS_ENV).unwrap_or_default(),
+            ),
+            corrupt_chunks: flag(CHAOS_CORRUPT_CHUNKS_ENV),
+            refuse_quotes: flag(CHAOS_REFUSE_QUOTES_ENV),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self != &Self::default()
+    }
+
+    /// Whether to handle, drop or answer the inbound request straight away.
+    pub(crate) fn on_request(
+        &self,
+        request: &Request,
+        self_address: NetworkAddress,
+    ) -> ChaosAction {
+        if self.drop_requests_percent > 0
+            && rand::thread_rng().gen_range(0..100) < self.drop_requests_percent
+        {
+            return ChaosAction::Drop;
+        }
+        if self.refuse_quotes {
+            if let Request::Query(Query::GetStoreQuote { .. }) = request {
+                return ChaosAction::Respond(Box::new(Response::Query(
+                    QueryResponse::GetStoreQuote {
+                        quote: Err(ProtocolError::GetStoreQuoteFailed),
+                        peer_address: self_address,
+                        storage_proofs: vec![],
+                    },
+                )));
+            }
+        }
+        ChaosAction::Handle
+    }
+}
+
+/// The record with the last byte of its value flipped if it's a chunk, leaving its header intact.
+pub(crate) fn corrupt_chunk(mut record: Record) -> Record {
+    let is_chunk = RecordHeader::from_record(&record)
+        .is_ok_and(|header| matches!(header.kind, RecordKind::Chunk));
+    if is_chunk && record.value.len() > RecordHeader::SIZE {
+        if let Some(byte) = record.value.last_mut() {
+            *byte ^= 0xff;
+        }
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::storage::{try_serialize_record, Chunk, ChunkAddress};
+    use bytes::Bytes;
+    use libp2p::PeerId;
+
+    #[test]
+    fn chunks_are_corrupted_past_their_header() -> eyre::Result<()> {
+        let chunk = Chunk::new(Bytes::from_static(b"chunk to corrupt"));
+        let record = Record::new(
+            NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+        );
+
+        let corrupted = corrupt_chunk(record.clone());
+        assert_ne!(corrupted.value, record.value);
+        assert_eq!(
+            RecordHeader::from_record(&corrupted)?.kind,
+            RecordKind::Chunk
+        );
+
+        let not_a_chunk = Record::new(
+            NetworkAddress::from_chunk_address(ChunkAddress::new(Default::default()))
+                .to_record_key(),
+            vec![0; 8],
+        );
+        assert_eq!(corrupt_chunk(not_a_chunk.clone()).value, not_a_chunk.value);
+        Ok(())
+    }
+
+    #[test]
+    fn quotes_are_refused_and_requests_dropped() {
+        let self_address = NetworkAddress::from_peer(PeerId::random());
+        let quote_request = Request::Query(Query::GetStoreQuote {
+            key: self_address.clone(),
+            nonce: None,
+            difficulty: 0,
+        });
+
+        let config = ChaosConfig::default();
+        assert!(!config.is_enabled());
+        assert!(matches!(
+            config.on_request(&quote_request, self_address.clone()),
+            ChaosAction::Handle
+        ));
+
+        let config = ChaosConfig {
+            refuse_quotes: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.on_request(&quote_request, self_address.clone()),
+            ChaosAction::Respond(response) if matches!(
+                *response,
+                Response::Query(QueryResponse::GetStoreQuote { quote: Err(_), .. })
+            )
+        ));
+
+        let config = ChaosConfig {
+            drop_requests_percent: 100,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.on_request(&quote_request, self_address),
+            ChaosAction::Drop
+        ));
+    }
+}
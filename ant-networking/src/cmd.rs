@@ -6,20 +6,32 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosConfig;
 use crate::{
+    audit_log::RecordSource,
+    bandwidth::PeerBandwidth,
+    cmd_channel::send_cmd,
     driver::{PendingGetClosestType, SwarmDriver},
+    driver_state::SwarmDriverState,
     error::{NetworkError, Result},
     event::TerminateNodeReason,
     log_markers::Marker,
-    multiaddr_pop_p2p, GetRecordCfg, GetRecordError, MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE,
+    multiaddr_pop_p2p,
+    peer_scoring::PeerScore,
+    reachability::Reachability,
+    record_store::{RecordCategory, StoredRecord},
+    GetRecordCfg, GetRecordError, GetRecordOk, MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE,
 };
 use ant_evm::{PaymentQuote, QuotingMetrics, U256};
 use ant_protocol::{
     convert_distance_to_u256,
     messages::{Cmd, Request, Response},
     storage::{RecordHeader, RecordKind, RecordType},
+    version::NodeCapabilities,
     NetworkAddress, PrettyPrintRecordKey,
 };
+use bytes::Bytes;
 use libp2p::{
     kad::{
         store::{Error as StoreError, RecordStore},
@@ -27,8 +39,9 @@ use libp2p::{
     },
     Multiaddr, PeerId,
 };
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
     time::Duration,
 };
@@ -45,7 +58,7 @@ const REPLICATION_TIMEOUT: Duration = Duration::from_secs(45);
 // Throttles replication to at most once every 30 seconds
 const MIN_REPLICATION_INTERVAL_S: Duration = Duration::from_secs(30);
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum NodeIssue {
     /// Data Replication failed
     ReplicationFailure,
@@ -55,6 +68,13 @@ pub enum NodeIssue {
     BadQuoting,
     /// Peer failed to pass the chunk proof verification
     FailedChunkProofCheck,
+    /// Served a record whose content doesn't match its key
+    CorruptedRecord,
+    /// Didn't answer a request in time. No longer struck, as a timeout is as likely to come from our
+    /// own connection, but kept for the persisted reputations to load.
+    RequestTimeout,
+    /// Didn't return a record it notified us of holding, when fetched
+    MissingRecord,
 }
 
 /// Commands to send to the Swarm
@@ -94,26 +114,41 @@ pub enum LocalSwarmCmd {
     GetAllLocalRecordAddresses {
         sender: oneshot::Sender<HashMap<NetworkAddress, RecordType>>,
     },
+    /// Get the Addresses of the locally stored Records beyond our responsible range, the farthest first
+    GetIrrelevantRecordAddresses {
+        sender: oneshot::Sender<Vec<(NetworkAddress, RecordType)>>,
+    },
+    /// Get the records held by the local RecordStore, sorted by their distance to us
+    GetLocalStoredRecords {
+        sender: oneshot::Sender<Vec<StoredRecord>>,
+    },
     /// Get data from the local RecordStore
     GetLocalRecord {
         key: RecordKey,
         sender: oneshot::Sender<Option<Record>>,
     },
     /// GetLocalQuotingMetrics for this node
-    /// Returns the quoting metrics and whether the record at `key` is already stored locally
+    /// Returns the quoting metrics and whether the record at `key` is already stored locally,
+    /// or an error if a new record of the `category` would be rejected for its quota being full
     GetLocalQuotingMetrics {
         key: RecordKey,
-        sender: oneshot::Sender<(QuotingMetrics, bool)>,
+        category: Option<RecordCategory>,
+        sender: oneshot::Sender<Result<(QuotingMetrics, bool)>>,
     },
     /// Notify the node received a payment.
     PaymentReceived,
     /// Put record to the local RecordStore
     PutLocalRecord {
         record: Record,
+        source: RecordSource,
+    },
+    /// Remove a local record from the RecordStore, because its write failed
+    /// Counts towards the continuous HDD write errors terminating the node
+    RemoveLocalRecord {
+        key: RecordKey,
     },
-    /// Remove a local record from the RecordStore
-    /// Typically because the write failed
-    RemoveFailedLocalRecord {
+    /// Remove a local record from the RecordStore, as no longer relevant to us and held enough by its close group
+    PruneLocalRecord {
         key: RecordKey,
     },
     /// Add a local record to the RecordStore's HashSet of stored records
@@ -131,6 +166,35 @@ pub enum LocalSwarmCmd {
         peer_id: PeerId,
         issue: NodeIssue,
     },
+    /// Persist the peer reputation to the node's root dir
+    SavePeerReputation {
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Persist the warm standby snapshot to the node's root dir, if in warm standby mode
+    SaveWarmStandby {
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Get the bytes sent and received per peer, highest first
+    GetBandwidthUsage {
+        sender: oneshot::Sender<Vec<PeerBandwidth>>,
+    },
+    /// Get the sizes of the internal state of the `SwarmDriver`
+    GetDriverState {
+        sender: oneshot::Sender<SwarmDriverState>,
+    },
+    /// Replace the faults injected on purpose
+    #[cfg(feature = "chaos")]
+    SetChaosConfig {
+        config: ChaosConfig,
+    },
+    /// Get the scores of the peers that misbehaved
+    GetPeerScores {
+        sender: oneshot::Sender<Vec<PeerScore>>,
+    },
+    /// Get the capabilities the peers in our RT advertised
+    GetPeerCapabilities {
+        sender: oneshot::Sender<HashMap<PeerId, NodeCapabilities>>,
+    },
     // Whether peer is considered as `in trouble` by self
     IsPeerShunned {
         target: NetworkAddress,
@@ -147,6 +211,8 @@ pub enum LocalSwarmCmd {
     TriggerIntervalReplication,
     /// Triggers unrelevant record cleanup
     TriggerIrrelevantRecordCleanup,
+    /// Stop quoting for and storing new records, as the node is decommissioning
+    StopAcceptingRecords,
     /// Add a network density sample
     AddNetworkDensitySample {
         distance: Distance,
@@ -164,6 +230,11 @@ pub enum NetworkSwarmCmd {
         key: NetworkAddress,
         sender: oneshot::Sender<Vec<PeerId>>,
     },
+    /// Look up the closest peers of each address, to warm up the routing table toward them
+    Prewarm {
+        addresses: Vec<NetworkAddress>,
+        sender: oneshot::Sender<()>,
+    },
 
     // Send Request to the PeerId.
     SendRequest {
@@ -186,23 +257,34 @@ pub enum NetworkSwarmCmd {
     /// Get Record from the Kad network
     GetNetworkRecord {
         key: RecordKey,
-        sender: oneshot::Sender<std::result::Result<Record, GetRecordError>>,
+        sender: oneshot::Sender<std::result::Result<GetRecordOk, GetRecordError>>,
         cfg: GetRecordCfg,
     },
+    /// Cancel any in-flight GetRecord query for the key
+    CancelGetRecord { key: RecordKey },
 
-    /// Put record to network
+    /// Put record to the closest peers of the record, in the network.
+    /// Replies with the peers that acknowledged storing the record.
     PutRecord {
         record: Record,
-        sender: oneshot::Sender<Result<()>>,
+        sender: oneshot::Sender<Result<Vec<PeerId>>>,
         quorum: Quorum,
     },
     /// Put record to specific node
+    /// Replies with the peers that acknowledged storing the record.
     PutRecordTo {
         peers: Vec<PeerId>,
         record: Record,
-        sender: oneshot::Sender<Result<()>>,
+        sender: oneshot::Sender<Result<Vec<PeerId>>>,
         quorum: Quorum,
     },
+
+    /// Subscribe to a gossipsub topic
+    GossipsubSubscribe(String),
+    /// Unsubscribe from a gossipsub topic
+    GossipsubUnsubscribe(String),
+    /// Publish a message on a gossipsub topic
+    GossipsubPublish { topic_id: String, msg: Bytes },
 }
 
 /// Debug impl for LocalSwarmCmd to avoid printing full Record, instead only RecodKey
@@ -210,17 +292,24 @@ pub enum NetworkSwarmCmd {
 impl Debug for LocalSwarmCmd {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            LocalSwarmCmd::PutLocalRecord { record } => {
+            LocalSwarmCmd::PutLocalRecord { record, .. } => {
                 write!(
                     f,
                     "LocalSwarmCmd::PutLocalRecord {{ key: {:?} }}",
                     PrettyPrintRecordKey::from(&record.key)
                 )
             }
-            LocalSwarmCmd::RemoveFailedLocalRecord { key } => {
+            LocalSwarmCmd::RemoveLocalRecord { key } => {
                 write!(
                     f,
-                    "LocalSwarmCmd::RemoveFailedLocalRecord {{ key: {:?} }}",
+                    "LocalSwarmCmd::RemoveLocalRecord {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(key)
+                )
+            }
+            LocalSwarmCmd::PruneLocalRecord { key } => {
+                write!(
+                    f,
+                    "LocalSwarmCmd::PruneLocalRecord {{ key: {:?} }}",
                     PrettyPrintRecordKey::from(key)
                 )
             }
@@ -259,6 +348,12 @@ impl Debug for LocalSwarmCmd {
             LocalSwarmCmd::GetAllLocalRecordAddresses { .. } => {
                 write!(f, "LocalSwarmCmd::GetAllLocalRecordAddresses")
             }
+            LocalSwarmCmd::GetIrrelevantRecordAddresses { .. } => {
+                write!(f, "LocalSwarmCmd::GetIrrelevantRecordAddresses")
+            }
+            LocalSwarmCmd::GetLocalStoredRecords { .. } => {
+                write!(f, "LocalSwarmCmd::GetLocalStoredRecords")
+            }
             LocalSwarmCmd::GetPeersWithMultiaddr { .. } => {
                 write!(f, "LocalSwarmCmd::GetPeersWithMultiaddr")
             }
@@ -284,6 +379,28 @@ impl Debug for LocalSwarmCmd {
                     "LocalSwarmCmd::SendNodeStatus peer {peer_id:?}, issue: {issue:?}"
                 )
             }
+            LocalSwarmCmd::SavePeerReputation { .. } => {
+                write!(f, "LocalSwarmCmd::SavePeerReputation")
+            }
+            LocalSwarmCmd::SaveWarmStandby { .. } => {
+                write!(f, "LocalSwarmCmd::SaveWarmStandby")
+            }
+            LocalSwarmCmd::GetBandwidthUsage { .. } => {
+                write!(f, "LocalSwarmCmd::GetBandwidthUsage")
+            }
+            LocalSwarmCmd::GetDriverState { .. } => {
+                write!(f, "LocalSwarmCmd::GetDriverState")
+            }
+            #[cfg(feature = "chaos")]
+            LocalSwarmCmd::SetChaosConfig { config } => {
+                write!(f, "LocalSwarmCmd::SetChaosConfig {{ config: {config:?} }}")
+            }
+            LocalSwarmCmd::GetPeerScores { .. } => {
+                write!(f, "LocalSwarmCmd::GetPeerScores")
+            }
+            LocalSwarmCmd::GetPeerCapabilities { .. } => {
+                write!(f, "LocalSwarmCmd::GetPeerCapabilities")
+            }
             LocalSwarmCmd::IsPeerShunned { target, .. } => {
                 write!(f, "LocalSwarmCmd::IsPeerInTrouble target: {target:?}")
             }
@@ -310,6 +427,9 @@ impl Debug for LocalSwarmCmd {
             LocalSwarmCmd::AddNetworkDensitySample { distance } => {
                 write!(f, "LocalSwarmCmd::AddNetworkDensitySample({distance:?})")
             }
+            LocalSwarmCmd::StopAcceptingRecords => {
+                write!(f, "LocalSwarmCmd::StopAcceptingRecords")
+            }
         }
     }
 }
@@ -329,6 +449,13 @@ impl Debug for NetworkSwarmCmd {
                     PrettyPrintRecordKey::from(key)
                 )
             }
+            NetworkSwarmCmd::CancelGetRecord { key } => {
+                write!(
+                    f,
+                    "NetworkSwarmCmd::CancelGetRecord {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(key)
+                )
+            }
             NetworkSwarmCmd::PutRecord { record, .. } => {
                 write!(
                     f,
@@ -346,6 +473,13 @@ impl Debug for NetworkSwarmCmd {
             NetworkSwarmCmd::GetClosestPeersToAddressFromNetwork { key, .. } => {
                 write!(f, "NetworkSwarmCmd::GetClosestPeers {{ key: {key:?} }}")
             }
+            NetworkSwarmCmd::Prewarm { addresses, .. } => {
+                write!(
+                    f,
+                    "NetworkSwarmCmd::Prewarm {{ addresses: {} }}",
+                    addresses.len()
+                )
+            }
             NetworkSwarmCmd::SendResponse { resp, .. } => {
                 write!(f, "NetworkSwarmCmd::SendResponse resp: {resp:?}")
             }
@@ -355,6 +489,19 @@ impl Debug for NetworkSwarmCmd {
                     "NetworkSwarmCmd::SendRequest req: {req:?}, peer: {peer:?}"
                 )
             }
+            NetworkSwarmCmd::GossipsubSubscribe(topic) => {
+                write!(f, "NetworkSwarmCmd::GossipsubSubscribe({topic})")
+            }
+            NetworkSwarmCmd::GossipsubUnsubscribe(topic) => {
+                write!(f, "NetworkSwarmCmd::GossipsubUnsubscribe({topic})")
+            }
+            NetworkSwarmCmd::GossipsubPublish { topic_id, msg } => {
+                write!(
+                    f,
+                    "NetworkSwarmCmd::GossipsubPublish {{ topic_id: {topic_id}, msg len: {} }}",
+                    msg.len()
+                )
+            }
         }
     }
 }
@@ -365,6 +512,8 @@ pub struct SwarmLocalState {
     pub connected_peers: Vec<PeerId>,
     /// List of addresses the node is currently listening on
     pub listeners: Vec<Multiaddr>,
+    /// Whether the node is reachable from the public internet
+    pub reachability: Reachability,
 }
 
 impl SwarmDriver {
@@ -372,7 +521,11 @@ impl SwarmDriver {
         let start = Instant::now();
         let cmd_string;
         match cmd {
-            NetworkSwarmCmd::GetNetworkRecord { key, sender, cfg } => {
+            NetworkSwarmCmd::GetNetworkRecord {
+                key,
+                sender,
+                mut cfg,
+            } => {
                 cmd_string = "GetNetworkRecord";
 
                 for (pending_query, (inflight_record_query_key, senders, _, _)) in
@@ -390,6 +543,10 @@ impl SwarmDriver {
                     }
                 }
 
+                if cfg.expected_holders.is_empty() && self.replication_fetcher.tracks_holders() {
+                    cfg.expected_holders = self.known_close_holders(&key);
+                }
+
                 let query_id = self.swarm.behaviour_mut().kademlia.get_record(key.clone());
 
                 debug!(
@@ -415,6 +572,36 @@ impl SwarmDriver {
                 info!("We now have {} pending get record attempts and cached {total_records} fetched copies",
                       self.pending_get_record.len());
             }
+            NetworkSwarmCmd::CancelGetRecord { key } => {
+                cmd_string = "CancelGetRecord";
+                let query_ids = self
+                    .pending_get_record
+                    .iter()
+                    .filter(|(_, (inflight_record_query_key, ..))| {
+                        *inflight_record_query_key == key
+                    })
+                    .map(|(query_id, _)| *query_id)
+                    .collect::<Vec<_>>();
+
+                for query_id in query_ids {
+                    debug!(
+                        "Cancelling GetNetworkRecord task {query_id:?} for {:?}",
+                        PrettyPrintRecordKey::from(&key)
+                    );
+                    if let Some(mut query) =
+                        self.swarm.behaviour_mut().kademlia.query_mut(&query_id)
+                    {
+                        query.finish();
+                    }
+                    if let Some((_, senders, _, _)) = self.pending_get_record.remove(&query_id) {
+                        for sender in senders {
+                            if sender.send(Err(GetRecordError::Cancelled)).is_err() {
+                                debug!("Waiter of the cancelled task {query_id:?} has dropped");
+                            }
+                        }
+                    }
+                }
+            }
             NetworkSwarmCmd::PutRecord {
                 record,
                 sender,
@@ -427,25 +614,24 @@ impl SwarmDriver {
                     record.value.len(),
                     record_key
                 );
-                let res = match self
+                // Look up the closest peers first, so that we know exactly which peers the record is sent to.
+                let query_id = self
                     .swarm
                     .behaviour_mut()
                     .kademlia
-                    .put_record(record, quorum)
-                {
-                    Ok(request_id) => {
-                        debug!("Sent record {record_key:?} to network. Request id: {request_id:?} to network");
-                        Ok(())
-                    }
-                    Err(error) => {
-                        error!("Error sending record {record_key:?} to network");
-                        Err(NetworkError::from(error))
-                    }
-                };
-
-                if let Err(err) = sender.send(res) {
-                    error!("Could not send response to PutRecord cmd: {:?}", err);
-                }
+                    .get_closest_peers(record.key.to_vec());
+                debug!("Looking up the closest peers to PUT record {record_key:?} to. Request id: {query_id:?}");
+                let _ = self.pending_get_closest_peers.insert(
+                    query_id,
+                    (
+                        PendingGetClosestType::PutRecord {
+                            record,
+                            quorum,
+                            sender,
+                        },
+                        Default::default(),
+                    ),
+                );
             }
             NetworkSwarmCmd::PutRecordTo {
                 peers,
@@ -459,17 +645,7 @@ impl SwarmDriver {
                     "Putting record {record_key:?} sized: {:?} to {peers:?}",
                     record.value.len(),
                 );
-                let peers_count = peers.len();
-                let request_id = self.swarm.behaviour_mut().kademlia.put_record_to(
-                    record,
-                    peers.into_iter(),
-                    quorum,
-                );
-                debug!("Sent record {record_key:?} to {peers_count:?} peers. Request id: {request_id:?}");
-
-                if let Err(err) = sender.send(Ok(())) {
-                    error!("Could not send response to PutRecordTo cmd: {:?}", err);
-                }
+                self.put_record_to_peers(record, peers, quorum, sender);
             }
 
             NetworkSwarmCmd::Dial { addr, sender } => {
@@ -503,6 +679,11 @@ impl SwarmDriver {
                     ),
                 );
             }
+            NetworkSwarmCmd::Prewarm { addresses, sender } => {
+                cmd_string = "Prewarm";
+                self.routing_table_prewarm.add_batch(addresses, sender);
+                self.issue_prewarm_lookups();
+            }
 
             NetworkSwarmCmd::SendRequest { req, peer, sender } => {
                 cmd_string = "SendRequest";
@@ -529,6 +710,7 @@ impl SwarmDriver {
                         .send_request(&peer, req);
                     trace!("Sending request {request_id:?} to peer {peer:?}");
                     let _ = self.pending_requests.insert(request_id, sender);
+                    self.connection_limiter.on_peer_activity(&peer);
 
                     trace!("Pending Requests now: {:?}", self.pending_requests.len());
                 }
@@ -561,6 +743,29 @@ impl SwarmDriver {
                     }
                 }
             }
+            NetworkSwarmCmd::GossipsubSubscribe(topic_id) => {
+                cmd_string = "GossipsubSubscribe";
+                let topic = libp2p::gossipsub::IdentTopic::new(topic_id);
+                let _ = self
+                    .swarm
+                    .behaviour_mut()
+                    .gossipsub
+                    .subscribe(&topic)
+                    .map_err(|err| NetworkError::BehaviourErr(err.to_string()))?;
+            }
+            NetworkSwarmCmd::GossipsubUnsubscribe(topic_id) => {
+                cmd_string = "GossipsubUnsubscribe";
+                let topic = libp2p::gossipsub::IdentTopic::new(topic_id);
+                let _ = self.swarm.behaviour_mut().gossipsub.unsubscribe(&topic);
+            }
+            NetworkSwarmCmd::GossipsubPublish { topic_id, msg } => {
+                cmd_string = "GossipsubPublish";
+                let topic = libp2p::gossipsub::IdentTopic::new(topic_id);
+                // Having no peers subscribed to the topic is the common case, nobody is watching.
+                if let Err(err) = self.swarm.behaviour_mut().gossipsub.publish(topic, msg) {
+                    debug!("Did not publish gossipsub msg: {err}");
+                }
+            }
         }
 
         self.log_handling(cmd_string.to_string(), start.elapsed());
@@ -575,8 +780,22 @@ impl SwarmDriver {
                 cmd_string = "TriggerIntervalReplication";
                 self.try_interval_replication()?;
             }
-            LocalSwarmCmd::GetLocalQuotingMetrics { key, sender } => {
+            LocalSwarmCmd::GetLocalQuotingMetrics {
+                key,
+                category,
+                sender,
+            } => {
                 cmd_string = "GetLocalQuotingMetrics";
+                if !self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .is_accepting_records()
+                {
+                    let _res = sender.send(Err(NetworkError::NotAcceptingRecords));
+                    return Ok(());
+                }
                 let (
                     _index,
                     _total_peers,
@@ -593,6 +812,23 @@ impl SwarmDriver {
                     .store_mut()
                     .quoting_metrics(&key, Some(estimated_network_size as u64));
 
+                if let Some(category) = category.filter(|_| !is_already_stored) {
+                    if self
+                        .swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .store_mut()
+                        .is_quota_full_for(&key, category)
+                    {
+                        warn!(
+                            "Not quoting {:?}, as the quota of {category} records is full",
+                            PrettyPrintRecordKey::from(&key)
+                        );
+                        let _res = sender.send(Err(NetworkError::QuotaFull(category)));
+                        return Ok(());
+                    }
+                }
+
                 self.record_metrics(Marker::QuotingMetrics {
                     quoting_metrics: &quoting_metrics,
                 });
@@ -600,15 +836,9 @@ impl SwarmDriver {
                 // To avoid sending entire list to client, sending those that:
                 //     closer than the CLOSE_GROUP_SIZEth closest node to the target
                 let mut bad_nodes: Vec<_> = self
-                    .bad_nodes
-                    .iter()
-                    .filter_map(|(peer_id, (_issue_list, is_bad))| {
-                        if *is_bad {
-                            Some(NetworkAddress::from_peer(*peer_id))
-                        } else {
-                            None
-                        }
-                    })
+                    .peer_scores
+                    .bad_peers()
+                    .map(|peer_id| NetworkAddress::from_peer(*peer_id))
                     .collect();
 
                 // List is ordered already, hence the last one is always the one wanted
@@ -631,7 +861,7 @@ impl SwarmDriver {
                         .retain(|peer_addr| key_address.distance(peer_addr) < boundary_distance);
                 }
 
-                let _res = sender.send((quoting_metrics, is_already_stored));
+                let _res = sender.send(Ok((quoting_metrics, is_already_stored)));
             }
             LocalSwarmCmd::PaymentReceived => {
                 cmd_string = "PaymentReceived";
@@ -653,29 +883,30 @@ impl SwarmDriver {
                 let _ = sender.send(record);
             }
 
-            LocalSwarmCmd::PutLocalRecord { record } => {
+            LocalSwarmCmd::PutLocalRecord { record, source } => {
                 cmd_string = "PutLocalRecord";
                 let key = record.key.clone();
                 let record_key = PrettyPrintRecordKey::from(&key);
 
                 let record_type = match RecordHeader::from_record(&record) {
-                    Ok(record_header) => {
-                        match record_header.kind {
-                            RecordKind::Chunk => RecordType::Chunk,
-                            RecordKind::Scratchpad => RecordType::Scratchpad,
-                            RecordKind::Transaction | RecordKind::Register => {
-                                let content_hash = XorName::from_content(&record.value);
-                                RecordType::NonChunk(content_hash)
-                            }
-                            RecordKind::ChunkWithPayment
-                            | RecordKind::RegisterWithPayment
-                            | RecordKind::TransactionWithPayment
-                            | RecordKind::ScratchpadWithPayment => {
-                                error!("Record {record_key:?} with payment shall not be stored locally.");
-                                return Err(NetworkError::InCorrectRecordHeader);
-                            }
+                    Ok(record_header) => match record_header.kind {
+                        RecordKind::Chunk => RecordType::Chunk,
+                        RecordKind::Scratchpad => RecordType::Scratchpad,
+                        RecordKind::Transaction | RecordKind::Register | RecordKind::Pointer => {
+                            let content_hash = XorName::from_content(&record.value);
+                            RecordType::NonChunk(content_hash)
                         }
-                    }
+                        RecordKind::ChunkWithPayment
+                        | RecordKind::RegisterWithPayment
+                        | RecordKind::TransactionWithPayment
+                        | RecordKind::ScratchpadWithPayment
+                        | RecordKind::PointerWithPayment => {
+                            error!(
+                                "Record {record_key:?} with payment shall not be stored locally."
+                            );
+                            return Err(NetworkError::InCorrectRecordHeader);
+                        }
+                    },
                     Err(err) => {
                         error!("For record {record_key:?}, failed to parse record_header {err:?}");
                         return Err(NetworkError::InCorrectRecordHeader);
@@ -691,6 +922,11 @@ impl SwarmDriver {
 
                 match result {
                     Ok(_) => {
+                        self.swarm
+                            .behaviour_mut()
+                            .kademlia
+                            .store_mut()
+                            .audit_write(&key, source);
                         // `replication_fetcher.farthest_acceptable_distance` shall only get
                         // shrinked, instead of expanding, even with more nodes joined to share
                         // the responsibility. Hence no need to reset it.
@@ -698,7 +934,7 @@ impl SwarmDriver {
                         // once capacity reached max_records, there is only chance of rising slowly.
                         // Due to the async/parrellel handling in replication_fetcher & record_store.
                     }
-                    Err(StoreError::MaxRecords) => {
+                    Err(NetworkError::KademliaStoreError(StoreError::MaxRecords)) => {
                         // In case the capacity reaches full, restrict replication_fetcher to
                         // only fetch entries not farther than the current farthest record
                         let farthest = self
@@ -724,7 +960,7 @@ impl SwarmDriver {
                     .notify_about_new_put(key.clone(), record_type);
 
                 if !new_keys_to_fetch.is_empty() {
-                    self.send_event(NetworkEvent::KeysToFetchForReplication(new_keys_to_fetch));
+                    self.send_keys_to_fetch(new_keys_to_fetch);
                 }
 
                 // The record_store will prune far records and setup a `distance range`,
@@ -744,7 +980,7 @@ impl SwarmDriver {
                     error!("Can't store verified record {record_key:?} locally: {err:?}");
                     cmd_string = "PutLocalRecord error";
                     self.log_handling(cmd_string.to_string(), start.elapsed());
-                    return Err(err.into());
+                    return Err(err);
                 };
             }
             LocalSwarmCmd::AddLocalRecordAsStored { key, record_type } => {
@@ -761,9 +997,9 @@ impl SwarmDriver {
                 // Reset counter on any success HDD write.
                 self.hard_disk_write_error = 0;
             }
-            LocalSwarmCmd::RemoveFailedLocalRecord { key } => {
+            LocalSwarmCmd::RemoveLocalRecord { key } => {
                 info!("Removing Record locally, for {key:?}");
-                cmd_string = "RemoveFailedLocalRecord";
+                cmd_string = "RemoveLocalRecord";
                 self.swarm.behaviour_mut().kademlia.store_mut().remove(&key);
                 self.hard_disk_write_error = self.hard_disk_write_error.saturating_add(1);
                 // When there is certain amount of continuous HDD write error,
@@ -774,6 +1010,15 @@ impl SwarmDriver {
                     });
                 }
             }
+            LocalSwarmCmd::PruneLocalRecord { key } => {
+                info!("Pruning Record locally, for {key:?}");
+                cmd_string = "PruneLocalRecord";
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .prune_replicated(&key);
+            }
             LocalSwarmCmd::RecordStoreHasKey { key, sender } => {
                 cmd_string = "RecordStoreHasKey";
                 let has_key = self
@@ -795,6 +1040,26 @@ impl SwarmDriver {
                     .record_addresses();
                 let _ = sender.send(addresses);
             }
+            LocalSwarmCmd::GetIrrelevantRecordAddresses { sender } => {
+                cmd_string = "GetIrrelevantRecordAddresses";
+                let addresses = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .irrelevant_record_addresses();
+                let _ = sender.send(addresses);
+            }
+            LocalSwarmCmd::GetLocalStoredRecords { sender } => {
+                cmd_string = "GetLocalStoredRecords";
+                let stored_records = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .stored_records();
+                let _ = sender.send(stored_records);
+            }
             LocalSwarmCmd::GetKBuckets { sender } => {
                 cmd_string = "GetKBuckets";
                 let mut ilog2_kbuckets = BTreeMap::new();
@@ -860,6 +1125,7 @@ impl SwarmDriver {
                 let current_state = SwarmLocalState {
                     connected_peers: self.swarm.connected_peers().cloned().collect(),
                     listeners: self.swarm.listeners().cloned().collect(),
+                    reachability: self.reachability,
                 };
 
                 sender
@@ -874,27 +1140,73 @@ impl SwarmDriver {
                 cmd_string = "RecordNodeIssues";
                 self.record_node_issue(peer_id, issue);
             }
-            LocalSwarmCmd::IsPeerShunned { target, sender } => {
-                cmd_string = "IsPeerInTrouble";
-                let is_bad = if let Some(peer_id) = target.as_peer_id() {
-                    if let Some((_issues, is_bad)) = self.bad_nodes.get(&peer_id) {
-                        *is_bad
-                    } else {
-                        false
+            LocalSwarmCmd::SavePeerReputation { sender } => {
+                cmd_string = "SavePeerReputation";
+                match self.peer_reputation_dir.clone() {
+                    Some(root_dir) => {
+                        let reputation = self.peer_reputation();
+                        // Off the driver, for a slow disk not to stall it
+                        let _handle = tokio::task::spawn_blocking(move || {
+                            let _ =
+                                sender.send(reputation.save(&root_dir).map_err(NetworkError::from));
+                        });
                     }
-                } else {
-                    false
+                    None => {
+                        let _ = sender.send(Ok(()));
+                    }
+                }
+            }
+            LocalSwarmCmd::SaveWarmStandby { sender } => {
+                cmd_string = "SaveWarmStandby";
+                let result = match self.warm_standby_dir.clone() {
+                    Some(root_dir) => self
+                        .warm_standby_snapshot()
+                        .save(&root_dir)
+                        .map_err(NetworkError::from),
+                    None => Ok(()),
                 };
+                let _ = sender.send(result);
+            }
+            LocalSwarmCmd::GetBandwidthUsage { sender } => {
+                cmd_string = "GetBandwidthUsage";
+                let _ = sender.send(self.bandwidth_tracker.usage());
+            }
+            LocalSwarmCmd::GetDriverState { sender } => {
+                cmd_string = "GetDriverState";
+                let _ = sender.send(self.driver_state());
+            }
+            #[cfg(feature = "chaos")]
+            LocalSwarmCmd::SetChaosConfig { config } => {
+                cmd_string = "SetChaosConfig";
+                warn!("Injecting faults: {config:?}");
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .set_corrupt_chunks(config.corrupt_chunks);
+                self.chaos = config;
+            }
+            LocalSwarmCmd::GetPeerScores { sender } => {
+                cmd_string = "GetPeerScores";
+                let _ = sender.send(self.peer_scores.scores());
+            }
+            LocalSwarmCmd::GetPeerCapabilities { sender } => {
+                cmd_string = "GetPeerCapabilities";
+                let _ = sender.send(self.peer_capabilities.clone());
+            }
+            LocalSwarmCmd::IsPeerShunned { target, sender } => {
+                cmd_string = "IsPeerInTrouble";
+                let is_bad = target
+                    .as_peer_id()
+                    .is_some_and(|peer_id| self.peer_scores.is_bad(&peer_id));
                 let _ = sender.send(is_bad);
             }
             LocalSwarmCmd::QuoteVerification { quotes } => {
                 cmd_string = "QuoteVerification";
                 for (peer_id, quote) in quotes {
                     // Do nothing if already being bad
-                    if let Some((_issues, is_bad)) = self.bad_nodes.get(&peer_id) {
-                        if *is_bad {
-                            continue;
-                        }
+                    if self.peer_scores.is_bad(&peer_id) {
+                        continue;
                     }
                     self.verify_peer_quote(peer_id, quote);
                 }
@@ -909,7 +1221,7 @@ impl SwarmDriver {
                     .replication_fetcher
                     .notify_fetch_early_completed(key, record_type);
                 if !new_keys_to_fetch.is_empty() {
-                    self.send_event(NetworkEvent::KeysToFetchForReplication(new_keys_to_fetch));
+                    self.send_keys_to_fetch(new_keys_to_fetch);
                 }
             }
             LocalSwarmCmd::TriggerIrrelevantRecordCleanup => {
@@ -920,6 +1232,14 @@ impl SwarmDriver {
                     .store_mut()
                     .cleanup_irrelevant_records();
             }
+            LocalSwarmCmd::StopAcceptingRecords => {
+                cmd_string = "StopAcceptingRecords";
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .stop_accepting_records();
+            }
             LocalSwarmCmd::AddNetworkDensitySample { distance } => {
                 cmd_string = "AddNetworkDensitySample";
                 self.network_density_samples.add(distance);
@@ -931,55 +1251,45 @@ impl SwarmDriver {
         Ok(())
     }
 
-    fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
-        info!("Peer {peer_id:?} is reported as having issue {issue:?}");
-        let (issue_vec, is_bad) = self.bad_nodes.entry(peer_id).or_default();
-
-        let mut is_new_bad = false;
-        let mut bad_behaviour: String = "".to_string();
-
-        // If being considered as bad already, skip certain operations
-        if !(*is_bad) {
-            // Remove outdated entries
-            issue_vec.retain(|(_, timestamp)| timestamp.elapsed().as_secs() < 300);
-
-            // check if vec is already 10 long, if so, remove the oldest issue
-            // we only track 10 issues to avoid mem leaks
-            if issue_vec.len() == 10 {
-                issue_vec.remove(0);
-            }
-
-            // To avoid being too sensitive, only consider as a new issue
-            // when after certain while since the last one
-            let is_new_issue = if let Some((_issue, timestamp)) = issue_vec.last() {
-                timestamp.elapsed().as_secs() > 10
-            } else {
-                true
-            };
-
-            if is_new_issue {
-                issue_vec.push((issue, Instant::now()));
-            }
+    /// The peers among the close group of the key in our routing table that notified us of holding it, as the ones
+    /// sure to be asked for it.
+    fn known_close_holders(&mut self, key: &RecordKey) -> HashSet<PeerId> {
+        let known_holders = self.replication_fetcher.known_holders(key);
+        if known_holders.is_empty() {
+            return known_holders;
+        }
+        let kbucket_key = NetworkAddress::from_record_key(key).as_kbucket_key();
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .get_closest_local_peers(&kbucket_key)
+            .map(|peer| peer.into_preimage())
+            .take(CLOSE_GROUP_SIZE)
+            .filter(|peer| known_holders.contains(peer))
+            .collect()
+    }
 
-            // Only consider candidate as a bad node when:
-            //   accumulated THREE same kind issues within certain period
-            for (issue, _timestamp) in issue_vec.iter() {
-                let issue_counts = issue_vec
-                    .iter()
-                    .filter(|(i, _timestamp)| *issue == *i)
-                    .count();
-                if issue_counts >= 3 {
-                    *is_bad = true;
-                    is_new_bad = true;
-                    bad_behaviour = format!("{issue:?}");
-                    info!("Peer {peer_id:?} accumulated {issue_counts} times of issue {issue:?}. Consider it as a bad node now.");
-                    // Once a bad behaviour detected, no point to continue
-                    break;
-                }
-            }
+    /// Strike the expected holders that didn't return the record, once the query asked its whole close group.
+    /// The holders given by the caller are only struck if we're tracking the holders ourselves.
+    pub(crate) fn strike_missing_holders(&mut self, key: &RecordKey, missing: HashSet<PeerId>) {
+        if missing.is_empty() || !self.replication_fetcher.tracks_holders() {
+            return;
+        }
+        warn!(
+            "Expected holders {missing:?} didn't return record {:?}",
+            PrettyPrintRecordKey::from(key)
+        );
+        for peer_id in missing {
+            self.record_node_issue(peer_id, NodeIssue::MissingRecord);
         }
+    }
+
+    pub(crate) fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
+        info!("Peer {peer_id:?} is reported as having issue {issue:?}");
+        let is_new_bad = self.peer_scores.strike(peer_id, issue);
+        let bad_behaviour = format!("{issue:?}");
 
-        if *is_bad {
+        if self.peer_scores.is_bad(&peer_id) {
             warn!("Cleaning out bad_peer {peer_id:?}. Will be added to the blocklist after informing that peer.");
             if let Some(dead_peer) = self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id) {
                 self.update_on_peer_removal(*dead_peer.node.key.preimage());
@@ -996,9 +1306,11 @@ impl SwarmDriver {
                     match rx.await {
                         Ok(result) => {
                             debug!("Got response for Cmd::PeerConsideredAsBad from {peer_id:?} {result:?}");
-                            if let Err(err) = local_swarm_cmd_sender
-                                .send(LocalSwarmCmd::AddPeerToBlockList { peer_id })
-                                .await
+                            if let Err(err) = send_cmd(
+                                &local_swarm_cmd_sender,
+                                LocalSwarmCmd::AddPeerToBlockList { peer_id },
+                            )
+                            .await
                             {
                                 error!("SwarmDriver failed to send LocalSwarmCmd: {err}");
                             }
@@ -1068,9 +1380,8 @@ impl SwarmDriver {
             .behaviour_mut()
             .kademlia
             .store_mut()
-            .record_addresses_ref()
-            .values()
-            .cloned()
+            .record_addresses()
+            .into_iter()
             .collect();
 
         if !all_records.is_empty() {
@@ -0,0 +1,147 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    cmd::{LocalSwarmCmd, NetworkSwarmCmd},
+    error::{NetworkError, Result},
+    target_arch::{spawn, Instant},
+};
+use std::fmt::Debug;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// The number of cmds each channel into the `SwarmDriver` holds at most.
+pub(crate) const SWARM_CMD_CHANNEL_SIZE: usize = 10_000;
+
+/// The channel is saturated once at most this percentage of it is free, the low priority cmds being shed then.
+const SATURATION_FREE_PERCENT: usize = 10;
+
+pub type CmdSender<T> = mpsc::Sender<QueuedCmd<T>>;
+pub type CmdReceiver<T> = mpsc::Receiver<QueuedCmd<T>>;
+
+/// A cmd queued for the `SwarmDriver`, along with when it was, to measure how long it waited.
+#[derive(Debug)]
+pub struct QueuedCmd<T> {
+    pub(crate) cmd: T,
+    pub(crate) queued_at: Instant,
+}
+
+pub(crate) fn cmd_channel<T>() -> (CmdSender<T>, CmdReceiver<T>) {
+    mpsc::channel(SWARM_CMD_CHANNEL_SIZE)
+}
+
+/// The cmds that can be dropped when the `SwarmDriver` falls behind, as they are periodic or best effort.
+pub(crate) trait SwarmCmdPriority: Debug {
+    fn is_low_priority(&self) -> bool;
+}
+
+impl SwarmCmdPriority for LocalSwarmCmd {
+    fn is_low_priority(&self) -> bool {
+        matches!(
+            self,
+            LocalSwarmCmd::AddNetworkDensitySample { .. }
+                | LocalSwarmCmd::QuoteVerification { .. }
+                | LocalSwarmCmd::TriggerIntervalReplication
+                | LocalSwarmCmd::TriggerIrrelevantRecordCleanup
+        )
+    }
+}
+
+impl SwarmCmdPriority for NetworkSwarmCmd {
+    fn is_low_priority(&self) -> bool {
+        matches!(self, NetworkSwarmCmd::Prewarm { .. })
+    }
+}
+
+/// The number of cmds waiting in the channel.
+pub(crate) fn queue_depth<T>(sender: &CmdSender<T>) -> usize {
+    sender.max_capacity() - sender.capacity()
+}
+
+fn is_saturated<T>(sender: &CmdSender<T>) -> bool {
+    sender.capacity() * 100 <= sender.max_capacity() * SATURATION_FREE_PERCENT
+}
+
+/// Send the cmd, waiting for room in the channel, for the caller to slow down to the pace of the `SwarmDriver`.
+///
+/// Low priority cmds are rejected with `NetworkError::SwarmCmdChannelSaturated` when the channel is saturated.
+pub(crate) async fn send_cmd<T: SwarmCmdPriority>(sender: &CmdSender<T>, cmd: T) -> Result<()> {
+    if cmd.is_low_priority() && is_saturated(sender) {
+        warn!("SwarmCmd channel is saturated, rejecting the low priority cmd: {cmd:?}");
+        return Err(NetworkError::SwarmCmdChannelSaturated);
+    }
+    sender
+        .send(QueuedCmd {
+            cmd,
+            queued_at: Instant::now(),
+        })
+        .await
+        .map_err(|_| NetworkError::InternalMsgChannelDropped)
+}
+
+/// Queue the cmd without waiting, for the callers that can't.
+///
+/// Low priority cmds are dropped when the channel is saturated. The others wait for room off thread when it's full.
+pub(crate) fn queue_cmd<T: SwarmCmdPriority + Send + 'static>(sender: CmdSender<T>, cmd: T) {
+    if cmd.is_low_priority() && is_saturated(&sender) {
+        warn!("SwarmCmd channel is saturated, dropping the low priority cmd: {cmd:?}");
+        return;
+    }
+    let queued = QueuedCmd {
+        cmd,
+        queued_at: Instant::now(),
+    };
+    match sender.try_send(queued) {
+        Ok(()) => {}
+        Err(TrySendError::Full(queued)) => {
+            error!(
+                "SwarmCmd channel is full. Await capacity to send: {:?}",
+                queued.cmd
+            );
+            let _handle = spawn(async move {
+                if let Err(error) = sender.send(queued).await {
+                    error!("Failed to send SwarmCmd: {error}");
+                }
+            });
+        }
+        Err(TrySendError::Closed(queued)) => {
+            error!(
+                "Failed to send SwarmCmd, the channel is closed: {:?}",
+                queued.cmd
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn low_priority_cmds_are_shed_when_the_channel_is_saturated() {
+        let (sender, mut receiver) = mpsc::channel(10);
+        for _ in 0..9 {
+            queue_cmd(sender.clone(), LocalSwarmCmd::StopAcceptingRecords);
+        }
+        assert_eq!(queue_depth(&sender), 9);
+
+        queue_cmd(sender.clone(), LocalSwarmCmd::TriggerIntervalReplication);
+        assert!(matches!(
+            send_cmd(&sender, LocalSwarmCmd::TriggerIrrelevantRecordCleanup).await,
+            Err(NetworkError::SwarmCmdChannelSaturated)
+        ));
+        assert_eq!(queue_depth(&sender), 9);
+
+        assert!(send_cmd(&sender, LocalSwarmCmd::StopAcceptingRecords)
+            .await
+            .is_ok());
+        assert_eq!(queue_depth(&sender), 10);
+
+        let _ = receiver.recv().await;
+        assert_eq!(queue_depth(&sender), 9);
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_protocol::messages::{Request, Response};
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::{request_response, StreamProtocol};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+
+/// The suffix of the req/response protocol whose messages are compressed with LZ4.
+const COMPRESSED_PROTOCOL_SUFFIX: &str = "/lz4";
+
+/// The max size of a request, compressed or not. Same as the libp2p CBOR codec.
+const REQUEST_SIZE_MAXIMUM: usize = 1024 * 1024;
+/// The max size of a response, compressed or not. Same as the libp2p CBOR codec.
+const RESPONSE_SIZE_MAXIMUM: usize = 10 * 1024 * 1024;
+
+/// The req/response protocols, the compressed one first for the peers supporting it to negotiate it.
+/// The uncompressed one is still offered for the peers running an older version.
+pub(crate) fn req_res_protocols(version_str: &str) -> [StreamProtocol; 2] {
+    let compressed = format!("{version_str}{COMPRESSED_PROTOCOL_SUFFIX}");
+    [
+        StreamProtocol::try_from_owned(compressed).expect("StreamProtocol should start with a /"),
+        StreamProtocol::try_from_owned(version_str.to_string())
+            .expect("StreamProtocol should start with a /"),
+    ]
+}
+
+/// CBOR encoded messages, compressed with LZ4 when the compressed protocol was negotiated.
+///
+/// The replication and register messages are highly compressible, and LZ4 is fast enough not to
+/// be a bottleneck. It's pure Rust too, so it builds for wasm.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RequestResponseCodec;
+
+#[async_trait]
+impl request_response::Codec for RequestResponseCodec {
+    type Protocol = StreamProtocol;
+    type Request = Request;
+    type Response = Response;
+
+    async fn read_request<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_msg(protocol, io, REQUEST_SIZE_MAXIMUM).await
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+    ) -> io::Result<Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_msg(protocol, io, RESPONSE_SIZE_MAXIMUM).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        request: Request,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_msg(protocol, io, &request).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        protocol: &StreamProtocol,
+        io: &mut T,
+        response: Response,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_msg(protocol, io, &response).await
+    }
+}
+
+fn is_compressed(protocol: &StreamProtocol) -> bool {
+    protocol.as_ref().ends_with(COMPRESSED_PROTOCOL_SUFFIX)
+}
+
+fn invalid_data(err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+async fn read_msg<M, T>(protocol: &StreamProtocol, io: &mut T, max_size: usize) -> io::Result<M>
+where
+    M: DeserializeOwned,
+    T: AsyncRead + Unpin + Send,
+{
+    let mut bytes = Vec::new();
+    let _ = io.take(max_size as u64).read_to_end(&mut bytes).await?;
+    if is_compressed(protocol) {
+        bytes = decompress(&bytes, max_size)?;
+    }
+    cbor4ii::serde::from_slice(&bytes).map_err(invalid_data)
+}
+
+async fn write_msg<M, T>(protocol: &StreamProtocol, io: &mut T, msg: &M) -> io::Result<()>
+where
+    M: Serialize,
+    T: AsyncWrite + Unpin + Send,
+{
+    let mut bytes = cbor4ii::serde::to_vec(Vec::new(), msg).map_err(io::Error::other)?;
+    if is_compressed(protocol) {
+        bytes = lz4_flex::compress_prepend_size(&bytes);
+    }
+    io.write_all(&bytes).await
+}
+
+/// Decompress the bytes, as long as they don't claim to decompress to more than the max size.
+fn decompress(bytes: &[u8], max_size: usize) -> io::Result<Vec<u8>> {
+    let (size, _) = lz4_flex::block::uncompressed_size(bytes).map_err(invalid_data)?;
+    if size > max_size {
+        return Err(invalid_data(format!(
+            "Message decompresses to {size} bytes, more than the max of {max_size} bytes"
+        )));
+    }
+    lz4_flex::decompress_size_prepended(bytes).map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::{messages::Query, NetworkAddress};
+    use futures::io::Cursor;
+    use libp2p::request_response::Codec;
+
+    #[tokio::test]
+    async fn messages_are_compressed_on_the_compressed_protocol_only() -> eyre::Result<()> {
+        let [compressed, plain] = req_res_protocols("/ant/0.1/1");
+        assert_eq!(compressed.as_ref(), "/ant/0.1/1/lz4");
+        let key = NetworkAddress::from_record_key(&libp2p::kad::RecordKey::new(&[7u8; 32]));
+        let request = Request::Query(Query::GetRegisterRecord {
+            requester: key.clone(),
+            key,
+        });
+
+        let mut sizes = vec![];
+        for protocol in [&compressed, &plain] {
+            let mut io = Cursor::new(Vec::new());
+            RequestResponseCodec
+                .write_request(protocol, &mut io, request.clone())
+                .await?;
+            sizes.push(io.get_ref().len());
+
+            io.set_position(0);
+            let read = RequestResponseCodec.read_request(protocol, &mut io).await?;
+            assert_eq!(read, request);
+        }
+        assert!(sizes[0] < sizes[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn decompression_is_bounded() {
+        let bytes = lz4_flex::compress_prepend_size(&[0u8; 4096]);
+        assert!(decompress(&bytes, 4096).is_ok());
+        assert!(decompress(&bytes, 1024).is_err());
+    }
+}
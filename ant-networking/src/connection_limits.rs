@@ -0,0 +1,253 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::target_arch::{Duration, Instant};
+use libp2p::{core::ConnectedPoint, swarm::ConnectionId, PeerId};
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+};
+
+/// How long after its last request or response a peer is considered active, its connections protected from
+/// eviction.
+const ACTIVE_PEER_WINDOW: Duration = Duration::from_secs(30);
+
+/// Limits on the number of live connections, `None` being unlimited.
+///
+/// Once a new connection takes a limit over, the connections counting toward it are evicted, the connections to
+/// the farthest peers first, until back within the limit. The connections to the close group and to the peers
+/// recently talked to are never evicted, which lets a limit be exceeded by them alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// The maximum number of connections overall.
+    pub max_connections: Option<usize>,
+    /// The maximum number of connections to a single IP, e.g. the peers of a same host or behind a same NAT.
+    pub max_connections_per_ip: Option<usize>,
+    /// The maximum number of connections dialed by the peers.
+    pub max_inbound: Option<usize>,
+    /// The maximum number of connections we dialed.
+    pub max_outbound: Option<usize>,
+}
+
+impl ConnectionLimits {
+    fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TrackedConnection {
+    peer: PeerId,
+    ip: Option<IpAddr>,
+    inbound: bool,
+}
+
+/// The connections a limit counts.
+#[derive(Debug, Clone, Copy)]
+enum Scope {
+    All,
+    Ip(IpAddr),
+    Direction { inbound: bool },
+    None,
+}
+
+impl Scope {
+    fn counts(&self, conn: &TrackedConnection) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Ip(ip) => conn.ip == Some(*ip),
+            Scope::Direction { inbound } => conn.inbound == *inbound,
+            Scope::None => false,
+        }
+    }
+}
+
+/// Keeps track of the live connections against the [`ConnectionLimits`], choosing the ones to evict.
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionLimiter {
+    limits: ConnectionLimits,
+    connections: HashMap<ConnectionId, TrackedConnection>,
+    last_active: HashMap<PeerId, Instant>,
+}
+
+impl ConnectionLimiter {
+    pub(crate) fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    /// Track the new connection, returning the connections to close to be back within the limits, which may include
+    /// the new one.
+    ///
+    /// `is_protected` tells the peers to keep the connections to regardless, besides the active ones, and `distance`
+    /// orders the peers by how far from us they are.
+    pub(crate) fn on_established<D: Ord>(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        endpoint: &ConnectedPoint,
+        is_protected: impl Fn(&PeerId) -> bool,
+        distance: impl Fn(&PeerId) -> D,
+    ) -> Vec<ConnectionId> {
+        if self.limits.is_unlimited() {
+            return vec![];
+        }
+        let new = TrackedConnection {
+            peer,
+            ip: crate::multiaddr_get_ip(endpoint.get_remote_address()),
+            inbound: endpoint.is_listener(),
+        };
+        let _ = self.connections.insert(connection_id, new.clone());
+
+        let now = Instant::now();
+        let is_protected = |peer: &PeerId| {
+            is_protected(peer)
+                || self.last_active.get(peer).is_some_and(|last_active| {
+                    now.duration_since(*last_active) < ACTIVE_PEER_WINDOW
+                })
+        };
+
+        let mut evicted = HashSet::new();
+        let direction = Scope::Direction {
+            inbound: new.inbound,
+        };
+        let limits = [
+            (self.limits.max_connections, Scope::All),
+            (
+                self.limits.max_connections_per_ip,
+                new.ip.map_or(Scope::None, Scope::Ip),
+            ),
+            (
+                if new.inbound {
+                    self.limits.max_inbound
+                } else {
+                    self.limits.max_outbound
+                },
+                direction,
+            ),
+        ];
+        for (limit, scope) in limits {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let mut candidates: Vec<_> = self
+                .connections
+                .iter()
+                .filter(|(id, conn)| !evicted.contains(*id) && scope.counts(conn))
+                .collect();
+            let excess = candidates.len().saturating_sub(limit);
+            if excess == 0 {
+                continue;
+            }
+            candidates.retain(|(_, conn)| !is_protected(&conn.peer));
+            // The farthest peers first.
+            candidates.sort_by_key(|(_, conn)| std::cmp::Reverse(distance(&conn.peer)));
+            evicted.extend(candidates.into_iter().take(excess).map(|(id, _)| *id));
+        }
+
+        evicted.into_iter().collect()
+    }
+
+    pub(crate) fn is_limited(&self) -> bool {
+        !self.limits.is_unlimited()
+    }
+
+    pub(crate) fn on_closed(&mut self, connection_id: &ConnectionId) {
+        let Some(closed) = self.connections.remove(connection_id) else {
+            return;
+        };
+        if !self
+            .connections
+            .values()
+            .any(|conn| conn.peer == closed.peer)
+        {
+            let _ = self.last_active.remove(&closed.peer);
+        }
+    }
+
+    /// Record a request or response exchanged with the peer, protecting its connections for a while.
+    pub(crate) fn on_peer_activity(&mut self, peer: &PeerId) {
+        if self.limits.is_unlimited() {
+            return;
+        }
+        let _ = self.last_active.insert(*peer, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::{core::Endpoint, Multiaddr};
+
+    fn listener(ip: &str) -> ConnectedPoint {
+        ConnectedPoint::Listener {
+            local_addr: Multiaddr::empty(),
+            send_back_addr: format!("/ip4/{ip}/udp/1200/quic-v1")
+                .parse()
+                .expect("valid multiaddr"),
+        }
+    }
+
+    fn dialer(ip: &str) -> ConnectedPoint {
+        ConnectedPoint::Dialer {
+            address: format!("/ip4/{ip}/udp/1200/quic-v1")
+                .parse()
+                .expect("valid multiaddr"),
+            role_override: Endpoint::Dialer,
+            port_use: Default::default(),
+        }
+    }
+
+    #[test]
+    fn the_farthest_unprotected_connections_are_evicted() {
+        let mut limiter = ConnectionLimiter::new(ConnectionLimits {
+            max_connections: Some(3),
+            max_connections_per_ip: Some(1),
+            max_outbound: Some(1),
+            ..Default::default()
+        });
+        // Peers numbered by their distance to us, the first one being in our close group.
+        let peers: Vec<_> = (0..6).map(|_| PeerId::random()).collect();
+        let distance = |peer: &PeerId| peers.iter().position(|p| p == peer);
+        let is_protected = |peer: &PeerId| *peer == peers[0];
+        let mut next_id = 0;
+        let mut establish = |limiter: &mut ConnectionLimiter, peer: usize, endpoint| {
+            next_id += 1;
+            let id = ConnectionId::new_unchecked(next_id);
+            let evicted =
+                limiter.on_established(id, peers[peer], &endpoint, is_protected, distance);
+            for id in &evicted {
+                limiter.on_closed(id);
+            }
+            (id, evicted)
+        };
+
+        let _ = establish(&mut limiter, 0, listener("10.0.0.1"));
+        let (far, _) = establish(&mut limiter, 4, listener("10.0.0.2"));
+        // Over the per-IP limit, only the connections from that IP are candidates.
+        let (_, evicted) = establish(&mut limiter, 1, dialer("10.0.0.2"));
+        assert_eq!(evicted, vec![far]);
+        limiter.on_peer_activity(&peers[1]);
+
+        // Over the global limit, a closer peer takes the place of the farthest one, the active and close group
+        // peers being kept.
+        let (far, evicted) = establish(&mut limiter, 5, listener("10.0.0.5"));
+        assert!(evicted.is_empty());
+        let (_, evicted) = establish(&mut limiter, 2, listener("10.0.0.3"));
+        assert_eq!(evicted, vec![far]);
+        let (new, evicted) = establish(&mut limiter, 4, listener("10.0.0.4"));
+        assert_eq!(evicted, vec![new]);
+
+        // The protected peers alone can exceed the limits.
+        limiter.on_peer_activity(&peers[2]);
+        let (_, evicted) = establish(&mut limiter, 0, dialer("10.0.0.6"));
+        assert!(evicted.is_empty());
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::target_arch::Instant;
+use libp2p::{multiaddr::Protocol, swarm::ConnectionId, Multiaddr, PeerId};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+/// The delay between the starts of the dials to the addresses of a peer, see [`DialStrategy::start_race`].
+pub(crate) const DIAL_STAGGER: Duration = Duration::from_millis(250);
+
+/// A race not won by then is given up on, its dials are left to fail or connect on their own.
+const DIAL_RACE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Max number of addresses the dial outcomes are kept for.
+const MAX_ADDR_STATS: usize = 2000;
+
+/// The IP version of an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    /// The family of the first IP of the address, i.e. of the relay for a relayed address.
+    pub(crate) fn of(addr: &Multiaddr) -> Option<Self> {
+        addr.iter().find_map(|protocol| match protocol {
+            Protocol::Ip4(_) => Some(Self::V4),
+            Protocol::Ip6(_) => Some(Self::V6),
+            _ => None,
+        })
+    }
+}
+
+/// The outcomes of the dials to an address.
+#[derive(Debug, Default, Clone, Copy)]
+struct AddrStats {
+    successes: u32,
+    failures: u32,
+}
+
+impl AddrStats {
+    fn score(&self) -> i64 {
+        i64::from(self.successes) - i64::from(self.failures)
+    }
+}
+
+/// How costly it is to talk through the address: direct QUIC, then WebSocket, then relayed.
+fn transport_rank(addr: &Multiaddr) -> u8 {
+    let mut rank = 0;
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::P2pCircuit => return 2,
+            Protocol::Ws(_) | Protocol::Wss(_) => rank = 1,
+            _ => {}
+        }
+    }
+    rank
+}
+
+/// The addresses of a peer dialed one after the other, until one of them connects.
+#[derive(Debug)]
+struct DialRace {
+    /// The addresses left to dial, the best first
+    queued: VecDeque<Multiaddr>,
+    /// When to dial the next queued address, if none connected or failed by then
+    next_dial_at: Instant,
+    /// The ongoing dials
+    dials: HashMap<ConnectionId, Multiaddr>,
+    won: bool,
+    started: Instant,
+}
+
+/// Picks the peer addresses to dial, out of the IP families the node can reach.
+///
+/// Until the node listens on any address, e.g. for a client, all the families are dialed. Then
+/// only the families it listens on are, the one with the most established outbound connections first.
+/// Within a family, the direct addresses come before the WebSocket and relayed ones, and the addresses
+/// connected to the most, net of their failures, first.
+///
+/// The addresses of a peer are raced, happy eyeballs style: each one is dialed [`DIAL_STAGGER`] after
+/// the previous one, or right away if the previous one failed, and the first connection is kept.
+#[derive(Debug, Default)]
+pub(crate) struct DialStrategy {
+    /// The families of our listen addresses
+    listening: HashSet<IpFamily>,
+    /// The outbound connections established per family
+    established: HashMap<IpFamily, u64>,
+    /// The outcomes of the dials per address
+    addr_stats: HashMap<Multiaddr, AddrStats>,
+    /// The ongoing races, per peer
+    races: HashMap<PeerId, DialRace>,
+}
+
+impl DialStrategy {
+    pub(crate) fn on_new_listen_addr(&mut self, addr: &Multiaddr) {
+        if let Some(family) = IpFamily::of(addr) {
+            let _ = self.listening.insert(family);
+        }
+    }
+
+    /// Records the connection, returning whether it lost the race of the dials to the peer, to be closed.
+    pub(crate) fn on_outbound_connection(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        remote_addr: &Multiaddr,
+    ) -> bool {
+        if let Some(family) = IpFamily::of(remote_addr) {
+            *self.established.entry(family).or_default() += 1;
+        }
+        self.addr_stats_mut(remote_addr).successes += 1;
+
+        let Some(race) = self.races.get_mut(&peer_id) else {
+            return false;
+        };
+        if race.dials.remove(&connection_id).is_none() {
+            return false;
+        }
+        let lost = race.won;
+        race.won = true;
+        race.queued.clear();
+        if race.dials.is_empty() {
+            let _ = self.races.remove(&peer_id);
+        }
+        lost
+    }
+
+    /// Records the failure of a dial, returning the next address of the race to dial right away.
+    pub(crate) fn on_dial_failure(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        failed_addrs: &[Multiaddr],
+    ) -> Option<Multiaddr> {
+        for addr in failed_addrs {
+            self.addr_stats_mut(addr).failures += 1;
+        }
+
+        let race = self.races.get_mut(&peer_id)?;
+        let _ = race.dials.remove(&connection_id)?;
+        let next = if race.won {
+            None
+        } else {
+            race.next_dial_at = Instant::now() + DIAL_STAGGER;
+            race.queued.pop_front()
+        };
+        if next.is_none() && race.dials.is_empty() {
+            let _ = self.races.remove(&peer_id);
+        }
+        next
+    }
+
+    /// Starts racing the addresses of the peer, returning the one to dial first, unless they're raced already or
+    /// none can be reached.
+    pub(crate) fn start_race(
+        &mut self,
+        peer_id: PeerId,
+        addrs: impl IntoIterator<Item = Multiaddr>,
+    ) -> Option<Multiaddr> {
+        if self.races.contains_key(&peer_id) {
+            return None;
+        }
+        let mut queued: VecDeque<_> = self.addrs_to_dial(addrs).into();
+        let first = queued.pop_front()?;
+        let now = Instant::now();
+        let _ = self.races.insert(
+            peer_id,
+            DialRace {
+                queued,
+                next_dial_at: now + DIAL_STAGGER,
+                dials: HashMap::new(),
+                won: false,
+                started: now,
+            },
+        );
+        Some(first)
+    }
+
+    /// Tracks the dial to the address of the race.
+    pub(crate) fn on_race_dial(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        addr: Multiaddr,
+    ) {
+        if let Some(race) = self.races.get_mut(&peer_id) {
+            let _ = race.dials.insert(connection_id, addr);
+        }
+    }
+
+    /// Gives up on the race, e.g. as the peer couldn't be dialed at all.
+    pub(crate) fn abandon_race(&mut self, peer_id: &PeerId) {
+        let _ = self.races.remove(peer_id);
+    }
+
+    /// The next address of each race whose stagger elapsed without a connection, to be dialed now.
+    pub(crate) fn due_dials(&mut self, now: Instant) -> Vec<(PeerId, Multiaddr)> {
+        self.races.retain(|peer_id, race| {
+            let keep = now.saturating_duration_since(race.started) < DIAL_RACE_TIMEOUT
+                && !(race.won && race.dials.is_empty());
+            if !keep {
+                debug!("Giving up on the dial race to {peer_id:?}");
+            }
+            keep
+        });
+
+        let mut due = vec![];
+        for (peer_id, race) in self.races.iter_mut() {
+            if race.won || race.next_dial_at > now {
+                continue;
+            }
+            if let Some(addr) = race.queued.pop_front() {
+                race.next_dial_at = now + DIAL_STAGGER;
+                due.push((*peer_id, addr));
+            }
+        }
+        due
+    }
+
+    fn addr_stats_mut(&mut self, addr: &Multiaddr) -> &mut AddrStats {
+        if self.addr_stats.len() >= MAX_ADDR_STATS && !self.addr_stats.contains_key(addr) {
+            // Forget an arbitrary address, most are dialed once anyway.
+            if let Some(forgotten) = self.addr_stats.keys().next().cloned() {
+                let _ = self.addr_stats.remove(&forgotten);
+            }
+        }
+        self.addr_stats.entry(addr.clone()).or_default()
+    }
+
+    /// Whether the node can reach the address. The addresses without an IP, e.g. DNS ones, might
+    /// resolve to any family, so they are dialed.
+    pub(crate) fn can_dial(&self, addr: &Multiaddr) -> bool {
+        match IpFamily::of(addr) {
+            Some(family) => self.listening.is_empty() || self.listening.contains(&family),
+            None => true,
+        }
+    }
+
+    /// The addresses the node can reach, the best ones first.
+    pub(crate) fn addrs_to_dial(
+        &self,
+        addrs: impl IntoIterator<Item = Multiaddr>,
+    ) -> Vec<Multiaddr> {
+        let preferred = self.preferred_family();
+        let mut addrs: Vec<_> = addrs
+            .into_iter()
+            .filter(|addr| self.can_dial(addr))
+            .collect();
+        // Stable sort, the order of the addresses alike is kept.
+        addrs.sort_by_key(|addr| {
+            (
+                preferred.is_none() || IpFamily::of(addr) != preferred,
+                transport_rank(addr),
+                -self.addr_stats.get(addr).map_or(0, AddrStats::score),
+            )
+        });
+        addrs
+    }
+
+    /// The family with the most established outbound connections, if any.
+    fn preferred_family(&self) -> Option<IpFamily> {
+        self.established
+            .iter()
+            .filter(|(family, _count)| self.listening.is_empty() || self.listening.contains(family))
+            .max_by_key(|(_family, count)| **count)
+            .map(|(family, _count)| *family)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_reachable_families_are_dialed_the_preferred_one_first() -> eyre::Result<()> {
+        let v4: Multiaddr = "/ip4/203.0.113.7/udp/12000/quic-v1".parse()?;
+        let v6: Multiaddr = "/ip6/2001:db8::7/udp/12000/quic-v1".parse()?;
+        let dns: Multiaddr = "/dns4/node.example.com/tcp/443/wss".parse()?;
+        let mut strategy = DialStrategy::default();
+
+        // Not listening yet, everything is dialed as is
+        assert_eq!(
+            strategy.addrs_to_dial([v4.clone(), v6.clone()]),
+            vec![v4.clone(), v6.clone()]
+        );
+
+        strategy.on_new_listen_addr(&"/ip6/::/udp/12000/quic-v1".parse()?);
+        assert!(!strategy.can_dial(&v4));
+        assert!(strategy.can_dial(&dns));
+        assert_eq!(
+            strategy.addrs_to_dial([v4.clone(), v6.clone()]),
+            vec![v6.clone()]
+        );
+
+        // Dual-stack, the family peers were reached with first
+        strategy.on_new_listen_addr(&"/ip4/0.0.0.0/udp/12000/quic-v1".parse()?);
+        let _ =
+            strategy.on_outbound_connection(PeerId::random(), ConnectionId::new_unchecked(0), &v6);
+        assert_eq!(
+            strategy.addrs_to_dial([v4.clone(), dns.clone(), v6.clone()]),
+            vec![v6, v4, dns]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn addresses_are_raced_and_the_first_connection_is_kept() -> eyre::Result<()> {
+        let quic: Multiaddr = "/ip4/203.0.113.7/udp/12000/quic-v1".parse()?;
+        let other_quic: Multiaddr = "/ip4/203.0.113.8/udp/12000/quic-v1".parse()?;
+        let ws: Multiaddr = "/ip4/203.0.113.7/tcp/443/wss".parse()?;
+        let relayed: Multiaddr =
+            "/ip4/198.51.100.1/udp/12000/quic-v1/p2p/12D3KooWRBhwfeP2Y4TCx1SM6s9rUoHhR5STiGwxBhgFRcw3UERE/p2p-circuit"
+                .parse()?;
+        let peer_id = PeerId::random();
+        let mut strategy = DialStrategy::default();
+
+        // Direct addresses first, then the WebSocket and relayed ones
+        let first = strategy.start_race(
+            peer_id,
+            [
+                relayed.clone(),
+                ws.clone(),
+                quic.clone(),
+                other_quic.clone(),
+            ],
+        );
+        assert_eq!(first, Some(quic.clone()));
+        assert_eq!(strategy.start_race(peer_id, [quic.clone()]), None);
+        strategy.on_race_dial(peer_id, ConnectionId::new_unchecked(1), quic.clone());
+
+        // The next address is dialed once the stagger elapsed, or right away on a failure
+        assert!(strategy.due_dials(Instant::now()).is_empty());
+        assert_eq!(
+            strategy.due_dials(Instant::now() + DIAL_STAGGER),
+            vec![(peer_id, other_quic.clone())]
+        );
+        strategy.on_race_dial(peer_id, ConnectionId::new_unchecked(2), other_quic.clone());
+        assert_eq!(
+            strategy.on_dial_failure(
+                peer_id,
+                ConnectionId::new_unchecked(1),
+                std::slice::from_ref(&quic)
+            ),
+            Some(ws.clone())
+        );
+        strategy.on_race_dial(peer_id, ConnectionId::new_unchecked(3), ws.clone());
+
+        // The first connection wins, the later ones are to be closed
+        assert!(!strategy.on_outbound_connection(peer_id, ConnectionId::new_unchecked(3), &ws));
+        assert!(strategy
+            .due_dials(Instant::now() + DIAL_STAGGER * 4)
+            .is_empty());
+        assert!(strategy.on_outbound_connection(
+            peer_id,
+            ConnectionId::new_unchecked(2),
+            &other_quic
+        ));
+        assert!(strategy.races.is_empty());
+
+        // The addresses that connected are preferred over the ones that failed
+        assert_eq!(
+            strategy.addrs_to_dial([quic.clone(), other_quic.clone()]),
+            vec![other_quic, quic]
+        );
+        Ok(())
+    }
+}
@@ -6,50 +6,80 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosConfig;
+#[cfg(feature = "open-metrics")]
+use crate::cmd_channel::queue_depth;
+#[cfg(any(test, feature = "simulator"))]
+use crate::simulator::SimulatedLinks;
 use crate::{
+    bandwidth::BandwidthTracker,
     bootstrap::{ContinuousNetworkDiscover, NETWORK_DISCOVER_INTERVAL},
     circular_vec::CircularVec,
     cmd::{LocalSwarmCmd, NetworkSwarmCmd},
+    cmd_channel::{cmd_channel, queue_cmd, CmdReceiver, CmdSender, QueuedCmd},
+    codec::{req_res_protocols, RequestResponseCodec},
+    connection_limits::{ConnectionLimiter, ConnectionLimits},
+    dial_strategy::{DialStrategy, DIAL_STAGGER},
     error::{NetworkError, Result},
     event::{NetworkEvent, NodeEvent},
     external_address::ExternalAddressManager,
     fifo_register::FifoRegister,
+    kad_config::KademliaConfig,
     log_markers::Marker,
     multiaddr_pop_p2p,
     network_discovery::NetworkDiscovery,
-    record_store::{ClientRecordStore, NodeRecordStore, NodeRecordStoreConfig},
+    peer_access::PeerAccessList,
+    peer_scoring::{PeerReputation, PeerScores, MAX_PERSISTED_GOOD_PEERS},
+    prewarm::{PrewarmBatchId, RoutingTablePrewarm},
+    rate_limiter::{RateLimitConfig, RequestRateLimiter},
+    reachability::{autonat_behaviour, Reachability},
+    record_store::{
+        ClientRecordStore, EvictionPolicy, NodeRecordStore, NodeRecordStoreConfig, RecordCategory,
+        RecordEncryption,
+    },
     record_store_api::UnifiedRecordStore,
+    record_store_backend::RecordStoreBackendKind,
     relay_manager::RelayManager,
+    relay_server::RelayServerConfig,
     replication_fetcher::ReplicationFetcher,
+    routing_health::ROUTING_TABLE_HEALTH_CHECK_INTERVAL,
+    sybil_guard::{SybilGuard, SybilGuardConfig},
     target_arch::Interval,
     target_arch::{interval, spawn, Instant},
-    transport, GetRecordError, Network, NodeIssue, CLOSE_GROUP_SIZE,
+    transport,
+    transport::WebSocketConfig,
+    GetRecordError, Network, CLOSE_GROUP_SIZE,
 };
 #[cfg(feature = "open-metrics")]
 use crate::{
-    metrics::service::run_metrics_server, metrics::NetworkMetricsRecorder, MetricsRegistries,
+    bandwidth::{BandwidthDirection, BandwidthLabels},
+    metrics::service::run_metrics_server,
+    metrics::{NetworkMetricsRecorder, SwarmCmdChannel},
+    replication_fetcher::{FetchPriority, FetchPriorityLabels},
+    MetricsRegistries,
 };
 use ant_bootstrap::BootstrapCacheStore;
 use ant_evm::{PaymentQuote, U256};
 use ant_protocol::{
     convert_distance_to_u256,
-    messages::{ChunkProof, Nonce, Request, Response},
+    messages::{ChunkProof, Nonce, Response},
     storage::{try_deserialize_record, RetryStrategy},
     version::{
-        get_network_id, IDENTIFY_CLIENT_VERSION_STR, IDENTIFY_NODE_VERSION_STR,
-        IDENTIFY_PROTOCOL_STR, REQ_RESPONSE_VERSION_STR,
+        get_network_id, NodeCapabilities, ProtocolVersion, IDENTIFY_CLIENT_VERSION_STR,
+        IDENTIFY_NODE_VERSION_STR, IDENTIFY_PROTOCOL_STR, REQ_RESPONSE_VERSION_STR,
     },
     NetworkAddress, PrettyPrintKBucketKey, PrettyPrintRecordKey,
 };
 use ant_registers::SignedRegister;
 use futures::future::Either;
 use futures::StreamExt;
-#[cfg(feature = "local")]
+use libp2p::core::muxing::StreamMuxerBox;
+#[cfg(not(target_arch = "wasm32"))]
 use libp2p::mdns;
-use libp2p::{core::muxing::StreamMuxerBox, relay};
 use libp2p::{
     identity::Keypair,
-    kad::{self, QueryId, Quorum, Record, RecordKey, K_VALUE},
+    kad::{self, QueryId, QueryStats, Quorum, Record, RecordKey, K_VALUE},
     multiaddr::Protocol,
     request_response::{self, Config as RequestResponseConfig, OutboundRequestId, ProtocolSupport},
     swarm::{
@@ -68,7 +98,7 @@ use std::{
     fmt::Debug,
     fs,
     io::{Read, Write},
-    net::{IpAddr, SocketAddr},
+    net::{IpAddr, Ipv6Addr, SocketAddr},
     num::NonZeroUsize,
     path::PathBuf,
 };
@@ -84,6 +114,9 @@ pub(crate) const CLOSET_RECORD_CHECK_INTERVAL: Duration = Duration::from_secs(15
 /// Interval over which we query relay manager to check if we can make any more reservations.
 pub(crate) const RELAY_MANAGER_RESERVATION_INTERVAL: Duration = Duration::from_secs(30);
 
+/// Interval over which the peer reputation is persisted, so that it survives a crash.
+const PEER_REPUTATION_SAVE_INTERVAL: Duration = Duration::from_secs(600);
+
 const KAD_STREAM_PROTOCOL_ID: StreamProtocol = StreamProtocol::new("/autonomi/kad/1.0.0");
 
 /// The ways in which the Get Closest queries are used.
@@ -93,24 +126,36 @@ pub(crate) enum PendingGetClosestType {
     NetworkDiscovery,
     /// These are queries made by a function at the upper layers and contains a channel to send the result back.
     FunctionCall(oneshot::Sender<Vec<PeerId>>),
+    /// The closest peers are to be the targets of a PUT, which reports back the peers that stored the record.
+    PutRecord {
+        record: Record,
+        quorum: Quorum,
+        sender: oneshot::Sender<Result<Vec<PeerId>>>,
+    },
+    /// These are queries made to warm up the routing table toward the close group of an address.
+    Prewarm(PrewarmBatchId),
 }
 type PendingGetClosest = HashMap<QueryId, (PendingGetClosestType, Vec<PeerId>)>;
 
 /// Using XorName to differentiate different record content under the same key.
-type GetRecordResultMap = HashMap<XorName, (Record, HashSet<PeerId>)>;
+pub(crate) type GetRecordResultMap = HashMap<XorName, (Record, HashSet<PeerId>)>;
 pub(crate) type PendingGetRecord = HashMap<
     QueryId,
     (
         RecordKey, // record we're fetching, to dedupe repeat requests
-        Vec<oneshot::Sender<std::result::Result<Record, GetRecordError>>>, // vec of senders waiting for this record
+        Vec<oneshot::Sender<std::result::Result<GetRecordOk, GetRecordError>>>, // vec of senders waiting for this record
         GetRecordResultMap,
         GetRecordCfg,
     ),
 >;
 
-/// 10 is the max number of issues per node we track to avoid mem leaks
-/// The boolean flag to indicate whether the node is considered as bad or not
-pub(crate) type BadNodes = BTreeMap<PeerId, (Vec<(NodeIssue, Instant)>, bool)>;
+pub(crate) type PendingPutRecordEntry = (
+    RecordKey,                            // record we're storing
+    Vec<PeerId>,                          // peers the record has been sent to
+    Quorum,                               // the write quorum, evaluated against the number of peers
+    oneshot::Sender<Result<Vec<PeerId>>>, // to be sent the peers that acknowledged the store
+);
+pub(crate) type PendingPutRecord = HashMap<QueryId, PendingPutRecordEntry>;
 
 /// What is the largest packet to send over the network.
 /// Records larger than this will be rejected.
@@ -126,16 +171,13 @@ const RESEND_IDENTIFY_INVERVAL: Duration = Duration::from_secs(3600);
 
 const NETWORKING_CHANNEL_SIZE: usize = 10_000;
 
-/// Time before a Kad query times out if no response is received
-const KAD_QUERY_TIMEOUT_S: Duration = Duration::from_secs(10);
-
 /// Interval to trigger native libp2p::kad bootstrap.
 /// This is the max time it should take. Minimum interval at any node will be half this
 const PERIODIC_KAD_BOOTSTRAP_INTERVAL_MAX_S: u64 = 21600;
 
 // Init during compilation, instead of runtime error that should never happen
 // Option<T>::expect will be stabilised as const in the future (https://github.com/rust-lang/rust/issues/67441)
-const REPLICATION_FACTOR: NonZeroUsize = match NonZeroUsize::new(CLOSE_GROUP_SIZE + 2) {
+pub(crate) const REPLICATION_FACTOR: NonZeroUsize = match NonZeroUsize::new(CLOSE_GROUP_SIZE + 2) {
     Some(v) => v,
     None => panic!("CLOSE_GROUP_SIZE should not be zero"),
 };
@@ -209,17 +251,86 @@ impl Debug for GetRecordCfg {
     }
 }
 
+/// The content hash of the copy that each responding peer returned for a record.
+pub type RecordProvenance = HashMap<PeerId, XorName>;
+
+/// Flattens a `GetRecordResultMap` into the content hash returned by each peer.
+pub(crate) fn record_provenance(result_map: &GetRecordResultMap) -> RecordProvenance {
+    result_map
+        .iter()
+        .flat_map(|(content_hash, (_, peers))| peers.iter().map(|peer| (*peer, *content_hash)))
+        .collect()
+}
+
+/// A record successfully fetched from the network
+#[derive(Clone)]
+pub struct GetRecordOk {
+    /// The record that satisfied the query.
+    pub record: Record,
+    /// Which peers returned which copy. Peers whose content hash differs from the returned
+    /// record served a stale or divergent copy.
+    pub provenance: RecordProvenance,
+    /// Whether the query stopped as soon as the quorum was reached, rather than after asking every peer it could.
+    /// Without it, peers not asked yet might hold copies too.
+    pub completed_early: bool,
+    /// Whether the record was merged from the copies of the peers, e.g. a split register, rather than served as is
+    /// by any of them. None of the copies is then stale, each holding a part of the merged record.
+    pub merged: bool,
+}
+
+impl GetRecordOk {
+    /// The peers that returned the same copy as the returned record.
+    pub fn holders(&self) -> HashSet<PeerId> {
+        let content_hash = XorName::from_content(&self.record.value);
+        self.provenance
+            .iter()
+            .filter(|(_, hash)| **hash == content_hash)
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// The number of peers agreeing on the returned record, at least the quorum asked for, unless the record was
+    /// merged from divergent copies.
+    pub fn quorum_achieved(&self) -> usize {
+        self.holders().len()
+    }
+
+    /// The peers that returned a copy different from the returned record, none for a merged record.
+    pub fn divergent_peers(&self) -> Vec<PeerId> {
+        if self.merged {
+            return vec![];
+        }
+        let content_hash = XorName::from_content(&self.record.value);
+        self.provenance
+            .iter()
+            .filter(|(_, hash)| **hash != content_hash)
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+}
+
+impl Debug for GetRecordOk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GetRecordOk")
+            .field("record_key", &PrettyPrintRecordKey::from(&self.record.key))
+            .field("provenance", &self.provenance)
+            .field("completed_early", &self.completed_early)
+            .field("merged", &self.merged)
+            .finish()
+    }
+}
+
 /// The various settings related to writing a record to the network.
 #[derive(Debug, Clone)]
 pub struct PutRecordCfg {
-    /// The quorum used by KAD PUT. KAD still sends out the request to all the peers set by the `replication_factor`, it
-    /// just makes sure that we get atleast `n` successful responses defined by the Quorum.
-    /// Our nodes currently send `Ok()` response for every KAD PUT. Thus this field does not do anything atm.
+    /// The write quorum, evaluated against the number of peers the record is sent to. The PUT fails with
+    /// `NetworkError::PutRecordQuorumFailed` if fewer peers acknowledged storing the record, right away if it is
+    /// sent to fewer peers than the quorum.
     pub put_quorum: Quorum,
     /// If enabled, the provided `RetryStrategy` is used to retry if a PUT attempt fails.
     pub retry_strategy: Option<RetryStrategy>,
-    /// Use the `kad::put_record_to` to PUT the record only to the specified peers. If this option is set to None, we
-    /// will be using `kad::put_record` which would PUT the record to all the closest members of the record.
+    /// Use the `kad::put_record_to` to PUT the record only to the specified peers. If this option is set to None, the
+    /// record is PUT to the `KademliaConfig::replication_factor` closest peers found by a GetClosestPeers query.
     pub use_put_record_to: Option<Vec<PeerId>>,
     /// Enables verification after writing. The VerificationKind is used to determine the method to use.
     pub verification: Option<(VerificationKind, GetRecordCfg)>,
@@ -254,14 +365,17 @@ pub(super) struct NodeBehaviour {
     pub(super) blocklist:
         libp2p::allow_block_list::Behaviour<libp2p::allow_block_list::BlockedPeers>,
     pub(super) identify: libp2p::identify::Behaviour,
-    #[cfg(feature = "local")]
-    pub(super) mdns: mdns::tokio::Behaviour,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(super) mdns: libp2p::swarm::behaviour::toggle::Toggle<mdns::tokio::Behaviour>,
     #[cfg(feature = "upnp")]
     pub(super) upnp: libp2p::swarm::behaviour::toggle::Toggle<libp2p::upnp::tokio::Behaviour>,
     pub(super) relay_client: libp2p::relay::client::Behaviour,
-    pub(super) relay_server: libp2p::relay::Behaviour,
+    pub(super) dcutr: libp2p::dcutr::Behaviour,
+    pub(super) relay_server: libp2p::swarm::behaviour::toggle::Toggle<libp2p::relay::Behaviour>,
+    pub(super) autonat: libp2p::swarm::behaviour::toggle::Toggle<libp2p::autonat::Behaviour>,
     pub(super) kademlia: kad::Behaviour<UnifiedRecordStore>,
-    pub(super) request_response: request_response::cbor::Behaviour<Request, Response>,
+    pub(super) request_response: request_response::Behaviour<RequestResponseCodec>,
+    pub(super) gossipsub: libp2p::gossipsub::Behaviour,
 }
 
 #[derive(Debug)]
@@ -271,14 +385,34 @@ pub struct NetworkBuilder {
     is_behind_home_network: bool,
     keypair: Keypair,
     listen_addr: Option<SocketAddr>,
+    listen_addr_v6: Option<Ipv6Addr>,
     local: bool,
     #[cfg(feature = "open-metrics")]
     metrics_registries: Option<MetricsRegistries>,
     #[cfg(feature = "open-metrics")]
     metrics_server_port: Option<u16>,
     request_timeout: Option<Duration>,
+    max_bytes_per_category: HashMap<RecordCategory, usize>,
+    eviction_policy: EvictionPolicy,
+    record_store_backend: RecordStoreBackendKind,
+    record_encryption: RecordEncryption,
+    peer_access_list: PeerAccessList,
+    protocol_compat: bool,
+    rate_limit_config: RateLimitConfig,
+    kademlia_config: KademliaConfig,
+    relay_server: Option<RelayServerConfig>,
+    websocket: Option<WebSocketConfig>,
+    auto_expected_holders: bool,
+    archive_mode: bool,
+    audit_log_max_bytes: Option<u64>,
+    connection_limits: ConnectionLimits,
+    sybil_guard: SybilGuardConfig,
+    warm_standby: bool,
     #[cfg(feature = "upnp")]
     upnp: bool,
+    /// The links and memory port of a simulated node, replacing the QUIC and WebSocket transports.
+    #[cfg(any(test, feature = "simulator"))]
+    simulated: Option<(SimulatedLinks, u64)>,
 }
 
 impl NetworkBuilder {
@@ -289,14 +423,33 @@ impl NetworkBuilder {
             is_behind_home_network: false,
             keypair,
             listen_addr: None,
+            listen_addr_v6: None,
             local,
             #[cfg(feature = "open-metrics")]
             metrics_registries: None,
             #[cfg(feature = "open-metrics")]
             metrics_server_port: None,
             request_timeout: None,
+            max_bytes_per_category: HashMap::new(),
+            eviction_policy: EvictionPolicy::default(),
+            record_store_backend: RecordStoreBackendKind::default(),
+            record_encryption: RecordEncryption::default(),
+            peer_access_list: PeerAccessList::default(),
+            protocol_compat: false,
+            rate_limit_config: RateLimitConfig::default(),
+            kademlia_config: KademliaConfig::default(),
+            relay_server: None,
+            websocket: None,
+            auto_expected_holders: false,
+            archive_mode: false,
+            audit_log_max_bytes: None,
+            connection_limits: ConnectionLimits::default(),
+            sybil_guard: SybilGuardConfig::default(),
+            warm_standby: false,
             #[cfg(feature = "upnp")]
             upnp: false,
+            #[cfg(any(test, feature = "simulator"))]
+            simulated: None,
         }
     }
 
@@ -312,10 +465,53 @@ impl NetworkBuilder {
         self.listen_addr = Some(listen_addr);
     }
 
+    /// Listen on this IPv6 address as well, on the port of the listen addr, for dual-stack.
+    pub fn listen_addr_v6(&mut self, ip: Ipv6Addr) {
+        self.listen_addr_v6 = Some(ip);
+    }
+
     pub fn request_timeout(&mut self, request_timeout: Duration) {
         self.request_timeout = Some(request_timeout);
     }
 
+    /// Set the maximum total size of the records of each category the node stores, in bytes.
+    pub fn max_bytes_per_category(
+        &mut self,
+        max_bytes_per_category: HashMap<RecordCategory, usize>,
+    ) {
+        self.max_bytes_per_category = max_bytes_per_category;
+    }
+
+    /// Set how the node makes room for new records once its record store is full.
+    pub fn eviction_policy(&mut self, eviction_policy: EvictionPolicy) {
+        self.eviction_policy = eviction_policy;
+    }
+
+    /// Set the backend the node keeps its records in.
+    pub fn record_store_backend(&mut self, backend: RecordStoreBackendKind) {
+        self.record_store_backend = backend;
+    }
+
+    /// Set how the node encrypts the records it keeps on disk.
+    pub fn record_encryption(&mut self, record_encryption: RecordEncryption) {
+        self.record_encryption = record_encryption;
+    }
+
+    /// Set the peers and IP ranges to block or exclusively allow.
+    pub fn peer_access_list(&mut self, peer_access_list: PeerAccessList) {
+        self.peer_access_list = peer_access_list;
+    }
+
+    /// Set the budgets of inbound requests the node accepts, per peer and from all peers.
+    pub fn rate_limit_config(&mut self, rate_limit_config: RateLimitConfig) {
+        self.rate_limit_config = rate_limit_config;
+    }
+
+    /// Set the parameters of the Kademlia behaviour, checked when building.
+    pub fn kademlia_config(&mut self, kademlia_config: KademliaConfig) {
+        self.kademlia_config = kademlia_config;
+    }
+
     pub fn concurrency_limit(&mut self, concurrency_limit: usize) {
         self.concurrency_limit = Some(concurrency_limit);
     }
@@ -333,11 +529,77 @@ impl NetworkBuilder {
         self.metrics_server_port = port;
     }
 
+    /// Relay the connections to the nodes behind home network, within the limits of the config.
+    pub fn relay_server(&mut self, config: RelayServerConfig) {
+        self.relay_server = Some(config);
+    }
+
+    /// Keep talking to the peers one minor protocol version apart, e.g. during a rolling upgrade of the network.
+    pub fn protocol_compat(&mut self, enable: bool) {
+        self.protocol_compat = enable;
+    }
+
+    /// Listen on WebSocket as well, for the clients that can't use QUIC.
+    pub fn websocket(&mut self, config: WebSocketConfig) {
+        self.websocket = Some(config);
+    }
+
+    /// Expect the peers that notified us of holding a record through replication to return it when we fetch it,
+    /// striking those missing with [`crate::NodeIssue::MissingRecord`].
+    pub fn auto_expected_holders(&mut self, enable: bool) {
+        self.auto_expected_holders = enable;
+    }
+
+    /// Volunteer to hold records beyond our close group responsibility, keeping them rather than cleaning them up,
+    /// and advertise it for the clients to push cold data to us.
+    pub fn archive_mode(&mut self, enable: bool) {
+        self.archive_mode = enable;
+    }
+
+    /// Keep an append-only log of the records stored and removed, in the root dir, capped at `max_bytes`.
+    pub fn audit_log(&mut self, max_bytes: u64) {
+        self.audit_log_max_bytes = Some(max_bytes);
+    }
+
+    /// Cap the number of live connections, evicting the idle connections to the farthest peers once over a limit.
+    pub fn connection_limits(&mut self, connection_limits: ConnectionLimits) {
+        self.connection_limits = connection_limits;
+    }
+
+    /// Keep the kbuckets from being crowded by the peers of a same IP or subnet, and flag the bursts of new peers
+    /// joining our close group or the watched ones.
+    pub fn sybil_guard(&mut self, config: SybilGuardConfig) {
+        self.sybil_guard = config;
+    }
+
+    /// Persist the routing table and the pending replication on a clean shutdown, and restore them on start, for a
+    /// node restarted for an upgrade to be back serving its records at once.
+    pub fn warm_standby(&mut self, enable: bool) {
+        self.warm_standby = enable;
+    }
+
     #[cfg(feature = "upnp")]
     pub fn upnp(&mut self, upnp: bool) {
         self.upnp = upnp;
     }
 
+    /// Run as a node of a simulated network, listening on the memory port through the simulated links instead of
+    /// on the listen address.
+    #[cfg(any(test, feature = "simulator"))]
+    pub fn simulated(&mut self, links: SimulatedLinks, port: u64) {
+        self.simulated = Some((links, port));
+    }
+
+    #[cfg(any(test, feature = "simulator"))]
+    fn is_simulated(&self) -> bool {
+        self.simulated.is_some()
+    }
+
+    #[cfg(not(any(test, feature = "simulator")))]
+    fn is_simulated(&self) -> bool {
+        false
+    }
+
     /// Creates a new `SwarmDriver` instance, along with a `Network` handle
     /// for sending commands and an `mpsc::Receiver<NetworkEvent>` for receiving
     /// network events. It initializes the swarm, sets up the transport, and
@@ -355,6 +617,8 @@ impl NetworkBuilder {
         self,
         root_dir: PathBuf,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
+        self.kademlia_config.validate()?;
+        let warm_standby = self.warm_standby;
         let bootstrap_interval = rand::thread_rng().gen_range(
             PERIODIC_KAD_BOOTSTRAP_INTERVAL_MAX_S / 2..PERIODIC_KAD_BOOTSTRAP_INTERVAL_MAX_S,
         );
@@ -371,20 +635,16 @@ impl NetworkBuilder {
             .set_publication_interval(None)
             // 1mb packet size
             .set_max_packet_size(MAX_PACKET_SIZE)
-            // How many nodes _should_ store data.
-            .set_replication_factor(REPLICATION_FACTOR)
-            .set_query_timeout(KAD_QUERY_TIMEOUT_S)
             // Require iterative queries to use disjoint paths for increased resiliency in the presence of potentially adversarial nodes.
             .disjoint_query_paths(true)
-            // Records never expire
-            .set_record_ttl(None)
-            .set_replication_factor(REPLICATION_FACTOR)
             .set_periodic_bootstrap_interval(Some(Duration::from_secs(bootstrap_interval)))
-            // Emit PUT events for validation prior to insertion into the RecordStore.
-            // This is no longer needed as the record_storage::put now can carry out validation.
-            // .set_record_filtering(KademliaStoreInserts::FilterBoth)
+            // Emit PUT events for validation prior to insertion into the RecordStore,
+            // for the records to be validated knowing the peer that put them.
+            .set_record_filtering(kad::StoreInserts::FilterBoth)
             // Disable provider records publication job
             .set_provider_publication_interval(None);
+        // The replication factor, parallelism, record TTLs and query timeout. Records never expire by default.
+        self.kademlia_config.apply(&mut kad_cfg, false);
 
         let store_cfg = {
             let storage_dir_path = root_dir.join("record_store");
@@ -406,23 +666,39 @@ impl NetworkBuilder {
                     source: error,
                 });
             }
-            let peer_id = PeerId::from(self.keypair.public());
-            let encryption_seed: [u8; 16] = peer_id
-                .to_bytes()
-                .get(..16)
-                .expect("Cann't get encryption_seed from keypair")
-                .try_into()
-                .expect("Cann't get 16 bytes from serialised key_pair");
+            let encryption_seed = match &self.record_encryption {
+                RecordEncryption::Disabled => None,
+                RecordEncryption::NodeKeypair => {
+                    let peer_id = PeerId::from(self.keypair.public());
+                    let encryption_seed: [u8; 16] = peer_id
+                        .to_bytes()
+                        .get(..16)
+                        .expect("Cann't get encryption_seed from keypair")
+                        .try_into()
+                        .expect("Cann't get 16 bytes from serialised key_pair");
+                    Some(encryption_seed.to_vec())
+                }
+                RecordEncryption::Key(key) => Some(key.clone()),
+            };
             NodeRecordStoreConfig {
                 max_value_bytes: MAX_PACKET_SIZE, // TODO, does this need to be _less_ than MAX_PACKET_SIZE
                 storage_dir: storage_dir_path,
+                backend: self.record_store_backend,
                 historic_quote_dir: root_dir.clone(),
                 encryption_seed,
+                max_bytes_per_category: self.max_bytes_per_category.clone(),
+                eviction_policy: self.eviction_policy,
+                archive_mode: self.archive_mode,
+                audit_log_max_bytes: self.audit_log_max_bytes,
                 ..Default::default()
             }
         };
 
+        #[cfg(any(test, feature = "simulator"))]
+        let simulated_port = self.simulated.as_ref().map(|(_, port)| *port);
         let listen_addr = self.listen_addr;
+        let listen_addr_v6 = self.listen_addr_v6;
+        let websocket = self.websocket.clone();
         #[cfg(feature = "upnp")]
         let upnp = self.upnp;
 
@@ -435,22 +711,58 @@ impl NetworkBuilder {
             upnp,
         )?;
 
+        #[cfg(any(test, feature = "simulator"))]
+        if let Some(port) = simulated_port {
+            swarm_driver
+                .listen_on(Multiaddr::empty().with(Protocol::Memory(port)))
+                .expect("Multiaddr should be supported by our configured transports");
+            swarm_driver.restore_peer_reputation(root_dir.clone());
+            if warm_standby {
+                swarm_driver.restore_warm_standby(&root_dir);
+                swarm_driver.warm_standby_dir = Some(root_dir);
+            }
+            return Ok((network, events_receiver, swarm_driver));
+        }
+
         // Listen on the provided address
         let listen_socket_addr = listen_addr.ok_or(NetworkError::ListenAddressNotProvided)?;
 
-        // Listen on QUIC
-        let addr_quic = Multiaddr::from(listen_socket_addr.ip())
-            .with(Protocol::Udp(listen_socket_addr.port()))
-            .with(Protocol::QuicV1);
-        swarm_driver
-            .listen_on(addr_quic)
-            .expect("Multiaddr should be supported by our configured transports");
+        let mut listen_ips = vec![listen_socket_addr.ip()];
+        if let Some(ip) = listen_addr_v6.map(IpAddr::V6) {
+            if !listen_ips.contains(&ip) {
+                listen_ips.push(ip);
+            }
+        }
+        for ip in listen_ips {
+            // Listen on QUIC
+            let addr_quic = Multiaddr::from(ip)
+                .with(Protocol::Udp(listen_socket_addr.port()))
+                .with(Protocol::QuicV1);
+            swarm_driver
+                .listen_on(addr_quic)
+                .expect("Multiaddr should be supported by our configured transports");
+
+            // Listen on WebSocket, if enabled
+            if let Some(websocket) = &websocket {
+                let addr_ws = websocket.listen_addr(ip);
+                swarm_driver
+                    .listen_on(addr_ws)
+                    .expect("Multiaddr should be supported by our configured transports");
+            }
+        }
+
+        swarm_driver.restore_peer_reputation(root_dir.clone());
+        if warm_standby {
+            swarm_driver.restore_warm_standby(&root_dir);
+            swarm_driver.warm_standby_dir = Some(root_dir);
+        }
 
         Ok((network, events_receiver, swarm_driver))
     }
 
     /// Same as `build_node` API but creates the network components in client mode
     pub fn build_client(self) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
+        self.kademlia_config.validate()?;
         // Create a Kademlia behaviour for client mode, i.e. set req/resp protocol
         // to outbound-only mode and don't listen on any address
         let mut kad_cfg = kad::Config::new(KAD_STREAM_PROTOCOL_ID); // default query timeout is 60 secs
@@ -459,11 +771,9 @@ impl NetworkBuilder {
         let _ = kad_cfg
             .set_kbucket_inserts(libp2p::kad::BucketInserts::Manual)
             .set_max_packet_size(MAX_PACKET_SIZE)
-            .set_replication_factor(REPLICATION_FACTOR)
             // Require iterative queries to use disjoint paths for increased resiliency in the presence of potentially adversarial nodes.
-            .disjoint_query_paths(true)
-            // How many nodes _should_ store data.
-            .set_replication_factor(REPLICATION_FACTOR);
+            .disjoint_query_paths(true);
+        self.kademlia_config.apply(&mut kad_cfg, true);
 
         let (network, net_event_recv, driver) = self.build(
             kad_cfg,
@@ -492,6 +802,8 @@ impl NetworkBuilder {
             .clone();
 
         let peer_id = PeerId::from(self.keypair.public());
+        #[cfg(not(target_arch = "wasm32"))]
+        let is_simulated = self.is_simulated();
         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
         #[cfg(not(target_arch = "wasm32"))]
         info!(
@@ -508,9 +820,18 @@ impl NetworkBuilder {
 
         // ==== Transport ====
         #[cfg(feature = "open-metrics")]
-        let main_transport = transport::build_transport(&self.keypair, &mut metrics_registries);
+        let main_transport = transport::build_transport(
+            &self.keypair,
+            self.websocket.as_ref(),
+            &mut metrics_registries,
+        )?;
         #[cfg(not(feature = "open-metrics"))]
-        let main_transport = transport::build_transport(&self.keypair);
+        let main_transport = transport::build_transport(&self.keypair, self.websocket.as_ref())?;
+        #[cfg(any(test, feature = "simulator"))]
+        let main_transport = match &self.simulated {
+            Some((links, _)) => links.build_transport(&self.keypair),
+            None => main_transport,
+        };
         let transport = if !self.local {
             debug!("Preventing non-global dials");
             // Wrap upper in a transport that prevents dialing local addresses.
@@ -530,10 +851,16 @@ impl NetworkBuilder {
             .multiplex(libp2p::yamux::Config::default())
             .or_transport(transport);
 
+        let bandwidth_tracker = BandwidthTracker::default();
+        let tracker = bandwidth_tracker.clone();
         let transport = relay_transport
-            .map(|either_output, _| match either_output {
-                Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
-                Either::Right((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+            .map(move |either_output, _endpoint| {
+                let (peer_id, muxer) = match either_output {
+                    Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+                    Either::Right((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+                };
+                let muxer = tracker.wrap(peer_id, muxer);
+                (peer_id, StreamMuxerBox::new(muxer))
             })
             .boxed();
 
@@ -573,22 +900,45 @@ impl NetworkBuilder {
                 .expect("Failed to obtain read lock for REQ_RESPONSE_VERSION_STR")
                 .clone();
 
-            info!("Building request response with {req_res_version_str:?}",);
-            request_response::cbor::Behaviour::new(
-                [(
-                    StreamProtocol::try_from_owned(req_res_version_str)
-                        .expect("StreamProtocol should start with a /"),
-                    req_res_protocol,
-                )],
+            // Also speak the protocol of the previous version, for its peers to keep talking to us.
+            let previous_version_str = ProtocolVersion::parse(&req_res_version_str)
+                .and_then(|version| version.previous())
+                .filter(|_| self.protocol_compat)
+                .map(|version| version.req_response_version_str());
+
+            info!("Building request response with {req_res_version_str:?}, and {previous_version_str:?} for compatibility");
+            request_response::Behaviour::with_codec(
+                RequestResponseCodec,
+                std::iter::once(req_res_version_str)
+                    .chain(previous_version_str)
+                    .flat_map(|version_str| req_res_protocols(&version_str))
+                    .map(|protocol| (protocol, req_res_protocol.clone())),
                 cfg,
             )
         };
 
+        #[cfg(feature = "chaos")]
+        let chaos = if is_client {
+            ChaosConfig::default()
+        } else {
+            ChaosConfig::from_env()
+        };
+        #[cfg(feature = "chaos")]
+        if chaos.is_enabled() {
+            warn!("Injecting faults: {chaos:?}");
+        }
+
         let (network_event_sender, network_event_receiver) = mpsc::channel(NETWORKING_CHANNEL_SIZE);
-        let (network_swarm_cmd_sender, network_swarm_cmd_receiver) =
-            mpsc::channel(NETWORKING_CHANNEL_SIZE);
-        let (local_swarm_cmd_sender, local_swarm_cmd_receiver) =
-            mpsc::channel(NETWORKING_CHANNEL_SIZE);
+        let (network_swarm_cmd_sender, network_swarm_cmd_receiver) = cmd_channel();
+        let (local_swarm_cmd_sender, local_swarm_cmd_receiver) = cmd_channel();
+
+        // The capabilities advertised through identify, which can't be changed once the swarm is built
+        let mut capabilities = NodeCapabilities {
+            relay_server: !is_client && !self.is_behind_home_network && self.relay_server.is_some(),
+            storage_near_full: false,
+            archive: !is_client && self.archive_mode,
+            client_only: !is_client && self.is_behind_home_network,
+        };
 
         // Kademlia Behaviour
         let kademlia = {
@@ -599,13 +949,17 @@ impl NetworkBuilder {
                         store_cfg,
                         network_event_sender.clone(),
                         local_swarm_cmd_sender.clone(),
-                    );
-                    #[cfg(feature = "open-metrics")]
+                    )?;
+                    capabilities.storage_near_full = node_record_store.is_near_full();
+                    #[cfg(any(feature = "open-metrics", feature = "chaos"))]
                     let mut node_record_store = node_record_store;
+                    #[cfg(feature = "chaos")]
+                    node_record_store.set_corrupt_chunks(chaos.corrupt_chunks);
                     #[cfg(feature = "open-metrics")]
                     if let Some(metrics_recorder) = &metrics_recorder {
                         node_record_store = node_record_store
-                            .set_record_count_metric(metrics_recorder.records_stored.clone());
+                            .set_record_count_metric(metrics_recorder.records_stored.clone())
+                            .set_record_eviction_metric(metrics_recorder.records_evicted.clone());
                     }
 
                     let store = UnifiedRecordStore::Node(node_record_store);
@@ -621,17 +975,21 @@ impl NetworkBuilder {
             }
         };
 
-        #[cfg(feature = "local")]
-        let mdns_config = mdns::Config {
-            // lower query interval to speed up peer discovery
-            // this increases traffic, but means we no longer have clients unable to connect
-            // after a few minutes
-            query_interval: Duration::from_secs(5),
-            ..Default::default()
-        };
-
-        #[cfg(feature = "local")]
-        let mdns = mdns::tokio::Behaviour::new(mdns_config, peer_id)?;
+        // Discover the nodes and clients on the same LAN, in local mode only
+        #[cfg(not(target_arch = "wasm32"))]
+        let mdns = if self.local && !is_simulated {
+            let mdns_config = mdns::Config {
+                // lower query interval to speed up peer discovery
+                // this increases traffic, but means we no longer have clients unable to connect
+                // after a few minutes
+                query_interval: Duration::from_secs(5),
+                ..Default::default()
+            };
+            Some(mdns::tokio::Behaviour::new(mdns_config, peer_id)?)
+        } else {
+            None
+        }
+        .into(); // Into `Toggle<T>`
 
         let agent_version = if is_client {
             IDENTIFY_CLIENT_VERSION_STR
@@ -639,10 +997,12 @@ impl NetworkBuilder {
                 .expect("Failed to obtain read lock for IDENTIFY_CLIENT_VERSION_STR")
                 .clone()
         } else {
-            IDENTIFY_NODE_VERSION_STR
+            let agent_version = IDENTIFY_NODE_VERSION_STR
                 .read()
                 .expect("Failed to obtain read lock for IDENTIFY_NODE_VERSION_STR")
-                .clone()
+                .clone();
+            info!("Advertising the capabilities {capabilities:?}");
+            capabilities.agent_version(&agent_version)
         };
         // Identify Behaviour
         info!("Building Identify with identify_protocol_str: {identify_protocol_str:?} and identify_protocol_str: {identify_protocol_str:?}");
@@ -663,30 +1023,68 @@ impl NetworkBuilder {
         }
         .into(); // Into `Toggle<T>`
 
-        let relay_server = {
-            let relay_server_cfg = relay::Config {
-                max_reservations: 128,             // Amount of peers we are relaying for
-                max_circuits: 1024, // The total amount of relayed connections at any given moment.
-                max_circuits_per_peer: 256, // Amount of relayed connections per peer (both dst and src)
-                circuit_src_rate_limiters: vec![], // No extra rate limiting for now
-                // We should at least be able to relay packets with chunks etc.
-                max_circuit_bytes: MAX_PACKET_SIZE as u64,
-                ..Default::default()
+        // The relay server only runs on the opted in public nodes, advertising the relay protocol via identify
+        let relay_server = match &self.relay_server {
+            Some(_) if self.is_behind_home_network => {
+                warn!("Relay server mode is disabled for a node behind home network");
+                None
+            }
+            Some(cfg) if !is_client => {
+                info!("Enabling relay server mode with {cfg:?}");
+                Some(cfg.behaviour(peer_id))
+            }
+            _ => None,
+        }
+        .into(); // Into `Toggle<T>`
+
+        // Gossipsub Behaviour, used to notify the peers subscribed to a topic of changes to the data they watch
+        let gossipsub = {
+            // All the nodes holding a record publish the changes they accept to it, so messages are identified by
+            // their content, for the same notification from different nodes to be only delivered once.
+            let message_id_fn = |message: &libp2p::gossipsub::Message| {
+                libp2p::gossipsub::MessageId::from(XorName::from_content(&message.data).0.to_vec())
             };
-            libp2p::relay::Behaviour::new(peer_id, relay_server_cfg)
+            let cfg = libp2p::gossipsub::ConfigBuilder::default()
+                .message_id_fn(message_id_fn)
+                .max_transmit_size(MAX_PACKET_SIZE)
+                .build()
+                .map_err(|err| NetworkError::BehaviourErr(err.to_string()))?;
+            libp2p::gossipsub::Behaviour::new(
+                libp2p::gossipsub::MessageAuthenticity::Signed(self.keypair.clone()),
+                cfg,
+            )
+            .map_err(|err| NetworkError::BehaviourErr(err.to_string()))?
         };
 
+        // AutoNAT Behaviour, for the nodes to find out whether they're reachable from the public internet
+        let autonat = if !self.local && !is_client {
+            Some(autonat_behaviour(peer_id))
+        } else {
+            None
+        }
+        .into(); // Into `Toggle<T>`
+
+        // DCUtR Behaviour, upgrading the relayed connections to direct ones through hole punching
+        let dcutr = libp2p::dcutr::Behaviour::new(peer_id);
+
+        let mut blocklist = libp2p::allow_block_list::Behaviour::default();
+        for peer_id in &self.peer_access_list.blocked_peers {
+            blocklist.block_peer(*peer_id);
+        }
         let behaviour = NodeBehaviour {
-            blocklist: libp2p::allow_block_list::Behaviour::default(),
+            blocklist,
             relay_client: relay_behaviour,
+            dcutr,
             relay_server,
+            autonat,
             #[cfg(feature = "upnp")]
             upnp,
             request_response,
             kademlia,
             identify,
-            #[cfg(feature = "local")]
+            #[cfg(not(target_arch = "wasm32"))]
             mdns,
+            gossipsub,
         };
 
         #[cfg(not(target_arch = "wasm32"))]
@@ -699,11 +1097,15 @@ impl NetworkBuilder {
         let swarm = Swarm::new(transport, behaviour, peer_id, swarm_config);
 
         let bootstrap = ContinuousNetworkDiscover::new();
-        let replication_fetcher = ReplicationFetcher::new(peer_id, network_event_sender.clone());
+        let mut replication_fetcher =
+            ReplicationFetcher::new(peer_id, network_event_sender.clone());
+        if self.auto_expected_holders && !is_client {
+            replication_fetcher.track_holders();
+        }
 
-        // Enable relay manager for nodes behind home network
-        let relay_manager = if !is_client && self.is_behind_home_network {
-            let relay_manager = RelayManager::new(peer_id);
+        // Relay manager for nodes, making reservations once behind home network
+        let relay_manager = if !is_client {
+            let relay_manager = RelayManager::new(peer_id, self.is_behind_home_network);
             Some(relay_manager)
         } else {
             info!("Relay manager is disabled for this node.");
@@ -723,18 +1125,29 @@ impl NetworkBuilder {
             self_peer_id: peer_id,
             local: self.local,
             is_client,
+            protocol_compat: self.protocol_compat,
+            replication_factor: self.kademlia_config.replication_factor,
             is_behind_home_network: self.is_behind_home_network,
+            reachability: Reachability::default(),
             #[cfg(feature = "open-metrics")]
             close_group: Vec::with_capacity(CLOSE_GROUP_SIZE),
             peers_in_rt: 0,
+            routing_table_degraded: false,
             bootstrap,
             bootstrap_cache: self.bootstrap_cache,
             relay_manager,
             connected_relay_clients: Default::default(),
+            relayed_circuits: 0,
+            dial_strategy: DialStrategy::default(),
+            connection_limiter: ConnectionLimiter::new(self.connection_limits),
+            sybil_guard: SybilGuard::new(self.sybil_guard, NetworkAddress::from_peer(peer_id)),
             external_address_manager,
             replication_fetcher,
+            #[cfg(feature = "chaos")]
+            chaos,
             #[cfg(feature = "open-metrics")]
             metrics_recorder,
+            bandwidth_tracker,
             // kept here to ensure we can push messages to the channel
             // and not block the processing thread unintentionally
             network_cmd_sender: network_swarm_cmd_sender.clone(),
@@ -745,17 +1158,26 @@ impl NetworkBuilder {
             pending_get_closest_peers: Default::default(),
             pending_requests: Default::default(),
             pending_get_record: Default::default(),
+            pending_put_record: Default::default(),
             // We use 255 here which allows covering a network larger than 64k without any rotating.
             // This is based on the libp2p kad::kBuckets peers distribution.
             dialed_peers: CircularVec::new(255),
             network_discovery: NetworkDiscovery::new(&peer_id),
+            routing_table_prewarm: Default::default(),
             bootstrap_peers: Default::default(),
             live_connected_peers: Default::default(),
             latest_established_connection_ids: Default::default(),
             handling_statistics: Default::default(),
             handled_times: 0,
             hard_disk_write_error: 0,
-            bad_nodes: Default::default(),
+            peer_scores: Default::default(),
+            peer_capabilities: Default::default(),
+            peer_reputation_dir: None,
+            warm_standby_dir: None,
+            restored_peers: Default::default(),
+            peer_access_list: self.peer_access_list,
+            request_rate_limiter: (!is_client)
+                .then(|| RequestRateLimiter::new(self.rate_limit_config)),
             quotes_history: Default::default(),
             replication_targets: Default::default(),
             last_replication: None,
@@ -819,25 +1241,44 @@ pub struct SwarmDriver {
     /// When true, we don't filter our local addresses
     pub(crate) local: bool,
     pub(crate) is_client: bool,
+    /// Whether the peers one minor protocol version apart are accepted
+    pub(crate) protocol_compat: bool,
+    /// How many of the closest peers a record is PUT to, see `KademliaConfig::replication_factor`.
+    pub(crate) replication_factor: NonZeroUsize,
     pub(crate) is_behind_home_network: bool,
+    /// Whether we're reachable from the public internet, as detected by AutoNAT
+    pub(crate) reachability: Reachability,
     #[cfg(feature = "open-metrics")]
     pub(crate) close_group: Vec<PeerId>,
     pub(crate) peers_in_rt: usize,
+    /// Whether the last health check found the routing table degraded
+    pub(crate) routing_table_degraded: bool,
     pub(crate) bootstrap: ContinuousNetworkDiscover,
     pub(crate) bootstrap_cache: Option<BootstrapCacheStore>,
     pub(crate) external_address_manager: Option<ExternalAddressManager>,
     pub(crate) relay_manager: Option<RelayManager>,
     /// The peers that are using our relay service.
     pub(crate) connected_relay_clients: HashSet<PeerId>,
+    /// The number of connections we are currently relaying.
+    pub(crate) relayed_circuits: usize,
+    /// Picks the IP families to dial the peers over.
+    pub(crate) dial_strategy: DialStrategy,
+    pub(crate) connection_limiter: ConnectionLimiter,
+    pub(crate) sybil_guard: SybilGuard,
     /// The peers that are closer to our PeerId. Includes self.
     pub(crate) replication_fetcher: ReplicationFetcher,
+    /// The faults injected on purpose
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos: ChaosConfig,
     #[cfg(feature = "open-metrics")]
     pub(crate) metrics_recorder: Option<NetworkMetricsRecorder>,
+    /// Accounts the bytes sent and received per peer
+    pub(crate) bandwidth_tracker: BandwidthTracker,
 
-    network_cmd_sender: mpsc::Sender<NetworkSwarmCmd>,
-    pub(crate) local_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
-    local_cmd_receiver: mpsc::Receiver<LocalSwarmCmd>,
-    network_cmd_receiver: mpsc::Receiver<NetworkSwarmCmd>,
+    pub(crate) network_cmd_sender: CmdSender<NetworkSwarmCmd>,
+    pub(crate) local_cmd_sender: CmdSender<LocalSwarmCmd>,
+    local_cmd_receiver: CmdReceiver<LocalSwarmCmd>,
+    network_cmd_receiver: CmdReceiver<NetworkSwarmCmd>,
     event_sender: mpsc::Sender<NetworkEvent>, // Use `self.send_event()` to send a NetworkEvent.
 
     /// Trackers for underlying behaviour related events
@@ -845,11 +1286,14 @@ pub struct SwarmDriver {
     pub(crate) pending_requests:
         HashMap<OutboundRequestId, Option<oneshot::Sender<Result<Response>>>>,
     pub(crate) pending_get_record: PendingGetRecord,
+    pub(crate) pending_put_record: PendingPutRecord,
     /// A list of the most recent peers we have dialed ourselves. Old dialed peers are evicted once the vec fills up.
     pub(crate) dialed_peers: CircularVec<PeerId>,
     // A list of random `PeerId` candidates that falls into kbuckets,
     // This is to ensure a more accurate network discovery.
     pub(crate) network_discovery: NetworkDiscovery,
+    /// The queued and in flight lookups requested through `Network::prewarm`.
+    pub(crate) routing_table_prewarm: RoutingTablePrewarm,
     pub(crate) bootstrap_peers: BTreeMap<Option<u32>, HashSet<PeerId>>,
     // Peers that having live connection to. Any peer got contacted during kad network query
     // will have live connection established. And they may not appear in the RT.
@@ -861,7 +1305,19 @@ pub struct SwarmDriver {
     handling_statistics: BTreeMap<String, Vec<Duration>>,
     handled_times: usize,
     pub(crate) hard_disk_write_error: usize,
-    pub(crate) bad_nodes: BadNodes,
+    pub(crate) peer_scores: PeerScores,
+    /// The capabilities the nodes advertised through identify, until they're removed from the RT.
+    pub(crate) peer_capabilities: HashMap<PeerId, NodeCapabilities>,
+    /// Where the peer reputation is persisted to. Only set for nodes.
+    pub(crate) peer_reputation_dir: Option<PathBuf>,
+    /// Where the warm standby snapshot is persisted to on a clean shutdown. Only set in warm standby mode.
+    pub(crate) warm_standby_dir: Option<PathBuf>,
+    /// The peers restored to the routing table from the warm standby snapshot, not to be taken for new ones.
+    pub(crate) restored_peers: HashSet<PeerId>,
+    /// The peers and IP ranges the operator blocked or exclusively allowed
+    pub(crate) peer_access_list: PeerAccessList,
+    /// Limits the rate of inbound requests. Only set for nodes.
+    pub(crate) request_rate_limiter: Option<RequestRateLimiter>,
     pub(crate) quotes_history: BTreeMap<PeerId, PaymentQuote>,
     pub(crate) replication_targets: BTreeMap<PeerId, Instant>,
     /// when was the last replication event
@@ -885,6 +1341,11 @@ impl SwarmDriver {
         let mut network_discover_interval = interval(NETWORK_DISCOVER_INTERVAL);
         let mut set_farthest_record_interval = interval(CLOSET_RECORD_CHECK_INTERVAL);
         let mut relay_manager_reservation_interval = interval(RELAY_MANAGER_RESERVATION_INTERVAL);
+        let mut peer_reputation_save_interval = interval(PEER_REPUTATION_SAVE_INTERVAL);
+        peer_reputation_save_interval.tick().await; // first tick completes immediately
+        let mut routing_table_health_interval = interval(ROUTING_TABLE_HEALTH_CHECK_INTERVAL);
+        routing_table_health_interval.tick().await; // first tick completes immediately
+        let mut dial_stagger_interval = interval(DIAL_STAGGER);
 
         let mut bootstrap_cache_save_interval = self.bootstrap_cache.as_ref().and_then(|cache| {
             if cache.config().disable_cache_writing {
@@ -914,7 +1375,16 @@ impl SwarmDriver {
                 // Prioritise any local cmds pending.
                 // https://github.com/libp2p/rust-libp2p/blob/master/docs/coding-guidelines.md#prioritize-local-work-over-new-work-from-a-remote
                 local_cmd = self.local_cmd_receiver.recv() => match local_cmd {
-                    Some(cmd) => {
+                    Some(QueuedCmd { cmd, queued_at }) => {
+                        #[cfg(feature = "open-metrics")]
+                        if let Some(metrics_recorder) = &self.metrics_recorder {
+                            metrics_recorder.record_swarm_cmd_dequeued(
+                                SwarmCmdChannel::Local,
+                                queue_depth(&self.local_cmd_sender),
+                                queued_at.elapsed(),
+                            );
+                        }
+                        trace!("LocalCmd waited {:?} in the queue", queued_at.elapsed());
                         let start = Instant::now();
                         let cmd_string = format!("{cmd:?}");
                         if let Err(err) = self.handle_local_cmd(cmd) {
@@ -926,7 +1396,16 @@ impl SwarmDriver {
                 },
                 // next check if we have locally generated network cmds
                 some_cmd = self.network_cmd_receiver.recv() => match some_cmd {
-                    Some(cmd) => {
+                    Some(QueuedCmd { cmd, queued_at }) => {
+                        #[cfg(feature = "open-metrics")]
+                        if let Some(metrics_recorder) = &self.metrics_recorder {
+                            metrics_recorder.record_swarm_cmd_dequeued(
+                                SwarmCmdChannel::Network,
+                                queue_depth(&self.network_cmd_sender),
+                                queued_at.elapsed(),
+                            );
+                        }
+                        trace!("SwarmCmd waited {:?} in the queue", queued_at.elapsed());
                         let start = Instant::now();
                         let cmd_string = format!("{cmd:?}");
                         if let Err(err) = self.handle_network_cmd(cmd) {
@@ -970,6 +1449,9 @@ impl SwarmDriver {
                 }
                 _ = set_farthest_record_interval.tick() => {
                     if !self.is_client {
+                        #[cfg(feature = "open-metrics")]
+                        self.record_node_health_metrics();
+
                         let (
                             _index,
                             _total_peers,
@@ -1031,7 +1513,24 @@ impl SwarmDriver {
                 }
                 _ = relay_manager_reservation_interval.tick() => {
                     if let Some(relay_manager) = &mut self.relay_manager {
-                        relay_manager.try_connecting_to_relay(&mut self.swarm, &self.bad_nodes)
+                        relay_manager.try_connecting_to_relay(&mut self.swarm, &self.peer_scores)
+                    }
+                },
+                _ = peer_reputation_save_interval.tick() => {
+                    if let Some(root_dir) = self.peer_reputation_dir.clone() {
+                        let reputation = self.peer_reputation();
+                        let _handle = tokio::task::spawn_blocking(move || {
+                            if let Err(err) = reputation.save(&root_dir) {
+                                error!("Failed to save the peer reputation: {err}");
+                            }
+                        });
+                    }
+                },
+                _ = dial_stagger_interval.tick() => self.dial_staggered_addrs(),
+                _ = routing_table_health_interval.tick() => {
+                    self.check_routing_table_health();
+                    for peer_id in self.peer_scores.release_expired() {
+                        self.swarm.behaviour_mut().blocklist.unblock_peer(peer_id);
                     }
                 },
                 Some(()) = Self::conditional_interval(&mut bootstrap_cache_save_interval) => {
@@ -1091,24 +1590,9 @@ impl SwarmDriver {
     // ---------- Crate helpers -------------------
     // --------------------------------------------
 
-    /// Pushes NetworkSwarmCmd off thread so as to be non-blocking
-    /// this is a wrapper around the `mpsc::Sender::send` call
-    pub(crate) fn queue_network_swarm_cmd(&self, event: NetworkSwarmCmd) {
-        let event_sender = self.network_cmd_sender.clone();
-        let capacity = event_sender.capacity();
-
-        // push the event off thread so as to be non-blocking
-        let _handle = spawn(async move {
-            if capacity == 0 {
-                warn!(
-                    "NetworkSwarmCmd channel is full. Await capacity to send: {:?}",
-                    event
-                );
-            }
-            if let Err(error) = event_sender.send(event).await {
-                error!("SwarmDriver failed to send event: {}", error);
-            }
-        });
+    /// Queues a NetworkSwarmCmd without blocking, see `cmd_channel::queue_cmd`
+    pub(crate) fn queue_network_swarm_cmd(&self, cmd: NetworkSwarmCmd) {
+        queue_cmd(self.network_cmd_sender.clone(), cmd);
     }
 
     /// Sends an event after pushing it off thread so as to be non-blocking
@@ -1131,6 +1615,26 @@ impl SwarmDriver {
         });
     }
 
+    /// Ask the node to fetch the keys through replication, once the replication delay of the injected faults, if
+    /// any, has passed.
+    pub(crate) fn send_keys_to_fetch(&self, keys: Vec<(PeerId, RecordKey)>) {
+        #[cfg(feature = "chaos")]
+        if !self.chaos.replication_delay.is_zero() {
+            let delay = self.chaos.replication_delay;
+            let event_sender = self.event_sender.clone();
+            debug!("Holding back {} keys to fetch for {delay:?}", keys.len());
+            let _handle = spawn(async move {
+                crate::target_arch::sleep(delay).await;
+                let event = NetworkEvent::KeysToFetchForReplication(keys);
+                if let Err(error) = event_sender.send(event).await {
+                    error!("SwarmDriver failed to send event: {}", error);
+                }
+            });
+            return;
+        }
+        self.send_event(NetworkEvent::KeysToFetchForReplication(keys));
+    }
+
     /// get closest k_value the peers from our local RoutingTable. Contains self.
     /// Is sorted for closeness to self.
     pub(crate) fn get_closest_k_value_local_peers(&mut self) -> Vec<PeerId> {
@@ -1153,6 +1657,67 @@ impl SwarmDriver {
             .collect()
     }
 
+    /// Snapshot the peer scores along with the recently-good peers of the routing table.
+    pub(crate) fn peer_reputation(&mut self) -> PeerReputation {
+        let good_peers = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .kbuckets()
+            .flat_map(|kbucket| {
+                kbucket
+                    .iter()
+                    .map(|entry| {
+                        (
+                            entry.node.key.into_preimage(),
+                            entry.node.value.clone().into_vec(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|(peer_id, addrs)| !addrs.is_empty() && !self.peer_scores.is_bad(peer_id))
+            .take(MAX_PERSISTED_GOOD_PEERS)
+            .collect();
+        PeerReputation::new(&self.peer_scores, good_peers)
+    }
+
+    /// Restore the peer reputation persisted in the root dir, if any.
+    /// The bad peers are blocked again, and the recently-good ones dialed to rejoin the network faster.
+    fn restore_peer_reputation(&mut self, root_dir: PathBuf) {
+        if let Some(reputation) = PeerReputation::load(&root_dir) {
+            reputation.restore_scores(&mut self.peer_scores);
+            for peer_id in self.peer_scores.bad_peers() {
+                self.swarm.behaviour_mut().blocklist.block_peer(*peer_id);
+            }
+            info!(
+                "Restored the peer reputation, with {} bad peers and {} good peers",
+                self.peer_scores.bad_peers().count(),
+                reputation.good_peers.len()
+            );
+
+            for (peer_id, addrs) in reputation.good_peers {
+                if self.peer_scores.is_bad(&peer_id) {
+                    continue;
+                }
+                if let Err(err) = self.dial_racing(peer_id, addrs) {
+                    debug!("Failed to dial persisted good peer {peer_id:?}: {err:?}");
+                }
+            }
+        }
+        self.peer_reputation_dir = Some(root_dir);
+    }
+
+    /// Whether the peer can be talked to, according to the peer access list and the addresses of
+    /// its live connections.
+    pub(crate) fn is_peer_allowed(&self, peer_id: &PeerId) -> bool {
+        let addrs = self
+            .live_connected_peers
+            .values()
+            .filter(|(peer, _addr, _expiry)| peer == peer_id)
+            .map(|(_peer, addr, _expiry)| addr);
+        self.peer_access_list.is_allowed(peer_id, addrs)
+    }
+
     /// Dials the given multiaddress. If address contains a peer ID, simultaneous
     /// dials to that peer are prevented.
     pub(crate) fn dial(&mut self, mut addr: Multiaddr) -> Result<(), DialError> {
@@ -1171,6 +1736,55 @@ impl SwarmDriver {
         self.swarm.dial(opts)
     }
 
+    /// Dials the peer on its addresses, racing them with staggered starts, see [`DialStrategy`].
+    pub(crate) fn dial_racing(
+        &mut self,
+        peer_id: PeerId,
+        addrs: impl IntoIterator<Item = Multiaddr>,
+    ) -> Result<(), DialError> {
+        let Some(addr) = self.dial_strategy.start_race(peer_id, addrs) else {
+            debug!("Not dialing {peer_id:?}, already dialing it or no address to reach it on");
+            return Ok(());
+        };
+        self.dial_race_addr(peer_id, addr, PeerCondition::NotDialing)
+    }
+
+    /// Dials the next address of the race to the peer. The later addresses are dialed alongside the ongoing dials,
+    /// as long as the peer isn't connected.
+    pub(crate) fn dial_race_addr(
+        &mut self,
+        peer_id: PeerId,
+        addr: Multiaddr,
+        condition: PeerCondition,
+    ) -> Result<(), DialError> {
+        let opts = DialOpts::peer_id(peer_id)
+            .condition(condition)
+            .addresses(vec![addr.clone()])
+            .build();
+        let connection_id = opts.connection_id();
+        match self.swarm.dial(opts) {
+            Ok(()) => {
+                self.dial_strategy
+                    .on_race_dial(peer_id, connection_id, addr);
+                Ok(())
+            }
+            Err(err) => {
+                self.dial_strategy.abandon_race(&peer_id);
+                Err(err)
+            }
+        }
+    }
+
+    /// Dials the next addresses of the races whose stagger elapsed.
+    fn dial_staggered_addrs(&mut self) {
+        for (peer_id, addr) in self.dial_strategy.due_dials(Instant::now()) {
+            debug!("Racing address {addr:?} of {peer_id:?}");
+            if let Err(err) = self.dial_race_addr(peer_id, addr, PeerCondition::Disconnected) {
+                debug!("Stopped racing the addresses of {peer_id:?}: {err:?}");
+            }
+        }
+    }
+
     /// Record one handling time.
     /// Log for every 100 received.
     pub(crate) fn log_handling(&mut self, handle_string: String, handle_time: Duration) {
@@ -1220,6 +1834,22 @@ impl SwarmDriver {
             metrics_recorder.record_from_marker(marker)
         }
     }
+    /// Records the outcome, duration and the number of copies received of a completed GetRecord query if the
+    /// `open-metrics` feature flag is enabled.
+    pub(crate) fn record_get_record_metrics(
+        &self,
+        result: &std::result::Result<GetRecordOk, GetRecordError>,
+        copies: usize,
+        stats: &QueryStats,
+    ) {
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics_recorder) = self.metrics_recorder.as_ref() {
+            metrics_recorder.record_get_record(result, copies, stats.duration());
+        }
+        #[cfg(not(feature = "open-metrics"))]
+        let _ = (result, copies, stats);
+    }
+
     #[cfg(feature = "open-metrics")]
     /// Updates metrics that rely on our current close group.
     pub(crate) fn record_change_in_close_group(&self, new_close_group: Vec<PeerId>) {
@@ -1228,6 +1858,84 @@ impl SwarmDriver {
         }
     }
 
+    #[cfg(feature = "open-metrics")]
+    /// Updates the metrics of the replication queue, the shunned peers and the coverage of our relevant records.
+    fn record_node_health_metrics(&mut self) {
+        let driver_state = self.driver_state();
+        self.record_driver_state_metrics(&driver_state);
+
+        let Some(metrics_recorder) = self.metrics_recorder.as_ref() else {
+            return;
+        };
+        let queue_status = self.replication_fetcher.queue_status();
+        let _ = metrics_recorder
+            .replication_pending_keys
+            .set(queue_status.pending_keys as i64);
+        for priority in [FetchPriority::Mutable, FetchPriority::Chunk] {
+            let pending_keys = queue_status
+                .pending_keys_per_priority
+                .get(&priority)
+                .copied()
+                .unwrap_or_default();
+            let _ = metrics_recorder
+                .replication_pending_keys_per_priority
+                .get_or_create(&FetchPriorityLabels { priority })
+                .set(pending_keys as i64);
+        }
+        let _ = metrics_recorder
+            .replication_ongoing_fetches
+            .set(queue_status.ongoing_fetches as i64);
+        let _ = metrics_recorder
+            .replication_oldest_pending_seconds
+            .set(queue_status.oldest_pending.as_secs() as i64);
+
+        let shunned_peers = self.peer_scores.bad_peers().count();
+        let _ = metrics_recorder.shunned_peers.set(shunned_peers as i64);
+
+        if let Some(relay_manager) = self.relay_manager.as_ref() {
+            let _ = metrics_recorder
+                .relay_reservations
+                .set(relay_manager.reservation_count() as i64);
+        }
+        if self.swarm.behaviour().relay_server.is_enabled() {
+            let _ = metrics_recorder
+                .relay_server_reservations
+                .set(self.connected_relay_clients.len() as i64);
+            let _ = metrics_recorder
+                .relay_server_circuits
+                .set(self.relayed_circuits as i64);
+        }
+
+        for (protocol, (sent, received)) in self.bandwidth_tracker.totals_per_protocol() {
+            for (direction, bytes) in [
+                (BandwidthDirection::Sent, sent),
+                (BandwidthDirection::Received, received),
+            ] {
+                let _ = metrics_recorder
+                    .bandwidth_bytes
+                    .get_or_create(&BandwidthLabels {
+                        protocol: protocol.clone(),
+                        direction,
+                    })
+                    .set(bytes as i64);
+            }
+        }
+
+        let relevant_records = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .relevant_records_count();
+        let expected_records = relevant_records + queue_status.pending_keys;
+        let coverage = (relevant_records * 100)
+            .checked_div(expected_records)
+            .unwrap_or(100);
+        let _ = metrics_recorder
+            .relevant_records_coverage_percentage
+            .set(coverage as i64);
+    }
+
     /// Listen on the provided address. Also records it within RelayManager
     pub(crate) fn listen_on(&mut self, addr: Multiaddr) -> Result<()> {
         let id = self.swarm.listen_on(addr.clone())?;
@@ -1261,8 +1969,42 @@ impl SwarmDriver {
 
 #[cfg(test)]
 mod tests {
-    use super::check_and_wipe_storage_dir_if_necessary;
+    use super::{check_and_wipe_storage_dir_if_necessary, GetRecordOk};
+    use libp2p::{kad::Record, PeerId};
     use std::{fs, io::Read, time::Duration};
+    use xor_name::XorName;
+
+    #[test]
+    fn holders_are_the_peers_serving_the_returned_copy() {
+        let record = Record::new(vec![1; 32], b"current".to_vec());
+        let (holder_a, holder_b, stale) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let current = XorName::from_content(b"current");
+        let get_record_ok = GetRecordOk {
+            record,
+            provenance: [
+                (holder_a, current),
+                (holder_b, current),
+                (stale, XorName::from_content(b"previous")),
+            ]
+            .into_iter()
+            .collect(),
+            completed_early: true,
+            merged: false,
+        };
+
+        assert_eq!(
+            get_record_ok.holders(),
+            [holder_a, holder_b].into_iter().collect()
+        );
+        assert_eq!(get_record_ok.quorum_achieved(), 2);
+        assert_eq!(get_record_ok.divergent_peers(), vec![stale]);
+
+        let merged = GetRecordOk {
+            merged: true,
+            ..get_record_ok
+        };
+        assert!(merged.divergent_peers().is_empty());
+    }
 
     #[tokio::test]
     async fn version_file_update() {
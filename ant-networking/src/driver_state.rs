@@ -0,0 +1,132 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{cmd_channel::queue_depth, SwarmDriver};
+#[cfg(feature = "open-metrics")]
+use prometheus_client::encoding::EncodeLabelSet;
+use std::{collections::BTreeMap, time::Duration};
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct KBucketLabels {
+    /// The ilog2 distance of the kbucket
+    pub(crate) distance: u32,
+}
+
+/// The sizes of the internal state of the `SwarmDriver`, to spot the queues and maps that keep on growing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SwarmDriverState {
+    /// The number of get_closest_peers queries in flight.
+    pub pending_get_closest_peers: usize,
+    /// The number of requests sent to peers that are waiting for a response.
+    pub pending_requests: usize,
+    /// The number of get_record queries in flight.
+    pub pending_get_record: usize,
+    /// The number of put_record queries in flight.
+    pub pending_put_record: usize,
+    /// The number of outgoing connections being established.
+    pub pending_dials: u32,
+    /// The number of keys waiting to be fetched through replication.
+    pub replication_pending_keys: usize,
+    /// The number of replication fetches in progress.
+    pub replication_ongoing_fetches: usize,
+    /// How long the oldest key waiting to be fetched through replication has been waiting for.
+    pub replication_oldest_pending: Duration,
+    /// The number of cmds waiting in the local cmd channel.
+    pub local_cmd_queue_depth: usize,
+    /// The number of cmds waiting in the network cmd channel.
+    pub network_cmd_queue_depth: usize,
+    /// The number of peers in each kbucket, keyed by its ilog2 distance.
+    pub peers_per_kbucket: BTreeMap<u32, usize>,
+}
+
+impl SwarmDriverState {
+    /// The number of peers across all the kbuckets.
+    pub fn peers_in_routing_table(&self) -> usize {
+        self.peers_per_kbucket.values().sum()
+    }
+}
+
+impl SwarmDriver {
+    /// Take a snapshot of the sizes of our internal state.
+    pub(crate) fn driver_state(&mut self) -> SwarmDriverState {
+        let queue_status = self.replication_fetcher.queue_status();
+
+        let mut peers_per_kbucket = BTreeMap::new();
+        for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
+            if let Some(distance) = kbucket.range().0.ilog2() {
+                let _ = peers_per_kbucket.insert(distance, kbucket.num_entries());
+            }
+        }
+
+        SwarmDriverState {
+            pending_get_closest_peers: self.pending_get_closest_peers.len(),
+            pending_requests: self.pending_requests.len(),
+            pending_get_record: self.pending_get_record.len(),
+            pending_put_record: self.pending_put_record.len(),
+            pending_dials: self
+                .swarm
+                .network_info()
+                .connection_counters()
+                .num_pending_outgoing(),
+            replication_pending_keys: queue_status.pending_keys,
+            replication_ongoing_fetches: queue_status.ongoing_fetches,
+            replication_oldest_pending: queue_status.oldest_pending,
+            local_cmd_queue_depth: queue_depth(&self.local_cmd_sender),
+            network_cmd_queue_depth: queue_depth(&self.network_cmd_sender),
+            peers_per_kbucket,
+        }
+    }
+
+    /// Record the sizes of our internal state, reusing the given snapshot.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn record_driver_state_metrics(&self, state: &SwarmDriverState) {
+        let Some(metrics_recorder) = self.metrics_recorder.as_ref() else {
+            return;
+        };
+
+        let _ = metrics_recorder
+            .pending_get_closest_peers
+            .set(state.pending_get_closest_peers as i64);
+        let _ = metrics_recorder
+            .pending_requests
+            .set(state.pending_requests as i64);
+        let _ = metrics_recorder
+            .pending_get_record
+            .set(state.pending_get_record as i64);
+        let _ = metrics_recorder
+            .pending_put_record
+            .set(state.pending_put_record as i64);
+        let _ = metrics_recorder
+            .pending_dials
+            .set(state.pending_dials as i64);
+        for (distance, peers) in state.peers_per_kbucket.iter() {
+            let _ = metrics_recorder
+                .peers_per_kbucket
+                .get_or_create(&KBucketLabels {
+                    distance: *distance,
+                })
+                .set(*peers as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_in_routing_table_sums_the_kbuckets() {
+        let state = SwarmDriverState {
+            peers_per_kbucket: BTreeMap::from([(250, 3), (254, 20), (255, 20)]),
+            ..Default::default()
+        };
+        assert_eq!(state.peers_in_routing_table(), 43);
+        assert_eq!(SwarmDriverState::default().peers_in_routing_table(), 0);
+    }
+}
@@ -6,8 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::driver::RecordProvenance;
+use crate::record_store::RecordCategory;
 use ant_protocol::storage::TransactionAddress;
-use ant_protocol::{messages::Response, storage::RecordKind, NetworkAddress, PrettyPrintRecordKey};
+use ant_protocol::{
+    messages::Response, storage::RecordKind, ErrorCode, NetworkAddress, PrettyPrintRecordKey,
+};
 use libp2p::{
     kad::{self, QueryId, Record},
     request_response::{OutboundFailure, OutboundRequestId},
@@ -37,6 +41,8 @@ pub enum GetRecordError {
     },
     #[error("Network query timed out")]
     QueryTimeout,
+    #[error("Network query was cancelled")]
+    Cancelled,
     #[error("Record retrieved from the network does not match the provided target record.")]
     RecordDoesNotMatch(Record),
     #[error("The record kind for the split records did not match")]
@@ -50,6 +56,8 @@ pub enum GetRecordError {
     #[error("Split Record has {} different copies", result_map.len())]
     SplitRecord {
         result_map: HashMap<XorName, (Record, HashSet<PeerId>)>,
+        /// The content hash returned by each peer, to tell which peers served which copy.
+        provenance: RecordProvenance,
     },
 }
 
@@ -69,6 +77,7 @@ impl Debug for GetRecordError {
                     .finish()
             }
             Self::QueryTimeout => write!(f, "QueryTimeout"),
+            Self::Cancelled => write!(f, "Cancelled"),
             Self::RecordDoesNotMatch(record) => {
                 let pretty_key = PrettyPrintRecordKey::from(&record.key);
                 f.debug_tuple("RecordDoesNotMatch")
@@ -77,14 +86,37 @@ impl Debug for GetRecordError {
             }
             Self::RecordKindMismatch => write!(f, "RecordKindMismatch"),
             Self::RecordNotFound => write!(f, "RecordNotFound"),
-            Self::SplitRecord { result_map } => f
+            Self::SplitRecord {
+                result_map,
+                provenance,
+            } => f
                 .debug_struct("SplitRecord")
                 .field("result_map_count", &result_map.len())
+                .field("provenance", &provenance)
                 .finish(),
         }
     }
 }
 
+impl GetRecordError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::NotEnoughCopies { .. } => ErrorCode::NotEnoughPeers,
+            Self::QueryTimeout => ErrorCode::Timeout,
+            Self::Cancelled => ErrorCode::Cancelled,
+            Self::RecordDoesNotMatch(_) | Self::RecordKindMismatch => ErrorCode::InvalidRecord,
+            Self::RecordNotFound => ErrorCode::RecordNotFound,
+            Self::SplitRecord { .. } => ErrorCode::SplitRecord,
+        }
+    }
+
+    /// Whether the query may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
 /// Network Errors
 #[derive(Debug, Error)]
 pub enum NetworkError {
@@ -115,6 +147,13 @@ pub enum NetworkError {
     GetRecordError(#[from] GetRecordError),
     #[error("Record not stored by nodes, it could be invalid, else you should retry: {0:?}")]
     RecordNotStoredByNodes(NetworkAddress),
+    #[error("Record {key:?} was stored by {} peers, but {required} were required", confirmed.len())]
+    PutRecordQuorumFailed {
+        key: PrettyPrintRecordKey<'static>,
+        /// The peers that acknowledged storing the record.
+        confirmed: Vec<PeerId>,
+        required: usize,
+    },
 
     // The RecordKind that was obtained did not match with the expected one
     #[error("The RecordKind obtained from the Record did not match with the expected kind: {0}")]
@@ -151,6 +190,15 @@ pub enum NetworkError {
     #[error("Could not get enough peers ({required}) to satisfy the request, found {found}")]
     NotEnoughPeers { found: usize, required: usize },
 
+    #[error("Invalid peer access list: {0}")]
+    InvalidPeerAccessList(String),
+
+    #[error("Invalid Kademlia config: {0}")]
+    InvalidKademliaConfig(String),
+
+    #[error("Invalid WebSocket TLS certificate: {0}")]
+    InvalidWebSocketTls(String),
+
     #[error("Node Listen Address was not provided during construction")]
     ListenAddressNotProvided,
 
@@ -168,6 +216,9 @@ pub enum NetworkError {
     #[error("The oneshot::sender has been dropped")]
     SenderDropped(#[from] oneshot::error::RecvError),
 
+    #[error("The SwarmCmd channel is saturated, the low priority cmd was rejected")]
+    SwarmCmdChannelSaturated,
+
     #[error("Internal messaging channel was dropped")]
     InternalMsgChannelDropped,
 
@@ -182,6 +233,70 @@ pub enum NetworkError {
 
     #[error("Register already exists at this address")]
     RegisterAlreadyExists,
+
+    #[error("The node is decommissioning and no longer accepts records")]
+    NotAcceptingRecords,
+
+    #[error("The quota of {0} records is full")]
+    QuotaFull(RecordCategory),
+}
+
+impl NetworkError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            NetworkError::DialError(_) | NetworkError::TransportError(_) => {
+                ErrorCode::ConnectionFailed
+            }
+            NetworkError::Io(_)
+            | NetworkError::KademliaStoreError(_)
+            | NetworkError::FailedToCreateRecordStoreDir { .. } => ErrorCode::Io,
+            NetworkError::ProtocolError(err) => err.code(),
+            NetworkError::EvmPaymemt(err) => ErrorCode::of_evm_error(err),
+            NetworkError::SigningFailed(_) => ErrorCode::Internal,
+            NetworkError::GetRecordError(err) => err.code(),
+            NetworkError::RecordNotStoredByNodes(_)
+            | NetworkError::PutRecordQuorumFailed { .. } => ErrorCode::RecordNotStored,
+            NetworkError::RecordKindMismatch(_)
+            | NetworkError::InCorrectRecordHeader
+            | NetworkError::FailedToVerifyChunkProof(_) => ErrorCode::InvalidRecord,
+            NetworkError::FailedToGetSpend(_) | NetworkError::NoTransactionFoundInsideRecord(_) => {
+                ErrorCode::RecordNotFound
+            }
+            NetworkError::InvalidTransfer(_) => ErrorCode::InvalidRecord,
+            NetworkError::NoStoreCostResponses => ErrorCode::QuoteUnavailable,
+            NetworkError::NotEnoughPeers { .. } => ErrorCode::NotEnoughPeers,
+            NetworkError::InvalidPeerAccessList(_)
+            | NetworkError::InvalidKademliaConfig(_)
+            | NetworkError::InvalidWebSocketTls(_)
+            | NetworkError::ListenAddressNotProvided => ErrorCode::InvalidInput,
+            #[cfg(feature = "open-metrics")]
+            NetworkError::NetworkMetricError => ErrorCode::Internal,
+            NetworkError::OutboundError(failure) => match failure {
+                OutboundFailure::Timeout => ErrorCode::Timeout,
+                OutboundFailure::UnsupportedProtocols => ErrorCode::IncompatibleProtocol,
+                _ => ErrorCode::ConnectionFailed,
+            },
+            // The query or the response was lost along with its connection
+            NetworkError::ReceivedKademliaEventDropped { .. }
+            | NetworkError::OutgoingResponseDropped(_) => ErrorCode::ConnectionFailed,
+            NetworkError::SwarmCmdChannelSaturated => ErrorCode::Overloaded,
+            NetworkError::SenderDropped(_)
+            | NetworkError::InternalMsgChannelDropped
+            | NetworkError::ReceivedResponseDropped(_)
+            | NetworkError::BehaviourErr(_) => ErrorCode::Internal,
+            NetworkError::RegisterAlreadyExists => ErrorCode::RecordExists,
+            // Other nodes of the close group still accept it
+            NetworkError::NotAcceptingRecords | NetworkError::QuotaFull(_) => {
+                ErrorCode::RecordNotStored
+            }
+        }
+    }
+
+    /// Whether the operation may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
 }
 
 #[cfg(test)]
@@ -208,4 +323,26 @@ mod tests {
         println!("xor_name_str: {xor_name_str}");
         assert_eq!(record_str, xor_name_str);
     }
+
+    #[test]
+    fn transient_failures_are_retryable() {
+        let timeout = NetworkError::GetRecordError(GetRecordError::QueryTimeout);
+        assert_eq!(timeout.code(), ErrorCode::Timeout);
+        assert!(timeout.is_retryable());
+        assert!(NetworkError::NotEnoughPeers {
+            found: 2,
+            required: 5
+        }
+        .is_retryable());
+        assert!(NetworkError::SwarmCmdChannelSaturated.is_retryable());
+
+        let not_found = NetworkError::GetRecordError(GetRecordError::RecordNotFound);
+        assert_eq!(not_found.code(), ErrorCode::RecordNotFound);
+        assert!(!not_found.is_retryable());
+        assert!(!NetworkError::RegisterAlreadyExists.is_retryable());
+        assert_eq!(
+            NetworkError::ProtocolError(ant_protocol::Error::RequestThrottled).code(),
+            ErrorCode::Overloaded
+        );
+    }
 }
@@ -7,25 +7,26 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    driver::PendingGetClosestType, get_quorum_value, get_transactions_from_record,
-    target_arch::Instant, GetRecordCfg, GetRecordError, NetworkError, Result, SwarmDriver,
-    CLOSE_GROUP_SIZE,
+    driver::{record_provenance, PendingGetClosestType, RecordProvenance},
+    get_quorum_value, get_transactions_from_record,
+    target_arch::Instant,
+    GetRecordCfg, GetRecordError, GetRecordOk, NetworkError, Result, SwarmDriver, CLOSE_GROUP_SIZE,
 };
 use ant_protocol::{
-    storage::{try_serialize_record, RecordKind, Transaction},
+    storage::{is_early_completion_eligible, try_serialize_record, RecordKind, Transaction},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use itertools::Itertools;
 use libp2p::kad::{
     self, GetClosestPeersError, InboundRequest, PeerRecord, ProgressStep, QueryId, QueryResult,
-    QueryStats, Record, K_VALUE,
+    QueryStats, Quorum, Record, K_VALUE,
 };
 use std::collections::{hash_map::Entry, BTreeSet, HashSet};
 use tokio::sync::oneshot;
 use xor_name::XorName;
 
 impl SwarmDriver {
-    pub(super) fn handle_kad_event(&mut self, kad_event: libp2p::kad::Event) -> Result<()> {
+    pub(crate) fn handle_kad_event(&mut self, kad_event: libp2p::kad::Event) -> Result<()> {
         let start = Instant::now();
         let event_string;
 
@@ -62,6 +63,27 @@ impl SwarmDriver {
                                     .send(current_closest)
                                     .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
                             }
+                            PendingGetClosestType::PutRecord {
+                                record,
+                                quorum,
+                                sender,
+                            } => self.put_record_to_closest_peers(
+                                record,
+                                current_closest,
+                                quorum,
+                                sender,
+                            ),
+                            PendingGetClosestType::Prewarm(batch_id) => {
+                                // Enough peers to warm the buckets, no need to carry on with the query.
+                                if let Some(mut query) =
+                                    self.swarm.behaviour_mut().kademlia.query_mut(&id)
+                                {
+                                    query.finish();
+                                }
+                                self.network_discovery
+                                    .handle_get_closest_query(current_closest);
+                                self.handle_prewarm_lookup_completed(batch_id);
+                            }
                         }
                     }
                 } else {
@@ -111,6 +133,16 @@ impl SwarmDriver {
                             .send(current_closest)
                             .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
                     }
+                    PendingGetClosestType::PutRecord {
+                        record,
+                        quorum,
+                        sender,
+                    } => self.put_record_to_closest_peers(record, current_closest, quorum, sender),
+                    PendingGetClosestType::Prewarm(batch_id) => {
+                        self.network_discovery
+                            .handle_get_closest_query(current_closest);
+                        self.handle_prewarm_lookup_completed(batch_id);
+                    }
                 }
             }
 
@@ -139,7 +171,7 @@ impl SwarmDriver {
             } => {
                 event_string = "kad_event::get_record::finished_no_additional";
                 debug!("Query task {id:?} of get_record completed with {stats:?} - {step:?} - {cache_candidates:?}");
-                self.handle_get_record_finished(id, step)?;
+                self.handle_get_record_finished(id, stats, step)?;
             }
             kad::Event::OutboundQueryProgressed {
                 id,
@@ -184,9 +216,8 @@ impl SwarmDriver {
                 stats,
                 step,
             } => {
-                // Currently, only `client` calls `put_record_to` to upload data.
-                // The result of such operation is not critical to client in general.
-                // However, if client keeps receiving error responses, it may indicating:
+                // Not all the peers acknowledged the store, the write quorum decides whether that is enough.
+                // If client keeps receiving error responses, it may indicating:
                 //   1, Client itself is with slow connection
                 //   OR
                 //   2, The payee node selected could be in trouble
@@ -213,6 +244,7 @@ impl SwarmDriver {
                 };
                 error!("Query task {id:?} failed put record {:?} {:?}, required quorum {quorum}, stored on {success:?}, {stats:?} - {step:?}",
                        PrettyPrintRecordKey::from(key), event_string);
+                self.handle_put_record_error(id, &put_record_err)?;
             }
             kad::Event::OutboundQueryProgressed {
                 id,
@@ -225,6 +257,7 @@ impl SwarmDriver {
                     "Query task {id:?} put record {:?} ok, {stats:?} - {step:?}",
                     PrettyPrintRecordKey::from(&put_record_ok.key)
                 );
+                self.handle_put_record_ok(id)?;
             }
             // Shall no longer receive this event
             kad::Event::OutboundQueryProgressed {
@@ -248,11 +281,17 @@ impl SwarmDriver {
                 event_string = "kad_event::RoutingUpdated";
                 if is_new_peer {
                     self.update_on_peer_addition(peer, addresses);
+                    self.check_close_group_bursts(peer);
 
                     // This should only happen once
                     if self.bootstrap.notify_new_peer() {
                         info!("Performing the first bootstrap");
                         self.trigger_network_discovery();
+                        // Resume the replication restored from the warm standby snapshot, if any
+                        let keys_to_fetch = self.replication_fetcher.next_keys_to_fetch();
+                        if !keys_to_fetch.is_empty() {
+                            self.send_keys_to_fetch(keys_to_fetch);
+                        }
                     }
                 }
 
@@ -263,11 +302,23 @@ impl SwarmDriver {
                 }
             }
             kad::Event::InboundRequest {
-                request: InboundRequest::PutRecord { .. },
+                request: InboundRequest::PutRecord { source, record, .. },
             } => {
                 event_string = "kad_event::InboundRequest::PutRecord";
-                // Ignored to reduce logging. When `Record filtering` is enabled,
-                // the `record` variable will contain the content for further validation before put.
+                // With the record filtering enabled, the record is handed to us rather than put to the store,
+                // for it to be validated knowing which peer put it.
+                if let Some(record) = record {
+                    let record_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                    if let Err(err) = self
+                        .swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .store_mut()
+                        .put_unverified(record, source)
+                    {
+                        warn!("Record {record_key:?} put by {source:?} not accepted: {err:?}");
+                    }
+                }
             }
             kad::Event::InboundRequest {
                 request: InboundRequest::FindNode { .. },
@@ -340,11 +391,14 @@ impl SwarmDriver {
     ///   check fails.
     /// - if multiple content hashes are found, we return a SplitRecord Error
     ///   And then we stop the kad query as we are done here.
+    ///
+    /// Records whose kind allows it in `EARLY_COMPLETION_POLICY` and that verify against their own
+    /// key complete on the first copy, as though the query had been made with `Quorum::One`.
     fn accumulate_get_record_found(
         &mut self,
         query_id: QueryId,
         peer_record: PeerRecord,
-        _stats: QueryStats,
+        stats: QueryStats,
         step: ProgressStep,
     ) -> Result<()> {
         let peer_id = if let Some(peer_id) = peer_record.peer {
@@ -384,7 +438,18 @@ impl SwarmDriver {
             let expected_answers = get_quorum_value(&cfg.get_quorum);
             debug!("Expecting {expected_answers:?} answers for record {pretty_key:?} task {query_id:?}, received {responded_peers} so far");
 
-            if responded_peers >= expected_answers {
+            // A self-verifiable record can complete the query on its first copy, as long as no
+            // diverging copy has been seen yet. `Quorum::All` is never short-circuited, as the
+            // caller explicitly wants every copy (e.g. for double spend detection).
+            let early_completion = responded_peers < expected_answers
+                && result_map.len() == 1
+                && !matches!(cfg.get_quorum, Quorum::All)
+                && is_early_completion_eligible(&peer_record.record);
+            if early_completion {
+                debug!("For record {pretty_key:?} task {query_id:?}, completing early with a self-verified copy from {peer_id:?}");
+            }
+
+            if responded_peers >= expected_answers || early_completion {
                 if !cfg.expected_holders.is_empty() {
                     debug!("For record {pretty_key:?} task {query_id:?}, fetch completed with non-responded expected holders {:?}", cfg.expected_holders);
                 }
@@ -392,9 +457,18 @@ impl SwarmDriver {
 
                 // Remove the query task and consume the variables.
                 let (_key, senders, result_map, _) = entry.remove();
+                let provenance = record_provenance(&result_map);
+
+                let copies = provenance.len();
 
                 if result_map.len() == 1 {
-                    Self::send_record_after_checking_target(senders, peer_record.record, &cfg)?;
+                    let result = Self::record_after_checking_target(
+                        peer_record.record,
+                        provenance,
+                        true,
+                        &cfg,
+                    );
+                    self.send_get_record_result(senders, result, copies, &stats)?;
                 } else {
                     debug!("For record {pretty_key:?} task {query_id:?}, fetch completed with split record");
                     let mut accumulated_transactions = BTreeSet::new();
@@ -425,20 +499,19 @@ impl SwarmDriver {
                             publisher: None,
                             expires: None,
                         };
-                        for sender in senders {
-                            let new_accumulated_record = new_accumulated_record.clone();
-
-                            sender
-                                .send(Ok(new_accumulated_record))
-                                .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                        }
+                        let get_record_ok = GetRecordOk {
+                            record: new_accumulated_record,
+                            provenance,
+                            completed_early: true,
+                            merged: true,
+                        };
+                        self.send_get_record_result(senders, Ok(get_record_ok), copies, &stats)?;
                     } else {
-                        for sender in senders {
-                            let result_map = result_map.clone();
-                            sender
-                                .send(Err(GetRecordError::SplitRecord { result_map }))
-                                .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                        }
+                        let split_record = GetRecordError::SplitRecord {
+                            result_map,
+                            provenance,
+                        };
+                        self.send_get_record_result(senders, Err(split_record), copies, &stats)?;
                     }
                 }
 
@@ -469,37 +542,41 @@ impl SwarmDriver {
     ///     RecordNotFound if the result_map is empty.
     ///     NotEnoughCopies if there is only a single content hash version.
     ///     SplitRecord if there are multiple content hash versions.
-    fn handle_get_record_finished(&mut self, query_id: QueryId, step: ProgressStep) -> Result<()> {
+    fn handle_get_record_finished(
+        &mut self,
+        query_id: QueryId,
+        stats: QueryStats,
+        step: ProgressStep,
+    ) -> Result<()> {
         // return error if the entry cannot be found
         if let Some((r_key, senders, result_map, cfg)) = self.pending_get_record.remove(&query_id) {
             let num_of_versions = result_map.len();
             let data_key_address = NetworkAddress::from_record_key(&r_key);
+            let provenance = record_provenance(&result_map);
+            let copies = provenance.len();
+            self.strike_missing_holders(&r_key, cfg.expected_holders.clone());
 
             // we have a split record, return it
             if num_of_versions > 1 {
                 warn!(
                     "Multiple versions ({num_of_versions}) found for record {data_key_address:?}!"
                 );
-                for sender in senders {
-                    sender
-                        .send(Err(GetRecordError::SplitRecord {
-                            result_map: result_map.clone(),
-                        }))
-                        .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                }
-
-                return Ok(());
+                let split_record = GetRecordError::SplitRecord {
+                    result_map,
+                    provenance,
+                };
+                return self.send_get_record_result(senders, Err(split_record), copies, &stats);
             }
 
             // we have no results, bail
             if num_of_versions == 0 {
                 debug!("No versions found for record {data_key_address:?}!");
-                for sender in senders {
-                    sender
-                        .send(Err(GetRecordError::RecordNotFound))
-                        .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                }
-                return Ok(());
+                return self.send_get_record_result(
+                    senders,
+                    Err(GetRecordError::RecordNotFound),
+                    copies,
+                    &stats,
+                );
             }
 
             // if we have searched thoroughly, we can return the record
@@ -508,7 +585,12 @@ impl SwarmDriver {
                     trace!("one version found for record {data_key_address:?}!");
 
                     if peers.len() >= get_quorum_value(&cfg.get_quorum) {
-                        Ok(record.clone())
+                        Ok(GetRecordOk {
+                            record: record.clone(),
+                            provenance,
+                            completed_early: false,
+                            merged: false,
+                        })
                     } else {
                         Err(GetRecordError::NotEnoughCopies {
                             record: record.clone(),
@@ -520,11 +602,7 @@ impl SwarmDriver {
                     debug!("Getting record task {query_id:?} completed with step count {:?}, but no copy found.", step.count);
                     Err(GetRecordError::RecordNotFound)
                 };
-                for sender in senders {
-                    sender
-                        .send(result.clone())
-                        .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                }
+                self.send_get_record_result(senders, result, copies, &stats)?;
             }
         } else {
             debug!("Can't locate query task {query_id:?} during GetRecord finished. We might have already returned the result to the sender.");
@@ -543,13 +621,13 @@ impl SwarmDriver {
         &mut self,
         query_id: QueryId,
         get_record_err: kad::GetRecordError,
-        _stats: QueryStats,
+        stats: QueryStats,
         _step: ProgressStep,
     ) -> Result<()> {
         match &get_record_err {
             kad::GetRecordError::NotFound { .. } | kad::GetRecordError::QuorumFailed { .. } => {
                 // return error if the entry cannot be found
-                let (_key, senders, _, cfg) =
+                let (key, senders, _, cfg) =
                 self.pending_get_record.remove(&query_id).ok_or_else(|| {
                     debug!("Can't locate query task {query_id:?}, it has likely been completed already.");
                     NetworkError::ReceivedKademliaEventDropped {
@@ -562,12 +640,14 @@ impl SwarmDriver {
                     info!("Get record task {query_id:?} failed with error {get_record_err:?}");
                 } else {
                     debug!("Get record task {query_id:?} failed with {:?} expected holders not responded, error {get_record_err:?}", cfg.expected_holders);
+                    self.strike_missing_holders(&key, cfg.expected_holders);
                 }
-                for sender in senders {
-                    sender
-                        .send(Err(GetRecordError::RecordNotFound))
-                        .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                }
+                self.send_get_record_result(
+                    senders,
+                    Err(GetRecordError::RecordNotFound),
+                    0,
+                    &stats,
+                )?;
             }
             kad::GetRecordError::Timeout { key } => {
                 // return error if the entry cannot be found
@@ -584,6 +664,7 @@ impl SwarmDriver {
                     })?;
 
                 let required_response_count = get_quorum_value(&cfg.get_quorum);
+                let copies = record_provenance(&result_map).len();
 
                 // if we've a split over the result xorname, then we don't attempt to resolve this here.
                 // Retry and resolve through normal flows without a timeout.
@@ -592,50 +673,72 @@ impl SwarmDriver {
                     warn!(
                         "Get record task {query_id:?} for {pretty_key:?} timed out with split result map"
                     );
-                    for sender in senders {
-                        sender
-                            .send(Err(GetRecordError::QueryTimeout))
-                            .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                    }
-
-                    return Ok(());
+                    return self.send_get_record_result(
+                        senders,
+                        Err(GetRecordError::QueryTimeout),
+                        copies,
+                        &stats,
+                    );
                 }
 
                 // if we have enough responses here, we can return the record
                 if let Some((record, peers)) = result_map.values().next() {
                     if peers.len() >= required_response_count {
-                        Self::send_record_after_checking_target(senders, record.clone(), &cfg)?;
-                        return Ok(());
+                        let result = Self::record_after_checking_target(
+                            record.clone(),
+                            record_provenance(&result_map),
+                            false,
+                            &cfg,
+                        );
+                        return self.send_get_record_result(senders, result, copies, &stats);
                     }
                 }
 
                 warn!("Get record task {query_id:?} for {pretty_key:?} returned insufficient responses. {:?} did not return record", cfg.expected_holders);
-                for sender in senders {
-                    // Otherwise report the timeout
-                    sender
-                        .send(Err(GetRecordError::QueryTimeout))
-                        .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
-                }
+                // Otherwise report the timeout
+                self.send_get_record_result(
+                    senders,
+                    Err(GetRecordError::QueryTimeout),
+                    copies,
+                    &stats,
+                )?;
             }
         }
 
         Ok(())
     }
 
-    fn send_record_after_checking_target(
-        senders: Vec<oneshot::Sender<std::result::Result<Record, GetRecordError>>>,
+    fn record_after_checking_target(
         record: Record,
+        provenance: RecordProvenance,
+        completed_early: bool,
         cfg: &GetRecordCfg,
-    ) -> Result<()> {
-        let res = if cfg.does_target_match(&record) {
-            Ok(record)
+    ) -> std::result::Result<GetRecordOk, GetRecordError> {
+        if cfg.does_target_match(&record) {
+            Ok(GetRecordOk {
+                record,
+                provenance,
+                completed_early,
+                merged: false,
+            })
         } else {
             Err(GetRecordError::RecordDoesNotMatch(record))
-        };
+        }
+    }
+
+    /// Sends the result of a completed GetRecord query to all of its waiters, recording the query metrics.
+    fn send_get_record_result(
+        &self,
+        senders: Vec<oneshot::Sender<std::result::Result<GetRecordOk, GetRecordError>>>,
+        result: std::result::Result<GetRecordOk, GetRecordError>,
+        copies: usize,
+        stats: &QueryStats,
+    ) -> Result<()> {
+        self.record_get_record_metrics(&result, copies, stats);
 
         for sender in senders {
             sender
-                .send(res.clone())
+                .send(result.clone())
                 .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
         }
 
@@ -7,13 +7,18 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod kad;
+mod put_record;
 mod request_response;
 mod swarm;
 
-use crate::{driver::SwarmDriver, error::Result};
+use crate::{
+    driver::SwarmDriver, error::Result, routing_health::RoutingTableHealth,
+    sybil_guard::SybilSuspicion,
+};
+use bytes::Bytes;
 use core::fmt;
 use custom_debug::Debug as CustomDebug;
-#[cfg(feature = "local")]
+#[cfg(not(target_arch = "wasm32"))]
 use libp2p::mdns;
 use libp2p::{
     kad::{Addresses, Record, RecordKey, K_VALUE},
@@ -26,6 +31,7 @@ use ant_evm::PaymentQuote;
 use ant_protocol::CLOSE_GROUP_SIZE;
 use ant_protocol::{
     messages::{Query, Request, Response},
+    version::ProtocolMismatch,
     NetworkAddress, PrettyPrintRecordKey,
 };
 #[cfg(feature = "open-metrics")]
@@ -46,11 +52,14 @@ pub(super) enum NodeEvent {
     Upnp(libp2p::upnp::Event),
     MsgReceived(libp2p::request_response::Event<Request, Response>),
     Kademlia(libp2p::kad::Event),
-    #[cfg(feature = "local")]
+    #[cfg(not(target_arch = "wasm32"))]
     Mdns(Box<mdns::Event>),
     Identify(Box<libp2p::identify::Event>),
     RelayClient(Box<libp2p::relay::client::Event>),
+    Dcutr(Box<libp2p::dcutr::Event>),
     RelayServer(Box<libp2p::relay::Event>),
+    Autonat(Box<libp2p::autonat::Event>),
+    Gossipsub(Box<libp2p::gossipsub::Event>),
     Void(void::Void),
 }
 
@@ -73,7 +82,7 @@ impl From<libp2p::kad::Event> for NodeEvent {
     }
 }
 
-#[cfg(feature = "local")]
+#[cfg(not(target_arch = "wasm32"))]
 impl From<mdns::Event> for NodeEvent {
     fn from(event: mdns::Event) -> Self {
         NodeEvent::Mdns(Box::new(event))
@@ -90,12 +99,30 @@ impl From<libp2p::relay::client::Event> for NodeEvent {
         NodeEvent::RelayClient(Box::new(event))
     }
 }
+impl From<libp2p::dcutr::Event> for NodeEvent {
+    fn from(event: libp2p::dcutr::Event) -> Self {
+        NodeEvent::Dcutr(Box::new(event))
+    }
+}
+
 impl From<libp2p::relay::Event> for NodeEvent {
     fn from(event: libp2p::relay::Event) -> Self {
         NodeEvent::RelayServer(Box::new(event))
     }
 }
 
+impl From<libp2p::autonat::Event> for NodeEvent {
+    fn from(event: libp2p::autonat::Event) -> Self {
+        NodeEvent::Autonat(Box::new(event))
+    }
+}
+
+impl From<libp2p::gossipsub::Event> for NodeEvent {
+    fn from(event: libp2p::gossipsub::Event) -> Self {
+        NodeEvent::Gossipsub(Box::new(event))
+    }
+}
+
 impl From<void::Void> for NodeEvent {
     fn from(event: void::Void) -> Self {
         NodeEvent::Void(event)
@@ -133,26 +160,40 @@ pub enum NetworkEvent {
     PeerWithUnsupportedProtocol {
         our_protocol: String,
         their_protocol: String,
+        /// Why their protocol is not compatible with ours
+        mismatch: ProtocolMismatch,
     },
     /// The records bearing these keys are to be fetched from the holder or the network
     KeysToFetchForReplication(Vec<(PeerId, RecordKey)>),
     /// Started listening on a new address
     NewListenAddr(Multiaddr),
-    /// Report unverified record
-    UnverifiedRecord(Record),
+    /// Report unverified record, put to us by the peer, or by ourselves if `None`
+    UnverifiedRecord(Record, Option<PeerId>),
     /// Terminate Node on unrecoverable errors
     TerminateNode { reason: TerminateNodeReason },
     /// List of peer nodes that failed to fetch replication copy from.
     FailedToFetchHolders(BTreeSet<PeerId>),
     /// Quotes to be verified
     QuoteVerification { quotes: Vec<(PeerId, PaymentQuote)> },
+    /// Gossipsub message received on a topic we're subscribed to
+    GossipsubMsgReceived {
+        /// Topic the message was published on
+        topic: String,
+        /// The raw bytes of the received message
+        msg: Bytes,
+    },
+    /// The routing table fell below the health thresholds, we're re-bootstrapping
+    ConnectivityDegraded(RoutingTableHealth),
+    /// The routing table is back above the health thresholds
+    ConnectivityRestored(RoutingTableHealth),
+    /// The sybil heuristics spotted something, see `SybilGuardConfig`
+    SybilSuspected(SybilSuspicion),
 }
 
 /// Terminate node for the following reason
 #[derive(Debug, Clone)]
 pub enum TerminateNodeReason {
     HardDiskWriteError,
-    UpnpGatewayNotFound,
 }
 
 // Manually implement Debug as `#[debug(with = "unverified_record_fmt")]` not working as expected.
@@ -177,6 +218,7 @@ impl Debug for NetworkEvent {
             NetworkEvent::PeerWithUnsupportedProtocol {
                 our_protocol,
                 their_protocol,
+                ..
             } => {
                 write!(f, "NetworkEvent::PeerWithUnsupportedProtocol({our_protocol:?}, {their_protocol:?})")
             }
@@ -187,7 +229,7 @@ impl Debug for NetworkEvent {
             NetworkEvent::NewListenAddr(addr) => {
                 write!(f, "NetworkEvent::NewListenAddr({addr:?})")
             }
-            NetworkEvent::UnverifiedRecord(record) => {
+            NetworkEvent::UnverifiedRecord(record, _source) => {
                 let pretty_key = PrettyPrintRecordKey::from(&record.key);
                 write!(f, "NetworkEvent::UnverifiedRecord({pretty_key:?})")
             }
@@ -204,6 +246,18 @@ impl Debug for NetworkEvent {
                     quotes.len()
                 )
             }
+            NetworkEvent::GossipsubMsgReceived { topic, .. } => {
+                write!(f, "NetworkEvent::GossipsubMsgReceived({topic})")
+            }
+            NetworkEvent::ConnectivityDegraded(health) => {
+                write!(f, "NetworkEvent::ConnectivityDegraded({health:?})")
+            }
+            NetworkEvent::ConnectivityRestored(health) => {
+                write!(f, "NetworkEvent::ConnectivityRestored({health:?})")
+            }
+            NetworkEvent::SybilSuspected(suspicion) => {
+                write!(f, "NetworkEvent::SybilSuspected({suspicion:?})")
+            }
         }
     }
 }
@@ -265,6 +319,8 @@ impl SwarmDriver {
     /// Update state on removal of a peer from the routing table.
     pub(crate) fn update_on_peer_removal(&mut self, removed_peer: PeerId) {
         self.peers_in_rt = self.peers_in_rt.saturating_sub(1);
+        let _ = self.peer_capabilities.remove(&removed_peer);
+        self.replication_fetcher.remove_holder(&removed_peer);
 
         // ensure we disconnect bad peer
         // err result just means no connections were open
@@ -0,0 +1,199 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    driver::PendingPutRecordEntry, sort_peers_by_address, NetworkError, Result, SwarmDriver,
+};
+use ant_protocol::{NetworkAddress, PrettyPrintRecordKey};
+use libp2p::{
+    kad::{self, QueryId, Quorum, Record},
+    PeerId,
+};
+use tokio::sync::oneshot;
+
+impl SwarmDriver {
+    /// PUT the record to the `KademliaConfig::replication_factor` closest peers out of the provided ones.
+    pub(crate) fn put_record_to_closest_peers(
+        &mut self,
+        record: Record,
+        closest_peers: Vec<PeerId>,
+        quorum: Quorum,
+        sender: oneshot::Sender<Result<Vec<PeerId>>>,
+    ) {
+        let address = NetworkAddress::from_record_key(&record.key);
+        let peers =
+            match sort_peers_by_address(&closest_peers, &address, self.replication_factor.get()) {
+                Ok(sorted_peers) => sorted_peers.into_iter().cloned().collect(),
+                // Too few peers found to sort, leave it to the write quorum to decide whether they are enough.
+                Err(_) => closest_peers,
+            };
+
+        self.put_record_to_peers(record, peers, quorum, sender);
+    }
+
+    /// PUT the record to the provided peers, tracking the query until the store acknowledgements are in.
+    ///
+    /// The kad query is made with `Quorum::All`, so that it only completes successfully once every peer has
+    /// acknowledged. Otherwise kad reports the peers that did acknowledge via the error, which allows us to return
+    /// exactly where the record landed. The requested `quorum` is evaluated by us on completion.
+    pub(crate) fn put_record_to_peers(
+        &mut self,
+        record: Record,
+        peers: Vec<PeerId>,
+        quorum: Quorum,
+        sender: oneshot::Sender<Result<Vec<PeerId>>>,
+    ) {
+        let key = record.key.clone();
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+
+        let required = put_quorum_value(&quorum, peers.len());
+        if peers.len() < required {
+            warn!(
+                "Only {} peers to PUT record {pretty_key:?} to, too few for write quorum {quorum:?}",
+                peers.len()
+            );
+            if let Err(err) = sender.send(Err(NetworkError::PutRecordQuorumFailed {
+                key: pretty_key,
+                confirmed: vec![],
+                required,
+            })) {
+                error!("Could not send response to PutRecord cmd: {err:?}");
+            }
+            return;
+        }
+
+        let query_id = self.swarm.behaviour_mut().kademlia.put_record_to(
+            record,
+            peers.clone().into_iter(),
+            Quorum::All,
+        );
+        debug!(
+            "Sent record {pretty_key:?} to {} peers with write quorum {quorum:?}. Request id: {query_id:?}",
+            peers.len()
+        );
+
+        let _ = self
+            .pending_put_record
+            .insert(query_id, (key, peers, quorum, sender));
+    }
+
+    /// Every peer the record was sent to acknowledged storing it.
+    pub(super) fn handle_put_record_ok(&mut self, query_id: QueryId) -> Result<()> {
+        let (key, peers, quorum, sender) = self.remove_pending_put_record(query_id)?;
+        Self::send_put_record_result(key, peers.clone(), peers.len(), &quorum, sender)
+    }
+
+    /// Not every peer acknowledged storing the record. Returns the acknowledged peers if they satisfy the write
+    /// quorum, otherwise errors out with them.
+    pub(super) fn handle_put_record_error(
+        &mut self,
+        query_id: QueryId,
+        put_record_err: &kad::PutRecordError,
+    ) -> Result<()> {
+        let success = match put_record_err {
+            kad::PutRecordError::QuorumFailed { success, .. }
+            | kad::PutRecordError::Timeout { success, .. } => success,
+        };
+        let (key, peers, quorum, sender) = self.remove_pending_put_record(query_id)?;
+
+        let not_confirmed = peers
+            .iter()
+            .filter(|peer| !success.contains(peer))
+            .collect::<Vec<_>>();
+        debug!(
+            "Query task {query_id:?} for record {:?} not acknowledged by {not_confirmed:?}",
+            PrettyPrintRecordKey::from(&key)
+        );
+
+        Self::send_put_record_result(key, success.clone(), peers.len(), &quorum, sender)
+    }
+
+    fn remove_pending_put_record(&mut self, query_id: QueryId) -> Result<PendingPutRecordEntry> {
+        self.pending_put_record.remove(&query_id).ok_or_else(|| {
+            debug!(
+                "Can't locate put record task {query_id:?}, it has likely been completed already."
+            );
+            NetworkError::ReceivedKademliaEventDropped {
+                query_id,
+                event: "PutRecord".to_string(),
+            }
+        })
+    }
+
+    fn send_put_record_result(
+        key: kad::RecordKey,
+        confirmed: Vec<PeerId>,
+        targets: usize,
+        quorum: &Quorum,
+        sender: oneshot::Sender<Result<Vec<PeerId>>>,
+    ) -> Result<()> {
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+        let required = put_quorum_value(quorum, targets);
+
+        let result = if confirmed.len() >= required {
+            debug!(
+                "Record {pretty_key:?} stored by {}/{targets} peers, satisfying write quorum {quorum:?}",
+                confirmed.len()
+            );
+            Ok(confirmed)
+        } else {
+            warn!(
+                "Record {pretty_key:?} stored by {}/{targets} peers, failing write quorum {quorum:?}",
+                confirmed.len()
+            );
+            Err(NetworkError::PutRecordQuorumFailed {
+                key: pretty_key,
+                confirmed,
+                required,
+            })
+        };
+
+        sender
+            .send(result)
+            .map_err(|_| NetworkError::InternalMsgChannelDropped)
+    }
+}
+
+/// Get the number of store acknowledgements required by the write quorum, given the number of peers the record was
+/// sent to. It can be more than the peers, for a `Quorum::N` out of reach.
+fn put_quorum_value(quorum: &Quorum, targets: usize) -> usize {
+    match quorum {
+        Quorum::One => 1,
+        Quorum::Majority => targets / 2 + 1,
+        Quorum::All => targets.max(1),
+        Quorum::N(n) => n.get(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroUsize;
+
+    #[test]
+    fn put_quorum_is_evaluated_against_targets() {
+        assert_eq!(put_quorum_value(&Quorum::One, 7), 1);
+        assert_eq!(put_quorum_value(&Quorum::Majority, 7), 4);
+        assert_eq!(put_quorum_value(&Quorum::All, 7), 7);
+        assert_eq!(put_quorum_value(&Quorum::All, 0), 1);
+
+        let two = Quorum::N(NonZeroUsize::new(2).expect("2 is non-zero"));
+        assert_eq!(put_quorum_value(&two, 7), 2);
+    }
+
+    #[test]
+    fn put_quorum_is_not_lowered_to_fewer_targets() {
+        let five = Quorum::N(NonZeroUsize::new(5).expect("5 is non-zero"));
+        assert_eq!(put_quorum_value(&five, 1), 5);
+        assert!(put_quorum_value(&five, 4) > 4);
+        assert!(put_quorum_value(&five, 5) <= 5);
+
+        assert!(put_quorum_value(&Quorum::One, 0) > 0);
+        assert!(put_quorum_value(&Quorum::Majority, 0) > 0);
+    }
+}
@@ -6,9 +6,13 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosAction;
 use crate::{
-    cmd::NetworkSwarmCmd, log_markers::Marker, MsgResponder, NetworkError, NetworkEvent,
-    SwarmDriver,
+    cmd::NetworkSwarmCmd,
+    log_markers::Marker,
+    rate_limiter::{throttled_response, RequestKind},
+    MsgResponder, NetworkError, NetworkEvent, SwarmDriver,
 };
 use ant_protocol::{
     messages::{CmdResponse, Request, Response},
@@ -32,6 +36,48 @@ impl SwarmDriver {
                     ..
                 } => {
                     debug!("Received request {request_id:?} from peer {peer:?}, req: {request:?}");
+                    self.connection_limiter.on_peer_activity(&peer);
+                    if !self.is_peer_allowed(&peer) {
+                        warn!("Dropping request {request_id:?} from peer {peer:?}, not allowed by the peer access list");
+                        return Ok(());
+                    }
+                    if let (Some(limiter), Some(kind)) = (
+                        self.request_rate_limiter.as_mut(),
+                        RequestKind::of(&request),
+                    ) {
+                        if !limiter.allow(peer, kind) {
+                            warn!("Throttling {kind} request {request_id:?} from peer {peer:?}, over its budget");
+                            if let Some(response) = throttled_response(
+                                &request,
+                                NetworkAddress::from_peer(self.self_peer_id),
+                            ) {
+                                self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
+                                    resp: response,
+                                    channel: MsgResponder::FromPeer(channel),
+                                });
+                            }
+                            return Ok(());
+                        }
+                    }
+                    #[cfg(feature = "chaos")]
+                    match self
+                        .chaos
+                        .on_request(&request, NetworkAddress::from_peer(self.self_peer_id))
+                    {
+                        ChaosAction::Handle => {}
+                        ChaosAction::Drop => {
+                            warn!("Chaos: dropping request {request_id:?} from peer {peer:?}");
+                            return Ok(());
+                        }
+                        ChaosAction::Respond(response) => {
+                            warn!("Chaos: refusing request {request_id:?} from peer {peer:?}");
+                            self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse {
+                                resp: *response,
+                                channel: MsgResponder::FromPeer(channel),
+                            });
+                            return Ok(());
+                        }
+                    }
                     // If the request is replication or quote verification,
                     // we can handle it and send the OK response here.
                     // As the handle result is unimportant to the sender.
@@ -91,6 +137,7 @@ impl SwarmDriver {
                     response,
                 } => {
                     debug!("Got response {request_id:?} from peer {peer:?}, res: {response}.");
+                    self.connection_limiter.on_peer_activity(&peer);
                     if let Some(sender) = self.pending_requests.remove(&request_id) {
                         // The sender will be provided if the caller (Requester) is awaiting for a response
                         // at the call site.
@@ -198,7 +245,7 @@ impl SwarmDriver {
         if keys_to_fetch.is_empty() {
             debug!("no waiting keys to fetch from the network");
         } else {
-            self.send_event(NetworkEvent::KeysToFetchForReplication(keys_to_fetch));
+            self.send_keys_to_fetch(keys_to_fetch);
         }
     }
 }
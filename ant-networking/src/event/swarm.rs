@@ -10,8 +10,12 @@ use crate::{
     event::NodeEvent, multiaddr_get_ip, multiaddr_is_global, multiaddr_strip_p2p,
     relay_manager::is_a_relayed_peer, target_arch::Instant, NetworkEvent, Result, SwarmDriver,
 };
-use ant_protocol::version::{IDENTIFY_NODE_VERSION_STR, IDENTIFY_PROTOCOL_STR};
-#[cfg(feature = "local")]
+use ant_protocol::version::{
+    check_protocol_compatibility, NodeCapabilities, IDENTIFY_NODE_VERSION_STR,
+    IDENTIFY_PROTOCOL_STR,
+};
+use ant_protocol::{NetworkAddress, CLOSE_GROUP_SIZE};
+#[cfg(not(target_arch = "wasm32"))]
 use libp2p::mdns;
 #[cfg(feature = "open-metrics")]
 use libp2p::metrics::Recorder;
@@ -19,10 +23,7 @@ use libp2p::{
     core::ConnectedPoint,
     kad::K_VALUE,
     multiaddr::Protocol,
-    swarm::{
-        dial_opts::{DialOpts, PeerCondition},
-        ConnectionId, DialError, SwarmEvent,
-    },
+    swarm::{dial_opts::PeerCondition, ConnectionId, DialError, SwarmEvent},
     Multiaddr, PeerId, TransportError,
 };
 use std::collections::HashSet;
@@ -60,12 +61,41 @@ impl SwarmDriver {
                 info!(?event, "relay client event");
 
                 if let libp2p::relay::client::Event::ReservationReqAccepted {
-                    relay_peer_id, ..
+                    relay_peer_id,
+                    renewal,
+                    ..
                 } = *event
                 {
                     if let Some(relay_manager) = self.relay_manager.as_mut() {
-                        relay_manager
-                            .on_successful_reservation_by_client(&relay_peer_id, &mut self.swarm);
+                        relay_manager.on_successful_reservation_by_client(
+                            &relay_peer_id,
+                            renewal,
+                            &mut self.swarm,
+                        );
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(NodeEvent::Dcutr(event)) => {
+                // Records the outcomes of the hole punching
+                #[cfg(feature = "open-metrics")]
+                if let Some(metrics_recorder) = &self.metrics_recorder {
+                    metrics_recorder.record(&(*event));
+                }
+                event_string = "dcutr_event";
+
+                let libp2p::dcutr::Event {
+                    remote_peer_id,
+                    result,
+                } = *event;
+                match result {
+                    Ok(connection_id) => {
+                        info!("Upgraded the relayed connection to {remote_peer_id:?} to the direct connection {connection_id:?}");
+                        self.close_relayed_connections(&remote_peer_id);
+                    }
+                    Err(err) => {
+                        debug!(
+                            "Failed to upgrade the relayed connection to {remote_peer_id:?}: {err}"
+                        );
                     }
                 }
             }
@@ -77,11 +107,41 @@ impl SwarmDriver {
                 }
                 event_string = "upnp_event";
                 info!(?upnp_event, "UPnP event");
-                if let libp2p::upnp::Event::GatewayNotFound = upnp_event {
-                    warn!("UPnP is not enabled/supported on the gateway. Please rerun without the `--upnp` flag");
-                    self.send_event(NetworkEvent::TerminateNode {
-                        reason: crate::event::TerminateNodeReason::UpnpGatewayNotFound,
-                    });
+                match upnp_event {
+                    libp2p::upnp::Event::GatewayNotFound => {
+                        warn!("UPnP is not enabled/supported on the gateway, the port could not be mapped");
+                        self.on_port_mapping_failure();
+                    }
+                    libp2p::upnp::Event::NonRoutableGateway => {
+                        warn!("The UPnP gateway is not exposed to the public internet, the port could not be mapped");
+                        self.on_port_mapping_failure();
+                    }
+                    // The mapping is renewed by the behaviour before its lease expires, and its
+                    // external address is confirmed to the swarm, i.e. reported through identify.
+                    libp2p::upnp::Event::NewExternalAddr(addr) => {
+                        info!("UPnP mapped the listen port to the external address {addr}");
+                    }
+                    libp2p::upnp::Event::ExpiredExternalAddr(addr) => {
+                        warn!("UPnP mapping to the external address {addr} expired");
+                    }
+                }
+            }
+
+            SwarmEvent::Behaviour(NodeEvent::Gossipsub(event)) => {
+                event_string = "gossipsub_event";
+                match *event {
+                    libp2p::gossipsub::Event::Message { message, .. } => {
+                        let topic = message.topic.into_string();
+                        trace!(
+                            "Received gossipsub msg of {} bytes on {topic}",
+                            message.data.len()
+                        );
+                        self.send_event(NetworkEvent::GossipsubMsgReceived {
+                            topic,
+                            msg: message.data.into(),
+                        });
+                    }
+                    other => trace!("gossipsub event: {other:?}"),
                 }
             }
 
@@ -105,9 +165,19 @@ impl SwarmDriver {
                     libp2p::relay::Event::ReservationTimedOut { src_peer_id } => {
                         self.connected_relay_clients.remove(&src_peer_id);
                     }
+                    libp2p::relay::Event::CircuitReqAccepted { .. } => {
+                        self.relayed_circuits += 1;
+                    }
+                    libp2p::relay::Event::CircuitClosed { .. } => {
+                        self.relayed_circuits = self.relayed_circuits.saturating_sub(1);
+                    }
                     _ => {}
                 }
             }
+            SwarmEvent::Behaviour(NodeEvent::Autonat(event)) => {
+                event_string = "autonat_event";
+                self.handle_autonat_event(*event);
+            }
             SwarmEvent::Behaviour(NodeEvent::Identify(iden)) => {
                 // Record the Identify event for metrics if the feature is enabled.
                 #[cfg(feature = "open-metrics")]
@@ -126,12 +196,17 @@ impl SwarmDriver {
 
                         let our_identify_protocol = IDENTIFY_PROTOCOL_STR.read().expect("IDENTIFY_PROTOCOL_STR has been locked to write. A call to set_network_id performed. This should not happen.").to_string();
 
-                        if info.protocol_version != our_identify_protocol {
-                            warn!(?info.protocol_version, "identify: {peer_id:?} does not have the same protocol. Our IDENTIFY_PROTOCOL_STR: {our_identify_protocol:?}");
+                        if let Err(mismatch) = check_protocol_compatibility(
+                            &our_identify_protocol,
+                            &info.protocol_version,
+                            self.protocol_compat,
+                        ) {
+                            warn!(?info.protocol_version, "identify: {peer_id:?} does not have a compatible protocol, {mismatch}. Our IDENTIFY_PROTOCOL_STR: {our_identify_protocol:?}");
 
                             self.send_event(NetworkEvent::PeerWithUnsupportedProtocol {
                                 our_protocol: our_identify_protocol,
                                 their_protocol: info.protocol_version,
+                                mismatch,
                             });
                             // Block the peer from any further communication.
                             self.swarm.behaviour_mut().blocklist.block_peer(peer_id);
@@ -146,10 +221,16 @@ impl SwarmDriver {
                         }
 
                         let our_agent_version = IDENTIFY_NODE_VERSION_STR.read().expect("IDENTIFY_NODE_VERSION_STR has been locked to write. A call to set_network_id performed. This should not happen.").to_string();
+                        let (agent_version, capabilities) =
+                            NodeCapabilities::parse_agent_version(&info.agent_version);
                         // if client, return.
-                        if info.agent_version != our_agent_version {
+                        if agent_version != our_agent_version {
                             return Ok(());
                         }
+                        if capabilities != NodeCapabilities::default() {
+                            debug!(%peer_id, ?capabilities, "identify: peer advertised capabilities");
+                        }
+                        let _ = self.peer_capabilities.insert(peer_id, capabilities);
 
                         let has_dialed = self.dialed_peers.contains(&peer_id);
 
@@ -170,6 +251,9 @@ impl SwarmDriver {
                                 .collect(),
                         };
 
+                        // Only keep the addresses of the IP families we can reach.
+                        addrs.retain(|addr| self.dial_strategy.can_dial(addr));
+
                         let has_relayed = is_a_relayed_peer(&addrs);
 
                         let is_bootstrap_peer = self
@@ -177,8 +261,8 @@ impl SwarmDriver {
                             .iter()
                             .any(|(_ilog2, peers)| peers.contains(&peer_id));
 
-                        // Do not use an `already relayed` peer as `potential relay candidate`.
-                        if !has_relayed && !is_bootstrap_peer {
+                        // Do not use an `already relayed` nor a `client-only` peer as `potential relay candidate`.
+                        if !has_relayed && !capabilities.client_only && !is_bootstrap_peer {
                             if let Some(relay_manager) = self.relay_manager.as_mut() {
                                 debug!("Adding candidate relay server {peer_id:?}, it's not a bootstrap node");
                                 relay_manager.add_potential_candidates(
@@ -234,12 +318,7 @@ impl SwarmDriver {
                             }
 
                             info!(%peer_id, ?addrs, "received identify info from undialed peer for not full kbucket {ilog2:?}, dial back to confirm external accessible");
-                            if let Err(err) = self.swarm.dial(
-                                DialOpts::peer_id(peer_id)
-                                    .condition(PeerCondition::NotDialing)
-                                    .addresses(addrs.iter().cloned().collect())
-                                    .build(),
-                            ) {
+                            if let Err(err) = self.dial_racing(peer_id, addrs.iter().cloned()) {
                                 warn!(%peer_id, ?addrs, "dialing error: {err:?}");
                             }
 
@@ -265,6 +344,10 @@ impl SwarmDriver {
                                 });
                             }
 
+                            if self.is_crowding_kbucket(peer_id, &addrs, has_relayed) {
+                                return Ok(());
+                            }
+
                             debug!(%peer_id, ?addrs, "identify: attempting to add addresses to routing table");
 
                             // Attempt to add the addresses to the routing table.
@@ -287,7 +370,7 @@ impl SwarmDriver {
                     libp2p::identify::Event::Error { .. } => debug!("identify: {iden:?}"),
                 }
             }
-            #[cfg(feature = "local")]
+            #[cfg(not(target_arch = "wasm32"))]
             SwarmEvent::Behaviour(NodeEvent::Mdns(mdns_event)) => {
                 event_string = "mdns";
                 match *mdns_event {
@@ -317,6 +400,7 @@ impl SwarmDriver {
                 event_string = "new listen addr";
 
                 info!("Local node is listening {listener_id:?} on {address:?}");
+                self.dial_strategy.on_new_listen_addr(&address);
 
                 let local_peer_id = *self.swarm.local_peer_id();
                 // Make sure the address ends with `/p2p/<local peer ID>`. In case of relay, `/p2p` is already there.
@@ -351,7 +435,16 @@ impl SwarmDriver {
                 event_string = "listener closed";
                 info!("Listener {listener_id:?} with add {addresses:?} has been closed for {reason:?}");
                 if let Some(relay_manager) = self.relay_manager.as_mut() {
-                    relay_manager.on_listener_closed(&listener_id, &mut self.swarm);
+                    let reservation_failed =
+                        relay_manager.on_listener_closed(&listener_id, &mut self.swarm);
+                    #[cfg(feature = "open-metrics")]
+                    if let Some(metrics_recorder) = &self.metrics_recorder {
+                        if reservation_failed {
+                            let _ = metrics_recorder.relay_reservation_failures.inc();
+                        }
+                    }
+                    #[cfg(not(feature = "open-metrics"))]
+                    let _ = reservation_failed;
                 }
             }
             SwarmEvent::IncomingConnection {
@@ -372,6 +465,24 @@ impl SwarmDriver {
             } => {
                 event_string = "ConnectionEstablished";
                 debug!(%peer_id, num_established, ?concurrent_dial_errors, "ConnectionEstablished ({connection_id:?}) in {established_in:?}: {}", endpoint_str(&endpoint));
+                if !self
+                    .peer_access_list
+                    .is_allowed(&peer_id, [endpoint.get_remote_address()])
+                {
+                    warn!("Closing connection {connection_id:?} to {peer_id:?}, not allowed by the peer access list: {}", endpoint_str(&endpoint));
+                    let _ = self.swarm.close_connection(connection_id);
+                    return Ok(());
+                }
+                if let ConnectedPoint::Dialer { address, .. } = &endpoint {
+                    if self
+                        .dial_strategy
+                        .on_outbound_connection(peer_id, connection_id, address)
+                    {
+                        debug!("Closing connection {connection_id:?} to {peer_id:?}, another address won the dial race: {}", endpoint_str(&endpoint));
+                        let _ = self.swarm.close_connection(connection_id);
+                        return Ok(());
+                    }
+                }
                 if let Some(external_addr_manager) = self.external_address_manager.as_mut() {
                     if let ConnectedPoint::Listener { local_addr, .. } = &endpoint {
                         external_addr_manager
@@ -392,6 +503,10 @@ impl SwarmDriver {
                     bootstrap_cache.update_addr_status(endpoint.get_remote_address(), true);
                 }
 
+                if self.enforce_connection_limits(connection_id, peer_id, &endpoint) {
+                    return Ok(());
+                }
+
                 self.insert_latest_established_connection_ids(
                     connection_id,
                     endpoint.get_remote_address(),
@@ -412,6 +527,7 @@ impl SwarmDriver {
                 event_string = "ConnectionClosed";
                 debug!(%peer_id, ?connection_id, ?cause, num_established, "ConnectionClosed: {}", endpoint_str(&endpoint));
                 let _ = self.live_connected_peers.remove(&connection_id);
+                self.connection_limiter.on_closed(&connection_id);
                 self.record_connection_metrics();
             }
             SwarmEvent::OutgoingConnectionError {
@@ -424,6 +540,24 @@ impl SwarmDriver {
                 let connection_details = self.live_connected_peers.remove(&connection_id);
                 self.record_connection_metrics();
 
+                let failed_addrs: Vec<_> = match &error {
+                    DialError::Transport(errors) => {
+                        errors.iter().map(|(addr, _err)| addr.clone()).collect()
+                    }
+                    _ => vec![],
+                };
+                if let Some(next_addr) =
+                    self.dial_strategy
+                        .on_dial_failure(failed_peer_id, connection_id, &failed_addrs)
+                {
+                    debug!("Racing address {next_addr:?} of {failed_peer_id:?} right away");
+                    if let Err(err) =
+                        self.dial_race_addr(failed_peer_id, next_addr, PeerCondition::Disconnected)
+                    {
+                        debug!("Stopped racing the addresses of {failed_peer_id:?}: {err:?}");
+                    }
+                }
+
                 // we need to decide if this was a critical error and the peer should be removed from the routing table
                 let should_clean_peer = match error {
                     DialError::Transport(errors) => {
@@ -731,6 +865,69 @@ impl SwarmDriver {
         );
     }
 
+    /// Close the connections evicted for the new one to be within the connection limits, returning whether the new
+    /// one was itself evicted.
+    ///
+    /// The connections to our close group, to the relays we use and to the peers relayed through us are kept.
+    fn enforce_connection_limits(
+        &mut self,
+        connection_id: ConnectionId,
+        peer_id: PeerId,
+        endpoint: &ConnectedPoint,
+    ) -> bool {
+        if !self.connection_limiter.is_limited() {
+            return false;
+        }
+        let close_group: HashSet<_> = self
+            .get_closest_k_value_local_peers()
+            .into_iter()
+            .take(CLOSE_GROUP_SIZE + 1)
+            .collect();
+        let self_addr = NetworkAddress::from_peer(self.self_peer_id);
+        let evicted = self.connection_limiter.on_established(
+            connection_id,
+            peer_id,
+            endpoint,
+            |peer| {
+                close_group.contains(peer)
+                    || self.connected_relay_clients.contains(peer)
+                    || self
+                        .relay_manager
+                        .as_ref()
+                        .is_some_and(|relay_manager| relay_manager.keep_alive_peer(peer))
+            },
+            |peer| NetworkAddress::from_peer(*peer).distance(&self_addr),
+        );
+
+        for evicted_id in &evicted {
+            let result = self.swarm.close_connection(*evicted_id);
+            debug!("Evicted connection {evicted_id:?} to stay within the connection limits, with result: {result:?}");
+            let _ = self.live_connected_peers.remove(evicted_id);
+            self.connection_limiter.on_closed(evicted_id);
+        }
+        if !evicted.is_empty() {
+            self.record_connection_metrics();
+        }
+        evicted.contains(&connection_id)
+    }
+
+    /// Close the connections to the peer going through a relay, once a direct one is established, to spare the
+    /// relay. The connections to the relays themselves are direct ones, hence kept.
+    fn close_relayed_connections(&mut self, peer_id: &PeerId) {
+        let relayed_connections: Vec<_> = self
+            .live_connected_peers
+            .iter()
+            .filter(|(_connection_id, (peer, addr, _timeout))| {
+                peer == peer_id && addr.iter().any(|p| matches!(p, Protocol::P2pCircuit))
+            })
+            .map(|(connection_id, _)| *connection_id)
+            .collect();
+        for connection_id in relayed_connections {
+            let result = self.swarm.close_connection(connection_id);
+            debug!("Closed the relayed connection {connection_id:?} to {peer_id:?} with result: {result:?}");
+        }
+    }
+
     /// Record the metrics on update of connection state.
     fn record_connection_metrics(&self) {
         #[cfg(feature = "open-metrics")]
@@ -31,8 +31,8 @@ const MAX_CANDIDATES: usize = 50;
 pub struct ExternalAddressManager {
     /// All the external addresses of the node
     address_states: Vec<ExternalAddressState>,
-    /// The current IP address of all the external addresses.
-    current_ip_address: Option<IpAddr>,
+    /// The current IP address of all the external addresses, one per IP family for dual-stack nodes.
+    current_ip_addresses: Vec<IpAddr>,
     /// The peer id of the node
     peer_id: PeerId,
     // Port -> (ok, error) count
@@ -75,7 +75,7 @@ impl ExternalAddressManager {
     pub fn new(peer_id: PeerId) -> Self {
         Self {
             address_states: Vec::new(),
-            current_ip_address: None,
+            current_ip_addresses: Vec::new(),
             peer_id,
             connection_stats: HashMap::new(),
             bad_ports: HashSet::new(),
@@ -134,11 +134,12 @@ impl ExternalAddressManager {
             if state.is_candidate() {
                 if state.num_reports() >= MAX_REPORTS_BEFORE_CONFIRMATION {
                     // if the IP address of our confirmed address is the same as the new address, then add it
-                    let confirmed = if let Some(current_ip_address) = self.current_ip_address {
-                        current_ip_address == *state.ip_address()
-                    } else {
-                        true
-                    };
+                    let ip_address = state.ip_address();
+                    let confirmed = self
+                        .current_ip_addresses
+                        .iter()
+                        .filter(|current| current.is_ipv4() == ip_address.is_ipv4())
+                        .all(|current| current == ip_address);
 
                     if confirmed {
                         info!("External address confirmed, adding it to swarm: {address:?}");
@@ -166,7 +167,7 @@ impl ExternalAddressManager {
         }
         // check if we need to update to new ip.
         // TODO: Need to observe this
-        if let Some(current_ip_address) = self.current_ip_address {
+        if !self.current_ip_addresses.is_empty() {
             let mut new_ip_map = HashMap::new();
 
             for state in &self.address_states {
@@ -176,9 +177,10 @@ impl ExternalAddressManager {
                     ..
                 } = state
                 {
-                    if current_ip_address != *ip_address
-                        && *num_reports >= MAX_REPORTS_BEFORE_SWITCHING_IP
-                    {
+                    let is_new_ip = self
+                        .current_ip_address(ip_address)
+                        .is_some_and(|current_ip_address| current_ip_address != *ip_address);
+                    if is_new_ip && *num_reports >= MAX_REPORTS_BEFORE_SWITCHING_IP {
                         *new_ip_map.entry(ip_address).or_insert(0) += 1;
                     }
                 }
@@ -242,13 +244,13 @@ impl ExternalAddressManager {
             return;
         };
 
-        // set the current IP address if it is not set
-        if self.current_ip_address.is_none() {
-            self.current_ip_address = Some(ip_address);
+        // set the current IP address of the family if it is not set
+        if self.current_ip_address(&ip_address).is_none() {
+            self.current_ip_addresses.push(ip_address);
         }
 
         // Switch to new IP early.
-        if let Some(current_ip_address) = self.current_ip_address {
+        if let Some(current_ip_address) = self.current_ip_address(&ip_address) {
             if current_ip_address != ip_address {
                 self.address_states.push(ExternalAddressState::Listener {
                     address: address.clone(),
@@ -386,11 +388,21 @@ impl ExternalAddressManager {
         stats.ok = stats.ok.saturating_add(1);
     }
 
-    /// Switch to a new IP address. The old external addresses are removed and the new ones are added.
-    /// The new IP address is set as the current IP address.
+    /// The current IP address of the same family as the given one.
+    fn current_ip_address(&self, ip_address: &IpAddr) -> Option<IpAddr> {
+        self.current_ip_addresses
+            .iter()
+            .find(|current| current.is_ipv4() == ip_address.is_ipv4())
+            .copied()
+    }
+
+    /// Switch to a new IP address. The old external addresses of the same IP family are removed and the new ones
+    /// are added. The new IP address is set as the current IP address of its family.
     fn switch_to_new_ip(&mut self, new_ip: IpAddr, swarm: &mut Swarm<NodeBehaviour>) {
         info!("Switching to new IpAddr: {new_ip}");
-        self.current_ip_address = Some(new_ip);
+        self.current_ip_addresses
+            .retain(|current| current.is_ipv4() != new_ip.is_ipv4());
+        self.current_ip_addresses.push(new_ip);
 
         // remove all the old confirmed addresses with different ip
         let mut removed_addresses = Vec::new();
@@ -400,7 +412,7 @@ impl ExternalAddressManager {
                 continue;
             }
 
-            if state.ip_address() != &new_ip {
+            if state.ip_address().is_ipv4() == new_ip.is_ipv4() && state.ip_address() != &new_ip {
                 // todo: should we remove listener from swarm?
                 swarm.remove_external_address(state.multiaddr());
                 removed_addresses.push(state.multiaddr().clone());
@@ -452,7 +464,7 @@ impl ExternalAddressManager {
 
         let ip = given_address
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Ip4(_)))?;
+            .find(|protocol| matches!(protocol, Protocol::Ip4(_) | Protocol::Ip6(_)))?;
         output_address.push(ip);
 
         if let Some(ws_protocol) = given_address
@@ -0,0 +1,118 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{driver::REPLICATION_FACTOR, error::NetworkError, CLOSE_GROUP_SIZE};
+use libp2p::kad;
+use std::{num::NonZeroUsize, time::Duration};
+
+/// The time before a Kad query of a node times out if no response is received.
+const NODE_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The parameters of the Kademlia behaviour, e.g. for a private network or a test harness to tune.
+///
+/// The defaults are the ones of the public network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KademliaConfig {
+    /// How many of the closest peers a record is stored on. At least `CLOSE_GROUP_SIZE`.
+    pub replication_factor: NonZeroUsize,
+    /// How many peers an iterative query asks at once, aka alpha.
+    pub parallelism: NonZeroUsize,
+    /// How long the records are kept for. `None` keeps them forever.
+    pub record_ttl: Option<Duration>,
+    /// How long the provider records are kept for. `None` keeps them forever.
+    pub provider_record_ttl: Option<Duration>,
+    /// The time before a query times out if no response is received.
+    ///
+    /// `None` uses the default of the mode, 10s for nodes and libp2p's 60s for clients.
+    pub query_timeout: Option<Duration>,
+}
+
+impl Default for KademliaConfig {
+    fn default() -> Self {
+        Self {
+            replication_factor: REPLICATION_FACTOR,
+            parallelism: kad::ALPHA_VALUE,
+            record_ttl: None,
+            provider_record_ttl: Some(Duration::from_secs(48 * 3600)),
+            query_timeout: None,
+        }
+    }
+}
+
+impl KademliaConfig {
+    /// Check the parameters can make a working network.
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        if self.replication_factor.get() < CLOSE_GROUP_SIZE {
+            return Err(NetworkError::InvalidKademliaConfig(format!(
+                "the replication factor {} is below the close group size {CLOSE_GROUP_SIZE}",
+                self.replication_factor
+            )));
+        }
+        if self.query_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(NetworkError::InvalidKademliaConfig(
+                "the query timeout is zero".to_string(),
+            ));
+        }
+        if [self.record_ttl, self.provider_record_ttl]
+            .iter()
+            .any(|ttl| ttl.is_some_and(|ttl| ttl.is_zero()))
+        {
+            return Err(NetworkError::InvalidKademliaConfig(
+                "a record TTL is zero, use None to keep the records forever".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply the parameters to the libp2p config, `is_client` picking the default query timeout.
+    pub(crate) fn apply(&self, kad_cfg: &mut kad::Config, is_client: bool) {
+        let _ = kad_cfg
+            // How many nodes _should_ store data.
+            .set_replication_factor(self.replication_factor)
+            .set_parallelism(self.parallelism)
+            .set_record_ttl(self.record_ttl)
+            .set_provider_record_ttl(self.provider_record_ttl);
+        match (self.query_timeout, is_client) {
+            (Some(timeout), _) => {
+                let _ = kad_cfg.set_query_timeout(timeout);
+            }
+            (None, false) => {
+                let _ = kad_cfg.set_query_timeout(NODE_QUERY_TIMEOUT);
+            }
+            (None, true) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kademlia_config_rejects_parameters_breaking_the_network() {
+        assert!(KademliaConfig::default().validate().is_ok());
+
+        let below_close_group = KademliaConfig {
+            replication_factor: NonZeroUsize::new(CLOSE_GROUP_SIZE - 1).expect("non-zero"),
+            ..Default::default()
+        };
+        assert!(below_close_group.validate().is_err());
+
+        let zero_timeout = KademliaConfig {
+            query_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert!(zero_timeout.validate().is_err());
+
+        let zero_ttl = KademliaConfig {
+            record_ttl: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert!(zero_ttl.validate().is_err());
+    }
+}
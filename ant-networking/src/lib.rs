@@ -9,39 +9,81 @@
 #[macro_use]
 extern crate tracing;
 
+mod audit_log;
+mod bandwidth;
+#[cfg(feature = "bench")]
+pub mod bench;
 mod bootstrap;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 mod circular_vec;
 mod cmd;
+mod cmd_channel;
+mod codec;
+mod connection_limits;
+mod dial_strategy;
 mod driver;
+mod driver_state;
 mod error;
 mod event;
 mod external_address;
 mod fifo_register;
+mod kad_config;
 mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 mod network_discovery;
+mod peer_access;
+mod peer_scoring;
+mod prewarm;
+mod rate_limiter;
+mod reachability;
 mod record_store;
 mod record_store_api;
+mod record_store_backend;
 mod relay_manager;
+mod relay_server;
 mod replication_fetcher;
+mod routing_health;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
+mod sybil_guard;
 pub mod target_arch;
 mod transactions;
 mod transport;
+mod warm_standby;
 
 use cmd::LocalSwarmCmd;
+use cmd_channel::{queue_cmd, send_cmd, CmdSender};
 use xor_name::XorName;
 
 // re-export arch dependent deps for use in the crate, or above
 pub use self::{
+    audit_log::{tail_audit_log, AuditAction, AuditEntry, RecordSource, AUDIT_LOG_FILENAME},
+    bandwidth::PeerBandwidth,
     cmd::{NodeIssue, SwarmLocalState},
+    connection_limits::ConnectionLimits,
     driver::{
-        GetRecordCfg, NetworkBuilder, PutRecordCfg, SwarmDriver, VerificationKind, MAX_PACKET_SIZE,
+        GetRecordCfg, GetRecordOk, NetworkBuilder, PutRecordCfg, RecordProvenance, SwarmDriver,
+        VerificationKind, MAX_PACKET_SIZE,
     },
+    driver_state::SwarmDriverState,
     error::{GetRecordError, NetworkError},
     event::{MsgResponder, NetworkEvent},
-    record_store::NodeRecordStore,
+    kad_config::KademliaConfig,
+    peer_access::PeerAccessList,
+    peer_scoring::PeerScore,
+    rate_limiter::{RateLimitConfig, RequestBudget, RequestKind},
+    reachability::Reachability,
+    record_store::{
+        EvictionPolicy, NodeRecordStore, RecordCategory, RecordEncryption, StoredRecord,
+    },
+    record_store_backend::RecordStoreBackendKind,
+    relay_server::RelayServerConfig,
+    routing_health::RoutingTableHealth,
+    sybil_guard::{SybilGuardConfig, SybilSuspicion},
     transactions::get_transactions_from_record,
+    transport::{WebSocketConfig, WebSocketTls},
 };
 #[cfg(feature = "open-metrics")]
 pub use metrics::service::MetricsRegistries;
@@ -51,10 +93,15 @@ use self::{cmd::NetworkSwarmCmd, error::Result};
 use ant_evm::{PaymentQuote, QuotingMetrics};
 use ant_protocol::{
     error::Error as ProtocolError,
-    messages::{ChunkProof, Nonce, Query, QueryResponse, Request, Response},
-    storage::{RecordType, RetryStrategy, Scratchpad},
+    messages::{
+        ChunkProof, Nonce, PaymentRejection, Query, QueryResponse, Request, Response,
+        StorageReceipt,
+    },
+    storage::{Pointer, RecordType, RetryStrategy, Scratchpad},
+    version::NodeCapabilities,
     NetworkAddress, PrettyPrintKBucketKey, PrettyPrintRecordKey, CLOSE_GROUP_SIZE,
 };
+use bytes::Bytes;
 use futures::future::select_all;
 use libp2p::{
     identity::Keypair,
@@ -67,12 +114,12 @@ use rand::Rng;
 use std::{
     collections::{BTreeMap, HashMap},
     net::IpAddr,
-    sync::Arc,
-};
-use tokio::sync::{
-    mpsc::{self, Sender},
-    oneshot,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
+use tokio::sync::oneshot;
 use tokio::time::Duration;
 use {
     ant_protocol::storage::Transaction,
@@ -96,6 +143,9 @@ const MAX_WAIT_BEFORE_READING_A_PUT: Duration = Duration::from_millis(750);
 /// Min duration to wait for verification
 const MIN_WAIT_BEFORE_READING_A_PUT: Duration = Duration::from_millis(300);
 
+/// The time the close group has to answer a store quote request, not to wait on a slow node.
+const STORE_QUOTE_DEADLINE: Duration = Duration::from_secs(15);
+
 /// Sort the provided peers by their distance to the given `NetworkAddress`.
 /// Return with the closest expected number of entries if has.
 pub fn sort_peers_by_address<'a>(
@@ -146,6 +196,34 @@ pub fn sort_peers_by_key<'a, T>(
     Ok(sorted_peers)
 }
 
+/// A handle to abort an in-flight `get_record`.
+///
+/// Cancelling stops the underlying kad query, and every caller currently waiting on the same record key receives a
+/// `GetRecordError::Cancelled`. Only the fetch that the handle was returned with stops on it though, the others
+/// carry on with their retries.
+#[derive(Clone, Debug)]
+pub struct GetRecordCancelHandle {
+    key: RecordKey,
+    cancelled: Arc<AtomicBool>,
+    network: Network,
+}
+
+impl GetRecordCancelHandle {
+    /// Cancel the fetch. Calling this after the fetch has completed has no effect.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.network
+            .queue_network_swarm_cmd(NetworkSwarmCmd::CancelGetRecord {
+                key: self.key.clone(),
+            });
+    }
+
+    /// Whether `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
 #[derive(Clone, Debug)]
 /// API to interact with the underlying Swarm
 pub struct Network {
@@ -156,16 +234,16 @@ pub struct Network {
 /// the Arc from the interface.
 #[derive(Debug)]
 struct NetworkInner {
-    network_swarm_cmd_sender: mpsc::Sender<NetworkSwarmCmd>,
-    local_swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
+    network_swarm_cmd_sender: CmdSender<NetworkSwarmCmd>,
+    local_swarm_cmd_sender: CmdSender<LocalSwarmCmd>,
     peer_id: PeerId,
     keypair: Keypair,
 }
 
 impl Network {
-    pub fn new(
-        network_swarm_cmd_sender: mpsc::Sender<NetworkSwarmCmd>,
-        local_swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
+    pub(crate) fn new(
+        network_swarm_cmd_sender: CmdSender<NetworkSwarmCmd>,
+        local_swarm_cmd_sender: CmdSender<LocalSwarmCmd>,
         peer_id: PeerId,
         keypair: Keypair,
     ) -> Self {
@@ -190,11 +268,11 @@ impl Network {
     }
 
     /// Get the sender to send a `NetworkSwarmCmd` to the underlying `Swarm`.
-    pub(crate) fn network_swarm_cmd_sender(&self) -> &mpsc::Sender<NetworkSwarmCmd> {
+    pub(crate) fn network_swarm_cmd_sender(&self) -> &CmdSender<NetworkSwarmCmd> {
         &self.inner.network_swarm_cmd_sender
     }
     /// Get the sender to send a `LocalSwarmCmd` to the underlying `Swarm`.
-    pub(crate) fn local_swarm_cmd_sender(&self) -> &mpsc::Sender<LocalSwarmCmd> {
+    pub(crate) fn local_swarm_cmd_sender(&self) -> &CmdSender<LocalSwarmCmd> {
         &self.inner.local_swarm_cmd_sender
     }
 
@@ -217,10 +295,26 @@ impl Network {
     /// This function will only be called for the bootstrap nodes.
     pub async fn dial(&self, addr: Multiaddr) -> Result<()> {
         let (sender, receiver) = oneshot::channel();
-        self.send_network_swarm_cmd(NetworkSwarmCmd::Dial { addr, sender });
+        self.send_network_swarm_cmd(NetworkSwarmCmd::Dial { addr, sender })
+            .await?;
         receiver.await?
     }
 
+    /// Subscribe to the given gossipsub topic, receiving its messages as `NetworkEvent::GossipsubMsgReceived`.
+    pub fn subscribe_to_topic(&self, topic_id: String) {
+        self.queue_network_swarm_cmd(NetworkSwarmCmd::GossipsubSubscribe(topic_id));
+    }
+
+    /// Unsubscribe from the given gossipsub topic.
+    pub fn unsubscribe_from_topic(&self, topic_id: String) {
+        self.queue_network_swarm_cmd(NetworkSwarmCmd::GossipsubUnsubscribe(topic_id));
+    }
+
+    /// Publish a message on the given gossipsub topic, to the peers subscribed to it.
+    pub fn publish_on_topic(&self, topic_id: String, msg: Bytes) {
+        self.queue_network_swarm_cmd(NetworkSwarmCmd::GossipsubPublish { topic_id, msg });
+    }
+
     /// Returns the closest peers to the given `XorName`, sorted by their distance to the xor_name.
     /// Excludes the client's `PeerId` while calculating the closest peers.
     pub async fn client_get_all_close_peers_in_range_or_close_group(
@@ -243,7 +337,8 @@ impl Network {
     /// Does not include self
     pub async fn get_local_peers_with_multiaddr(&self) -> Result<Vec<(PeerId, Vec<Multiaddr>)>> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetPeersWithMultiaddr { sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetPeersWithMultiaddr { sender })
+            .await?;
         receiver
             .await
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
@@ -254,7 +349,8 @@ impl Network {
     /// Does not include self
     pub async fn get_kbuckets(&self) -> Result<BTreeMap<u32, Vec<PeerId>>> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetKBuckets { sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetKBuckets { sender })
+            .await?;
         receiver
             .await
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
@@ -264,7 +360,8 @@ impl Network {
     /// Also contains our own PeerId.
     pub async fn get_closest_k_value_local_peers(&self) -> Result<Vec<PeerId>> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetClosestKLocalPeers { sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetClosestKLocalPeers { sender })
+            .await?;
 
         receiver
             .await
@@ -274,7 +371,8 @@ impl Network {
     /// Returns the replicate candidates in range.
     pub async fn get_replicate_candidates(&self, data_addr: NetworkAddress) -> Result<Vec<PeerId>> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetReplicateCandidates { data_addr, sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetReplicateCandidates { data_addr, sender })
+            .await?;
 
         receiver
             .await
@@ -370,9 +468,102 @@ impl Network {
         ))
     }
 
+    /// Get signed receipts from the close nodes to the record that they hold it, paid for with the payment of the
+    /// given hash. Only valid receipts, signed by the nodes that sent them, are returned.
+    pub async fn get_storage_receipts(
+        &self,
+        record_address: NetworkAddress,
+        payment_hash: XorName,
+    ) -> Result<Vec<StorageReceipt>> {
+        let close_nodes = self
+            .client_get_all_close_peers_in_range_or_close_group(&record_address)
+            .await?;
+
+        let request = Request::Query(Query::GetStorageReceipt {
+            key: record_address.clone(),
+            payment_hash,
+        });
+        let responses = self
+            .send_and_get_responses(&close_nodes, &request, true)
+            .await;
+
+        let receipts: Vec<_> = responses
+            .into_iter()
+            .filter_map(|(peer, resp)| match resp {
+                Ok(Response::Query(QueryResponse::GetStorageReceipt(Ok(receipt))))
+                    if receipt.node() == Some(peer)
+                        && receipt.address == record_address
+                        && receipt.payment_hash == payment_hash
+                        && receipt.verify() =>
+                {
+                    Some(receipt)
+                }
+                Ok(Response::Query(QueryResponse::GetStorageReceipt(Ok(_)))) => {
+                    warn!("Got an invalid storage receipt for {record_address:?} from {peer:?}");
+                    None
+                }
+                other => {
+                    debug!("Did not get a storage receipt for {record_address:?} from {peer:?}: {other:?}");
+                    None
+                }
+            })
+            .collect();
+        debug!(
+            "Got {} storage receipts for {record_address:?}",
+            receipts.len()
+        );
+
+        Ok(receipts)
+    }
+
+    /// Ask the payees of the payment of the given hash for their view of it, if they rejected the record put with
+    /// it. Only the rejections from the nodes that sent them, for that record and payment, are returned.
+    pub async fn get_payment_rejections(
+        &self,
+        record_address: NetworkAddress,
+        payment_hash: XorName,
+        payees: &[PeerId],
+    ) -> Vec<PaymentRejection> {
+        let request = Request::Query(Query::GetPaymentRejection {
+            key: record_address.clone(),
+            payment_hash,
+        });
+        let responses = self.send_and_get_responses(payees, &request, true).await;
+
+        let rejections: Vec<_> = responses
+            .into_iter()
+            .filter_map(|(peer, resp)| match resp {
+                Ok(Response::Query(QueryResponse::GetPaymentRejection(Ok(rejection))))
+                    if rejection.node == NetworkAddress::from_peer(peer)
+                        && rejection.address == record_address
+                        && rejection.payment_hash == payment_hash =>
+                {
+                    Some(rejection)
+                }
+                Ok(Response::Query(QueryResponse::GetPaymentRejection(Ok(_)))) => {
+                    warn!("Got a mismatched payment rejection for {record_address:?} from {peer:?}");
+                    None
+                }
+                other => {
+                    debug!("Did not get a payment rejection for {record_address:?} from {peer:?}: {other:?}");
+                    None
+                }
+            })
+            .collect();
+        debug!(
+            "Got {} payment rejections for {record_address:?}",
+            rejections.len()
+        );
+
+        rejections
+    }
+
     /// Get the store costs from the majority of the closest peers to the provided RecordKey.
     /// Record already exists will have a cost of zero to be returned.
     ///
+    /// All the peers are queried at once, the ones not answering within `STORE_QUOTE_DEADLINE`
+    /// are left out, as are the quotes not signed by their peer or for another address.
+    ///
     /// Ignore the quote from any peers from `ignore_peers`.
     /// This is useful if we want to repay a different PeerId on failure.
     pub async fn get_store_quote_from_network(
@@ -387,6 +578,20 @@ impl Network {
             .await?;
         // Filter out results from the ignored peers.
         close_nodes.retain(|peer_id| !ignore_peers.contains(peer_id));
+        // Leave out the peers that advertised near full storage, as long as enough are left to quote.
+        let capabilities = self.get_peer_capabilities().await?;
+        let has_room = |peer_id: &PeerId| {
+            !capabilities
+                .get(peer_id)
+                .is_some_and(|capabilities| capabilities.storage_near_full)
+        };
+        let with_room = close_nodes
+            .iter()
+            .filter(|peer_id| has_room(peer_id))
+            .count();
+        if with_room >= CLOSE_GROUP_SIZE && with_room < close_nodes.len() {
+            close_nodes.retain(has_room);
+        }
         info!(
             "For record {record_address:?} quoting {} nodes. ignore_peers is {ignore_peers:?}",
             close_nodes.len()
@@ -403,9 +608,20 @@ impl Network {
             nonce: None,
             difficulty: 0,
         });
-        let responses = self
-            .send_and_get_responses(&close_nodes, &request, true)
-            .await;
+        let responses = futures::future::join_all(close_nodes.iter().map(|peer| {
+            let request = request.clone();
+            async move {
+                let response =
+                    target_arch::timeout(STORE_QUOTE_DEADLINE, self.send_request(request, *peer))
+                        .await;
+                if response.is_err() {
+                    warn!("No quote from {peer:?} within {STORE_QUOTE_DEADLINE:?}");
+                }
+                response.ok().map(|response| (*peer, response))
+            }
+        }))
+        .await;
+        let expected_content = record_address.as_xorname().unwrap_or_default();
 
         // consider data to be already paid for if 1/2 of the close nodes already have it
         let mut peer_already_have_it = 0;
@@ -414,7 +630,7 @@ impl Network {
         // loop over responses
         let mut all_quotes = vec![];
         let mut quotes_to_pay = vec![];
-        for (peer, response) in responses {
+        for (peer, response) in responses.into_iter().flatten() {
             info!("StoreCostReq for {record_address:?} received response: {response:?}");
             match response {
                 Ok(Response::Query(QueryResponse::GetStoreQuote {
@@ -430,6 +646,12 @@ impl Network {
                         warn!("Received invalid quote from {peer_address:?}, {quote:?}");
                         continue;
                     }
+                    if quote.content != expected_content {
+                        warn!(
+                            "Received a quote for another address from {peer_address:?}, {quote:?}"
+                        );
+                        continue;
+                    }
 
                     all_quotes.push((peer_address.clone(), quote.clone()));
                     quotes_to_pay.push((peer, quote));
@@ -521,12 +743,51 @@ impl Network {
     /// It also handles the split record error for transactions and registers.
     /// For transactions, it accumulates the transactions and returns an error if more than one.
     /// For registers, it merges the registers and returns the merged record.
+    ///
+    /// Along with the record, returns which peers served which copy of it, for the caller to tell how well replicated
+    /// it is, or to act on the peers that returned stale or divergent data.
     pub async fn get_record_from_network(
         &self,
         key: RecordKey,
         cfg: &GetRecordCfg,
-    ) -> Result<Record> {
+    ) -> Result<GetRecordOk> {
+        self.get_record_with_cancellation(key, cfg, None).await
+    }
+
+    /// Same as `get_record_from_network`, but also returns a handle that can be used to abort the
+    /// fetch, e.g. when the user cancels a download.
+    pub fn get_record_cancellable(
+        &self,
+        key: RecordKey,
+        cfg: GetRecordCfg,
+    ) -> (
+        GetRecordCancelHandle,
+        impl std::future::Future<Output = Result<GetRecordOk>>,
+    ) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let handle = GetRecordCancelHandle {
+            key: key.clone(),
+            cancelled: Arc::clone(&cancelled),
+            network: self.clone(),
+        };
+        let network = self.clone();
+        let fetch = async move {
+            network
+                .get_record_with_cancellation(key, &cfg, Some(&cancelled))
+                .await
+        };
+
+        (handle, fetch)
+    }
+
+    async fn get_record_with_cancellation(
+        &self,
+        key: RecordKey,
+        cfg: &GetRecordCfg,
+        cancelled: Option<&AtomicBool>,
+    ) -> Result<GetRecordOk> {
         let pretty_key = PrettyPrintRecordKey::from(&key);
+        let is_cancelled = || cancelled.is_some_and(|cancelled| cancelled.load(Ordering::SeqCst));
         let mut backoff = cfg
             .retry_strategy
             .unwrap_or(RetryStrategy::None)
@@ -534,13 +795,18 @@ impl Network {
             .into_iter();
 
         loop {
+            if is_cancelled() {
+                info!("Getting record from network of {pretty_key:?} has been cancelled.");
+                return Err(GetRecordError::Cancelled.into());
+            }
             info!("Getting record from network of {pretty_key:?}. with cfg {cfg:?}",);
             let (sender, receiver) = oneshot::channel();
             self.send_network_swarm_cmd(NetworkSwarmCmd::GetNetworkRecord {
                 key: key.clone(),
                 sender,
                 cfg: cfg.clone(),
-            });
+            })
+            .await?;
             let result = match receiver.await {
                 Ok(result) => result,
                 Err(err) => {
@@ -553,9 +819,13 @@ impl Network {
             };
 
             let err = match result {
-                Ok(record) => {
+                Ok(get_record_ok) => {
                     info!("Record returned: {pretty_key:?}.");
-                    return Ok(record);
+                    let divergent_peers = get_record_ok.divergent_peers();
+                    if !divergent_peers.is_empty() {
+                        warn!("Record {pretty_key:?} returned, but {divergent_peers:?} served a different copy.");
+                    }
+                    return Ok(get_record_ok);
                 }
                 Err(err) => err,
             };
@@ -578,16 +848,32 @@ impl Network {
                 GetRecordError::RecordKindMismatch => {
                     error!("Record kind mismatch for {pretty_key:?}. This error should not happen here.");
                 }
-                GetRecordError::SplitRecord { result_map } => {
-                    error!("Encountered a split record for {pretty_key:?}.");
+                GetRecordError::SplitRecord {
+                    result_map,
+                    provenance,
+                } => {
+                    error!("Encountered a split record for {pretty_key:?}, with copies from {provenance:?}.");
                     if let Some(record) = Self::handle_split_record_error(result_map, &key)? {
                         info!("Merged the split record (register) for {pretty_key:?}, into a single record");
-                        return Ok(record);
+                        return Ok(GetRecordOk {
+                            record,
+                            provenance: provenance.clone(),
+                            completed_early: false,
+                            merged: true,
+                        });
                     }
                 }
                 GetRecordError::QueryTimeout => {
                     error!("Encountered query timeout for {pretty_key:?}.");
                 }
+                // The query could have been shared with, and cancelled by, another caller fetching the same key.
+                GetRecordError::Cancelled => {
+                    if is_cancelled() {
+                        info!("Getting record from network of {pretty_key:?} has been cancelled.");
+                        return Err(err.into());
+                    }
+                    warn!("Query for {pretty_key:?} was cancelled by another caller.");
+                }
             }
 
             match backoff.next() {
@@ -603,6 +889,7 @@ impl Network {
     /// Handle the split record error.
     /// Transaction: Accumulate transactions.
     /// Register: Merge registers and return the merged record.
+    /// Scratchpad and Pointer: Return the valid one with the highest count.
     fn handle_split_record_error(
         result_map: &HashMap<XorName, (Record, HashSet<PeerId>)>,
         key: &RecordKey,
@@ -614,6 +901,7 @@ impl Network {
         let mut accumulated_transactions = HashSet::new();
         let mut collected_registers = Vec::new();
         let mut valid_scratchpad: Option<Scratchpad> = None;
+        let mut valid_pointer: Option<Pointer> = None;
 
         if results_count > 1 {
             let mut record_kind = None;
@@ -635,7 +923,8 @@ impl Network {
                     | RecordKind::ChunkWithPayment
                     | RecordKind::TransactionWithPayment
                     | RecordKind::RegisterWithPayment
-                    | RecordKind::ScratchpadWithPayment => {
+                    | RecordKind::ScratchpadWithPayment
+                    | RecordKind::PointerWithPayment => {
                         error!("Encountered a split record for {pretty_key:?} with unexpected RecordKind {kind:?}, skipping.");
                         continue;
                     }
@@ -702,6 +991,29 @@ impl Network {
                             valid_scratchpad = Some(scratchpad);
                         }
                     }
+                    RecordKind::Pointer => {
+                        info!("For record {pretty_key:?}, we have a split record for a pointer. Selecting the one with the highest count");
+                        let Ok(pointer) = try_deserialize_record::<Pointer>(record) else {
+                            error!(
+                                "Failed to deserialize pointer {pretty_key}. Skipping accumulation"
+                            );
+                            continue;
+                        };
+
+                        if !pointer.verify() {
+                            warn!("Rejecting Pointer for {pretty_key} with invalid signature during split record error");
+                            continue;
+                        }
+
+                        if valid_pointer
+                            .as_ref()
+                            .is_some_and(|old| old.count() >= pointer.count())
+                        {
+                            info!("Rejecting Pointer for {pretty_key} with lower count than the previous one");
+                            continue;
+                        }
+                        valid_pointer = Some(pointer);
+                    }
                 }
             }
         }
@@ -767,26 +1079,45 @@ impl Network {
                 expires: None,
             };
             return Ok(Some(record));
+        } else if let Some(pointer) = valid_pointer {
+            info!("Found a valid pointer for {pretty_key:?}, returning it");
+            let record = Record {
+                key: key.clone(),
+                value: try_serialize_record(&pointer, RecordKind::Pointer)
+                    .map_err(|err| {
+                        error!("Error while serializing valid pointer for {pretty_key:?}: {err:?}");
+                        NetworkError::from(err)
+                    })?
+                    .to_vec(),
+                publisher: None,
+                expires: None,
+            };
+            return Ok(Some(record));
         }
         Ok(None)
     }
 
-    /// Get the quoting metrics for storing the next record from the network
+    /// Get the quoting metrics for storing the record at the address, failing if the quota of its type is full
     pub async fn get_local_quoting_metrics(
         &self,
-        key: RecordKey,
+        address: &NetworkAddress,
     ) -> Result<(QuotingMetrics, bool)> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetLocalQuotingMetrics { key, sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetLocalQuotingMetrics {
+            key: address.to_record_key(),
+            category: RecordCategory::from_address(address),
+            sender,
+        })
+        .await?;
 
         receiver
             .await
-            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)?
     }
 
     /// Notify the node receicced a payment.
     pub fn notify_payment_received(&self) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::PaymentReceived);
+        self.queue_local_swarm_cmd(LocalSwarmCmd::PaymentReceived);
     }
 
     /// Get `Record` from the local RecordStore
@@ -795,7 +1126,8 @@ impl Network {
         self.send_local_swarm_cmd(LocalSwarmCmd::GetLocalRecord {
             key: key.clone(),
             sender,
-        });
+        })
+        .await?;
 
         receiver
             .await
@@ -805,7 +1137,8 @@ impl Network {
     /// Whether the target peer is considered blacklisted by self
     pub async fn is_peer_shunned(&self, target: NetworkAddress) -> Result<bool> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::IsPeerShunned { target, sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::IsPeerShunned { target, sender })
+            .await?;
 
         receiver
             .await
@@ -815,7 +1148,8 @@ impl Network {
     /// Put `Record` to network
     /// Optionally verify the record is stored after putting it to network
     /// If verify is on, we retry.
-    pub async fn put_record(&self, record: Record, cfg: &PutRecordCfg) -> Result<()> {
+    /// Returns the peers that acknowledged storing the record.
+    pub async fn put_record(&self, record: Record, cfg: &PutRecordCfg) -> Result<Vec<PeerId>> {
         let pretty_key = PrettyPrintRecordKey::from(&record.key);
         let mut backoff = cfg
             .retry_strategy
@@ -829,7 +1163,7 @@ impl Network {
             );
 
             let err = match self.put_record_once(record.clone(), cfg).await {
-                Ok(_) => break Ok(()),
+                Ok(confirmed_peers) => break Ok(confirmed_peers),
                 Err(err) => err,
             };
 
@@ -845,7 +1179,7 @@ impl Network {
         }
     }
 
-    async fn put_record_once(&self, record: Record, cfg: &PutRecordCfg) -> Result<()> {
+    async fn put_record_once(&self, record: Record, cfg: &PutRecordCfg) -> Result<Vec<PeerId>> {
         let record_key = record.key.clone();
         let pretty_key = PrettyPrintRecordKey::from(&record_key);
         info!(
@@ -862,16 +1196,20 @@ impl Network {
                 record: record.clone(),
                 sender,
                 quorum: cfg.put_quorum,
-            });
+            })
+            .await?;
         } else {
             self.send_network_swarm_cmd(NetworkSwarmCmd::PutRecord {
                 record: record.clone(),
                 sender,
                 quorum: cfg.put_quorum,
-            });
+            })
+            .await?;
         }
 
-        let response = receiver.await?;
+        // Wait for the store acknowledgements, there is no point verifying if the write quorum failed.
+        let confirmed_peers = receiver.await??;
+        debug!("Record {pretty_key:?} acknowledged by {confirmed_peers:?}");
 
         if let Some((verification_kind, get_cfg)) = &cfg.verification {
             // Generate a random duration between MAX_WAIT_BEFORE_READING_A_PUT and MIN_WAIT_BEFORE_READING_A_PUT
@@ -924,24 +1262,42 @@ impl Network {
                 }
             }
         }
-        response
+        Ok(confirmed_peers)
     }
 
     /// Notify ReplicationFetch a fetch attempt is completed.
     /// (but it won't trigger any real writes to disk, say fetched an old version of register)
     pub fn notify_fetch_completed(&self, key: RecordKey, record_type: RecordType) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::FetchCompleted((key, record_type)))
+        self.queue_local_swarm_cmd(LocalSwarmCmd::FetchCompleted((key, record_type)))
     }
 
     /// Put `Record` to the local RecordStore
     /// Must be called after the validations are performed on the Record
-    pub fn put_local_record(&self, record: Record) {
+    pub fn put_local_record(&self, record: Record, source: RecordSource) {
         debug!(
             "Writing Record locally, for {:?} - length {:?}",
             PrettyPrintRecordKey::from(&record.key),
             record.value.len()
         );
-        self.send_local_swarm_cmd(LocalSwarmCmd::PutLocalRecord { record })
+        self.queue_local_swarm_cmd(LocalSwarmCmd::PutLocalRecord { record, source })
+    }
+
+    /// Remove a `Record` from the local RecordStore
+    pub fn remove_local_record(&self, key: RecordKey) {
+        debug!(
+            "Removing Record locally, for {:?}",
+            PrettyPrintRecordKey::from(&key)
+        );
+        self.queue_local_swarm_cmd(LocalSwarmCmd::RemoveLocalRecord { key })
+    }
+
+    /// Prune a `Record` no longer relevant to us, and held enough by its close group, from the local RecordStore
+    pub fn prune_local_record(&self, key: RecordKey) {
+        debug!(
+            "Pruning Record locally, for {:?}",
+            PrettyPrintRecordKey::from(&key)
+        );
+        self.queue_local_swarm_cmd(LocalSwarmCmd::PruneLocalRecord { key })
     }
 
     /// Returns true if a RecordKey is present locally in the RecordStore
@@ -950,7 +1306,8 @@ impl Network {
         self.send_local_swarm_cmd(LocalSwarmCmd::RecordStoreHasKey {
             key: key.clone(),
             sender,
-        });
+        })
+        .await?;
 
         receiver
             .await
@@ -962,7 +1319,32 @@ impl Network {
         &self,
     ) -> Result<HashMap<NetworkAddress, RecordType>> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetAllLocalRecordAddresses { sender });
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetAllLocalRecordAddresses { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Returns the Addresses of the locally stored Records beyond our responsible range, the farthest first
+    pub async fn get_irrelevant_record_addresses(
+        &self,
+    ) -> Result<Vec<(NetworkAddress, RecordType)>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetIrrelevantRecordAddresses { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Returns the records held by the local RecordStore, sorted by their distance to us
+    pub async fn get_local_stored_records(&self) -> Result<Vec<StoredRecord>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetLocalStoredRecords { sender })
+            .await?;
 
         receiver
             .await
@@ -981,7 +1363,8 @@ impl Network {
             req: req.clone(),
             peer,
             sender: Some(sender),
-        });
+        })
+        .await?;
         let mut r = receiver.await?;
 
         if let Err(error) = &r {
@@ -1000,7 +1383,8 @@ impl Network {
                         req,
                         peer,
                         sender: Some(sender),
-                    });
+                    })
+                    .await?;
 
                     r = receiver.await?;
                 }
@@ -1022,49 +1406,137 @@ impl Network {
             peer,
             sender: None,
         };
-        self.send_network_swarm_cmd(swarm_cmd)
+        self.queue_network_swarm_cmd(swarm_cmd)
     }
 
     /// Send a `Response` through the channel opened by the requester.
     pub fn send_response(&self, resp: Response, channel: MsgResponder) {
-        self.send_network_swarm_cmd(NetworkSwarmCmd::SendResponse { resp, channel })
+        self.queue_network_swarm_cmd(NetworkSwarmCmd::SendResponse { resp, channel })
     }
 
     /// Return a `SwarmLocalState` with some information obtained from swarm's local state.
     pub async fn get_swarm_local_state(&self) -> Result<SwarmLocalState> {
         let (sender, receiver) = oneshot::channel();
-        self.send_local_swarm_cmd(LocalSwarmCmd::GetSwarmLocalState(sender));
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetSwarmLocalState(sender))
+            .await?;
         let state = receiver.await?;
         Ok(state)
     }
 
     pub fn trigger_interval_replication(&self) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::TriggerIntervalReplication)
+        self.queue_local_swarm_cmd(LocalSwarmCmd::TriggerIntervalReplication)
     }
 
     pub fn record_node_issues(&self, peer_id: PeerId, issue: NodeIssue) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::RecordNodeIssue { peer_id, issue });
+        self.queue_local_swarm_cmd(LocalSwarmCmd::RecordNodeIssue { peer_id, issue });
+    }
+
+    /// Persist the peer scores and the recently-good peers to the node's root dir,
+    /// to be restored on the next start.
+    pub async fn save_peer_reputation(&self) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::SavePeerReputation { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)?
+    }
+
+    /// Persist the routing table and the pending replication to the node's root dir, to be restored on the next
+    /// start. Does nothing unless in warm standby mode.
+    pub async fn save_warm_standby(&self) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::SaveWarmStandby { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)?
+    }
+
+    /// Returns the bytes sent and received per peer and negotiated protocol, highest first.
+    pub async fn get_bandwidth_usage(&self) -> Result<Vec<PeerBandwidth>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetBandwidthUsage { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Replace the faults the `SwarmDriver` injects on purpose.
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos_config(&self, config: chaos::ChaosConfig) {
+        self.queue_local_swarm_cmd(LocalSwarmCmd::SetChaosConfig { config })
+    }
+
+    /// Returns the sizes of the internal state of the `SwarmDriver`.
+    pub async fn get_driver_state(&self) -> Result<SwarmDriverState> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetDriverState { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Returns the scores of the peers that misbehaved, highest first.
+    pub async fn get_peer_scores(&self) -> Result<Vec<PeerScore>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetPeerScores { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// Returns the capabilities the peers in our RT advertised
+    pub async fn get_peer_capabilities(&self) -> Result<HashMap<PeerId, NodeCapabilities>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_local_swarm_cmd(LocalSwarmCmd::GetPeerCapabilities { sender })
+            .await?;
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
     }
 
     pub fn historical_verify_quotes(&self, quotes: Vec<(PeerId, PaymentQuote)>) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::QuoteVerification { quotes });
+        self.queue_local_swarm_cmd(LocalSwarmCmd::QuoteVerification { quotes });
     }
 
     pub fn trigger_irrelevant_record_cleanup(&self) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::TriggerIrrelevantRecordCleanup)
+        self.queue_local_swarm_cmd(LocalSwarmCmd::TriggerIrrelevantRecordCleanup)
+    }
+
+    /// Stop quoting for and storing new records, e.g. before decommissioning the node.
+    pub fn stop_accepting_records(&self) {
+        self.queue_local_swarm_cmd(LocalSwarmCmd::StopAcceptingRecords)
     }
 
     pub fn add_network_density_sample(&self, distance: KBucketDistance) {
-        self.send_local_swarm_cmd(LocalSwarmCmd::AddNetworkDensitySample { distance })
+        self.queue_local_swarm_cmd(LocalSwarmCmd::AddNetworkDensitySample { distance })
     }
 
-    /// Helper to send NetworkSwarmCmd
-    fn send_network_swarm_cmd(&self, cmd: NetworkSwarmCmd) {
-        send_network_swarm_cmd(self.network_swarm_cmd_sender().clone(), cmd);
+    /// Helper to send NetworkSwarmCmd, waiting for room in the channel
+    async fn send_network_swarm_cmd(&self, cmd: NetworkSwarmCmd) -> Result<()> {
+        send_cmd(self.network_swarm_cmd_sender(), cmd).await
     }
-    /// Helper to send LocalSwarmCmd
-    fn send_local_swarm_cmd(&self, cmd: LocalSwarmCmd) {
-        send_local_swarm_cmd(self.local_swarm_cmd_sender().clone(), cmd);
+    /// Helper to send LocalSwarmCmd, waiting for room in the channel
+    async fn send_local_swarm_cmd(&self, cmd: LocalSwarmCmd) -> Result<()> {
+        send_cmd(self.local_swarm_cmd_sender(), cmd).await
+    }
+    /// Helper to queue NetworkSwarmCmd without waiting
+    fn queue_network_swarm_cmd(&self, cmd: NetworkSwarmCmd) {
+        queue_cmd(self.network_swarm_cmd_sender().clone(), cmd);
+    }
+    /// Helper to queue LocalSwarmCmd without waiting
+    fn queue_local_swarm_cmd(&self, cmd: LocalSwarmCmd) {
+        queue_cmd(self.local_swarm_cmd_sender().clone(), cmd);
     }
 
     /// Returns the closest peers to the given `XorName`, sorted by their distance to the xor_name.
@@ -1082,7 +1554,8 @@ impl Network {
         self.send_network_swarm_cmd(NetworkSwarmCmd::GetClosestPeersToAddressFromNetwork {
             key: key.clone(),
             sender,
-        });
+        })
+        .await?;
 
         let found_peers = receiver.await?;
 
@@ -1120,6 +1593,23 @@ impl Network {
         Ok(closest_peers.into_iter().cloned().collect())
     }
 
+    /// Warm up the routing table toward the close groups of the given addresses, e.g. before a large download, so
+    /// that the subsequent `get_record` calls start from well populated buckets.
+    ///
+    /// The `get_closest_peers` lookups are made with a bounded parallelism. Returns once all of them have completed.
+    pub async fn prewarm(&self, addresses: Vec<NetworkAddress>) -> Result<()> {
+        debug!(
+            "Prewarming the routing table toward {} addresses",
+            addresses.len()
+        );
+        let (sender, receiver) = oneshot::channel();
+        self.send_network_swarm_cmd(NetworkSwarmCmd::Prewarm { addresses, sender })
+            .await?;
+
+        receiver.await?;
+        Ok(())
+    }
+
     /// Send a `Request` to the provided set of peers and wait for their responses concurrently.
     /// If `get_all_responses` is true, we wait for the responses from all the peers.
     /// NB TODO: Will return an error if the request timeouts.
@@ -1171,7 +1661,7 @@ pub fn get_quorum_value(quorum: &Quorum) -> usize {
     }
 }
 
-/// Verifies if `Multiaddr` contains IPv4 address that is not global.
+/// Verifies if `Multiaddr` contains IPv4 or IPv6 address that is not global.
 /// This is used to filter out unroutable addresses from the Kademlia routing table.
 pub fn multiaddr_is_global(multiaddr: &Multiaddr) -> bool {
     !multiaddr.iter().any(|addr| match addr {
@@ -1185,6 +1675,16 @@ pub fn multiaddr_is_global(multiaddr: &Multiaddr) -> bool {
                 | ip.is_documentation()
                 | ip.is_broadcast()
         }
+        Protocol::Ip6(ip) => {
+            // Based on the nightly `is_global` method (`Ipv6Addrs::is_global`), only using what is available in stable.
+            // Unique local `fc00::/7`, unicast link local `fe80::/10` and documentation `2001:db8::/32`.
+            let segments = ip.segments();
+            ip.is_unspecified()
+                | ip.is_loopback()
+                | ((segments[0] & 0xfe00) == 0xfc00)
+                | ((segments[0] & 0xffc0) == 0xfe80)
+                | ((segments[0] == 0x2001) && (segments[1] == 0xdb8))
+        }
         _ => false,
     })
 }
@@ -1244,45 +1744,6 @@ pub(crate) fn multiaddr_get_port(addr: &Multiaddr) -> Option<u16> {
     })
 }
 
-pub(crate) fn send_local_swarm_cmd(swarm_cmd_sender: Sender<LocalSwarmCmd>, cmd: LocalSwarmCmd) {
-    let capacity = swarm_cmd_sender.capacity();
-
-    if capacity == 0 {
-        error!(
-            "SwarmCmd channel is full. Await capacity to send: {:?}",
-            cmd
-        );
-    }
-
-    // Spawn a task to send the SwarmCmd and keep this fn sync
-    let _handle = spawn(async move {
-        if let Err(error) = swarm_cmd_sender.send(cmd).await {
-            error!("Failed to send SwarmCmd: {}", error);
-        }
-    });
-}
-
-pub(crate) fn send_network_swarm_cmd(
-    swarm_cmd_sender: Sender<NetworkSwarmCmd>,
-    cmd: NetworkSwarmCmd,
-) {
-    let capacity = swarm_cmd_sender.capacity();
-
-    if capacity == 0 {
-        error!(
-            "SwarmCmd channel is full. Await capacity to send: {:?}",
-            cmd
-        );
-    }
-
-    // Spawn a task to send the SwarmCmd and keep this fn sync
-    let _handle = spawn(async move {
-        if let Err(error) = swarm_cmd_sender.send(cmd).await {
-            error!("Failed to send SwarmCmd: {}", error);
-        }
-    });
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,77 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{GetRecordError, GetRecordOk};
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::histogram::{exponential_buckets, linear_buckets, Histogram},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct GetRecordOutcomeLabels {
+    outcome: GetRecordOutcome,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum GetRecordOutcome {
+    Found,
+    RecordNotFound,
+    NotEnoughCopies,
+    SplitRecord,
+    RecordDoesNotMatch,
+    RecordKindMismatch,
+    QueryTimeout,
+    Cancelled,
+}
+
+impl From<&Result<GetRecordOk, GetRecordError>> for GetRecordOutcomeLabels {
+    fn from(result: &Result<GetRecordOk, GetRecordError>) -> Self {
+        let outcome = match result {
+            Ok(_) => GetRecordOutcome::Found,
+            Err(GetRecordError::RecordNotFound) => GetRecordOutcome::RecordNotFound,
+            Err(GetRecordError::NotEnoughCopies { .. }) => GetRecordOutcome::NotEnoughCopies,
+            Err(GetRecordError::SplitRecord { .. }) => GetRecordOutcome::SplitRecord,
+            Err(GetRecordError::RecordDoesNotMatch(_)) => GetRecordOutcome::RecordDoesNotMatch,
+            Err(GetRecordError::RecordKindMismatch) => GetRecordOutcome::RecordKindMismatch,
+            Err(GetRecordError::QueryTimeout) => GetRecordOutcome::QueryTimeout,
+            Err(GetRecordError::Cancelled) => GetRecordOutcome::Cancelled,
+        };
+        Self { outcome }
+    }
+}
+
+/// Buckets from 100ms up to ~51s, covering both the early completions and the full kad timeout.
+pub(crate) fn duration_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.1, 2.0, 10))
+}
+
+/// One bucket per copy, up to the kad replication factor.
+pub(crate) fn copies_histogram() -> Histogram {
+    Histogram::new(linear_buckets(0.0, 1.0, 21))
+}
+
+impl super::NetworkMetricsRecorder {
+    /// Records the outcome of a completed GetRecord query, along with the time it took and the number of copies
+    /// received.
+    pub(crate) fn record_get_record(
+        &self,
+        result: &Result<GetRecordOk, GetRecordError>,
+        copies: usize,
+        duration: Option<Duration>,
+    ) {
+        let labels = GetRecordOutcomeLabels::from(result);
+        let _ = self.get_record_outcomes.get_or_create(&labels).inc();
+        if let Some(duration) = duration {
+            self.get_record_duration_seconds
+                .get_or_create(&labels)
+                .observe(duration.as_secs_f64());
+        }
+        self.get_record_copies_received.observe(copies as f64);
+    }
+}
@@ -8,21 +8,35 @@
 
 // Implementation to record `libp2p::upnp::Event` metrics
 mod bad_node;
+mod get_record;
 pub mod service;
+mod swarm_cmd;
 #[cfg(feature = "upnp")]
 mod upnp;
 
 use crate::MetricsRegistries;
-use crate::{log_markers::Marker, target_arch::sleep};
+use crate::{
+    bandwidth::BandwidthLabels,
+    driver_state::KBucketLabels,
+    log_markers::Marker,
+    reachability::{Reachability, ReachabilityLabels},
+    record_store::RecordEvictionLabels,
+    replication_fetcher::FetchPriorityLabels,
+    sybil_guard::SybilSuspicionLabels,
+    target_arch::sleep,
+};
 use bad_node::{BadNodeMetrics, BadNodeMetricsMsg, TimeFrame};
+use get_record::GetRecordOutcomeLabels;
 use libp2p::{
     metrics::{Metrics as Libp2pMetrics, Recorder},
     PeerId,
 };
 use prometheus_client::{
     metrics::family::Family,
-    metrics::{counter::Counter, gauge::Gauge},
+    metrics::{counter::Counter, gauge::Gauge, histogram::Histogram},
 };
+pub(crate) use swarm_cmd::SwarmCmdChannel;
+use swarm_cmd::SwarmCmdChannelLabels;
 use sysinfo::{Pid, ProcessRefreshKind, System};
 use tokio::time::Duration;
 
@@ -44,15 +58,49 @@ pub(crate) struct NetworkMetricsRecorder {
     pub(crate) open_connections: Gauge,
     pub(crate) peers_in_routing_table: Gauge,
     pub(crate) records_stored: Gauge,
+    pub(crate) records_evicted: Family<RecordEvictionLabels, Counter>,
+    pub(crate) bandwidth_bytes: Family<BandwidthLabels, Gauge>,
+    reachability: Family<ReachabilityLabels, Gauge>,
+    pub(crate) relay_reservations: Gauge,
+    pub(crate) relay_reservation_failures: Counter,
+    pub(crate) relay_server_reservations: Gauge,
+    pub(crate) relay_server_circuits: Gauge,
+    pub(crate) sybil_suspicions: Family<SybilSuspicionLabels, Counter>,
+
+    // replication metrics
+    pub(crate) replication_pending_keys: Gauge,
+    pub(crate) replication_pending_keys_per_priority: Family<FetchPriorityLabels, Gauge>,
+    pub(crate) replication_ongoing_fetches: Gauge,
+    pub(crate) replication_oldest_pending_seconds: Gauge,
+    pub(crate) relevant_records_coverage_percentage: Gauge,
+
+    // SwarmDriver state metrics
+    pub(crate) pending_get_closest_peers: Gauge,
+    pub(crate) pending_requests: Gauge,
+    pub(crate) pending_get_record: Gauge,
+    pub(crate) pending_put_record: Gauge,
+    pub(crate) pending_dials: Gauge,
+    pub(crate) peers_per_kbucket: Family<KBucketLabels, Gauge>,
+
+    // get_record metrics
+    get_record_outcomes: Family<GetRecordOutcomeLabels, Counter>,
+    get_record_duration_seconds: Family<GetRecordOutcomeLabels, Histogram>,
+    get_record_copies_received: Histogram,
+
+    // swarm cmd channel metrics
+    swarm_cmd_queue_depth: Family<SwarmCmdChannelLabels, Gauge>,
+    swarm_cmd_queue_latency_seconds: Family<SwarmCmdChannelLabels, Histogram>,
 
     // quoting metrics
     relevant_records: Gauge,
     max_records: Gauge,
     received_payment_count: Gauge,
     live_time: Gauge,
+    store_cost_quotes: Counter,
 
     // bad node metrics
     bad_peers_count: Counter,
+    pub(crate) shunned_peers: Gauge,
     shunned_count: Counter,
     #[allow(dead_code)] // updated by background task
     shunned_count_across_time_frames: Family<TimeFrame, Gauge>,
@@ -85,6 +133,128 @@ impl NetworkMetricsRecorder {
             records_stored.clone(),
         );
 
+        let records_evicted = Family::default();
+        sub_registry.register(
+            "records_evicted",
+            "The number of records evicted to stay within the limits of the record store",
+            records_evicted.clone(),
+        );
+
+        let bandwidth_bytes = Family::default();
+        sub_registry.register(
+            "bandwidth_bytes",
+            "The bytes sent and received over all the streams, per negotiated protocol",
+            bandwidth_bytes.clone(),
+        );
+
+        let reachability = Family::default();
+        sub_registry.register(
+            "reachability",
+            "Whether the node is reachable from the public internet, as detected by AutoNAT. Set to 1 for the current status",
+            reachability.clone(),
+        );
+
+        let relay_reservations = Gauge::default();
+        sub_registry.register(
+            "relay_reservations",
+            "The number of reservations held on relay servers, for a node behind home network to be reachable",
+            relay_reservations.clone(),
+        );
+        let relay_reservation_failures = Counter::default();
+        sub_registry.register(
+            "relay_reservation_failures",
+            "The number of reservations on relay servers that failed or were lost",
+            relay_reservation_failures.clone(),
+        );
+
+        let relay_server_reservations = Gauge::default();
+        sub_registry.register(
+            "relay_server_reservations",
+            "The number of peers behind home network we are relaying for, in relay server mode",
+            relay_server_reservations.clone(),
+        );
+        let relay_server_circuits = Gauge::default();
+        sub_registry.register(
+            "relay_server_circuits",
+            "The number of connections we are currently relaying, in relay server mode",
+            relay_server_circuits.clone(),
+        );
+
+        let sybil_suspicions = Family::default();
+        sub_registry.register(
+            "sybil_suspicions",
+            "The number of peers kept out of crowded kbuckets and of bursts of new peers joining a close group",
+            sybil_suspicions.clone(),
+        );
+
+        let replication_pending_keys = Gauge::default();
+        sub_registry.register(
+            "replication_pending_keys",
+            "The number of keys waiting to be fetched through replication",
+            replication_pending_keys.clone(),
+        );
+        let replication_pending_keys_per_priority = Family::default();
+        sub_registry.register(
+            "replication_pending_keys_per_priority",
+            "The number of keys waiting to be fetched through replication, per fetch priority",
+            replication_pending_keys_per_priority.clone(),
+        );
+        let replication_ongoing_fetches = Gauge::default();
+        sub_registry.register(
+            "replication_ongoing_fetches",
+            "The number of replication fetches in progress",
+            replication_ongoing_fetches.clone(),
+        );
+        let replication_oldest_pending_seconds = Gauge::default();
+        sub_registry.register(
+            "replication_oldest_pending_seconds",
+            "How long the oldest key waiting to be fetched through replication has been waiting for",
+            replication_oldest_pending_seconds.clone(),
+        );
+
+        let pending_get_closest_peers = Gauge::default();
+        sub_registry.register(
+            "pending_get_closest_peers",
+            "The number of get_closest_peers queries in flight",
+            pending_get_closest_peers.clone(),
+        );
+        let pending_requests = Gauge::default();
+        sub_registry.register(
+            "pending_requests",
+            "The number of requests sent to peers that are waiting for a response",
+            pending_requests.clone(),
+        );
+        let pending_get_record = Gauge::default();
+        sub_registry.register(
+            "pending_get_record",
+            "The number of get_record queries in flight",
+            pending_get_record.clone(),
+        );
+        let pending_put_record = Gauge::default();
+        sub_registry.register(
+            "pending_put_record",
+            "The number of put_record queries in flight",
+            pending_put_record.clone(),
+        );
+        let pending_dials = Gauge::default();
+        sub_registry.register(
+            "pending_dials",
+            "The number of outgoing connections being established",
+            pending_dials.clone(),
+        );
+        let peers_per_kbucket = Family::default();
+        sub_registry.register(
+            "peers_per_kbucket",
+            "The number of peers in each kbucket, labelled by its ilog2 distance",
+            peers_per_kbucket.clone(),
+        );
+        let relevant_records_coverage_percentage = Gauge::default();
+        sub_registry.register(
+            "relevant_records_coverage_percentage",
+            "The percentage of the records we're responsible for that we hold, the rest waiting to be replicated. Value is from 0-100",
+            relevant_records_coverage_percentage.clone(),
+        );
+
         let connected_peers = Gauge::default();
         sub_registry.register(
             "connected_peers",
@@ -111,6 +281,40 @@ impl NetworkMetricsRecorder {
             peers_in_routing_table.clone(),
         );
 
+        let get_record_outcomes = Family::default();
+        sub_registry.register(
+            "get_record_outcomes",
+            "The number of completed get_record queries, by outcome",
+            get_record_outcomes.clone(),
+        );
+        let duration_histogram: fn() -> Histogram = get_record::duration_histogram;
+        let get_record_duration_seconds = Family::new_with_constructor(duration_histogram);
+        sub_registry.register(
+            "get_record_duration_seconds",
+            "The time taken from the start of a get_record query to its completion, by outcome",
+            get_record_duration_seconds.clone(),
+        );
+        let get_record_copies_received = get_record::copies_histogram();
+        sub_registry.register(
+            "get_record_copies_received",
+            "The number of record copies received by a get_record query before its completion",
+            get_record_copies_received.clone(),
+        );
+
+        let swarm_cmd_queue_depth = Family::default();
+        sub_registry.register(
+            "swarm_cmd_queue_depth",
+            "The number of cmds waiting in the channels to the SwarmDriver, per channel",
+            swarm_cmd_queue_depth.clone(),
+        );
+        let latency_histogram: fn() -> Histogram = swarm_cmd::queue_latency_histogram;
+        let swarm_cmd_queue_latency_seconds = Family::new_with_constructor(latency_histogram);
+        sub_registry.register(
+            "swarm_cmd_queue_latency_seconds",
+            "The time the cmds waited in the channels to the SwarmDriver before being handled, per channel",
+            swarm_cmd_queue_latency_seconds.clone(),
+        );
+
         let shunned_count = Counter::default();
         sub_registry.register(
             "shunned_count",
@@ -118,6 +322,13 @@ impl NetworkMetricsRecorder {
             shunned_count.clone(),
         );
 
+        let shunned_peers = Gauge::default();
+        sub_registry.register(
+            "shunned_peers",
+            "The number of peers currently considered as bad by our node",
+            shunned_peers.clone(),
+        );
+
         let bad_peers_count = Counter::default();
         sub_registry.register(
             "bad_peers_count",
@@ -174,6 +385,13 @@ impl NetworkMetricsRecorder {
             live_time.clone(),
         );
 
+        let store_cost_quotes = Counter::default();
+        sub_registry.register(
+            "store_cost_quotes",
+            "The number of store cost quotes given by our node",
+            store_cost_quotes.clone(),
+        );
+
         let shunned_by_close_group = Gauge::default();
         sub_registry.register(
             "shunned_by_close_group",
@@ -211,16 +429,42 @@ impl NetworkMetricsRecorder {
             upnp_events,
 
             records_stored,
+            records_evicted,
+            replication_pending_keys,
+            replication_pending_keys_per_priority,
+            replication_ongoing_fetches,
+            replication_oldest_pending_seconds,
+            relevant_records_coverage_percentage,
+            pending_get_closest_peers,
+            pending_requests,
+            pending_get_record,
+            pending_put_record,
+            pending_dials,
+            peers_per_kbucket,
+            bandwidth_bytes,
+            reachability,
+            relay_reservations,
+            relay_reservation_failures,
+            relay_server_reservations,
+            relay_server_circuits,
+            sybil_suspicions,
             estimated_network_size,
             connected_peers,
             open_connections,
             peers_in_routing_table,
+            get_record_outcomes,
+            get_record_duration_seconds,
+            get_record_copies_received,
+            swarm_cmd_queue_depth,
+            swarm_cmd_queue_latency_seconds,
             relevant_records,
             max_records,
             received_payment_count,
             live_time,
+            store_cost_quotes,
 
             bad_peers_count,
+            shunned_peers,
             shunned_count_across_time_frames,
             shunned_count,
             shunned_by_close_group,
@@ -232,6 +476,7 @@ impl NetworkMetricsRecorder {
             bad_nodes_notifier,
         };
 
+        network_metrics.record_reachability(Reachability::default());
         network_metrics.system_metrics_recorder_task();
         network_metrics
     }
@@ -285,6 +530,7 @@ impl NetworkMetricsRecorder {
                 });
             }
             Marker::QuotingMetrics { quoting_metrics } => {
+                let _ = self.store_cost_quotes.inc();
                 let _ = self.relevant_records.set(
                     quoting_metrics
                         .close_records_stored
@@ -304,6 +550,15 @@ impl NetworkMetricsRecorder {
         }
     }
 
+    pub(crate) fn record_reachability(&self, current: Reachability) {
+        for status in Reachability::ALL {
+            let _ = self
+                .reachability
+                .get_or_create(&ReachabilityLabels { status })
+                .set(i64::from(status == current));
+        }
+    }
+
     pub(crate) fn record_change_in_close_group(&self, new_close_group: Vec<PeerId>) {
         let bad_nodes_notifier = self.bad_nodes_notifier.clone();
         crate::target_arch::spawn(async move {
@@ -330,6 +585,12 @@ impl Recorder<libp2p::relay::Event> for NetworkMetricsRecorder {
     }
 }
 
+impl Recorder<libp2p::dcutr::Event> for NetworkMetricsRecorder {
+    fn record(&self, event: &libp2p::dcutr::Event) {
+        self.libp2p_metrics.record(event)
+    }
+}
+
 impl Recorder<libp2p::identify::Event> for NetworkMetricsRecorder {
     fn record(&self, event: &libp2p::identify::Event) {
         self.libp2p_metrics.record(event)
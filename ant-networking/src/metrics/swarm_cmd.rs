@@ -0,0 +1,49 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::histogram::{exponential_buckets, Histogram},
+};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct SwarmCmdChannelLabels {
+    channel: SwarmCmdChannel,
+}
+
+/// The channels the cmds to the `SwarmDriver` are queued on.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+pub(crate) enum SwarmCmdChannel {
+    Local,
+    Network,
+}
+
+/// Buckets from 0.1ms up to ~6.5s.
+pub(crate) fn queue_latency_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.0001, 2.0, 17))
+}
+
+impl super::NetworkMetricsRecorder {
+    /// Records the cmds left in the channel and how long the dequeued cmd waited in it.
+    pub(crate) fn record_swarm_cmd_dequeued(
+        &self,
+        channel: SwarmCmdChannel,
+        queue_depth: usize,
+        waited: Duration,
+    ) {
+        let labels = SwarmCmdChannelLabels { channel };
+        let _ = self
+            .swarm_cmd_queue_depth
+            .get_or_create(&labels)
+            .set(queue_depth as i64);
+        self.swarm_cmd_queue_latency_seconds
+            .get_or_create(&labels)
+            .observe(waited.as_secs_f64());
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{NetworkError, Result};
+use ipnet::IpNet;
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{net::IpAddr, path::Path};
+
+/// Static rules on which peers to talk to, set by the operator.
+///
+/// Blocked peers are never talked to. If any allowed peer or IP range is set, only the peers
+/// matching one of them are talked to, e.g. to build a private network.
+///
+/// IP ranges are checked against the remote address of the connections to the peer, which is the
+/// address of the relay for relayed connections.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerAccessList {
+    /// Peers never to talk to
+    pub blocked_peers: Vec<PeerId>,
+    /// IP ranges never to talk to, e.g. `203.0.113.0/24`
+    pub blocked_ip_ranges: Vec<IpNet>,
+    /// Peers to talk to, if the allowlist is enabled
+    pub allowed_peers: Vec<PeerId>,
+    /// IP ranges to talk to, if the allowlist is enabled
+    pub allowed_ip_ranges: Vec<IpNet>,
+}
+
+impl PeerAccessList {
+    /// Read the list from a JSON file, with any of the `blocked_peers`, `blocked_ip_ranges`,
+    /// `allowed_peers` and `allowed_ip_ranges` fields.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|err| NetworkError::InvalidPeerAccessList(err.to_string()))
+    }
+
+    /// Whether only the allowed peers and IP ranges are talked to.
+    pub fn is_allowlist_enabled(&self) -> bool {
+        !self.allowed_peers.is_empty() || !self.allowed_ip_ranges.is_empty()
+    }
+
+    /// Whether the peer, connected through the addresses, can be talked to.
+    pub fn is_allowed<'a>(
+        &self,
+        peer_id: &PeerId,
+        addrs: impl IntoIterator<Item = &'a Multiaddr>,
+    ) -> bool {
+        if self.blocked_peers.contains(peer_id) {
+            return false;
+        }
+
+        let ips: Vec<_> = addrs.into_iter().filter_map(multiaddr_ip).collect();
+        let in_ranges = |ranges: &[IpNet]| {
+            ips.iter()
+                .any(|ip| ranges.iter().any(|range| range.contains(ip)))
+        };
+        if in_ranges(&self.blocked_ip_ranges) {
+            return false;
+        }
+
+        !self.is_allowlist_enabled()
+            || self.allowed_peers.contains(peer_id)
+            || in_ranges(&self.allowed_ip_ranges)
+    }
+}
+
+/// The IP of a multiaddr, if any.
+fn multiaddr_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peers_are_checked_against_the_blocklist_and_allowlist() -> eyre::Result<()> {
+        let (peer, other_peer) = (PeerId::random(), PeerId::random());
+        let addr: Multiaddr = "/ip4/203.0.113.7/udp/12000/quic-v1".parse()?;
+        let other_addr: Multiaddr = "/ip4/198.51.100.1/udp/12000/quic-v1".parse()?;
+
+        let mut access_list = PeerAccessList::default();
+        assert!(access_list.is_allowed(&peer, [&addr]));
+
+        access_list.blocked_ip_ranges = vec!["203.0.113.0/24".parse()?];
+        assert!(!access_list.is_allowed(&peer, [&addr]));
+        assert!(access_list.is_allowed(&peer, [&other_addr]));
+
+        access_list.blocked_peers = vec![other_peer];
+        assert!(!access_list.is_allowed(&other_peer, [&other_addr]));
+
+        // Only the allowed peers and ranges once the allowlist is enabled
+        access_list.allowed_peers = vec![peer];
+        assert!(access_list.is_allowed(&peer, [&other_addr]));
+        assert!(!access_list.is_allowed(&PeerId::random(), [&other_addr]));
+        access_list.allowed_ip_ranges = vec!["198.51.100.0/24".parse()?];
+        assert!(access_list.is_allowed(&PeerId::random(), [&other_addr]));
+        // The blocklist still wins
+        assert!(!access_list.is_allowed(&PeerId::random(), [&other_addr, &addr]));
+        Ok(())
+    }
+
+    #[test]
+    fn access_list_is_read_from_json() -> eyre::Result<()> {
+        let peer = PeerId::random();
+        let json =
+            format!(r#"{{"blocked_peers": ["{peer}"], "allowed_ip_ranges": ["10.0.0.0/8"]}}"#);
+        let access_list: PeerAccessList = serde_json::from_str(&json)?;
+
+        assert_eq!(access_list.blocked_peers, vec![peer]);
+        assert!(access_list.blocked_ip_ranges.is_empty());
+        assert!(access_list.is_allowlist_enabled());
+        Ok(())
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{cmd::NodeIssue, target_arch::Instant};
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// A peer is shunned once its score reaches this many strikes.
+pub(crate) const SHUNNING_THRESHOLD: f64 = 3.0;
+
+/// How long a peer stays shunned, for a peer that was only struggling to get a second chance.
+const SHUNNING_DURATION: Duration = Duration::from_secs(6 * 3600);
+
+/// Time for the score of a peer to halve, if not striking again.
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(300);
+
+/// The same issue is only struck once within this interval, to avoid being too sensitive to a burst.
+const STRIKE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Peers whose score decayed below this are forgotten.
+const FORGOTTEN_SCORE: f64 = 0.1;
+
+/// The file in the node's root dir the peer reputation is persisted to.
+const PEER_REPUTATION_FILENAME: &str = "peer_reputation";
+
+/// Max number of recently-good peers persisted, to be dialed on the next start.
+pub(crate) const MAX_PERSISTED_GOOD_PEERS: usize = 50;
+
+impl NodeIssue {
+    /// The number of strikes the issue is worth.
+    fn strikes(&self) -> f64 {
+        match self {
+            // Serving corrupted data is never accidental
+            NodeIssue::CorruptedRecord => SHUNNING_THRESHOLD,
+            NodeIssue::FailedChunkProofCheck => 1.5,
+            NodeIssue::ReplicationFailure
+            | NodeIssue::CloseNodesShunning
+            | NodeIssue::BadQuoting => 1.0,
+            // Lagging peers are only shunned if failing repeatedly
+            NodeIssue::RequestTimeout | NodeIssue::MissingRecord => 0.5,
+        }
+    }
+}
+
+/// The score of a peer, as reported by the node RPC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerScore {
+    pub peer_id: PeerId,
+    /// The decayed number of strikes of the peer
+    pub score: f64,
+    /// Whether the peer is shunned
+    pub is_bad: bool,
+    /// The latest issue the peer was struck for
+    pub last_issue: Option<NodeIssue>,
+    /// When the peer got shunned, for the shunning to expire across restarts
+    #[serde(default)]
+    pub shunned_at: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+struct ScoreEntry {
+    score: f64,
+    updated_at: Instant,
+    last_strikes: BTreeMap<NodeIssue, Instant>,
+    last_issue: Option<NodeIssue>,
+    /// When the peer got shunned, if it is
+    shunned_at: Option<Instant>,
+    /// The wall clock time of `shunned_at`, to be persisted
+    shunned_since: Option<SystemTime>,
+}
+
+impl ScoreEntry {
+    fn new(now: Instant) -> Self {
+        Self {
+            score: 0.0,
+            updated_at: now,
+            last_strikes: BTreeMap::new(),
+            last_issue: None,
+            shunned_at: None,
+            shunned_since: None,
+        }
+    }
+
+    fn is_bad(&self, now: Instant) -> bool {
+        self.shunned_at
+            .is_some_and(|shunned_at| now.saturating_duration_since(shunned_at) < SHUNNING_DURATION)
+    }
+
+    fn decayed_score(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.score * 0.5_f64.powf(elapsed.as_secs_f64() / SCORE_HALF_LIFE.as_secs_f64())
+    }
+}
+
+/// Scores the peers on their observed misbehaviour, shunning the ones striking too often.
+///
+/// Scores decay over time, so that occasional issues of a healthy peer never add up to the threshold.
+/// Once shunned, a peer stays so for `SHUNNING_DURATION`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PeerScores {
+    peers: BTreeMap<PeerId, ScoreEntry>,
+}
+
+impl PeerScores {
+    /// Strike the peer for the issue. Returns `true` if this got the peer shunned.
+    pub(crate) fn strike(&mut self, peer_id: PeerId, issue: NodeIssue) -> bool {
+        self.strike_at(peer_id, issue, Instant::now())
+    }
+
+    fn strike_at(&mut self, peer_id: PeerId, issue: NodeIssue, now: Instant) -> bool {
+        self.forget_well_behaved(now);
+        let entry = self
+            .peers
+            .entry(peer_id)
+            .or_insert_with(|| ScoreEntry::new(now));
+        if entry.is_bad(now) {
+            return false;
+        }
+
+        let is_new_strike = entry
+            .last_strikes
+            .get(&issue)
+            .is_none_or(|last| now.saturating_duration_since(*last) > STRIKE_INTERVAL);
+        if !is_new_strike {
+            return false;
+        }
+
+        entry.score = entry.decayed_score(now) + issue.strikes();
+        entry.updated_at = now;
+        let _ = entry.last_strikes.insert(issue, now);
+        entry.last_issue = Some(issue);
+
+        if entry.score >= SHUNNING_THRESHOLD {
+            info!(
+                "Peer {peer_id:?} reached a score of {:.2} with issue {issue:?}. Consider it as a bad node now.",
+                entry.score
+            );
+            entry.shunned_at = Some(now);
+            entry.shunned_since = Some(SystemTime::now());
+            return true;
+        }
+        false
+    }
+
+    /// Whether the peer is shunned.
+    pub(crate) fn is_bad(&self, peer_id: &PeerId) -> bool {
+        let now = Instant::now();
+        self.peers
+            .get(peer_id)
+            .is_some_and(|entry| entry.is_bad(now))
+    }
+
+    /// The shunned peers.
+    pub(crate) fn bad_peers(&self) -> impl Iterator<Item = &PeerId> {
+        let now = Instant::now();
+        self.peers
+            .iter()
+            .filter(move |(_peer_id, entry)| entry.is_bad(now))
+            .map(|(peer_id, _entry)| peer_id)
+    }
+
+    /// Release the peers whose shunning expired, returning them to be unblocked.
+    pub(crate) fn release_expired(&mut self) -> Vec<PeerId> {
+        self.release_expired_at(Instant::now())
+    }
+
+    fn release_expired_at(&mut self, now: Instant) -> Vec<PeerId> {
+        let mut released = vec![];
+        for (peer_id, entry) in self.peers.iter_mut() {
+            if entry.shunned_at.is_some() && !entry.is_bad(now) {
+                info!("Peer {peer_id:?} was shunned for long enough, giving it another chance");
+                entry.shunned_at = None;
+                entry.shunned_since = None;
+                released.push(*peer_id);
+            }
+        }
+        self.forget_well_behaved(now);
+        released
+    }
+
+    /// The current scores of the peers with any strike, highest first.
+    pub(crate) fn scores(&self) -> Vec<PeerScore> {
+        let now = Instant::now();
+        let mut scores: Vec<_> = self
+            .peers
+            .iter()
+            .map(|(peer_id, entry)| PeerScore {
+                peer_id: *peer_id,
+                score: entry.decayed_score(now),
+                is_bad: entry.is_bad(now),
+                last_issue: entry.last_issue,
+                shunned_at: entry.shunned_since,
+            })
+            .collect();
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scores
+    }
+
+    /// Restore the scores persisted `elapsed` ago, decaying them for the time the node was down.
+    ///
+    /// The peers shunned for longer than `SHUNNING_DURATION` are released, the ones persisted
+    /// without the time they got shunned at being taken as shunned when persisted.
+    pub(crate) fn restore(&mut self, scores: &[PeerScore], elapsed: Duration) {
+        let now = Instant::now();
+        let decay = 0.5_f64.powf(elapsed.as_secs_f64() / SCORE_HALF_LIFE.as_secs_f64());
+        for peer in scores {
+            let shunned_for = peer
+                .shunned_at
+                .and_then(|shunned_at| SystemTime::now().duration_since(shunned_at).ok())
+                .unwrap_or(elapsed);
+            let is_bad = peer.is_bad && shunned_for < SHUNNING_DURATION;
+            let _ = self.peers.insert(
+                peer.peer_id,
+                ScoreEntry {
+                    score: peer.score * decay,
+                    updated_at: now,
+                    last_strikes: BTreeMap::new(),
+                    last_issue: peer.last_issue,
+                    shunned_at: is_bad.then(|| now.checked_sub(shunned_for).unwrap_or(now)),
+                    shunned_since: is_bad
+                        .then(|| SystemTime::now().checked_sub(shunned_for))
+                        .flatten(),
+                },
+            );
+        }
+        self.forget_well_behaved(now);
+    }
+
+    /// Drop the peers not shunned whose score decayed to almost nothing, to avoid growing forever.
+    fn forget_well_behaved(&mut self, now: Instant) {
+        self.peers.retain(|_peer_id, entry| {
+            entry.shunned_at.is_some() || entry.decayed_score(now) >= FORGOTTEN_SCORE
+        });
+    }
+}
+
+/// What the node learnt about its peers, persisted across restarts to avoid re-learning it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PeerReputation {
+    saved_at: SystemTime,
+    scores: Vec<PeerScore>,
+    /// Peers recently in the routing table, with their dialable addresses
+    pub(crate) good_peers: Vec<(PeerId, Vec<Multiaddr>)>,
+}
+
+impl PeerReputation {
+    pub(crate) fn new(scores: &PeerScores, good_peers: Vec<(PeerId, Vec<Multiaddr>)>) -> Self {
+        Self {
+            saved_at: SystemTime::now(),
+            scores: scores.scores(),
+            good_peers,
+        }
+    }
+
+    /// Load the reputation persisted in the root dir, if any.
+    pub(crate) fn load(root_dir: &Path) -> Option<Self> {
+        let file = fs::File::open(root_dir.join(PEER_REPUTATION_FILENAME)).ok()?;
+        match rmp_serde::from_read(&file) {
+            Ok(reputation) => Some(reputation),
+            Err(err) => {
+                warn!("Failed to deserialize the persisted peer reputation: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// Persist the reputation to the root dir, replacing the previous one.
+    pub(crate) fn save(&self, root_dir: &Path) -> std::io::Result<()> {
+        let bytes = rmp_serde::to_vec(self).map_err(std::io::Error::other)?;
+        // Write to a temp file first, to never leave a truncated file behind
+        let tmp_path = root_dir.join(format!("{PEER_REPUTATION_FILENAME}.tmp"));
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, root_dir.join(PEER_REPUTATION_FILENAME))
+    }
+
+    /// Restore the persisted scores, decayed for the time since they were saved.
+    pub(crate) fn restore_scores(&self, peer_scores: &mut PeerScores) {
+        let elapsed = SystemTime::now()
+            .duration_since(self.saved_at)
+            .unwrap_or_default();
+        peer_scores.restore(&self.scores, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    #[test]
+    fn repeated_issues_get_a_peer_shunned() {
+        let mut scores = PeerScores::default();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert!(!scores.strike_at(peer, NodeIssue::ReplicationFailure, now));
+        // A burst of the same issue only strikes once
+        assert!(!scores.strike_at(peer, NodeIssue::ReplicationFailure, now));
+        assert!(!scores.strike_at(peer, NodeIssue::BadQuoting, now));
+        assert!(!scores.is_bad(&peer));
+
+        let later = now + STRIKE_INTERVAL * 2;
+        assert!(scores.strike_at(peer, NodeIssue::FailedChunkProofCheck, later));
+        assert!(scores.is_bad(&peer));
+        assert_eq!(scores.bad_peers().collect::<Vec<_>>(), vec![&peer]);
+        // Already shunned
+        assert!(!scores.strike_at(peer, NodeIssue::ReplicationFailure, later));
+    }
+
+    #[test]
+    fn scores_decay_over_time() {
+        let mut scores = PeerScores::default();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert!(!scores.strike_at(peer, NodeIssue::ReplicationFailure, now));
+        assert!(!scores.strike_at(peer, NodeIssue::BadQuoting, now));
+
+        // Two half lives later, the two strikes are down to half of one
+        let later = now + SCORE_HALF_LIFE * 2;
+        assert!(!scores.strike_at(peer, NodeIssue::ReplicationFailure, later));
+        assert!(!scores.is_bad(&peer));
+
+        let entry = &scores.peers[&peer];
+        assert!((entry.decayed_score(later) - 1.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn shunning_expires() {
+        let mut scores = PeerScores::default();
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        assert!(scores.strike_at(peer, NodeIssue::CorruptedRecord, now));
+        assert!(scores.release_expired_at(now).is_empty());
+
+        let later = now + SHUNNING_DURATION;
+        assert_eq!(scores.release_expired_at(later), vec![peer]);
+        // The score decayed meanwhile, so the peer is forgotten
+        assert!(scores.peers.is_empty());
+        assert!(!scores.strike_at(peer, NodeIssue::ReplicationFailure, later));
+    }
+
+    #[test]
+    fn serving_corrupted_records_gets_a_peer_shunned_at_once() {
+        let mut scores = PeerScores::default();
+        let peer = PeerId::random();
+
+        assert!(scores.strike(peer, NodeIssue::CorruptedRecord));
+        assert!(scores.is_bad(&peer));
+        assert_eq!(
+            scores.scores()[0].last_issue,
+            Some(NodeIssue::CorruptedRecord)
+        );
+    }
+
+    #[test]
+    fn reputation_is_persisted_across_restarts() -> eyre::Result<()> {
+        let dir = TempDir::new()?;
+        let (bad_peer, struck_peer, good_peer) =
+            (PeerId::random(), PeerId::random(), PeerId::random());
+
+        let mut scores = PeerScores::default();
+        assert!(scores.strike(bad_peer, NodeIssue::CorruptedRecord));
+        assert!(!scores.strike(struck_peer, NodeIssue::BadQuoting));
+        let good_peers = vec![(good_peer, vec!["/ip4/127.0.0.1/udp/12000/quic-v1".parse()?])];
+        PeerReputation::new(&scores, good_peers.clone()).save(dir.path())?;
+
+        let reputation = PeerReputation::load(dir.path()).expect("reputation to be persisted");
+        assert_eq!(reputation.good_peers, good_peers);
+
+        let mut restored = PeerScores::default();
+        reputation.restore_scores(&mut restored);
+        assert!(restored.is_bad(&bad_peer));
+        assert!(!restored.is_bad(&struck_peer));
+        assert_eq!(restored.scores().len(), 2);
+
+        // Scores keep decaying while the node is down
+        let mut restored = PeerScores::default();
+        restored.restore(&reputation.scores, SCORE_HALF_LIFE * 10);
+        assert!(restored.is_bad(&bad_peer));
+        assert_eq!(restored.scores().len(), 1);
+
+        // And the shunning expires meanwhile
+        let mut scores = reputation.scores.clone();
+        for score in scores.iter_mut() {
+            score.shunned_at = score
+                .shunned_at
+                .and_then(|shunned_at| shunned_at.checked_sub(SHUNNING_DURATION));
+        }
+        let mut restored = PeerScores::default();
+        restored.restore(&scores, Duration::ZERO);
+        assert!(!restored.is_bad(&bad_peer));
+
+        // Without the time it got shunned at, the peer is taken as shunned when persisted
+        for score in scores.iter_mut() {
+            score.shunned_at = None;
+        }
+        let mut restored = PeerScores::default();
+        restored.restore(&scores, SCORE_HALF_LIFE);
+        assert!(restored.is_bad(&bad_peer));
+        let mut restored = PeerScores::default();
+        restored.restore(&scores, SHUNNING_DURATION);
+        assert!(!restored.is_bad(&bad_peer));
+        Ok(())
+    }
+}
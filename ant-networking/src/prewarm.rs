@@ -0,0 +1,142 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{driver::PendingGetClosestType, SwarmDriver};
+use ant_protocol::NetworkAddress;
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::oneshot;
+
+/// The max number of prewarm `get_closest_peers` queries to have in flight at once, across all the batches.
+const MAX_PARALLEL_PREWARM_QUERIES: usize = 4;
+
+/// Identifies the set of addresses passed to a single `Network::prewarm` call.
+pub(crate) type PrewarmBatchId = u64;
+
+/// Queue of the addresses to be looked up to warm the routing table toward their close groups.
+///
+/// Addresses are queried in the order they were requested, with at most `MAX_PARALLEL_PREWARM_QUERIES` lookups in
+/// flight. The caller of each batch is notified once all of its lookups have completed.
+#[derive(Debug, Default)]
+pub(crate) struct RoutingTablePrewarm {
+    queue: VecDeque<(PrewarmBatchId, NetworkAddress)>,
+    /// The number of lookups yet to complete for a batch, and the channel to notify its caller.
+    batches: HashMap<PrewarmBatchId, (usize, oneshot::Sender<()>)>,
+    in_flight: usize,
+    next_batch_id: PrewarmBatchId,
+}
+
+impl RoutingTablePrewarm {
+    /// Queue up the addresses of a new batch. The sender is notified straight away if there is nothing to look up.
+    pub(crate) fn add_batch(
+        &mut self,
+        addresses: Vec<NetworkAddress>,
+        sender: oneshot::Sender<()>,
+    ) {
+        if addresses.is_empty() {
+            let _ = sender.send(());
+            return;
+        }
+
+        let batch_id = self.next_batch_id;
+        self.next_batch_id = self.next_batch_id.wrapping_add(1);
+
+        let _ = self.batches.insert(batch_id, (addresses.len(), sender));
+        self.queue
+            .extend(addresses.into_iter().map(|address| (batch_id, address)));
+    }
+
+    /// Take the queued lookups that fit within the parallelism bound, marking them as in flight.
+    pub(crate) fn next_lookups(&mut self) -> Vec<(PrewarmBatchId, NetworkAddress)> {
+        let available = MAX_PARALLEL_PREWARM_QUERIES.saturating_sub(self.in_flight);
+        let lookups = self
+            .queue
+            .drain(..available.min(self.queue.len()))
+            .collect::<Vec<_>>();
+        self.in_flight += lookups.len();
+        lookups
+    }
+
+    /// A lookup of the batch has completed, notify the caller if it was the last one.
+    pub(crate) fn lookup_completed(&mut self, batch_id: PrewarmBatchId) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+
+        let batch_done = if let Some((remaining, _)) = self.batches.get_mut(&batch_id) {
+            *remaining = remaining.saturating_sub(1);
+            *remaining == 0
+        } else {
+            false
+        };
+        if batch_done {
+            if let Some((_, sender)) = self.batches.remove(&batch_id) {
+                if sender.send(()).is_err() {
+                    debug!("The caller of prewarm batch {batch_id} has dropped");
+                }
+            }
+        }
+    }
+}
+
+impl SwarmDriver {
+    /// Issue the `get_closest_peers` lookups of the prewarm queue, as far as the parallelism bound allows.
+    pub(crate) fn issue_prewarm_lookups(&mut self) {
+        for (batch_id, address) in self.routing_table_prewarm.next_lookups() {
+            let query_id = self
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .get_closest_peers(address.as_bytes());
+            debug!("Prewarming the routing table toward {address:?} with query {query_id:?}");
+            let _ = self.pending_get_closest_peers.insert(
+                query_id,
+                (PendingGetClosestType::Prewarm(batch_id), Default::default()),
+            );
+        }
+    }
+
+    /// A prewarm lookup has completed, move on to the next queued ones.
+    pub(crate) fn handle_prewarm_lookup_completed(&mut self, batch_id: PrewarmBatchId) {
+        self.routing_table_prewarm.lookup_completed(batch_id);
+        self.issue_prewarm_lookups();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::PeerId;
+
+    #[test]
+    fn prewarm_lookups_are_bounded_and_batches_notified() {
+        let mut prewarm = RoutingTablePrewarm::default();
+        let addresses = (0..MAX_PARALLEL_PREWARM_QUERIES + 2)
+            .map(|_| NetworkAddress::from_peer(PeerId::random()))
+            .collect::<Vec<_>>();
+        let (sender, mut receiver) = oneshot::channel();
+        prewarm.add_batch(addresses, sender);
+
+        let lookups = prewarm.next_lookups();
+        assert_eq!(lookups.len(), MAX_PARALLEL_PREWARM_QUERIES);
+        assert!(prewarm.next_lookups().is_empty());
+
+        for (batch_id, _) in lookups {
+            prewarm.lookup_completed(batch_id);
+        }
+        assert!(receiver.try_recv().is_err());
+
+        let lookups = prewarm.next_lookups();
+        assert_eq!(lookups.len(), 2);
+        for (batch_id, _) in lookups {
+            prewarm.lookup_completed(batch_id);
+        }
+        assert!(receiver.try_recv().is_ok());
+
+        let (sender, mut receiver) = oneshot::channel();
+        prewarm.add_batch(vec![], sender);
+        assert!(receiver.try_recv().is_ok());
+    }
+}
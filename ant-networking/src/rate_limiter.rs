@@ -0,0 +1,311 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::target_arch::Instant;
+use ant_protocol::{
+    messages::{Cmd, CmdResponse, Query, QueryResponse, Request, Response},
+    Error as ProtocolError, NetworkAddress,
+};
+use libp2p::PeerId;
+use std::{collections::HashMap, fmt, str::FromStr, time::Duration};
+
+/// Interval over which the buckets of the idle peers are dropped.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The kinds of inbound requests, each with its own budget.
+///
+/// Only the requests over the request/response protocol are budgeted; kademlia requests are
+/// answered by the kademlia behaviour itself. Puts are budgeted at the quoting step preceding them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum RequestKind {
+    Get,
+    Put,
+    Replication,
+}
+
+impl RequestKind {
+    /// The kind of the request, if it is budgeted.
+    pub(crate) fn of(request: &Request) -> Option<Self> {
+        match request {
+            Request::Query(Query::GetStoreQuote { .. }) => Some(Self::Put),
+            Request::Query(_) => Some(Self::Get),
+            Request::Cmd(Cmd::Replicate { .. }) => Some(Self::Replication),
+            Request::Cmd(Cmd::PeerConsideredAsBad { .. }) => None,
+        }
+    }
+}
+
+impl fmt::Display for RequestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Get => "get",
+            Self::Put => "put",
+            Self::Replication => "replication",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+impl FromStr for RequestKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "get" => Ok(Self::Get),
+            "put" => Ok(Self::Put),
+            "replication" => Ok(Self::Replication),
+            _ => Err(format!("Unknown request kind: {s}")),
+        }
+    }
+}
+
+/// The number of requests per second accepted from a single peer and from all peers.
+/// A budget of 0 is unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestBudget {
+    pub per_peer: u32,
+    pub global: u32,
+}
+
+/// The budgets of inbound requests of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub gets: RequestBudget,
+    pub puts: RequestBudget,
+    pub replication: RequestBudget,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            gets: RequestBudget {
+                per_peer: 100,
+                global: 1000,
+            },
+            puts: RequestBudget {
+                per_peer: 20,
+                global: 500,
+            },
+            replication: RequestBudget {
+                per_peer: 10,
+                global: 200,
+            },
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn budget(&self, kind: RequestKind) -> RequestBudget {
+        match kind {
+            RequestKind::Get => self.gets,
+            RequestKind::Put => self.puts,
+            RequestKind::Replication => self.replication,
+        }
+    }
+
+    pub fn set_budget(&mut self, kind: RequestKind, budget: RequestBudget) {
+        match kind {
+            RequestKind::Get => self.gets = budget,
+            RequestKind::Put => self.puts = budget,
+            RequestKind::Replication => self.replication = budget,
+        }
+    }
+}
+
+/// A token bucket refilled at `rate` tokens per second, holding at most a second worth of them.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(rate: u32, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(rate),
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, rate: u32, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * f64::from(rate)).min(f64::from(rate));
+        self.updated_at = now;
+    }
+
+    fn has_token(&self, rate: u32) -> bool {
+        rate == 0 || self.tokens >= 1.0
+    }
+
+    fn take(&mut self, rate: u32) {
+        if rate > 0 {
+            self.tokens -= 1.0;
+        }
+    }
+
+    fn is_full(&self, rate: u32, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens + elapsed.as_secs_f64() * f64::from(rate) >= f64::from(rate)
+    }
+}
+
+/// Limits the rate of the inbound requests, per peer and from all peers.
+#[derive(Debug)]
+pub(crate) struct RequestRateLimiter {
+    config: RateLimitConfig,
+    peers: HashMap<(PeerId, RequestKind), TokenBucket>,
+    global: HashMap<RequestKind, TokenBucket>,
+    last_pruned: Instant,
+}
+
+impl RequestRateLimiter {
+    pub(crate) fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+            global: HashMap::new(),
+            last_pruned: Instant::now(),
+        }
+    }
+
+    /// Whether the request from the peer is within the budgets. If so, it's counted against them.
+    pub(crate) fn allow(&mut self, peer_id: PeerId, kind: RequestKind) -> bool {
+        self.allow_at(peer_id, kind, Instant::now())
+    }
+
+    fn allow_at(&mut self, peer_id: PeerId, kind: RequestKind, now: Instant) -> bool {
+        self.prune_idle_peers(now);
+        let budget = self.config.budget(kind);
+
+        let global = self
+            .global
+            .entry(kind)
+            .or_insert_with(|| TokenBucket::full(budget.global, now));
+        global.refill(budget.global, now);
+        let peer = self
+            .peers
+            .entry((peer_id, kind))
+            .or_insert_with(|| TokenBucket::full(budget.per_peer, now));
+        peer.refill(budget.per_peer, now);
+
+        if !global.has_token(budget.global) || !peer.has_token(budget.per_peer) {
+            return false;
+        }
+        global.take(budget.global);
+        peer.take(budget.per_peer);
+        true
+    }
+
+    /// Drop the buckets of the peers that haven't sent requests for long enough to refill them.
+    fn prune_idle_peers(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_pruned) < PRUNE_INTERVAL {
+            return;
+        }
+        self.last_pruned = now;
+        let config = &self.config;
+        self.peers
+            .retain(|(_peer_id, kind), bucket| !bucket.is_full(config.budget(*kind).per_peer, now));
+    }
+}
+
+/// The response telling the peer its request was throttled, if the request has an error response.
+pub(crate) fn throttled_response(
+    request: &Request,
+    our_address: NetworkAddress,
+) -> Option<Response> {
+    let response = match request {
+        Request::Query(Query::GetStoreQuote { .. }) => {
+            Response::Query(QueryResponse::GetStoreQuote {
+                quote: Err(ProtocolError::RequestThrottled),
+                peer_address: our_address,
+                storage_proofs: vec![],
+            })
+        }
+        Request::Query(Query::GetReplicatedRecord { .. }) => Response::Query(
+            QueryResponse::GetReplicatedRecord(Err(ProtocolError::RequestThrottled)),
+        ),
+        Request::Query(Query::GetRegisterRecord { .. }) => Response::Query(
+            QueryResponse::GetRegisterRecord(Err(ProtocolError::RequestThrottled)),
+        ),
+        Request::Query(Query::GetChunkExistenceProof { key, .. }) => {
+            Response::Query(QueryResponse::GetChunkExistenceProof(vec![(
+                key.clone(),
+                Err(ProtocolError::RequestThrottled),
+            )]))
+        }
+        Request::Query(Query::GetStorageReceipt { .. }) => Response::Query(
+            QueryResponse::GetStorageReceipt(Err(ProtocolError::RequestThrottled)),
+        ),
+        Request::Query(Query::GetPaymentRejection { .. }) => Response::Query(
+            QueryResponse::GetPaymentRejection(Err(ProtocolError::RequestThrottled)),
+        ),
+        Request::Cmd(Cmd::Replicate { .. }) => {
+            Response::Cmd(CmdResponse::Replicate(Err(ProtocolError::RequestThrottled)))
+        }
+        Request::Query(Query::CheckNodeInProblem(_))
+        | Request::Query(Query::GetClosestPeers { .. })
+        | Request::Cmd(Cmd::PeerConsideredAsBad { .. }) => return None,
+    };
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_over_the_per_peer_budget_are_throttled() {
+        let mut config = RateLimitConfig::default();
+        config.set_budget(
+            RequestKind::Get,
+            RequestBudget {
+                per_peer: 2,
+                global: 0,
+            },
+        );
+        let mut limiter = RequestRateLimiter::new(config);
+        let (peer, other_peer) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+
+        assert!(limiter.allow_at(peer, RequestKind::Get, now));
+        assert!(limiter.allow_at(peer, RequestKind::Get, now));
+        assert!(!limiter.allow_at(peer, RequestKind::Get, now));
+        // Other peers and kinds have their own budgets
+        assert!(limiter.allow_at(other_peer, RequestKind::Get, now));
+        assert!(limiter.allow_at(peer, RequestKind::Put, now));
+
+        // Refilled over time
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.allow_at(peer, RequestKind::Get, later));
+        assert!(!limiter.allow_at(peer, RequestKind::Get, later));
+    }
+
+    #[test]
+    fn requests_over_the_global_budget_are_throttled() {
+        let mut config = RateLimitConfig::default();
+        config.set_budget(
+            RequestKind::Replication,
+            RequestBudget {
+                per_peer: 10,
+                global: 3,
+            },
+        );
+        let mut limiter = RequestRateLimiter::new(config);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.allow_at(PeerId::random(), RequestKind::Replication, now));
+        }
+        assert!(!limiter.allow_at(PeerId::random(), RequestKind::Replication, now));
+
+        // Idle peers are dropped once their budget is refilled
+        let later = now + PRUNE_INTERVAL;
+        assert!(limiter.allow_at(PeerId::random(), RequestKind::Replication, later));
+        assert_eq!(limiter.peers.len(), 1);
+    }
+}
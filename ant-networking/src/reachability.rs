@@ -0,0 +1,144 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::driver::SwarmDriver;
+#[cfg(feature = "upnp")]
+use crate::multiaddr_is_global;
+use libp2p::{
+    autonat::{self, NatStatus},
+    PeerId,
+};
+#[cfg(feature = "open-metrics")]
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use std::{fmt, time::Duration};
+
+/// Interval between two probes once the reachability is confirmed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The number of probes confirming the node is not reachable, on top of the first one, before
+/// switching to the home network mode.
+const MIN_CONFIDENCE_TO_SWITCH: usize = 2;
+
+/// Whether the node is reachable from the public internet, as detected by AutoNAT.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "open-metrics", derive(EncodeLabelValue))]
+pub enum Reachability {
+    /// Not enough peers probed our addresses yet
+    #[default]
+    Unknown,
+    /// Peers can dial us directly
+    Public,
+    /// Peers can't dial us, we are behind a NAT or a firewall
+    Private,
+}
+
+impl Reachability {
+    /// All the statuses, e.g. to reset the metrics of the previous one.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) const ALL: [Self; 3] = [Self::Unknown, Self::Public, Self::Private];
+}
+
+impl From<&NatStatus> for Reachability {
+    fn from(status: &NatStatus) -> Self {
+        match status {
+            NatStatus::Public(_) => Self::Public,
+            NatStatus::Private => Self::Private,
+            NatStatus::Unknown => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Reachability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            Self::Unknown => "unknown",
+            Self::Public => "public",
+            Self::Private => "private",
+        };
+        write!(f, "{status}")
+    }
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct ReachabilityLabels {
+    pub(crate) status: Reachability,
+}
+
+/// The AutoNAT behaviour, probing our addresses through the connected nodes and serving their probes.
+pub(crate) fn autonat_behaviour(peer_id: PeerId) -> autonat::Behaviour {
+    let cfg = autonat::Config {
+        refresh_interval: REFRESH_INTERVAL,
+        ..Default::default()
+    };
+    autonat::Behaviour::new(peer_id, cfg)
+}
+
+impl SwarmDriver {
+    pub(crate) fn handle_autonat_event(&mut self, event: autonat::Event) {
+        match event {
+            autonat::Event::StatusChanged { old, new } => {
+                info!("AutoNAT status changed from {old:?} to {new:?}");
+            }
+            autonat::Event::OutboundProbe(event) => {
+                debug!("AutoNAT outbound probe: {event:?}");
+            }
+            autonat::Event::InboundProbe(event) => {
+                trace!("AutoNAT inbound probe: {event:?}");
+            }
+        }
+
+        let Some(autonat) = self.swarm.behaviour().autonat.as_ref() else {
+            return;
+        };
+        let reachability = Reachability::from(&autonat.nat_status());
+        let confidence = autonat.confidence();
+        if reachability != self.reachability {
+            self.reachability = reachability;
+            #[cfg(feature = "open-metrics")]
+            if let Some(metrics_recorder) = &self.metrics_recorder {
+                metrics_recorder.record_reachability(reachability);
+            }
+        }
+
+        if reachability == Reachability::Private
+            && confidence >= MIN_CONFIDENCE_TO_SWITCH
+            && !self.is_behind_home_network
+        {
+            self.switch_to_home_network_mode();
+        }
+    }
+
+    /// The listen port could not be mapped on the router. Unless the node has a public address of
+    /// its own, or was found to be reachable already, peers can't dial it.
+    #[cfg(feature = "upnp")]
+    pub(crate) fn on_port_mapping_failure(&mut self) {
+        if self.is_behind_home_network || self.reachability == Reachability::Public {
+            return;
+        }
+        if self.swarm.listeners().any(multiaddr_is_global) {
+            debug!("Listening on a public address, no port mapping is required");
+            return;
+        }
+        self.switch_to_home_network_mode();
+    }
+
+    /// Stop advertising our addresses, as peers can't dial them, and get reachable through relays instead.
+    fn switch_to_home_network_mode(&mut self) {
+        warn!("The node is not reachable from the public internet, switching to the home network mode");
+        self.is_behind_home_network = true;
+        self.external_address_manager = None;
+        let external_addrs: Vec<_> = self.swarm.external_addresses().cloned().collect();
+        for addr in external_addrs {
+            self.swarm.remove_external_address(&addr);
+        }
+        if let Some(relay_manager) = self.relay_manager.as_mut() {
+            relay_manager.enable();
+        }
+    }
+}
@@ -7,9 +7,11 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for the Bytes in NetworkAddress
 
+use crate::audit_log::{AuditAction, AuditEntry, AuditLog, RecordSource, AUDIT_LOG_FILENAME};
 use crate::cmd::LocalSwarmCmd;
+use crate::cmd_channel::{queue_cmd, CmdSender};
 use crate::driver::MAX_PACKET_SIZE;
-use crate::send_local_swarm_cmd;
+use crate::record_store_backend::{generate_filename, RecordStoreBackend, RecordStoreBackendKind};
 use crate::target_arch::{spawn, Instant};
 use crate::{event::NetworkEvent, log_markers::Marker};
 use aes_gcm_siv::{
@@ -19,11 +21,10 @@ use aes_gcm_siv::{
 use ant_evm::{QuotingMetrics, U256};
 use ant_protocol::{
     convert_distance_to_u256,
-    storage::{RecordHeader, RecordKind, RecordType},
+    storage::{try_deserialize_record, RecordHeader, RecordKind, RecordType, Scratchpad},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use hkdf::Hkdf;
-use itertools::Itertools;
 use libp2p::{
     identity::PeerId,
     kad::{
@@ -32,20 +33,24 @@ use libp2p::{
     },
 };
 #[cfg(feature = "open-metrics")]
-use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::{
+    encoding::{EncodeLabelSet, EncodeLabelValue},
+    metrics::{counter::Counter, family::Family, gauge::Gauge},
+};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap},
-    fs,
+    fmt, fs,
     path::{Path, PathBuf},
-    time::SystemTime,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
     vec,
 };
 use tokio::sync::mpsc;
-use walkdir::{DirEntry, WalkDir};
 use xor_name::XorName;
 
 // A transaction record is at the size of 4KB roughly.
@@ -62,7 +67,7 @@ const MAX_RECORDS_CACHE_SIZE: usize = 25;
 /// File name of the recorded historical quoting metrics.
 const HISTORICAL_QUOTING_METRICS_FILENAME: &str = "historic_quoting_metrics";
 
-fn derive_aes256gcm_siv_from_seed(seed: &[u8; 16]) -> (Aes256GcmSiv, [u8; 4]) {
+fn derive_aes256gcm_siv_from_seed(seed: &[u8]) -> (Aes256GcmSiv, [u8; 4]) {
     // shall be unique for purpose.
     let salt = b"autonomi_record_store";
 
@@ -139,6 +144,8 @@ pub struct NodeRecordStore {
     config: NodeRecordStoreConfig,
     /// Main records store remains unchanged for compatibility
     records: HashMap<Key, (NetworkAddress, RecordType)>,
+    /// Where the bytes of the records are kept
+    backend: Arc<dyn RecordStoreBackend>,
     /// Additional index organizing records by distance
     records_by_distance: BTreeMap<U256, Key>,
     /// FIFO simple cache of records to reduce read times
@@ -146,7 +153,7 @@ pub struct NodeRecordStore {
     /// Send network events to the node layer.
     network_event_sender: mpsc::Sender<NetworkEvent>,
     /// Send cmds to the network layer. Used to interact with self in an async fashion.
-    local_swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
+    local_swarm_cmd_sender: CmdSender<LocalSwarmCmd>,
     /// ilog2 distance range of responsible records
     /// AKA: how many buckets of data do we consider "close"
     /// None means accept all records.
@@ -154,15 +161,31 @@ pub struct NodeRecordStore {
     #[cfg(feature = "open-metrics")]
     /// Used to report the number of records held by the store to the metrics server.
     record_count_metric: Option<Gauge>,
+    #[cfg(feature = "open-metrics")]
+    /// Used to report the records evicted to stay within the limits of the store.
+    record_eviction_metric: Option<Family<RecordEvictionLabels, Counter>>,
+    /// The category and size of the records held or being written, to enforce the quotas
+    record_sizes: HashMap<Key, (RecordCategory, usize)>,
+    /// Total size of the records per category
+    bytes_by_category: HashMap<RecordCategory, usize>,
+    /// When the transient records expire, in seconds since the UNIX epoch
+    record_expiries: HashMap<Key, u64>,
     /// Counting how many times got paid
     received_payment_count: usize,
-    /// Encyption cipher for the records, randomly generated at node startup
-    /// Plus a 4 byte nonce starter
-    encryption_details: (Aes256GcmSiv, [u8; 4]),
+    /// Encyption cipher for the records, derived from the encryption seed at node startup
+    /// Plus a 4 byte nonce starter. The records are stored unencrypted if not set.
+    encryption_details: Option<(Aes256GcmSiv, [u8; 4])>,
     /// Time that this record_store got started
     timestamp: SystemTime,
     /// Farthest record to self
     farthest_record: Option<(Key, Distance)>,
+    /// Cleared once the node is decommissioning, to stop storing new records
+    accepting_records: bool,
+    /// The log of the records stored and removed, if kept
+    audit_log: Option<AuditLog>,
+    /// Serve the chunks corrupted, as a fault injected on purpose
+    #[cfg(feature = "chaos")]
+    corrupt_chunks: bool,
 }
 
 /// Configuration for a `DiskBackedRecordStore`.
@@ -170,6 +193,8 @@ pub struct NodeRecordStore {
 pub struct NodeRecordStoreConfig {
     /// The directory where the records are stored.
     pub storage_dir: PathBuf,
+    /// The backend keeping the records in the storage dir.
+    pub backend: RecordStoreBackendKind,
     /// The directory where the historic quote to be stored
     /// (normally to be the parent dir of the storage_dir)
     pub historic_quote_dir: PathBuf,
@@ -179,8 +204,17 @@ pub struct NodeRecordStoreConfig {
     pub max_value_bytes: usize,
     /// The maximum number of records to cache in memory.
     pub records_cache_size: usize,
-    /// The seed to generate record_store encryption_details
-    pub encryption_seed: [u8; 16],
+    /// The seed to generate record_store encryption_details, the records are stored unencrypted if not set
+    pub encryption_seed: Option<Vec<u8>>,
+    /// The maximum total size of the records of each category, in bytes. Unlimited if not set.
+    pub max_bytes_per_category: HashMap<RecordCategory, usize>,
+    /// How to make room once the store or the quota of a category is full.
+    pub eviction_policy: EvictionPolicy,
+    /// Keep the records beyond our responsible range rather than cleaning them up, as a volunteer archiver.
+    pub archive_mode: bool,
+    /// Keep an audit log of the records stored and removed in the historic_quote_dir, capped at this size in
+    /// bytes. Not kept if not set.
+    pub audit_log_max_bytes: Option<u64>,
 }
 
 impl Default for NodeRecordStoreConfig {
@@ -188,15 +222,214 @@ impl Default for NodeRecordStoreConfig {
         let historic_quote_dir = std::env::temp_dir();
         Self {
             storage_dir: historic_quote_dir.clone(),
+            backend: RecordStoreBackendKind::default(),
             historic_quote_dir,
             max_records: MAX_RECORDS_COUNT,
             max_value_bytes: MAX_PACKET_SIZE,
             records_cache_size: MAX_RECORDS_CACHE_SIZE,
-            encryption_seed: [0u8; 16],
+            encryption_seed: None,
+            max_bytes_per_category: HashMap::new(),
+            eviction_policy: EvictionPolicy::default(),
+            archive_mode: false,
+            audit_log_max_bytes: None,
+        }
+    }
+}
+
+/// The categories of records a storage quota can be set for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "open-metrics", derive(EncodeLabelValue))]
+pub enum RecordCategory {
+    Chunk,
+    Transaction,
+    Register,
+    Scratchpad,
+    Pointer,
+}
+
+impl From<RecordKind> for RecordCategory {
+    fn from(kind: RecordKind) -> Self {
+        match kind {
+            RecordKind::Chunk | RecordKind::ChunkWithPayment => Self::Chunk,
+            RecordKind::Transaction | RecordKind::TransactionWithPayment => Self::Transaction,
+            RecordKind::Register | RecordKind::RegisterWithPayment => Self::Register,
+            RecordKind::Scratchpad | RecordKind::ScratchpadWithPayment => Self::Scratchpad,
+            RecordKind::Pointer | RecordKind::PointerWithPayment => Self::Pointer,
+        }
+    }
+}
+
+impl RecordCategory {
+    /// The category of the records at the address, if it's the address of a record.
+    pub fn from_address(address: &NetworkAddress) -> Option<Self> {
+        match address {
+            NetworkAddress::ChunkAddress(_) => Some(Self::Chunk),
+            NetworkAddress::TransactionAddress(_) => Some(Self::Transaction),
+            NetworkAddress::RegisterAddress(_) => Some(Self::Register),
+            NetworkAddress::ScratchpadAddress(_) => Some(Self::Scratchpad),
+            NetworkAddress::PointerAddress(_) => Some(Self::Pointer),
+            NetworkAddress::PeerId(_) | NetworkAddress::RecordKey(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for RecordCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Chunk => "chunk",
+            Self::Transaction => "transaction",
+            Self::Register => "register",
+            Self::Scratchpad => "scratchpad",
+            Self::Pointer => "pointer",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for RecordCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chunk" => Ok(Self::Chunk),
+            "transaction" => Ok(Self::Transaction),
+            "register" => Ok(Self::Register),
+            "scratchpad" => Ok(Self::Scratchpad),
+            "pointer" => Ok(Self::Pointer),
+            _ => Err(format!("Unknown record type: {s}")),
+        }
+    }
+}
+
+/// A record held by the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredRecord {
+    pub address: NetworkAddress,
+    pub category: RecordCategory,
+    /// Size of the record on disk, in bytes
+    pub size: usize,
+}
+
+/// How the store makes room for an incoming record once it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Evict the records farthest from us, as long as they are farther than the incoming record.
+    #[default]
+    FarthestFirst,
+    /// Never evict, reject the incoming record instead.
+    RejectNew,
+}
+
+impl FromStr for EvictionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "farthest-first" => Ok(Self::FarthestFirst),
+            "reject-new" => Ok(Self::RejectNew),
+            _ => Err(format!("Unknown eviction policy: {s}")),
+        }
+    }
+}
+
+/// The min length of an operator-supplied record encryption key, in bytes.
+const MIN_RECORD_ENCRYPTION_KEY_LEN: usize = 16;
+
+/// How the store encrypts the record values it keeps on disk.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RecordEncryption {
+    /// Keep the records unencrypted.
+    Disabled,
+    /// Encrypt the records with a key derived from the node's keypair.
+    /// The records are unreadable, hence dropped, once the node starts with a new keypair.
+    NodeKeypair,
+    /// Encrypt the records with a key derived from this operator-supplied key.
+    Key(Vec<u8>),
+}
+
+impl Default for RecordEncryption {
+    fn default() -> Self {
+        if cfg!(feature = "encrypt-records") {
+            Self::NodeKeypair
+        } else {
+            Self::Disabled
+        }
+    }
+}
+
+impl fmt::Debug for RecordEncryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disabled => write!(f, "Disabled"),
+            Self::NodeKeypair => write!(f, "NodeKeypair"),
+            // not leaking the key into the logs
+            Self::Key(_) => write!(f, "Key(..)"),
         }
     }
 }
 
+impl RecordEncryption {
+    /// An operator-supplied key, hex encoded, as read from a key file.
+    pub fn from_hex_key(hex_key: &str) -> std::result::Result<Self, String> {
+        let key = hex::decode(hex_key.trim())
+            .map_err(|err| format!("Invalid hex record encryption key: {err}"))?;
+        if key.len() < MIN_RECORD_ENCRYPTION_KEY_LEN {
+            return Err(format!(
+                "The record encryption key must be at least {MIN_RECORD_ENCRYPTION_KEY_LEN} bytes long"
+            ));
+        }
+        Ok(Self::Key(key))
+    }
+}
+
+impl FromStr for RecordEncryption {
+    type Err = String;
+
+    /// Parse `disabled` or `node-keypair`. The operator-supplied keys are read from a file with
+    /// [`RecordEncryption::from_hex_key`], not to be exposed on the command line.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disabled" => Ok(Self::Disabled),
+            "node-keypair" => Ok(Self::NodeKeypair),
+            _ => Err(format!("Unknown record encryption: {s}")),
+        }
+    }
+}
+
+/// Which limit of the store a record was evicted for.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "open-metrics", derive(EncodeLabelValue))]
+pub(crate) enum EvictionReason {
+    MaxRecords,
+    Quota,
+    Expired,
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct RecordEvictionLabels {
+    record_type: RecordCategory,
+    reason: EvictionReason,
+}
+
+/// The current time, in seconds since the UNIX epoch.
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The expiry the owner signed into a transient record, i.e. a scratchpad, if any.
+fn signed_expiry(kind: RecordKind, record: &Record) -> Option<u64> {
+    match kind {
+        RecordKind::Scratchpad => try_deserialize_record::<Scratchpad>(record)
+            .ok()?
+            .expires_at(),
+        _ => None,
+    }
+}
+
 /// Generate an encryption nonce for a given record key and nonce_starter bytes.
 fn generate_nonce_for_record(nonce_starter: &[u8; 4], key: &Key) -> Nonce {
     let mut nonce_bytes = nonce_starter.to_vec();
@@ -214,100 +447,100 @@ struct HistoricQuotingMetrics {
 }
 
 impl NodeRecordStore {
-    /// If a directory for our node already exists, repopulate the records from the files in the dir
+    /// If the backend already holds records for our node, repopulate them,
+    /// along with their category, size and expiry.
+    #[allow(clippy::type_complexity)]
     fn update_records_from_an_existing_store(
-        config: &NodeRecordStoreConfig,
-        encryption_details: &(Aes256GcmSiv, [u8; 4]),
-    ) -> HashMap<Key, (NetworkAddress, RecordType)> {
-        let process_entry = |entry: &DirEntry| -> _ {
-            let path = entry.path();
-            if path.is_file() {
-                debug!("Existing record found: {path:?}");
-                // if we've got a file, lets try and read it
-                let filename = match path.file_name().and_then(|n| n.to_str()) {
-                    Some(file_name) => file_name,
-                    None => {
-                        // warn and remove this file as it's not a valid record
-                        warn!(
-                            "Found a file in the storage dir that is not a valid record: {:?}",
-                            path
-                        );
-                        if let Err(e) = fs::remove_file(path) {
+        backend: &dyn RecordStoreBackend,
+        encryption_details: &Option<(Aes256GcmSiv, [u8; 4])>,
+    ) -> (
+        HashMap<Key, (NetworkAddress, RecordType)>,
+        HashMap<Key, (RecordCategory, usize)>,
+        HashMap<Key, u64>,
+    ) {
+        let process_entry = |key: &Key| -> _ {
+            let pretty_key = PrettyPrintRecordKey::from(key);
+            let record = match backend.read(key) {
+                Ok(bytes) => {
+                    // and the stored record
+                    if let Some(record) =
+                        Self::get_record_from_bytes(bytes, key, encryption_details)
+                    {
+                        record
+                    } else {
+                        // This will be due to node restart, result in different encrypt_detail.
+                        // Hence need to clean up the old copy.
+                        info!("Failed to decrypt record {pretty_key:?}, clean it up.");
+                        if let Err(e) = backend.remove(key) {
                             warn!(
-                                "Failed to remove invalid record file from storage dir: {:?}",
+                                "Failed to remove outdated record {pretty_key:?} from storage: {:?}",
                                 e
                             );
                         }
                         return None;
                     }
-                };
-                // get the record key from the filename
-                let key = Self::get_data_from_filename(filename)?;
-                let record = match fs::read(path) {
-                    Ok(bytes) => {
-                        // and the stored record
-                        if let Some(record) =
-                            Self::get_record_from_bytes(bytes, &key, encryption_details)
-                        {
-                            record
-                        } else {
-                            // This will be due to node restart, result in different encrypt_detail.
-                            // Hence need to clean up the old copy.
-                            info!("Failed to decrypt record from file {filename:?}, clean it up.");
-                            if let Err(e) = fs::remove_file(path) {
-                                warn!(
-                                    "Failed to remove outdated record file {filename:?} from storage dir: {:?}",
-                                    e
-                                );
-                            }
-                            return None;
-                        }
-                    }
-                    Err(err) => {
-                        error!("Error while reading file. filename: {filename}, error: {err:?}");
-                        return None;
-                    }
-                };
+                }
+                Err(err) => {
+                    error!("Error while reading record {pretty_key:?}, error: {err:?}");
+                    return None;
+                }
+            };
 
-                let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
-                    Ok(true) => RecordType::Chunk,
-                    Ok(false) => {
-                        let xorname_hash = XorName::from_content(&record.value);
-                        RecordType::NonChunk(xorname_hash)
-                    }
-                    Err(error) => {
+            let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
+                Ok(true) => RecordType::Chunk,
+                Ok(false) => {
+                    let xorname_hash = XorName::from_content(&record.value);
+                    RecordType::NonChunk(xorname_hash)
+                }
+                Err(error) => {
+                    warn!(
+                        "Failed to parse record type of record {pretty_key:?}: {:?}",
+                        error
+                    );
+                    // In correct decryption using different key could result in this.
+                    // In that case, a cleanup shall be carried out.
+                    if let Err(e) = backend.remove(key) {
                         warn!(
-                            "Failed to parse record type of record {filename:?}: {:?}",
-                            error
+                            "Failed to remove invalid record {pretty_key:?} from storage: {:?}",
+                            e
                         );
-                        // In correct decryption using different key could result in this.
-                        // In that case, a cleanup shall be carried out.
-                        if let Err(e) = fs::remove_file(path) {
-                            warn!(
-                                "Failed to remove invalid record file {filename:?} from storage dir: {:?}",
-                                e
-                            );
-                        }
-                        return None;
                     }
-                };
+                    return None;
+                }
+            };
 
-                let address = NetworkAddress::from_record_key(&key);
-                info!("Existing record loaded: {path:?}");
-                return Some((key, (address, record_type)));
-            }
-            None
+            let header = RecordHeader::from_record(&record).ok()?;
+            let category = RecordCategory::from(header.kind);
+            let size = record.value.len();
+
+            let address = NetworkAddress::from_record_key(key);
+            info!("Existing record loaded: {pretty_key:?}");
+            Some((
+                key.clone(),
+                (address, record_type),
+                (category, size),
+                signed_expiry(header.kind, &record),
+            ))
         };
 
         info!("Attempting to repopulate records from existing store...");
-        let records = WalkDir::new(&config.storage_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .collect_vec()
+        let entries: Vec<_> = backend
+            .keys()
             .par_iter()
             .filter_map(process_entry)
             .collect();
-        records
+
+        let mut records = HashMap::new();
+        let mut record_sizes = HashMap::new();
+        let mut record_expiries = HashMap::new();
+        for (key, record, size, expires_at) in entries {
+            if let Some(expires_at) = expires_at {
+                let _ = record_expiries.insert(key.clone(), expires_at);
+            }
+            let _ = records.insert(key.clone(), record);
+            let _ = record_sizes.insert(key, size);
+        }
+        (records, record_sizes, record_expiries)
     }
 
     /// If quote_metrics file already exists, using the existing parameters.
@@ -342,15 +575,23 @@ impl NodeRecordStore {
         });
     }
 
-    /// Creates a new `DiskBackedStore` with the given configuration.
+    /// Creates a new `DiskBackedStore` with the given configuration, failing if its backend can't be opened.
     pub fn with_config(
         local_id: PeerId,
         config: NodeRecordStoreConfig,
         network_event_sender: mpsc::Sender<NetworkEvent>,
-        swarm_cmd_sender: mpsc::Sender<LocalSwarmCmd>,
-    ) -> Self {
-        info!("Using encryption_seed of {:?}", config.encryption_seed);
-        let encryption_details = derive_aes256gcm_siv_from_seed(&config.encryption_seed);
+        swarm_cmd_sender: CmdSender<LocalSwarmCmd>,
+    ) -> std::io::Result<Self> {
+        let encryption_details = match &config.encryption_seed {
+            Some(seed) => {
+                info!("Encrypting the records at rest");
+                Some(derive_aes256gcm_siv_from_seed(seed))
+            }
+            None => {
+                info!("Storing the records unencrypted");
+                None
+            }
+        };
 
         // Recover the quoting_metrics first, as the historical file will be cleaned by
         // the later on update_records_from_an_existing_store function
@@ -365,7 +606,21 @@ impl NodeRecordStore {
             (0, SystemTime::now())
         };
 
-        let records = Self::update_records_from_an_existing_store(&config, &encryption_details);
+        let backend = config
+            .backend
+            .open(&config.storage_dir)
+            .inspect_err(|err| {
+                error!(
+                    "Failed to open the {:?} record store backend in {:?}: {err:?}",
+                    config.backend, config.storage_dir
+                );
+            })?;
+        let (records, record_sizes, record_expiries) =
+            Self::update_records_from_an_existing_store(backend.as_ref(), &encryption_details);
+        let mut bytes_by_category: HashMap<RecordCategory, usize> = HashMap::new();
+        for (category, size) in record_sizes.values() {
+            *bytes_by_category.entry(*category).or_default() += size;
+        }
         let local_address = NetworkAddress::from_peer(local_id);
 
         // Initialize records_by_distance
@@ -375,11 +630,20 @@ impl NodeRecordStore {
             let _ = records_by_distance.insert(distance, key.clone());
         }
 
+        let audit_log = config.audit_log_max_bytes.and_then(|max_bytes| {
+            let path = config.historic_quote_dir.join(AUDIT_LOG_FILENAME);
+            AuditLog::open(path.clone(), max_bytes)
+                .inspect(|_| info!("Keeping the audit log of the records at {path:?}"))
+                .inspect_err(|err| error!("Failed to open the audit log at {path:?}: {err:?}"))
+                .ok()
+        });
+
         let cache_size = config.records_cache_size;
         let mut record_store = NodeRecordStore {
             local_address,
             config,
             records,
+            backend,
             records_by_distance,
             records_cache: RecordCache::new(cache_size),
             network_event_sender,
@@ -387,17 +651,30 @@ impl NodeRecordStore {
             responsible_distance_range: None,
             #[cfg(feature = "open-metrics")]
             record_count_metric: None,
+            #[cfg(feature = "open-metrics")]
+            record_eviction_metric: None,
+            record_sizes,
+            bytes_by_category,
+            record_expiries,
             received_payment_count,
             encryption_details,
             timestamp,
             farthest_record: None,
+            accepting_records: true,
+            audit_log,
+            #[cfg(feature = "chaos")]
+            corrupt_chunks: false,
         };
 
         record_store.farthest_record = record_store.calculate_farthest();
 
+        // The quotas could have been lowered since the records were stored
+        record_store.evict_records_over_quota();
+        record_store.prune_expired_records();
+
         record_store.flush_historic_quoting_metrics();
 
-        record_store
+        Ok(record_store)
     }
 
     /// Set the record_count_metric to report the number of records stored to the metrics server
@@ -407,32 +684,26 @@ impl NodeRecordStore {
         self
     }
 
+    /// Set the record_eviction_metric to report the records evicted to stay within the limits of the store
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn set_record_eviction_metric(
+        mut self,
+        metric: Family<RecordEvictionLabels, Counter>,
+    ) -> Self {
+        self.record_eviction_metric = Some(metric);
+        self
+    }
+
     /// Returns the current distance ilog2 (aka bucket) range of CLOSE_GROUP nodes.
     pub fn get_responsible_distance_range(&self) -> Option<U256> {
         self.responsible_distance_range
     }
 
-    // Converts a Key into a Hex string.
-    fn generate_filename(key: &Key) -> String {
-        hex::encode(key.as_ref())
-    }
-
-    // Converts a Hex string back into a Key.
-    fn get_data_from_filename(hex_str: &str) -> Option<Key> {
-        match hex::decode(hex_str) {
-            Ok(bytes) => Some(Key::from(bytes)),
-            Err(error) => {
-                error!("Error decoding hex string: {:?}", error);
-                None
-            }
-        }
-    }
-
     /// Upon read perform any data transformations required to return a `Record`.
     fn get_record_from_bytes<'a>(
         bytes: Vec<u8>,
         key: &Key,
-        encryption_details: &(Aes256GcmSiv, [u8; 4]),
+        encryption_details: &Option<(Aes256GcmSiv, [u8; 4])>,
     ) -> Option<Cow<'a, Record>> {
         let mut record = Record {
             key: key.clone(),
@@ -442,11 +713,9 @@ impl NodeRecordStore {
         };
 
         // if we're not encrypting, lets just return the record
-        if !cfg!(feature = "encrypt-records") {
+        let Some((cipher, nonce_starter)) = encryption_details else {
             return Some(Cow::Owned(record));
-        }
-
-        let (cipher, nonce_starter) = encryption_details;
+        };
         let nonce = generate_nonce_for_record(nonce_starter, key);
 
         match cipher.decrypt(&nonce, record.value.as_ref()) {
@@ -462,17 +731,15 @@ impl NodeRecordStore {
     }
 
     fn read_from_disk<'a>(
-        encryption_details: &(Aes256GcmSiv, [u8; 4]),
+        encryption_details: &Option<(Aes256GcmSiv, [u8; 4])>,
         key: &Key,
-        storage_dir: &Path,
+        backend: &dyn RecordStoreBackend,
     ) -> Option<Cow<'a, Record>> {
         let start = Instant::now();
-        let filename = Self::generate_filename(key);
-
-        let file_path = storage_dir.join(&filename);
+        let filename = generate_filename(key);
 
         // we should only be reading if we know the record is written to disk properly
-        match fs::read(file_path) {
+        match backend.read(key) {
             Ok(bytes) => {
                 // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                 info!(
@@ -527,6 +794,10 @@ impl NodeRecordStore {
             return Ok(());
         }
 
+        if self.config.eviction_policy == EvictionPolicy::RejectNew {
+            return Err(Error::MaxRecords);
+        }
+
         if let Some((farthest_record, farthest_record_distance)) = self.farthest_record.clone() {
             // if the incoming record is farther than the farthest record, we can't store it
             if farthest_record_distance
@@ -541,12 +812,292 @@ impl NodeRecordStore {
                 "Record {:?} will be pruned to free up space for new records",
                 PrettyPrintRecordKey::from(&farthest_record)
             );
-            self.remove(&farthest_record);
+            self.evict(&farthest_record, EvictionReason::MaxRecords);
         }
 
         Ok(())
     }
 
+    /// Evict records of the category to make room for the incoming record within the quota of the category.
+    ///
+    /// Err MaxRecords if the quota is full of records closer to us than the incoming one,
+    /// or if the eviction policy does not allow evicting records.
+    fn make_room_within_quota(
+        &mut self,
+        incoming_record_key: &Key,
+        category: RecordCategory,
+        size: usize,
+    ) -> Result<()> {
+        let Some(quota) = self.config.max_bytes_per_category.get(&category).copied() else {
+            return Ok(());
+        };
+
+        // A record replacing an existing copy only needs room for the difference
+        let replaced_size = match self.record_sizes.get(incoming_record_key) {
+            Some((existing_category, existing_size)) if *existing_category == category => {
+                *existing_size
+            }
+            _ => 0,
+        };
+        let incoming_distance = convert_distance_to_u256(
+            &self
+                .local_address
+                .distance(&NetworkAddress::from_record_key(incoming_record_key)),
+        );
+
+        loop {
+            let used = self
+                .bytes_by_category
+                .get(&category)
+                .copied()
+                .unwrap_or_default()
+                .saturating_sub(replaced_size);
+            if used.saturating_add(size) <= quota {
+                return Ok(());
+            }
+
+            if self.config.eviction_policy == EvictionPolicy::RejectNew {
+                warn!(
+                    "Quota of {quota} bytes for {category} records is full, rejecting {:?}",
+                    PrettyPrintRecordKey::from(incoming_record_key)
+                );
+                return Err(Error::MaxRecords);
+            }
+
+            match self.farthest_record_of_category(category, Some(incoming_record_key)) {
+                Some((distance, key)) if distance > incoming_distance => {
+                    info!(
+                        "Record {:?} will be evicted to stay within the quota of {category} records",
+                        PrettyPrintRecordKey::from(&key)
+                    );
+                    self.evict(&key, EvictionReason::Quota);
+                }
+                _ => {
+                    warn!(
+                        "Quota of {quota} bytes for {category} records is full of closer records, rejecting {:?}",
+                        PrettyPrintRecordKey::from(incoming_record_key)
+                    );
+                    return Err(Error::MaxRecords);
+                }
+            }
+        }
+    }
+
+    /// Whether a record of the category at the key would be rejected for its quota being full, not to be quoted
+    /// and paid for. Its size isn't known when quoting, so the quota is taken as full once it can't take a record of
+    /// the max size without evicting records closer to us.
+    pub(crate) fn is_quota_full_for(&self, key: &Key, category: RecordCategory) -> bool {
+        let Some(quota) = self.config.max_bytes_per_category.get(&category).copied() else {
+            return false;
+        };
+        let used = self
+            .bytes_by_category
+            .get(&category)
+            .copied()
+            .unwrap_or_default();
+        if used.saturating_add(self.config.max_value_bytes.min(quota)) <= quota {
+            return false;
+        }
+        if self.config.eviction_policy == EvictionPolicy::RejectNew {
+            return true;
+        }
+
+        let incoming_distance = convert_distance_to_u256(
+            &self
+                .local_address
+                .distance(&NetworkAddress::from_record_key(key)),
+        );
+        !matches!(
+            self.farthest_record_of_category(category, Some(key)),
+            Some((distance, _key)) if distance > incoming_distance
+        )
+    }
+
+    /// Evict the farthest records of every category above its quota.
+    fn evict_records_over_quota(&mut self) {
+        if self.config.eviction_policy == EvictionPolicy::RejectNew {
+            return;
+        }
+
+        for (category, quota) in self.config.max_bytes_per_category.clone() {
+            while self
+                .bytes_by_category
+                .get(&category)
+                .is_some_and(|used| *used > quota)
+            {
+                let Some((_distance, key)) = self.farthest_record_of_category(category, None)
+                else {
+                    break;
+                };
+                info!(
+                    "Record {:?} will be evicted as the quota of {category} records is exceeded",
+                    PrettyPrintRecordKey::from(&key)
+                );
+                self.evict(&key, EvictionReason::Quota);
+            }
+        }
+    }
+
+    /// The stored record of the category farthest from us, other than the excluded one.
+    fn farthest_record_of_category(
+        &self,
+        category: RecordCategory,
+        excluded: Option<&Key>,
+    ) -> Option<(U256, Key)> {
+        self.records_by_distance
+            .iter()
+            .rev()
+            .find(|(_distance, key)| {
+                Some(*key) != excluded
+                    && self
+                        .record_sizes
+                        .get(*key)
+                        .is_some_and(|(record_category, _)| *record_category == category)
+            })
+            .map(|(distance, key)| (*distance, key.clone()))
+    }
+
+    /// Remove a record to stay within the limits of the store.
+    fn evict(&mut self, key: &Key, reason: EvictionReason) {
+        debug!(
+            "Evicting record {:?} for {reason:?}",
+            PrettyPrintRecordKey::from(key)
+        );
+        #[cfg(feature = "open-metrics")]
+        if let (Some(metric), Some((category, _))) =
+            (&self.record_eviction_metric, self.record_sizes.get(key))
+        {
+            let _ = metric
+                .get_or_create(&RecordEvictionLabels {
+                    record_type: *category,
+                    reason,
+                })
+                .inc();
+        }
+        let (action, reason) = match reason {
+            EvictionReason::MaxRecords => (AuditAction::Evict, "max_records"),
+            EvictionReason::Quota => (AuditAction::Evict, "quota"),
+            EvictionReason::Expired => (AuditAction::Prune, "expired"),
+        };
+        self.audit_removal(key, action, Some(reason));
+        self.remove_record(key);
+    }
+
+    /// Remove a record held enough by its close group, which we're out of.
+    pub(crate) fn prune_replicated(&mut self, key: &Key) {
+        self.audit_removal(key, AuditAction::Prune, Some("replicated"));
+        self.remove_record(key);
+    }
+
+    /// Log the record having been written to the store, once its write was accepted.
+    pub(crate) fn audit_write(&mut self, key: &Key, source: RecordSource) {
+        let category = self.record_sizes.get(key).map(|(category, _)| *category);
+        if let Some(audit_log) = &mut self.audit_log {
+            audit_log.append(AuditEntry::written(key, category, source));
+        }
+    }
+
+    /// Log the record being removed from the store, if held.
+    fn audit_removal(&mut self, key: &Key, action: AuditAction, reason: Option<&str>) {
+        let Some(audit_log) = &mut self.audit_log else {
+            return;
+        };
+        if !self.records.contains_key(key) {
+            return;
+        }
+        let category = self.record_sizes.get(key).map(|(category, _)| *category);
+        let entry = AuditEntry::new(action, key, category);
+        audit_log.append(match reason {
+            Some(reason) => entry.with_reason(reason),
+            None => entry,
+        });
+    }
+
+    /// Remove the record from the store and the disk, without logging it.
+    fn remove_record(&mut self, k: &Key) {
+        // Remove from main store
+        if let Some((addr, _)) = self.records.remove(k) {
+            let distance = convert_distance_to_u256(&self.local_address.distance(&addr));
+            let _ = self.records_by_distance.remove(&distance);
+        }
+
+        self.records_cache.remove(k);
+        self.untrack_record_size(k);
+        let _ = self.record_expiries.remove(k);
+
+        #[cfg(feature = "open-metrics")]
+        if let Some(metric) = &self.record_count_metric {
+            let _ = metric.set(self.records.len() as i64);
+        }
+
+        if let Some((farthest_record, _)) = self.farthest_record.clone() {
+            if farthest_record == *k {
+                self.farthest_record = self.calculate_farthest();
+            }
+        }
+
+        let filename = generate_filename(k);
+        let backend = Arc::clone(&self.backend);
+        let key = k.clone();
+
+        let _handle = spawn(async move {
+            match backend.remove(&key) {
+                Ok(_) => {
+                    info!("Removed record from disk! filename: {filename}");
+                }
+                Err(err) => {
+                    error!("Error while removing file. filename: {filename}, error: {err:?}");
+                }
+            }
+        });
+    }
+
+    /// Track the size of a record being stored, replacing any previous copy.
+    fn track_record_size(&mut self, key: &Key, category: RecordCategory, size: usize) {
+        self.untrack_record_size(key);
+        let _ = self.record_sizes.insert(key.clone(), (category, size));
+        *self.bytes_by_category.entry(category).or_default() += size;
+    }
+
+    fn untrack_record_size(&mut self, key: &Key) {
+        if let Some((category, size)) = self.record_sizes.remove(key) {
+            if let Some(used) = self.bytes_by_category.get_mut(&category) {
+                *used = used.saturating_sub(size);
+            }
+        }
+    }
+
+    /// Whether the record is a transient one past its expiry.
+    fn is_expired(&self, key: &Key) -> bool {
+        self.record_expiries
+            .get(key)
+            .is_some_and(|expires_at| *expires_at <= now_secs())
+    }
+
+    /// Remove the transient records past their expiry.
+    pub(crate) fn prune_expired_records(&mut self) {
+        let now = now_secs();
+        let expired: Vec<Key> = self
+            .record_expiries
+            .iter()
+            .filter(|(_key, expires_at)| **expires_at <= now)
+            .map(|(key, _expires_at)| key.clone())
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+
+        info!("Pruning {} expired records", expired.len());
+        for key in expired {
+            self.evict(&key, EvictionReason::Expired);
+        }
+    }
+
+    /// Total size of the records of each category held by the store, in bytes.
+    pub fn bytes_by_category(&self) -> &HashMap<RecordCategory, usize> {
+        &self.bytes_by_category
+    }
+
     // When the accumulated record copies exceeds the `expotional pricing point` (max_records * 0.1)
     // those `out of range` records shall be cleaned up.
     // This is to avoid :
@@ -554,6 +1105,11 @@ impl NodeRecordStore {
     //   * `over-quoting` during restart, when RT is not fully populated,
     //     result in mis-calculation of relevant records.
     pub fn cleanup_irrelevant_records(&mut self) {
+        self.prune_expired_records();
+        if self.config.archive_mode {
+            return;
+        }
+
         let accumulated_records = self.records.len();
         if accumulated_records < MAX_RECORDS_COUNT / 10 {
             return;
@@ -576,7 +1132,8 @@ impl NodeRecordStore {
 
         // Remove collected keys
         for key in keys_to_remove {
-            self.remove(&key);
+            self.audit_removal(&key, AuditAction::Prune, Some("out_of_range"));
+            self.remove_record(&key);
         }
 
         info!("Cleaned up {} unrelevant records, among the original {accumulated_records} accumulated_records",
@@ -590,11 +1147,13 @@ impl NodeRecordStore {
         self.records.contains_key(key)
     }
 
-    /// Returns the set of `NetworkAddress::RecordKey` held by the store
+    /// Returns the set of `NetworkAddress::RecordKey` held by the store, leaving out the expired
+    /// records not to replicate them.
     /// Use `record_addresses_ref` to get a borrowed type
     pub(crate) fn record_addresses(&self) -> HashMap<NetworkAddress, RecordType> {
         self.records
             .iter()
+            .filter(|(record_key, _)| !self.is_expired(record_key))
             .map(|(_record_key, (addr, record_type))| (addr.clone(), record_type.clone()))
             .collect()
     }
@@ -604,6 +1163,39 @@ impl NodeRecordStore {
         &self.records
     }
 
+    /// Returns the records held by the store beyond our responsible range, the farthest first, none in archive mode
+    pub(crate) fn irrelevant_record_addresses(&self) -> Vec<(NetworkAddress, RecordType)> {
+        if self.config.archive_mode {
+            return vec![];
+        }
+        let Some(responsible_distance) = self.responsible_distance_range else {
+            return vec![];
+        };
+        self.records_by_distance
+            .range(responsible_distance..)
+            .rev()
+            .filter_map(|(_distance, key)| self.records.get(key).cloned())
+            .collect()
+    }
+
+    /// Returns the records held by the store, sorted by their distance to us
+    pub(crate) fn stored_records(&self) -> Vec<StoredRecord> {
+        let mut stored_records: Vec<_> = self
+            .records
+            .iter()
+            .filter_map(|(key, (address, _record_type))| {
+                let (category, size) = self.record_sizes.get(key)?;
+                Some(StoredRecord {
+                    address: address.clone(),
+                    category: *category,
+                    size: *size,
+                })
+            })
+            .collect();
+        stored_records.sort_by_key(|record| self.local_address.distance(&record.address));
+        stored_records
+    }
+
     /// The follow up to `put_verified`, this only registers the RecordKey
     /// in the RecordStore records set. After this it should be safe
     /// to return the record as stored.
@@ -630,16 +1222,14 @@ impl NodeRecordStore {
     }
 
     /// Prepare record bytes for storage
-    /// If feats are enabled, this will eg, encrypt the record for storage
+    /// If encryption is enabled, this will encrypt the record for storage
     fn prepare_record_bytes(
         record: Record,
-        encryption_details: (Aes256GcmSiv, [u8; 4]),
+        encryption_details: Option<(Aes256GcmSiv, [u8; 4])>,
     ) -> Option<Vec<u8>> {
-        if !cfg!(feature = "encrypt-records") {
+        let Some((cipher, nonce_starter)) = encryption_details else {
             return Some(record.value);
-        }
-
-        let (cipher, nonce_starter) = encryption_details;
+        };
         let nonce = generate_nonce_for_record(&nonce_starter, &record.key);
 
         match cipher.encrypt(&nonce, record.value.as_ref()) {
@@ -678,13 +1268,35 @@ impl NodeRecordStore {
             }
         }
 
+        let header = RecordHeader::from_record(&r).ok();
+        let category = header
+            .as_ref()
+            .map(|header| RecordCategory::from(header.kind));
+        let expires_at = header.and_then(|header| signed_expiry(header.kind, &r));
+        let size = r.value.len();
+        if let Some(category) = category {
+            self.make_room_within_quota(key, category, size)?;
+        }
+
         // Store the new record to the cache
         self.records_cache.push_back(key.clone(), r.clone());
 
         self.prune_records_if_needed(key)?;
 
-        let filename = Self::generate_filename(key);
-        let file_path = self.config.storage_dir.join(&filename);
+        if let Some(category) = category {
+            self.track_record_size(key, category, size);
+        }
+        match expires_at {
+            Some(expires_at) => {
+                let _ = self.record_expiries.insert(key.clone(), expires_at);
+            }
+            None => {
+                let _ = self.record_expiries.remove(key);
+            }
+        }
+
+        let filename = generate_filename(key);
+        let backend = Arc::clone(&self.backend);
 
         #[cfg(feature = "open-metrics")]
         if let Some(metric) = &self.record_count_metric {
@@ -698,7 +1310,7 @@ impl NodeRecordStore {
         spawn(async move {
             let key = r.key.clone();
             if let Some(bytes) = Self::prepare_record_bytes(r, encryption_details) {
-                let cmd = match fs::write(&file_path, bytes) {
+                let cmd = match backend.write(&key, &bytes) {
                     Ok(_) => {
                         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                         info!("Wrote record {record_key2:?} to disk! filename: {filename}");
@@ -709,11 +1321,11 @@ impl NodeRecordStore {
                         error!(
                         "Error writing record {record_key2:?} filename: {filename}, error: {err:?}"
                     );
-                        LocalSwarmCmd::RemoveFailedLocalRecord { key }
+                        LocalSwarmCmd::RemoveLocalRecord { key }
                     }
                 };
 
-                send_local_swarm_cmd(cloned_cmd_sender, cmd);
+                queue_cmd(cloned_cmd_sender, cmd);
             }
         });
 
@@ -770,6 +1382,15 @@ impl NodeRecordStore {
         self.flush_historic_quoting_metrics();
     }
 
+    /// The number of records within our responsible range, or of all our records if it isn't set yet
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn relevant_records_count(&self) -> usize {
+        match self.responsible_distance_range {
+            Some(range) => self.get_records_within_distance_range(range),
+            None => self.records.len(),
+        }
+    }
+
     /// Calculate how many records are stored within a distance range
     pub fn get_records_within_distance_range(&self, range: U256) -> usize {
         let within_range = self
@@ -783,39 +1404,40 @@ impl NodeRecordStore {
         within_range
     }
 
-    /// Setup the distance range.
-    pub(crate) fn set_responsible_distance_range(&mut self, responsible_distance: U256) {
-        self.responsible_distance_range = Some(responsible_distance);
+    #[cfg(feature = "chaos")]
+    pub(crate) fn corrupts_chunks(&self) -> bool {
+        self.corrupt_chunks
     }
-}
 
-impl RecordStore for NodeRecordStore {
-    type RecordsIter<'a> = vec::IntoIter<Cow<'a, Record>>;
-    type ProvidedIter<'a> = vec::IntoIter<Cow<'a, ProviderRecord>>;
-
-    fn get(&self, k: &Key) -> Option<Cow<'_, Record>> {
-        // When a client calls GET, the request is forwarded to the nodes until one node returns
-        // with the record. Thus a node can be bombarded with GET reqs for random keys. These can be safely
-        // ignored if we don't have the record locally.
-        let key = PrettyPrintRecordKey::from(k);
+    #[cfg(feature = "chaos")]
+    pub(crate) fn set_corrupt_chunks(&mut self, corrupt_chunks: bool) {
+        self.corrupt_chunks = corrupt_chunks;
+    }
 
-        let cached_record = self.records_cache.get(k);
-        // first return from FIFO cache if existing there
-        if let Some((record, _timestamp)) = cached_record {
-            return Some(Cow::Borrowed(record));
-        }
+    /// Whether new records are stored, unless the node is decommissioning.
+    /// Whether the store holds at least 90% of the max number of records.
+    pub(crate) fn is_near_full(&self) -> bool {
+        self.records.len() * 10 >= self.config.max_records * 9
+    }
 
-        if !self.records.contains_key(k) {
-            debug!("Record not found locally: {key:?}");
-            return None;
-        }
+    pub(crate) fn is_accepting_records(&self) -> bool {
+        self.accepting_records
+    }
 
-        debug!("GET request for Record key: {key}");
+    /// Stop storing new records, as the node is decommissioning.
+    pub(crate) fn stop_accepting_records(&mut self) {
+        info!("The record store no longer accepts new records");
+        self.accepting_records = false;
+    }
 
-        Self::read_from_disk(&self.encryption_details, k, &self.config.storage_dir)
+    /// Setup the distance range.
+    pub(crate) fn set_responsible_distance_range(&mut self, responsible_distance: U256) {
+        self.responsible_distance_range = Some(responsible_distance);
     }
 
-    fn put(&mut self, record: Record) -> Result<()> {
+    /// Pass a record put to us on for validation, unless already held. `source` is the peer that put it, or
+    /// `None` if put by ourselves.
+    pub(crate) fn put_unverified(&mut self, record: Record, source: Option<PeerId>) -> Result<()> {
         let record_key = PrettyPrintRecordKey::from(&record.key);
 
         if record.value.len() >= self.config.max_value_bytes {
@@ -867,7 +1489,7 @@ impl RecordStore for NodeRecordStore {
         // push the event off thread so as to be non-blocking
         let _handle = spawn(async move {
             if let Err(error) = event_sender
-                .send(NetworkEvent::UnverifiedRecord(record))
+                .send(NetworkEvent::UnverifiedRecord(record, source))
                 .await
             {
                 error!("SwarmDriver failed to send event: {}", error);
@@ -876,40 +1498,46 @@ impl RecordStore for NodeRecordStore {
 
         Ok(())
     }
+}
 
-    fn remove(&mut self, k: &Key) {
-        // Remove from main store
-        if let Some((addr, _)) = self.records.remove(k) {
-            let distance = convert_distance_to_u256(&self.local_address.distance(&addr));
-            let _ = self.records_by_distance.remove(&distance);
-        }
+impl RecordStore for NodeRecordStore {
+    type RecordsIter<'a> = vec::IntoIter<Cow<'a, Record>>;
+    type ProvidedIter<'a> = vec::IntoIter<Cow<'a, ProviderRecord>>;
 
-        self.records_cache.remove(k);
+    fn get(&self, k: &Key) -> Option<Cow<'_, Record>> {
+        // When a client calls GET, the request is forwarded to the nodes until one node returns
+        // with the record. Thus a node can be bombarded with GET reqs for random keys. These can be safely
+        // ignored if we don't have the record locally.
+        let key = PrettyPrintRecordKey::from(k);
 
-        #[cfg(feature = "open-metrics")]
-        if let Some(metric) = &self.record_count_metric {
-            let _ = metric.set(self.records.len() as i64);
+        if self.is_expired(k) {
+            debug!("Record {key:?} has expired");
+            return None;
         }
 
-        if let Some((farthest_record, _)) = self.farthest_record.clone() {
-            if farthest_record == *k {
-                self.farthest_record = self.calculate_farthest();
-            }
+        let cached_record = self.records_cache.get(k);
+        // first return from FIFO cache if existing there
+        if let Some((record, _timestamp)) = cached_record {
+            return Some(Cow::Borrowed(record));
         }
 
-        let filename = Self::generate_filename(k);
-        let file_path = self.config.storage_dir.join(&filename);
+        if !self.records.contains_key(k) {
+            debug!("Record not found locally: {key:?}");
+            return None;
+        }
 
-        let _handle = spawn(async move {
-            match fs::remove_file(file_path) {
-                Ok(_) => {
-                    info!("Removed record from disk! filename: {filename}");
-                }
-                Err(err) => {
-                    error!("Error while removing file. filename: {filename}, error: {err:?}");
-                }
-            }
-        });
+        debug!("GET request for Record key: {key}");
+
+        Self::read_from_disk(&self.encryption_details, k, self.backend.as_ref())
+    }
+
+    fn put(&mut self, record: Record) -> Result<()> {
+        self.put_unverified(record, None)
+    }
+
+    fn remove(&mut self, k: &Key) {
+        self.audit_removal(k, AuditAction::Remove, None);
+        self.remove_record(k);
     }
 
     fn records(&self) -> Self::RecordsIter<'_> {
@@ -956,6 +1584,14 @@ impl ClientRecordStore {
         &self.empty_record_addresses
     }
 
+    pub(crate) fn irrelevant_record_addresses(&self) -> Vec<(NetworkAddress, RecordType)> {
+        vec![]
+    }
+
+    pub(crate) fn stored_records(&self) -> Vec<StoredRecord> {
+        vec![]
+    }
+
     pub(crate) fn put_verified(&mut self, _r: Record, _record_type: RecordType) -> Result<()> {
         Ok(())
     }
@@ -1000,6 +1636,7 @@ impl RecordStore for ClientRecordStore {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{error::NetworkError, record_store_api::UnifiedRecordStore};
     use bls::SecretKey;
     use xor_name::XorName;
 
@@ -1013,6 +1650,7 @@ mod tests {
     };
     use bytes::Bytes;
     use eyre::ContextCompat;
+    use itertools::Itertools;
     use libp2p::{core::multihash::Multihash, kad::RecordKey};
     use quickcheck::*;
     use tokio::runtime::Runtime;
@@ -1076,14 +1714,15 @@ mod tests {
             Default::default(),
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("store to open");
 
         // An initial unverified put should not write to disk
         assert!(store.put(r.clone()).is_ok());
         assert!(store.get(&r.key).is_none());
 
         let returned_record = if let Some(event) = network_event_receiver.recv().await {
-            if let NetworkEvent::UnverifiedRecord(record) = event {
+            if let NetworkEvent::UnverifiedRecord(record, None) = event {
                 record
             } else {
                 panic!("Unexpected network event {event:?}");
@@ -1134,103 +1773,180 @@ mod tests {
 
     #[tokio::test]
     async fn can_store_after_restart() -> eyre::Result<()> {
-        let tmp_dir = TempDir::new()?;
-        let current_test_dir = tmp_dir.child("can_store_after_restart");
-        current_test_dir.create_dir_all()?;
+        for backend in [
+            RecordStoreBackendKind::Disk,
+            RecordStoreBackendKind::SingleFile,
+            RecordStoreBackendKind::Redb,
+        ] {
+            let tmp_dir = TempDir::new()?;
+            let current_test_dir = tmp_dir.child(format!("can_store_after_restart_{backend:?}"));
+            current_test_dir.create_dir_all()?;
+
+            let store_config = NodeRecordStoreConfig {
+                storage_dir: current_test_dir.to_path_buf(),
+                encryption_seed: Some(vec![1u8; 16]),
+                backend,
+                ..Default::default()
+            };
+            let self_id = PeerId::random();
+            let (network_event_sender, _) = mpsc::channel(1);
+            let (swarm_cmd_sender, _) = mpsc::channel(1);
+
+            let mut store = NodeRecordStore::with_config(
+                self_id,
+                store_config.clone(),
+                network_event_sender.clone(),
+                swarm_cmd_sender.clone(),
+            )
+            .expect("store to open");
 
-        let store_config = NodeRecordStoreConfig {
-            storage_dir: current_test_dir.to_path_buf(),
-            encryption_seed: [1u8; 16],
-            ..Default::default()
-        };
-        let self_id = PeerId::random();
-        let (network_event_sender, _) = mpsc::channel(1);
-        let (swarm_cmd_sender, _) = mpsc::channel(1);
+            // Create a chunk
+            let chunk_data = Bytes::from_static(b"Test chunk data");
+            let chunk = Chunk::new(chunk_data);
+            let chunk_address = *chunk.address();
 
-        let mut store = NodeRecordStore::with_config(
-            self_id,
-            store_config.clone(),
-            network_event_sender.clone(),
-            swarm_cmd_sender.clone(),
-        );
+            // Create a record from the chunk
+            let record = Record {
+                key: NetworkAddress::ChunkAddress(chunk_address).to_record_key(),
+                value: try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+                expires: None,
+                publisher: None,
+            };
 
-        // Create a chunk
-        let chunk_data = Bytes::from_static(b"Test chunk data");
-        let chunk = Chunk::new(chunk_data);
-        let chunk_address = *chunk.address();
+            // Store the chunk using put_verified
+            assert!(store
+                .put_verified(record.clone(), RecordType::Chunk)
+                .is_ok());
 
-        // Create a record from the chunk
-        let record = Record {
-            key: NetworkAddress::ChunkAddress(chunk_address).to_record_key(),
-            value: try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
-            expires: None,
-            publisher: None,
-        };
+            // Mark as stored (simulating the CompletedWrite event)
+            store.mark_as_stored(record.key.clone(), RecordType::Chunk);
 
-        // Store the chunk using put_verified
-        assert!(store
-            .put_verified(record.clone(), RecordType::Chunk)
-            .is_ok());
+            // Verify the chunk is stored
+            let stored_record = store.get(&record.key);
+            assert!(stored_record.is_some(), "Chunk should be stored");
 
-        // Mark as stored (simulating the CompletedWrite event)
-        store.mark_as_stored(record.key.clone(), RecordType::Chunk);
+            // Sleep a while to let OS completes the flush to disk
+            sleep(Duration::from_secs(5)).await;
 
-        // Verify the chunk is stored
-        let stored_record = store.get(&record.key);
-        assert!(stored_record.is_some(), "Chunk should be stored");
+            // Restart the store with same encrypt_seed
+            drop(store);
+            let store = NodeRecordStore::with_config(
+                self_id,
+                store_config,
+                network_event_sender.clone(),
+                swarm_cmd_sender.clone(),
+            )
+            .expect("store to open");
+
+            // Sleep a lit bit to let OS completes restoring
+            sleep(Duration::from_secs(1)).await;
+
+            // Verify the record still exists
+            let stored_record = store.get(&record.key);
+            assert!(stored_record.is_some(), "Chunk should be stored");
+
+            // Restart the store with different encrypt_seed
+            drop(store);
+            let self_id_diff = PeerId::random();
+            let store_config_diff = NodeRecordStoreConfig {
+                storage_dir: current_test_dir.to_path_buf(),
+                encryption_seed: Some(vec![2u8; 16]),
+                backend,
+                ..Default::default()
+            };
+            let store_diff = NodeRecordStore::with_config(
+                self_id_diff,
+                store_config_diff,
+                network_event_sender,
+                swarm_cmd_sender,
+            )
+            .expect("store to open");
 
-        // Sleep a while to let OS completes the flush to disk
-        sleep(Duration::from_secs(5)).await;
+            // Sleep a lit bit to let OS completes restoring (if has)
+            sleep(Duration::from_secs(1)).await;
 
-        // Restart the store with same encrypt_seed
-        drop(store);
-        let store = NodeRecordStore::with_config(
-            self_id,
-            store_config,
-            network_event_sender.clone(),
-            swarm_cmd_sender.clone(),
-        );
+            // Verify the record got removed, as it can't be decrypted with the different seed
+            assert!(
+                store_diff.get(&record.key).is_none(),
+                "Chunk should be gone"
+            );
+        }
 
-        // Sleep a lit bit to let OS completes restoring
-        sleep(Duration::from_secs(1)).await;
+        Ok(())
+    }
 
-        // Verify the record still exists
-        let stored_record = store.get(&record.key);
-        assert!(stored_record.is_some(), "Chunk should be stored");
+    #[tokio::test]
+    async fn records_are_encrypted_on_disk_only_with_a_seed() -> eyre::Result<()> {
+        for encryption_seed in [None, Some(vec![3u8; 32])] {
+            let tmp_dir = TempDir::new()?;
+            let store_config = NodeRecordStoreConfig {
+                storage_dir: tmp_dir.to_path_buf(),
+                encryption_seed: encryption_seed.clone(),
+                ..Default::default()
+            };
+            let (network_event_sender, _) = mpsc::channel(1);
+            let (swarm_cmd_sender, _) = mpsc::channel(1);
+            let mut store = NodeRecordStore::with_config(
+                PeerId::random(),
+                store_config,
+                network_event_sender,
+                swarm_cmd_sender,
+            )
+            .expect("store to open");
 
-        // Restart the store with different encrypt_seed
-        let self_id_diff = PeerId::random();
-        let store_config_diff = NodeRecordStoreConfig {
-            storage_dir: current_test_dir.to_path_buf(),
-            encryption_seed: [2u8; 16],
-            ..Default::default()
-        };
-        let store_diff = NodeRecordStore::with_config(
-            self_id_diff,
-            store_config_diff,
-            network_event_sender,
-            swarm_cmd_sender,
-        );
+            let chunk = Chunk::new(Bytes::from_static(b"Test chunk data"));
+            let record = Record {
+                key: NetworkAddress::ChunkAddress(*chunk.address()).to_record_key(),
+                value: try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+                expires: None,
+                publisher: None,
+            };
+            store.put_verified(record.clone(), RecordType::Chunk)?;
 
-        // Sleep a lit bit to let OS completes restoring (if has)
-        sleep(Duration::from_secs(1)).await;
+            // Let the write complete
+            sleep(Duration::from_secs(1)).await;
 
-        // Verify the record existence, shall get removed when encryption enabled
-        if cfg!(feature = "encrypt-records") {
-            assert!(
-                store_diff.get(&record.key).is_none(),
-                "Chunk should be gone"
-            );
-        } else {
-            assert!(
-                store_diff.get(&record.key).is_some(),
-                "Chunk shall persists without encryption"
+            let on_disk = store.backend.read(&record.key)?;
+            if encryption_seed.is_some() {
+                assert_ne!(on_disk, record.value, "Record should be encrypted on disk");
+            } else {
+                assert_eq!(on_disk, record.value, "Record should be stored as is");
+            }
+
+            store.mark_as_stored(record.key.clone(), RecordType::Chunk);
+            assert_eq!(
+                store.get(&record.key).map(|stored| stored.value.clone()),
+                Some(record.value),
+                "Record should be read back as it was put"
             );
         }
 
         Ok(())
     }
 
+    #[test]
+    fn record_encryption_from_str() {
+        assert_eq!(
+            RecordEncryption::from_str("disabled"),
+            Ok(RecordEncryption::Disabled)
+        );
+        assert_eq!(
+            RecordEncryption::from_str("node-keypair"),
+            Ok(RecordEncryption::NodeKeypair)
+        );
+        // the keys are only read from a file
+        assert!(RecordEncryption::from_str(&format!("key:{}", "ab".repeat(16))).is_err());
+        assert!(RecordEncryption::from_str("enabled").is_err());
+
+        assert_eq!(
+            RecordEncryption::from_hex_key(&format!("{}\n", "ab".repeat(16))),
+            Ok(RecordEncryption::Key(vec![0xab; 16]))
+        );
+        // too short
+        assert!(RecordEncryption::from_hex_key("abab").is_err());
+        assert!(RecordEncryption::from_hex_key("not-hex").is_err());
+    }
+
     #[tokio::test]
     async fn can_store_and_retrieve_chunk() {
         let temp_dir = std::env::temp_dir();
@@ -1247,7 +1963,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("store to open");
 
         // Create a chunk
         let chunk_data = Bytes::from_static(b"Test chunk data");
@@ -1311,7 +2028,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("store to open");
 
         // Create a scratchpad
         let unencrypted_scratchpad_data = Bytes::from_static(b"Test scratchpad data");
@@ -1404,7 +2122,8 @@ mod tests {
             store_config.clone(),
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("store to open");
         // keep track of everything ever stored, to check missing at the end are further away
         let mut stored_records_at_some_point: Vec<RecordKey> = vec![];
         let self_address = NetworkAddress::from_peer(self_id);
@@ -1506,6 +2225,303 @@ mod tests {
         Ok(())
     }
 
+    fn test_record(kind: RecordKind) -> Record {
+        let value = try_serialize_record(
+            &(0..50).map(|_| rand::random::<u8>()).collect::<Bytes>(),
+            kind,
+        )
+        .expect("Cannot generate record value")
+        .to_vec();
+        Record {
+            key: NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+            value,
+            publisher: None,
+            expires: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn quota_evicts_farthest_records_of_its_category() -> Result<()> {
+        let chunk_size = test_record(RecordKind::Chunk).value.len();
+        let quota = 10 * chunk_size;
+
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            max_bytes_per_category: HashMap::from([(RecordCategory::Chunk, quota)]),
+            ..Default::default()
+        };
+        let self_id = PeerId::random();
+        let self_address = NetworkAddress::from_peer(self_id);
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            self_id,
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+        .expect("store to open");
+
+        // Registers have no quota, so are never evicted for the chunks
+        let mut registers = vec![];
+        for _ in 0..5 {
+            let record = test_record(RecordKind::Register);
+            let key = record.key.clone();
+            let record_type = RecordType::NonChunk(XorName::from_content(&record.value));
+            store.put_verified(record, record_type.clone())?;
+            store.mark_as_stored(key.clone(), record_type);
+            registers.push(key);
+        }
+
+        let mut failed_chunks = vec![];
+        for _ in 0..30 {
+            let record = test_record(RecordKind::Chunk);
+            let key = record.key.clone();
+            if store.put_verified(record, RecordType::Chunk).is_ok() {
+                store.mark_as_stored(key, RecordType::Chunk);
+            } else {
+                failed_chunks.push(key);
+            }
+            assert!(store.bytes_by_category()[&RecordCategory::Chunk] <= quota);
+        }
+
+        let stored = store.record_addresses();
+        let stored_chunks: Vec<_> = stored
+            .iter()
+            .filter(|(_addr, record_type)| **record_type == RecordType::Chunk)
+            .map(|(addr, _)| addr.clone())
+            .collect();
+        assert_eq!(stored_chunks.len(), 10);
+        assert!(registers.iter().all(|key| store.contains(key)));
+
+        let farthest_stored = stored_chunks
+            .iter()
+            .map(|addr| self_address.distance(addr))
+            .max()
+            .expect("Chunks are stored");
+        for key in failed_chunks {
+            assert!(
+                self_address.distance(&NetworkAddress::from_record_key(&key)) > farthest_stored
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expired_records_are_not_replicated_and_get_pruned() -> Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+        .expect("store to open");
+
+        let mut put_scratchpad = |expires_at: u64| -> Result<Key> {
+            let owner_sk = SecretKey::random();
+            let mut scratchpad = Scratchpad::new(owner_sk.public_key(), 0);
+            let data = (0..50).map(|_| rand::random::<u8>()).collect::<Bytes>();
+            let _ = scratchpad.update_and_sign_with_expiry(data, &owner_sk, Some(expires_at));
+            let value = try_serialize_record(&scratchpad, RecordKind::Scratchpad)
+                .expect("Cannot generate record value")
+                .to_vec();
+            let key = scratchpad.network_address().to_record_key();
+            let record = Record {
+                key: key.clone(),
+                value,
+                publisher: None,
+                expires: None,
+            };
+            store.put_verified(record, RecordType::Scratchpad)?;
+            store.mark_as_stored(key.clone(), RecordType::Scratchpad);
+            Ok(key)
+        };
+        let expired = put_scratchpad(now_secs() - 1)?;
+        let live = put_scratchpad(now_secs() + 3600)?;
+
+        let replicated = store.record_addresses();
+        assert!(!replicated.contains_key(&NetworkAddress::from_record_key(&expired)));
+        assert!(replicated.contains_key(&NetworkAddress::from_record_key(&live)));
+        assert!(store.get(&expired).is_none());
+        assert!(store.get(&live).is_some());
+
+        store.prune_expired_records();
+        assert!(!store.contains(&expired));
+        assert!(store.contains(&live));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_new_policy_keeps_existing_records() -> Result<()> {
+        let chunk_size = test_record(RecordKind::Chunk).value.len();
+
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            max_bytes_per_category: HashMap::from([(RecordCategory::Chunk, 3 * chunk_size)]),
+            eviction_policy: EvictionPolicy::RejectNew,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+        .expect("store to open");
+
+        let mut stored = vec![];
+        for _ in 0..3 {
+            let record = test_record(RecordKind::Chunk);
+            let key = record.key.clone();
+            store.put_verified(record, RecordType::Chunk)?;
+            store.mark_as_stored(key.clone(), RecordType::Chunk);
+            stored.push(key);
+        }
+
+        assert!(matches!(
+            store.put_verified(test_record(RecordKind::Chunk), RecordType::Chunk),
+            Err(Error::MaxRecords)
+        ));
+        assert!(stored.iter().all(|key| store.contains(key)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_are_not_quoted_once_their_quota_is_full() -> Result<()> {
+        let chunk_size = test_record(RecordKind::Chunk).value.len();
+
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            max_value_bytes: chunk_size,
+            max_bytes_per_category: HashMap::from([(RecordCategory::Chunk, 3 * chunk_size)]),
+            eviction_policy: EvictionPolicy::RejectNew,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+        .expect("store to open");
+
+        let incoming = test_record(RecordKind::Chunk).key;
+        for _ in 0..3 {
+            assert!(!store.is_quota_full_for(&incoming, RecordCategory::Chunk));
+            let record = test_record(RecordKind::Chunk);
+            let key = record.key.clone();
+            store.put_verified(record, RecordType::Chunk)?;
+            store.mark_as_stored(key, RecordType::Chunk);
+        }
+        assert!(store.is_quota_full_for(&incoming, RecordCategory::Chunk));
+        // Registers have no quota
+        assert!(!store.is_quota_full_for(&incoming, RecordCategory::Register));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decommissioning_store_rejects_new_records() -> Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+        .expect("store to open");
+
+        let record = test_record(RecordKind::Chunk);
+        let key = record.key.clone();
+        store.put_verified(record, RecordType::Chunk)?;
+        store.mark_as_stored(key.clone(), RecordType::Chunk);
+
+        store.stop_accepting_records();
+        assert!(!store.is_accepting_records());
+        let mut store = UnifiedRecordStore::Node(store);
+        assert!(matches!(
+            store.put_verified(test_record(RecordKind::Chunk), RecordType::Chunk),
+            Err(NetworkError::NotAcceptingRecords)
+        ));
+        assert!(matches!(
+            store.put_unverified(test_record(RecordKind::ChunkWithPayment), PeerId::random()),
+            Err(NetworkError::NotAcceptingRecords)
+        ));
+        // Held records are still served, to be handed off
+        assert!(store.get(&key).is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stored_records_are_sorted_by_distance() -> Result<()> {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            ..Default::default()
+        };
+        let self_id = PeerId::random();
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+        let mut store = NodeRecordStore::with_config(
+            self_id,
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        )
+        .expect("store to open");
+
+        for kind in [RecordKind::Chunk, RecordKind::Scratchpad, RecordKind::Chunk] {
+            let record = test_record(kind);
+            let key = record.key.clone();
+            store.put_verified(record, RecordType::Chunk)?;
+            store.mark_as_stored(key, RecordType::Chunk);
+        }
+
+        let stored_records = store.stored_records();
+        assert_eq!(stored_records.len(), 3);
+        assert_eq!(
+            stored_records
+                .iter()
+                .filter(|record| record.category == RecordCategory::Scratchpad)
+                .count(),
+            1
+        );
+        let self_address = NetworkAddress::from_peer(self_id);
+        assert!(stored_records.is_sorted_by_key(|record| self_address.distance(&record.address)));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn get_records_within_range() -> eyre::Result<()> {
         let max_records = 50;
@@ -1528,7 +2544,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("store to open");
 
         let mut stored_records: Vec<RecordKey> = vec![];
         let self_address = NetworkAddress::from_peer(self_id);
@@ -1587,6 +2604,23 @@ mod tests {
             max_records / 2
         );
 
+        // the records from the halfway one are beyond the range, the farthest first
+        let irrelevant: Vec<_> = store
+            .irrelevant_record_addresses()
+            .into_iter()
+            .map(|(addr, _record_type)| addr)
+            .collect();
+        let expected: Vec<_> = stored_records[max_records / 2..]
+            .iter()
+            .rev()
+            .map(NetworkAddress::from_record_key)
+            .collect();
+        assert_eq!(irrelevant, expected);
+
+        // an archiver keeps them all
+        store.config.archive_mode = true;
+        assert!(store.irrelevant_record_addresses().is_empty());
+
         Ok(())
     }
 
@@ -1612,7 +2646,8 @@ mod tests {
             store_config.clone(),
             network_event_sender.clone(),
             swarm_cmd_sender.clone(),
-        );
+        )
+        .expect("store to open");
 
         store.payment_received();
 
@@ -1624,7 +2659,8 @@ mod tests {
             store_config,
             network_event_sender,
             swarm_cmd_sender,
-        );
+        )
+        .expect("store to open");
 
         assert_eq!(1, new_store.received_payment_count);
         assert_eq!(store.timestamp, new_store.timestamp);
@@ -7,13 +7,16 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for the Bytes in NetworkAddress
 
-use crate::record_store::{ClientRecordStore, NodeRecordStore};
+use crate::audit_log::RecordSource;
+use crate::error::NetworkError;
+use crate::record_store::{ClientRecordStore, NodeRecordStore, RecordCategory, StoredRecord};
 use ant_evm::{QuotingMetrics, U256};
 use ant_protocol::{storage::RecordType, NetworkAddress};
 use libp2p::kad::{
     store::{RecordStore, Result},
     ProviderRecord, Record, RecordKey,
 };
+use libp2p::PeerId;
 use std::{borrow::Cow, collections::HashMap};
 
 pub enum UnifiedRecordStore {
@@ -28,6 +31,10 @@ impl RecordStore for UnifiedRecordStore {
     fn get(&self, k: &RecordKey) -> Option<std::borrow::Cow<'_, Record>> {
         match self {
             Self::Client(store) => store.get(k),
+            #[cfg(feature = "chaos")]
+            Self::Node(store) if store.corrupts_chunks() => store
+                .get(k)
+                .map(|record| Cow::Owned(crate::chaos::corrupt_chunk(record.into_owned()))),
             Self::Node(store) => store.get(k),
         }
     }
@@ -104,10 +111,62 @@ impl UnifiedRecordStore {
         }
     }
 
-    pub(crate) fn put_verified(&mut self, r: Record, record_type: RecordType) -> Result<()> {
+    pub(crate) fn irrelevant_record_addresses(&self) -> Vec<(NetworkAddress, RecordType)> {
         match self {
-            Self::Client(store) => store.put_verified(r, record_type),
-            Self::Node(store) => store.put_verified(r, record_type),
+            Self::Client(store) => store.irrelevant_record_addresses(),
+            Self::Node(store) => store.irrelevant_record_addresses(),
+        }
+    }
+
+    pub(crate) fn stored_records(&self) -> Vec<StoredRecord> {
+        match self {
+            Self::Client(store) => store.stored_records(),
+            Self::Node(store) => store.stored_records(),
+        }
+    }
+
+    /// Store a validated record, unless the node is decommissioning.
+    pub(crate) fn put_verified(
+        &mut self,
+        r: Record,
+        record_type: RecordType,
+    ) -> crate::error::Result<()> {
+        match self {
+            Self::Client(store) => Ok(store.put_verified(r, record_type)?),
+            Self::Node(store) if !store.is_accepting_records() => {
+                Err(NetworkError::NotAcceptingRecords)
+            }
+            Self::Node(store) => Ok(store.put_verified(r, record_type)?),
+        }
+    }
+
+    /// Pass a record put to us by the peer on for validation, unless the node is decommissioning.
+    pub(crate) fn put_unverified(
+        &mut self,
+        record: Record,
+        source: PeerId,
+    ) -> crate::error::Result<()> {
+        match self {
+            Self::Client(store) => Ok(store.put(record)?),
+            Self::Node(store) if !store.is_accepting_records() => {
+                Err(NetworkError::NotAcceptingRecords)
+            }
+            Self::Node(store) => Ok(store.put_unverified(record, Some(source))?),
+        }
+    }
+
+    pub(crate) fn audit_write(&mut self, key: &RecordKey, source: RecordSource) {
+        match self {
+            Self::Client(_) => {}
+            Self::Node(store) => store.audit_write(key, source),
+        }
+    }
+
+    /// Whether a record of the category at the key would be rejected for its quota being full.
+    pub(crate) fn is_quota_full_for(&self, key: &RecordKey, category: RecordCategory) -> bool {
+        match self {
+            Self::Client(_) => false,
+            Self::Node(store) => store.is_quota_full_for(key, category),
         }
     }
 
@@ -136,6 +195,17 @@ impl UnifiedRecordStore {
         }
     }
 
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn relevant_records_count(&self) -> usize {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling relevant_records_count at Client. This should not happen");
+                0
+            }
+            Self::Node(store) => store.relevant_records_count(),
+        }
+    }
+
     pub(crate) fn get_farthest_replication_distance(&self) -> Option<U256> {
         match self {
             Self::Client(_store) => {
@@ -155,6 +225,35 @@ impl UnifiedRecordStore {
         }
     }
 
+    #[cfg(feature = "chaos")]
+    pub(crate) fn set_corrupt_chunks(&mut self, corrupt_chunks: bool) {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling set_corrupt_chunks at Client. This should not happen");
+            }
+            Self::Node(store) => store.set_corrupt_chunks(corrupt_chunks),
+        }
+    }
+
+    pub(crate) fn is_accepting_records(&self) -> bool {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling is_accepting_records at Client. This should not happen");
+                false
+            }
+            Self::Node(store) => store.is_accepting_records(),
+        }
+    }
+
+    pub(crate) fn stop_accepting_records(&mut self) {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling stop_accepting_records at Client. This should not happen");
+            }
+            Self::Node(store) => store.stop_accepting_records(),
+        }
+    }
+
     pub(crate) fn get_farthest(&self) -> Option<RecordKey> {
         match self {
             Self::Client(_store) => {
@@ -175,6 +274,16 @@ impl UnifiedRecordStore {
         };
     }
 
+    /// Remove a record held enough by its close group, which we're out of.
+    pub(crate) fn prune_replicated(&mut self, k: &RecordKey) {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling prune_replicated at Client. This should not happen");
+            }
+            Self::Node(store) => store.prune_replicated(k),
+        }
+    }
+
     pub(crate) fn cleanup_irrelevant_records(&mut self) {
         match self {
             Self::Client(_store) => {
@@ -0,0 +1,711 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::driver::MAX_PACKET_SIZE;
+use libp2p::kad::RecordKey as Key;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+    thread::JoinHandle,
+};
+use walkdir::WalkDir;
+
+/// The file the `SingleFileBackend` keeps all the records in, within the storage dir.
+const SINGLE_FILE_BACKEND_FILENAME: &str = "records.db";
+
+/// The database the `RedbBackend` keeps all the records in, within the storage dir.
+#[cfg(not(target_arch = "wasm32"))]
+const REDB_BACKEND_FILENAME: &str = "records.redb";
+
+/// The table of the records in the database of the `RedbBackend`, from their keys to their bytes.
+#[cfg(not(target_arch = "wasm32"))]
+const REDB_RECORDS_TABLE: redb::TableDefinition<&[u8], &[u8]> =
+    redb::TableDefinition::new("records");
+
+/// The single file is compacted once this many bytes are taken by removed or replaced records,
+/// and they take more space than the records still stored.
+const MIN_GARBAGE_BYTES_TO_COMPACT: u64 = 64 * 1024 * 1024;
+
+/// The max length of the keys of the records.
+const MAX_KEY_LEN: usize = 1024;
+
+/// The max length of the bytes of the records, with room for the encryption overhead.
+const MAX_VALUE_LEN: usize = MAX_PACKET_SIZE + 1024;
+
+const ENTRY_PUT: u8 = 0;
+const ENTRY_REMOVE: u8 = 1;
+
+/// Where a `NodeRecordStore` keeps the (possibly encrypted) bytes of its records.
+///
+/// The store keeps its own index of the records, so a backend only needs to map keys to bytes.
+pub trait RecordStoreBackend: Send + Sync {
+    /// The keys of all the records held, to repopulate the store on startup.
+    fn keys(&self) -> Vec<Key>;
+
+    /// Read the bytes of a record.
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>>;
+
+    /// Write the bytes of a record, replacing any previous copy.
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()>;
+
+    /// Remove a record.
+    fn remove(&self, key: &Key) -> io::Result<()>;
+}
+
+/// The backends a node can keep its records in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordStoreBackendKind {
+    /// A file per record in the storage dir.
+    #[default]
+    Disk,
+    /// All the records in a single append-only file, for nodes holding many small records.
+    SingleFile,
+    /// All the records in an embedded key-value database, for nodes holding many small records.
+    #[cfg(not(target_arch = "wasm32"))]
+    Redb,
+}
+
+impl RecordStoreBackendKind {
+    /// Open the backend in the storage dir.
+    pub(crate) fn open(&self, storage_dir: &Path) -> io::Result<Arc<dyn RecordStoreBackend>> {
+        Ok(match self {
+            Self::Disk => Arc::new(DiskBackend::new(storage_dir)),
+            Self::SingleFile => Arc::new(SingleFileBackend::open(storage_dir)?),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Redb => Arc::new(RedbBackend::open(storage_dir)?),
+        })
+    }
+}
+
+impl FromStr for RecordStoreBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disk" => Ok(Self::Disk),
+            "single-file" => Ok(Self::SingleFile),
+            #[cfg(not(target_arch = "wasm32"))]
+            "redb" => Ok(Self::Redb),
+            _ => Err(format!("Unknown record store backend: {s}")),
+        }
+    }
+}
+
+/// Converts a Key into a Hex string.
+pub(crate) fn generate_filename(key: &Key) -> String {
+    hex::encode(key.as_ref())
+}
+
+/// Converts a Hex string back into a Key.
+fn get_data_from_filename(hex_str: &str) -> Option<Key> {
+    match hex::decode(hex_str) {
+        Ok(bytes) => Some(Key::from(bytes)),
+        Err(error) => {
+            error!("Error decoding hex string: {:?}", error);
+            None
+        }
+    }
+}
+
+/// Keeps every record in its own file in the storage dir, named after the hex of its key.
+#[derive(Debug)]
+pub(crate) struct DiskBackend {
+    storage_dir: PathBuf,
+}
+
+impl DiskBackend {
+    pub(crate) fn new(storage_dir: &Path) -> Self {
+        Self {
+            storage_dir: storage_dir.to_path_buf(),
+        }
+    }
+}
+
+impl RecordStoreBackend for DiskBackend {
+    fn keys(&self) -> Vec<Key> {
+        WalkDir::new(&self.storage_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| {
+                let path = entry.path();
+                debug!("Existing record found: {path:?}");
+                match path.file_name().and_then(|n| n.to_str()) {
+                    Some(filename) => get_data_from_filename(filename),
+                    None => {
+                        // warn and remove this file as it's not a valid record
+                        warn!(
+                            "Found a file in the storage dir that is not a valid record: {:?}",
+                            path
+                        );
+                        if let Err(e) = fs::remove_file(path) {
+                            warn!(
+                                "Failed to remove invalid record file from storage dir: {:?}",
+                                e
+                            );
+                        }
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>> {
+        fs::read(self.storage_dir.join(generate_filename(key)))
+    }
+
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()> {
+        fs::write(self.storage_dir.join(generate_filename(key)), bytes)
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        fs::remove_file(self.storage_dir.join(generate_filename(key)))
+    }
+}
+
+/// Keeps all the records in a single append-only file, with an index of their positions in memory.
+///
+/// Every write and removal is appended as an entry of
+/// `[kind: u8][key length: u32][key][value length: u32][value]`, and the index is rebuilt by
+/// replaying the entries on startup. The file is rewritten without the outdated entries once they
+/// take more space than the records still stored, on a thread of its own for the reads not to wait
+/// on it.
+#[derive(Debug)]
+pub(crate) struct SingleFileBackend {
+    path: PathBuf,
+    inner: Arc<Mutex<SingleFileInner>>,
+}
+
+#[derive(Debug)]
+struct SingleFileInner {
+    file: File,
+    /// Offset of the value and its length, for every record held
+    index: HashMap<Key, (u64, u32)>,
+    /// Offset of the end of the last entry, where the next one is appended
+    end: u64,
+    /// Bytes taken by the values of removed or replaced records
+    garbage_bytes: u64,
+    live_bytes: u64,
+    /// The thread compacting the file, if any
+    compaction: Option<JoinHandle<()>>,
+}
+
+impl SingleFileInner {
+    /// Update the index with an entry appended at the end of the file.
+    fn apply(&mut self, kind: u8, key: &Key, value_offset: u64, value_len: u32) {
+        if let Some((_, len)) = self.index.remove(key) {
+            self.garbage_bytes += len as u64;
+            self.live_bytes -= len as u64;
+        }
+        if kind == ENTRY_PUT {
+            let _ = self.index.insert(key.clone(), (value_offset, value_len));
+            self.live_bytes += value_len as u64;
+        }
+        self.end = value_offset + value_len as u64;
+    }
+
+    fn is_compacting(&self) -> bool {
+        self.compaction
+            .as_ref()
+            .is_some_and(|compaction| !compaction.is_finished())
+    }
+}
+
+impl SingleFileBackend {
+    /// Open the file, replaying its entries. A corrupt entry fails the opening rather than the records after it
+    /// being dropped, for the operator to look into it.
+    pub(crate) fn open(storage_dir: &Path) -> io::Result<Self> {
+        let path = storage_dir.join(SINGLE_FILE_BACKEND_FILENAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let mut inner = SingleFileInner {
+            file: file.try_clone()?,
+            index: HashMap::new(),
+            end: 0,
+            garbage_bytes: 0,
+            live_bytes: 0,
+            compaction: None,
+        };
+        let mut reader = BufReader::new(&mut file);
+        while let Some(entry) = read_entry(&mut reader, inner.end).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Corrupt entry at {} of {path:?}: {err}", inner.end),
+            )
+        })? {
+            inner.apply(entry.kind, &entry.key, entry.value_offset, entry.value_len);
+        }
+
+        info!(
+            "Opened {path:?} holding {} records, with {} bytes to compact",
+            inner.index.len(),
+            inner.garbage_bytes
+        );
+        Ok(Self {
+            path,
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    fn lock(&self) -> io::Result<MutexGuard<'_, SingleFileInner>> {
+        lock(&self.inner)
+    }
+
+    fn append(&self, key: &Key, kind: u8, value: &[u8]) -> io::Result<()> {
+        let mut inner = self.lock()?;
+        let end = inner.end;
+        let offset = inner.file.seek(SeekFrom::Start(end))?;
+        let mut writer = BufWriter::new(&mut inner.file);
+        let value_offset = write_entry(&mut writer, offset, kind, key, value)?;
+        writer.flush()?;
+        drop(writer);
+        inner.file.sync_data()?;
+        inner.apply(kind, key, value_offset, value.len() as u32);
+
+        if inner.garbage_bytes >= MIN_GARBAGE_BYTES_TO_COMPACT
+            && inner.garbage_bytes > inner.live_bytes
+            && !inner.is_compacting()
+        {
+            let path = self.path.clone();
+            let shared = Arc::clone(&self.inner);
+            inner.compaction = Some(
+                std::thread::Builder::new()
+                    .name("record-store-compaction".to_string())
+                    .spawn(move || {
+                        if let Err(err) = compact(&path, &shared) {
+                            error!("Failed to compact {path:?}: {err:?}");
+                            let _ = fs::remove_file(path.with_extension("compacting"));
+                        }
+                    })?,
+            );
+        }
+        Ok(())
+    }
+
+    /// Wait for the ongoing compaction to be done, if any.
+    #[cfg(test)]
+    fn wait_for_compaction(&self) -> io::Result<()> {
+        let compaction = self.lock()?.compaction.take();
+        if let Some(compaction) = compaction {
+            compaction
+                .join()
+                .map_err(|_| io::Error::other("The compaction panicked"))?;
+        }
+        Ok(())
+    }
+}
+
+fn lock(inner: &Mutex<SingleFileInner>) -> io::Result<MutexGuard<'_, SingleFileInner>> {
+    inner
+        .lock()
+        .map_err(|_| io::Error::other("The record store file lock is poisoned"))
+}
+
+/// Rewrite the file with only the records still stored.
+///
+/// The records are copied from a snapshot of the index without holding the lock, the file only
+/// being appended to in the meantime. The entries appended since are then copied under the lock,
+/// before the compacted file replaces the original one.
+fn compact(path: &Path, shared: &Mutex<SingleFileInner>) -> io::Result<()> {
+    let (snapshot, snapshot_end) = {
+        let inner = lock(shared)?;
+        info!(
+            "Compacting {path:?}, dropping {} bytes of outdated records",
+            inner.garbage_bytes
+        );
+        (inner.index.clone(), inner.end)
+    };
+
+    let mut source = File::open(path)?;
+    let compacted_path = path.with_extension("compacting");
+    let mut compacted = SingleFileInner {
+        file: File::create(&compacted_path)?,
+        index: HashMap::with_capacity(snapshot.len()),
+        end: 0,
+        garbage_bytes: 0,
+        live_bytes: 0,
+        compaction: None,
+    };
+    let mut writer = BufWriter::new(compacted.file.try_clone()?);
+    for (key, (value_offset, value_len)) in &snapshot {
+        let mut value = vec![0u8; *value_len as usize];
+        let _ = source.seek(SeekFrom::Start(*value_offset))?;
+        source.read_exact(&mut value)?;
+        let value_offset = write_entry(&mut writer, compacted.end, ENTRY_PUT, key, &value)?;
+        compacted.apply(ENTRY_PUT, key, value_offset, *value_len);
+    }
+
+    let mut inner = lock(shared)?;
+    let mut values = File::open(path)?;
+    let _ = source.seek(SeekFrom::Start(snapshot_end))?;
+    let mut reader = BufReader::new(&mut source);
+    let mut offset = snapshot_end;
+    while offset < inner.end {
+        let Some(entry) = read_entry(&mut reader, offset)? else {
+            break;
+        };
+        offset = entry.end;
+        let mut value = vec![0u8; entry.value_len as usize];
+        let _ = values.seek(SeekFrom::Start(entry.value_offset))?;
+        values.read_exact(&mut value)?;
+        let value_offset = write_entry(&mut writer, compacted.end, entry.kind, &entry.key, &value)?;
+        compacted.apply(entry.kind, &entry.key, value_offset, entry.value_len);
+    }
+    writer.flush()?;
+    drop(writer);
+    compacted.file.sync_all()?;
+
+    fs::rename(&compacted_path, path)?;
+    sync_dir(path)?;
+    inner.file = OpenOptions::new().read(true).write(true).open(path)?;
+    inner.index = compacted.index;
+    inner.end = compacted.end;
+    inner.garbage_bytes = compacted.garbage_bytes;
+    inner.live_bytes = compacted.live_bytes;
+    Ok(())
+}
+
+/// Sync the directory of the file, for a rename within it to survive a crash.
+fn sync_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+impl RecordStoreBackend for SingleFileBackend {
+    fn keys(&self) -> Vec<Key> {
+        match self.lock() {
+            Ok(inner) => inner.index.keys().cloned().collect(),
+            Err(err) => {
+                error!("Failed to list the records of {:?}: {err:?}", self.path);
+                vec![]
+            }
+        }
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>> {
+        let mut inner = self.lock()?;
+        let (value_offset, value_len) = *inner
+            .index
+            .get(key)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        let mut value = vec![0u8; value_len as usize];
+        let _ = inner.file.seek(SeekFrom::Start(value_offset))?;
+        inner.file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()> {
+        if key.as_ref().len() > MAX_KEY_LEN || bytes.len() > MAX_VALUE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Record too large for the single file backend",
+            ));
+        }
+        self.append(key, ENTRY_PUT, bytes)
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        if !self.lock()?.index.contains_key(key) {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        }
+        self.append(key, ENTRY_REMOVE, &[])
+    }
+}
+
+/// Keeps all the records in a `redb` database, an embedded key-value store.
+///
+/// The database commits every write durably, and its reads don't wait on the writes.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct RedbBackend {
+    path: PathBuf,
+    db: redb::Database,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for RedbBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedbBackend")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RedbBackend {
+    pub(crate) fn open(storage_dir: &Path) -> io::Result<Self> {
+        let path = storage_dir.join(REDB_BACKEND_FILENAME);
+        let db = redb::Database::create(&path).map_err(io::Error::other)?;
+        // Create the table upfront, for the reads not to fail before the first write
+        let txn = db.begin_write().map_err(io::Error::other)?;
+        let _ = txn
+            .open_table(REDB_RECORDS_TABLE)
+            .map_err(io::Error::other)?;
+        txn.commit().map_err(io::Error::other)?;
+        info!("Opened the record database {path:?}");
+        Ok(Self { path, db })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RecordStoreBackend for RedbBackend {
+    fn keys(&self) -> Vec<Key> {
+        use redb::ReadableTable;
+
+        let keys = || -> io::Result<Vec<Key>> {
+            let txn = self.db.begin_read().map_err(io::Error::other)?;
+            let table = txn
+                .open_table(REDB_RECORDS_TABLE)
+                .map_err(io::Error::other)?;
+            table
+                .iter()
+                .map_err(io::Error::other)?
+                .map(|entry| {
+                    let (key, _) = entry.map_err(io::Error::other)?;
+                    Ok(Key::from(key.value().to_vec()))
+                })
+                .collect()
+        };
+        keys().unwrap_or_else(|err| {
+            error!("Failed to list the records of {:?}: {err:?}", self.path);
+            vec![]
+        })
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>> {
+        let txn = self.db.begin_read().map_err(io::Error::other)?;
+        let table = txn
+            .open_table(REDB_RECORDS_TABLE)
+            .map_err(io::Error::other)?;
+        let value = table
+            .get(key.as_ref())
+            .map_err(io::Error::other)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        Ok(value.value().to_vec())
+    }
+
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()> {
+        let txn = self.db.begin_write().map_err(io::Error::other)?;
+        {
+            let mut table = txn
+                .open_table(REDB_RECORDS_TABLE)
+                .map_err(io::Error::other)?;
+            let _ = table
+                .insert(key.as_ref(), bytes)
+                .map_err(io::Error::other)?;
+        }
+        txn.commit().map_err(io::Error::other)
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        let txn = self.db.begin_write().map_err(io::Error::other)?;
+        let removed = {
+            let mut table = txn
+                .open_table(REDB_RECORDS_TABLE)
+                .map_err(io::Error::other)?;
+            let removed = table
+                .remove(key.as_ref())
+                .map_err(io::Error::other)?
+                .is_some();
+            removed
+        };
+        if !removed {
+            return Err(io::Error::from(io::ErrorKind::NotFound));
+        }
+        txn.commit().map_err(io::Error::other)
+    }
+}
+
+struct Entry {
+    kind: u8,
+    key: Key,
+    value_offset: u64,
+    value_len: u32,
+    /// Offset of the next entry
+    end: u64,
+}
+
+/// Read the entry at the offset, skipping over its value. `None` at the end of the file.
+fn read_entry(reader: &mut impl Read, offset: u64) -> io::Result<Option<Entry>> {
+    let mut kind = [0u8; 1];
+    if reader.read(&mut kind)? == 0 {
+        return Ok(None);
+    }
+    let kind = kind[0];
+    if kind != ENTRY_PUT && kind != ENTRY_REMOVE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid entry kind {kind}"),
+        ));
+    }
+
+    let key_len = read_len(reader, MAX_KEY_LEN)?;
+    let mut key = vec![0u8; key_len as usize];
+    reader.read_exact(&mut key)?;
+    let value_len = read_len(reader, MAX_VALUE_LEN)?;
+    let value_offset = offset + 1 + 4 + key_len as u64 + 4;
+    let skipped = io::copy(&mut reader.take(value_len as u64), &mut io::sink())?;
+    if skipped != value_len as u64 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(Some(Entry {
+        kind,
+        key: Key::from(key),
+        value_offset,
+        value_len,
+        end: value_offset + value_len as u64,
+    }))
+}
+
+/// Write an entry at the offset, returning the offset of its value.
+fn write_entry(
+    writer: &mut impl Write,
+    offset: u64,
+    kind: u8,
+    key: &Key,
+    value: &[u8],
+) -> io::Result<u64> {
+    let key = key.as_ref();
+    writer.write_all(&[kind])?;
+    writer.write_all(&(key.len() as u32).to_le_bytes())?;
+    writer.write_all(key)?;
+    writer.write_all(&(value.len() as u32).to_le_bytes())?;
+    writer.write_all(value)?;
+    Ok(offset + 1 + 4 + key.len() as u64 + 4)
+}
+
+/// Read a length, refusing the ones over the max rather than trusting the bytes on disk.
+fn read_len(reader: &mut impl Read, max: usize) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    let len = u32::from_le_bytes(bytes);
+    if len as usize > max {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Length {len} over the max of {max}"),
+        ));
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(i: u8) -> Key {
+        Key::from(vec![i; 32])
+    }
+
+    #[test]
+    fn single_file_backend_survives_reopening() -> eyre::Result<()> {
+        let dir = assert_fs::TempDir::new()?;
+
+        let backend = SingleFileBackend::open(dir.path())?;
+        backend.write(&key(1), b"first")?;
+        backend.write(&key(2), b"second")?;
+        backend.write(&key(1), b"first, replaced")?;
+        backend.remove(&key(2))?;
+        assert!(backend.remove(&key(2)).is_err());
+        drop(backend);
+
+        let backend = SingleFileBackend::open(dir.path())?;
+        assert_eq!(backend.keys(), vec![key(1)]);
+        assert_eq!(backend.read(&key(1))?, b"first, replaced");
+        assert!(backend.read(&key(2)).is_err());
+
+        backend.write(&key(3), b"third")?;
+        assert_eq!(backend.read(&key(3))?, b"third");
+        Ok(())
+    }
+
+    #[test]
+    fn redb_backend_survives_reopening() -> eyre::Result<()> {
+        let dir = assert_fs::TempDir::new()?;
+
+        let backend = RedbBackend::open(dir.path())?;
+        backend.write(&key(1), b"first")?;
+        backend.write(&key(2), b"second")?;
+        backend.write(&key(1), b"first, replaced")?;
+        backend.remove(&key(2))?;
+        assert!(backend.remove(&key(2)).is_err());
+        drop(backend);
+
+        let backend = RedbBackend::open(dir.path())?;
+        assert_eq!(backend.keys(), vec![key(1)]);
+        assert_eq!(backend.read(&key(1))?, b"first, replaced");
+        assert!(backend.read(&key(2)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn single_file_backend_refuses_to_open_a_corrupt_file() -> eyre::Result<()> {
+        let dir = assert_fs::TempDir::new()?;
+        let path = dir.path().join(SINGLE_FILE_BACKEND_FILENAME);
+
+        let backend = SingleFileBackend::open(dir.path())?;
+        backend.write(&key(1), b"first")?;
+        drop(backend);
+
+        // A length over the max isn't trusted to allocate the key
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(&[ENTRY_PUT])?;
+        file.write_all(&u32::MAX.to_le_bytes())?;
+        drop(file);
+        let err = SingleFileBackend::open(dir.path()).expect_err("corrupt file to be refused");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Nor is the file truncated, to lose the records after the corrupt entry
+        assert_eq!(fs::metadata(&path)?.len(), 1 + 4 + 32 + 4 + 5 + 1 + 4);
+        Ok(())
+    }
+
+    #[test]
+    fn single_file_backend_compacts_outdated_records() -> eyre::Result<()> {
+        let dir = assert_fs::TempDir::new()?;
+        let backend = SingleFileBackend::open(dir.path())?;
+        let value = vec![7u8; 1024 * 1024];
+
+        for i in 0..=(MIN_GARBAGE_BYTES_TO_COMPACT / value.len() as u64) {
+            backend.write(&key(1), &value)?;
+            backend.write(&key(2), &[i as u8])?;
+        }
+        // The records written while compacting are kept
+        backend.write(&key(3), b"third")?;
+        backend.remove(&key(2))?;
+        backend.wait_for_compaction()?;
+        backend.write(&key(2), b"second")?;
+
+        let file_len = fs::metadata(dir.path().join(SINGLE_FILE_BACKEND_FILENAME))?.len();
+        assert!(file_len < MIN_GARBAGE_BYTES_TO_COMPACT);
+        assert_eq!(backend.read(&key(1))?, value);
+        assert_eq!(backend.read(&key(2))?, b"second");
+        assert_eq!(backend.read(&key(3))?, b"third");
+        drop(backend);
+
+        let backend = SingleFileBackend::open(dir.path())?;
+        assert_eq!(backend.read(&key(1))?, value);
+        assert_eq!(backend.read(&key(2))?, b"second");
+        assert_eq!(backend.read(&key(3))?, b"third");
+        Ok(())
+    }
+}
@@ -6,16 +6,21 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::driver::{BadNodes, NodeBehaviour};
+use crate::{driver::NodeBehaviour, peer_scoring::PeerScores, target_arch::Instant};
 use itertools::Itertools;
 use libp2p::{
     core::transport::ListenerId, multiaddr::Protocol, Multiaddr, PeerId, StreamProtocol, Swarm,
 };
 use rand::Rng;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    time::Duration,
+};
 
 const MAX_CONCURRENT_RELAY_CONNECTIONS: usize = 4;
 const MAX_POTENTIAL_CANDIDATES: usize = 1000;
+/// Time before trying again a relay whose reservation failed or was lost.
+const RELAY_RETRY_BACKOFF: Duration = Duration::from_secs(10 * 60);
 
 pub(crate) fn is_a_relayed_peer(addrs: &HashSet<Multiaddr>) -> bool {
     addrs
@@ -23,30 +28,56 @@ pub(crate) fn is_a_relayed_peer(addrs: &HashSet<Multiaddr>) -> bool {
         .any(|multiaddr| multiaddr.iter().any(|p| matches!(p, Protocol::P2pCircuit)))
 }
 
+/// A reservation we hold on a relay server.
+#[derive(Debug)]
+struct RelayReservation {
+    addr: Multiaddr,
+    reserved_at: Instant,
+    renewals: usize,
+}
+
 /// Manage the relay servers that we are connected to.
 /// This is the client side of the relay server protocol.
 #[derive(Debug)]
 pub(crate) struct RelayManager {
     self_peer_id: PeerId,
+    /// Whether reservations are made. The candidates are collected regardless, for a node found to be unreachable to
+    /// start making reservations right away.
+    enabled: bool,
     candidates: VecDeque<(PeerId, Multiaddr)>,
     waiting_for_reservation: BTreeMap<PeerId, Multiaddr>,
-    connected_relays: BTreeMap<PeerId, Multiaddr>,
+    connected_relays: BTreeMap<PeerId, RelayReservation>,
+    /// The relays whose reservation failed or was lost, and when.
+    failed_relays: HashMap<PeerId, Instant>,
 
     /// Tracker for the relayed listen addresses.
     relayed_listener_id_map: HashMap<ListenerId, PeerId>,
 }
 
 impl RelayManager {
-    pub(crate) fn new(self_peer_id: PeerId) -> Self {
+    pub(crate) fn new(self_peer_id: PeerId, enabled: bool) -> Self {
         Self {
             self_peer_id,
+            enabled,
             connected_relays: Default::default(),
             waiting_for_reservation: Default::default(),
             candidates: Default::default(),
+            failed_relays: Default::default(),
             relayed_listener_id_map: Default::default(),
         }
     }
 
+    /// Start making reservations with the collected candidates.
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// The number of reservations we currently hold.
+    #[cfg(feature = "open-metrics")]
+    pub(crate) fn reservation_count(&self) -> usize {
+        self.connected_relays.len()
+    }
+
     /// Should we keep this peer alive? Closing a connection to that peer would remove that server from the listen addr.
     pub(crate) fn keep_alive_peer(&self, peer_id: &PeerId) -> bool {
         self.connected_relays.contains_key(peer_id)
@@ -54,14 +85,16 @@ impl RelayManager {
     }
 
     /// Add a potential candidate to the list if it satisfies all the identify checks and also supports the relay server
-    /// protocol.
+    /// protocol, i.e. it runs in relay server mode.
     pub(crate) fn add_potential_candidates(
         &mut self,
         peer_id: &PeerId,
         addrs: &HashSet<Multiaddr>,
         stream_protocols: &Vec<StreamProtocol>,
     ) {
-        if self.candidates.len() >= MAX_POTENTIAL_CANDIDATES {
+        if self.candidates.len() >= MAX_POTENTIAL_CANDIDATES
+            || self.candidates.iter().any(|(peer, _addr)| peer == peer_id)
+        {
             return;
         }
 
@@ -86,36 +119,28 @@ impl RelayManager {
     pub(crate) fn try_connecting_to_relay(
         &mut self,
         swarm: &mut Swarm<NodeBehaviour>,
-        bad_nodes: &BadNodes,
+        peer_scores: &PeerScores,
     ) {
-        if self.connected_relays.len() >= MAX_CONCURRENT_RELAY_CONNECTIONS
+        let reservations = self.connected_relays.len() + self.waiting_for_reservation.len();
+        if !self.enabled
+            || reservations >= MAX_CONCURRENT_RELAY_CONNECTIONS
             || self.candidates.is_empty()
         {
             return;
         }
 
-        let reservations_to_make = MAX_CONCURRENT_RELAY_CONNECTIONS - self.connected_relays.len();
+        let reservations_to_make = MAX_CONCURRENT_RELAY_CONNECTIONS - reservations;
         let mut n_reservations = 0;
 
         while n_reservations < reservations_to_make {
             // todo: should we remove all our other `listen_addr`? And should we block from adding `add_external_address` if
             // we're behind nat?
 
-            // Pick a random candidate from the vector. Check if empty, or `gen_range` panics for empty range.
-            let index = if self.candidates.is_empty() {
-                debug!("No more relay candidates.");
-                break;
-            } else {
-                rand::thread_rng().gen_range(0..self.candidates.len())
-            };
-
-            if let Some((peer_id, relay_addr)) = self.candidates.remove(index) {
+            if let Some((peer_id, relay_addr)) = self.pick_candidate(Instant::now()) {
                 // skip if detected as a bad node
-                if let Some((_, is_bad)) = bad_nodes.get(&peer_id) {
-                    if *is_bad {
-                        debug!("Peer {peer_id:?} is considered as a bad node. Skipping it.");
-                        continue;
-                    }
+                if peer_scores.is_bad(&peer_id) {
+                    debug!("Peer {peer_id:?} is considered as a bad node. Skipping it.");
+                    continue;
                 }
 
                 if self.connected_relays.contains_key(&peer_id)
@@ -143,12 +168,43 @@ impl RelayManager {
         }
     }
 
-    /// Update client state after we've successfully made reservation with a relay.
+    /// Pick a random candidate, skipping the relays that failed recently.
+    fn pick_candidate(&mut self, now: Instant) -> Option<(PeerId, Multiaddr)> {
+        self.failed_relays.retain(|_peer, failed_at| {
+            now.saturating_duration_since(*failed_at) < RELAY_RETRY_BACKOFF
+        });
+        let eligible = self
+            .candidates
+            .iter()
+            .positions(|(peer_id, _addr)| !self.failed_relays.contains_key(peer_id))
+            .collect_vec();
+        // Check if empty, or `gen_range` panics for empty range.
+        if eligible.is_empty() {
+            return None;
+        }
+        let index = eligible[rand::thread_rng().gen_range(0..eligible.len())];
+        self.candidates.remove(index)
+    }
+
+    /// Update client state after we've successfully made or renewed a reservation with a relay.
     pub(crate) fn on_successful_reservation_by_client(
         &mut self,
         peer_id: &PeerId,
+        renewal: bool,
         swarm: &mut Swarm<NodeBehaviour>,
     ) {
+        if renewal {
+            if let Some(reservation) = self.connected_relays.get_mut(peer_id) {
+                reservation.renewals += 1;
+                debug!(
+                    "Renewed reservation with {peer_id:?}, held for {:?} over {} renewals",
+                    reservation.reserved_at.elapsed(),
+                    reservation.renewals
+                );
+                return;
+            }
+        }
+
         if tracing::level_enabled!(tracing::Level::DEBUG) {
             let all_external_addresses = swarm.external_addresses().collect_vec();
             let all_listeners = swarm.listeners().collect_vec();
@@ -160,7 +216,14 @@ impl RelayManager {
             Some(addr) => {
                 info!("Successfully made reservation with {peer_id:?} on {addr:?}. Adding the addr to external address.");
                 swarm.add_external_address(addr.clone());
-                self.connected_relays.insert(*peer_id, addr);
+                self.connected_relays.insert(
+                    *peer_id,
+                    RelayReservation {
+                        addr,
+                        reserved_at: Instant::now(),
+                        renewals: 0,
+                    },
+                );
             }
             None => {
                 debug!("Made a reservation with a peer that we had not requested to");
@@ -169,41 +232,57 @@ impl RelayManager {
     }
 
     /// Update client state if the reservation has been cancelled or if the relay has closed.
+    /// Returns whether a reservation failed or was lost, the relay being retried after a back off.
     pub(crate) fn on_listener_closed(
         &mut self,
         listener_id: &ListenerId,
         swarm: &mut Swarm<NodeBehaviour>,
-    ) {
+    ) -> bool {
         let Some(peer_id) = self.relayed_listener_id_map.remove(listener_id) else {
-            return;
+            return false;
         };
 
-        if let Some(addr) = self.connected_relays.remove(&peer_id) {
-            info!("Removing connected relay server as the listener has been closed: {peer_id:?}");
+        let relay_addr = if let Some(reservation) = self.connected_relays.remove(&peer_id) {
+            let addr = reservation.addr;
+            info!(
+                "Removing connected relay server as the listener has been closed after {:?}: {peer_id:?}",
+                reservation.reserved_at.elapsed()
+            );
             info!("Removing external addr: {addr:?}");
             swarm.remove_external_address(&addr);
 
             // Even though we craft and store addrs in this format /ip4/198.51.100.0/tcp/55555/p2p/QmRelay/p2p-circuit/,
             // sometimes our PeerId is added at the end by the swarm?, which we want to remove as well i.e.,
             // /ip4/198.51.100.0/tcp/55555/p2p/QmRelay/p2p-circuit/p2p/QmSelf
-            let Ok(addr_with_self_peer_id) = addr.with_p2p(self.self_peer_id) else {
-                return;
-            };
-            info!("Removing external addr: {addr_with_self_peer_id:?}");
-            swarm.remove_external_address(&addr_with_self_peer_id);
-        }
-        if let Some(addr) = self.waiting_for_reservation.remove(&peer_id) {
+            if let Ok(addr_with_self_peer_id) = addr.clone().with_p2p(self.self_peer_id) {
+                info!("Removing external addr: {addr_with_self_peer_id:?}");
+                swarm.remove_external_address(&addr_with_self_peer_id);
+            }
+            Some(addr)
+        } else if let Some(addr) = self.waiting_for_reservation.remove(&peer_id) {
             info!("Removed peer form waiting_for_reservation as the listener has been closed {peer_id:?}: {addr:?}");
             debug!(
                 "waiting_for_reservation len: {:?}",
                 self.waiting_for_reservation.len()
-            )
+            );
+            Some(addr)
+        } else {
+            None
+        };
+
+        let Some(relay_addr) = relay_addr else {
+            return false;
+        };
+        let _ = self.failed_relays.insert(peer_id, Instant::now());
+        if self.candidates.len() < MAX_POTENTIAL_CANDIDATES {
+            self.candidates.push_back((peer_id, relay_addr));
         }
+        true
     }
 
     fn does_it_support_relay_server_protocol(protocols: &Vec<StreamProtocol>) -> bool {
         for stream_protocol in protocols {
-            if *stream_protocol == "/libp2p/circuit/relay/0.2.0/stop" {
+            if *stream_protocol == "/libp2p/circuit/relay/0.2.0/hop" {
                 return true;
             }
         }
@@ -216,7 +295,7 @@ impl RelayManager {
 
         let ip = addr
             .iter()
-            .find(|protocol| matches!(protocol, Protocol::Ip4(_)))?;
+            .find(|protocol| matches!(protocol, Protocol::Ip4(_) | Protocol::Ip6(_)))?;
         output_addr.push(ip);
         let port = addr
             .iter()
@@ -239,3 +318,37 @@ impl RelayManager {
         Some(output_addr)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_relays_are_retried_after_a_back_off() -> eyre::Result<()> {
+        let mut relay_manager = RelayManager::new(PeerId::random(), true);
+        let (relay, failed_relay) = (PeerId::random(), PeerId::random());
+        let addrs = HashSet::from(["/ip4/203.0.113.7/udp/12000/quic-v1".parse()?]);
+        let protocols = vec![StreamProtocol::new("/libp2p/circuit/relay/0.2.0/hop")];
+        relay_manager.add_potential_candidates(&relay, &addrs, &protocols);
+        relay_manager.add_potential_candidates(&failed_relay, &addrs, &protocols);
+        // Identified again
+        relay_manager.add_potential_candidates(&relay, &addrs, &protocols);
+        assert_eq!(relay_manager.candidates.len(), 2);
+
+        let now = Instant::now();
+        let _ = relay_manager.failed_relays.insert(failed_relay, now);
+        let picked = relay_manager
+            .pick_candidate(now)
+            .map(|(peer_id, _addr)| peer_id);
+        assert_eq!(picked, Some(relay));
+        assert!(relay_manager.pick_candidate(now).is_none());
+
+        let later = now + RELAY_RETRY_BACKOFF;
+        let picked = relay_manager
+            .pick_candidate(later)
+            .map(|(peer_id, _addr)| peer_id);
+        assert_eq!(picked, Some(failed_relay));
+        assert!(relay_manager.failed_relays.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::driver::MAX_PACKET_SIZE;
+use libp2p::{relay, PeerId};
+use std::time::Duration;
+
+/// The limits of the relay service a public node offers to the nodes behind home network.
+///
+/// The bandwidth relayed for a peer is capped by the bytes and the duration of each of its circuits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayServerConfig {
+    /// Amount of peers we are relaying for
+    pub max_reservations: usize,
+    /// The total amount of relayed connections at any given moment
+    pub max_circuits: usize,
+    /// Amount of relayed connections per peer (both dst and src)
+    pub max_circuits_per_peer: usize,
+    /// The bytes relayed over a circuit before it's closed
+    pub max_circuit_bytes: u64,
+    /// The time a circuit stays open
+    pub max_circuit_duration: Duration,
+}
+
+impl Default for RelayServerConfig {
+    fn default() -> Self {
+        Self {
+            max_reservations: 128,
+            max_circuits: 1024,
+            max_circuits_per_peer: 256,
+            // We should at least be able to relay packets with chunks etc.
+            max_circuit_bytes: MAX_PACKET_SIZE as u64,
+            max_circuit_duration: Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+impl RelayServerConfig {
+    pub(crate) fn behaviour(&self, peer_id: PeerId) -> relay::Behaviour {
+        let cfg = relay::Config {
+            max_reservations: self.max_reservations,
+            max_circuits: self.max_circuits,
+            max_circuits_per_peer: self.max_circuits_per_peer,
+            max_circuit_bytes: self.max_circuit_bytes,
+            max_circuit_duration: self.max_circuit_duration,
+            circuit_src_rate_limiters: vec![], // No extra rate limiting for now
+            ..Default::default()
+        };
+        relay::Behaviour::new(peer_id, cfg)
+    }
+}
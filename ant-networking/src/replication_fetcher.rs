@@ -17,12 +17,17 @@ use libp2p::{
     kad::{KBucketDistance as Distance, RecordKey, K_VALUE},
     PeerId,
 };
-use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+#[cfg(feature = "open-metrics")]
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet};
 use tokio::{sync::mpsc, time::Duration};
 
 // Max parallel fetches that can be undertaken at the same time.
 const MAX_PARALLEL_FETCH: usize = K_VALUE.get();
 
+// Max parallel fetches from the same holder, not to overload it nor to depend on it only.
+const MAX_PARALLEL_FETCH_PER_PEER: usize = 5;
+
 // The duration after which a peer will be considered failed to fetch data from,
 // if no response got from that peer.
 // Note this will also cover the period that node self write the fetched copy to disk.
@@ -33,9 +38,50 @@ const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
 // This is to avoid holding too many outdated entries when the fetching speed is slow.
 const PENDING_TIMEOUT: Duration = Duration::from_secs(900);
 
+// The duration a peer is expected to hold a key for after notifying us of it,
+// it shall notify us again within that period if still holding it.
+const HOLDER_TIMEOUT: Duration = Duration::from_secs(900);
+
 // The time the entry will be considered as `time out` and to be cleared.
 type ReplicationTimeout = Instant;
 
+/// The order the keys are fetched in, the mutable records first then the closest ones to us.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "open-metrics", derive(EncodeLabelValue))]
+pub(crate) enum FetchPriority {
+    /// Transactions, registers, pointers and scratchpads. They are small, and a missing update
+    /// can have the other versions be accepted in the meantime.
+    Mutable,
+    /// The chunks, the bulk of the data.
+    Chunk,
+}
+
+impl From<&RecordType> for FetchPriority {
+    fn from(record_type: &RecordType) -> Self {
+        match record_type {
+            RecordType::Chunk => Self::Chunk,
+            RecordType::Scratchpad | RecordType::NonChunk(_) => Self::Mutable,
+        }
+    }
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct FetchPriorityLabels {
+    pub(crate) priority: FetchPriority,
+}
+
+/// The state of the replication queue.
+#[derive(Debug, Default)]
+pub(crate) struct ReplicationQueueStatus {
+    pub(crate) pending_keys: usize,
+    #[cfg_attr(not(feature = "open-metrics"), allow(dead_code))]
+    pub(crate) pending_keys_per_priority: HashMap<FetchPriority, usize>,
+    pub(crate) ongoing_fetches: usize,
+    /// How long the oldest key has been waiting for
+    pub(crate) oldest_pending: Duration,
+}
+
 #[derive(Debug)]
 pub(crate) struct ReplicationFetcher {
     self_peer_id: PeerId,
@@ -50,6 +96,9 @@ pub(crate) struct ReplicationFetcher {
     /// used when the node is full, but we still have "close" data coming in
     /// that is _not_ closer than our farthest max record
     farthest_acceptable_distance: Option<Distance>,
+    /// The peers that notified us of holding the in range keys, until when they're expected to hold them.
+    /// Only tracked once enabled, see [`Self::track_holders`].
+    known_holders: Option<HashMap<RecordKey, HashMap<PeerId, ReplicationTimeout>>>,
 }
 
 impl ReplicationFetcher {
@@ -62,6 +111,69 @@ impl ReplicationFetcher {
             event_sender,
             distance_range: None,
             farthest_acceptable_distance: None,
+            known_holders: None,
+        }
+    }
+
+    /// Keep track of the peers that notified us of holding the keys, to expect them to return the records when
+    /// fetched, see [`Self::known_holders`].
+    pub(crate) fn track_holders(&mut self) {
+        if self.known_holders.is_none() {
+            self.known_holders = Some(HashMap::new());
+        }
+    }
+
+    pub(crate) fn tracks_holders(&self) -> bool {
+        self.known_holders.is_some()
+    }
+
+    /// The peers expected to hold the key, as they notified us of it recently.
+    pub(crate) fn known_holders(&self, key: &RecordKey) -> HashSet<PeerId> {
+        let now = Instant::now();
+        self.known_holders
+            .as_ref()
+            .and_then(|known_holders| known_holders.get(key))
+            .map(|holders| {
+                holders
+                    .iter()
+                    .filter(|(_, time_out)| **time_out > now)
+                    .map(|(holder, _)| *holder)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forget about the keys held by the peer, e.g. once removed from the routing table.
+    pub(crate) fn remove_holder(&mut self, holder: &PeerId) {
+        if let Some(known_holders) = self.known_holders.as_mut() {
+            known_holders.retain(|_, holders| {
+                let _ = holders.remove(holder);
+                !holders.is_empty()
+            });
+        }
+    }
+
+    fn record_holder(&mut self, holder: PeerId, keys: &[(NetworkAddress, RecordType)]) {
+        let Some(known_holders) = self.known_holders.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        known_holders.retain(|_, holders| {
+            holders.retain(|_, time_out| *time_out > now);
+            !holders.is_empty()
+        });
+
+        let self_address = NetworkAddress::from_peer(self.self_peer_id);
+        for (addr, _record_type) in keys {
+            if let Some(ref distance_range) = self.distance_range {
+                if convert_distance_to_u256(&self_address.distance(addr)) > *distance_range {
+                    continue;
+                }
+            }
+            let _ = known_holders
+                .entry(addr.to_record_key())
+                .or_default()
+                .insert(holder, now + HOLDER_TIMEOUT);
         }
     }
 
@@ -83,6 +195,7 @@ impl ReplicationFetcher {
         // Pre-calculate self_address since it's used multiple times
         let self_address = NetworkAddress::from_peer(self.self_peer_id);
         let total_incoming_keys = incoming_keys.len();
+        self.record_holder(holder, &incoming_keys);
 
         // Avoid multiple allocations by using with_capacity
         let mut new_incoming_keys = Vec::with_capacity(incoming_keys.len());
@@ -167,6 +280,44 @@ impl ReplicationFetcher {
         keys_to_fetch
     }
 
+    /// The keys pending or being fetched, with their holders, to carry on with after a restart.
+    pub(crate) fn pending_keys(&self) -> Vec<(PeerId, NetworkAddress, RecordType)> {
+        self.to_be_fetched
+            .keys()
+            .map(|(key, record_type, holder)| (*holder, key, record_type))
+            .chain(
+                self.on_going_fetches
+                    .iter()
+                    .map(|((key, record_type), (holder, _))| (*holder, key, record_type)),
+            )
+            .map(|(holder, key, record_type)| {
+                (
+                    holder,
+                    NetworkAddress::from_record_key(key),
+                    record_type.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Queue the keys that were pending before a restart again, except the ones stored since.
+    pub(crate) fn restore_pending_keys(
+        &mut self,
+        keys: Vec<(PeerId, NetworkAddress, RecordType)>,
+        locally_stored_keys: &HashMap<RecordKey, (NetworkAddress, RecordType)>,
+    ) {
+        for (holder, addr, record_type) in keys {
+            let key = addr.to_record_key();
+            if locally_stored_keys.contains_key(&key) {
+                continue;
+            }
+            let _ = self
+                .to_be_fetched
+                .entry((key, record_type, holder))
+                .or_insert(Instant::now() + PENDING_TIMEOUT);
+        }
+    }
+
     // Node is full, any fetch (ongoing or new) shall no farther than the current farthest.
     pub(crate) fn set_farthest_on_full(&mut self, farthest_in: Option<RecordKey>) {
         let self_addr = NetworkAddress::from_peer(self.self_peer_id);
@@ -243,9 +394,40 @@ impl ReplicationFetcher {
         self.next_keys_to_fetch()
     }
 
+    /// The keys waiting to be fetched, overall and per priority, the number of ongoing fetches,
+    /// and how long the oldest key has been waiting for.
+    pub(crate) fn queue_status(&self) -> ReplicationQueueStatus {
+        let pending_keys: HashSet<_> = self
+            .to_be_fetched
+            .keys()
+            .map(|(key, record_type, _holder)| (key, record_type))
+            .collect();
+        let mut pending_keys_per_priority = HashMap::new();
+        for (_key, record_type) in pending_keys.iter() {
+            *pending_keys_per_priority
+                .entry(FetchPriority::from(*record_type))
+                .or_default() += 1;
+        }
+        let now = Instant::now();
+        let oldest_pending = self
+            .to_be_fetched
+            .values()
+            .min()
+            .map(|timeout| PENDING_TIMEOUT.saturating_sub(timeout.saturating_duration_since(now)))
+            .unwrap_or_default();
+        ReplicationQueueStatus {
+            pending_keys: pending_keys.len(),
+            pending_keys_per_priority,
+            ongoing_fetches: self.on_going_fetches.len(),
+            oldest_pending,
+        }
+    }
+
     // Returns the set of keys that has to be fetched from the peer/network.
     // Target must not be under-fetching
-    // and no more than MAX_PARALLEL_FETCH fetches to be undertaken at the same time.
+    // and no more than MAX_PARALLEL_FETCH fetches to be undertaken at the same time,
+    // MAX_PARALLEL_FETCH_PER_PEER of them from the same holder.
+    // The mutable records are fetched first, then the closest ones to us.
     pub(crate) fn next_keys_to_fetch(&mut self) -> Vec<(PeerId, RecordKey)> {
         self.prune_expired_keys_and_slow_nodes();
 
@@ -271,26 +453,33 @@ impl ReplicationFetcher {
         let remaining_capacity = MAX_PARALLEL_FETCH - self.on_going_fetches.len();
         let mut data_to_fetch = Vec::with_capacity(remaining_capacity);
 
-        // Sort to_be_fetched by key closeness to our PeerId
+        // Sort to_be_fetched by priority, then by key closeness to our PeerId
         let mut to_be_fetched_sorted: Vec<_> = self.to_be_fetched.iter_mut().collect();
 
         let self_address = NetworkAddress::from_peer(self.self_peer_id);
 
-        to_be_fetched_sorted.sort_by(|((a, _, _), _), ((b, _, _), _)| {
-            let a = NetworkAddress::from_record_key(a);
-            let b = NetworkAddress::from_record_key(b);
-            self_address.distance(&a).cmp(&self_address.distance(&b))
+        to_be_fetched_sorted.sort_by_cached_key(|((key, t, _), _)| {
+            let addr = NetworkAddress::from_record_key(key);
+            (FetchPriority::from(t), self_address.distance(&addr))
         });
 
+        let mut fetches_per_peer: HashMap<PeerId, usize> = HashMap::new();
+        for (holder, _time_out) in self.on_going_fetches.values() {
+            *fetches_per_peer.entry(*holder).or_default() += 1;
+        }
+
         for ((key, t, holder), _) in to_be_fetched_sorted {
             // Already carried out expiration pruning above.
             // Hence here only need to check whether is ongoing fetching.
             // Also avoid fetching same record from different nodes.
+            let holder_fetches = fetches_per_peer.entry(*holder).or_default();
             if self.on_going_fetches.len() < MAX_PARALLEL_FETCH
+                && *holder_fetches < MAX_PARALLEL_FETCH_PER_PEER
                 && !self
                     .on_going_fetches
                     .contains_key(&(key.clone(), t.clone()))
             {
+                *holder_fetches += 1;
                 data_to_fetch.push((*holder, key.clone(), t.clone()));
                 let _ = self.on_going_fetches.insert(
                     (key.clone(), t.clone()),
@@ -411,7 +600,9 @@ impl ReplicationFetcher {
 
 #[cfg(test)]
 mod tests {
-    use super::{ReplicationFetcher, FETCH_TIMEOUT, MAX_PARALLEL_FETCH};
+    use super::{
+        ReplicationFetcher, FETCH_TIMEOUT, MAX_PARALLEL_FETCH, MAX_PARALLEL_FETCH_PER_PEER,
+    };
     use ant_protocol::{convert_distance_to_u256, storage::RecordType, NetworkAddress};
     use eyre::Result;
     use libp2p::{kad::RecordKey, PeerId};
@@ -426,15 +617,22 @@ mod tests {
         let mut replication_fetcher = ReplicationFetcher::new(peer_id, event_sender);
         let locally_stored_keys = HashMap::new();
 
-        let mut incoming_keys = Vec::new();
-        (0..MAX_PARALLEL_FETCH * 2).for_each(|_| {
-            let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
-            let key = NetworkAddress::from_record_key(&RecordKey::from(random_data));
-            incoming_keys.push((key, RecordType::Chunk));
-        });
-
-        let keys_to_fetch =
-            replication_fetcher.add_keys(PeerId::random(), incoming_keys, &locally_stored_keys);
+        // Enough holders for the fetches per peer not to be the limit
+        let holders = MAX_PARALLEL_FETCH / MAX_PARALLEL_FETCH_PER_PEER;
+        let mut keys_to_fetch = vec![];
+        for _ in 0..holders {
+            let mut incoming_keys = Vec::new();
+            (0..MAX_PARALLEL_FETCH * 2 / holders).for_each(|_| {
+                let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+                let key = NetworkAddress::from_record_key(&RecordKey::from(random_data));
+                incoming_keys.push((key, RecordType::Chunk));
+            });
+            keys_to_fetch.extend(replication_fetcher.add_keys(
+                PeerId::random(),
+                incoming_keys,
+                &locally_stored_keys,
+            ));
+        }
         assert_eq!(keys_to_fetch.len(), MAX_PARALLEL_FETCH);
 
         // we should not fetch anymore keys
@@ -472,6 +670,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn mutable_records_are_fetched_first_within_the_limit_per_peer() {
+        let (event_sender, _event_receiver) = mpsc::channel(4);
+        let mut replication_fetcher = ReplicationFetcher::new(PeerId::random(), event_sender);
+
+        let random_key = || {
+            let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+            NetworkAddress::from_record_key(&RecordKey::from(random_data))
+        };
+        let mut incoming_keys: Vec<_> = (0..MAX_PARALLEL_FETCH_PER_PEER * 2)
+            .map(|_| (random_key(), RecordType::Chunk))
+            .collect();
+        let register = random_key();
+        let scratchpad = random_key();
+        incoming_keys.push((
+            register.clone(),
+            RecordType::NonChunk(xor_name::XorName::random(&mut rand::thread_rng())),
+        ));
+        incoming_keys.push((scratchpad.clone(), RecordType::Scratchpad));
+
+        let holder = PeerId::random();
+        let keys_to_fetch =
+            replication_fetcher.add_keys(holder, incoming_keys, &Default::default());
+        assert_eq!(keys_to_fetch.len(), MAX_PARALLEL_FETCH_PER_PEER);
+        assert!(keys_to_fetch.contains(&(holder, register.to_record_key())));
+        assert!(keys_to_fetch.contains(&(holder, scratchpad.to_record_key())));
+
+        // The holder is at its limit, the other holders are still fetched from
+        let other_holder = PeerId::random();
+        let keys_to_fetch = replication_fetcher.add_keys(
+            other_holder,
+            vec![
+                (random_key(), RecordType::Chunk),
+                (random_key(), RecordType::Chunk),
+            ],
+            &Default::default(),
+        );
+        assert_eq!(keys_to_fetch.len(), 2);
+        assert!(keys_to_fetch
+            .iter()
+            .all(|(peer_id, _key)| *peer_id == other_holder));
+    }
+
     #[test]
     fn verify_in_range_check() {
         //random peer_id
@@ -512,4 +753,44 @@ mod tests {
             "all keys should be in range and in the fetcher"
         );
     }
+
+    #[test]
+    fn holders_are_known_until_removed() {
+        let peer_id = PeerId::random();
+        let (event_sender, _event_receiver) = mpsc::channel(4);
+        let mut replication_fetcher = ReplicationFetcher::new(peer_id, event_sender);
+
+        let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+        let record_key = RecordKey::from(random_data);
+        let key = NetworkAddress::from_record_key(&record_key);
+        let holder = PeerId::random();
+
+        // Not tracked until enabled
+        let _ = replication_fetcher.add_keys(
+            holder,
+            vec![(key.clone(), RecordType::Chunk)],
+            &Default::default(),
+        );
+        assert!(replication_fetcher.known_holders(&record_key).is_empty());
+
+        replication_fetcher.track_holders();
+        let other_holder = PeerId::random();
+        for holder in [holder, other_holder] {
+            let _ = replication_fetcher.add_keys(
+                holder,
+                vec![(key.clone(), RecordType::Chunk)],
+                &Default::default(),
+            );
+        }
+        assert_eq!(
+            replication_fetcher.known_holders(&record_key),
+            [holder, other_holder].into_iter().collect()
+        );
+
+        replication_fetcher.remove_holder(&holder);
+        assert_eq!(
+            replication_fetcher.known_holders(&record_key),
+            [other_holder].into_iter().collect()
+        );
+    }
 }
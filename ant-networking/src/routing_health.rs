@@ -0,0 +1,150 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{event::NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE};
+use std::time::Duration;
+
+/// The interval at which the health of the routing table is checked.
+pub(crate) const ROUTING_TABLE_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The routing table is degraded below this many peers in it.
+const MIN_PEERS_IN_RT: usize = 2 * CLOSE_GROUP_SIZE;
+
+/// The routing table is degraded below this many non-empty kbuckets, i.e. when we only know peers of a narrow part
+/// of the address space.
+const MIN_NON_EMPTY_KBUCKETS: usize = 2;
+
+/// The routing table is degraded below this many peers we're connected to.
+const MIN_CONNECTED_PEERS: usize = CLOSE_GROUP_SIZE;
+
+/// The max number of the bootstrap cache addrs dialed on each re-bootstrap, the most reliable ones first.
+const MAX_REBOOTSTRAP_DIALS: usize = 30;
+
+/// A snapshot of the routing table, as taken by the periodic health check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutingTableHealth {
+    /// The number of peers in the routing table.
+    pub peers_in_rt: usize,
+    /// The number of kbuckets holding at least one peer.
+    pub non_empty_kbuckets: usize,
+    /// The number of peers we currently have a connection to.
+    pub connected_peers: usize,
+}
+
+impl RoutingTableHealth {
+    /// Whether the routing table is too sparse or too few of its peers are reachable for us to be well connected.
+    pub fn is_degraded(&self) -> bool {
+        self.peers_in_rt < MIN_PEERS_IN_RT
+            || self.non_empty_kbuckets < MIN_NON_EMPTY_KBUCKETS
+            || self.connected_peers < MIN_CONNECTED_PEERS
+    }
+}
+
+impl SwarmDriver {
+    fn routing_table_health(&mut self) -> RoutingTableHealth {
+        let mut peers_in_rt = 0;
+        let mut non_empty_kbuckets = 0;
+        for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
+            let num_entries = kbucket.num_entries();
+            peers_in_rt += num_entries;
+            if num_entries > 0 {
+                non_empty_kbuckets += 1;
+            }
+        }
+        RoutingTableHealth {
+            peers_in_rt,
+            non_empty_kbuckets,
+            connected_peers: self.swarm.connected_peers().count(),
+        }
+    }
+
+    /// Check the health of the routing table, re-bootstrapping while it's degraded.
+    ///
+    /// A `NetworkEvent::ConnectivityDegraded` is emitted once it becomes degraded, and a
+    /// `NetworkEvent::ConnectivityRestored` once it recovers.
+    pub(crate) fn check_routing_table_health(&mut self) {
+        let health = self.routing_table_health();
+        let degraded = health.is_degraded();
+
+        if degraded {
+            warn!("The routing table is degraded, re-bootstrapping: {health:?}");
+            self.rebootstrap();
+        } else {
+            trace!("The routing table is healthy: {health:?}");
+        }
+
+        if degraded != self.routing_table_degraded {
+            self.routing_table_degraded = degraded;
+            if degraded {
+                self.send_event(NetworkEvent::ConnectivityDegraded(health));
+            } else {
+                info!("The routing table recovered: {health:?}");
+                self.send_event(NetworkEvent::ConnectivityRestored(health));
+            }
+        }
+    }
+
+    /// Dial the most reliable peers of the bootstrap cache, then look up our surroundings again.
+    fn rebootstrap(&mut self) {
+        let addrs = self
+            .bootstrap_cache
+            .as_ref()
+            .map(|cache| {
+                cache
+                    .get_sorted_addrs()
+                    .take(MAX_REBOOTSTRAP_DIALS)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        debug!("Re-bootstrapping from {} cached addrs", addrs.len());
+        for addr in addrs {
+            if let Err(err) = self.dial(addr.clone()) {
+                debug!("Failed to dial the cached addr {addr:?} to re-bootstrap: {err}");
+            }
+        }
+
+        if let Err(err) = self.swarm.behaviour_mut().kademlia.bootstrap() {
+            debug!("Could not run a kad bootstrap to re-bootstrap: {err:?}");
+        }
+        self.trigger_network_discovery();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routing_table_is_degraded_below_any_threshold() {
+        let healthy = RoutingTableHealth {
+            peers_in_rt: MIN_PEERS_IN_RT,
+            non_empty_kbuckets: MIN_NON_EMPTY_KBUCKETS,
+            connected_peers: MIN_CONNECTED_PEERS,
+        };
+        assert!(!healthy.is_degraded());
+
+        let sparse = RoutingTableHealth {
+            peers_in_rt: MIN_PEERS_IN_RT - 1,
+            ..healthy
+        };
+        assert!(sparse.is_degraded());
+
+        let narrow = RoutingTableHealth {
+            non_empty_kbuckets: MIN_NON_EMPTY_KBUCKETS - 1,
+            ..healthy
+        };
+        assert!(narrow.is_degraded());
+
+        let unreachable = RoutingTableHealth {
+            connected_peers: MIN_CONNECTED_PEERS - 1,
+            ..healthy
+        };
+        assert!(unreachable.is_degraded());
+    }
+}
@@ -0,0 +1,605 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! An in-process network of `SwarmDriver`s over an in-memory transport, with controllable latency, loss and
+//! churn, to test the behaviours of the network in CI rather than only on live testnets.
+
+use crate::{
+    driver::NetworkBuilder,
+    error::{NetworkError, Result},
+    Network, NetworkEvent, SwarmDriverState,
+};
+use libp2p::{
+    core::{
+        muxing::StreamMuxerBox,
+        transport::{self, MemoryTransport},
+        upgrade,
+    },
+    futures::{AsyncRead, AsyncWrite},
+    identity::Keypair,
+    multiaddr::Protocol,
+    noise, yamux, Multiaddr, PeerId, Transport as _,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    future::Future,
+    io,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::mpsc, task::JoinHandle, time::Sleep};
+
+/// The memory transport ports are global to the process, so each simulated node gets its own, even across the
+/// simulations of tests running in parallel.
+static NEXT_MEMORY_PORT: AtomicU64 = AtomicU64::new(1 << 40);
+
+/// How often the simulation checks on the state of its nodes when waiting for them.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct LinksState {
+    latency: Duration,
+    loss: f64,
+    rng: StdRng,
+}
+
+/// The links between the simulated nodes, shared by all of them.
+///
+/// Every read on a connection waits out the latency before seeing the data, and every dial is dropped with the
+/// loss probability. The drops are drawn from a seeded rng, so a simulation replays the same losses for the same
+/// order of dials.
+#[derive(Debug, Clone)]
+pub struct SimulatedLinks {
+    state: Arc<Mutex<LinksState>>,
+}
+
+impl SimulatedLinks {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LinksState {
+                latency: Duration::ZERO,
+                loss: 0.0,
+                rng: StdRng::seed_from_u64(seed),
+            })),
+        }
+    }
+
+    /// Set the one way latency of all the links.
+    pub fn set_latency(&self, latency: Duration) {
+        if let Ok(mut state) = self.state.lock() {
+            state.latency = latency;
+        }
+    }
+
+    /// Set the probability, between 0 and 1, of a dial to be dropped.
+    pub fn set_loss(&self, loss: f64) {
+        if let Ok(mut state) = self.state.lock() {
+            state.loss = loss.clamp(0.0, 1.0);
+        }
+    }
+
+    fn latency(&self) -> Duration {
+        self.state
+            .lock()
+            .map(|state| state.latency)
+            .unwrap_or_default()
+    }
+
+    fn drops_dial(&self) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let loss = state.loss;
+        loss > 0.0 && state.rng.gen_bool(loss)
+    }
+
+    /// The memory transport through these links, secured and multiplexed like the real ones.
+    pub(crate) fn build_transport(
+        &self,
+        keypair: &Keypair,
+    ) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+        let links = self.clone();
+        MemoryTransport::default()
+            .and_then(move |channel, endpoint| {
+                let links = links.clone();
+                async move {
+                    if endpoint.is_dialer() && links.drops_dial() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::ConnectionRefused,
+                            "Dial dropped by the simulated link",
+                        ));
+                    }
+                    Ok(DelayedStream::new(channel, links))
+                }
+            })
+            .upgrade(upgrade::Version::V1)
+            .authenticate(
+                noise::Config::new(keypair)
+                    .expect("Signing libp2p-noise static DH keypair failed."),
+            )
+            .multiplex(yamux::Config::default())
+            .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+            .boxed()
+    }
+}
+
+/// A connection whose reads only see the data once the latency of the links has passed since it arrived.
+struct DelayedStream<S> {
+    inner: S,
+    links: SimulatedLinks,
+    /// The data read from the inner stream, waiting out the latency when `delay` is set.
+    buffer: Vec<u8>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> DelayedStream<S> {
+    fn new(inner: S, links: SimulatedLinks) -> Self {
+        Self {
+            inner,
+            links,
+            buffer: Vec::new(),
+            delay: None,
+        }
+    }
+
+    fn drain_into(&mut self, buf: &mut [u8]) -> usize {
+        let len = usize::min(buf.len(), self.buffer.len());
+        buf[..len].copy_from_slice(&self.buffer[..len]);
+        let _ = self.buffer.drain(..len);
+        len
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for DelayedStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        if this.buffer.is_empty() {
+            let mut read = vec![0; 64 * 1024];
+            let len = match Pin::new(&mut this.inner).poll_read(cx, &mut read) {
+                Poll::Ready(Ok(len)) => len,
+                other => return other,
+            };
+            if len == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            this.buffer.extend_from_slice(&read[..len]);
+
+            let latency = this.links.latency();
+            if !latency.is_zero() {
+                this.delay = Some(Box::pin(tokio::time::sleep(latency)));
+            }
+        }
+
+        if let Some(delay) = this.delay.as_mut() {
+            if delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            this.delay = None;
+        }
+        Poll::Ready(Ok(this.drain_into(buf)))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for DelayedStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// What happens to a node at a step of a churn schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChurnKind {
+    /// The node is stopped, dropping all of its connections.
+    Leave,
+    /// The node is started again, with the same identity and records on a new address, and dials the running nodes.
+    Rejoin,
+}
+
+/// A step of a churn schedule, applied once the given time has passed since the previous step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChurnEvent {
+    pub after: Duration,
+    pub node: usize,
+    pub kind: ChurnKind,
+}
+
+/// A node of the simulation.
+pub struct SimulatedNode {
+    keypair: Keypair,
+    port: u64,
+    root_dir: PathBuf,
+    /// The handle to the node, replaced each time the node rejoins.
+    pub network: Network,
+    /// The events of the node, replaced each time the node rejoins. Left unread, they only pile up in memory.
+    pub events: mpsc::Receiver<NetworkEvent>,
+    driver: JoinHandle<()>,
+    running: bool,
+}
+
+impl SimulatedNode {
+    pub fn peer_id(&self) -> PeerId {
+        PeerId::from(self.keypair.public())
+    }
+
+    /// The address the other nodes dial the node on, a new one each time the node rejoins.
+    pub fn addr(&self) -> Multiaddr {
+        Multiaddr::empty()
+            .with(Protocol::Memory(self.port))
+            .with(Protocol::P2p(self.peer_id()))
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/// A network of `SwarmDriver`s running in this process.
+pub struct SimulatedNetwork {
+    links: SimulatedLinks,
+    nodes: Vec<SimulatedNode>,
+    root_dir: PathBuf,
+}
+
+impl SimulatedNetwork {
+    /// Start the given number of nodes, each dialing the ones started before it. The identities of the nodes,
+    /// and the losses of the links, are derived from the seed.
+    pub async fn start(node_count: usize, seed: u64) -> Result<Self> {
+        let links = SimulatedLinks::new(seed);
+        let root_dir = std::env::temp_dir().join(format!(
+            "ant-networking-simulation-{}-{}",
+            std::process::id(),
+            NEXT_MEMORY_PORT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut simulation = Self {
+            links,
+            nodes: Vec::with_capacity(node_count),
+            root_dir,
+        };
+        for index in 0..node_count {
+            let mut secret = [0; 32];
+            secret[..8].copy_from_slice(&seed.to_le_bytes());
+            secret[8..16].copy_from_slice(&(index as u64).to_le_bytes());
+            let keypair = Keypair::ed25519_from_bytes(secret)
+                .map_err(|err| NetworkError::BehaviourErr(err.to_string()))?;
+            let port = NEXT_MEMORY_PORT.fetch_add(1, Ordering::Relaxed);
+            let root_dir = simulation.root_dir.join(format!("node-{index}"));
+
+            let (network, events, driver) =
+                simulation.spawn_driver(keypair.clone(), port, root_dir.clone())?;
+            simulation.nodes.push(SimulatedNode {
+                keypair,
+                port,
+                root_dir,
+                network,
+                events,
+                driver,
+                running: true,
+            });
+            simulation.dial_running_nodes(index).await?;
+        }
+        Ok(simulation)
+    }
+
+    fn spawn_driver(
+        &self,
+        keypair: Keypair,
+        port: u64,
+        root_dir: PathBuf,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, JoinHandle<()>)> {
+        std::fs::create_dir_all(&root_dir)?;
+        let mut builder = NetworkBuilder::new(keypair, true);
+        builder.simulated(self.links.clone(), port);
+        let (network, events, swarm_driver) = builder.build_node(root_dir)?;
+        let driver = tokio::spawn(swarm_driver.run());
+        Ok((network, events, driver))
+    }
+
+    async fn dial_running_nodes(&self, index: usize) -> Result<()> {
+        let addrs: Vec<_> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(other, node)| *other != index && node.is_running())
+            .map(|(_, node)| node.addr())
+            .collect();
+        for addr in addrs {
+            if let Err(err) = self.nodes[index].network.dial(addr.clone()).await {
+                debug!("Simulated node {index} failed to dial {addr:?}: {err:?}");
+            }
+        }
+        Ok(())
+    }
+
+    /// The links between the nodes, to change their latency and loss.
+    pub fn links(&self) -> &SimulatedLinks {
+        &self.links
+    }
+
+    pub fn nodes(&self) -> &[SimulatedNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &SimulatedNode {
+        &self.nodes[index]
+    }
+
+    pub fn node_mut(&mut self, index: usize) -> &mut SimulatedNode {
+        &mut self.nodes[index]
+    }
+
+    /// Stop the node, dropping its connections. Its records stay on disk for it to rejoin with.
+    pub fn leave(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        if node.running {
+            info!("Simulated node {index} leaves the network");
+            node.driver.abort();
+            node.running = false;
+        }
+    }
+
+    /// Start the node again, with the same identity and records on a new address, dialing the running nodes.
+    pub async fn rejoin(&mut self, index: usize) -> Result<()> {
+        if self.nodes[index].is_running() {
+            return Ok(());
+        }
+        info!("Simulated node {index} rejoins the network");
+
+        // Let the aborted driver be dropped before its records are opened again. Its memory port stays taken,
+        // the memory transport only releases the ports of the listeners removed from it.
+        let node = &mut self.nodes[index];
+        let _ = (&mut node.driver).await;
+        let port = NEXT_MEMORY_PORT.fetch_add(1, Ordering::Relaxed);
+        let (keypair, root_dir) = (node.keypair.clone(), node.root_dir.clone());
+        let (network, events, driver) = self.spawn_driver(keypair, port, root_dir)?;
+
+        let node = &mut self.nodes[index];
+        node.port = port;
+        node.network = network;
+        node.events = events;
+        node.driver = driver;
+        node.running = true;
+        self.dial_running_nodes(index).await
+    }
+
+    /// Apply the steps of the churn schedule in order.
+    pub async fn run_churn(&mut self, schedule: &[ChurnEvent]) -> Result<()> {
+        for event in schedule {
+            tokio::time::sleep(event.after).await;
+            match event.kind {
+                ChurnKind::Leave => self.leave(event.node),
+                ChurnKind::Rejoin => self.rejoin(event.node).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The state of each running node.
+    pub async fn driver_states(&self) -> Result<Vec<Option<SwarmDriverState>>> {
+        let mut states = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            if node.is_running() {
+                states.push(Some(node.network.get_driver_state().await?));
+            } else {
+                states.push(None);
+            }
+        }
+        Ok(states)
+    }
+
+    /// Wait until each running node has at least the given number of peers in its routing table, failing after
+    /// the timeout.
+    pub async fn wait_for_routing_tables(&self, min_peers: usize, timeout: Duration) -> Result<()> {
+        let wait = async {
+            loop {
+                let states = self.driver_states().await?;
+                if states
+                    .iter()
+                    .flatten()
+                    .all(|state| state.peers_in_routing_table() >= min_peers)
+                {
+                    return Ok(());
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.map_err(|_| {
+            NetworkError::BehaviourErr(format!(
+                "No {min_peers} peers in the routing tables after {timeout:?}"
+            ))
+        })?
+    }
+}
+
+impl Drop for SimulatedNetwork {
+    fn drop(&mut self) {
+        for index in 0..self.nodes.len() {
+            self.leave(index);
+        }
+        let _ = std::fs::remove_dir_all(&self.root_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GetRecordCfg, RecordSource};
+    use ant_protocol::{
+        storage::{try_serialize_record, Chunk, RecordKind},
+        NetworkAddress,
+    };
+    use bytes::Bytes;
+    use libp2p::kad::{Quorum, Record};
+
+    #[test]
+    fn dial_losses_replay_for_the_same_seed() {
+        let draw = |seed| {
+            let links = SimulatedLinks::new(seed);
+            links.set_loss(0.5);
+            (0..64).map(|_| links.drops_dial()).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(7), draw(7));
+        assert_ne!(draw(7), draw(8));
+
+        let links = SimulatedLinks::new(7);
+        assert!((0..64).all(|_| !links.drops_dial()));
+        links.set_loss(1.0);
+        assert!((0..64).all(|_| links.drops_dial()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn records_are_fetched_through_the_simulated_network() -> eyre::Result<()> {
+        let simulation = SimulatedNetwork::start(4, 1).await?;
+        simulation
+            .wait_for_routing_tables(3, Duration::from_secs(30))
+            .await?;
+        simulation.links().set_latency(Duration::from_millis(20));
+
+        let chunk = Chunk::new(Bytes::from_static(b"simulated chunk"));
+        let record = Record {
+            key: NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            value: try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        simulation
+            .node(0)
+            .network
+            .put_local_record(record.clone(), RecordSource::Put(None));
+
+        let cfg = GetRecordCfg {
+            get_quorum: Quorum::One,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: Default::default(),
+            is_register: false,
+        };
+        let fetched = simulation
+            .node(3)
+            .network
+            .get_record_from_network(record.key.clone(), &cfg)
+            .await?;
+        assert_eq!(fetched.record.value, record.value);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pruning_records_does_not_count_as_write_errors() -> eyre::Result<()> {
+        let mut simulation = SimulatedNetwork::start(1, 3).await?;
+        let keys: Vec<_> = (0..8u8)
+            .map(|index| -> eyre::Result<_> {
+                let chunk = Chunk::new(Bytes::from(vec![index; 32]));
+                let record = Record {
+                    key: NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+                    value: try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+                    publisher: None,
+                    expires: None,
+                };
+                simulation
+                    .node(0)
+                    .network
+                    .put_local_record(record.clone(), RecordSource::Put(None));
+                Ok(record.key)
+            })
+            .collect::<eyre::Result<_>>()?;
+        for key in &keys {
+            while !simulation
+                .node(0)
+                .network
+                .is_record_key_present_locally(key)
+                .await?
+            {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+
+        let terminated = |simulation: &mut SimulatedNetwork| {
+            let events = &mut simulation.node_mut(0).events;
+            std::iter::from_fn(|| events.try_recv().ok())
+                .any(|event| matches!(event, NetworkEvent::TerminateNode { .. }))
+        };
+
+        for key in &keys {
+            simulation.node(0).network.prune_local_record(key.clone());
+        }
+        for key in &keys {
+            assert!(
+                !simulation
+                    .node(0)
+                    .network
+                    .is_record_key_present_locally(key)
+                    .await?
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!terminated(&mut simulation));
+
+        // Whereas as many failed writes do terminate the node.
+        for key in &keys {
+            simulation.node(0).network.remove_local_record(key.clone());
+        }
+        let _ = simulation.node(0).network.get_driver_state().await?;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(terminated(&mut simulation));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn nodes_rejoin_with_the_same_identity() -> eyre::Result<()> {
+        let mut simulation = SimulatedNetwork::start(3, 2).await?;
+        simulation
+            .wait_for_routing_tables(2, Duration::from_secs(30))
+            .await?;
+        let peer_id = simulation.node(2).peer_id();
+
+        simulation
+            .run_churn(&[
+                ChurnEvent {
+                    after: Duration::ZERO,
+                    node: 2,
+                    kind: ChurnKind::Leave,
+                },
+                ChurnEvent {
+                    after: Duration::from_millis(500),
+                    node: 2,
+                    kind: ChurnKind::Rejoin,
+                },
+            ])
+            .await?;
+
+        assert!(simulation.node(2).is_running());
+        assert_eq!(simulation.node(2).peer_id(), peer_id);
+        simulation
+            .wait_for_routing_tables(2, Duration::from_secs(30))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    event::NetworkEvent,
+    multiaddr_get_ip,
+    target_arch::{Duration, Instant},
+    SwarmDriver, CLOSE_GROUP_SIZE,
+};
+use ant_protocol::NetworkAddress;
+use libp2p::{Multiaddr, PeerId};
+#[cfg(feature = "open-metrics")]
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use std::{
+    collections::{HashSet, VecDeque},
+    net::IpAddr,
+};
+
+/// The prefix length of the IPv4 subnets the peers are grouped by, e.g. the hosts of a same provider.
+const IPV4_SUBNET_PREFIX: u32 = 24;
+/// The prefix length of the IPv6 subnets the peers are grouped by, what a site is usually allocated.
+const IPV6_SUBNET_PREFIX: u32 = 48;
+
+/// Heuristics against the peers trying to take over a part of the address space, `None` disabling a check.
+///
+/// A kbucket is kept from holding too many peers of a same IP or subnet, the peers over the limit not being added
+/// to the routing table. The close groups of our address and of the watched ones are also monitored, too many new
+/// peers joining one of them within the burst window being flagged, as a sybil attack would show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SybilGuardConfig {
+    /// The maximum number of peers of a same IP in a kbucket.
+    pub max_peers_per_ip_per_kbucket: Option<usize>,
+    /// The maximum number of peers of a same /24 IPv4 or /48 IPv6 subnet in a kbucket.
+    pub max_peers_per_subnet_per_kbucket: Option<usize>,
+    /// The number of new peers joining a close group within the burst window for it to be flagged.
+    pub close_group_burst: Option<usize>,
+    pub burst_window: Duration,
+    /// The addresses to monitor the close groups of, besides ours.
+    pub watched_addresses: Vec<NetworkAddress>,
+}
+
+impl Default for SybilGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_peers_per_ip_per_kbucket: None,
+            max_peers_per_subnet_per_kbucket: None,
+            close_group_burst: None,
+            burst_window: Duration::from_secs(10 * 60),
+            watched_addresses: vec![],
+        }
+    }
+}
+
+/// Something suspicious the [`SybilGuardConfig`] heuristics spotted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SybilSuspicion {
+    /// The peer was not added to its kbucket, holding too many peers of its IP or subnet already.
+    CrowdedKBucket {
+        peer: PeerId,
+        ilog2: Option<u32>,
+        ip: IpAddr,
+        /// Whether the subnet limit was the one hit, rather than the IP one.
+        subnet: bool,
+    },
+    /// The peers that joined the close group of the address within the burst window.
+    CloseGroupBurst {
+        target: NetworkAddress,
+        peers: Vec<PeerId>,
+    },
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+pub(crate) enum SybilSuspicionKind {
+    CrowdedIp,
+    CrowdedSubnet,
+    CloseGroupBurst,
+}
+
+#[cfg(feature = "open-metrics")]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub(crate) struct SybilSuspicionLabels {
+    kind: SybilSuspicionKind,
+}
+
+#[cfg(feature = "open-metrics")]
+impl From<&SybilSuspicion> for SybilSuspicionLabels {
+    fn from(suspicion: &SybilSuspicion) -> Self {
+        let kind = match suspicion {
+            SybilSuspicion::CrowdedKBucket { subnet: false, .. } => SybilSuspicionKind::CrowdedIp,
+            SybilSuspicion::CrowdedKBucket { subnet: true, .. } => {
+                SybilSuspicionKind::CrowdedSubnet
+            }
+            SybilSuspicion::CloseGroupBurst { .. } => SybilSuspicionKind::CloseGroupBurst,
+        };
+        Self { kind }
+    }
+}
+
+fn subnet(ip: &IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            IpAddr::V4((u32::from(*ip) & (u32::MAX << (32 - IPV4_SUBNET_PREFIX))).into())
+        }
+        IpAddr::V6(ip) => {
+            IpAddr::V6((u128::from(*ip) & (u128::MAX << (128 - IPV6_SUBNET_PREFIX))).into())
+        }
+    }
+}
+
+/// Applies the [`SybilGuardConfig`] heuristics, keeping track of the peers recently joining the watched close groups.
+#[derive(Debug)]
+pub(crate) struct SybilGuard {
+    config: SybilGuardConfig,
+    /// Ours first, then the watched ones.
+    targets: Vec<NetworkAddress>,
+    /// The peers recently joining the close group of each target, oldest first.
+    recent_joins: Vec<VecDeque<(PeerId, Instant)>>,
+}
+
+impl SybilGuard {
+    pub(crate) fn new(config: SybilGuardConfig, self_addr: NetworkAddress) -> Self {
+        let targets: Vec<_> = std::iter::once(self_addr)
+            .chain(config.watched_addresses.iter().cloned())
+            .collect();
+        Self {
+            recent_joins: vec![VecDeque::new(); targets.len()],
+            targets,
+            config,
+        }
+    }
+
+    pub(crate) fn limits_kbuckets(&self) -> bool {
+        self.config.max_peers_per_ip_per_kbucket.is_some()
+            || self.config.max_peers_per_subnet_per_kbucket.is_some()
+    }
+
+    pub(crate) fn monitors_close_groups(&self) -> bool {
+        self.config.close_group_burst.is_some()
+    }
+
+    pub(crate) fn targets(&self) -> &[NetworkAddress] {
+        &self.targets
+    }
+
+    /// The IP of the new peer over a limit for its kbucket, if any, along with whether it's the subnet limit.
+    ///
+    /// `bucket_ips` holds the IPs of each of the other peers of the kbucket.
+    pub(crate) fn crowded_ip(
+        &self,
+        peer_ips: &HashSet<IpAddr>,
+        bucket_ips: &[HashSet<IpAddr>],
+    ) -> Option<(IpAddr, bool)> {
+        for ip in peer_ips {
+            if let Some(limit) = self.config.max_peers_per_ip_per_kbucket {
+                let same_ip = bucket_ips.iter().filter(|ips| ips.contains(ip)).count();
+                if same_ip >= limit {
+                    return Some((*ip, false));
+                }
+            }
+            if let Some(limit) = self.config.max_peers_per_subnet_per_kbucket {
+                let ip_subnet = subnet(ip);
+                let same_subnet = bucket_ips
+                    .iter()
+                    .filter(|ips| ips.iter().any(|other| subnet(other) == ip_subnet))
+                    .count();
+                if same_subnet >= limit {
+                    return Some((*ip, true));
+                }
+            }
+        }
+        None
+    }
+
+    /// Track the peer joining the close groups of the targets of the given indexes, returning the bursts spotted.
+    ///
+    /// A close group flagged starts over, for a same burst not to be flagged again on each new peer.
+    pub(crate) fn on_close_group_joined(
+        &mut self,
+        peer: PeerId,
+        target_indexes: impl IntoIterator<Item = usize>,
+        now: Instant,
+    ) -> Vec<SybilSuspicion> {
+        let Some(burst) = self.config.close_group_burst else {
+            return vec![];
+        };
+        let mut suspicions = vec![];
+        for index in target_indexes {
+            let (Some(target), Some(joins)) =
+                (self.targets.get(index), self.recent_joins.get_mut(index))
+            else {
+                continue;
+            };
+            while joins
+                .front()
+                .is_some_and(|(_, joined)| now.duration_since(*joined) > self.config.burst_window)
+            {
+                let _ = joins.pop_front();
+            }
+            joins.push_back((peer, now));
+            if joins.len() >= burst {
+                suspicions.push(SybilSuspicion::CloseGroupBurst {
+                    target: target.clone(),
+                    peers: joins.drain(..).map(|(peer, _)| peer).collect(),
+                });
+            }
+        }
+        suspicions
+    }
+}
+
+impl SwarmDriver {
+    /// Whether the peer is to be kept out of the routing table, its kbucket holding too many peers of its IP or
+    /// subnet already.
+    ///
+    /// The relayed addresses are the relay's, hence not telling the peers apart, and the local peers all share a
+    /// same IP, so neither are checked.
+    pub(crate) fn is_crowding_kbucket(
+        &mut self,
+        peer_id: PeerId,
+        addrs: &HashSet<Multiaddr>,
+        has_relayed: bool,
+    ) -> bool {
+        if self.local || has_relayed || !self.sybil_guard.limits_kbuckets() {
+            return false;
+        }
+        let peer_ips: HashSet<_> = addrs.iter().filter_map(multiaddr_get_ip).collect();
+        let Some(kbucket) = self.swarm.behaviour_mut().kademlia.kbucket(peer_id) else {
+            return false;
+        };
+        let ilog2 = kbucket.range().0.ilog2();
+        let bucket_ips: Vec<HashSet<_>> = kbucket
+            .iter()
+            .filter(|entry| entry.node.key.preimage() != &peer_id)
+            .map(|entry| {
+                entry
+                    .node
+                    .value
+                    .iter()
+                    .filter_map(multiaddr_get_ip)
+                    .collect()
+            })
+            .collect();
+        let Some((ip, subnet)) = self.sybil_guard.crowded_ip(&peer_ips, &bucket_ips) else {
+            return false;
+        };
+
+        warn!("Not adding {peer_id:?} to the kbucket {ilog2:?}, it holds too many peers of the same {} as {ip}", if subnet { "subnet" } else { "IP" });
+        self.report_sybil_suspicion(SybilSuspicion::CrowdedKBucket {
+            peer: peer_id,
+            ilog2,
+            ip,
+            subnet,
+        });
+        true
+    }
+
+    /// Track the new peer of the routing table joining the close groups monitored, flagging the bursts.
+    pub(crate) fn check_close_group_bursts(&mut self, peer: PeerId) {
+        // The peers restored with the routing table joined long ago
+        if self.restored_peers.remove(&peer) || !self.sybil_guard.monitors_close_groups() {
+            return;
+        }
+        let kademlia = &mut self.swarm.behaviour_mut().kademlia;
+        let joined: Vec<_> = self
+            .sybil_guard
+            .targets()
+            .iter()
+            .enumerate()
+            .filter(|(_, target)| {
+                kademlia
+                    .get_closest_local_peers(&target.as_kbucket_key())
+                    .take(CLOSE_GROUP_SIZE)
+                    .any(|key| key.preimage() == &peer)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        if joined.is_empty() {
+            return;
+        }
+
+        let suspicions = self
+            .sybil_guard
+            .on_close_group_joined(peer, joined, Instant::now());
+        for suspicion in suspicions {
+            warn!("Too many new peers joined a close group within the burst window: {suspicion:?}");
+            self.report_sybil_suspicion(suspicion);
+        }
+    }
+
+    fn report_sybil_suspicion(&mut self, suspicion: SybilSuspicion) {
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics_recorder) = &self.metrics_recorder {
+            let _ = metrics_recorder
+                .sybil_suspicions
+                .get_or_create(&SybilSuspicionLabels::from(&suspicion))
+                .inc();
+        }
+        self.send_event(NetworkEvent::SybilSuspected(suspicion));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ips(ips: &[&str]) -> HashSet<IpAddr> {
+        ips.iter().map(|ip| ip.parse().expect("valid ip")).collect()
+    }
+
+    #[test]
+    fn kbuckets_and_close_groups_are_kept_from_being_crowded() {
+        let guard = SybilGuard::new(
+            SybilGuardConfig {
+                max_peers_per_ip_per_kbucket: Some(2),
+                max_peers_per_subnet_per_kbucket: Some(3),
+                ..Default::default()
+            },
+            NetworkAddress::from_peer(PeerId::random()),
+        );
+        let bucket = vec![
+            ips(&["1.2.3.4"]),
+            ips(&["1.2.3.4"]),
+            ips(&["1.2.3.5"]),
+            ips(&["2001:db8:1:2::1"]),
+        ];
+        assert_eq!(
+            guard.crowded_ip(&ips(&["1.2.3.4"]), &bucket),
+            Some(("1.2.3.4".parse().expect("valid ip"), false))
+        );
+        assert_eq!(
+            guard.crowded_ip(&ips(&["1.2.3.6"]), &bucket),
+            Some(("1.2.3.6".parse().expect("valid ip"), true))
+        );
+        assert_eq!(guard.crowded_ip(&ips(&["1.2.4.1"]), &bucket), None);
+        assert_eq!(guard.crowded_ip(&ips(&["2001:db8:1:3::1"]), &bucket), None);
+
+        let watched = NetworkAddress::from_peer(PeerId::random());
+        let window = Duration::from_secs(60);
+        let mut guard = SybilGuard::new(
+            SybilGuardConfig {
+                close_group_burst: Some(3),
+                burst_window: window,
+                watched_addresses: vec![watched.clone()],
+                ..Default::default()
+            },
+            NetworkAddress::from_peer(PeerId::random()),
+        );
+        let start = Instant::now();
+        let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+        // The peers joining slower than the burst window are churn.
+        assert!(guard.on_close_group_joined(peers[0], [1], start).is_empty());
+        assert!(guard
+            .on_close_group_joined(peers[1], [1], start + window * 2)
+            .is_empty());
+        assert!(guard
+            .on_close_group_joined(peers[2], [0, 1], start + window * 2)
+            .is_empty());
+        assert_eq!(
+            guard.on_close_group_joined(peers[3], [1], start + window * 2),
+            vec![SybilSuspicion::CloseGroupBurst {
+                target: watched,
+                peers: peers[1..].to_vec(),
+            }]
+        );
+        // The close group flagged starts over.
+        assert!(guard
+            .on_close_group_joined(peers[0], [1], start + window * 2)
+            .is_empty());
+    }
+}
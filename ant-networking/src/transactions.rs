@@ -25,7 +25,10 @@ impl Network {
             expected_holders: Default::default(),
             is_register: false,
         };
-        let record = self.get_record_from_network(key.clone(), &get_cfg).await?;
+        let record = self
+            .get_record_from_network(key.clone(), &get_cfg)
+            .await?
+            .record;
         debug!(
             "Got record from the network, {:?}",
             PrettyPrintRecordKey::from(&record.key)
@@ -3,3 +3,64 @@
 pub(crate) mod mod_impl;
 
 pub(crate) use mod_impl::build_transport;
+
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use std::{borrow::Cow, net::IpAddr, path::PathBuf};
+
+/// The WebSocket listener of a node, alongside its QUIC one, for the clients that can't use QUIC,
+/// e.g. browsers or clients behind proxies only letting TCP through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketConfig {
+    /// The TCP port to listen on
+    pub port: u16,
+    /// The certificate to listen on secure WebSocket (`wss`) with, instead of plain `ws`
+    pub tls: Option<WebSocketTls>,
+}
+
+/// The PEM files of the certificate a node serves secure WebSocket with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketTls {
+    /// The certificate chain, starting with the certificate of the node
+    pub cert_chain_path: PathBuf,
+    /// The private key of the certificate, in PKCS#8, PKCS#1 or SEC1 format
+    pub private_key_path: PathBuf,
+}
+
+impl WebSocketConfig {
+    /// The address to listen on, e.g. `/ip4/0.0.0.0/tcp/443/wss`.
+    pub(crate) fn listen_addr(&self, ip: IpAddr) -> Multiaddr {
+        let addr = Multiaddr::from(ip).with(Protocol::Tcp(self.port));
+        if self.tls.is_some() {
+            addr.with(Protocol::Wss(Cow::Borrowed("/")))
+        } else {
+            addr.with(Protocol::Ws(Cow::Borrowed("/")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn websocket_listen_addr_is_secure_with_a_certificate() {
+        let mut config = WebSocketConfig {
+            port: 8080,
+            tls: None,
+        };
+        let ip = IpAddr::from([0, 0, 0, 0]);
+        assert_eq!(
+            config.listen_addr(ip).to_string(),
+            "/ip4/0.0.0.0/tcp/8080/ws"
+        );
+
+        config.tls = Some(WebSocketTls {
+            cert_chain_path: PathBuf::from("cert.pem"),
+            private_key_path: PathBuf::from("key.pem"),
+        });
+        assert_eq!(
+            config.listen_addr(ip).to_string(),
+            "/ip4/0.0.0.0/tcp/8080/wss"
+        );
+    }
+}
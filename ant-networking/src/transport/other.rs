@@ -1,22 +1,38 @@
+use super::{WebSocketConfig, WebSocketTls};
+use crate::error::{NetworkError, Result};
 #[cfg(feature = "open-metrics")]
 use crate::MetricsRegistries;
 use libp2p::{
-    core::{muxing::StreamMuxerBox, transport},
+    core::{muxing::StreamMuxerBox, transport, upgrade},
+    dns,
+    futures::future::Either,
     identity::Keypair,
-    PeerId, Transport as _,
+    noise, tcp,
+    websocket::{self, tls},
+    yamux, PeerId, Transport as _,
 };
+use std::{fs::File, io::BufReader};
 
 pub(crate) fn build_transport(
     keypair: &Keypair,
+    websocket: Option<&WebSocketConfig>,
     #[cfg(feature = "open-metrics")] registries: &mut MetricsRegistries,
-) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
-    let trans = generate_quic_transport(keypair);
+) -> Result<transport::Boxed<(PeerId, StreamMuxerBox)>> {
+    let quic = generate_quic_transport(keypair);
+    let websocket = generate_websocket_transport(keypair, websocket)?;
+    // QUIC is preferred, WebSocket is only dialed for the `ws`/`wss` addresses.
+    let trans = quic
+        .or_transport(websocket)
+        .map(|either_output, _| match either_output {
+            Either::Left((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+            Either::Right((peer_id, muxer)) => (peer_id, StreamMuxerBox::new(muxer)),
+        });
     #[cfg(feature = "open-metrics")]
     let trans = libp2p::metrics::BandwidthTransport::new(trans, &mut registries.standard_metrics);
 
     let trans = trans.map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
 
-    trans.boxed()
+    Ok(trans.boxed())
 }
 
 fn generate_quic_transport(
@@ -24,3 +40,49 @@ fn generate_quic_transport(
 ) -> libp2p::quic::GenTransport<libp2p::quic::tokio::Provider> {
     libp2p::quic::tokio::Transport::new(libp2p::quic::Config::new(keypair))
 }
+
+/// WebSocket over TCP, secured by noise like the relayed connections, and by TLS as well for `wss`.
+fn generate_websocket_transport(
+    keypair: &Keypair,
+    config: Option<&WebSocketConfig>,
+) -> Result<transport::Boxed<(PeerId, StreamMuxerBox)>> {
+    let tcp = dns::tokio::Transport::system(tcp::tokio::Transport::new(tcp::Config::default()))?;
+    let mut ws = websocket::WsConfig::new(tcp);
+    if let Some(tls) = config.and_then(|config| config.tls.as_ref()) {
+        let _ = ws.set_tls_config(load_tls_config(tls)?);
+    }
+
+    let trans = ws
+        .upgrade(upgrade::Version::V1)
+        .authenticate(
+            noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed."),
+        )
+        .multiplex(yamux::Config::default())
+        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+    Ok(trans.boxed())
+}
+
+/// The TLS config serving the certificate and able to dial other `wss` listeners.
+fn load_tls_config(tls: &WebSocketTls) -> Result<tls::Config> {
+    let invalid = |err: String| {
+        NetworkError::InvalidWebSocketTls(format!(
+            "{err}, for {:?} and {:?}",
+            tls.cert_chain_path, tls.private_key_path
+        ))
+    };
+
+    let mut reader = BufReader::new(File::open(&tls.cert_chain_path)?);
+    let certs = rustls_pemfile::certs(&mut reader)
+        .map(|cert| cert.map(|cert| tls::Certificate::new(cert.to_vec())))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(invalid("No certificate found".to_string()));
+    }
+
+    let mut reader = BufReader::new(File::open(&tls.private_key_path)?);
+    let key = rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| invalid("No private key found".to_string()))?;
+    let key = tls::PrivateKey::new(key.secret_der().to_vec());
+
+    tls::Config::new(key, certs).map_err(|err| invalid(err.to_string()))
+}
@@ -1,18 +1,24 @@
 // wasm32 environments typically only support WebSockets (and WebRTC or WebTransport), so no plain UDP or TCP.
 
+use super::WebSocketConfig;
+use crate::error::Result;
 use libp2p::{
     core::{muxing::StreamMuxerBox, transport, upgrade},
     identity::Keypair,
     noise, websocket_websys, yamux, PeerId, Transport as _,
 };
 
-pub(crate) fn build_transport(keypair: &Keypair) -> transport::Boxed<(PeerId, StreamMuxerBox)> {
+/// Browsers can't listen, so the WebSocket listener config is unused.
+pub(crate) fn build_transport(
+    keypair: &Keypair,
+    _websocket: Option<&WebSocketConfig>,
+) -> Result<transport::Boxed<(PeerId, StreamMuxerBox)>> {
     // We build a single transport here, WebSockets.
-    websocket_websys::Transport::default()
+    Ok(websocket_websys::Transport::default()
         .upgrade(upgrade::Version::V1)
         .authenticate(
             noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed."),
         )
         .multiplex(yamux::Config::default())
-        .boxed()
+        .boxed())
 }
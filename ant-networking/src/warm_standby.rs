@@ -0,0 +1,181 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::SwarmDriver;
+use ant_protocol::{storage::RecordType, NetworkAddress};
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const WARM_STANDBY_FILENAME: &str = "warm_standby";
+
+/// A node down for longer than this is restarted cold, its routing table and replication too outdated to be of use.
+const MAX_SNAPSHOT_AGE: Duration = Duration::from_secs(30 * 60);
+
+/// What the node was doing when cleanly shut down, to carry on with after a restart instead of starting over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct WarmStandbySnapshot {
+    saved_at: SystemTime,
+    /// The peers of the routing table, with their addresses
+    pub(crate) routing_table: Vec<(PeerId, Vec<Multiaddr>)>,
+    /// The keys pending or being fetched through replication, with their holders
+    pub(crate) pending_replication: Vec<(PeerId, NetworkAddress, RecordType)>,
+}
+
+impl WarmStandbySnapshot {
+    pub(crate) fn new(
+        routing_table: Vec<(PeerId, Vec<Multiaddr>)>,
+        pending_replication: Vec<(PeerId, NetworkAddress, RecordType)>,
+    ) -> Self {
+        Self {
+            saved_at: SystemTime::now(),
+            routing_table,
+            pending_replication,
+        }
+    }
+
+    /// Load the snapshot persisted in the root dir, if any and recent enough.
+    ///
+    /// The snapshot is removed once loaded, for a node that didn't shut down cleanly since not to restore it again.
+    pub(crate) fn take(root_dir: &Path) -> Option<Self> {
+        let path = root_dir.join(WARM_STANDBY_FILENAME);
+        let bytes = fs::read(&path).ok()?;
+        if let Err(err) = fs::remove_file(&path) {
+            warn!("Failed to remove the warm standby snapshot once loaded: {err:?}");
+        }
+        let snapshot: Self = match rmp_serde::from_slice(&bytes) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                warn!("Failed to deserialize the warm standby snapshot: {err:?}");
+                return None;
+            }
+        };
+        let age = SystemTime::now()
+            .duration_since(snapshot.saved_at)
+            .unwrap_or_default();
+        if age > MAX_SNAPSHOT_AGE {
+            info!("Not restoring the warm standby snapshot, saved {age:?} ago");
+            return None;
+        }
+        Some(snapshot)
+    }
+
+    /// Persist the snapshot to the root dir, replacing the previous one.
+    pub(crate) fn save(&self, root_dir: &Path) -> std::io::Result<()> {
+        let bytes = rmp_serde::to_vec(self).map_err(std::io::Error::other)?;
+        // Write to a temp file first, to never leave a truncated file behind
+        let tmp_path = root_dir.join(format!("{WARM_STANDBY_FILENAME}.tmp"));
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, root_dir.join(WARM_STANDBY_FILENAME))
+    }
+}
+
+impl SwarmDriver {
+    /// Snapshot the routing table and the replication in progress.
+    pub(crate) fn warm_standby_snapshot(&mut self) -> WarmStandbySnapshot {
+        let routing_table = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .kbuckets()
+            .flat_map(|kbucket| {
+                kbucket
+                    .iter()
+                    .map(|entry| {
+                        (
+                            entry.node.key.into_preimage(),
+                            entry.node.value.clone().into_vec(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|(_peer_id, addrs)| !addrs.is_empty())
+            .collect();
+        WarmStandbySnapshot::new(routing_table, self.replication_fetcher.pending_keys())
+    }
+
+    /// Restore the snapshot persisted in the root dir, if any.
+    ///
+    /// The peers are put back in the routing table without being dialed, to be validated lazily: the ones gone are
+    /// cleared out on the first failed dial, as any other peer. The pending replication is resumed once the first
+    /// peer is back in the routing table.
+    pub(crate) fn restore_warm_standby(&mut self, root_dir: &Path) {
+        let Some(snapshot) = WarmStandbySnapshot::take(root_dir) else {
+            return;
+        };
+
+        let mut restored_peers = 0;
+        for (peer_id, addrs) in snapshot.routing_table {
+            if peer_id == self.self_peer_id || self.peer_scores.is_bad(&peer_id) {
+                continue;
+            }
+            let _ = self.restored_peers.insert(peer_id);
+            for addr in addrs {
+                let _routing_update = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .add_address(&peer_id, addr);
+            }
+            restored_peers += 1;
+        }
+
+        let pending_keys = snapshot.pending_replication.len();
+        let locally_stored_keys = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref();
+        self.replication_fetcher
+            .restore_pending_keys(snapshot.pending_replication, locally_stored_keys);
+        info!("Restored the warm standby snapshot, with {restored_peers} peers in the routing table and {pending_keys} keys pending replication");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use xor_name::XorName;
+
+    #[test]
+    fn snapshot_is_restored_once_and_only_if_recent() -> eyre::Result<()> {
+        let dir = TempDir::new()?;
+        let peer = PeerId::random();
+        let snapshot = WarmStandbySnapshot::new(
+            vec![(peer, vec!["/ip4/127.0.0.1/udp/12000/quic-v1".parse()?])],
+            vec![(
+                peer,
+                NetworkAddress::from_chunk_address(ant_protocol::storage::ChunkAddress::new(
+                    XorName::from_content(b"chunk"),
+                )),
+                RecordType::Chunk,
+            )],
+        );
+        snapshot.save(dir.path())?;
+        assert_eq!(
+            WarmStandbySnapshot::take(dir.path()),
+            Some(snapshot.clone())
+        );
+        // A node that crashed after starting from it doesn't restore it again
+        assert_eq!(WarmStandbySnapshot::take(dir.path()), None);
+
+        let outdated = WarmStandbySnapshot {
+            saved_at: SystemTime::now() - MAX_SNAPSHOT_AGE * 2,
+            ..snapshot
+        };
+        outdated.save(dir.path())?;
+        assert_eq!(WarmStandbySnapshot::take(dir.path()), None);
+        Ok(())
+    }
+}
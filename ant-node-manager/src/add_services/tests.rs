@@ -113,6 +113,8 @@ async fn add_genesis_node_should_use_latest_version_and_add_one_service() -> Res
         first: true,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -266,6 +268,8 @@ async fn add_genesis_node_should_return_an_error_if_there_is_already_a_genesis_n
         first: true,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -406,6 +410,8 @@ async fn add_genesis_node_should_return_an_error_if_count_is_greater_than_1() ->
         first: true,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -1122,6 +1128,8 @@ async fn add_node_should_create_service_file_with_first_arg() -> Result<()> {
         first: true,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -1277,6 +1285,8 @@ async fn add_node_should_create_service_file_with_peers_args() -> Result<()> {
                 .parse()?,
         ],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -1431,6 +1441,8 @@ async fn add_node_should_create_service_file_with_local_arg() -> Result<()> {
         first: false,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: true,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -1586,6 +1598,8 @@ async fn add_node_should_create_service_file_with_network_contacts_url_arg() ->
             "http://localhost:8080/contacts".to_string(),
             "http://localhost:8081/contacts".to_string(),
         ],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
@@ -1742,6 +1756,8 @@ async fn add_node_should_create_service_file_with_testnet_arg() -> Result<()> {
         first: false,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: true,
         ignore_cache: false,
@@ -1894,6 +1910,8 @@ async fn add_node_should_create_service_file_with_ignore_cache_arg() -> Result<(
         first: false,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: true,
@@ -2046,6 +2064,8 @@ async fn add_node_should_create_service_file_with_custom_bootstrap_cache_path()
         first: false,
         addrs: vec![],
         network_contacts_url: vec![],
+        dns_seeds: vec![],
+        skip_contacts_verification: false,
         local: false,
         disable_mainnet_contacts: false,
         ignore_cache: false,
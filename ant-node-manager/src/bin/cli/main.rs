@@ -15,12 +15,13 @@ use ant_logging::{LogBuilder, LogFormat};
 use ant_node_manager::{
     add_services::config::PortRange,
     cmd::{self},
+    upgrade_schedule::{MaintenanceWindow, UpgradeSchedule},
     VerbosityLevel, DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S,
 };
 use clap::{Parser, Subcommand};
 use color_eyre::{eyre::eyre, Result};
 use libp2p::Multiaddr;
-use std::{net::Ipv4Addr, path::PathBuf};
+use std::{net::Ipv4Addr, path::PathBuf, time::Duration};
 use tracing::Level;
 
 const DEFAULT_NODE_COUNT: u16 = 25;
@@ -277,6 +278,28 @@ pub enum SubCmd {
     Faucet(FaucetSubCmd),
     #[clap(subcommand)]
     Local(LocalSubCmd),
+    /// Change the log levels of running antnode services, without restarting them.
+    ///
+    /// If no peer ID(s) or service name(s) are supplied, the log levels of all running services will be changed.
+    #[clap(name = "log-level")]
+    LogLevel {
+        /// The log levels, in the same format as the `ANT_LOG` variable.
+        ///
+        /// For example, 'ant_networking=trace,libp2p=debug'. The levels replace the ones the services were started
+        /// with.
+        #[clap(value_name = "LEVELS")]
+        log_levels: String,
+        /// The peer ID of the service to update.
+        ///
+        /// The argument can be used multiple times to update many services.
+        #[clap(long)]
+        peer_id: Vec<String>,
+        /// The name of the service to update.
+        ///
+        /// The argument can be used multiple times to update many services.
+        #[clap(long, conflicts_with = "peer_id")]
+        service_name: Vec<String>,
+    },
     #[clap(subcommand)]
     NatDetection(NatDetectionSubCmd),
     /// Remove antnode service(s).
@@ -328,6 +351,11 @@ pub enum SubCmd {
     /// sudo if you defined system-wide services; otherwise, do not run the command elevated.
     #[clap(name = "start")]
     Start {
+        /// The number of services to start at the same time.
+        ///
+        /// Defaults to 1, i.e. one service after the other.
+        #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..), conflicts_with = "interval")]
+        concurrency: u16,
         /// The max time in seconds to wait for a node to connect to the network. If the node does not connect to the
         /// network within this time, the node is considered failed.
         ///
@@ -336,6 +364,11 @@ pub enum SubCmd {
         /// Defaults to 300s.
         #[clap(long, default_value_t = DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S, conflicts_with = "interval")]
         connection_timeout: u64,
+        /// Set this flag to still start the remaining services after one has failed.
+        ///
+        /// By default, no more services are started after a failure.
+        #[clap(long)]
+        continue_on_error: bool,
         /// An interval applied between launching each service.
         ///
         /// Use connection-timeout to scale the interval automatically. This argument is mutually exclusive with the
@@ -376,6 +409,16 @@ pub enum SubCmd {
     /// sudo if you defined system-wide services; otherwise, do not run the command elevated.
     #[clap(name = "stop")]
     Stop {
+        /// The number of services to stop at the same time.
+        ///
+        /// Defaults to 1, i.e. one service after the other.
+        #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..), conflicts_with = "interval")]
+        concurrency: u16,
+        /// Set this flag to still stop the remaining services after one has failed.
+        ///
+        /// By default, no more services are stopped after a failure.
+        #[clap(long)]
+        continue_on_error: bool,
         /// An interval applied between stopping each service.
         ///
         /// Units are milliseconds.
@@ -405,6 +448,22 @@ pub enum SubCmd {
     /// sudo if you defined system-wide services; otherwise, do not run the command elevated.
     #[clap(name = "upgrade")]
     Upgrade {
+        /// The time to wait after upgrading a batch of services, before upgrading the next one.
+        ///
+        /// Units are minutes. Only used with the 'batch-percent' argument.
+        #[clap(long, requires = "batch_percent")]
+        batch_interval: Option<u64>,
+        /// Upgrade the services in batches of this percentage of them, e.g. 10 for a tenth of the services at a
+        /// time.
+        ///
+        /// Use 'batch-interval' to spread the batches out over time.
+        #[clap(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+        batch_percent: Option<u8>,
+        /// The number of services to upgrade at the same time.
+        ///
+        /// Defaults to 1, i.e. one service after the other.
+        #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..), conflicts_with = "interval")]
+        concurrency: u16,
         /// The max time in seconds to wait for a node to connect to the network. If the node does not connect to the
         /// network within this time, the node is considered failed.
         ///
@@ -413,6 +472,11 @@ pub enum SubCmd {
         /// Defaults to 300s.
         #[clap(long, default_value_t = DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S, conflicts_with = "interval")]
         connection_timeout: u64,
+        /// Set this flag to still upgrade the remaining services after one has failed.
+        ///
+        /// By default, no more services are upgraded after a failure.
+        #[clap(long)]
+        continue_on_error: bool,
         /// Set this flag to upgrade the nodes without automatically starting them.
         ///
         /// Can be useful for testing scenarios.
@@ -447,6 +511,12 @@ pub enum SubCmd {
         /// Useful for upgrading the service using a custom built binary.
         #[clap(long)]
         path: Option<PathBuf>,
+        /// Set this flag to put the previous binary back if a service does not start after its upgrade.
+        ///
+        /// The service is considered started once it has connected to the network, or after the
+        /// 'connection-timeout' period.
+        #[clap(long)]
+        rollback: bool,
         /// The peer ID of the service to upgrade
         #[clap(long)]
         peer_id: Vec<String>,
@@ -465,6 +535,11 @@ pub enum SubCmd {
         /// The version number should be in the form X.Y.Z, with no 'v' prefix.
         #[clap(long)]
         version: Option<String>,
+        /// Only upgrade the services within this daily maintenance window, in local time.
+        ///
+        /// For example, '02:00-05:00'. Outside of the window, the upgrade waits for it to open.
+        #[clap(long)]
+        window: Option<MaintenanceWindow>,
     },
 }
 
@@ -1308,6 +1383,11 @@ async fn main() -> Result<()> {
                 json,
             } => cmd::local::status(details, fail, json).await,
         },
+        Some(SubCmd::LogLevel {
+            log_levels,
+            peer_id: peer_ids,
+            service_name: service_names,
+        }) => cmd::node::log_level(log_levels, peer_ids, service_names, verbosity).await,
         Some(SubCmd::NatDetection(NatDetectionSubCmd::Run {
             path,
             servers,
@@ -1324,13 +1404,17 @@ async fn main() -> Result<()> {
         }) => cmd::node::remove(keep_directories, peer_ids, service_names, verbosity).await,
         Some(SubCmd::Reset { force }) => cmd::node::reset(force, verbosity).await,
         Some(SubCmd::Start {
+            concurrency,
             connection_timeout,
+            continue_on_error,
             interval,
             peer_id: peer_ids,
             service_name: service_names,
         }) => {
             cmd::node::start(
+                concurrency as usize,
                 connection_timeout,
+                continue_on_error,
                 interval,
                 peer_ids,
                 service_names,
@@ -1344,30 +1428,57 @@ async fn main() -> Result<()> {
             json,
         }) => cmd::node::status(details, fail, json).await,
         Some(SubCmd::Stop {
+            concurrency,
+            continue_on_error,
             interval,
             peer_id: peer_ids,
             service_name: service_names,
-        }) => cmd::node::stop(interval, peer_ids, service_names, verbosity).await,
+        }) => {
+            cmd::node::stop(
+                concurrency as usize,
+                continue_on_error,
+                interval,
+                peer_ids,
+                service_names,
+                verbosity,
+            )
+            .await
+        }
         Some(SubCmd::Upgrade {
+            batch_interval,
+            batch_percent,
+            concurrency,
             connection_timeout,
+            continue_on_error,
             do_not_start,
             force,
             interval,
             path,
             peer_id: peer_ids,
+            rollback,
             service_name: service_names,
             env_variables: provided_env_variable,
             url,
             version,
+            window,
         }) => {
+            let schedule = UpgradeSchedule {
+                batch_percent,
+                batch_interval: Duration::from_secs(batch_interval.unwrap_or(0) * 60),
+                window,
+            };
             cmd::node::upgrade(
+                concurrency as usize,
                 connection_timeout,
+                continue_on_error,
                 do_not_start,
                 path,
                 force,
                 interval,
                 peer_ids,
                 provided_env_variable,
+                rollback,
+                schedule,
                 service_names,
                 url,
                 version,
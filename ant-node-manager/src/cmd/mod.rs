@@ -124,6 +124,14 @@ pub fn print_upgrade_summary(upgrade_summary: Vec<(String, UpgradeResult)>) {
                     service_name
                 );
             }
+            UpgradeResult::RolledBack(previous_version, new_version, msg) => {
+                println!(
+                    "{} {} did not start after the upgrade from {previous_version} to {new_version}, so it was \
+                    rolled back: {msg}",
+                    "✕".red(),
+                    service_name
+                );
+            }
             UpgradeResult::Error(msg) => {
                 println!("{} {} was not upgraded: {}", "✕".red(), service_name, msg);
             }
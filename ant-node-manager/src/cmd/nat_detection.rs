@@ -37,6 +37,8 @@ pub async fn run_nat_detection(
         None => {
             let mut contacts_fetcher = ContactsFetcher::new()?;
             contacts_fetcher.ignore_peer_id(true);
+            // The servers are only probed, the node does not join the network through them
+            contacts_fetcher.skip_verification(true);
             contacts_fetcher.insert_endpoint(NAT_DETECTION_SERVERS_LIST_URL.parse()?);
 
             let servers = contacts_fetcher.fetch_addrs().await?;
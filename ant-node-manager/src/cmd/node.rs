@@ -16,7 +16,9 @@ use crate::{
     },
     config::{self, is_running_as_root},
     helpers::{download_and_extract_release, get_bin_version},
-    print_banner, refresh_node_registry, status_report, ServiceManager, VerbosityLevel,
+    print_banner, refresh_node_registry, status_report,
+    upgrade_schedule::UpgradeSchedule,
+    ServiceManager, VerbosityLevel,
 };
 use ant_bootstrap::PeersArgs;
 use ant_evm::{EvmNetwork, RewardsAddress};
@@ -24,14 +26,19 @@ use ant_logging::LogFormat;
 use ant_releases::{AntReleaseRepoActions, ReleaseType};
 use ant_service_management::{
     control::{ServiceControl, ServiceController},
-    rpc::RpcClient,
-    NodeRegistry, NodeService, ServiceStateActions, ServiceStatus, UpgradeOptions, UpgradeResult,
+    rpc::{RpcActions, RpcClient},
+    NodeRegistry, NodeService, NodeServiceData, ServiceStateActions, ServiceStatus, UpgradeOptions,
+    UpgradeResult,
 };
 use color_eyre::{eyre::eyre, Help, Result};
 use colored::Colorize;
 use libp2p_identity::PeerId;
 use semver::Version;
-use std::{cmp::Ordering, io::Write, net::Ipv4Addr, path::PathBuf, str::FromStr, time::Duration};
+use std::{
+    cmp::Ordering, future::Future, io::Write, net::Ipv4Addr, path::PathBuf, str::FromStr,
+    time::Duration,
+};
+use tokio::task::JoinSet;
 use tracing::debug;
 
 /// Returns the added service names
@@ -191,6 +198,68 @@ pub async fn balance(
     Ok(())
 }
 
+/// Change the log levels of running nodes, without restarting them.
+///
+/// The levels are given like the `ANT_LOG` variable, e.g. `ant_networking=trace,libp2p=debug`, and replace the ones
+/// the nodes were started with.
+pub async fn log_level(
+    log_levels: String,
+    peer_ids: Vec<String>,
+    service_names: Vec<String>,
+    verbosity: VerbosityLevel,
+) -> Result<()> {
+    if verbosity != VerbosityLevel::Minimal {
+        print_banner("Update Log Levels");
+    }
+    info!("Updating the log levels to {log_levels} for: {peer_ids:?}, {service_names:?}");
+
+    let mut node_registry = NodeRegistry::load(&config::get_node_registry_path()?)?;
+    refresh_node_registry(
+        &mut node_registry,
+        &ServiceController {},
+        verbosity != VerbosityLevel::Minimal,
+        false,
+        false,
+    )
+    .await?;
+
+    let service_indices = get_services_for_ops(&node_registry, peer_ids, service_names)?;
+    let mut failed_services = Vec::new();
+    for &index in &service_indices {
+        let node = &node_registry.nodes[index];
+        if node.status != ServiceStatus::Running {
+            debug!(
+                "Skipping service {} as it is not running",
+                node.service_name
+            );
+            continue;
+        }
+
+        let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
+        match rpc_client.update_log_level(log_levels.clone()).await {
+            Ok(()) => {
+                debug!("Updated the log levels of service {}", node.service_name);
+                if verbosity != VerbosityLevel::Minimal {
+                    println!(
+                        "{} Updated the log levels of {}",
+                        "✓".green(),
+                        node.service_name
+                    );
+                }
+            }
+            Err(err) => {
+                error!(
+                    "Failed to update the log levels of service {}: {err}",
+                    node.service_name
+                );
+                failed_services.push((node.service_name.clone(), err.to_string()))
+            }
+        }
+    }
+
+    summarise_any_failed_ops(failed_services, "update the log levels of", verbosity)
+}
+
 pub async fn remove(
     keep_directories: bool,
     peer_ids: Vec<String>,
@@ -262,7 +331,7 @@ pub async fn reset(force: bool, verbosity: VerbosityLevel) -> Result<()> {
         }
     }
 
-    stop(None, vec![], vec![], verbosity).await?;
+    stop(1, true, None, vec![], vec![], verbosity).await?;
     remove(false, vec![], vec![], verbosity).await?;
 
     // Due the possibility of repeated runs of the `reset` command, we need to check for the
@@ -278,7 +347,9 @@ pub async fn reset(force: bool, verbosity: VerbosityLevel) -> Result<()> {
 }
 
 pub async fn start(
+    concurrency: usize,
     connection_timeout_s: u64,
+    continue_on_error: bool,
     fixed_interval: Option<u64>,
     peer_ids: Vec<String>,
     service_names: Vec<String>,
@@ -309,49 +380,46 @@ pub async fn start(
         return Ok(());
     }
 
-    let mut failed_services = Vec::new();
-    for &index in &service_indices {
-        let node = &mut node_registry.nodes[index];
-        let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
-
-        let service = NodeService::new(node, Box::new(rpc_client));
-
-        // set dynamic startup delay if fixed_interval is not set
-        let service = if fixed_interval.is_none() {
-            service.with_connection_timeout(Duration::from_secs(connection_timeout_s))
-        } else {
-            service
-        };
-
-        let mut service_manager =
-            ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
-        if service_manager.service.status() != ServiceStatus::Running {
-            // It would be possible here to check if the service *is* running and then just
-            // continue without applying the delay. The reason for not doing so is because when
-            // `start` is called below, the user will get a message to say the service was already
-            // started, which I think is useful behaviour to retain.
-            if let Some(interval) = fixed_interval {
-                debug!("Sleeping for {} milliseconds", interval);
-                std::thread::sleep(std::time::Duration::from_millis(interval));
-            }
-        }
-        match service_manager.start().await {
-            Ok(start_duration) => {
-                debug!(
-                    "Started service {} in {start_duration:?}",
-                    node.service_name
-                );
-
-                node_registry.save()?;
+    let outcome = run_on_services(
+        &mut node_registry,
+        &service_indices,
+        concurrency,
+        continue_on_error,
+        Result::is_err,
+        move |mut node| async move {
+            let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
+            let service = NodeService::new(&mut node, Box::new(rpc_client));
+
+            // set dynamic startup delay if fixed_interval is not set
+            let service = if fixed_interval.is_none() {
+                service.with_connection_timeout(Duration::from_secs(connection_timeout_s))
+            } else {
+                service
+            };
+
+            let mut service_manager =
+                ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
+            if service_manager.service.status() != ServiceStatus::Running {
+                // It would be possible here to check if the service *is* running and then just
+                // continue without applying the delay. The reason for not doing so is because when
+                // `start` is called below, the user will get a message to say the service was already
+                // started, which I think is useful behaviour to retain.
+                if let Some(interval) = fixed_interval {
+                    debug!("Sleeping for {} milliseconds", interval);
+                    std::thread::sleep(std::time::Duration::from_millis(interval));
+                }
             }
-            Err(err) => {
-                error!("Failed to start service {}: {err}", node.service_name);
-                failed_services.push((node.service_name.clone(), err.to_string()))
+            let result = service_manager.start().await.map_err(|err| err.to_string());
+            match &result {
+                Ok(()) => debug!("Started service {}", node.service_name),
+                Err(err) => error!("Failed to start service {}: {err}", node.service_name),
             }
-        }
-    }
+            (node, result)
+        },
+    )
+    .await?;
 
-    summarise_any_failed_ops(failed_services, "start", verbosity)
+    summarise_ops(outcome, "start", "started", verbosity)
 }
 
 pub async fn status(details: bool, fail: bool, json: bool) -> Result<()> {
@@ -375,6 +443,8 @@ pub async fn status(details: bool, fail: bool, json: bool) -> Result<()> {
 }
 
 pub async fn stop(
+    concurrency: usize,
+    continue_on_error: bool,
     interval: Option<u64>,
     peer_ids: Vec<String>,
     service_names: Vec<String>,
@@ -405,43 +475,49 @@ pub async fn stop(
         return Ok(());
     }
 
-    let mut failed_services = Vec::new();
-    for &index in &service_indices {
-        let node = &mut node_registry.nodes[index];
-        let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
-        let service = NodeService::new(node, Box::new(rpc_client));
-        let mut service_manager =
-            ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
-
-        if service_manager.service.status() == ServiceStatus::Running {
-            if let Some(interval) = interval {
-                debug!("Sleeping for {} milliseconds", interval);
-                std::thread::sleep(std::time::Duration::from_millis(interval));
-            }
-        }
-        match service_manager.stop().await {
-            Ok(()) => {
-                debug!("Stopped service {}", node.service_name);
-                node_registry.save()?;
+    let outcome = run_on_services(
+        &mut node_registry,
+        &service_indices,
+        concurrency,
+        continue_on_error,
+        Result::is_err,
+        move |mut node| async move {
+            let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
+            let service = NodeService::new(&mut node, Box::new(rpc_client));
+            let mut service_manager =
+                ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
+
+            if service_manager.service.status() == ServiceStatus::Running {
+                if let Some(interval) = interval {
+                    debug!("Sleeping for {} milliseconds", interval);
+                    std::thread::sleep(std::time::Duration::from_millis(interval));
+                }
             }
-            Err(err) => {
-                error!("Failed to stop service {}: {err}", node.service_name);
-                failed_services.push((node.service_name.clone(), err.to_string()))
+            let result = service_manager.stop().await.map_err(|err| err.to_string());
+            match &result {
+                Ok(()) => debug!("Stopped service {}", node.service_name),
+                Err(err) => error!("Failed to stop service {}: {err}", node.service_name),
             }
-        }
-    }
+            (node, result)
+        },
+    )
+    .await?;
 
-    summarise_any_failed_ops(failed_services, "stop", verbosity)
+    summarise_ops(outcome, "stop", "stopped", verbosity)
 }
 
 pub async fn upgrade(
+    concurrency: usize,
     connection_timeout_s: u64,
+    continue_on_error: bool,
     do_not_start: bool,
     custom_bin_path: Option<PathBuf>,
     force: bool,
     fixed_interval: Option<u64>,
     peer_ids: Vec<String>,
     provided_env_variables: Option<Vec<(String, String)>>,
+    rollback: bool,
+    schedule: UpgradeSchedule,
     service_names: Vec<String>,
     url: Option<String>,
     version: Option<String>,
@@ -504,71 +580,114 @@ pub async fn upgrade(
     trace!("service_indices len: {}", service_indices.len());
     let mut upgrade_summary = Vec::new();
 
-    for &index in &service_indices {
-        let node = &mut node_registry.nodes[index];
-        let env_variables = if provided_env_variables.is_some() {
-            &provided_env_variables
-        } else {
-            &node_registry.environment_variables
-        };
-        let options = UpgradeOptions {
-            auto_restart: false,
-            env_variables: env_variables.clone(),
-            force: use_force,
-            start_service: !do_not_start,
-            target_bin_path: upgrade_bin_path.clone(),
-            target_version: target_version.clone(),
-        };
-        let service_name = node.service_name.clone();
-
-        let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
-        let service = NodeService::new(node, Box::new(rpc_client));
-        // set dynamic startup delay if fixed_interval is not set
-        let service = if fixed_interval.is_none() {
-            service.with_connection_timeout(Duration::from_secs(connection_timeout_s))
-        } else {
-            service
-        };
-
-        let mut service_manager =
-            ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
-
-        match service_manager.upgrade(options).await {
-            Ok(upgrade_result) => {
-                info!("Service: {service_name} has been upgraded, result: {upgrade_result:?}",);
-                if upgrade_result != UpgradeResult::NotRequired {
-                    // It doesn't seem useful to apply the interval if there was no upgrade
-                    // required for the previous service.
-                    if let Some(interval) = fixed_interval {
-                        debug!("Sleeping for {interval} milliseconds",);
-                        std::thread::sleep(std::time::Duration::from_millis(interval));
-                    }
-                }
+    let options = UpgradeOptions {
+        auto_restart: false,
+        env_variables: provided_env_variables.or(node_registry.environment_variables.clone()),
+        force: use_force,
+        rollback_on_failure: rollback,
+        start_service: !do_not_start,
+        target_bin_path: upgrade_bin_path,
+        target_version,
+    };
+    let batches = schedule.batches(&service_indices);
+    for (batch_number, batch) in batches.iter().enumerate() {
+        let any_failed = upgrade_summary
+            .iter()
+            .any(|(_, result)| is_failed_upgrade(result));
+        if any_failed && !continue_on_error {
+            for &index in batch {
                 upgrade_summary.push((
-                    service_manager.service.service_data.service_name.clone(),
-                    upgrade_result,
+                    node_registry.nodes[index].service_name.clone(),
+                    UpgradeResult::Error(SKIPPED_AFTER_FAILURE.to_string()),
                 ));
-                node_registry.save()?;
             }
-            Err(err) => {
-                error!("Error upgrading service {service_name}: {err}");
-                upgrade_summary.push((
-                    node.service_name.clone(),
-                    UpgradeResult::Error(format!("Error: {err}")),
-                ));
-                node_registry.save()?;
+            continue;
+        }
+
+        if batch_number > 0 && !schedule.batch_interval.is_zero() {
+            info!(
+                "Waiting {:?} before upgrading the next batch of services",
+                schedule.batch_interval
+            );
+            if verbosity != VerbosityLevel::Minimal {
+                println!(
+                    "Waiting {} minute(s) before upgrading the next batch of {} service(s)...",
+                    schedule.batch_interval.as_secs().div_ceil(60),
+                    batch.len()
+                );
             }
+            tokio::time::sleep(schedule.batch_interval).await;
         }
+
+        let options = options.clone();
+        let schedule = schedule.clone();
+        let outcome = run_on_services(
+            &mut node_registry,
+            batch,
+            concurrency,
+            continue_on_error,
+            is_failed_upgrade,
+            move |mut node| {
+                let options = options.clone();
+                let schedule = schedule.clone();
+                async move {
+                    schedule
+                        .wait_for_window(verbosity != VerbosityLevel::Minimal)
+                        .await;
+                    let service_name = node.service_name.clone();
+
+                    let rpc_client = RpcClient::from_socket_addr(node.rpc_socket_addr);
+                    let service = NodeService::new(&mut node, Box::new(rpc_client));
+                    // set dynamic startup delay if fixed_interval is not set
+                    let service = if fixed_interval.is_none() {
+                        service.with_connection_timeout(Duration::from_secs(connection_timeout_s))
+                    } else {
+                        service
+                    };
+
+                    let mut service_manager =
+                        ServiceManager::new(service, Box::new(ServiceController {}), verbosity);
+
+                    let upgrade_result = match service_manager.upgrade(options).await {
+                        Ok(upgrade_result) => {
+                            info!("Service: {service_name} has been upgraded, result: {upgrade_result:?}",);
+                            if upgrade_result != UpgradeResult::NotRequired {
+                                // It doesn't seem useful to apply the interval if there was no upgrade
+                                // required for the previous service.
+                                if let Some(interval) = fixed_interval {
+                                    debug!("Sleeping for {interval} milliseconds",);
+                                    std::thread::sleep(std::time::Duration::from_millis(interval));
+                                }
+                            }
+                            upgrade_result
+                        }
+                        Err(err) => {
+                            error!("Error upgrading service {service_name}: {err}");
+                            UpgradeResult::Error(format!("Error: {err}"))
+                        }
+                    };
+                    (node, upgrade_result)
+                }
+            },
+        )
+        .await?;
+        upgrade_summary.extend(outcome.results);
+        upgrade_summary.extend(outcome.skipped.into_iter().map(|service_name| {
+            (
+                service_name,
+                UpgradeResult::Error(SKIPPED_AFTER_FAILURE.to_string()),
+            )
+        }));
     }
 
     if verbosity != VerbosityLevel::Minimal {
         print_upgrade_summary(upgrade_summary.clone());
     }
 
-    if upgrade_summary.iter().any(|(_, r)| {
-        matches!(r, UpgradeResult::Error(_))
-            || matches!(r, UpgradeResult::UpgradedButNotStarted(_, _, _))
-    }) {
+    if upgrade_summary
+        .iter()
+        .any(|(_, result)| is_failed_upgrade(result))
+    {
         return Err(eyre!("There was a problem upgrading one or more nodes").suggestion(
             "For any services that were upgraded but did not start, you can attempt to start them \
                 again using the 'start' command."));
@@ -640,7 +759,7 @@ pub async fn maintain_n_running_nodes(
                 "Stopping {} excess nodes: {:?}",
                 to_stop_count, services_to_stop
             );
-            stop(None, vec![], services_to_stop, verbosity).await?;
+            stop(1, true, None, vec![], services_to_stop, verbosity).await?;
         }
         Ordering::Less => {
             let to_start_count = target_count - running_count;
@@ -662,7 +781,9 @@ pub async fn maintain_n_running_nodes(
                     to_start_count, nodes_to_start
                 );
                 start(
+                    1,
                     connection_timeout_s,
+                    true,
                     start_node_interval,
                     vec![],
                     nodes_to_start,
@@ -719,7 +840,9 @@ pub async fn maintain_n_running_nodes(
 
                     if i == 0 {
                         start(
+                            1,
                             connection_timeout_s,
+                            true,
                             start_node_interval,
                             vec![],
                             added_service,
@@ -731,7 +854,9 @@ pub async fn maintain_n_running_nodes(
 
                 if !inactive_nodes.is_empty() {
                     start(
+                        1,
                         connection_timeout_s,
+                        true,
                         start_node_interval,
                         vec![],
                         inactive_nodes,
@@ -768,6 +893,123 @@ pub async fn maintain_n_running_nodes(
     Ok(())
 }
 
+const SKIPPED_AFTER_FAILURE: &str = "Skipped after a previous failure";
+
+/// The outcome of running an operation on several services, see [`run_on_services`].
+struct OpsOutcome<T> {
+    /// The name of each service the operation ran on, along with its result.
+    results: Vec<(String, T)>,
+    /// The services the operation did not run on, because of a previous failure.
+    skipped: Vec<String>,
+}
+
+/// Run an operation on the services, up to `concurrency` of them at a time, saving the registry as each completes.
+///
+/// The operations run on blocking threads, since the service control calls block. Unless `continue_on_error` is
+/// set, no more operations are started once one has failed, though the ones in progress are awaited.
+async fn run_on_services<T, F, Fut>(
+    node_registry: &mut NodeRegistry,
+    service_indices: &[usize],
+    concurrency: usize,
+    continue_on_error: bool,
+    is_failure: fn(&T) -> bool,
+    op: F,
+) -> Result<OpsOutcome<T>>
+where
+    T: Send + 'static,
+    F: Fn(NodeServiceData) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = (NodeServiceData, T)>,
+{
+    let mut pending = service_indices.iter();
+    let mut tasks = JoinSet::new();
+    let mut results = Vec::new();
+    let mut failed = false;
+
+    loop {
+        while tasks.len() < concurrency.max(1) && (continue_on_error || !failed) {
+            let Some(&index) = pending.next() else {
+                break;
+            };
+            let node = node_registry.nodes[index].clone();
+            let op = op.clone();
+            let runtime = tokio::runtime::Handle::current();
+            let _ = tasks.spawn_blocking(move || (index, runtime.block_on(op(node))));
+        }
+
+        let Some(task) = tasks.join_next().await else {
+            break;
+        };
+        let (index, (node, result)) = task?;
+        if is_failure(&result) {
+            failed = true;
+        }
+        results.push((index, node.service_name.clone(), result));
+        node_registry.nodes[index] = node;
+        node_registry.save()?;
+    }
+
+    // Report the services in the order they were given, rather than the order they completed in.
+    results.sort_by_key(|(index, _, _)| service_indices.iter().position(|i| i == index));
+    Ok(OpsOutcome {
+        results: results
+            .into_iter()
+            .map(|(_, service_name, result)| (service_name, result))
+            .collect(),
+        skipped: pending
+            .map(|&index| node_registry.nodes[index].service_name.clone())
+            .collect(),
+    })
+}
+
+fn is_failed_upgrade(result: &UpgradeResult) -> bool {
+    matches!(
+        result,
+        UpgradeResult::Error(_)
+            | UpgradeResult::UpgradedButNotStarted(_, _, _)
+            | UpgradeResult::RolledBack(_, _, _)
+    )
+}
+
+/// Print the result of the operation for each service, returning an error if it failed for any of them.
+fn summarise_ops(
+    outcome: OpsOutcome<std::result::Result<(), String>>,
+    verb: &str,
+    past_participle: &str,
+    verbosity: VerbosityLevel,
+) -> Result<()> {
+    let failed = outcome
+        .results
+        .iter()
+        .filter(|(_, result)| result.is_err())
+        .count();
+    if verbosity != VerbosityLevel::Minimal && outcome.results.len() + outcome.skipped.len() > 1 {
+        println!("Summary:");
+        for (service_name, result) in &outcome.results {
+            match result {
+                Ok(()) => println!("{} {service_name} {past_participle}", "✓".green()),
+                Err(err) => println!(
+                    "{} {service_name} was not {past_participle}: {err}",
+                    "✕".red()
+                ),
+            }
+        }
+        for service_name in &outcome.skipped {
+            println!("- {service_name}: {SKIPPED_AFTER_FAILURE}");
+        }
+    }
+
+    if failed > 0 || !outcome.skipped.is_empty() {
+        error!(
+            "Failed to {verb} {failed} service(s), skipped {}",
+            outcome.skipped.len()
+        );
+        return Err(eyre!("Failed to {verb} one or more services").suggestion(
+            "Use the '--continue-on-error' flag to still attempt the other services after a failure",
+        ));
+    }
+    Ok(())
+}
+
 fn get_services_for_ops(
     node_registry: &NodeRegistry,
     peer_ids: Vec<String>,
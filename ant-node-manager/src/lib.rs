@@ -17,6 +17,7 @@ pub mod helpers;
 pub mod local;
 pub mod rpc;
 pub mod rpc_client;
+pub mod upgrade_schedule;
 
 pub const DEFAULT_NODE_STARTUP_CONNECTION_TIMEOUT_S: u64 = 300;
 
@@ -47,6 +48,7 @@ use ant_service_management::{
 };
 use colored::Colorize;
 use semver::Version;
+use std::path::Path;
 use tracing::debug;
 
 pub const DAEMON_DEFAULT_PORT: u16 = 12500;
@@ -328,6 +330,10 @@ impl<T: ServiceStateActions + Send> ServiceManager<T> {
 
         debug!("Stopping the service and copying the binary");
         self.stop().await?;
+        let previous_bin_path = self.service.bin_path().with_extension("previous");
+        if options.rollback_on_failure {
+            std::fs::copy(self.service.bin_path(), &previous_bin_path)?;
+        }
         std::fs::copy(options.clone().target_bin_path, self.service.bin_path())?;
 
         self.service_control
@@ -341,6 +347,15 @@ impl<T: ServiceStateActions + Send> ServiceManager<T> {
         if options.start_service {
             match self.start().await {
                 Ok(start_duration) => start_duration,
+                Err(err) if options.rollback_on_failure => {
+                    warn!("The upgraded service could not be started, rolling back: {err}");
+                    self.rollback(&previous_bin_path, options.clone()).await?;
+                    return Ok(UpgradeResult::RolledBack(
+                        current_version.to_string(),
+                        options.target_version.to_string(),
+                        err.to_string(),
+                    ));
+                }
                 Err(err) => {
                     self.service
                         .set_version(&options.target_version.to_string());
@@ -355,6 +370,9 @@ impl<T: ServiceStateActions + Send> ServiceManager<T> {
         }
         self.service
             .set_version(&options.target_version.to_string());
+        if options.rollback_on_failure {
+            std::fs::remove_file(&previous_bin_path)?;
+        }
 
         if options.force {
             Ok(UpgradeResult::Forced(
@@ -368,6 +386,18 @@ impl<T: ServiceStateActions + Send> ServiceManager<T> {
             ))
         }
     }
+
+    /// Put the previous binary back in place of the upgraded one, and start it again.
+    async fn rollback(&mut self, previous_bin_path: &Path, options: UpgradeOptions) -> Result<()> {
+        std::fs::rename(previous_bin_path, self.service.bin_path())?;
+        self.service_control
+            .uninstall(&self.service.name(), self.service.is_user_mode())?;
+        self.service_control.install(
+            self.service.build_upgrade_install_context(options)?,
+            self.service.is_user_mode(),
+        )?;
+        self.start().await
+    }
 }
 
 pub async fn status_report(
@@ -655,7 +685,9 @@ mod tests {
     use ant_service_management::{
         error::{Error as ServiceControlError, Result as ServiceControlResult},
         node::{NodeService, NodeServiceData},
-        rpc::{NetworkInfo, NodeInfo, RecordAddress, RpcActions},
+        rpc::{
+            DriverState, NetworkInfo, NodeInfo, PeerScore, RecordAddress, RecordsPage, RpcActions,
+        },
         UpgradeOptions, UpgradeResult,
     };
     use assert_fs::prelude::*;
@@ -683,6 +715,10 @@ mod tests {
             async fn record_addresses(&self) -> ServiceControlResult<Vec<RecordAddress>>;
             async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> ServiceControlResult<()>;
             async fn node_stop(&self, delay_millis: u64) -> ServiceControlResult<()>;
+            async fn peer_scores(&self) -> ServiceControlResult<Vec<PeerScore>>;
+            async fn driver_state(&self) -> ServiceControlResult<DriverState>;
+            async fn list_records(&self, record_type: Option<String>, offset: u64, limit: u64) -> ServiceControlResult<RecordsPage>;
+            async fn node_decommission(&self, timeout_millis: u64) -> ServiceControlResult<()>;
             async fn node_update(&self, delay_millis: u64) -> ServiceControlResult<()>;
             async fn is_node_connected_to_network(&self, timeout: std::time::Duration) -> ServiceControlResult<()>;
             async fn update_log_level(&self, log_levels: String) -> ServiceControlResult<()>;
@@ -733,6 +769,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -744,6 +781,7 @@ mod tests {
                         "12D3KooWS2tpXGGTmg2AHFiDh57yPQnat49YHnyqoggzXZWpqkCR",
                     )?],
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -849,6 +887,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -858,6 +897,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -1047,6 +1087,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -1056,6 +1097,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -1241,6 +1283,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -1250,6 +1293,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -1340,6 +1384,7 @@ mod tests {
                 version: "0.98.1".to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -1351,6 +1396,7 @@ mod tests {
                         "12D3KooWS2tpXGGTmg2AHFiDh57yPQnat49YHnyqoggzXZWpqkCR",
                     )?],
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -1816,6 +1862,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -1825,6 +1872,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -1883,6 +1931,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -1985,6 +2034,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -2063,6 +2113,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -2072,6 +2123,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -2131,6 +2183,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: true,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -2221,6 +2274,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -2230,6 +2284,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -2289,6 +2344,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: false,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -2441,6 +2497,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -2461,6 +2518,180 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn upgrade_should_roll_back_if_the_upgraded_service_did_not_start() -> Result<()> {
+        let current_version = "0.1.0";
+        let target_version = "0.2.0";
+
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let current_install_dir = tmp_data_dir.child("antnode_install");
+        current_install_dir.create_dir_all()?;
+
+        let current_node_bin = current_install_dir.child("antnode");
+        current_node_bin.write_binary(b"current antnode binary")?;
+        let target_node_bin = tmp_data_dir.child("antnode");
+        target_node_bin.write_binary(b"target antnode binary")?;
+
+        let current_node_bin_str = current_node_bin.to_path_buf().to_string_lossy().to_string();
+
+        let mut mock_service_control = MockServiceControl::new();
+
+        // before binary upgrade
+        mock_service_control
+            .expect_get_process_pid()
+            .with(eq(current_node_bin.to_path_buf().clone()))
+            .times(1)
+            .returning(|_| Ok(1000));
+        mock_service_control
+            .expect_stop()
+            .with(eq("antnode1"), eq(false))
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        // after binary upgrade, then after the rollback
+        mock_service_control
+            .expect_uninstall()
+            .with(eq("antnode1"), eq(false))
+            .times(2)
+            .returning(|_, _| Ok(()));
+        mock_service_control
+            .expect_install()
+            .with(always(), always())
+            .times(2)
+            .returning(|_, _| Ok(()));
+        mock_service_control
+            .expect_start()
+            .with(eq("antnode1"), eq(false))
+            .times(2)
+            .returning(|_, _| Ok(()));
+        mock_service_control
+            .expect_wait()
+            .with(eq(3000))
+            .times(2)
+            .returning(|_| ());
+        mock_service_control
+            .expect_get_process_pid()
+            .with(eq(current_node_bin.to_path_buf().clone()))
+            .times(1)
+            .returning(move |_| {
+                Err(ServiceControlError::ServiceProcessNotFound(
+                    current_node_bin_str.clone(),
+                ))
+            });
+        mock_service_control
+            .expect_get_process_pid()
+            .with(eq(current_node_bin.to_path_buf().clone()))
+            .times(1)
+            .returning(|_| Ok(2000));
+
+        let mut mock_rpc_client = MockRpcClient::new();
+        mock_rpc_client.expect_node_info().times(1).returning(|| {
+            Ok(NodeInfo {
+                pid: 2000,
+                peer_id: PeerId::from_str("12D3KooWS2tpXGGTmg2AHFiDh57yPQnat49YHnyqoggzXZWpqkCR")?,
+                data_path: PathBuf::from("/var/antctl/services/antnode1"),
+                log_path: PathBuf::from("/var/log/antnode/antnode1"),
+                version: current_version.to_string(),
+                uptime: std::time::Duration::from_secs(1),
+                wallet_balance: 0,
+                top_talkers: vec![],
+            })
+        });
+        mock_rpc_client
+            .expect_network_info()
+            .times(1)
+            .returning(|| {
+                Ok(NetworkInfo {
+                    connected_peers: Vec::new(),
+                    listeners: Vec::new(),
+                    reachability: "public".to_string(),
+                })
+            });
+
+        let mut service_data = NodeServiceData {
+            auto_restart: false,
+            connected_peers: None,
+            data_dir_path: PathBuf::from("/var/antctl/services/antnode1"),
+            evm_network: EvmNetwork::Custom(CustomNetwork {
+                rpc_url_http: "http://localhost:8545".parse()?,
+                payment_token_address: RewardsAddress::from_str(
+                    "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                )?,
+                data_payments_address: RewardsAddress::from_str(
+                    "0x8464135c8F25Da09e49BC8782676a84730C318bC",
+                )?,
+            }),
+            home_network: false,
+            listen_addr: None,
+            log_dir_path: PathBuf::from("/var/log/antnode/antnode1"),
+            log_format: None,
+            max_archived_log_files: None,
+            max_log_files: None,
+            metrics_port: None,
+            network_id: None,
+            node_ip: None,
+            node_port: None,
+            number: 1,
+            owner: None,
+            peer_id: Some(PeerId::from_str(
+                "12D3KooWS2tpXGGTmg2AHFiDh57yPQnat49YHnyqoggzXZWpqkCR",
+            )?),
+            peers_args: PeersArgs::default(),
+            pid: Some(1000),
+            rewards_address: RewardsAddress::from_str(
+                "0x03B770D9cD32077cC0bF330c13C114a87643B124",
+            )?,
+            reward_balance: Some(AttoTokens::zero()),
+            rpc_socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8081),
+            antnode_path: current_node_bin.to_path_buf(),
+            service_name: "antnode1".to_string(),
+            status: ServiceStatus::Running,
+            upnp: false,
+            user: Some("ant".to_string()),
+            user_mode: false,
+            version: current_version.to_string(),
+        };
+        let service = NodeService::new(&mut service_data, Box::new(mock_rpc_client));
+        let mut service_manager = ServiceManager::new(
+            service,
+            Box::new(mock_service_control),
+            VerbosityLevel::Normal,
+        );
+
+        let upgrade_result = service_manager
+            .upgrade(UpgradeOptions {
+                auto_restart: false,
+                env_variables: None,
+                force: false,
+                rollback_on_failure: true,
+                start_service: true,
+                target_bin_path: target_node_bin.to_path_buf(),
+                target_version: Version::parse(target_version).unwrap(),
+            })
+            .await?;
+
+        match upgrade_result {
+            UpgradeResult::RolledBack(old_version, new_version, _) => {
+                assert_eq!(old_version, current_version);
+                assert_eq!(new_version, target_version);
+            }
+            _ => panic!(
+                "Expected UpgradeResult::RolledBack but was {:#?}",
+                upgrade_result
+            ),
+        }
+
+        current_node_bin.assert("current antnode binary");
+        current_install_dir
+            .child("antnode.previous")
+            .assert(predicate::path::missing());
+        assert_eq!(
+            service_manager.service.service_data.version,
+            current_version
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn upgrade_should_upgrade_a_service_in_user_mode() -> Result<()> {
         let current_version = "0.1.0";
@@ -2528,6 +2759,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -2537,6 +2769,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -2596,6 +2829,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -2715,6 +2949,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -2724,6 +2959,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -2751,6 +2987,8 @@ mod tests {
                 first: true,
                 addrs: vec![],
                 network_contacts_url: vec![],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: false,
                 disable_mainnet_contacts: false,
                 ignore_cache: false,
@@ -2783,6 +3021,7 @@ mod tests {
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -2887,6 +3126,7 @@ mod tests {
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -2896,6 +3136,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -2926,6 +3167,8 @@ network_id: None,
                         .parse()?,
                 ],
                 network_contacts_url: vec![],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: false,
                 disable_mainnet_contacts: false,
                 ignore_cache: false,
@@ -2958,6 +3201,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -3065,6 +3309,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -3074,6 +3319,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -3125,6 +3371,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -3226,6 +3473,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -3235,6 +3483,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -3262,6 +3511,8 @@ network_id: None,
                 first: false,
                 addrs: vec![],
                 network_contacts_url: vec![],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: true,
                 disable_mainnet_contacts: false,
                 ignore_cache: false,
@@ -3294,6 +3545,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -3396,6 +3648,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -3405,6 +3658,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -3435,6 +3689,8 @@ network_id: None,
                     "http://localhost:8080/contacts.json".to_string(),
                     "http://localhost:8081/contacts.json".to_string(),
                 ],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: false,
                 disable_mainnet_contacts: false,
                 ignore_cache: false,
@@ -3467,6 +3723,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -3576,6 +3833,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -3585,6 +3843,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -3612,6 +3871,8 @@ network_id: None,
                 first: false,
                 addrs: vec![],
                 network_contacts_url: vec![],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: false,
                 disable_mainnet_contacts: true,
                 ignore_cache: false,
@@ -3644,6 +3905,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -3751,6 +4013,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -3760,6 +4023,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -3787,6 +4051,8 @@ network_id: None,
                 first: false,
                 addrs: vec![],
                 network_contacts_url: vec![],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: false,
                 disable_mainnet_contacts: false,
                 ignore_cache: true,
@@ -3819,6 +4085,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -3921,6 +4188,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -3930,6 +4198,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -3957,6 +4226,8 @@ network_id: None,
                 first: false,
                 addrs: vec![],
                 network_contacts_url: vec![],
+                dns_seeds: vec![],
+                skip_contacts_verification: false,
                 local: false,
                 disable_mainnet_contacts: false,
                 ignore_cache: false,
@@ -3991,6 +4262,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -4101,6 +4373,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -4110,6 +4383,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -4161,6 +4435,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -4263,6 +4538,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -4272,6 +4548,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -4323,6 +4600,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -4428,6 +4706,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -4437,6 +4716,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -4488,6 +4768,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -4590,6 +4871,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -4599,6 +4881,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -4650,6 +4933,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -4755,6 +5039,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -4764,6 +5049,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -4815,6 +5101,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -4917,6 +5204,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -4926,6 +5214,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -4977,6 +5266,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -5082,6 +5372,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -5091,6 +5382,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -5142,6 +5434,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -5244,6 +5537,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -5253,6 +5547,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -5304,6 +5599,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -5409,6 +5705,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -5418,6 +5715,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -5469,6 +5767,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -5574,6 +5873,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -5583,6 +5883,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -5634,6 +5935,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -5739,6 +6041,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -5748,6 +6051,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -5799,6 +6103,7 @@ network_id: None,
                 auto_restart: true,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -5907,6 +6212,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -5916,6 +6222,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -5976,6 +6283,7 @@ network_id: None,
                 auto_restart: true,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -6084,6 +6392,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -6093,6 +6402,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -6153,6 +6463,7 @@ network_id: None,
                 auto_restart: true,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
@@ -6257,6 +6568,7 @@ network_id: None,
                 version: target_version.to_string(),
                 uptime: std::time::Duration::from_secs(1), // the service was just started
                 wallet_balance: 0,
+                top_talkers: vec![],
             })
         });
         mock_rpc_client
@@ -6266,6 +6578,7 @@ network_id: None,
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -6320,6 +6633,7 @@ network_id: None,
                 auto_restart: false,
                 env_variables: None,
                 force: false,
+                rollback_on_failure: false,
                 start_service: true,
                 target_bin_path: target_node_bin.to_path_buf(),
                 target_version: Version::parse(target_version).unwrap(),
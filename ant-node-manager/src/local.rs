@@ -445,6 +445,8 @@ pub async fn run_node(
             first: run_options.first,
             addrs: vec![],
             network_contacts_url: vec![],
+            dns_seeds: vec![],
+            skip_contacts_verification: false,
             local: true,
             disable_mainnet_contacts: true,
             ignore_cache: true,
@@ -531,7 +533,9 @@ mod tests {
     use ant_evm::utils::dummy_address;
     use ant_service_management::{
         error::Result as RpcResult,
-        rpc::{NetworkInfo, NodeInfo, RecordAddress, RpcActions},
+        rpc::{
+            DriverState, NetworkInfo, NodeInfo, PeerScore, RecordAddress, RecordsPage, RpcActions,
+        },
     };
     use async_trait::async_trait;
     use libp2p_identity::PeerId;
@@ -548,6 +552,10 @@ mod tests {
             async fn record_addresses(&self) -> RpcResult<Vec<RecordAddress>>;
             async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> RpcResult<()>;
             async fn node_stop(&self, delay_millis: u64) -> RpcResult<()>;
+            async fn peer_scores(&self) -> RpcResult<Vec<PeerScore>>;
+            async fn driver_state(&self) -> RpcResult<DriverState>;
+            async fn list_records(&self, record_type: Option<String>, offset: u64, limit: u64) -> RpcResult<RecordsPage>;
+            async fn node_decommission(&self, timeout_millis: u64) -> RpcResult<()>;
             async fn node_update(&self, delay_millis: u64) -> RpcResult<()>;
             async fn is_node_connected_to_network(&self, timeout: std::time::Duration) -> RpcResult<()>;
             async fn update_log_level(&self, log_levels: String) -> RpcResult<()>;
@@ -598,6 +606,7 @@ mod tests {
                     version: "0.100.12".to_string(),
                     uptime: std::time::Duration::from_secs(1), // the service was just started
                     wallet_balance: 0,
+                    top_talkers: vec![],
                 })
             });
         mock_rpc_client
@@ -607,6 +616,7 @@ mod tests {
                 Ok(NetworkInfo {
                     connected_peers: Vec::new(),
                     listeners: Vec::new(),
+                    reachability: "public".to_string(),
                 })
             });
 
@@ -0,0 +1,183 @@
+// Copyright (C) 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use chrono::{Local, NaiveTime};
+use std::{str::FromStr, time::Duration};
+
+/// A daily time range, in local time, during which the services can be upgraded.
+///
+/// The range can go over midnight, e.g. `22:00-02:00`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaintenanceWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = String;
+
+    /// Parse a window like `02:00-05:00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("The window '{s}' should be like '02:00-05:00'"))?;
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|err| format!("Invalid time '{time}' in the window '{s}': {err}"))
+        };
+        let window = Self {
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        };
+        if window.start == window.end {
+            return Err(format!("The window '{s}' is empty"));
+        }
+        Ok(window)
+    }
+}
+
+impl std::fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl MaintenanceWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+
+    /// How long until the window opens, zero if it's open.
+    pub fn time_until_open(&self, time: NaiveTime) -> Duration {
+        if self.contains(time) {
+            return Duration::ZERO;
+        }
+        let until = self.start.signed_duration_since(time);
+        let until = if until < chrono::Duration::zero() {
+            until + chrono::Duration::days(1)
+        } else {
+            until
+        };
+        until.to_std().unwrap_or_default()
+    }
+}
+
+/// How the services are spread out over time when they are upgraded.
+///
+/// By default, they are all upgraded straight away, one after the other.
+#[derive(Clone, Debug, Default)]
+pub struct UpgradeSchedule {
+    /// The percentage of the services to upgrade in each batch.
+    pub batch_percent: Option<u8>,
+    /// The time to wait after a batch, before upgrading the next one.
+    pub batch_interval: Duration,
+    /// The services are only upgraded within this window, waiting for it to open otherwise.
+    pub window: Option<MaintenanceWindow>,
+}
+
+impl UpgradeSchedule {
+    /// Split the services into the batches to upgrade, keeping their order.
+    pub fn batches<T: Clone>(&self, services: &[T]) -> Vec<Vec<T>> {
+        let batch_size = match self.batch_percent {
+            Some(percent) => (services.len() * percent as usize).div_ceil(100).max(1),
+            None => services.len().max(1),
+        };
+        services
+            .chunks(batch_size)
+            .map(|batch| batch.to_vec())
+            .collect()
+    }
+
+    /// Wait for the maintenance window to open, if there is one.
+    pub async fn wait_for_window(&self, verbose: bool) {
+        let Some(window) = self.window else {
+            return;
+        };
+        let wait = window.time_until_open(Local::now().time());
+        if wait.is_zero() {
+            return;
+        }
+        info!("Waiting {wait:?} for the maintenance window {window} to open");
+        if verbose {
+            println!(
+                "Waiting {} minute(s) for the maintenance window {window} to open...",
+                wait.as_secs().div_ceil(60)
+            );
+        }
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).expect("valid time")
+    }
+
+    #[test]
+    fn windows_can_go_over_midnight() -> Result<(), String> {
+        let night: MaintenanceWindow = "02:00-05:00".parse()?;
+        assert!(night.contains(time(2, 0)));
+        assert!(night.contains(time(4, 59)));
+        assert!(!night.contains(time(5, 0)));
+        assert_eq!(night.time_until_open(time(3, 0)), Duration::ZERO);
+        assert_eq!(
+            night.time_until_open(time(1, 30)),
+            Duration::from_secs(30 * 60)
+        );
+        assert_eq!(
+            night.time_until_open(time(6, 0)),
+            Duration::from_secs(20 * 3600)
+        );
+
+        let over_midnight: MaintenanceWindow = "22:00-02:00".parse()?;
+        assert!(over_midnight.contains(time(23, 0)));
+        assert!(over_midnight.contains(time(1, 0)));
+        assert!(!over_midnight.contains(time(12, 0)));
+
+        assert!("02:00".parse::<MaintenanceWindow>().is_err());
+        assert!("02:00-25:00".parse::<MaintenanceWindow>().is_err());
+        assert!("02:00-02:00".parse::<MaintenanceWindow>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn services_are_split_into_batches_of_the_given_percentage() {
+        let services: Vec<usize> = (0..25).collect();
+        let batches = UpgradeSchedule {
+            batch_percent: Some(10),
+            ..Default::default()
+        }
+        .batches(&services);
+        assert_eq!(batches.len(), 9);
+        assert_eq!(batches[0], vec![0, 1, 2]);
+        assert_eq!(batches[8], vec![24]);
+
+        assert_eq!(UpgradeSchedule::default().batches(&services).len(), 1);
+        assert_eq!(
+            UpgradeSchedule {
+                batch_percent: Some(1),
+                ..Default::default()
+            }
+            .batches(&services[..3])
+            .len(),
+            3
+        );
+    }
+}
@@ -9,19 +9,43 @@
 
 use ant_logging::{Level, LogBuilder};
 use ant_node::NodeEvent;
-use ant_protocol::antnode_proto::{ant_node_client::AntNodeClient, NodeEventsRequest};
-use ant_service_management::rpc::{RpcActions, RpcClient};
+use ant_protocol::antnode_proto::NodeEventsRequest;
+use ant_service_management::rpc::{ChaosSettings, RpcActions, RpcClient};
 use clap::Parser;
 use color_eyre::eyre::Result;
-use std::{net::SocketAddr, time::Duration};
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
 use tokio_stream::StreamExt;
-use tonic::Request;
+use tonic::{
+    transport::{Certificate, ClientTlsConfig, Identity},
+    Request,
+};
 
 #[derive(Parser, Debug)]
 #[command(disable_version_flag = true)]
 struct Opt {
     /// Address of the node's RPC service, e.g. 127.0.0.1:12001.
     addr: SocketAddr,
+
+    /// File with the token to present to the mutating RPCs, for nodes started with an RPC token.
+    #[clap(long)]
+    token_file: Option<PathBuf>,
+
+    /// Connect over TLS, verifying the node's certificate against this PEM encoded CA.
+    #[clap(long)]
+    tls_ca: Option<PathBuf>,
+
+    /// The name the node's TLS certificate is issued for.
+    #[clap(long, default_value = "localhost", requires = "tls_ca")]
+    tls_domain: String,
+
+    /// Present this PEM encoded client certificate, for nodes requiring mutual TLS.
+    #[clap(long, requires_all = ["tls_ca", "tls_key"])]
+    tls_cert: Option<PathBuf>,
+
+    /// The PEM encoded private key of the client certificate.
+    #[clap(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
     /// subcommands
     #[clap(subcommand)]
     cmd: Cmd,
@@ -52,6 +76,32 @@ enum Cmd {
     /// Note this blocks the app and it will print events as they are broadcasted by the node
     #[clap(name = "events")]
     Events,
+    /// List the records held by the node, sorted by their distance to the node
+    #[clap(name = "records")]
+    Records {
+        /// Only list the records of this type: chunk, transaction, register, scratchpad or pointer
+        #[clap(long)]
+        record_type: Option<String>,
+        /// Number of records to skip
+        #[clap(long, default_value = "0")]
+        offset: u64,
+        /// Max number of records to list, all of them if 0
+        #[clap(long, default_value = "100")]
+        limit: u64,
+    },
+    /// Retrieve the scores of the peers that misbehaved, highest first
+    #[clap(name = "scores")]
+    Scores,
+    /// Retrieve the sizes of the internal state of the node's networking layer
+    #[clap(name = "driver-state")]
+    DriverState,
+    /// Retrieve the latest entries of the audit log of the records stored and removed by the node
+    #[clap(name = "audit-log")]
+    AuditLog {
+        /// Max number of entries to retrieve
+        #[clap(long, default_value = "100")]
+        count: u64,
+    },
     /// Restart the node after the specified delay
     #[clap(name = "restart")]
     Restart {
@@ -68,6 +118,13 @@ enum Cmd {
         #[clap(default_value = "0")]
         delay_millis: u64,
     },
+    /// Hand off the node's records to their close groups, then stop the node
+    #[clap(name = "decommission")]
+    Decommission {
+        /// Max time in milliseconds to wait for the records to be handed off
+        #[clap(default_value = "300000")]
+        timeout_millis: u64,
+    },
     /// Update to latest `antnode` released version, and restart it
     #[clap(name = "update")]
     Update {
@@ -85,6 +142,23 @@ enum Cmd {
         #[clap(name = "level", long)]
         log_level: String,
     },
+    /// Replace the faults the node injects on purpose, for nodes built with the chaos feature.
+    /// Passing no flags stops injecting faults.
+    #[clap(name = "chaos")]
+    Chaos {
+        /// Percentage, 0 to 100, of the inbound requests to drop without a response
+        #[clap(long, default_value = "0")]
+        drop_requests_percent: u8,
+        /// Delay in milliseconds before fetching the keys to replicate
+        #[clap(long, default_value = "0")]
+        replication_delay_millis: u64,
+        /// Serve the chunks corrupted
+        #[clap(long)]
+        corrupt_chunks: bool,
+        /// Refuse to quote for storing records
+        #[clap(long)]
+        refuse_quotes: bool,
+    },
 }
 
 #[tokio::main]
@@ -121,46 +195,91 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    let addr = opt.addr;
+    let mut client = RpcClient::from_socket_addr(opt.addr);
+    if let Some(token_file) = &opt.token_file {
+        client.set_auth_token(std::fs::read_to_string(token_file)?.trim().to_string());
+    }
+    if let Some(tls_ca) = &opt.tls_ca {
+        let mut tls_config = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(std::fs::read(tls_ca)?))
+            .domain_name(opt.tls_domain.clone());
+        if let (Some(cert), Some(key)) = (&opt.tls_cert, &opt.tls_key) {
+            tls_config = tls_config.identity(Identity::from_pem(
+                std::fs::read(cert)?,
+                std::fs::read(key)?,
+            ));
+        }
+        client.set_tls_config(tls_config);
+    }
+    let client = &client;
 
     match opt.cmd {
-        Cmd::Info => node_info(addr).await,
-        Cmd::Netinfo => network_info(addr).await,
-        Cmd::Events => node_events(addr).await,
+        Cmd::Info => node_info(client).await,
+        Cmd::Netinfo => network_info(client).await,
+        Cmd::Events => node_events(client).await,
+        Cmd::Records {
+            record_type,
+            offset,
+            limit,
+        } => list_records(client, record_type, offset, limit).await,
+        Cmd::Scores => peer_scores(client).await,
+        Cmd::DriverState => driver_state(client).await,
+        Cmd::AuditLog { count } => audit_log(client, count).await,
         Cmd::Restart {
             delay_millis,
             retain_peer_id,
-        } => node_restart(addr, delay_millis, retain_peer_id).await,
-        Cmd::Stop { delay_millis } => node_stop(addr, delay_millis).await,
-        Cmd::Update { delay_millis } => node_update(addr, delay_millis).await,
-        Cmd::Log { log_level } => update_log_level(addr, log_level).await,
+        } => node_restart(client, delay_millis, retain_peer_id).await,
+        Cmd::Stop { delay_millis } => node_stop(client, delay_millis).await,
+        Cmd::Decommission { timeout_millis } => node_decommission(client, timeout_millis).await,
+        Cmd::Update { delay_millis } => node_update(client, delay_millis).await,
+        Cmd::Log { log_level } => update_log_level(client, log_level).await,
+        Cmd::Chaos {
+            drop_requests_percent,
+            replication_delay_millis,
+            corrupt_chunks,
+            refuse_quotes,
+        } => {
+            let settings = ChaosSettings {
+                drop_requests_percent: drop_requests_percent.min(100),
+                replication_delay: Duration::from_millis(replication_delay_millis),
+                corrupt_chunks,
+                refuse_quotes,
+            };
+            set_chaos(client, settings).await
+        }
     }
 }
 
-pub async fn node_info(addr: SocketAddr) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
+pub async fn node_info(client: &RpcClient) -> Result<()> {
     let node_info = client.node_info().await?;
 
     println!("Node info:");
     println!("==========");
-    println!("RPC endpoint: {endpoint}");
+    println!("RPC endpoint: {}", client.endpoint());
     println!("Peer Id: {}", node_info.peer_id);
     println!("Logs dir: {}", node_info.log_path.to_string_lossy());
     println!("PID: {}", node_info.pid);
     println!("Binary version: {}", node_info.version);
     println!("Time since last restart: {:?}", node_info.uptime);
+    println!();
+    println!("Top talkers:");
+    for bandwidth in node_info.top_talkers.iter() {
+        println!(
+            "Peer {} over {}: {} bytes sent, {} bytes received",
+            bandwidth.peer_id, bandwidth.protocol, bandwidth.bytes_sent, bandwidth.bytes_received
+        );
+    }
 
     Ok(())
 }
 
-pub async fn network_info(addr: SocketAddr) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
+pub async fn network_info(client: &RpcClient) -> Result<()> {
     let network_info = client.network_info().await?;
 
     println!("Node's connections to the Network:");
     println!();
+    println!("Reachability: {}", network_info.reachability);
+    println!();
     println!("Connected peers:");
     for peer_id in network_info.connected_peers.iter() {
         println!("Peer: {peer_id}");
@@ -175,9 +294,8 @@ pub async fn network_info(addr: SocketAddr) -> Result<()> {
     Ok(())
 }
 
-pub async fn node_events(addr: SocketAddr) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let mut client = AntNodeClient::connect(endpoint).await?;
+pub async fn node_events(client: &RpcClient) -> Result<()> {
+    let mut client = client.connect_with_retry().await?;
     let response = client
         .node_events(Request::new(NodeEventsRequest {}))
         .await?;
@@ -197,9 +315,7 @@ pub async fn node_events(addr: SocketAddr) -> Result<()> {
     Ok(())
 }
 
-pub async fn record_addresses(addr: SocketAddr) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
+pub async fn record_addresses(client: &RpcClient) -> Result<()> {
     let record_addresses = client.record_addresses().await?;
 
     println!("Records held by the node:");
@@ -210,9 +326,98 @@ pub async fn record_addresses(addr: SocketAddr) -> Result<()> {
     Ok(())
 }
 
-pub async fn node_restart(addr: SocketAddr, delay_millis: u64, retain_peer_id: bool) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
+pub async fn list_records(
+    client: &RpcClient,
+    record_type: Option<String>,
+    offset: u64,
+    limit: u64,
+) -> Result<()> {
+    let page = client.list_records(record_type, offset, limit).await?;
+
+    println!(
+        "Records {}-{} of {} held by the node:",
+        offset,
+        offset + page.records.len() as u64,
+        page.total
+    );
+    for record in page.records.iter() {
+        println!(
+            "Key: {:?}, type: {}, size: {} bytes, distance: {}",
+            record.key, record.record_type, record.size, record.distance
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn peer_scores(client: &RpcClient) -> Result<()> {
+    let peer_scores = client.peer_scores().await?;
+
+    println!("Scores of the peers that misbehaved:");
+    for peer in peer_scores.iter() {
+        println!(
+            "Peer {}: score {:.2}, bad: {}, last issue: {}",
+            peer.peer_id, peer.score, peer.is_bad, peer.last_issue
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn driver_state(client: &RpcClient) -> Result<()> {
+    let state = client.driver_state().await?;
+
+    println!("Driver state:");
+    println!(
+        "Pending get_closest_peers: {}",
+        state.pending_get_closest_peers
+    );
+    println!("Pending requests: {}", state.pending_requests);
+    println!("Pending get_record: {}", state.pending_get_record);
+    println!("Pending put_record: {}", state.pending_put_record);
+    println!("Pending dials: {}", state.pending_dials);
+    println!(
+        "Replication: {} pending keys, {} ongoing fetches, oldest pending for {:?}",
+        state.replication_pending_keys,
+        state.replication_ongoing_fetches,
+        state.replication_oldest_pending
+    );
+    println!(
+        "Cmd queue depths: local {}, network {}",
+        state.local_cmd_queue_depth, state.network_cmd_queue_depth
+    );
+    println!("Peers per kbucket:");
+    for (distance, peers) in state.peers_per_kbucket.iter() {
+        println!("{distance}: {peers}");
+    }
+
+    Ok(())
+}
+
+pub async fn audit_log(client: &RpcClient, count: u64) -> Result<()> {
+    let entries = client.audit_log(count).await?;
+
+    println!("Latest {} entries of the audit log:", entries.len());
+    for entry in entries.iter() {
+        println!(
+            "{} {} {} type: {}, source: {}, reason: {}",
+            entry.timestamp,
+            entry.action,
+            entry.key,
+            entry.record_type.as_deref().unwrap_or("-"),
+            entry.source.as_deref().unwrap_or("-"),
+            entry.reason.as_deref().unwrap_or("-"),
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn node_restart(
+    client: &RpcClient,
+    delay_millis: u64,
+    retain_peer_id: bool,
+) -> Result<()> {
     client.node_restart(delay_millis, retain_peer_id).await?;
     println!(
         "Node successfully received the request to restart in {:?}",
@@ -221,9 +426,7 @@ pub async fn node_restart(addr: SocketAddr, delay_millis: u64, retain_peer_id: b
     Ok(())
 }
 
-pub async fn node_stop(addr: SocketAddr, delay_millis: u64) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
+pub async fn node_stop(client: &RpcClient, delay_millis: u64) -> Result<()> {
     client.node_stop(delay_millis).await?;
     println!(
         "Node successfully received the request to stop in {:?}",
@@ -232,9 +435,16 @@ pub async fn node_stop(addr: SocketAddr, delay_millis: u64) -> Result<()> {
     Ok(())
 }
 
-pub async fn node_update(addr: SocketAddr, delay_millis: u64) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
+pub async fn node_decommission(client: &RpcClient, timeout_millis: u64) -> Result<()> {
+    client.node_decommission(timeout_millis).await?;
+    println!(
+        "Node successfully received the request to decommission within {:?}",
+        Duration::from_millis(timeout_millis)
+    );
+    Ok(())
+}
+
+pub async fn node_update(client: &RpcClient, delay_millis: u64) -> Result<()> {
     client.node_update(delay_millis).await?;
     println!(
         "Node successfully received the request to try to update in {:?}",
@@ -243,11 +453,14 @@ pub async fn node_update(addr: SocketAddr, delay_millis: u64) -> Result<()> {
     Ok(())
 }
 
-pub async fn update_log_level(addr: SocketAddr, log_levels: String) -> Result<()> {
-    let endpoint = format!("https://{addr}");
-    let client = RpcClient::new(&endpoint);
-
+pub async fn update_log_level(client: &RpcClient, log_levels: String) -> Result<()> {
     client.update_log_level(log_levels.clone()).await?;
     println!("Node successfully received the request to update the log level to {log_levels:?}",);
     Ok(())
 }
+
+pub async fn set_chaos(client: &RpcClient, settings: ChaosSettings) -> Result<()> {
+    client.set_chaos(&settings).await?;
+    println!("Node successfully received the request to inject the faults {settings:?}");
+    Ok(())
+}
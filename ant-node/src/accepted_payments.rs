@@ -0,0 +1,69 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_protocol::NetworkAddress;
+use std::collections::{HashSet, VecDeque};
+use xor_name::XorName;
+
+/// The number of accepted payments kept at most, the oldest ones being dropped first.
+const MAX_ACCEPTED_PAYMENTS: usize = 16 * 1024;
+
+/// The payments we recently verified for the records put to us, for the storage receipts to only vouch for those.
+#[derive(Debug, Default)]
+pub(crate) struct AcceptedPayments {
+    payments: HashSet<(NetworkAddress, XorName)>,
+    order: VecDeque<(NetworkAddress, XorName)>,
+}
+
+impl AcceptedPayments {
+    pub(crate) fn insert(&mut self, address: NetworkAddress, payment_hash: XorName) {
+        let key = (address, payment_hash);
+        if !self.payments.insert(key.clone()) {
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > MAX_ACCEPTED_PAYMENTS {
+            if let Some(oldest) = self.order.pop_front() {
+                let _ = self.payments.remove(&oldest);
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, address: &NetworkAddress, payment_hash: &XorName) -> bool {
+        self.payments.contains(&(address.clone(), *payment_hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::storage::ChunkAddress;
+
+    fn address(index: usize) -> NetworkAddress {
+        NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::from_content(
+            &index.to_be_bytes(),
+        )))
+    }
+
+    #[test]
+    fn only_the_latest_accepted_payments_are_kept() {
+        let mut accepted = AcceptedPayments::default();
+        let payment_hash = XorName::from_content(b"payment");
+        accepted.insert(address(0), payment_hash);
+        assert!(accepted.contains(&address(0), &payment_hash));
+        // Another payment than the one verified is not vouched for.
+        assert!(!accepted.contains(&address(0), &XorName::from_content(b"other payment")));
+
+        for index in 1..=MAX_ACCEPTED_PAYMENTS {
+            accepted.insert(address(index), payment_hash);
+        }
+        assert!(!accepted.contains(&address(0), &payment_hash));
+        assert!(accepted.contains(&address(MAX_ACCEPTED_PAYMENTS), &payment_hash));
+        assert_eq!(accepted.payments.len(), MAX_ACCEPTED_PAYMENTS);
+    }
+}
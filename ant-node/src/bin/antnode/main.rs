@@ -12,13 +12,21 @@ extern crate tracing;
 mod rpc_service;
 mod subcommands;
 
-use crate::subcommands::EvmNetworkCommand;
+use crate::{rpc_service::RpcAuth, subcommands::EvmNetworkCommand};
 use ant_bootstrap::{BootstrapCacheConfig, BootstrapCacheStore, PeersArgs};
 use ant_evm::{get_evm_network_from_env, EvmNetwork, RewardsAddress};
 #[cfg(feature = "local")]
 use ant_logging::metrics::init_metrics;
 use ant_logging::{Level, LogFormat, LogOutputDest, ReloadHandle};
-use ant_node::{Marker, NodeBuilder, NodeEvent, NodeEventsReceiver};
+use ant_networking::{
+    ConnectionLimits, EvictionPolicy, PeerAccessList, RateLimitConfig, RecordCategory,
+    RecordEncryption, RecordStoreBackendKind, RelayServerConfig, RequestBudget, RequestKind,
+    SybilGuardConfig, WebSocketConfig, WebSocketTls,
+};
+use ant_node::{
+    Marker, NodeBuilder, NodeEvent, NodeEventsReceiver, ReplicationConfig, RewardsReport,
+    RunningNode,
+};
 use ant_protocol::{
     node::get_antnode_root_dir,
     node_rpc::{NodeCtrl, StopResult},
@@ -31,7 +39,7 @@ use libp2p::{identity::Keypair, PeerId};
 use std::{
     env,
     io::Write,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     path::{Path, PathBuf},
     process::Command,
     time::Duration,
@@ -71,6 +79,37 @@ pub fn parse_log_output(val: &str) -> Result<LogOutputDestArg> {
     }
 }
 
+/// Parse a storage quota given as TYPE=BYTES
+fn parse_storage_quota(val: &str) -> Result<(RecordCategory, usize)> {
+    let (category, bytes) = val
+        .split_once('=')
+        .ok_or_else(|| eyre!("Storage quota must be given as TYPE=BYTES"))?;
+    let category = category
+        .parse::<RecordCategory>()
+        .map_err(|err| eyre!(err))?;
+    let bytes = bytes
+        .parse::<usize>()
+        .map_err(|err| eyre!("Invalid number of bytes {bytes:?}: {err}"))?;
+    Ok((category, bytes))
+}
+
+fn parse_request_rate_limit(val: &str) -> Result<(RequestKind, RequestBudget)> {
+    let (kind, budget) = val
+        .split_once('=')
+        .ok_or_else(|| eyre!("Request rate limit must be given as KIND=PER_PEER/GLOBAL"))?;
+    let kind = kind.parse::<RequestKind>().map_err(|err| eyre!(err))?;
+    let (per_peer, global) = budget
+        .split_once('/')
+        .ok_or_else(|| eyre!("Request rate limit must be given as KIND=PER_PEER/GLOBAL"))?;
+    let per_peer = per_peer
+        .parse::<u32>()
+        .map_err(|err| eyre!("Invalid per peer budget {per_peer:?}: {err}"))?;
+    let global = global
+        .parse::<u32>()
+        .map_err(|err| eyre!("Invalid global budget {global:?}: {err}"))?;
+    Ok((kind, RequestBudget { per_peer, global }))
+}
+
 // Please do not remove the blank lines in these doc comments.
 // They are used for inserting line breaks when the help menu is rendered in the UI.
 #[derive(Parser, Debug)]
@@ -80,13 +119,17 @@ struct Opt {
     /// Specify whether the node is operating from a home network and situated behind a NAT without port forwarding
     /// capabilities. Setting this to true, activates hole-punching to facilitate direct connections from other nodes.
     ///
-    /// If this not enabled and you're behind a NAT, the node is terminated.
+    /// If this is not enabled, the node probes whether it's reachable from the public internet through the other nodes,
+    /// and switches to the home network mode by itself once it's found to be behind a NAT.
     #[clap(long, default_value_t = false)]
     home_network: bool,
 
     /// Try to use UPnP to open a port in the home router and allow incoming connections.
+    ///
+    /// The mapping is renewed for as long as the node runs, and the mapped address is advertised to the
+    /// other nodes. If the router doesn't support UPnP, the node switches to the home network mode.
     #[cfg(feature = "upnp")]
-    #[clap(long, default_value_t = false)]
+    #[clap(long, default_value_t = false, verbatim_doc_comment)]
     upnp: bool,
 
     /// Specify the logging output destination.
@@ -166,9 +209,17 @@ struct Opt {
     /// Specify the IP to listen on.
     ///
     /// The special value `0.0.0.0` binds to all network interfaces available.
-    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::UNSPECIFIED))]
+    /// Pass an IPv6, e.g. `::`, to only listen on IPv6.
+    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::UNSPECIFIED), verbatim_doc_comment)]
     ip: IpAddr,
 
+    /// Also listen on this IPv6, on the same port, to be reachable over both IPv4 and IPv6.
+    ///
+    /// The special value `::` binds to all network interfaces available.
+    /// Peers are then dialed over the IP version they were reached with the most so far.
+    #[clap(long, verbatim_doc_comment)]
+    ipv6: Option<Ipv6Addr>,
+
     #[command(flatten)]
     peers: PeersArgs,
 
@@ -178,6 +229,25 @@ struct Opt {
     #[clap(long)]
     rpc: Option<SocketAddr>,
 
+    /// Require the callers of the mutating RPCs (stop, restart, update...) to present the token
+    /// read from this file, as an `authorization: Bearer TOKEN` header.
+    #[clap(long, requires = "rpc")]
+    rpc_token_file: Option<PathBuf>,
+
+    /// Serve the RPC service over TLS, with this PEM encoded certificate.
+    ///
+    /// TLS only encrypts the connections, the callers are authenticated by a client CA or a token.
+    #[clap(long, requires_all = ["rpc", "rpc_tls_key"])]
+    rpc_tls_cert: Option<PathBuf>,
+
+    /// The PEM encoded private key of the RPC TLS certificate.
+    #[clap(long, requires = "rpc_tls_cert")]
+    rpc_tls_key: Option<PathBuf>,
+
+    /// Require the RPC clients to present a certificate signed by this PEM encoded CA, i.e. mutual TLS.
+    #[clap(long, requires = "rpc_tls_cert")]
+    rpc_tls_client_ca: Option<PathBuf>,
+
     /// Specify the owner(readable discord user name).
     #[clap(long)]
     owner: Option<String>,
@@ -201,6 +271,109 @@ struct Opt {
     )]
     enable_metrics_server: bool,
 
+    #[cfg(feature = "otlp")]
+    /// Export the traces to this OpenTelemetry collector (OTLP over gRPC), e.g. `http://localhost:4317`.
+    ///
+    /// Overrides the `OTEL_EXPORTER_OTLP_ENDPOINT` env variable. The traces are exported only if either is set,
+    /// with the peer id and the port of the node as resource attributes.
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+
+    #[cfg(feature = "otlp")]
+    /// The service name to export the traces under.
+    ///
+    /// Overrides the `OTLP_SERVICE_NAME` env variable. A random name is used if neither is set.
+    #[clap(long)]
+    otlp_service_name: Option<String>,
+
+    /// Keep talking to the peers running the previous or next minor protocol version.
+    ///
+    /// This is meant for rolling upgrades, for the network not to split between the upgraded nodes
+    /// and the others.
+    #[clap(long)]
+    protocol_compat: bool,
+
+    /// The max interval of the periodic replication of all the records, in seconds.
+    ///
+    /// Each node picks an interval between half this and this. Defaults to 180 seconds.
+    #[clap(long, value_name = "SECONDS")]
+    replication_interval: Option<u64>,
+
+    /// The min time between two replications triggered by peers joining or leaving the close
+    /// group, in seconds. Defaults to 10 seconds.
+    #[clap(long, value_name = "SECONDS")]
+    churn_replication_interval: Option<u64>,
+
+    /// The max number of record keys pushed to a peer per replication triggered by peers joining
+    /// or leaving the close group. The rest is left to the periodic replication. Defaults to 1024.
+    #[clap(long, value_name = "KEYS")]
+    replication_burst_limit: Option<usize>,
+
+    /// Expect the peers that notified us of holding a record through replication to return it
+    /// when we fetch it, and report those that don't as a node issue.
+    #[clap(long)]
+    auto_expected_holders: bool,
+
+    /// Volunteer to hold, for free, the chunks the clients push to us beyond our close group
+    /// responsibility, as an extra durability tier for cold data. Only worth it with ample disk.
+    #[clap(long)]
+    archive: bool,
+
+    /// Keep an audit log of the records stored and removed by the node, capped at this size in MB.
+    ///
+    /// The log is kept as `record_audit.jsonl` in the root dir, one JSON entry per line, and can
+    /// be tailed through the AuditLog RPC. Not kept by default.
+    #[clap(long, verbatim_doc_comment)]
+    audit_log_max_mb: Option<u64>,
+
+    /// The maximum number of live connections, unlimited by default.
+    ///
+    /// Once over a limit, the idle connections to the farthest peers are closed first. The
+    /// connections to the close group and to the peers being talked to are always kept.
+    #[clap(long, verbatim_doc_comment)]
+    max_connections: Option<usize>,
+
+    /// The maximum number of live connections to a single IP, unlimited by default.
+    #[clap(long)]
+    max_connections_per_ip: Option<usize>,
+
+    /// The maximum number of live connections dialed by the peers, unlimited by default.
+    #[clap(long)]
+    max_inbound_connections: Option<usize>,
+
+    /// The maximum number of live connections dialed by the node, unlimited by default.
+    #[clap(long)]
+    max_outbound_connections: Option<usize>,
+
+    /// The maximum number of peers of a same IP in a kbucket, unlimited by default.
+    ///
+    /// The peers over the limit are not added to the routing table, for a single host not to
+    /// take over a part of the address space. Not applied in local mode.
+    #[clap(long, verbatim_doc_comment)]
+    max_peers_per_ip_per_kbucket: Option<usize>,
+
+    /// The maximum number of peers of a same /24 IPv4 or /48 IPv6 subnet in a kbucket,
+    /// unlimited by default.
+    #[clap(long, verbatim_doc_comment)]
+    max_peers_per_subnet_per_kbucket: Option<usize>,
+
+    /// Flag a sybil attack once this many new peers joined our close group within the burst
+    /// window, not monitored by default.
+    #[clap(long, verbatim_doc_comment)]
+    close_group_burst: Option<usize>,
+
+    /// The window of the close group bursts, in seconds, 10 minutes by default.
+    #[clap(long)]
+    close_group_burst_window: Option<u64>,
+
+    /// Persist the routing table and the pending replication when the node is stopped or
+    /// restarted, and restore them on start.
+    ///
+    /// A node restarted within 30 minutes, e.g. for an upgrade, is then back serving its
+    /// records at once, instead of re-discovering the network first.
+    #[clap(long, verbatim_doc_comment)]
+    warm_standby: bool,
+
     /// Print the crate version.
     #[clap(long)]
     crate_version: bool,
@@ -217,6 +390,143 @@ struct Opt {
     /// Print version information.
     #[clap(long)]
     version: bool,
+
+    /// Limit the total size of the records of a type the node stores, given as TYPE=BYTES.
+    ///
+    /// The type is one of `chunk`, `transaction`, `register`, `scratchpad` or `pointer`.
+    /// Can be repeated to set the quota of several types. Types without a quota are unlimited.
+    #[clap(long = "storage-quota", value_parser = parse_storage_quota, verbatim_doc_comment)]
+    storage_quotas: Vec<(RecordCategory, usize)>,
+
+    /// How to make room for new records once the record store or a storage quota is full.
+    ///
+    /// `farthest-first` evicts the records farthest from the node, when the new record is closer.
+    /// `reject-new` never evicts and rejects the new record instead.
+    #[clap(long, default_value = "farthest-first", verbatim_doc_comment)]
+    eviction_policy: EvictionPolicy,
+
+    /// Where to keep the records.
+    ///
+    /// `disk` keeps every record in its own file.
+    /// `single-file` keeps all the records in a single file, which scales better to many small records.
+    /// `redb` keeps all the records in an embedded key-value database, which scales better to many small records.
+    /// Records stored with one backend are not visible to the others.
+    #[clap(long, default_value = "disk", verbatim_doc_comment)]
+    record_store_backend: RecordStoreBackendKind,
+
+    /// How to encrypt the records kept on disk.
+    ///
+    /// `node-keypair` encrypts them with a key derived from the node's keypair.
+    /// `disabled` keeps them unencrypted.
+    /// See `--record-encryption-key-file` to encrypt them with a key of your own.
+    /// Records that can't be decrypted on startup, e.g. after changing the key, are dropped.
+    /// Defaults to `node-keypair`, unless the node is built without the `encrypt-records` feature.
+    #[clap(long, verbatim_doc_comment)]
+    record_encryption: Option<RecordEncryption>,
+
+    /// Encrypt the records kept on disk with a key derived from the hex encoded key read from this file,
+    /// of at least 16 bytes.
+    #[clap(long, conflicts_with = "record_encryption")]
+    record_encryption_key_file: Option<PathBuf>,
+
+    /// Limit the rate of inbound requests, given as `KIND=PER_PEER/GLOBAL` requests per second, e.g. `get=50/500`.
+    ///
+    /// The kind is one of `get`, `put` or `replication`. A budget of 0 is unlimited.
+    /// Can be repeated to set the budget of several kinds. Kinds without a budget keep the default one.
+    #[clap(long = "request-rate-limit", value_parser = parse_request_rate_limit, verbatim_doc_comment)]
+    request_rate_limits: Vec<(RequestKind, RequestBudget)>,
+
+    /// Path to a JSON file of the peers and IP ranges to block or exclusively allow.
+    ///
+    /// The file can have any of the `blocked_peers`, `blocked_ip_ranges`, `allowed_peers` and
+    /// `allowed_ip_ranges` fields, e.g. `{"blocked_ip_ranges": ["203.0.113.0/24"]}`.
+    /// Once any allowed peer or IP range is set, only the matching peers are talked to.
+    #[clap(long, verbatim_doc_comment)]
+    peer_access_list: Option<PathBuf>,
+
+    /// Relay the connections to the nodes behind home network, for them to be reachable.
+    ///
+    /// Only enable this on a well-connected public node. The relay protocol is then advertised to
+    /// the other nodes via identify. The `--relay-max-*` arguments cap the relayed connections.
+    #[clap(
+        long,
+        default_value_t = false,
+        conflicts_with = "home_network",
+        verbatim_doc_comment
+    )]
+    relay_server: bool,
+
+    /// The maximum number of peers to relay for, in relay server mode.
+    #[clap(long, requires = "relay_server")]
+    relay_max_reservations: Option<usize>,
+
+    /// The maximum number of connections relayed at any given moment, in relay server mode.
+    #[clap(long, requires = "relay_server")]
+    relay_max_circuits: Option<usize>,
+
+    /// The maximum number of connections relayed per peer at any given moment, in relay server mode.
+    #[clap(long, requires = "relay_server")]
+    relay_max_circuits_per_peer: Option<usize>,
+
+    /// The maximum number of bytes relayed over a connection before it's closed, in relay server mode.
+    #[clap(long, requires = "relay_server")]
+    relay_max_circuit_bytes: Option<u64>,
+
+    /// The maximum duration in seconds of a relayed connection, in relay server mode.
+    #[clap(long, requires = "relay_server")]
+    relay_max_circuit_duration_secs: Option<u64>,
+
+    /// Listen on WebSocket over this TCP port as well, for the clients that can't use QUIC.
+    ///
+    /// This is plain WebSocket (`ws`) unless the `--wss-cert` and `--wss-key` arguments are provided.
+    #[clap(long, verbatim_doc_comment)]
+    ws_port: Option<u16>,
+
+    /// Path to the PEM certificate chain to listen on secure WebSocket (`wss`) with.
+    #[clap(long, requires_all = ["ws_port", "wss_key"])]
+    wss_cert: Option<PathBuf>,
+
+    /// Path to the PEM private key of the `--wss-cert` certificate.
+    #[clap(long, requires_all = ["ws_port", "wss_cert"])]
+    wss_key: Option<PathBuf>,
+
+    /// Print the rewards the node at the root dir has received, per day and per close group of payees.
+    #[clap(long, requires = "root_dir")]
+    rewards_report: bool,
+}
+
+/// The relay server limits set in the args, the other limits keeping their default.
+fn relay_server_config(opt: &Opt) -> RelayServerConfig {
+    let mut config = RelayServerConfig::default();
+    if let Some(max_reservations) = opt.relay_max_reservations {
+        config.max_reservations = max_reservations;
+    }
+    if let Some(max_circuits) = opt.relay_max_circuits {
+        config.max_circuits = max_circuits;
+    }
+    if let Some(max_circuits_per_peer) = opt.relay_max_circuits_per_peer {
+        config.max_circuits_per_peer = max_circuits_per_peer;
+    }
+    if let Some(max_circuit_bytes) = opt.relay_max_circuit_bytes {
+        config.max_circuit_bytes = max_circuit_bytes;
+    }
+    if let Some(secs) = opt.relay_max_circuit_duration_secs {
+        config.max_circuit_duration = Duration::from_secs(secs);
+    }
+    config
+}
+
+/// The WebSocket listener set in the args, if any.
+fn websocket_config(opt: &Opt) -> Option<WebSocketConfig> {
+    let port = opt.ws_port?;
+    let tls = match (&opt.wss_cert, &opt.wss_key) {
+        (Some(cert_chain_path), Some(private_key_path)) => Some(WebSocketTls {
+            cert_chain_path: cert_chain_path.clone(),
+            private_key_path: private_key_path.clone(),
+        }),
+        _ => None,
+    };
+    Some(WebSocketConfig { port, tls })
 }
 
 fn main() -> Result<()> {
@@ -263,6 +573,12 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if opt.rewards_report {
+        let root_dir = opt.root_dir.as_ref().expect("required by clap");
+        print!("{}", RewardsReport::from_root_dir(root_dir)?);
+        return Ok(());
+    }
+
     let evm_network: EvmNetwork = opt
         .evm_network
         .as_ref()
@@ -324,6 +640,69 @@ fn main() -> Result<()> {
         node_builder.initial_peers(initial_peres);
         node_builder.bootstrap_cache(bootstrap_cache);
         node_builder.is_behind_home_network(opt.home_network);
+        if let Some(ip) = opt.ipv6 {
+            node_builder.listen_addr_v6(ip);
+        }
+        if opt.relay_server {
+            node_builder.relay_server(relay_server_config(&opt));
+        }
+        if let Some(config) = websocket_config(&opt) {
+            node_builder.websocket(config);
+        }
+        node_builder.protocol_compat(opt.protocol_compat);
+        let mut replication_config = ReplicationConfig::default();
+        if let Some(secs) = opt.replication_interval {
+            replication_config.periodic_interval = Duration::from_secs(secs);
+        }
+        if let Some(secs) = opt.churn_replication_interval {
+            replication_config.churn_min_interval = Duration::from_secs(secs);
+        }
+        if let Some(max_keys) = opt.replication_burst_limit {
+            replication_config.max_keys_per_burst = max_keys;
+        }
+        node_builder.replication_config(replication_config);
+        node_builder.auto_expected_holders(opt.auto_expected_holders);
+        node_builder.archive_mode(opt.archive);
+        if let Some(max_mb) = opt.audit_log_max_mb {
+            node_builder.audit_log(max_mb * 1024 * 1024);
+        }
+        node_builder.connection_limits(ConnectionLimits {
+            max_connections: opt.max_connections,
+            max_connections_per_ip: opt.max_connections_per_ip,
+            max_inbound: opt.max_inbound_connections,
+            max_outbound: opt.max_outbound_connections,
+        });
+        let mut sybil_guard = SybilGuardConfig {
+            max_peers_per_ip_per_kbucket: opt.max_peers_per_ip_per_kbucket,
+            max_peers_per_subnet_per_kbucket: opt.max_peers_per_subnet_per_kbucket,
+            close_group_burst: opt.close_group_burst,
+            ..Default::default()
+        };
+        if let Some(secs) = opt.close_group_burst_window {
+            sybil_guard.burst_window = Duration::from_secs(secs);
+        }
+        node_builder.sybil_guard(sybil_guard);
+        node_builder.warm_standby(opt.warm_standby);
+        node_builder.max_bytes_per_category(opt.storage_quotas.into_iter().collect());
+        node_builder.eviction_policy(opt.eviction_policy);
+        node_builder.record_store_backend(opt.record_store_backend);
+        if let Some(record_encryption) = opt.record_encryption.clone() {
+            node_builder.record_encryption(record_encryption);
+        }
+        if let Some(path) = &opt.record_encryption_key_file {
+            let hex_key = std::fs::read_to_string(path)?;
+            let record_encryption = RecordEncryption::from_hex_key(&hex_key)
+                .map_err(|err| eyre::eyre!("Invalid record encryption key file {path:?}: {err}"))?;
+            node_builder.record_encryption(record_encryption);
+        }
+        let mut rate_limit_config = RateLimitConfig::default();
+        for (kind, budget) in opt.request_rate_limits.iter() {
+            rate_limit_config.set_budget(*kind, *budget);
+        }
+        node_builder.rate_limit_config(rate_limit_config);
+        if let Some(path) = &opt.peer_access_list {
+            node_builder.peer_access_list(PeerAccessList::from_file(path)?);
+        }
         #[cfg(feature = "open-metrics")]
         let mut node_builder = node_builder;
         // if enable flag is provided or only if the port is specified then enable the server by setting Some()
@@ -335,8 +714,20 @@ fn main() -> Result<()> {
         };
         #[cfg(feature = "open-metrics")]
         node_builder.metrics_server_port(metrics_server_port);
-        let restart_options =
-            run_node(node_builder, opt.rpc, &log_output_dest, log_reload_handle).await?;
+        let rpc_auth = RpcAuth::from_files(
+            opt.rpc_token_file.as_deref(),
+            opt.rpc_tls_cert.as_deref(),
+            opt.rpc_tls_key.as_deref(),
+            opt.rpc_tls_client_ca.as_deref(),
+        )?;
+        let restart_options = run_node(
+            node_builder,
+            opt.rpc,
+            rpc_auth,
+            &log_output_dest,
+            log_reload_handle,
+        )
+        .await?;
 
         Ok::<_, eyre::Report>(restart_options)
     })?;
@@ -355,6 +746,20 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Persist what the node learnt about its peers before shutting down, for the next start.
+async fn save_peer_reputation(running_node: &RunningNode) {
+    if let Err(err) = running_node.save_peer_reputation().await {
+        warn!("Failed to save the peer reputation: {err}");
+    }
+}
+
+/// Persist the routing table and the pending replication before a clean shutdown, if in warm standby mode.
+async fn save_warm_standby(running_node: &RunningNode) {
+    if let Err(err) = running_node.save_warm_standby().await {
+        warn!("Failed to save the warm standby snapshot: {err}");
+    }
+}
+
 /// Start a node with the given configuration.
 /// Returns:
 /// - `Ok(Some(_))` if we receive a restart request.
@@ -363,6 +768,7 @@ fn main() -> Result<()> {
 async fn run_node(
     node_builder: NodeBuilder,
     rpc: Option<SocketAddr>,
+    rpc_auth: RpcAuth,
     log_output_dest: &str,
     log_reload_handle: ReloadHandle,
 ) -> Result<Option<(bool, PathBuf, u16)>> {
@@ -469,12 +875,13 @@ You can check your reward balance by running:
     if let Some(addr) = rpc {
         rpc_service::start_rpc_service(
             addr,
+            rpc_auth,
             log_output_dest,
             running_node.clone(),
             ctrl_tx,
             started_instant,
             log_reload_handle,
-        );
+        )?;
     }
 
     // Keep the node and gRPC service (if enabled) running.
@@ -492,6 +899,8 @@ You can check your reward balance by running:
                 info!("{msg}");
                 println!("{msg} Node path: {log_output_dest}");
                 sleep(delay).await;
+                save_peer_reputation(&running_node).await;
+                save_warm_standby(&running_node).await;
 
                 return Ok(Some((retain_peer_id, root_dir, node_port)));
             }
@@ -500,6 +909,8 @@ You can check your reward balance by running:
                 info!("{msg}");
                 println!("{msg} Node log path: {log_output_dest}");
                 sleep(delay).await;
+                save_peer_reputation(&running_node).await;
+                save_warm_standby(&running_node).await;
                 match result {
                     StopResult::Success(message) => {
                         info!("Node stopped successfully: {}", message);
@@ -511,6 +922,18 @@ You can check your reward balance by running:
                     }
                 }
             }
+            Some(NodeCtrl::Decommission { timeout }) => {
+                let msg = format!(
+                    "Node is decommissioning, handing off records for up to {timeout:?}..."
+                );
+                info!("{msg}");
+                println!("{msg} Node log path: {log_output_dest}");
+                let report = running_node.decommission(timeout).await?;
+                info!("Node decommissioned: {report}");
+                println!("Node decommissioned: {report}");
+                save_peer_reputation(&running_node).await;
+                return Ok(None);
+            }
             Some(NodeCtrl::Update(_delay)) => {
                 // TODO: implement self-update once antnode app releases are published again
                 println!("No self-update supported yet.");
@@ -616,6 +1039,14 @@ fn init_logging(opt: &Opt, peer_id: PeerId) -> Result<(String, ReloadHandle, Opt
             if let Some(files) = opt.max_archived_log_files {
                 log_builder.max_archived_log_files(files);
             }
+            if let Some(endpoint) = &opt.otlp_endpoint {
+                log_builder.otlp_endpoint(endpoint.clone());
+            }
+            if let Some(service_name) = &opt.otlp_service_name {
+                log_builder.otlp_service_name(service_name.clone());
+            }
+            log_builder.otlp_resource_attribute("peer_id", peer_id.to_string());
+            log_builder.otlp_resource_attribute("node_port", opt.port.to_string());
             log_builder.initialize()
         })?;
         (rt, reload_handle, log_appender_guard)
@@ -7,31 +7,133 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use ant_logging::ReloadHandle;
+use ant_networking::RecordCategory;
 use ant_node::RunningNode;
 use ant_protocol::antnode_proto::{
     ant_node_server::{AntNode, AntNodeServer},
-    k_buckets_response, KBucketsRequest, KBucketsResponse, NetworkInfoRequest, NetworkInfoResponse,
-    NodeEvent, NodeEventsRequest, NodeInfoRequest, NodeInfoResponse, RecordAddressesRequest,
-    RecordAddressesResponse, RestartRequest, RestartResponse, StopRequest, StopResponse,
-    UpdateLogLevelRequest, UpdateLogLevelResponse, UpdateRequest, UpdateResponse,
+    audit_log_response, k_buckets_response, list_records_response, peer_scores_response,
+    AuditLogRequest, AuditLogResponse, DecommissionRequest, DecommissionResponse,
+    DriverStateRequest, DriverStateResponse, KBucketsRequest, KBucketsResponse, ListRecordsRequest,
+    ListRecordsResponse, NetworkInfoRequest, NetworkInfoResponse, NodeEvent, NodeEventsRequest,
+    NodeInfoRequest, NodeInfoResponse, PeerBandwidth, PeerScoresRequest, PeerScoresResponse,
+    RecordAddressesRequest, RecordAddressesResponse, RestartRequest, RestartResponse,
+    SetChaosRequest, SetChaosResponse, StopRequest, StopResponse, UpdateLogLevelRequest,
+    UpdateLogLevelResponse, UpdateRequest, UpdateResponse,
 };
-use ant_protocol::node_rpc::{NodeCtrl, StopResult};
+use ant_protocol::node_rpc::{
+    rpc_auth_matches, rpc_error_status, NodeCtrl, StopResult, RPC_AUTH_METADATA_KEY,
+};
+use ant_protocol::NetworkAddress;
 use eyre::{ErrReport, Result};
 use std::{
     collections::HashMap,
     env,
     net::SocketAddr,
+    path::Path,
     process,
+    str::FromStr,
     time::{Duration, Instant},
 };
 use tokio::sync::mpsc::{self, Sender};
 use tokio_stream::wrappers::ReceiverStream;
-use tonic::{transport::Server, Code, Request, Response, Status};
-use tracing::{debug, info};
+use tonic::{
+    transport::{Certificate, Identity, Server, ServerTlsConfig},
+    Code, Request, Response, Status,
+};
+use tracing::{debug, info, warn};
+
+/// Number of peers reported as top talkers in the node info.
+const TOP_TALKERS: usize = 10;
+
+/// How the callers of the RPC service are authenticated.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RpcAuth {
+    /// Token the callers of the mutating RPCs shall present
+    token: Option<String>,
+    /// TLS config of the server, requiring the clients to present a certificate when a CA is set
+    tls: Option<ServerTlsConfig>,
+    /// Whether the TLS config has a client CA, TLS only encrypting the connections without one
+    verifies_clients: bool,
+}
+
+impl RpcAuth {
+    /// Load the token and the PEM encoded TLS certificate, key and client CA from their files.
+    pub(crate) fn from_files(
+        token_file: Option<&Path>,
+        tls_cert: Option<&Path>,
+        tls_key: Option<&Path>,
+        tls_client_ca: Option<&Path>,
+    ) -> Result<Self> {
+        let token = match token_file {
+            Some(path) => {
+                let token = std::fs::read_to_string(path)?.trim().to_string();
+                if token.is_empty() {
+                    return Err(eyre::eyre!("The RPC token file {path:?} is empty"));
+                }
+                Some(token)
+            }
+            None => None,
+        };
+
+        let verifies_clients = tls_client_ca.is_some();
+        let tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => {
+                let identity = Identity::from_pem(std::fs::read(cert)?, std::fs::read(key)?);
+                let mut tls = ServerTlsConfig::new().identity(identity);
+                if let Some(client_ca) = tls_client_ca {
+                    tls = tls.client_ca_root(Certificate::from_pem(std::fs::read(client_ca)?));
+                }
+                Some(tls)
+            }
+            (None, None) => None,
+            _ => {
+                return Err(eyre::eyre!(
+                    "Both the RPC TLS certificate and key are required"
+                ))
+            }
+        };
+
+        Ok(Self {
+            token,
+            tls,
+            verifies_clients,
+        })
+    }
+
+    /// Whether the callers are authenticated, by a token or by a client certificate.
+    fn is_authenticated(&self) -> bool {
+        self.token.is_some() || (self.tls.is_some() && self.verifies_clients)
+    }
+
+    /// Check the request to a mutating RPC carries the token, if one is required.
+    fn authorize<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let Some(token) = &self.token else {
+            return Ok(());
+        };
+        let presented = request
+            .metadata()
+            .get(RPC_AUTH_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if rpc_auth_matches(presented, token) {
+            Ok(())
+        } else {
+            warn!(
+                "Rejected an unauthenticated RPC request from {:?}",
+                request.remote_addr()
+            );
+            Err(Status::new(
+                Code::Unauthenticated,
+                "A valid RPC token is required",
+            ))
+        }
+    }
+}
 
 // Defining a struct to hold information used by our gRPC service backend
 struct SafeNodeRpcService {
     addr: SocketAddr,
+    auth: RpcAuth,
     log_dir: String,
     running_node: RunningNode,
     ctrl_tx: Sender<NodeCtrl>,
@@ -54,6 +156,27 @@ impl AntNode for SafeNodeRpcService {
             request.get_ref()
         );
 
+        let top_talkers = self
+            .running_node
+            .get_bandwidth_usage()
+            .await
+            .map_err(|err| {
+                rpc_error_status(
+                    Code::Internal,
+                    format!("Failed to get the bandwidth usage: {err}"),
+                    err.code(),
+                )
+            })?
+            .into_iter()
+            .take(TOP_TALKERS)
+            .map(|bandwidth| PeerBandwidth {
+                peer_id: bandwidth.peer_id.to_bytes(),
+                protocol: bandwidth.protocol,
+                bytes_sent: bandwidth.bytes_sent,
+                bytes_received: bandwidth.bytes_received,
+            })
+            .collect();
+
         let resp = Response::new(NodeInfoResponse {
             peer_id: self.running_node.peer_id().to_bytes(),
             log_dir: self.log_dir.clone(),
@@ -66,6 +189,7 @@ impl AntNode for SafeNodeRpcService {
             bin_version: env!("CARGO_PKG_VERSION").to_string(),
             uptime_secs: self.started_instant.elapsed().as_secs(),
             wallet_balance: 0, // NB TODO: Implement this using metrics data?
+            top_talkers,
         });
 
         Ok(resp)
@@ -92,6 +216,7 @@ impl AntNode for SafeNodeRpcService {
         let resp = Response::new(NetworkInfoResponse {
             connected_peers,
             listeners,
+            reachability: state.reachability.to_string(),
         });
 
         Ok(resp)
@@ -159,6 +284,166 @@ impl AntNode for SafeNodeRpcService {
         Ok(Response::new(RecordAddressesResponse { addresses }))
     }
 
+    async fn list_records(
+        &self,
+        request: Request<ListRecordsRequest>,
+    ) -> Result<Response<ListRecordsResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let request = request.into_inner();
+        let category = if request.record_type.is_empty() {
+            None
+        } else {
+            Some(
+                RecordCategory::from_str(&request.record_type)
+                    .map_err(|err| Status::new(Code::InvalidArgument, err))?,
+            )
+        };
+        let limit = (request.limit > 0).then_some(request.limit as usize);
+
+        let (records, total) = self
+            .running_node
+            .list_records(category, request.offset as usize, limit)
+            .await
+            .map_err(|err| {
+                rpc_error_status(
+                    Code::Internal,
+                    format!("Failed to list the records: {err}"),
+                    err.code(),
+                )
+            })?;
+
+        let self_address = NetworkAddress::from_peer(self.running_node.peer_id());
+        let records = records
+            .into_iter()
+            .map(|record| list_records_response::Record {
+                distance: self_address
+                    .distance(&record.address)
+                    .ilog2()
+                    .unwrap_or_default(),
+                address: record.address.as_bytes(),
+                record_type: record.category.to_string(),
+                size: record.size as u64,
+            })
+            .collect();
+
+        Ok(Response::new(ListRecordsResponse {
+            records,
+            total: total as u64,
+        }))
+    }
+
+    async fn peer_scores(
+        &self,
+        request: Request<PeerScoresRequest>,
+    ) -> Result<Response<PeerScoresResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let peers = self
+            .running_node
+            .get_peer_scores()
+            .await
+            .map_err(|err| {
+                rpc_error_status(
+                    Code::Internal,
+                    format!("Failed to get the peer scores: {err}"),
+                    err.code(),
+                )
+            })?
+            .into_iter()
+            .map(|peer| peer_scores_response::PeerScore {
+                peer_id: peer.peer_id.to_bytes(),
+                score: peer.score,
+                is_bad: peer.is_bad,
+                last_issue: peer
+                    .last_issue
+                    .map(|issue| format!("{issue:?}"))
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(Response::new(PeerScoresResponse { peers }))
+    }
+
+    async fn audit_log(
+        &self,
+        request: Request<AuditLogRequest>,
+    ) -> Result<Response<AuditLogResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let entries = self
+            .running_node
+            .audit_log(request.get_ref().count as usize)
+            .map_err(|err| {
+                rpc_error_status(
+                    Code::Internal,
+                    format!("Failed to read the audit log: {err}"),
+                    err.code(),
+                )
+            })?
+            .into_iter()
+            .map(|entry| audit_log_response::Entry {
+                timestamp: entry.timestamp,
+                action: entry.action.to_string(),
+                key: entry.key,
+                record_type: entry.record_type.unwrap_or_default(),
+                source: entry.source.unwrap_or_default(),
+                reason: entry.reason.unwrap_or_default(),
+            })
+            .collect();
+
+        Ok(Response::new(AuditLogResponse { entries }))
+    }
+
+    async fn driver_state(
+        &self,
+        request: Request<DriverStateRequest>,
+    ) -> Result<Response<DriverStateResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+
+        let state = self.running_node.get_driver_state().await.map_err(|err| {
+            rpc_error_status(
+                Code::Internal,
+                format!("Failed to get the driver state: {err}"),
+                err.code(),
+            )
+        })?;
+
+        Ok(Response::new(DriverStateResponse {
+            pending_get_closest_peers: state.pending_get_closest_peers as u64,
+            pending_requests: state.pending_requests as u64,
+            pending_get_record: state.pending_get_record as u64,
+            pending_put_record: state.pending_put_record as u64,
+            pending_dials: state.pending_dials as u64,
+            replication_pending_keys: state.replication_pending_keys as u64,
+            replication_ongoing_fetches: state.replication_ongoing_fetches as u64,
+            replication_oldest_pending_secs: state.replication_oldest_pending.as_secs(),
+            local_cmd_queue_depth: state.local_cmd_queue_depth as u64,
+            network_cmd_queue_depth: state.network_cmd_queue_depth as u64,
+            peers_per_kbucket: state
+                .peers_per_kbucket
+                .into_iter()
+                .map(|(distance, peers)| (distance, peers as u64))
+                .collect(),
+        }))
+    }
+
     async fn k_buckets(
         &self,
         request: Request<KBucketsRequest>,
@@ -191,6 +476,7 @@ impl AntNode for SafeNodeRpcService {
             self.addr,
             request.get_ref()
         );
+        self.auth.authorize(&request)?;
 
         let cause = if let Some(addr) = request.remote_addr() {
             ErrReport::msg(format!(
@@ -226,6 +512,7 @@ impl AntNode for SafeNodeRpcService {
             self.addr,
             request.get_ref()
         );
+        self.auth.authorize(&request)?;
 
         let delay = Duration::from_millis(request.get_ref().delay_millis);
         match self
@@ -253,6 +540,7 @@ impl AntNode for SafeNodeRpcService {
             self.addr,
             request.get_ref()
         );
+        self.auth.authorize(&request)?;
 
         let delay = Duration::from_millis(request.get_ref().delay_millis);
         match self.ctrl_tx.send(NodeCtrl::Update(delay)).await {
@@ -264,6 +552,58 @@ impl AntNode for SafeNodeRpcService {
         }
     }
 
+    async fn decommission(
+        &self,
+        request: Request<DecommissionRequest>,
+    ) -> Result<Response<DecommissionResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+        self.auth.authorize(&request)?;
+
+        let timeout = Duration::from_millis(request.get_ref().timeout_millis);
+        match self.ctrl_tx.send(NodeCtrl::Decommission { timeout }).await {
+            Ok(()) => Ok(Response::new(DecommissionResponse {})),
+            Err(err) => Err(Status::new(
+                Code::Internal,
+                format!("Failed to decommission the node: {err}"),
+            )),
+        }
+    }
+
+    async fn set_chaos(
+        &self,
+        request: Request<SetChaosRequest>,
+    ) -> Result<Response<SetChaosResponse>, Status> {
+        debug!(
+            "RPC request received at {}: {:?}",
+            self.addr,
+            request.get_ref()
+        );
+        self.auth.authorize(&request)?;
+
+        #[cfg(feature = "chaos")]
+        {
+            let request = request.into_inner();
+            let config = ant_networking::chaos::ChaosConfig {
+                drop_requests_percent: request.drop_requests_percent.min(100) as u8,
+                replication_delay: Duration::from_millis(request.replication_delay_millis),
+                corrupt_chunks: request.corrupt_chunks,
+                refuse_quotes: request.refuse_quotes,
+            };
+            warn!("Injecting faults on purpose, as asked through RPC: {config:?}");
+            self.running_node.set_chaos_config(config);
+            Ok(Response::new(SetChaosResponse {}))
+        }
+        #[cfg(not(feature = "chaos"))]
+        Err(Status::new(
+            Code::Unimplemented,
+            "The node was built without the chaos feature",
+        ))
+    }
+
     async fn update_log_level(
         &self,
         request: Request<UpdateLogLevelRequest>,
@@ -273,6 +613,7 @@ impl AntNode for SafeNodeRpcService {
             self.addr,
             request.get_ref()
         );
+        self.auth.authorize(&request)?;
 
         match self
             .log_reload_handle
@@ -289,15 +630,25 @@ impl AntNode for SafeNodeRpcService {
 
 pub(crate) fn start_rpc_service(
     addr: SocketAddr,
+    auth: RpcAuth,
     log_dir_path: &str,
     running_node: RunningNode,
     ctrl_tx: Sender<NodeCtrl>,
     started_instant: Instant,
     log_reload_handle: ReloadHandle,
-) {
+) -> Result<()> {
+    if !auth.is_authenticated() {
+        warn!("The RPC service is unauthenticated, anyone reaching {addr} can control the node");
+    }
+    let mut server = Server::builder();
+    if let Some(tls) = auth.tls.clone() {
+        server = server.tls_config(tls)?;
+    }
+
     // creating a service
     let service = SafeNodeRpcService {
         addr,
+        auth,
         log_dir: log_dir_path.to_string(),
         running_node,
         ctrl_tx,
@@ -309,7 +660,7 @@ pub(crate) fn start_rpc_service(
 
     let _handle = tokio::spawn(async move {
         // adding our service to our server.
-        if let Err(e) = Server::builder()
+        if let Err(e) = server
             .add_service(AntNodeServer::new(service))
             .serve(addr)
             .await
@@ -317,4 +668,76 @@ pub(crate) fn start_rpc_service(
             error!("RPC Server failed to start: {e:?}");
         }
     });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::node_rpc::rpc_auth_metadata_value;
+
+    #[test]
+    fn mutating_rpcs_require_the_token_when_set() {
+        let auth = RpcAuth {
+            token: Some("secret".to_string()),
+            tls: None,
+            verifies_clients: false,
+        };
+
+        let mut request = Request::new(StopRequest { delay_millis: 0 });
+        assert!(auth.authorize(&request).is_err());
+
+        let _ = request.metadata_mut().insert(
+            RPC_AUTH_METADATA_KEY,
+            rpc_auth_metadata_value("wrong").parse().unwrap(),
+        );
+        assert!(auth.authorize(&request).is_err());
+
+        let _ = request.metadata_mut().insert(
+            RPC_AUTH_METADATA_KEY,
+            rpc_auth_metadata_value("secret").parse().unwrap(),
+        );
+        assert!(auth.authorize(&request).is_ok());
+
+        let request = Request::new(StopRequest { delay_millis: 0 });
+        assert!(RpcAuth::default().authorize(&request).is_ok());
+    }
+
+    #[test]
+    fn tls_authenticates_the_callers_only_with_a_client_ca() {
+        assert!(!RpcAuth::default().is_authenticated());
+
+        let encrypted_only = RpcAuth {
+            token: None,
+            tls: Some(ServerTlsConfig::new()),
+            verifies_clients: false,
+        };
+        assert!(!encrypted_only.is_authenticated());
+
+        let mutual_tls = RpcAuth {
+            verifies_clients: true,
+            ..encrypted_only.clone()
+        };
+        assert!(mutual_tls.is_authenticated());
+
+        let with_token = RpcAuth {
+            token: Some("secret".to_string()),
+            ..encrypted_only
+        };
+        assert!(with_token.is_authenticated());
+    }
+
+    #[test]
+    fn errors_carry_their_code_to_the_caller() {
+        let err = ant_networking::NetworkError::SwarmCmdChannelSaturated;
+        let status = rpc_error_status(Code::Internal, err.to_string(), err.code());
+        assert_eq!(
+            ant_protocol::node_rpc::rpc_error_code(&status),
+            Some(ant_protocol::ErrorCode::Overloaded)
+        );
+        assert_eq!(
+            ant_protocol::node_rpc::rpc_error_code(&Status::new(Code::Internal, "")),
+            None
+        );
+    }
 }
@@ -0,0 +1,203 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::Result;
+use ant_networking::Network;
+use ant_protocol::{
+    messages::{ChunkProof, Cmd, Nonce, Query, QueryResponse, Request, Response},
+    storage::RecordType,
+    NetworkAddress, PrettyPrintRecordKey, CLOSE_GROUP_SIZE,
+};
+use futures::{stream, StreamExt};
+use libp2p::PeerId;
+use rand::{thread_rng, Rng};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Max number of records checked at the same time during the hand off.
+const MAX_CONCURRENT_CHECKS: usize = 32;
+
+/// Interval between two rounds of checking the records are held by their new holders.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The outcome of decommissioning a node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecommissionReport {
+    /// The number of records the node held
+    pub records: usize,
+    /// The number of records confirmed to be held by a majority of their close group
+    pub handed_off: usize,
+}
+
+impl fmt::Display for DecommissionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} records handed off to their close groups",
+            self.handed_off, self.records
+        )
+    }
+}
+
+/// A record being handed off, and the peers yet to confirm they hold it.
+struct PendingRecord {
+    address: NetworkAddress,
+    candidates: Vec<PeerId>,
+    confirmed: HashSet<PeerId>,
+}
+
+impl PendingRecord {
+    /// The number of confirmations for the record to be considered handed off.
+    fn required_confirmations(&self) -> usize {
+        (CLOSE_GROUP_SIZE / 2 + 1).min(self.candidates.len())
+    }
+
+    fn is_handed_off(&self) -> bool {
+        !self.candidates.is_empty() && self.confirmed.len() >= self.required_confirmations()
+    }
+}
+
+/// Stop accepting records, then hand off the held ones to their close groups, waiting at most
+/// `timeout` for the new holders to prove they store them.
+pub(crate) async fn hand_off_records(
+    network: &Network,
+    timeout: Duration,
+) -> Result<DecommissionReport> {
+    let deadline = Instant::now() + timeout;
+    network.stop_accepting_records();
+
+    let our_peer_id = network.peer_id();
+    let our_address = NetworkAddress::from_peer(our_peer_id);
+    let records = network.get_all_local_record_addresses().await?;
+    info!(
+        "Decommissioning the node, handing off {} records",
+        records.len()
+    );
+
+    let mut pending = vec![];
+    let mut keys_per_peer: BTreeMap<PeerId, Vec<(NetworkAddress, RecordType)>> = BTreeMap::new();
+    for (address, record_type) in records.iter() {
+        let candidates: Vec<_> = network
+            .get_replicate_candidates(address.clone())
+            .await?
+            .into_iter()
+            .filter(|peer| *peer != our_peer_id)
+            .take(CLOSE_GROUP_SIZE)
+            .collect();
+        if candidates.is_empty() {
+            warn!(
+                "No peers to hand off record {:?} to",
+                PrettyPrintRecordKey::from(&address.to_record_key())
+            );
+        }
+        for peer in &candidates {
+            keys_per_peer
+                .entry(*peer)
+                .or_default()
+                .push((address.clone(), record_type.clone()));
+        }
+        pending.push(PendingRecord {
+            address: address.clone(),
+            candidates,
+            confirmed: HashSet::new(),
+        });
+    }
+
+    for (peer, keys) in keys_per_peer {
+        debug!("Handing off {} records to {peer:?}", keys.len());
+        let request = Request::Cmd(Cmd::Replicate {
+            holder: our_address.clone(),
+            keys,
+        });
+        network.send_req_ignore_reply(request, peer);
+    }
+
+    let mut handed_off = 0;
+    while !pending.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(CHECK_INTERVAL.min(deadline - Instant::now())).await;
+
+        let checked: Vec<_> = stream::iter(pending)
+            .map(|record| check_record(network, record))
+            .buffer_unordered(MAX_CONCURRENT_CHECKS)
+            .collect()
+            .await;
+        let (done, remaining): (Vec<_>, Vec<_>) =
+            checked.into_iter().partition(PendingRecord::is_handed_off);
+        handed_off += done.len();
+        pending = remaining;
+        debug!(
+            "{handed_off}/{} records handed off when decommissioning",
+            records.len()
+        );
+    }
+
+    for record in &pending {
+        warn!(
+            "Record {:?} was confirmed by {}/{} peers only when decommissioning",
+            PrettyPrintRecordKey::from(&record.address.to_record_key()),
+            record.confirmed.len(),
+            record.required_confirmations()
+        );
+    }
+
+    Ok(DecommissionReport {
+        records: records.len(),
+        handed_off,
+    })
+}
+
+/// Challenge the peers yet to confirm they hold the record, recording the ones that prove it.
+async fn check_record(network: &Network, mut record: PendingRecord) -> PendingRecord {
+    let key = record.address.to_record_key();
+    let local_record = match network.get_local_record(&key).await {
+        Ok(Some(local_record)) => local_record,
+        Ok(None) | Err(_) => {
+            error!(
+                "Local record {:?} can't be loaded to check its hand off",
+                PrettyPrintRecordKey::from(&key)
+            );
+            return record;
+        }
+    };
+
+    let nonce: Nonce = thread_rng().gen::<u64>();
+    let expected_proof = ChunkProof::new(&local_record.value, nonce);
+    let request = Request::Query(Query::GetChunkExistenceProof {
+        key: record.address.clone(),
+        nonce,
+        difficulty: 1,
+    });
+
+    let unconfirmed: Vec<_> = record
+        .candidates
+        .iter()
+        .filter(|peer| !record.confirmed.contains(peer))
+        .cloned()
+        .collect();
+    let responses = network
+        .send_and_get_responses(&unconfirmed, &request, true)
+        .await;
+    for (peer, response) in responses {
+        if let Ok(Response::Query(QueryResponse::GetChunkExistenceProof(proofs))) = response {
+            let proven = proofs.iter().any(|(address, proof)| {
+                *address == record.address
+                    && proof
+                        .as_ref()
+                        .is_ok_and(|proof| proof.verify(&expected_proof))
+            });
+            if proven {
+                let _ = record.confirmed.insert(peer);
+            }
+        }
+    }
+    record
+}
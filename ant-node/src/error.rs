@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use ant_evm::AttoTokens;
-use ant_protocol::{NetworkAddress, PrettyPrintRecordKey};
+use ant_protocol::{ErrorCode, NetworkAddress, PrettyPrintRecordKey};
 use thiserror::Error;
 
 pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
@@ -40,6 +40,11 @@ pub enum Error {
     // The Record::key must match with the one that is derived from the Record::value
     #[error("The Record::key does not match with the key derived from Record::value")]
     RecordKeyMismatch,
+    /// Scratchpads can only carry an expiry once all the peers decode it
+    #[error("Scratchpad came with an expiry not supported by our protocol version yet: {0:?}")]
+    UnexpectedRecordExpiry(PrettyPrintRecordKey<'static>),
+    #[error("Record has already expired: {0:?}")]
+    RecordExpired(PrettyPrintRecordKey<'static>),
 
     // Scratchpad is old version
     #[error("A newer version of this Scratchpad already exists")]
@@ -47,6 +52,16 @@ pub enum Error {
     // Scratchpad is invalid
     #[error("Scratchpad signature is invalid over the counter + content hash")]
     InvalidScratchpadSignature,
+    // Scratchpad is too big
+    #[error("Scratchpad of {size} bytes is bigger than the max of {max} bytes")]
+    ScratchpadTooBig { size: usize, max: usize },
+
+    // Pointer is old version
+    #[error("A newer version of this Pointer already exists")]
+    IgnoringOutdatedPointerPut,
+    // Pointer is invalid
+    #[error("Pointer signature is invalid over the name + counter + target")]
+    InvalidPointerSignature,
 
     // ---------- Payment Errors
     #[error("The content of the payment quote is invalid")]
@@ -83,4 +98,48 @@ pub enum Error {
     InvalidRequest(String),
     #[error("EVM Network error: {0}")]
     EvmNetwork(String),
+    #[error("Rewards ledger error: {0}")]
+    RewardsLedger(String),
+    #[error("Failed to read the audit log: {0}")]
+    AuditLog(std::io::Error),
+}
+
+impl Error {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::Network(err) => err.code(),
+            Error::Protocol(err) => err.code(),
+            Error::Register(_) => ErrorCode::InvalidRecord,
+            Error::Transfers(err) => ErrorCode::of_evm_error(err),
+            Error::NodeEventParsingFailed => ErrorCode::Serialization,
+            Error::InvalidPutWithoutPayment(_)
+            | Error::UnexpectedRecordWithPayment(_)
+            | Error::RecordKeyMismatch
+            | Error::UnexpectedRecordExpiry(_)
+            | Error::RecordExpired(_)
+            | Error::InvalidScratchpadSignature
+            | Error::ScratchpadTooBig { .. }
+            | Error::InvalidPointerSignature => ErrorCode::InvalidRecord,
+            Error::IgnoringOutdatedScratchpadPut | Error::IgnoringOutdatedPointerPut => {
+                ErrorCode::RecordExists
+            }
+            Error::InvalidQuoteContent
+            | Error::InvalidQuoteSignature
+            | Error::QuoteExpired(_)
+            | Error::NoPaymentToOurNode(_)
+            | Error::NoNetworkRoyaltiesPayment(_)
+            | Error::PaymentInsufficientAmount { .. }
+            | Error::ReusedPayment => ErrorCode::PaymentRejected,
+            Error::FailedToGenerateRewardKey | Error::FailedToGetNodePort => ErrorCode::Internal,
+            Error::InvalidRequest(_) => ErrorCode::InvalidInput,
+            Error::EvmNetwork(_) => ErrorCode::PaymentFailed,
+            Error::RewardsLedger(_) | Error::AuditLog(_) => ErrorCode::Io,
+        }
+    }
+
+    /// Whether the operation may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
 }
@@ -71,6 +71,10 @@ pub enum NodeEvent {
     ChannelClosed,
     /// Terminates the node
     TerminateNode(String),
+    /// The routing table degraded, the node is re-bootstrapping
+    ConnectivityDegraded,
+    /// The routing table recovered from being degraded
+    ConnectivityRestored,
 }
 
 impl NodeEvent {
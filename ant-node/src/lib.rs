@@ -21,32 +21,43 @@
 #[macro_use]
 extern crate tracing;
 
+mod accepted_payments;
+mod decommission;
 mod error;
 mod event;
 mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 mod node;
+mod payment_rejections;
 mod put_validation;
 #[cfg(feature = "extension-module")]
 mod python;
 mod quote;
 mod replication;
+mod rewards;
 
 pub use self::{
+    decommission::DecommissionReport,
     event::{NodeEvent, NodeEventsChannel, NodeEventsReceiver},
     log_markers::Marker,
     node::{NodeBuilder, PERIODIC_REPLICATION_INTERVAL_MAX_S},
+    replication::ReplicationConfig,
+    rewards::{close_group_id, read_rewards, RewardRecord, RewardsReport, RewardsTotal},
 };
 
 use crate::error::{Error, Result};
 
-use ant_networking::{Network, SwarmLocalState};
+use ant_networking::{
+    AuditEntry, Network, PeerBandwidth, PeerScore, RecordCategory, StoredRecord, SwarmDriverState,
+    SwarmLocalState, AUDIT_LOG_FILENAME,
+};
 use ant_protocol::{get_port_from_multiaddr, NetworkAddress};
 use libp2p::PeerId;
 use std::{
     collections::{BTreeMap, HashSet},
     path::PathBuf,
+    time::Duration,
 };
 
 use ant_evm::RewardsAddress;
@@ -114,6 +125,74 @@ impl RunningNode {
         Ok(addresses)
     }
 
+    /// Returns the last `count` entries of the audit log of the records stored and removed, oldest first, or none
+    /// if the node doesn't keep the log, see [`NodeBuilder::audit_log`].
+    pub fn audit_log(&self, count: usize) -> Result<Vec<AuditEntry>> {
+        ant_networking::tail_audit_log(&self.root_dir_path.join(AUDIT_LOG_FILENAME), count)
+            .map_err(Error::AuditLog)
+    }
+
+    /// Returns a page of the records held by the node, sorted by their distance to the node, along
+    /// with the number of records of the `category`, or of all of them if `None`.
+    pub async fn list_records(
+        &self,
+        category: Option<RecordCategory>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<(Vec<StoredRecord>, usize)> {
+        let records: Vec<_> = self
+            .network
+            .get_local_stored_records()
+            .await?
+            .into_iter()
+            .filter(|record| category.is_none_or(|category| record.category == category))
+            .collect();
+        let total = records.len();
+        let page = records
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok((page, total))
+    }
+
+    /// Persist the peer reputation, to be restored when the node restarts
+    pub async fn save_peer_reputation(&self) -> Result<()> {
+        self.network.save_peer_reputation().await?;
+        Ok(())
+    }
+
+    /// Persist the routing table and the pending replication, to be restored when the node restarts in warm
+    /// standby mode. To be called on a clean shutdown only, the snapshot being trusted as is on the next start.
+    pub async fn save_warm_standby(&self) -> Result<()> {
+        self.network.save_warm_standby().await?;
+        Ok(())
+    }
+
+    /// Returns the bytes sent and received per peer and negotiated protocol, highest first
+    pub async fn get_bandwidth_usage(&self) -> Result<Vec<PeerBandwidth>> {
+        let usage = self.network.get_bandwidth_usage().await?;
+        Ok(usage)
+    }
+
+    /// Returns the sizes of the internal state of the `SwarmDriver`
+    pub async fn get_driver_state(&self) -> Result<SwarmDriverState> {
+        let state = self.network.get_driver_state().await?;
+        Ok(state)
+    }
+
+    /// Replace the faults the node injects on purpose
+    #[cfg(feature = "chaos")]
+    pub fn set_chaos_config(&self, config: ant_networking::chaos::ChaosConfig) {
+        self.network.set_chaos_config(config);
+    }
+
+    /// Returns the scores of the peers that misbehaved, highest first
+    pub async fn get_peer_scores(&self) -> Result<Vec<PeerScore>> {
+        let scores = self.network.get_peer_scores().await?;
+        Ok(scores)
+    }
+
     /// Returns a map where each key is the ilog2 distance of that Kbucket and each value is a vector of peers in that
     /// bucket.
     pub async fn get_kbuckets(&self) -> Result<BTreeMap<u32, Vec<PeerId>>> {
@@ -125,4 +204,10 @@ impl RunningNode {
     pub fn reward_address(&self) -> &RewardsAddress {
         &self.rewards_address
     }
+
+    /// Stop accepting new records and hand off the held ones to their close groups, waiting at
+    /// most `timeout` for them to be confirmed. The node shall be stopped afterwards.
+    pub async fn decommission(&self, timeout: Duration) -> Result<DecommissionReport> {
+        decommission::hand_off_records(&self.network, timeout).await
+    }
 }
@@ -45,6 +45,8 @@ pub enum Marker<'a> {
     ValidTransactionRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
     /// Valid Scratchpad record PUT from the network received and stored
     ValidScratchpadRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
+    /// Valid Pointer record PUT from the network received and stored
+    ValidPointerRecordPutFromNetwork(&'a PrettyPrintRecordKey<'a>),
 
     /// Valid paid to us and royalty paid chunk stored
     ValidPaidChunkPutFromClient(&'a PrettyPrintRecordKey<'a>),
@@ -54,6 +56,8 @@ pub enum Marker<'a> {
     ValidTransactionPutFromClient(&'a PrettyPrintRecordKey<'a>),
     /// Valid scratchpad stored
     ValidScratchpadRecordPutFromClient(&'a PrettyPrintRecordKey<'a>),
+    /// Valid pointer stored
+    ValidPointerRecordPutFromClient(&'a PrettyPrintRecordKey<'a>),
 
     /// Record rejected
     RecordRejected(&'a PrettyPrintRecordKey<'a>, &'a Error),
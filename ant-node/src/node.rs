@@ -9,21 +9,31 @@
 use super::{
     error::Result, event::NodeEventsChannel, quote::quotes_verification, Marker, NodeEvent,
 };
+use crate::accepted_payments::AcceptedPayments;
 #[cfg(feature = "open-metrics")]
 use crate::metrics::NodeMetricsRecorder;
+use crate::payment_rejections::PaymentRejections;
+use crate::replication::{ReplicationConfig, ReplicationScheduler};
+use crate::rewards::RewardsLedger;
 use crate::RunningNode;
 use ant_bootstrap::BootstrapCacheStore;
 use ant_evm::RewardsAddress;
 #[cfg(feature = "open-metrics")]
 use ant_networking::MetricsRegistries;
 use ant_networking::{
-    target_arch::sleep, Instant, Network, NetworkBuilder, NetworkEvent, NodeIssue, SwarmDriver,
+    target_arch::sleep, ConnectionLimits, EvictionPolicy, Instant, KademliaConfig, Network,
+    NetworkBuilder, NetworkEvent, NodeIssue, PeerAccessList, RateLimitConfig, RecordCategory,
+    RecordEncryption, RecordStoreBackendKind, RelayServerConfig, SwarmDriver, SybilGuardConfig,
+    WebSocketConfig,
 };
 use ant_protocol::{
     convert_distance_to_u256,
     error::Error as ProtocolError,
-    messages::{ChunkProof, CmdResponse, Nonce, Query, QueryResponse, Request, Response},
+    messages::{
+        ChunkProof, CmdResponse, Nonce, Query, QueryResponse, Request, Response, StorageReceipt,
+    },
     storage::RecordType,
+    version::ProtocolMismatch,
     NetworkAddress, PrettyPrintRecordKey, CLOSE_GROUP_SIZE,
 };
 use bytes::Bytes;
@@ -32,22 +42,24 @@ use libp2p::{identity::Keypair, Multiaddr, PeerId};
 use num_traits::cast::ToPrimitive;
 use rand::{
     rngs::{OsRng, StdRng},
+    seq::SliceRandom,
     thread_rng, Rng, SeedableRng,
 };
 use std::{
     collections::HashMap,
-    net::SocketAddr,
+    net::{Ipv6Addr, SocketAddr},
     path::PathBuf,
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc,
+        Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 use tokio::{
     sync::mpsc::Receiver,
     task::{spawn, JoinSet},
 };
+use xor_name::XorName;
 
 use ant_evm::{EvmNetwork, U256};
 
@@ -65,6 +77,12 @@ const UPTIME_METRICS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
 /// Interval to clean up unrelevant records
 const UNRELEVANT_RECORDS_CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
 
+/// The number of local chunks whose closest holders get audited on each storage challenge.
+const AUDITED_CHUNKS_PER_CHALLENGE: usize = 3;
+
+/// The number of peers closest to an audited chunk that must prove holding it.
+const AUDITED_HOLDERS_PER_CHUNK: usize = 2;
+
 /// Highest score to achieve from each metric sub-sector during StorageChallenge.
 const HIGHEST_SCORE: usize = 100;
 
@@ -87,6 +105,7 @@ pub struct NodeBuilder {
     evm_address: RewardsAddress,
     evm_network: EvmNetwork,
     addr: SocketAddr,
+    addr_v6: Option<Ipv6Addr>,
     local: bool,
     root_dir: PathBuf,
     #[cfg(feature = "open-metrics")]
@@ -94,6 +113,23 @@ pub struct NodeBuilder {
     metrics_server_port: Option<u16>,
     /// Enable hole punching for nodes connecting from home networks.
     is_behind_home_network: bool,
+    max_bytes_per_category: HashMap<RecordCategory, usize>,
+    eviction_policy: EvictionPolicy,
+    record_store_backend: RecordStoreBackendKind,
+    record_encryption: RecordEncryption,
+    peer_access_list: PeerAccessList,
+    rate_limit_config: RateLimitConfig,
+    kademlia_config: KademliaConfig,
+    relay_server: Option<RelayServerConfig>,
+    websocket: Option<WebSocketConfig>,
+    protocol_compat: bool,
+    replication_config: ReplicationConfig,
+    auto_expected_holders: bool,
+    archive_mode: bool,
+    audit_log_max_bytes: Option<u64>,
+    connection_limits: ConnectionLimits,
+    sybil_guard: SybilGuardConfig,
+    warm_standby: bool,
     #[cfg(feature = "upnp")]
     upnp: bool,
 }
@@ -117,11 +153,29 @@ impl NodeBuilder {
             evm_address,
             evm_network,
             addr,
+            addr_v6: None,
             local,
             root_dir,
             #[cfg(feature = "open-metrics")]
             metrics_server_port: None,
             is_behind_home_network: false,
+            max_bytes_per_category: HashMap::new(),
+            eviction_policy: EvictionPolicy::default(),
+            record_store_backend: RecordStoreBackendKind::default(),
+            record_encryption: RecordEncryption::default(),
+            peer_access_list: PeerAccessList::default(),
+            rate_limit_config: RateLimitConfig::default(),
+            kademlia_config: KademliaConfig::default(),
+            relay_server: None,
+            websocket: None,
+            protocol_compat: false,
+            replication_config: ReplicationConfig::default(),
+            auto_expected_holders: false,
+            archive_mode: false,
+            audit_log_max_bytes: None,
+            connection_limits: ConnectionLimits::default(),
+            sybil_guard: SybilGuardConfig::default(),
+            warm_standby: false,
             #[cfg(feature = "upnp")]
             upnp,
         }
@@ -148,6 +202,106 @@ impl NodeBuilder {
         self.is_behind_home_network = is_behind_home_network;
     }
 
+    /// Set the maximum total size of the records of each category to store, in bytes.
+    pub fn max_bytes_per_category(
+        &mut self,
+        max_bytes_per_category: HashMap<RecordCategory, usize>,
+    ) {
+        self.max_bytes_per_category = max_bytes_per_category;
+    }
+
+    /// Set how to make room for new records once the record store is full.
+    pub fn eviction_policy(&mut self, eviction_policy: EvictionPolicy) {
+        self.eviction_policy = eviction_policy;
+    }
+
+    /// Set the backend to keep the records in.
+    pub fn record_store_backend(&mut self, backend: RecordStoreBackendKind) {
+        self.record_store_backend = backend;
+    }
+
+    /// Set how the node encrypts the records it keeps on disk.
+    pub fn record_encryption(&mut self, record_encryption: RecordEncryption) {
+        self.record_encryption = record_encryption;
+    }
+
+    /// Set the peers and IP ranges to block or exclusively allow.
+    pub fn peer_access_list(&mut self, peer_access_list: PeerAccessList) {
+        self.peer_access_list = peer_access_list;
+    }
+
+    /// Set the budgets of inbound requests to accept, per peer and from all peers.
+    pub fn rate_limit_config(&mut self, rate_limit_config: RateLimitConfig) {
+        self.rate_limit_config = rate_limit_config;
+    }
+
+    /// Set the parameters of the Kademlia behaviour, e.g. for a private network.
+    pub fn kademlia_config(&mut self, kademlia_config: KademliaConfig) {
+        self.kademlia_config = kademlia_config;
+    }
+
+    /// Relay the connections to the nodes behind home network, within the limits of the config.
+    pub fn relay_server(&mut self, config: RelayServerConfig) {
+        self.relay_server = Some(config);
+    }
+
+    /// Listen on this IPv6 address as well, on the port of the listen addr, for dual-stack.
+    pub fn listen_addr_v6(&mut self, ip: Ipv6Addr) {
+        self.addr_v6 = Some(ip);
+    }
+
+    /// Listen on WebSocket as well as QUIC, for the clients that can't use QUIC.
+    pub fn websocket(&mut self, config: WebSocketConfig) {
+        self.websocket = Some(config);
+    }
+
+    /// Keep talking to the peers one minor protocol version apart, for a rolling upgrade of the network.
+    pub fn protocol_compat(&mut self, enable: bool) {
+        self.protocol_compat = enable;
+    }
+
+    /// Set how often and how much to replicate the records, periodically and on churn.
+    pub fn replication_config(&mut self, config: ReplicationConfig) {
+        self.replication_config = config;
+    }
+
+    /// Expect the peers that notified us of holding a record to return it when we fetch it, shunning those that
+    /// repeatedly don't.
+    pub fn auto_expected_holders(&mut self, enable: bool) {
+        self.auto_expected_holders = enable;
+    }
+
+    /// Volunteer to hold, for free, the chunks the clients push to us beyond our close group responsibility, as
+    /// an extra durability tier for cold data. Only worth it with ample disk.
+    pub fn archive_mode(&mut self, enable: bool) {
+        self.archive_mode = enable;
+    }
+
+    /// Keep an append-only log of the records stored and removed, capped at `max_bytes`, for the operators to
+    /// investigate the reports of lost data, see [`crate::RunningNode::audit_log`].
+    pub fn audit_log(&mut self, max_bytes: u64) {
+        self.audit_log_max_bytes = Some(max_bytes);
+    }
+
+    /// Cap the number of live connections, for the memory used by a small node to stay bounded. The connections
+    /// to the close group and to the peers being talked to are never evicted.
+    pub fn connection_limits(&mut self, connection_limits: ConnectionLimits) {
+        self.connection_limits = connection_limits;
+    }
+
+    /// Keep the peers of a same IP or subnet from crowding a kbucket, and flag the bursts of new peers joining our
+    /// close group, as a close group takeover would show.
+    pub fn sybil_guard(&mut self, config: SybilGuardConfig) {
+        self.sybil_guard = config;
+    }
+
+    /// Persist the routing table and the pending replication on a clean shutdown, see
+    /// [`RunningNode::save_warm_standby`], and restore them on start, for a node restarted for an upgrade to be
+    /// back serving its records without re-discovering the network first.
+    pub fn warm_standby(&mut self, enable: bool) {
+        self.warm_standby = enable;
+    }
+
     /// Asynchronously runs a new node instance, setting up the swarm driver,
     /// creating a data storage, and handling network events. Returns the
     /// created `RunningNode` which contains a `NodeEventsChannel` for listening
@@ -177,9 +331,34 @@ impl NodeBuilder {
         };
 
         network_builder.listen_addr(self.addr);
+        if let Some(ip) = self.addr_v6 {
+            network_builder.listen_addr_v6(ip);
+        }
         #[cfg(feature = "open-metrics")]
         network_builder.metrics_server_port(self.metrics_server_port);
         network_builder.is_behind_home_network(self.is_behind_home_network);
+        network_builder.max_bytes_per_category(self.max_bytes_per_category);
+        network_builder.eviction_policy(self.eviction_policy);
+        network_builder.record_store_backend(self.record_store_backend);
+        network_builder.record_encryption(self.record_encryption);
+        network_builder.peer_access_list(self.peer_access_list);
+        network_builder.rate_limit_config(self.rate_limit_config);
+        network_builder.kademlia_config(self.kademlia_config);
+        if let Some(config) = self.relay_server {
+            network_builder.relay_server(config);
+        }
+        if let Some(config) = self.websocket {
+            network_builder.websocket(config);
+        }
+        network_builder.protocol_compat(self.protocol_compat);
+        network_builder.auto_expected_holders(self.auto_expected_holders);
+        network_builder.archive_mode(self.archive_mode);
+        if let Some(max_bytes) = self.audit_log_max_bytes {
+            network_builder.audit_log(max_bytes);
+        }
+        network_builder.connection_limits(self.connection_limits);
+        network_builder.sybil_guard(self.sybil_guard);
+        network_builder.warm_standby(self.warm_standby);
         if let Some(cache) = self.bootstrap_cache {
             network_builder.bootstrap_cache(cache);
         }
@@ -196,9 +375,15 @@ impl NodeBuilder {
             events_channel: node_events_channel.clone(),
             initial_peers: self.initial_peers,
             reward_address: self.evm_address,
+            rewards_ledger: RewardsLedger::new(&self.root_dir)?,
             #[cfg(feature = "open-metrics")]
             metrics_recorder,
             evm_network: self.evm_network,
+            replication_config: self.replication_config,
+            replication_scheduler: Mutex::default(),
+            payment_rejections: Mutex::default(),
+            accepted_payments: Mutex::default(),
+            archive_mode: self.archive_mode,
         };
         let node = Node {
             inner: Arc::new(node),
@@ -235,7 +420,13 @@ struct NodeInner {
     #[cfg(feature = "open-metrics")]
     metrics_recorder: Option<NodeMetricsRecorder>,
     reward_address: RewardsAddress,
+    rewards_ledger: RewardsLedger,
     evm_network: EvmNetwork,
+    replication_config: ReplicationConfig,
+    replication_scheduler: Mutex<ReplicationScheduler>,
+    payment_rejections: Mutex<PaymentRejections>,
+    accepted_payments: Mutex<AcceptedPayments>,
+    archive_mode: bool,
 }
 
 impl Node {
@@ -266,10 +457,38 @@ impl Node {
         &self.inner.reward_address
     }
 
+    /// Returns the ledger of the rewards received by the node
+    pub(crate) fn rewards_ledger(&self) -> &RewardsLedger {
+        &self.inner.rewards_ledger
+    }
+
     pub(crate) fn evm_network(&self) -> &EvmNetwork {
         &self.inner.evm_network
     }
 
+    pub(crate) fn replication_config(&self) -> &ReplicationConfig {
+        &self.inner.replication_config
+    }
+
+    pub(crate) fn replication_scheduler(&self) -> &Mutex<ReplicationScheduler> {
+        &self.inner.replication_scheduler
+    }
+
+    /// The payments recently rejected, for the clients to ask about
+    pub(crate) fn payment_rejections(&self) -> &Mutex<PaymentRejections> {
+        &self.inner.payment_rejections
+    }
+
+    /// The payments recently verified, for the storage receipts to vouch for
+    pub(crate) fn accepted_payments(&self) -> &Mutex<AcceptedPayments> {
+        &self.inner.accepted_payments
+    }
+
+    /// Whether the node accepts the unpaid chunks pushed to it, see [`NodeBuilder::archive_mode`]
+    pub(crate) fn archive_mode(&self) -> bool {
+        self.inner.archive_mode
+    }
+
     /// Runs the provided `SwarmDriver` and spawns a task to process for `NetworkEvents`
     fn run(self, swarm_driver: SwarmDriver, mut network_event_receiver: Receiver<NetworkEvent>) {
         let mut rng = StdRng::from_entropy();
@@ -280,9 +499,10 @@ impl Node {
         let _handle = spawn(async move {
             // use a random inactivity timeout to ensure that the nodes do not sync when messages
             // are being transmitted.
-            let replication_interval: u64 = rng.gen_range(
-                PERIODIC_REPLICATION_INTERVAL_MAX_S / 2..PERIODIC_REPLICATION_INTERVAL_MAX_S,
-            );
+            let replication_interval_max =
+                self.replication_config().periodic_interval.as_secs().max(2);
+            let replication_interval: u64 =
+                rng.gen_range(replication_interval_max / 2..replication_interval_max);
             let replication_interval_time = Duration::from_secs(replication_interval);
             debug!("Replication interval set to {replication_interval_time:?}");
 
@@ -367,7 +587,8 @@ impl Node {
                         let network = self.network().clone();
 
                         let _handle = spawn(async move {
-                            Self::trigger_irrelevant_record_cleanup(network);
+                            Self::trigger_irrelevant_record_cleanup(network.clone());
+                            Self::prune_replicated_irrelevant_records(network).await;
                         });
                     }
                     // runs every storage_challenge_interval time
@@ -377,7 +598,8 @@ impl Node {
                         let network = self.network().clone();
 
                         let _handle = spawn(async move {
-                            Self::storage_challenge(network).await;
+                            Self::storage_challenge(network.clone()).await;
+                            Self::audit_chunk_holders(network).await;
                             trace!("Periodic storage challenge took {:?}", start.elapsed());
                         });
                     }
@@ -433,26 +655,24 @@ impl Node {
                 self.record_metrics(Marker::PeersInRoutingTable(connected_peers));
                 self.record_metrics(Marker::PeerAddedToRoutingTable(&peer_id));
 
-                // try replication here
-                let network = self.network().clone();
-                self.record_metrics(Marker::IntervalReplicationTriggered);
-                let _handle = spawn(async move {
-                    Self::try_interval_replication(network);
-                });
+                // push the records the peer is now a holder of
+                self.schedule_churn_replication();
             }
             NetworkEvent::PeerRemoved(peer_id, connected_peers) => {
                 event_header = "PeerRemoved";
                 self.record_metrics(Marker::PeersInRoutingTable(connected_peers));
                 self.record_metrics(Marker::PeerRemovedFromRoutingTable(&peer_id));
 
-                let network = self.network().clone();
-                self.record_metrics(Marker::IntervalReplicationTriggered);
-                let _handle = spawn(async move {
-                    Self::try_interval_replication(network);
-                });
+                // push the records the peer held to their next holders
+                self.schedule_churn_replication();
             }
-            NetworkEvent::PeerWithUnsupportedProtocol { .. } => {
+            NetworkEvent::PeerWithUnsupportedProtocol { mismatch, .. } => {
                 event_header = "PeerWithUnsupportedProtocol";
+                if let ProtocolMismatch::IncompatibleVersion { ours, theirs } = &mismatch {
+                    if theirs > ours {
+                        warn!("Found a peer running a newer protocol, this node may need an upgrade: {mismatch}");
+                    }
+                }
             }
             NetworkEvent::NewListenAddr(_) => {
                 event_header = "NewListenAddr";
@@ -486,23 +706,30 @@ impl Node {
             }
             NetworkEvent::QueryRequestReceived { query, channel } => {
                 event_header = "QueryRequestReceived";
-                let network = self.network().clone();
-                let payment_address = *self.reward_address();
+                let node = self.clone();
 
                 let _handle = spawn(async move {
-                    let res = Self::handle_query(&network, query, payment_address).await;
+                    let network = node.network();
+                    let res = Self::handle_query(
+                        network,
+                        query,
+                        *node.reward_address(),
+                        node.payment_rejections(),
+                        node.accepted_payments(),
+                    )
+                    .await;
                     debug!("Sending response {res:?}");
 
                     network.send_response(res, channel);
                 });
             }
-            NetworkEvent::UnverifiedRecord(record) => {
+            NetworkEvent::UnverifiedRecord(record, source) => {
                 event_header = "UnverifiedRecord";
                 // queries can be long running and require validation, so we spawn a task to handle them
                 let self_clone = self.clone();
                 let _handle = spawn(async move {
                     let key = PrettyPrintRecordKey::from(&record.key).into_owned();
-                    match self_clone.validate_and_store_record(record).await {
+                    match self_clone.validate_and_store_record(record, source).await {
                         Ok(()) => debug!("UnverifiedRecord {key} has been stored"),
                         Err(err) => {
                             self_clone.record_metrics(Marker::RecordRejected(&key, &err));
@@ -538,6 +765,27 @@ impl Node {
                     quotes_verification(&network, quotes).await;
                 });
             }
+            NetworkEvent::GossipsubMsgReceived { topic, msg } => {
+                event_header = "GossipsubMsgReceived";
+                // Nodes only publish notifications, the clients are the ones subscribing to them
+                debug!("Ignoring gossipsub msg of {} bytes on {topic}", msg.len());
+            }
+            NetworkEvent::ConnectivityDegraded(health) => {
+                event_header = "ConnectivityDegraded";
+                warn!("Our connectivity to the network is degraded, re-bootstrapping: {health:?}");
+                self.events_channel()
+                    .broadcast(NodeEvent::ConnectivityDegraded);
+            }
+            NetworkEvent::ConnectivityRestored(health) => {
+                event_header = "ConnectivityRestored";
+                info!("Our connectivity to the network is restored: {health:?}");
+                self.events_channel()
+                    .broadcast(NodeEvent::ConnectivityRestored);
+            }
+            NetworkEvent::SybilSuspected(suspicion) => {
+                event_header = "SybilSuspected";
+                warn!("Suspected a sybil attack: {suspicion:?}");
+            }
         }
 
         trace!(
@@ -568,6 +816,8 @@ impl Node {
         network: &Network,
         query: Query,
         payment_address: RewardsAddress,
+        payment_rejections: &Mutex<PaymentRejections>,
+        accepted_payments: &Mutex<AcceptedPayments>,
     ) -> Response {
         let resp: QueryResponse = match query {
             Query::GetStoreQuote {
@@ -579,8 +829,7 @@ impl Node {
                 let record_key = key.to_record_key();
                 let self_id = network.peer_id();
 
-                let maybe_quoting_metrics =
-                    network.get_local_quoting_metrics(record_key.clone()).await;
+                let maybe_quoting_metrics = network.get_local_quoting_metrics(&key).await;
 
                 let storage_proofs = if let Some(nonce) = nonce {
                     Self::respond_x_closest_record_proof(
@@ -678,6 +927,28 @@ impl Node {
                         .await,
                 )
             }
+            Query::GetStorageReceipt { key, payment_hash } => {
+                debug!("Got GetStorageReceipt for {key:?} paid with {payment_hash:?}");
+
+                QueryResponse::GetStorageReceipt(
+                    Self::respond_storage_receipt(network, key, payment_hash, accepted_payments)
+                        .await,
+                )
+            }
+            Query::GetPaymentRejection { key, payment_hash } => {
+                debug!("Got GetPaymentRejection for {key:?} paid with {payment_hash:?}");
+
+                let rejection = payment_rejections
+                    .lock()
+                    .ok()
+                    .and_then(|rejections| rejections.get(&key, &payment_hash));
+                QueryResponse::GetPaymentRejection(rejection.ok_or_else(|| {
+                    ProtocolError::PaymentNotRejected {
+                        holder: Box::new(NetworkAddress::from_peer(network.peer_id())),
+                        key: Box::new(key),
+                    }
+                }))
+            }
             Query::CheckNodeInProblem(target_address) => {
                 debug!("Got CheckNodeInProblem for peer {target_address:?}");
 
@@ -711,6 +982,54 @@ impl Node {
         Response::Query(resp)
     }
 
+    /// Sign a receipt for the record, if we hold it and verified its payment of the given hash.
+    async fn respond_storage_receipt(
+        network: &Network,
+        key: NetworkAddress,
+        payment_hash: XorName,
+        accepted_payments: &Mutex<AcceptedPayments>,
+    ) -> Result<StorageReceipt, ProtocolError> {
+        let is_held = match key.as_record_key() {
+            Some(record_key) => network
+                .is_record_key_present_locally(&record_key)
+                .await
+                .unwrap_or(false),
+            None => false,
+        };
+        if !is_held {
+            return Err(ProtocolError::RecordNotHeld {
+                holder: Box::new(NetworkAddress::from_peer(network.peer_id())),
+                key: Box::new(key),
+            });
+        }
+        let is_verified = accepted_payments
+            .lock()
+            .is_ok_and(|accepted| accepted.contains(&key, &payment_hash));
+        if !is_verified {
+            return Err(ProtocolError::PaymentNotVerified {
+                holder: Box::new(NetworkAddress::from_peer(network.peer_id())),
+                key: Box::new(key),
+                payment_hash,
+            });
+        }
+
+        let node_key = network.get_pub_key();
+        let timestamp = SystemTime::now();
+        let bytes = StorageReceipt::bytes_for_signing(&key, &payment_hash, &node_key, timestamp);
+        let signature = network.sign(&bytes).map_err(|err| {
+            error!("Failed to sign the storage receipt for {key:?}: {err}");
+            ProtocolError::StorageReceiptSigningFailed
+        })?;
+
+        Ok(StorageReceipt {
+            address: key,
+            payment_hash,
+            node_key,
+            timestamp,
+            signature,
+        })
+    }
+
     async fn respond_get_closest_peers(
         network: &Network,
         target: NetworkAddress,
@@ -956,6 +1275,100 @@ impl Node {
         );
     }
 
+    /// Challenge the closest holders of a few random local chunks to prove they hold them,
+    /// by hashing the chunk with a fresh nonce. Unlike the storage challenge, which scores the
+    /// neighbours on a range of records, this audits each holder on specific records.
+    async fn audit_chunk_holders(network: Network) {
+        let start = Instant::now();
+        let self_id = network.peer_id();
+        let closest_peers = match network.get_closest_k_value_local_peers().await {
+            Ok(closest_peers) => closest_peers
+                .into_iter()
+                .filter(|peer_id| *peer_id != self_id)
+                .take(CLOSE_GROUP_SIZE)
+                .collect_vec(),
+            Err(err) => {
+                error!("Cannot get local neighbours to audit: {err:?}");
+                return;
+            }
+        };
+        let local_chunks = match network.get_all_local_record_addresses().await {
+            Ok(all_keys) => all_keys
+                .into_iter()
+                .filter_map(|(addr, record_type)| {
+                    (record_type == RecordType::Chunk).then_some(addr)
+                })
+                .collect_vec(),
+            Err(err) => {
+                error!("Failed to get local record addresses to audit: {err:?}");
+                return;
+            }
+        };
+        if closest_peers.is_empty() || local_chunks.is_empty() {
+            debug!("No neighbours or local chunks to audit the holders of");
+            return;
+        }
+
+        let audited_chunks = local_chunks
+            .choose_multiple(&mut OsRng, AUDITED_CHUNKS_PER_CHALLENGE)
+            .cloned()
+            .collect_vec();
+        let mut tasks = JoinSet::new();
+        for addr in audited_chunks {
+            let Ok(Some(record)) = network.get_local_record(&addr.to_record_key()).await else {
+                error!("Local record {addr:?} cann't be loaded from disk.");
+                continue;
+            };
+            let nonce: Nonce = thread_rng().gen::<u64>();
+            let expected_proof = ChunkProof::new(&record.value, nonce);
+            let request = Request::Query(Query::GetChunkExistenceProof {
+                key: addr.clone(),
+                nonce,
+                difficulty: 1,
+            });
+
+            let holders = closest_peers
+                .iter()
+                .sorted_by_key(|peer_id| addr.distance(&NetworkAddress::from_peer(**peer_id)))
+                .take(AUDITED_HOLDERS_PER_CHUNK)
+                .cloned()
+                .collect_vec();
+            for peer_id in holders {
+                let network = network.clone();
+                let request = request.clone();
+                let expected_proof = expected_proof.clone();
+                let addr = addr.clone();
+                let _ = tasks.spawn(async move {
+                    let responses = network
+                        .send_and_get_responses(&[peer_id], &request, true)
+                        .await;
+                    let verdict = audit_verdict(&addr, &expected_proof, responses.get(&peer_id));
+                    (peer_id, addr, verdict)
+                });
+            }
+        }
+
+        while let Some(res) = tasks.join_next().await {
+            match res {
+                Ok((peer_id, addr, AuditVerdict::Passed)) => {
+                    trace!("Peer {peer_id:?} proved holding {addr:?}");
+                }
+                Ok((peer_id, addr, verdict)) => {
+                    info!("Peer {peer_id:?} failed the audit of {addr:?}: {verdict:?}");
+                    network.record_node_issues(peer_id, NodeIssue::FailedChunkProofCheck);
+                }
+                Err(e) => {
+                    info!("Chunk holders audit task completed with error {e:?}");
+                }
+            }
+        }
+
+        info!(
+            "Completed the audit of chunk holders in {:?}",
+            start.elapsed()
+        );
+    }
+
     #[allow(dead_code)]
     async fn network_density_sampling(network: Network) {
         for _ in 0..10 {
@@ -1015,6 +1428,38 @@ async fn scoring_peer(
     }
 }
 
+/// The outcome of challenging a peer to prove it holds a chunk.
+#[derive(Debug, PartialEq, Eq)]
+enum AuditVerdict {
+    /// The proof matches the one over our copy of the chunk
+    Passed,
+    /// The peer answered it doesn't hold the chunk
+    Missing,
+    /// The proof doesn't match, the peer's copy is tampered or it's faking the proof
+    WrongProof,
+    /// The peer didn't answer the challenge
+    Unanswered,
+}
+
+fn audit_verdict(
+    addr: &NetworkAddress,
+    expected_proof: &ChunkProof,
+    response: Option<&Result<Response, ant_networking::NetworkError>>,
+) -> AuditVerdict {
+    let Some(Ok(Response::Query(QueryResponse::GetChunkExistenceProof(answers)))) = response else {
+        return AuditVerdict::Unanswered;
+    };
+    match answers
+        .iter()
+        .find(|(answered_addr, _)| answered_addr == addr)
+    {
+        Some((_, Ok(proof))) if expected_proof.verify(proof) => AuditVerdict::Passed,
+        Some((_, Ok(_))) => AuditVerdict::WrongProof,
+        Some((_, Err(_))) => AuditVerdict::Missing,
+        None => AuditVerdict::Unanswered,
+    }
+}
+
 // Based on following metrics:
 //   * the duration
 //   * is there false answer
@@ -1079,6 +1524,55 @@ mod tests {
     use super::*;
     use std::str::FromStr;
 
+    #[test]
+    fn audit_verdict_checks_the_proof_of_the_audited_chunk() {
+        let addr = NetworkAddress::from_peer(PeerId::random());
+        let nonce: Nonce = 7;
+        let expected_proof = ChunkProof::new(b"chunk", nonce);
+        let answer = |answers| {
+            Ok(Response::Query(QueryResponse::GetChunkExistenceProof(
+                answers,
+            )))
+        };
+
+        let passed = answer(vec![(addr.clone(), Ok(expected_proof.clone()))]);
+        assert_eq!(
+            audit_verdict(&addr, &expected_proof, Some(&passed)),
+            AuditVerdict::Passed
+        );
+
+        let wrong_proof = answer(vec![(
+            addr.clone(),
+            Ok(ChunkProof::new(b"tampered", nonce)),
+        )]);
+        assert_eq!(
+            audit_verdict(&addr, &expected_proof, Some(&wrong_proof)),
+            AuditVerdict::WrongProof
+        );
+
+        let missing = answer(vec![(
+            addr.clone(),
+            Err(ProtocolError::ChunkDoesNotExist(addr.clone())),
+        )]);
+        assert_eq!(
+            audit_verdict(&addr, &expected_proof, Some(&missing)),
+            AuditVerdict::Missing
+        );
+
+        let other_chunk = answer(vec![(
+            NetworkAddress::from_peer(PeerId::random()),
+            Ok(expected_proof.clone()),
+        )]);
+        assert_eq!(
+            audit_verdict(&addr, &expected_proof, Some(&other_chunk)),
+            AuditVerdict::Unanswered
+        );
+        assert_eq!(
+            audit_verdict(&addr, &expected_proof, None),
+            AuditVerdict::Unanswered
+        );
+    }
+
     #[test]
     fn test_no_local_peers() {
         let local_peers: Vec<(PeerId, Vec<Multiaddr>)> = vec![];
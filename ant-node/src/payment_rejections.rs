@@ -0,0 +1,94 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_protocol::{messages::PaymentRejection, NetworkAddress};
+use std::collections::{HashMap, VecDeque};
+use xor_name::XorName;
+
+/// The number of payment rejections kept at most, the oldest ones being dropped first.
+const MAX_PAYMENT_REJECTIONS: usize = 1024;
+
+/// The payments we recently rejected, for the clients to ask us why, there being no response to a put to carry it.
+#[derive(Debug, Default)]
+pub(crate) struct PaymentRejections {
+    rejections: HashMap<(NetworkAddress, XorName), PaymentRejection>,
+    order: VecDeque<(NetworkAddress, XorName)>,
+}
+
+impl PaymentRejections {
+    pub(crate) fn insert(&mut self, rejection: PaymentRejection) {
+        let key = (rejection.address.clone(), rejection.payment_hash);
+        if self.rejections.insert(key.clone(), rejection).is_some() {
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > MAX_PAYMENT_REJECTIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                let _ = self.rejections.remove(&oldest);
+            }
+        }
+    }
+
+    pub(crate) fn get(
+        &self,
+        address: &NetworkAddress,
+        payment_hash: &XorName,
+    ) -> Option<PaymentRejection> {
+        self.rejections
+            .get(&(address.clone(), *payment_hash))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_evm::RewardsAddress;
+    use ant_protocol::messages::PaymentRejectionReason;
+    use libp2p::PeerId;
+    use std::time::SystemTime;
+
+    fn rejection(index: usize) -> PaymentRejection {
+        PaymentRejection {
+            node: NetworkAddress::from_peer(PeerId::random()),
+            address: NetworkAddress::from_chunk_address(ant_protocol::storage::ChunkAddress::new(
+                XorName::from_content(&index.to_be_bytes()),
+            )),
+            payment_hash: XorName::from_content(b"payment"),
+            reason: PaymentRejectionReason::QuoteExpired,
+            quote_hashes: vec![],
+            rewards_address: RewardsAddress::ZERO,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn the_latest_rejections_are_kept() {
+        let mut rejections = PaymentRejections::default();
+        let first = rejection(0);
+        rejections.insert(first.clone());
+        assert_eq!(
+            rejections.get(&first.address, &first.payment_hash),
+            Some(first.clone())
+        );
+        // Only the payment the record was put with is told about.
+        assert!(rejections
+            .get(&first.address, &XorName::from_content(b"other payment"))
+            .is_none());
+
+        for index in 1..=MAX_PAYMENT_REJECTIONS {
+            rejections.insert(rejection(index));
+        }
+        assert!(rejections
+            .get(&first.address, &first.payment_hash)
+            .is_none());
+        let last = rejection(MAX_PAYMENT_REJECTIONS);
+        assert!(rejections.get(&last.address, &last.payment_hash).is_some());
+        assert_eq!(rejections.rejections.len(), MAX_PAYMENT_REJECTIONS);
+    }
+}
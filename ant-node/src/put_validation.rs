@@ -7,26 +7,37 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use std::collections::BTreeSet;
+use std::time::SystemTime;
 
+use crate::rewards::{close_group_id, RewardRecord};
 use crate::{node::Node, Error, Marker, Result};
 use ant_evm::payment_vault::verify_data_payment;
 use ant_evm::{AttoTokens, ProofOfPayment};
-use ant_networking::NetworkError;
+use ant_networking::{NetworkError, RecordSource};
 use ant_protocol::storage::Transaction;
 use ant_protocol::{
+    messages::{register_topic, PaymentRejection, PaymentRejectionReason, RegisterNotification},
     storage::{
-        try_deserialize_record, try_serialize_record, Chunk, RecordHeader, RecordKind, RecordType,
-        Scratchpad, TransactionAddress,
+        try_deserialize_record, try_serialize_record, Chunk, Pointer, RecordHeader, RecordKind,
+        RecordType, Scratchpad, TransactionAddress, MAX_SCRATCHPAD_SIZE,
     },
+    version::supports_scratchpad_expiry,
     NetworkAddress, PrettyPrintRecordKey,
 };
-use ant_registers::SignedRegister;
+use ant_registers::{RegisterOp, SignedRegister};
 use libp2p::kad::{Record, RecordKey};
+use libp2p::PeerId;
 use xor_name::XorName;
 
 impl Node {
-    /// Validate a record and its payment, and store the record to the RecordStore
-    pub(crate) async fn validate_and_store_record(&self, record: Record) -> Result<()> {
+    /// Validate a record and its payment, and store the record to the RecordStore.
+    /// `source` is the peer that put it, or `None` if put by ourselves.
+    pub(crate) async fn validate_and_store_record(
+        &self,
+        record: Record,
+        source: Option<PeerId>,
+    ) -> Result<()> {
+        let source = RecordSource::Put(source);
         let record_header = RecordHeader::from_record(&record)?;
 
         match record_header.kind {
@@ -70,7 +81,7 @@ impl Node {
                 // Writing chunk to disk takes time, hence try to execute it first.
                 // So that when the replicate target asking for the copy,
                 // the node can have a higher chance to respond.
-                let store_chunk_result = self.store_chunk(&chunk);
+                let store_chunk_result = self.store_chunk(&chunk, source);
 
                 if store_chunk_result.is_ok() {
                     Marker::ValidPaidChunkPutFromClient(&PrettyPrintRecordKey::from(&record.key))
@@ -87,6 +98,23 @@ impl Node {
                 store_chunk_result
             }
 
+            RecordKind::Chunk if self.archive_mode() => {
+                let chunk = try_deserialize_record::<Chunk>(&record)?;
+                let already_exists = self
+                    .validate_key_and_existence(&chunk.network_address(), &record.key)
+                    .await?;
+                if already_exists {
+                    debug!(
+                        "Archived chunk with addr {:?} already exists",
+                        chunk.network_address()
+                    );
+                    return Ok(());
+                }
+
+                // Archived for free, beyond our close group responsibility, so not replicated further.
+                debug!("Archiving chunk {:?}", chunk.network_address());
+                self.store_chunk(&chunk, source)
+            }
             RecordKind::Chunk => {
                 error!("Chunk should not be validated at this point");
                 Err(Error::InvalidPutWithoutPayment(
@@ -117,7 +145,12 @@ impl Node {
                 // So that when the replicate target asking for the copy,
                 // the node can have a higher chance to respond.
                 let store_scratchpad_result = self
-                    .validate_and_store_scratchpad_record(scratchpad, record_key.clone(), true)
+                    .validate_and_store_scratchpad_record(
+                        scratchpad,
+                        record_key.clone(),
+                        true,
+                        source,
+                    )
                     .await;
 
                 match store_scratchpad_result {
@@ -160,7 +193,72 @@ impl Node {
                 }
 
                 // store the scratchpad
-                self.validate_and_store_scratchpad_record(scratchpad, key, false)
+                self.validate_and_store_scratchpad_record(scratchpad, key, false, source)
+                    .await
+            }
+            RecordKind::PointerWithPayment => {
+                let record_key = record.key.clone();
+                let (payment, pointer) =
+                    try_deserialize_record::<(ProofOfPayment, Pointer)>(&record)?;
+                let _already_exists = self
+                    .validate_key_and_existence(&pointer.network_address(), &record_key)
+                    .await?;
+
+                // Validate the payment and that we received what we asked.
+                // This stores any payments to disk
+                let payment_res = self
+                    .payment_for_us_exists_and_is_still_valid(&pointer.network_address(), payment)
+                    .await;
+
+                // Finally before we store, lets bail for any payment issues
+                payment_res?;
+
+                let record_type = pointer_record_type(&pointer)?;
+                // Replicated once below rather than when stored, for an outdated PUT to be retried as well.
+                let store_pointer_result = self
+                    .validate_and_store_pointer_record(pointer, record_key.clone(), false, source)
+                    .await;
+
+                match store_pointer_result {
+                    // if we're receiving this pointer PUT again, and we have been paid,
+                    // we eagerly retry replicaiton as it seems like other nodes are having trouble
+                    // did not manage to get this pointer as yet.
+                    Ok(_) | Err(Error::IgnoringOutdatedPointerPut) => {
+                        Marker::ValidPointerRecordPutFromClient(&PrettyPrintRecordKey::from(
+                            &record_key,
+                        ))
+                        .log();
+                        self.replicate_valid_fresh_record(record_key.clone(), record_type.clone());
+
+                        // Notify replication_fetcher to mark the attempt as completed.
+                        // Send the notification earlier to avoid it got skipped due to:
+                        // the record becomes stored during the fetch because of other interleaved process.
+                        self.network()
+                            .notify_fetch_completed(record_key, record_type);
+                    }
+                    Err(_) => {}
+                }
+
+                store_pointer_result
+            }
+            RecordKind::Pointer => {
+                // make sure we already have this pointer locally, else reject it as first time upload needs payment
+                let key = record.key.clone();
+                let pointer = try_deserialize_record::<Pointer>(&record)?;
+                let pretty_key = PrettyPrintRecordKey::from(&key);
+                trace!("Got record to store without payment for pointer at {pretty_key:?}");
+                if !self
+                    .validate_key_and_existence(&pointer.network_address(), &key)
+                    .await?
+                {
+                    warn!("Ignore store without payment for pointer at {pretty_key:?}");
+                    return Err(Error::InvalidPutWithoutPayment(
+                        PrettyPrintRecordKey::from(&record.key).into_owned(),
+                    ));
+                }
+
+                // store the pointer
+                self.validate_and_store_pointer_record(pointer, key, true, source)
                     .await
             }
             RecordKind::Transaction => {
@@ -204,7 +302,7 @@ impl Node {
                 }
 
                 let res = self
-                    .validate_merge_and_store_transactions(vec![transaction], &key)
+                    .validate_merge_and_store_transactions(vec![transaction], &key, source)
                     .await;
                 if res.is_ok() {
                     let content_hash = XorName::from_content(&record.value);
@@ -242,7 +340,9 @@ impl Node {
 
                 // store the update
                 debug!("Store update without payment as we already had register at {pretty_key:?}");
-                let result = self.validate_and_store_register(register, true).await;
+                let result = self
+                    .validate_and_store_register(register, true, source)
+                    .await;
 
                 if result.is_ok() {
                     debug!("Successfully stored register update at {pretty_key:?}");
@@ -297,7 +397,9 @@ impl Node {
                     }
                 }
 
-                let res = self.validate_and_store_register(register, true).await;
+                let res = self
+                    .validate_and_store_register(register, true, source)
+                    .await;
                 if res.is_ok() {
                     let content_hash = XorName::from_content(&record.value);
 
@@ -315,7 +417,11 @@ impl Node {
     }
 
     /// Store a pre-validated, and already paid record to the RecordStore
-    pub(crate) async fn store_replicated_in_record(&self, record: Record) -> Result<()> {
+    pub(crate) async fn store_replicated_in_record(
+        &self,
+        record: Record,
+        source: RecordSource,
+    ) -> Result<()> {
         debug!("Storing record which was replicated to us {:?}", record.key);
         let record_header = RecordHeader::from_record(&record)?;
         match record_header.kind {
@@ -323,7 +429,8 @@ impl Node {
             RecordKind::ChunkWithPayment
             | RecordKind::TransactionWithPayment
             | RecordKind::RegisterWithPayment
-            | RecordKind::ScratchpadWithPayment => {
+            | RecordKind::ScratchpadWithPayment
+            | RecordKind::PointerWithPayment => {
                 warn!("Prepaid record came with Payment, which should be handled in another flow");
                 Err(Error::UnexpectedRecordWithPayment(
                     PrettyPrintRecordKey::from(&record.key).into_owned(),
@@ -344,18 +451,24 @@ impl Node {
                     return Ok(());
                 }
 
-                self.store_chunk(&chunk)
+                self.store_chunk(&chunk, source)
             }
             RecordKind::Scratchpad => {
                 let key = record.key.clone();
                 let scratchpad = try_deserialize_record::<Scratchpad>(&record)?;
-                self.validate_and_store_scratchpad_record(scratchpad, key, false)
+                self.validate_and_store_scratchpad_record(scratchpad, key, false, source)
+                    .await
+            }
+            RecordKind::Pointer => {
+                let key = record.key.clone();
+                let pointer = try_deserialize_record::<Pointer>(&record)?;
+                self.validate_and_store_pointer_record(pointer, key, false, source)
                     .await
             }
             RecordKind::Transaction => {
                 let record_key = record.key.clone();
                 let transactions = try_deserialize_record::<Vec<Transaction>>(&record)?;
-                self.validate_merge_and_store_transactions(transactions, &record_key)
+                self.validate_merge_and_store_transactions(transactions, &record_key, source)
                     .await
             }
             RecordKind::Register => {
@@ -370,7 +483,8 @@ impl Node {
                     );
                     return Err(Error::RecordKeyMismatch);
                 }
-                self.validate_and_store_register(register, false).await
+                self.validate_and_store_register(register, false, source)
+                    .await
             }
         }
     }
@@ -413,7 +527,7 @@ impl Node {
     }
 
     /// Store a `Chunk` to the RecordStore
-    pub(crate) fn store_chunk(&self, chunk: &Chunk) -> Result<()> {
+    pub(crate) fn store_chunk(&self, chunk: &Chunk, source: RecordSource) -> Result<()> {
         let chunk_name = *chunk.name();
         let chunk_addr = *chunk.address();
 
@@ -429,7 +543,7 @@ impl Node {
 
         // finally store the Record directly into the local storage
         debug!("Storing chunk {chunk_name:?} as Record locally");
-        self.network().put_local_record(record);
+        self.network().put_local_record(record, source);
 
         self.record_metrics(Marker::ValidChunkRecordPutFromNetwork(&pretty_key));
 
@@ -446,11 +560,14 @@ impl Node {
     /// Check Counter: It MUST ensure that the new counter value is strictly greater than the currently stored value to prevent replay attacks.
     /// Verify Signature: It MUST use the public key to verify the BLS12-381 signature against the content hash and the counter.
     /// Accept or Reject: If all verifications succeed, the node MUST accept the packet and replace any previous version. Otherwise, it MUST reject the update.
+    ///
+    /// A scratchpad with an expiry, signed along with its content, is only stored until then.
     pub(crate) async fn validate_and_store_scratchpad_record(
         &self,
         scratchpad: Scratchpad,
         record_key: RecordKey,
         is_client_put: bool,
+        source: RecordSource,
     ) -> Result<()> {
         // owner PK is defined herein, so as long as record key and this match, we're good
         let addr = scratchpad.address();
@@ -478,6 +595,16 @@ impl Node {
             warn!("Rejecting Scratchpad PUT with invalid signature");
             return Err(Error::InvalidScratchpadSignature);
         }
+        validate_scratchpad_expiry(&scratchpad, &record_key)?;
+
+        let size = scratchpad.payload_size();
+        if size > MAX_SCRATCHPAD_SIZE {
+            warn!("Rejecting Scratchpad PUT of {size} bytes, which is too big");
+            return Err(Error::ScratchpadTooBig {
+                size,
+                max: MAX_SCRATCHPAD_SIZE,
+            });
+        }
 
         info!(
             "Storing sratchpad {addr:?} with content of {:?} as Record locally",
@@ -490,7 +617,7 @@ impl Node {
             publisher: None,
             expires: None,
         };
-        self.network().put_local_record(record);
+        self.network().put_local_record(record, source);
 
         let pretty_key = PrettyPrintRecordKey::from(&scratchpad_key);
 
@@ -502,11 +629,78 @@ impl Node {
 
         Ok(())
     }
+
+    /// Validate and store a `Pointer` to the RecordStore
+    ///
+    /// The pointer is accepted if it is signed by the owner its address derives from, and its counter is strictly
+    /// greater than the one of the locally stored version.
+    pub(crate) async fn validate_and_store_pointer_record(
+        &self,
+        pointer: Pointer,
+        record_key: RecordKey,
+        is_client_put: bool,
+        source: RecordSource,
+    ) -> Result<()> {
+        let addr = *pointer.address();
+        let count = pointer.count();
+        debug!("Validating and storing pointer {addr:?} with count {count}");
+
+        // check if the deserialized value's PointerAddress matches the record's key
+        let pointer_key = pointer.network_address().to_record_key();
+        if pointer_key != record_key {
+            warn!("Record's key does not match with the value's PointerAddress, ignoring PUT.");
+            return Err(Error::RecordKeyMismatch);
+        }
+
+        // check if the Pointer is present locally that we don't have a newer version
+        if let Some(local_pointer) = self.network().get_local_record(&pointer_key).await? {
+            let local_pointer = try_deserialize_record::<Pointer>(&local_pointer)?;
+            if local_pointer.count() >= count {
+                warn!(
+                    "Rejecting Pointer PUT with counter less than or equal to the current counter"
+                );
+                return Err(Error::IgnoringOutdatedPointerPut);
+            }
+        }
+
+        // ensure data integrity
+        if !pointer.verify() {
+            warn!("Rejecting Pointer PUT with invalid signature");
+            return Err(Error::InvalidPointerSignature);
+        }
+
+        let record_type = pointer_record_type(&pointer)?;
+
+        info!(
+            "Storing pointer {addr:?} targeting {:?} as Record locally",
+            pointer.target()
+        );
+
+        let record = Record {
+            key: pointer_key.clone(),
+            value: try_serialize_record(&pointer, RecordKind::Pointer)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        self.network().put_local_record(record, source);
+
+        let pretty_key = PrettyPrintRecordKey::from(&pointer_key);
+
+        self.record_metrics(Marker::ValidPointerRecordPutFromNetwork(&pretty_key));
+
+        if is_client_put {
+            self.replicate_valid_fresh_record(pointer_key, record_type);
+        }
+
+        Ok(())
+    }
+
     /// Validate and store a `Register` to the RecordStore
     pub(crate) async fn validate_and_store_register(
         &self,
         register: SignedRegister,
         is_client_put: bool,
+        source: RecordSource,
     ) -> Result<()> {
         let reg_addr = register.address();
         debug!("Validating and storing register {reg_addr:?}");
@@ -517,16 +711,17 @@ impl Node {
         let pretty_key = PrettyPrintRecordKey::from(&key);
 
         // check register and merge if needed
-        let updated_register = match self.register_validation(&register, present_locally).await? {
-            Some(reg) => {
-                debug!("Register {pretty_key:?} needed to be updated");
-                reg
-            }
-            None => {
-                debug!("No update needed for register");
-                return Ok(());
-            }
-        };
+        let (updated_register, new_ops) =
+            match self.register_validation(&register, present_locally).await? {
+                Some(update) => {
+                    debug!("Register {pretty_key:?} needed to be updated");
+                    update
+                }
+                None => {
+                    debug!("No update needed for register");
+                    return Ok(());
+                }
+            };
 
         // store in kad
         let record = Record {
@@ -538,10 +733,23 @@ impl Node {
         let content_hash = XorName::from_content(&record.value);
 
         info!("Storing register {reg_addr:?} with content of {content_hash:?} as Record locally");
-        self.network().put_local_record(record);
+        self.network().put_local_record(record, source);
 
         self.record_metrics(Marker::ValidRegisterRecordPutFromNetwork(&pretty_key));
 
+        // Let the clients watching the register know of the accepted writes
+        if !new_ops.is_empty() {
+            let notification = RegisterNotification { ops: new_ops };
+            match rmp_serde::to_vec(&notification) {
+                Ok(msg) => self
+                    .network()
+                    .publish_on_topic(register_topic(reg_addr), msg.into()),
+                Err(err) => {
+                    warn!("Failed to serialize notification for register {pretty_key:?}: {err}")
+                }
+            }
+        }
+
         // Updated register needs to be replicated out as well,
         // to avoid `leaking` of old version due to the mismatch of
         // `close_range` and `replication_range`, combined with nodes churning
@@ -561,6 +769,7 @@ impl Node {
         &self,
         transactions: Vec<Transaction>,
         record_key: &RecordKey,
+        source: RecordSource,
     ) -> Result<()> {
         let pretty_key = PrettyPrintRecordKey::from(record_key);
         debug!("Validating transactions before storage at {pretty_key:?}");
@@ -617,7 +826,7 @@ impl Node {
             publisher: None,
             expires: None,
         };
-        self.network().put_local_record(record);
+        self.network().put_local_record(record, source);
         debug!("Successfully stored validated transactions at {pretty_key:?}");
 
         // Just log the multiple transactions
@@ -646,15 +855,33 @@ impl Node {
         let self_peer_id = self.network().peer_id();
         if !payment.verify_for(self_peer_id) {
             warn!("Payment is not valid for record {pretty_key}");
+            self.record_payment_rejection(address, &payment, PaymentRejectionReason::NotForUs);
             return Err(Error::InvalidRequest(format!(
                 "Payment is not valid for record {pretty_key}"
             )));
         }
         debug!("Payment is valid for record {pretty_key}");
 
+        // verify our quotes are for this record, or scoped to us for any of the records we're responsible for
+        let content = address.as_xorname().unwrap_or_default();
+        if !payment
+            .quotes_by_peer(&self_peer_id)
+            .iter()
+            .all(|quote| quote.covers(&content))
+        {
+            warn!("Payment quote is for another content than record {pretty_key}");
+            self.record_payment_rejection(
+                address,
+                &payment,
+                PaymentRejectionReason::QuoteNotForContent,
+            );
+            return Err(Error::InvalidQuoteContent);
+        }
+
         // verify quote expiration
         if payment.has_expired() {
             warn!("Payment quote has expired for record {pretty_key}");
+            self.record_payment_rejection(address, &payment, PaymentRejectionReason::QuoteExpired);
             return Err(Error::InvalidRequest(format!(
                 "Payment quote has expired for record {pretty_key}"
             )));
@@ -665,6 +892,16 @@ impl Node {
         let mut payees = payment.payees();
         payees.retain(|peer_id| !closest_k_peers.contains(peer_id));
         if !payees.is_empty() {
+            self.record_payment_rejection(
+                address,
+                &payment,
+                PaymentRejectionReason::PayeesOutOfRange(
+                    payees
+                        .iter()
+                        .map(|peer| NetworkAddress::from_peer(*peer))
+                        .collect(),
+                ),
+            );
             return Err(Error::InvalidRequest(format!(
                 "Payment quote has out-of-range payees {payees:?}"
             )));
@@ -681,12 +918,37 @@ impl Node {
         let reward_amount =
             verify_data_payment(self.evm_network(), owned_payment_quotes, payments_to_verify)
                 .await
-                .map_err(|e| Error::EvmNetwork(format!("Failed to verify chunk payment: {e}")))?;
+                .map_err(|e| {
+                    self.record_payment_rejection(
+                        address,
+                        &payment,
+                        PaymentRejectionReason::NotPaidOnChain(e.to_string()),
+                    );
+                    Error::EvmNetwork(format!("Failed to verify chunk payment: {e}"))
+                })?;
         debug!("Payment of {reward_amount:?} is valid for record {pretty_key}");
+        match payment.hash() {
+            Ok(payment_hash) => {
+                if let Ok(mut accepted) = self.accepted_payments().lock() {
+                    accepted.insert(address.clone(), payment_hash);
+                }
+            }
+            Err(err) => warn!("Failed to hash the payment for record {pretty_key}: {err}"),
+        }
 
         // Notify `record_store` that the node received a payment.
         self.network().notify_payment_received();
 
+        let reward = RewardRecord {
+            timestamp: SystemTime::now(),
+            amount: AttoTokens::from(reward_amount),
+            record: hex::encode(key.to_vec()),
+            close_group: close_group_id(&payment.payees()),
+        };
+        if let Err(err) = self.rewards_ledger().append(reward) {
+            warn!("Failed to record the reward for {pretty_key} in the ledger: {err}");
+        }
+
         #[cfg(feature = "open-metrics")]
         if let Some(metrics_recorder) = self.metrics_recorder() {
             // FIXME: We would reach the MAX if the storecost is scaled up.
@@ -719,11 +981,45 @@ impl Node {
         Ok(())
     }
 
+    /// Keep our view of the payment we rejected, for the client to ask about it, see
+    /// [`ant_protocol::messages::Query::GetPaymentRejection`].
+    fn record_payment_rejection(
+        &self,
+        address: &NetworkAddress,
+        payment: &ProofOfPayment,
+        reason: PaymentRejectionReason,
+    ) {
+        let payment_hash = match payment.hash() {
+            Ok(payment_hash) => payment_hash,
+            Err(err) => {
+                warn!("Failed to hash the rejected payment for record {address:?}: {err}");
+                return;
+            }
+        };
+        let self_peer_id = self.network().peer_id();
+        let rejection = PaymentRejection {
+            node: NetworkAddress::from_peer(self_peer_id),
+            address: address.clone(),
+            payment_hash,
+            reason,
+            quote_hashes: payment
+                .quotes_by_peer(&self_peer_id)
+                .iter()
+                .map(|quote| quote.hash())
+                .collect(),
+            rewards_address: *self.reward_address(),
+            timestamp: SystemTime::now(),
+        };
+        if let Ok(mut rejections) = self.payment_rejections().lock() {
+            rejections.insert(rejection);
+        }
+    }
+
     async fn register_validation(
         &self,
         register: &SignedRegister,
         present_locally: bool,
-    ) -> Result<Option<SignedRegister>> {
+    ) -> Result<Option<(SignedRegister, BTreeSet<RegisterOp>)>> {
         // check if register is valid
         let reg_addr = register.address();
         register.verify()?;
@@ -731,7 +1027,7 @@ impl Node {
         // if we don't have it locally return it
         if !present_locally {
             debug!("Register with addr {reg_addr:?} is valid and doesn't exist locally");
-            return Ok(Some(register.to_owned()));
+            return Ok(Some((register.to_owned(), register.ops().clone())));
         }
         debug!("Register with addr {reg_addr:?} exists locally, comparing with local version");
 
@@ -758,7 +1054,12 @@ impl Node {
             Ok(None)
         } else {
             debug!("Register with addr {reg_addr:?} is different from the local version");
-            Ok(Some(merged_register))
+            let new_ops = merged_register
+                .ops()
+                .difference(local_register.ops())
+                .cloned()
+                .collect();
+            Ok(Some((merged_register, new_ops)))
         }
     }
 
@@ -787,3 +1088,34 @@ impl Node {
         Ok(local_transactions)
     }
 }
+
+/// The record type of a stored pointer. Each version of a pointer is tracked by the content hash of its record, so
+/// that replication picks up updates.
+fn pointer_record_type(pointer: &Pointer) -> Result<RecordType> {
+    let value = try_serialize_record(pointer, RecordKind::Pointer)?;
+    Ok(RecordType::NonChunk(XorName::from_content(&value)))
+}
+
+/// A scratchpad can only expire once all the peers decode its expiry, and an expired one is not stored.
+fn validate_scratchpad_expiry(scratchpad: &Scratchpad, key: &RecordKey) -> Result<()> {
+    if scratchpad.expires_at().is_none() {
+        return Ok(());
+    }
+    if !supports_scratchpad_expiry() {
+        warn!("Rejecting a scratchpad with an expiry, not supported by our protocol version yet");
+        return Err(Error::UnexpectedRecordExpiry(
+            PrettyPrintRecordKey::from(key).into_owned(),
+        ));
+    }
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if scratchpad.is_expired(now) {
+        warn!("Rejecting an expired scratchpad");
+        return Err(Error::RecordExpired(
+            PrettyPrintRecordKey::from(key).into_owned(),
+        ));
+    }
+    Ok(())
+}
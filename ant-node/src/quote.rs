@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{node::Node, Error, Result};
-use ant_evm::{PaymentQuote, QuotingMetrics, RewardsAddress};
+use ant_evm::{PaymentQuote, QuotingMetrics, RewardsAddress, QUOTE_EXPIRATION_SECS};
 use ant_networking::Network;
 use ant_protocol::{error::Error as ProtocolError, storage::ChunkAddress, NetworkAddress};
 use libp2p::PeerId;
@@ -22,8 +22,15 @@ impl Node {
     ) -> Result<PaymentQuote, ProtocolError> {
         let content = address.as_xorname().unwrap_or_default();
         let timestamp = std::time::SystemTime::now();
-        let bytes =
-            PaymentQuote::bytes_for_signing(content, timestamp, quoting_metrics, payment_address);
+        // Signing an expiry scopes the quote to us, for the client to reuse it for the records of our close groups.
+        let expires_at = Some(timestamp + Duration::from_secs(QUOTE_EXPIRATION_SECS));
+        let bytes = PaymentQuote::bytes_for_signing(
+            content,
+            timestamp,
+            quoting_metrics,
+            payment_address,
+            expires_at,
+        );
 
         let Ok(signature) = network.sign(&bytes) else {
             return Err(ProtocolError::QuoteGenerationFailed);
@@ -36,6 +43,7 @@ impl Node {
             pub_key: network.get_pub_key(),
             rewards_address: *payment_address,
             signature,
+            expires_at,
         };
 
         debug!("Created payment quote for {address:?}: {quote:?}");
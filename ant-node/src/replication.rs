@@ -6,25 +6,298 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, node::Node};
-use ant_networking::{GetRecordCfg, Network};
+use crate::{
+    error::{Error, Result},
+    node::Node,
+};
+use ant_networking::{GetRecordCfg, Instant, Network, NodeIssue, RecordSource};
 use ant_protocol::{
-    messages::{Cmd, Query, QueryResponse, Request, Response},
+    messages::{ChunkProof, Cmd, Nonce, Query, QueryResponse, Request, Response},
     storage::RecordType,
-    NetworkAddress, PrettyPrintRecordKey,
+    NetworkAddress, PrettyPrintRecordKey, CLOSE_GROUP_SIZE,
 };
 use libp2p::{
     kad::{Quorum, Record, RecordKey},
     PeerId,
 };
+use rand::{thread_rng, Rng};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::PoisonError,
+    time::Duration,
+};
 use tokio::task::spawn;
 
+/// The max number of irrelevant records pruned per round, as each costs a network query.
+const MAX_IRRELEVANT_RECORDS_PRUNED_PER_ROUND: usize = 50;
+
+/// The number of peers of the close group of a record that must prove they hold it, for us to drop our copy.
+const MIN_REPLICAS_TO_PRUNE: usize = CLOSE_GROUP_SIZE / 2 + 1;
+
+/// The config of the replication of the records held by the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationConfig {
+    /// The max interval of the periodic replication of all the records to the close peers.
+    /// Each node picks an interval between half this and this, not to replicate in sync.
+    pub periodic_interval: Duration,
+    /// The min time between two replications triggered by a change of the close group. The changes
+    /// within it are replicated at the end of it.
+    pub churn_min_interval: Duration,
+    /// The max number of record keys pushed to each peer per replication triggered by a change of
+    /// the close group. The rest is left to the periodic replication.
+    pub max_keys_per_burst: usize,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            periodic_interval: Duration::from_secs(crate::PERIODIC_REPLICATION_INTERVAL_MAX_S),
+            churn_min_interval: Duration::from_secs(10),
+            max_keys_per_burst: 1024,
+        }
+    }
+}
+
+/// Schedules the replications triggered by the changes of the routing table, at most one per
+/// `churn_min_interval`, and keeps the close group they were computed against.
+#[derive(Debug, Default)]
+pub(crate) struct ReplicationScheduler {
+    close_group: Vec<PeerId>,
+    last_run: Option<Instant>,
+    pending: bool,
+}
+
+impl ReplicationScheduler {
+    /// The delay to run a replication after, or None if one is already pending.
+    fn schedule(&mut self, min_interval: Duration, now: Instant) -> Option<Duration> {
+        if self.pending {
+            return None;
+        }
+        self.pending = true;
+        Some(
+            self.last_run
+                .map(|last_run| (last_run + min_interval).saturating_duration_since(now))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Record the run of the replication against the new close group, returning the previous one.
+    fn start_run(&mut self, close_group: Vec<PeerId>, now: Instant) -> Vec<PeerId> {
+        self.pending = false;
+        self.last_run = Some(now);
+        std::mem::replace(&mut self.close_group, close_group)
+    }
+
+    /// Drop the pending replication that failed to start, for the next change to schedule another.
+    fn abort_run(&mut self) {
+        self.pending = false;
+    }
+}
+
+/// The keys of the records each peer became a holder of when the close group changed from
+/// `old_group` to `new_group`, at most `max_keys_per_peer` of them.
+///
+/// The holders of a record are the `CLOSE_GROUP_SIZE` peers closest to it, out of the close group
+/// and us. We already hold all the records, so we are never a target.
+pub(crate) fn keys_for_new_holders(
+    our_peer_id: PeerId,
+    old_group: &[PeerId],
+    new_group: &[PeerId],
+    records: impl IntoIterator<Item = (NetworkAddress, RecordType)>,
+    max_keys_per_peer: usize,
+) -> BTreeMap<PeerId, Vec<(NetworkAddress, RecordType)>> {
+    let mut keys_per_peer: BTreeMap<PeerId, Vec<_>> = BTreeMap::new();
+    if old_group.iter().collect::<HashSet<_>>() == new_group.iter().collect::<HashSet<_>>() {
+        return keys_per_peer;
+    }
+
+    let with_us = |group: &[PeerId]| -> Vec<(PeerId, NetworkAddress)> {
+        group
+            .iter()
+            .chain(std::iter::once(&our_peer_id))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|peer| (*peer, NetworkAddress::from_peer(*peer)))
+            .collect()
+    };
+    let old_group = with_us(old_group);
+    let new_group = with_us(new_group);
+    let holders = |group: &[(PeerId, NetworkAddress)], addr: &NetworkAddress| -> Vec<PeerId> {
+        let mut group = group.to_vec();
+        group.sort_by_key(|(_peer, peer_addr)| addr.distance(peer_addr));
+        group
+            .into_iter()
+            .take(CLOSE_GROUP_SIZE)
+            .map(|(peer, _peer_addr)| peer)
+            .collect()
+    };
+
+    for (addr, record_type) in records {
+        let old_holders = holders(&old_group, &addr);
+        for peer in holders(&new_group, &addr) {
+            if peer == our_peer_id || old_holders.contains(&peer) {
+                continue;
+            }
+            let keys = keys_per_peer.entry(peer).or_default();
+            if keys.len() < max_keys_per_peer {
+                keys.push((addr.clone(), record_type.clone()));
+            }
+        }
+    }
+    keys_per_peer
+}
+
 impl Node {
     /// Sends _all_ record keys every interval to all peers within the REPLICATE_RANGE.
     pub(crate) fn try_interval_replication(network: Network) {
         network.trigger_interval_replication()
     }
 
+    /// Push the records to the peers that became their holders with the last changes of the close
+    /// group, without waiting for the periodic replication.
+    pub(crate) fn schedule_churn_replication(&self) {
+        let min_interval = self.replication_config().churn_min_interval;
+        let Some(delay) = self
+            .replication_scheduler()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .schedule(min_interval, Instant::now())
+        else {
+            trace!("A churn replication is already scheduled");
+            return;
+        };
+
+        let node = self.clone();
+        let _handle = spawn(async move {
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            node.churn_replication().await;
+        });
+    }
+
+    async fn churn_replication(&self) {
+        let network = self.network();
+        let (new_group, records) = match (
+            network.get_closest_k_value_local_peers().await,
+            network.get_all_local_record_addresses().await,
+        ) {
+            (Ok(new_group), Ok(records)) => (new_group, records),
+            (Err(err), _) | (_, Err(err)) => {
+                error!("Failed to get the close group or the records to replicate: {err:?}");
+                self.replication_scheduler()
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .abort_run();
+                return;
+            }
+        };
+        let old_group = self
+            .replication_scheduler()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .start_run(new_group.clone(), Instant::now());
+
+        let our_peer_id = network.peer_id();
+        let keys_per_peer = keys_for_new_holders(
+            our_peer_id,
+            &old_group,
+            &new_group,
+            records,
+            self.replication_config().max_keys_per_burst,
+        );
+        let holder = NetworkAddress::from_peer(our_peer_id);
+        for (peer_id, keys) in keys_per_peer {
+            debug!(
+                "Replicating {} keys to {peer_id:?}, which joined their close group",
+                keys.len()
+            );
+            let request = Request::Cmd(Cmd::Replicate {
+                holder: holder.clone(),
+                keys,
+            });
+            network.send_req_ignore_reply(request, peer_id);
+        }
+    }
+
+    /// Drop the records beyond our responsible range, e.g. as the network grew around us, once
+    /// enough peers of their close group proved they hold them.
+    pub(crate) async fn prune_replicated_irrelevant_records(network: Network) {
+        let records = match network.get_irrelevant_record_addresses().await {
+            Ok(records) => records,
+            Err(err) => {
+                error!("Failed to get the irrelevant records: {err:?}");
+                return;
+            }
+        };
+        if records.is_empty() {
+            return;
+        }
+
+        let total = records.len();
+        let mut pruned = 0;
+        for (addr, record_type) in records
+            .into_iter()
+            .take(MAX_IRRELEVANT_RECORDS_PRUNED_PER_ROUND)
+        {
+            if Self::has_enough_replicas(&network, &addr, &record_type).await {
+                network.prune_local_record(addr.to_record_key());
+                pruned += 1;
+            }
+        }
+        info!("Pruned {pruned} irrelevant records replicated at their close group, among {total}");
+    }
+
+    /// Whether enough peers of the close group of the record, which we're out of, hold it.
+    async fn has_enough_replicas(
+        network: &Network,
+        addr: &NetworkAddress,
+        record_type: &RecordType,
+    ) -> bool {
+        let close_group: Vec<_> = match network.node_get_closest_peers(addr).await {
+            Ok(peers) => peers.into_iter().take(CLOSE_GROUP_SIZE).collect(),
+            Err(err) => {
+                debug!("Failed to get the close group of {addr:?}: {err:?}");
+                return false;
+            }
+        };
+        if close_group.contains(&network.peer_id()) {
+            debug!("Still in the close group of {addr:?}, keeping it");
+            return false;
+        }
+        let Ok(Some(record)) = network.get_local_record(&addr.to_record_key()).await else {
+            return false;
+        };
+
+        let nonce: Nonce = thread_rng().gen::<u64>();
+        let expected_proof = ChunkProof::new(&record.value, nonce);
+        let request = Request::Query(Query::GetChunkExistenceProof {
+            key: addr.clone(),
+            nonce,
+            difficulty: 1,
+        });
+        let responses = network
+            .send_and_get_responses(&close_group, &request, true)
+            .await;
+        let replicas = responses
+            .values()
+            .filter(|response| {
+                let Ok(Response::Query(QueryResponse::GetChunkExistenceProof(answers))) = response
+                else {
+                    return false;
+                };
+                // The mutable records may have moved on to a newer version, which is as good.
+                answers.iter().any(|(_addr, proof)| {
+                    proof.as_ref().is_ok_and(|proof| {
+                        *record_type != RecordType::Chunk || proof.verify(&expected_proof)
+                    })
+                })
+            })
+            .count();
+        debug!("{replicas} peers of the close group of {addr:?} hold it");
+        replicas >= MIN_REPLICAS_TO_PRUNE
+    }
+
     /// Cleanup unrelevant records if accumulated too many.
     pub(crate) fn trigger_irrelevant_record_cleanup(network: Network) {
         network.trigger_irrelevant_record_cleanup()
@@ -64,6 +337,7 @@ impl Node {
                     None
                 };
 
+                let fetched_from_holder = record_opt.is_some();
                 let record = if let Some(record_content) = record_opt {
                     Record::new(key, record_content.to_vec())
                 } else {
@@ -80,7 +354,7 @@ impl Node {
                         is_register: false,
                     };
                     match node.network().get_record_from_network(key, &get_cfg).await {
-                        Ok(record) => record,
+                        Ok(get_record_ok) => get_record_ok.record,
                         Err(err) => {
                             error!("During replication fetch of {pretty_key:?}, failed in re-attempt of get from network {err:?}");
                             return;
@@ -91,8 +365,13 @@ impl Node {
                 debug!(
                     "Got Replication Record {pretty_key:?} from network, validating and storing it"
                 );
-                if let Err(err) = node.store_replicated_in_record(record).await {
+                let source = RecordSource::Replication(fetched_from_holder.then_some(holder));
+                if let Err(err) = node.store_replicated_in_record(record, source).await {
                     error!("During store replication fetched {pretty_key:?}, got error {err:?}");
+                    if fetched_from_holder && matches!(err, Error::RecordKeyMismatch) {
+                        node.network()
+                            .record_node_issues(holder, NodeIssue::CorruptedRecord);
+                    }
                 } else {
                     debug!("Completed storing Replication Record {pretty_key:?} from network.");
                 }
@@ -178,3 +457,76 @@ impl Node {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_pushed_to_their_new_holders_only() {
+        let our_peer_id = PeerId::random();
+        let old_group: Vec<_> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let records: Vec<_> = (0..50)
+            .map(|_| {
+                (
+                    NetworkAddress::from_peer(PeerId::random()),
+                    RecordType::Chunk,
+                )
+            })
+            .collect();
+
+        // No change, nothing to push
+        assert!(
+            keys_for_new_holders(our_peer_id, &old_group, &old_group, records.clone(), 100)
+                .is_empty()
+        );
+
+        // Only the peer joining can become a holder
+        let newcomer = PeerId::random();
+        let mut new_group = old_group.clone();
+        new_group.push(newcomer);
+        let keys_per_peer =
+            keys_for_new_holders(our_peer_id, &old_group, &new_group, records.clone(), 100);
+        assert_eq!(keys_per_peer.keys().collect::<Vec<_>>(), vec![&newcomer]);
+
+        // The records of the peer leaving fall to the next closest ones
+        let keys_per_peer =
+            keys_for_new_holders(our_peer_id, &new_group, &old_group, records.clone(), 100);
+        assert!(!keys_per_peer.is_empty());
+        assert!(keys_per_peer.keys().all(|peer| old_group.contains(peer)));
+
+        let keys_per_peer = keys_for_new_holders(our_peer_id, &old_group, &new_group, records, 10);
+        assert!(keys_per_peer.values().all(|keys| keys.len() <= 10));
+    }
+
+    #[test]
+    fn churn_replications_are_spaced_by_the_min_interval() {
+        let min_interval = Duration::from_secs(10);
+        let mut scheduler = ReplicationScheduler::default();
+        let now = Instant::now();
+
+        assert_eq!(scheduler.schedule(min_interval, now), Some(Duration::ZERO));
+        assert_eq!(scheduler.schedule(min_interval, now), None);
+
+        let group = vec![PeerId::random()];
+        assert!(scheduler.start_run(group.clone(), now).is_empty());
+        assert_eq!(
+            scheduler.schedule(min_interval, now + Duration::from_secs(4)),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(scheduler.start_run(vec![], now), group);
+    }
+
+    #[test]
+    fn a_failed_churn_replication_leaves_room_for_the_next_one() {
+        let min_interval = Duration::from_secs(10);
+        let mut scheduler = ReplicationScheduler::default();
+        let now = Instant::now();
+
+        assert_eq!(scheduler.schedule(min_interval, now), Some(Duration::ZERO));
+        scheduler.abort_run();
+        // Not having run, the next one isn't delayed either.
+        assert_eq!(scheduler.schedule(min_interval, now), Some(Duration::ZERO));
+        assert_eq!(scheduler.schedule(min_interval, now), None);
+    }
+}
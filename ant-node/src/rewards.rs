@@ -0,0 +1,365 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{Error, Result};
+use ant_evm::{Amount, AttoTokens};
+use chrono::{DateTime, Utc};
+use libp2p::PeerId;
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::mpsc::{channel, Sender},
+    thread::JoinHandle,
+    time::{Duration, SystemTime},
+};
+use xor_name::XorName;
+
+/// The file in the node's root dir the received rewards are appended to.
+const REWARDS_LEDGER_FILENAME: &str = "rewards.csv";
+
+/// The max size of the ledger file. Once reached, it's moved aside, replacing the previous one, and a new file is
+/// started, for the ledger to take twice that at most.
+const MAX_REWARDS_LEDGER_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A payment the node received for storing a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardRecord {
+    /// When the payment was accepted
+    pub timestamp: SystemTime,
+    /// The amount paid to the node
+    pub amount: AttoTokens,
+    /// Hex of the key of the paid record
+    pub record: String,
+    /// Identifies the close group that was paid along with the node, see [`close_group_id`]
+    pub close_group: XorName,
+}
+
+impl RewardRecord {
+    fn to_line(&self) -> String {
+        let secs = self
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        format!(
+            "{secs},{},{},{}\n",
+            self.amount.as_atto(),
+            self.record,
+            hex::encode(self.close_group.0)
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.trim().split(',');
+        let secs: u64 = fields.next()?.parse().ok()?;
+        let amount = Amount::from_str(fields.next()?).ok()?;
+        let record = fields.next()?.to_string();
+        let close_group: [u8; 32] = hex::decode(fields.next()?).ok()?.try_into().ok()?;
+
+        Some(Self {
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            amount: AttoTokens::from_atto(amount),
+            record,
+            close_group: XorName(close_group),
+        })
+    }
+}
+
+/// Identify the close group of payees of a payment, regardless of their order.
+pub fn close_group_id(payees: &[PeerId]) -> XorName {
+    let mut payees = payees.to_vec();
+    payees.sort();
+    let bytes: Vec<u8> = payees.iter().flat_map(|peer| peer.to_bytes()).collect();
+    XorName::from_content(&bytes)
+}
+
+/// The file the previous ledger is moved to once the ledger is full.
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = OsString::from(path.as_os_str());
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// The append-only ledger of the rewards received by the node, capped to the latest ones.
+///
+/// The rewards are written on a thread of their own, for the PUTs paying them not to wait on the disk.
+#[derive(Debug)]
+pub(crate) struct RewardsLedger {
+    rewards: Option<Sender<RewardRecord>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl RewardsLedger {
+    pub(crate) fn new(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(REWARDS_LEDGER_FILENAME);
+        let (rewards, received) = channel::<RewardRecord>();
+        let writer = std::thread::Builder::new()
+            .name("rewards-ledger".to_string())
+            .spawn(move || {
+                let mut file = None;
+                for reward in received {
+                    if let Err(err) = append(&path, &mut file, &reward, MAX_REWARDS_LEDGER_BYTES) {
+                        warn!(
+                            "Failed to record the reward for {} in the ledger: {err}",
+                            reward.record
+                        );
+                        file = None;
+                    }
+                }
+            })
+            .map_err(|err| Error::RewardsLedger(err.to_string()))?;
+        Ok(Self {
+            rewards: Some(rewards),
+            writer: Some(writer),
+        })
+    }
+
+    /// Queue the reward to be appended to the ledger.
+    pub(crate) fn append(&self, reward: RewardRecord) -> Result<()> {
+        let Some(rewards) = &self.rewards else {
+            return Err(Error::RewardsLedger("The ledger is closed".to_string()));
+        };
+        rewards
+            .send(reward)
+            .map_err(|_| Error::RewardsLedger("The ledger writer is gone".to_string()))
+    }
+}
+
+impl Drop for RewardsLedger {
+    /// Write the queued rewards before closing the ledger.
+    fn drop(&mut self) {
+        drop(self.rewards.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Append the reward to the ledger file, opened if need be, rotating it once it would exceed `max_bytes`.
+fn append(
+    path: &Path,
+    file: &mut Option<(File, u64)>,
+    reward: &RewardRecord,
+    max_bytes: u64,
+) -> io::Result<()> {
+    let line = reward.to_line();
+    let (ledger, size) = match file {
+        Some((ledger, size)) if *size + line.len() as u64 <= max_bytes => (ledger, size),
+        _ => {
+            if let Some((_, size)) = file.take() {
+                if size > 0 {
+                    std::fs::rename(path, rotated_path(path))?;
+                }
+            }
+            let ledger = OpenOptions::new().create(true).append(true).open(path)?;
+            let size = ledger.metadata()?.len();
+            let (ledger, size) = file.insert((ledger, size));
+            (ledger, size)
+        }
+    };
+    ledger.write_all(line.as_bytes())?;
+    *size += line.len() as u64;
+    Ok(())
+}
+
+/// Read all the rewards the node at the root dir has received, oldest first, as kept by the capped ledger.
+pub fn read_rewards(root_dir: &Path) -> Result<Vec<RewardRecord>> {
+    let path = root_dir.join(REWARDS_LEDGER_FILENAME);
+    let mut records = vec![];
+    for path in [rotated_path(&path), path] {
+        if !path.exists() {
+            continue;
+        }
+        let contents =
+            std::fs::read_to_string(&path).map_err(|err| Error::RewardsLedger(err.to_string()))?;
+        records.extend(
+            contents
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| {
+                    let record = RewardRecord::from_line(line);
+                    if record.is_none() {
+                        warn!("Skipping invalid line in the rewards ledger: {line}");
+                    }
+                    record
+                }),
+        );
+    }
+    Ok(records)
+}
+
+/// The number of payments and their total amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RewardsTotal {
+    /// The number of payments
+    pub payments: usize,
+    /// Their total amount
+    pub amount: AttoTokens,
+}
+
+impl Default for RewardsTotal {
+    fn default() -> Self {
+        Self {
+            payments: 0,
+            amount: AttoTokens::zero(),
+        }
+    }
+}
+
+impl RewardsTotal {
+    fn add(&mut self, amount: AttoTokens) {
+        self.payments += 1;
+        self.amount = AttoTokens::from_atto(self.amount.as_atto().saturating_add(amount.as_atto()));
+    }
+}
+
+/// The rewards of a node, aggregated per day and per close group of payees.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewardsReport {
+    /// All the rewards
+    pub total: RewardsTotal,
+    /// Rewards per UTC day, formatted as `YYYY-MM-DD`
+    pub by_day: BTreeMap<String, RewardsTotal>,
+    /// Rewards per close group, see [`close_group_id`]
+    pub by_close_group: BTreeMap<XorName, RewardsTotal>,
+}
+
+impl RewardsReport {
+    /// Aggregate the rewards.
+    pub fn new(records: &[RewardRecord]) -> Self {
+        let mut report = Self::default();
+        for record in records {
+            let day = DateTime::<Utc>::from(record.timestamp)
+                .format("%Y-%m-%d")
+                .to_string();
+            report.total.add(record.amount);
+            report.by_day.entry(day).or_default().add(record.amount);
+            report
+                .by_close_group
+                .entry(record.close_group)
+                .or_default()
+                .add(record.amount);
+        }
+        report
+    }
+
+    /// Aggregate the rewards the node at the root dir has received.
+    pub fn from_root_dir(root_dir: &Path) -> Result<Self> {
+        Ok(Self::new(&read_rewards(root_dir)?))
+    }
+}
+
+impl fmt::Display for RewardsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Total: {} tokens in {} payments",
+            self.total.amount, self.total.payments
+        )?;
+        writeln!(f, "Per day:")?;
+        for (day, total) in &self.by_day {
+            writeln!(
+                f,
+                "  {day}: {} tokens in {} payments",
+                total.amount, total.payments
+            )?;
+        }
+        writeln!(f, "Per close group:")?;
+        for (close_group, total) in &self.by_close_group {
+            writeln!(
+                f,
+                "  {close_group:x}: {} tokens in {} payments",
+                total.amount, total.payments
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(secs: u64, amount: u64, close_group: XorName) -> RewardRecord {
+        RewardRecord {
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            amount: AttoTokens::from_u64(amount),
+            record: hex::encode(XorName::from_content(&secs.to_be_bytes()).0),
+            close_group,
+        }
+    }
+
+    #[test]
+    fn rewards_are_persisted_and_aggregated() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let ledger = RewardsLedger::new(dir.path())?;
+
+        let group_a = close_group_id(&[PeerId::random(), PeerId::random()]);
+        let group_b = close_group_id(&[PeerId::random()]);
+        const DAY: u64 = 24 * 60 * 60;
+        let records = [
+            record(DAY, 10, group_a),
+            record(DAY + 60, 5, group_b),
+            record(2 * DAY, 20, group_a),
+        ];
+        for record in &records {
+            ledger.append(record.clone())?;
+        }
+        // Closing the ledger writes the queued rewards.
+        drop(ledger);
+        assert_eq!(read_rewards(dir.path())?, records);
+
+        let report = RewardsReport::from_root_dir(dir.path())?;
+        assert_eq!(report.total.payments, 3);
+        assert_eq!(report.total.amount, AttoTokens::from_u64(35));
+        assert_eq!(
+            report.by_day["1970-01-02"],
+            RewardsTotal {
+                payments: 2,
+                amount: AttoTokens::from_u64(15),
+            }
+        );
+        assert_eq!(report.by_day["1970-01-03"].amount, AttoTokens::from_u64(20));
+        assert_eq!(
+            report.by_close_group[&group_a].amount,
+            AttoTokens::from_u64(30)
+        );
+        assert_eq!(report.by_close_group[&group_b].payments, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn the_ledger_is_rotated_once_full() -> eyre::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(REWARDS_LEDGER_FILENAME);
+        let group = close_group_id(&[PeerId::random()]);
+        let records: Vec<_> = (0..10).map(|secs| record(secs, 1, group)).collect();
+        let line_len = records[0].to_line().len() as u64;
+
+        let mut file = None;
+        for record in &records {
+            append(&path, &mut file, record, 3 * line_len)?;
+        }
+        // Only the current and the previous files are kept
+        assert_eq!(read_rewards(dir.path())?, records[6..]);
+        assert!(std::fs::metadata(&path)?.len() <= 3 * line_len);
+        Ok(())
+    }
+
+    #[test]
+    fn close_group_id_ignores_the_order_of_payees() {
+        let (a, b) = (PeerId::random(), PeerId::random());
+        assert_eq!(close_group_id(&[a, b]), close_group_id(&[b, a]));
+        assert_ne!(close_group_id(&[a, b]), close_group_id(&[a]));
+    }
+}
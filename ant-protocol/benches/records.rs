@@ -0,0 +1,84 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_protocol::{
+    storage::{
+        try_deserialize_record, try_serialize_record, Chunk, RecordHeader, RecordKind, Scratchpad,
+    },
+    NetworkAddress,
+};
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use libp2p::kad::Record;
+use rand::{thread_rng, Rng};
+
+/// The sizes of the chunks to serialize, in KiB, up to the max chunk size.
+const CHUNK_SIZES_KB: [usize; 3] = [1, 512, 4096];
+
+fn random_bytes(size: usize) -> Bytes {
+    let mut bytes = vec![0u8; size];
+    thread_rng().fill(&mut bytes[..]);
+    Bytes::from(bytes)
+}
+
+fn chunk_serialization(c: &mut Criterion) {
+    for size_kb in CHUNK_SIZES_KB {
+        let chunk = Chunk::new(random_bytes(size_kb * 1024));
+        let record = Record::new(
+            NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            try_serialize_record(&chunk, RecordKind::Chunk)
+                .expect("Failed to serialize")
+                .to_vec(),
+        );
+
+        let mut group = c.benchmark_group(format!("chunk_record_{size_kb}KB"));
+        group.throughput(Throughput::Bytes(record.value.len() as u64));
+
+        group.bench_function("serialize", |b| {
+            b.iter(|| try_serialize_record(&chunk, RecordKind::Chunk).expect("Failed to serialize"))
+        });
+        group.bench_function("deserialize", |b| {
+            b.iter(|| try_deserialize_record::<Chunk>(&record).expect("Failed to deserialize"))
+        });
+        group.bench_function("header", |b| {
+            b.iter(|| RecordHeader::from_record(&record).expect("Failed to read the header"))
+        });
+
+        group.finish();
+    }
+}
+
+fn scratchpad_serialization(c: &mut Criterion) {
+    let owner = bls::SecretKey::random();
+    let mut scratchpad = Scratchpad::new(owner.public_key(), 0);
+    let _ = scratchpad.update_and_sign(random_bytes(64 * 1024), &owner);
+    let record = Record::new(
+        NetworkAddress::from_scratchpad_address(*scratchpad.address()).to_record_key(),
+        try_serialize_record(&scratchpad, RecordKind::Scratchpad)
+            .expect("Failed to serialize")
+            .to_vec(),
+    );
+
+    let mut group = c.benchmark_group("scratchpad_record");
+    group.throughput(Throughput::Bytes(record.value.len() as u64));
+
+    group.bench_function("serialize", |b| {
+        b.iter(|| {
+            try_serialize_record(&scratchpad, RecordKind::Scratchpad).expect("Failed to serialize")
+        })
+    });
+    // Deserializing a scratchpad hashes its data to recalculate its address.
+    group.bench_function("deserialize", |b| {
+        b.iter(|| try_deserialize_record::<Scratchpad>(&record).expect("Failed to deserialize"))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, chunk_serialization, scratchpad_serialization);
+criterion_main!(benches);
@@ -8,5 +8,6 @@
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::compile_protos("./src/antnode_proto/antnode.proto")?;
+    tonic_build::compile_protos("./src/antclient_proto/antclient.proto")?;
     Ok(())
 }
@@ -9,6 +9,7 @@
 use crate::{storage::RegisterAddress, NetworkAddress, PrettyPrintRecordKey};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use xor_name::XorName;
 
 /// A specialised `Result` type for protocol crate.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -55,12 +56,21 @@ pub enum Error {
     #[error("Provided cypher text is invalid")]
     ScratchpadCipherTextInvalid,
 
+    // ---------- Pointer errors
+    /// The provided String can't be deserialized as a PointerAddress
+    #[error("Failed to deserialize hex PointerAddress")]
+    PointerHexDeserializeFailed,
+
     // ---------- payment errors
     #[error("There was an error getting the storecost from kademlia store")]
     GetStoreQuoteFailed,
     #[error("There was an error generating the payment quote")]
     QuoteGenerationFailed,
 
+    // ---------- rate limiting errors
+    #[error("The request was throttled, too many requests were sent recently")]
+    RequestThrottled,
+
     // ---------- replication errors
     /// Replication not found.
     #[error("Peer {holder:?} cannot find Record {key:?}")]
@@ -71,6 +81,35 @@ pub enum Error {
         key: Box<NetworkAddress>,
     },
 
+    // ---------- storage receipt errors
+    #[error("Peer {holder:?} does not hold Record {key:?} to issue a storage receipt for")]
+    RecordNotHeld {
+        /// Holder that being contacted
+        holder: Box<NetworkAddress>,
+        /// Key of the record
+        key: Box<NetworkAddress>,
+    },
+    #[error("Peer {holder:?} did not verify the payment {payment_hash:?} for Record {key:?}")]
+    PaymentNotVerified {
+        /// Holder that being contacted
+        holder: Box<NetworkAddress>,
+        /// Key of the record
+        key: Box<NetworkAddress>,
+        /// Hash of the proof of payment the receipt was asked for
+        payment_hash: XorName,
+    },
+    #[error("Failed to sign the storage receipt")]
+    StorageReceiptSigningFailed,
+
+    // ---------- payment rejection errors
+    #[error("Peer {holder:?} has no rejected payment for Record {key:?}")]
+    PaymentNotRejected {
+        /// Holder that being contacted
+        holder: Box<NetworkAddress>,
+        /// Key of the record
+        key: Box<NetworkAddress>,
+    },
+
     // ---------- record errors
     // Could not Serialize/Deserialize RecordHeader from Record
     #[error("Could not Serialize/Deserialize RecordHeader to/from Record")]
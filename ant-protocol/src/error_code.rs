@@ -0,0 +1,265 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Error;
+use ant_evm::{payment_vault, EvmError, EvmWalletError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The class of an error, as a stable code for the apps to tell the errors apart with, rather than by their messages.
+///
+/// The numeric codes are kept across releases and carried as is over the RPC and the C bindings: a new class of
+/// error gets a new code, an existing code is never reused for another class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[non_exhaustive]
+#[repr(u16)]
+pub enum ErrorCode {
+    /// A bug or a broken invariant, not expected to go away.
+    Internal = 1,
+    /// A local I/O failure, e.g. a full disk or missing permissions.
+    Io = 2,
+    /// The configuration or the arguments given are invalid.
+    InvalidInput = 3,
+    /// The operation was cancelled by the caller.
+    Cancelled = 4,
+
+    // ---------- Network, 1xx
+    /// The network didn't answer in time.
+    Timeout = 100,
+    /// Not enough peers could be reached for the operation.
+    NotEnoughPeers = 101,
+    /// The peers could not be connected to or the connection was lost.
+    ConnectionFailed = 102,
+    /// The local node or the peers are too busy, the request was refused or throttled.
+    Overloaded = 103,
+    /// The peers run a version of the protocol incompatible with ours.
+    IncompatibleProtocol = 104,
+
+    // ---------- Records, 2xx
+    /// The record isn't found in the network.
+    RecordNotFound = 200,
+    /// Not enough peers confirmed storing the record.
+    RecordNotStored = 201,
+    /// The peers hold diverging copies of the record.
+    SplitRecord = 202,
+    /// The record is malformed or not of the kind expected.
+    InvalidRecord = 203,
+    /// The record already exists and cannot be replaced.
+    RecordExists = 204,
+
+    // ---------- Payments, 3xx
+    /// The wallet doesn't hold enough tokens for the payment.
+    InsufficientFunds = 300,
+    /// No usable quote could be gathered for the payment.
+    QuoteUnavailable = 301,
+    /// The payees rejected the payment.
+    PaymentRejected = 302,
+    /// The payment could not be made on the EVM network.
+    PaymentFailed = 303,
+    /// The wallet or its key is invalid.
+    InvalidWallet = 304,
+
+    // ---------- Data, 4xx
+    /// The data could not be encrypted, decrypted or decompressed.
+    Encryption = 400,
+    /// The data could not be serialized or deserialized.
+    Serialization = 401,
+}
+
+impl ErrorCode {
+    /// Every code, for the foreign bindings to be generated from.
+    pub const ALL: [Self; 21] = [
+        Self::Internal,
+        Self::Io,
+        Self::InvalidInput,
+        Self::Cancelled,
+        Self::Timeout,
+        Self::NotEnoughPeers,
+        Self::ConnectionFailed,
+        Self::Overloaded,
+        Self::IncompatibleProtocol,
+        Self::RecordNotFound,
+        Self::RecordNotStored,
+        Self::SplitRecord,
+        Self::InvalidRecord,
+        Self::RecordExists,
+        Self::InsufficientFunds,
+        Self::QuoteUnavailable,
+        Self::PaymentRejected,
+        Self::PaymentFailed,
+        Self::InvalidWallet,
+        Self::Encryption,
+        Self::Serialization,
+    ];
+
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+
+    /// The code of the number, `None` for a code unknown to this version.
+    pub fn from_u16(code: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|known| known.as_u16() == code)
+    }
+
+    /// The name of the code, e.g. `RECORD_NOT_FOUND`, as stable as its number.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Internal => "INTERNAL",
+            Self::Io => "IO",
+            Self::InvalidInput => "INVALID_INPUT",
+            Self::Cancelled => "CANCELLED",
+            Self::Timeout => "TIMEOUT",
+            Self::NotEnoughPeers => "NOT_ENOUGH_PEERS",
+            Self::ConnectionFailed => "CONNECTION_FAILED",
+            Self::Overloaded => "OVERLOADED",
+            Self::IncompatibleProtocol => "INCOMPATIBLE_PROTOCOL",
+            Self::RecordNotFound => "RECORD_NOT_FOUND",
+            Self::RecordNotStored => "RECORD_NOT_STORED",
+            Self::SplitRecord => "SPLIT_RECORD",
+            Self::InvalidRecord => "INVALID_RECORD",
+            Self::RecordExists => "RECORD_EXISTS",
+            Self::InsufficientFunds => "INSUFFICIENT_FUNDS",
+            Self::QuoteUnavailable => "QUOTE_UNAVAILABLE",
+            Self::PaymentRejected => "PAYMENT_REJECTED",
+            Self::PaymentFailed => "PAYMENT_FAILED",
+            Self::InvalidWallet => "INVALID_WALLET",
+            Self::Encryption => "ENCRYPTION",
+            Self::Serialization => "SERIALIZATION",
+        }
+    }
+
+    /// Whether the same operation may succeed when retried later, the failure coming from the state of the network
+    /// rather than from the operation itself.
+    ///
+    /// A record not found isn't retryable: it may show up once replicated, but retrying won't make a missing record
+    /// appear, the app knows best how long to wait for one.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::Timeout
+                | Self::NotEnoughPeers
+                | Self::ConnectionFailed
+                | Self::Overloaded
+                | Self::RecordNotStored
+                | Self::QuoteUnavailable
+                | Self::PaymentFailed
+        )
+    }
+}
+
+// The payment errors are classified here, below which the codes aren't known.
+impl ErrorCode {
+    /// The code of an error of the payment amounts and quotes.
+    pub fn of_evm_error(err: &EvmError) -> Self {
+        match err {
+            EvmError::LossOfPrecision
+            | EvmError::ExcessiveValue
+            | EvmError::FailedToParseAttoToken(_)
+            | EvmError::NumericOverflow => Self::InvalidInput,
+            EvmError::NotEnoughBalance(..) => Self::InsufficientFunds,
+            // Another quote may be valid
+            EvmError::InvalidQuotePublicKey => Self::QuoteUnavailable,
+            EvmError::ProofSerialization(_) => Self::Serialization,
+            _ => Self::Internal,
+        }
+    }
+
+    /// The code of an error of the wallet paying for the storage.
+    pub fn of_evm_wallet_error(err: &EvmWalletError) -> Self {
+        match err {
+            EvmWalletError::InsufficientTokensForQuotes(..) => Self::InsufficientFunds,
+            EvmWalletError::PrivateKeyInvalid | EvmWalletError::Mnemonic(_) => Self::InvalidWallet,
+            EvmWalletError::RpcError(_) | EvmWalletError::NetworkTokenContract(_) => {
+                Self::PaymentFailed
+            }
+            EvmWalletError::ChunkPaymentsContract(err) => Self::of_payment_vault_error(err),
+        }
+    }
+
+    /// The code of an error of the payment vault contract.
+    pub fn of_payment_vault_error(err: &payment_vault::error::Error) -> Self {
+        match err {
+            payment_vault::error::Error::ContractError(_)
+            | payment_vault::error::Error::RpcError(_)
+            | payment_vault::error::Error::PendingTransactionError(_) => Self::PaymentFailed,
+            payment_vault::error::Error::PaymentInvalid => Self::PaymentRejected,
+            payment_vault::error::Error::PaymentVerificationLengthInvalid => Self::InvalidInput,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name(), self.as_u16())
+    }
+}
+
+impl Error {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::UserDataDirectoryNotObtainable | Error::CouldNotObtainDataDir => ErrorCode::Io,
+            Error::CouldNotObtainPortFromMultiAddr | Error::ParseRetryStrategyError => {
+                ErrorCode::InvalidInput
+            }
+            Error::ChunkDoesNotExist(_)
+            | Error::RegisterNotFound(_)
+            | Error::RegisterRecordNotFound { .. }
+            | Error::ReplicatedRecordNotFound { .. }
+            | Error::RecordNotHeld { .. } => ErrorCode::RecordNotFound,
+            Error::RegisterAlreadyClaimed(_) | Error::RecordExists(_) => ErrorCode::RecordExists,
+            Error::ScratchpadHexDeserializeFailed | Error::PointerHexDeserializeFailed => {
+                ErrorCode::InvalidInput
+            }
+            Error::ScratchpadCipherTextFailed | Error::ScratchpadCipherTextInvalid => {
+                ErrorCode::Encryption
+            }
+            Error::GetStoreQuoteFailed | Error::QuoteGenerationFailed => {
+                ErrorCode::QuoteUnavailable
+            }
+            Error::RequestThrottled => ErrorCode::Overloaded,
+            Error::StorageReceiptSigningFailed => ErrorCode::Internal,
+            Error::PaymentNotRejected { .. } => ErrorCode::InvalidInput,
+            Error::PaymentNotVerified { .. } => ErrorCode::PaymentRejected,
+            Error::RecordHeaderParsingFailed | Error::RecordParsingFailed => {
+                ErrorCode::InvalidRecord
+            }
+        }
+    }
+
+    /// Whether the operation may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_stable_and_unique() {
+        let mut numbers: Vec<_> = ErrorCode::ALL.iter().map(|code| code.as_u16()).collect();
+        numbers.sort();
+        numbers.dedup();
+        assert_eq!(numbers.len(), ErrorCode::ALL.len());
+
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_u16(code.as_u16()), Some(code));
+        }
+        assert_eq!(ErrorCode::from_u16(0), None);
+        assert_eq!(ErrorCode::RecordNotFound.as_u16(), 200);
+        assert_eq!(
+            ErrorCode::RecordNotFound.to_string(),
+            "RECORD_NOT_FOUND (200)"
+        );
+
+        assert!(Error::RequestThrottled.is_retryable());
+        assert!(!Error::RecordParsingFailed.is_retryable());
+    }
+}
@@ -11,6 +11,8 @@ extern crate tracing;
 
 /// Errors.
 pub mod error;
+/// Stable codes classifying the errors.
+pub mod error_code;
 /// Messages types
 pub mod messages;
 /// Helpers for antnode
@@ -28,8 +30,14 @@ pub mod version;
 pub mod antnode_proto {
     tonic::include_proto!("antnode_proto");
 }
+#[expect(clippy::unwrap_used, clippy::clone_on_ref_ptr)]
+#[cfg(feature = "rpc")]
+pub mod antclient_proto {
+    tonic::include_proto!("antclient_proto");
+}
 pub use error::Error;
-use storage::ScratchpadAddress;
+pub use error_code::ErrorCode;
+use storage::{PointerAddress, ScratchpadAddress};
 
 use self::storage::{ChunkAddress, RegisterAddress, TransactionAddress};
 
@@ -102,6 +110,8 @@ pub enum NetworkAddress {
     RecordKey(Bytes),
     /// The NetworkAddress is representing a ScratchpadAddress.
     ScratchpadAddress(ScratchpadAddress),
+    /// The NetworkAddress is representing a PointerAddress.
+    PointerAddress(PointerAddress),
 }
 
 impl NetworkAddress {
@@ -119,6 +129,11 @@ impl NetworkAddress {
         NetworkAddress::ScratchpadAddress(address)
     }
 
+    /// Return a `NetworkAddress` representation of the `PointerAddress`.
+    pub fn from_pointer_address(address: PointerAddress) -> Self {
+        NetworkAddress::PointerAddress(address)
+    }
+
     /// Return a `NetworkAddress` representation of the `RegisterAddress`.
     pub fn from_register_address(register_address: RegisterAddress) -> Self {
         NetworkAddress::RegisterAddress(register_address)
@@ -143,6 +158,7 @@ impl NetworkAddress {
                 transaction_address.xorname().0.to_vec()
             }
             NetworkAddress::ScratchpadAddress(addr) => addr.xorname().0.to_vec(),
+            NetworkAddress::PointerAddress(addr) => addr.xorname().0.to_vec(),
             NetworkAddress::RegisterAddress(register_address) => {
                 register_address.xorname().0.to_vec()
             }
@@ -169,6 +185,7 @@ impl NetworkAddress {
             NetworkAddress::ChunkAddress(chunk_address) => Some(*chunk_address.xorname()),
             NetworkAddress::RegisterAddress(register_address) => Some(register_address.xorname()),
             NetworkAddress::ScratchpadAddress(address) => Some(address.xorname()),
+            NetworkAddress::PointerAddress(address) => Some(address.xorname()),
             _ => None,
         }
     }
@@ -193,6 +210,7 @@ impl NetworkAddress {
                 RecordKey::new(transaction_address.xorname())
             }
             NetworkAddress::ScratchpadAddress(addr) => RecordKey::new(&addr.xorname()),
+            NetworkAddress::PointerAddress(addr) => RecordKey::new(&addr.xorname()),
             NetworkAddress::PeerId(bytes) => RecordKey::new(bytes),
         }
     }
@@ -251,6 +269,12 @@ impl Debug for NetworkAddress {
                     &scratchpad_address.to_hex()[0..6]
                 )
             }
+            NetworkAddress::PointerAddress(pointer_address) => {
+                format!(
+                    "NetworkAddress::PointerAddress({} - ",
+                    &pointer_address.to_hex()[0..6]
+                )
+            }
             NetworkAddress::RegisterAddress(register_address) => format!(
                 "NetworkAddress::RegisterAddress({} - ",
                 &register_address.to_hex()[0..6]
@@ -283,6 +307,9 @@ impl Display for NetworkAddress {
             NetworkAddress::ScratchpadAddress(addr) => {
                 write!(f, "NetworkAddress::ScratchpadAddress({addr:?})")
             }
+            NetworkAddress::PointerAddress(addr) => {
+                write!(f, "NetworkAddress::PointerAddress({addr:?})")
+            }
             NetworkAddress::RegisterAddress(addr) => {
                 write!(f, "NetworkAddress::RegisterAddress({addr:?})")
             }
@@ -10,7 +10,9 @@
 mod chunk_proof;
 mod cmd;
 mod node_id;
+mod payment_rejection;
 mod query;
+mod receipt;
 mod register;
 mod response;
 
@@ -18,8 +20,10 @@ pub use self::{
     chunk_proof::{ChunkProof, Nonce},
     cmd::Cmd,
     node_id::NodeId,
+    payment_rejection::{PaymentRejection, PaymentRejectionReason},
     query::Query,
-    register::RegisterCmd,
+    receipt::StorageReceipt,
+    register::{register_topic, RegisterCmd, RegisterNotification},
     response::{CmdResponse, QueryResponse},
 };
 
@@ -0,0 +1,46 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::NetworkAddress;
+use ant_evm::{QuoteHash, RewardsAddress};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use xor_name::XorName;
+
+/// Why a node rejected the payment a record was put to it with.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum PaymentRejectionReason {
+    /// The node isn't one of the payees, or a quote isn't signed by the payee it's for.
+    NotForUs,
+    /// A quote of the payment expired.
+    QuoteExpired,
+    /// The payees the node doesn't know to be close to it.
+    PayeesOutOfRange(Vec<NetworkAddress>),
+    /// The payment of the quotes couldn't be verified on chain.
+    NotPaidOnChain(String),
+    /// A quote of the node is for another content, without being scoped to the node for any of its records.
+    QuoteNotForContent,
+}
+
+/// A node's view of a payment it rejected, for the client to find out what went wrong and whether to pay again.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct PaymentRejection {
+    /// The node that rejected the payment
+    pub node: NetworkAddress,
+    /// Address of the record put
+    pub address: NetworkAddress,
+    /// Hash of the proof of payment the record came with, see `ProofOfPayment::hash`
+    pub payment_hash: XorName,
+    pub reason: PaymentRejectionReason,
+    /// Hashes of the quotes of the node found in the proof, the ones expected to be paid on chain
+    pub quote_hashes: Vec<QuoteHash>,
+    /// The address the node expects to be paid to
+    pub rewards_address: RewardsAddress,
+    /// When the payment was rejected
+    pub timestamp: SystemTime,
+}
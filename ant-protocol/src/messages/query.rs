@@ -9,6 +9,7 @@
 use crate::{messages::Nonce, NetworkAddress};
 use ant_evm::U256;
 use serde::{Deserialize, Serialize};
+use xor_name::XorName;
 
 /// Data queries - retrieving data and inspecting their structure.
 ///
@@ -64,6 +65,28 @@ pub enum Query {
         /// Node shall try their best to fulfill the number, based on their capacity.
         difficulty: usize,
     },
+    /// Get a signed receipt from the node that it holds the record, paid for with the payment of the given hash.
+    ///
+    /// This should eventually lead to a [`GetStorageReceipt`] response.
+    ///
+    /// [`GetStorageReceipt`]: super::QueryResponse::GetStorageReceipt
+    GetStorageReceipt {
+        /// Key of the stored record
+        key: NetworkAddress,
+        /// Hash of the proof of payment for the record
+        payment_hash: XorName,
+    },
+    /// Get the node's view of the payment of the given hash, if it rejected the record put with it.
+    ///
+    /// This should eventually lead to a [`GetPaymentRejection`] response.
+    ///
+    /// [`GetPaymentRejection`]: super::QueryResponse::GetPaymentRejection
+    GetPaymentRejection {
+        /// Key of the record put
+        key: NetworkAddress,
+        /// Hash of the proof of payment the record was put with
+        payment_hash: XorName,
+    },
     /// Queries close_group peers whether the target peer is a bad_node
     CheckNodeInProblem(NetworkAddress),
     /// Query the the peers in range to the target address, from the receiver's perspective.
@@ -91,6 +114,8 @@ impl Query {
             | Query::GetReplicatedRecord { key, .. }
             | Query::GetRegisterRecord { key, .. }
             | Query::GetChunkExistenceProof { key, .. }
+            | Query::GetStorageReceipt { key, .. }
+            | Query::GetPaymentRejection { key, .. }
             | Query::GetClosestPeers { key, .. } => key.clone(),
         }
     }
@@ -122,6 +147,12 @@ impl std::fmt::Display for Query {
                     "Query::GetChunkExistenceProof({key:?} {nonce:?} {difficulty})"
                 )
             }
+            Query::GetStorageReceipt { key, payment_hash } => {
+                write!(f, "Query::GetStorageReceipt({key:?} {payment_hash:?})")
+            }
+            Query::GetPaymentRejection { key, payment_hash } => {
+                write!(f, "Query::GetPaymentRejection({key:?} {payment_hash:?})")
+            }
             Query::CheckNodeInProblem(address) => {
                 write!(f, "Query::CheckNodeInProblem({address:?})")
             }
@@ -0,0 +1,121 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::NetworkAddress;
+use libp2p::identity::PublicKey;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use xor_name::XorName;
+
+/// A node's signed statement that it holds a record, paid for with the payment of the given hash.
+///
+/// Clients keep these to later demonstrate that the close group accepted their data.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct StorageReceipt {
+    /// Address of the stored record
+    pub address: NetworkAddress,
+    /// Hash of the proof of payment for the record, see `ProofOfPayment::hash`
+    pub payment_hash: XorName,
+    /// Protobuf encoded public key of the node
+    pub node_key: Vec<u8>,
+    /// When the receipt was issued
+    pub timestamp: SystemTime,
+    /// Signature of the node over all of the above
+    pub signature: Vec<u8>,
+}
+
+impl StorageReceipt {
+    /// Bytes the node signs to issue the receipt.
+    pub fn bytes_for_signing(
+        address: &NetworkAddress,
+        payment_hash: &XorName,
+        node_key: &[u8],
+        timestamp: SystemTime,
+    ) -> Vec<u8> {
+        let mut bytes = address.as_bytes();
+        bytes.extend_from_slice(&payment_hash.0);
+        bytes.extend_from_slice(node_key);
+        let secs = timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        bytes.extend_from_slice(&secs.to_be_bytes());
+        bytes
+    }
+
+    /// The node that issued the receipt.
+    pub fn node(&self) -> Option<PeerId> {
+        PublicKey::try_decode_protobuf(&self.node_key)
+            .ok()
+            .map(PeerId::from)
+    }
+
+    /// Check that the receipt is signed by the node it claims to be from.
+    pub fn verify(&self) -> bool {
+        let Ok(node_key) = PublicKey::try_decode_protobuf(&self.node_key) else {
+            warn!(
+                "Storage receipt for {:?} has an invalid node key",
+                self.address
+            );
+            return false;
+        };
+        let bytes = Self::bytes_for_signing(
+            &self.address,
+            &self.payment_hash,
+            &self.node_key,
+            self.timestamp,
+        );
+        node_key.verify(&bytes, &self.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    fn signed_receipt(keypair: &Keypair) -> StorageReceipt {
+        let address = NetworkAddress::from_peer(PeerId::random());
+        let payment_hash = XorName::from_content(b"payment");
+        let node_key = keypair.public().encode_protobuf();
+        let timestamp = SystemTime::now();
+        let bytes =
+            StorageReceipt::bytes_for_signing(&address, &payment_hash, &node_key, timestamp);
+
+        StorageReceipt {
+            address,
+            payment_hash,
+            node_key,
+            timestamp,
+            signature: keypair.sign(&bytes).expect("Failed to sign"),
+        }
+    }
+
+    #[test]
+    fn storage_receipt_verifies_against_its_node() {
+        let keypair = Keypair::generate_ed25519();
+        let receipt = signed_receipt(&keypair);
+
+        assert!(receipt.verify());
+        assert_eq!(receipt.node(), Some(keypair.public().to_peer_id()));
+    }
+
+    #[test]
+    fn tampered_storage_receipts_fail_to_verify() {
+        let keypair = Keypair::generate_ed25519();
+
+        let mut receipt = signed_receipt(&keypair);
+        receipt.payment_hash = XorName::from_content(b"another payment");
+        assert!(!receipt.verify());
+
+        let mut receipt = signed_receipt(&keypair);
+        receipt.node_key = Keypair::generate_ed25519().public().encode_protobuf();
+        assert!(!receipt.verify());
+    }
+}
@@ -9,6 +9,7 @@
 use ant_registers::{Register, RegisterAddress, RegisterOp};
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// A register cmd that is sent over to the Network
 #[derive(Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -37,6 +38,19 @@ impl std::fmt::Debug for RegisterCmd {
     }
 }
 
+/// Notification of the ops a node holding a register accepted to it, published on the register's
+/// [`register_topic`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterNotification {
+    /// The ops that were new to the register held by the node.
+    pub ops: BTreeSet<RegisterOp>,
+}
+
+/// The gossipsub topic on which the nodes holding a register publish a [`RegisterNotification`] on accepted writes.
+pub fn register_topic(address: &RegisterAddress) -> String {
+    format!("register/{}", address.to_hex())
+}
+
 impl RegisterCmd {
     /// Returns the dst address of the register.
     pub fn dst(&self) -> RegisterAddress {
@@ -8,7 +8,7 @@
 
 use crate::{error::Result, NetworkAddress};
 
-use super::ChunkProof;
+use super::{ChunkProof, PaymentRejection, StorageReceipt};
 use ant_evm::PaymentQuote;
 use bytes::Bytes;
 use core::fmt;
@@ -58,6 +58,18 @@ pub enum QueryResponse {
     ///
     /// [`GetChunkExistenceProof`]: crate::messages::Query::GetChunkExistenceProof
     GetChunkExistenceProof(Vec<(NetworkAddress, Result<ChunkProof>)>),
+    // ===== GetStorageReceipt =====
+    //
+    /// Response to [`GetStorageReceipt`]
+    ///
+    /// [`GetStorageReceipt`]: crate::messages::Query::GetStorageReceipt
+    GetStorageReceipt(Result<StorageReceipt>),
+    // ===== GetPaymentRejection =====
+    //
+    /// Response to [`GetPaymentRejection`]
+    ///
+    /// [`GetPaymentRejection`]: crate::messages::Query::GetPaymentRejection
+    GetPaymentRejection(Result<PaymentRejection>),
     // ===== GetClosestPeers =====
     //
     /// Response to [`GetClosestPeers`]
@@ -130,6 +142,12 @@ impl Debug for QueryResponse {
                 let addresses: Vec<_> = proofs.iter().map(|(addr, _)| addr.clone()).collect();
                 write!(f, "GetChunkExistenceProof(checked chunks: {addresses:?})")
             }
+            QueryResponse::GetStorageReceipt(result) => {
+                write!(f, "GetStorageReceipt({result:?})")
+            }
+            QueryResponse::GetPaymentRejection(result) => {
+                write!(f, "GetPaymentRejection({result:?})")
+            }
             QueryResponse::GetClosestPeers { target, peers, .. } => {
                 let addresses: Vec<_> = peers.iter().map(|(addr, _)| addr.clone()).collect();
                 write!(
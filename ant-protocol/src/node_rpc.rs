@@ -9,6 +9,58 @@
 use color_eyre::eyre::Error;
 use std::time::Duration;
 
+/// Metadata key of the token authenticating the callers of the mutating node RPCs.
+pub const RPC_AUTH_METADATA_KEY: &str = "authorization";
+
+/// Metadata key of the [`crate::ErrorCode`] of a failed node RPC, as its number.
+pub const RPC_ERROR_CODE_METADATA_KEY: &str = "x-autonomi-error-code";
+
+/// Metadata value carrying the token to the mutating node RPCs.
+pub fn rpc_auth_metadata_value(token: &str) -> String {
+    format!("Bearer {token}")
+}
+
+/// Whether the metadata value presented by a caller carries the token, compared without leaking the length of their
+/// common prefix through timing.
+pub fn rpc_auth_matches(presented: &str, token: &str) -> bool {
+    let expected = rpc_auth_metadata_value(token);
+    let (presented, expected) = (presented.as_bytes(), expected.as_bytes());
+    presented.len() == expected.len()
+        && presented
+            .iter()
+            .zip(expected)
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// The status of a failed node RPC, carrying the code of the error it failed with.
+#[cfg(feature = "rpc")]
+pub fn rpc_error_status(
+    code: tonic::Code,
+    message: impl Into<String>,
+    error_code: crate::ErrorCode,
+) -> tonic::Status {
+    let mut status = tonic::Status::new(code, message);
+    let _ = status.metadata_mut().insert(
+        RPC_ERROR_CODE_METADATA_KEY,
+        tonic::metadata::MetadataValue::from(error_code.as_u16()),
+    );
+    status
+}
+
+/// The code of the error a node RPC failed with, if the node told it.
+#[cfg(feature = "rpc")]
+pub fn rpc_error_code(status: &tonic::Status) -> Option<crate::ErrorCode> {
+    status
+        .metadata()
+        .get(RPC_ERROR_CODE_METADATA_KEY)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+        .and_then(crate::ErrorCode::from_u16)
+}
+
 #[derive(Debug)]
 /// To be sent to the main thread in order to stop/restart the execution of the antnode app.
 pub enum NodeCtrl {
@@ -25,6 +77,12 @@ pub enum NodeCtrl {
     },
     // Request to update the antnode app, and restart it, after the requested delay.
     Update(Duration),
+    /// Request to stop accepting records, hand off the stored ones to their close groups, and then stop
+    /// the execution of the antnode app. The antnode app stops once the `timeout` elapses, even if not all of the
+    /// records were handed off.
+    Decommission {
+        timeout: Duration,
+    },
 }
 
 #[derive(Debug)]
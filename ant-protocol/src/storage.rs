@@ -8,7 +8,9 @@
 
 mod address;
 mod chunks;
+mod fetch_policy;
 mod header;
+mod pointer;
 mod scratchpad;
 mod transaction;
 
@@ -17,10 +19,14 @@ use exponential_backoff::Backoff;
 use std::{num::NonZeroUsize, time::Duration};
 
 pub use self::{
-    address::{ChunkAddress, RegisterAddress, ScratchpadAddress, TransactionAddress},
+    address::{
+        ChunkAddress, PointerAddress, RegisterAddress, ScratchpadAddress, TransactionAddress,
+    },
     chunks::Chunk,
+    fetch_policy::{is_early_completion_eligible, EarlyCompletion, EARLY_COMPLETION_POLICY},
     header::{try_deserialize_record, try_serialize_record, RecordHeader, RecordKind, RecordType},
-    scratchpad::Scratchpad,
+    pointer::{Pointer, PointerTarget},
+    scratchpad::{Scratchpad, MAX_SCRATCHPAD_SIZE},
     transaction::Transaction,
 };
 
@@ -7,10 +7,12 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod chunk;
+mod pointer;
 mod scratchpad;
 mod transaction;
 
 pub use self::chunk::ChunkAddress;
+pub use self::pointer::PointerAddress;
 pub use self::scratchpad::ScratchpadAddress;
 pub use self::transaction::TransactionAddress;
 pub use ant_registers::RegisterAddress;
@@ -0,0 +1,124 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{Error, Result};
+use bls::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+use xor_name::XorName;
+
+/// Length of the hex encoding of a `PointerAddress`: a BLS public key followed by the name.
+const POINTER_ADDRESS_HEX_LEN: usize = 2 * (bls::PK_SIZE + xor_name::XOR_NAME_LEN);
+
+/// Address of a Pointer on the SAFE Network, derived from its owner and name.
+/// An owner can hold any number of pointers, one per name.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct PointerAddress {
+    /// Owner of the pointer
+    pub(crate) owner: PublicKey,
+    /// Name of the pointer, unique per owner
+    pub(crate) name: XorName,
+}
+
+impl Display for PointerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({:?})", &self.to_hex()[0..6])
+    }
+}
+
+impl Debug for PointerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PointerAddress({}) {{ owner: {:?}, name: {:?} }}",
+            &self.to_hex()[0..6],
+            self.owner,
+            self.name
+        )
+    }
+}
+
+impl PointerAddress {
+    /// Construct a new `PointerAddress` given `owner` and `name`.
+    pub fn new(owner: PublicKey, name: XorName) -> Self {
+        Self { owner, name }
+    }
+
+    /// Construct a new `PointerAddress` for a human-readable name, e.g. "my-website".
+    pub fn from_name(owner: PublicKey, name: &str) -> Self {
+        Self::new(owner, XorName::from_content(name.as_bytes()))
+    }
+
+    /// Return the network name of the pointer.
+    /// This is used to locate the pointer on the network.
+    pub fn xorname(&self) -> XorName {
+        XorName::from_content_parts(&[&self.owner.to_bytes(), &self.name.0])
+    }
+
+    /// Serialize this `PointerAddress` instance to a hex-encoded `String`.
+    pub fn to_hex(&self) -> String {
+        let mut bytes = self.owner.to_bytes().to_vec();
+        bytes.extend_from_slice(&self.name.0);
+        hex::encode(bytes)
+    }
+
+    /// Deserialize a hex-encoded representation of a `PointerAddress` to a `PointerAddress` instance.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        if hex.len() != POINTER_ADDRESS_HEX_LEN {
+            return Err(Error::PointerHexDeserializeFailed);
+        }
+        let (owner_hex, name_hex) = hex.split_at(2 * bls::PK_SIZE);
+        let owner =
+            PublicKey::from_hex(owner_hex).map_err(|_| Error::PointerHexDeserializeFailed)?;
+        let name = hex::decode(name_hex)
+            .ok()
+            .and_then(|bytes| <[u8; xor_name::XOR_NAME_LEN]>::try_from(bytes).ok())
+            .ok_or(Error::PointerHexDeserializeFailed)?;
+        Ok(Self {
+            owner,
+            name: XorName(name),
+        })
+    }
+
+    /// Return the owner.
+    pub fn owner(&self) -> &PublicKey {
+        &self.owner
+    }
+
+    /// Return the name.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+
+    #[test]
+    fn test_pointer_hex_conversion() {
+        let owner = SecretKey::random().public_key();
+        let addr = PointerAddress::from_name(owner, "my-website");
+        let hex = addr.to_hex();
+        let addr2 = PointerAddress::from_hex(&hex).unwrap();
+
+        assert_eq!(addr, addr2);
+        assert_ne!(
+            addr.xorname(),
+            PointerAddress::from_name(owner, "my-blog").xorname()
+        );
+
+        let bad_hex = format!("{hex}0");
+        let err = PointerAddress::from_hex(&bad_hex);
+        assert_eq!(err, Err(Error::PointerHexDeserializeFailed));
+    }
+}
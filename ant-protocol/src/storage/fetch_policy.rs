@@ -0,0 +1,166 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{try_deserialize_record, Chunk, RecordHeader, RecordKind};
+use libp2p::kad::Record;
+
+/// Whether a GetRecord query for a kind of record is allowed to complete on the first copy
+/// received, before the requested quorum has been reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EarlyCompletion {
+    /// Always wait for the requested quorum.
+    Disabled,
+    /// A single copy is enough, provided the copy can be verified against its own record key.
+    SelfVerified,
+}
+
+/// The early completion policy for each `RecordKind` that can be returned by a GetRecord query.
+/// Any kind not listed here falls back to `EarlyCompletion::Disabled`.
+///
+/// Only chunks are immutable. Scratchpads and pointers are excluded as a valid signature only proves
+/// ownership, while a stale copy with a lower counter would still pass the check. Registers and
+/// transactions are CRDTs merged from the copies of the close group, a single copy may be missing
+/// the latest ops.
+pub const EARLY_COMPLETION_POLICY: [(RecordKind, EarlyCompletion); 5] = [
+    (RecordKind::Chunk, EarlyCompletion::SelfVerified),
+    (RecordKind::Transaction, EarlyCompletion::Disabled),
+    (RecordKind::Register, EarlyCompletion::Disabled),
+    (RecordKind::Scratchpad, EarlyCompletion::Disabled),
+    (RecordKind::Pointer, EarlyCompletion::Disabled),
+];
+
+impl RecordKind {
+    /// Look up the early completion policy of this kind in `EARLY_COMPLETION_POLICY`.
+    pub fn early_completion(&self) -> EarlyCompletion {
+        EARLY_COMPLETION_POLICY
+            .iter()
+            .find(|(kind, _)| kind == self)
+            .map(|(_, policy)| *policy)
+            .unwrap_or(EarlyCompletion::Disabled)
+    }
+}
+
+/// Returns true if the policy table allows the record to complete a GetRecord query on its own,
+/// and the record content verifies against the record key, i.e. a chunk's content hashes to the key.
+pub fn is_early_completion_eligible(record: &Record) -> bool {
+    let kind = match RecordHeader::from_record(record) {
+        Ok(header) => header.kind,
+        Err(_) => return false,
+    };
+
+    if kind.early_completion() != EarlyCompletion::SelfVerified {
+        return false;
+    }
+
+    match kind {
+        RecordKind::Chunk => try_deserialize_record::<Chunk>(record)
+            .map(|chunk| chunk.network_address().to_record_key() == record.key)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{try_serialize_record, Scratchpad, Transaction};
+    use crate::NetworkAddress;
+    use ant_registers::{Permissions, Register, SignedRegister};
+    use bls::{rand::thread_rng, SecretKey};
+    use bytes::Bytes;
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    fn to_record<T: serde::Serialize>(
+        key: &NetworkAddress,
+        data: &T,
+        kind: RecordKind,
+    ) -> crate::error::Result<Record> {
+        Ok(Record {
+            key: key.to_record_key(),
+            value: try_serialize_record(data, kind)?.to_vec(),
+            publisher: None,
+            expires: None,
+        })
+    }
+
+    #[test]
+    fn policy_table_lookup() {
+        assert_eq!(
+            RecordKind::Chunk.early_completion(),
+            EarlyCompletion::SelfVerified
+        );
+        assert_eq!(
+            RecordKind::Scratchpad.early_completion(),
+            EarlyCompletion::Disabled
+        );
+        assert_eq!(
+            RecordKind::Pointer.early_completion(),
+            EarlyCompletion::Disabled
+        );
+        assert_eq!(
+            RecordKind::ChunkWithPayment.early_completion(),
+            EarlyCompletion::Disabled
+        );
+    }
+
+    #[test]
+    fn chunk_must_match_its_key() -> crate::error::Result<()> {
+        let chunk = Chunk::new(Bytes::from_static(b"early completion"));
+        let record = to_record(&chunk.network_address(), &chunk, RecordKind::Chunk)?;
+        assert!(is_early_completion_eligible(&record));
+
+        let other_key = NetworkAddress::from_chunk_address(crate::storage::ChunkAddress::new(
+            XorName::random(&mut thread_rng()),
+        ));
+        let record = to_record(&other_key, &chunk, RecordKind::Chunk)?;
+        assert!(!is_early_completion_eligible(&record));
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_is_never_eligible() -> crate::error::Result<()> {
+        let sk = SecretKey::random();
+        let tx = Transaction::new(sk.public_key(), vec![], [0; 32], vec![], &sk);
+        let key = NetworkAddress::from_transaction_address(tx.address());
+        let record = to_record(&key, &vec![tx], RecordKind::Transaction)?;
+        assert!(!is_early_completion_eligible(&record));
+        Ok(())
+    }
+
+    #[test]
+    fn register_is_never_eligible() -> color_eyre::Result<()> {
+        let sk = SecretKey::random();
+        let base_register = Register::new(
+            sk.public_key(),
+            XorName::random(&mut thread_rng()),
+            Permissions::default(),
+        );
+        let signature = sk.sign(base_register.bytes()?);
+        let key = NetworkAddress::from_register_address(*base_register.address());
+        let register = SignedRegister::new(base_register, signature, BTreeSet::new());
+        // A valid copy may still be missing the ops merged in by the other copies
+        let record = to_record(&key, &register, RecordKind::Register)?;
+        assert!(!is_early_completion_eligible(&record));
+        Ok(())
+    }
+
+    #[test]
+    fn scratchpad_is_never_eligible() -> crate::error::Result<()> {
+        let sk = SecretKey::random();
+        let mut scratchpad = Scratchpad::new(sk.public_key(), 0);
+        let _ = scratchpad.update_and_sign(Bytes::from_static(b"data"), &sk);
+        let record = to_record(
+            &scratchpad.network_address(),
+            &scratchpad,
+            RecordKind::Scratchpad,
+        )?;
+        assert!(!is_early_completion_eligible(&record));
+        Ok(())
+    }
+}
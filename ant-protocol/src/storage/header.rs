@@ -40,6 +40,8 @@ pub enum RecordKind {
     RegisterWithPayment,
     Scratchpad,
     ScratchpadWithPayment,
+    Pointer,
+    PointerWithPayment,
 }
 
 impl Serialize for RecordKind {
@@ -56,6 +58,8 @@ impl Serialize for RecordKind {
             Self::Scratchpad => serializer.serialize_u32(5),
             Self::ScratchpadWithPayment => serializer.serialize_u32(6),
             Self::TransactionWithPayment => serializer.serialize_u32(7),
+            Self::Pointer => serializer.serialize_u32(8),
+            Self::PointerWithPayment => serializer.serialize_u32(9),
         }
     }
 }
@@ -75,6 +79,8 @@ impl<'de> Deserialize<'de> for RecordKind {
             5 => Ok(Self::Scratchpad),
             6 => Ok(Self::ScratchpadWithPayment),
             7 => Ok(Self::TransactionWithPayment),
+            8 => Ok(Self::Pointer),
+            9 => Ok(Self::PointerWithPayment),
             _ => Err(serde::de::Error::custom(
                 "Unexpected integer for RecordKind variant",
             )),
@@ -207,6 +213,18 @@ mod tests {
         .try_serialize()?;
         assert_eq!(scratchpad_with_payment.len(), RecordHeader::SIZE);
 
+        let pointer = RecordHeader {
+            kind: RecordKind::Pointer,
+        }
+        .try_serialize()?;
+        assert_eq!(pointer.len(), RecordHeader::SIZE);
+
+        let pointer_with_payment = RecordHeader {
+            kind: RecordKind::PointerWithPayment,
+        }
+        .try_serialize()?;
+        assert_eq!(pointer_with_payment.len(), RecordHeader::SIZE);
+
         Ok(())
     }
 }
@@ -0,0 +1,144 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{ChunkAddress, PointerAddress};
+use crate::NetworkAddress;
+use bls::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use xor_name::XorName;
+
+/// The address a [`Pointer`] points at.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum PointerTarget {
+    /// A chunk, e.g. the data map chunk of a file, an archive or a folder.
+    ChunkAddress(ChunkAddress),
+    /// Another pointer, allowing for chained names.
+    PointerAddress(PointerAddress),
+}
+
+impl PointerTarget {
+    /// Returns the bytes identifying the target, used when signing the pointer.
+    fn to_bytes(self) -> Vec<u8> {
+        match self {
+            PointerTarget::ChunkAddress(addr) => {
+                let mut bytes = vec![0];
+                bytes.extend_from_slice(&addr.xorname().0);
+                bytes
+            }
+            PointerTarget::PointerAddress(addr) => {
+                let mut bytes = vec![1];
+                bytes.extend_from_slice(&addr.xorname().0);
+                bytes
+            }
+        }
+    }
+}
+
+/// Pointer, a mutable name pointing at an address on the network, which can only be updated by its owner.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Debug, Serialize, Deserialize)]
+pub struct Pointer {
+    /// Network address, derived from the owner and the name.
+    address: PointerAddress,
+    /// The address the pointer currently points at.
+    target: PointerTarget,
+    /// Monotonically increasing counter to track the number of times this has been updated.
+    counter: u64,
+    /// Signature over the name, the counter and the target from the owning key.
+    signature: Signature,
+}
+
+impl Pointer {
+    /// Creates a new `Pointer` signed by the owner.
+    pub fn new(owner: &SecretKey, name: XorName, counter: u64, target: PointerTarget) -> Self {
+        let address = PointerAddress::new(owner.public_key(), name);
+        let signature = owner.sign(Self::bytes_for_signature(&address, counter, &target));
+        Self {
+            address,
+            target,
+            counter,
+            signature,
+        }
+    }
+
+    /// Returns the bytes signed by the owner of the pointer.
+    fn bytes_for_signature(
+        address: &PointerAddress,
+        counter: u64,
+        target: &PointerTarget,
+    ) -> Vec<u8> {
+        let mut bytes = address.name().0.to_vec();
+        bytes.extend_from_slice(&counter.to_be_bytes());
+        bytes.extend(target.to_bytes());
+        bytes
+    }
+
+    /// Verifies the signature of the pointer is valid for the owner's public key.
+    pub fn verify(&self) -> bool {
+        let bytes = Self::bytes_for_signature(&self.address, self.counter, &self.target);
+        self.owner().verify(&self.signature, bytes)
+    }
+
+    /// Returns the target.
+    pub fn target(&self) -> &PointerTarget {
+        &self.target
+    }
+
+    /// Return the current count
+    pub fn count(&self) -> u64 {
+        self.counter
+    }
+
+    /// Returns the owner.
+    pub fn owner(&self) -> &PublicKey {
+        self.address.owner()
+    }
+
+    /// Returns the address.
+    pub fn address(&self) -> &PointerAddress {
+        &self.address
+    }
+
+    /// Returns the NetworkAddress.
+    pub fn network_address(&self) -> NetworkAddress {
+        NetworkAddress::PointerAddress(self.address)
+    }
+
+    /// Returns the network name of the pointer, where it's stored.
+    pub fn xorname(&self) -> XorName {
+        self.address.xorname()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointer_signature_is_verified() {
+        let sk = SecretKey::random();
+        let name = XorName::from_content(b"my-website");
+        let target = PointerTarget::ChunkAddress(ChunkAddress::new(XorName::from_content(b"v1")));
+        let pointer = Pointer::new(&sk, name, 0, target);
+        assert!(pointer.verify());
+
+        let other_target =
+            PointerTarget::ChunkAddress(ChunkAddress::new(XorName::from_content(b"v2")));
+        let forged = Pointer {
+            target: other_target,
+            ..pointer.clone()
+        };
+        assert!(!forged.verify());
+
+        let replayed = Pointer {
+            counter: 1,
+            ..pointer
+        };
+        assert!(!replayed.verify());
+    }
+}
@@ -15,6 +15,9 @@ use serde::{Deserialize, Serialize};
 
 use xor_name::XorName;
 
+/// Maximum size of the encrypted data of a scratchpad, which is meant for small mutable data.
+pub const MAX_SCRATCHPAD_SIZE: usize = 4 * 1024 * 1024;
+
 /// Scratchpad, an mutable address for encrypted data
 #[derive(
     Hash, Eq, PartialEq, PartialOrd, Ord, Clone, custom_debug::Debug, Serialize, Deserialize,
@@ -30,9 +33,14 @@ pub struct Scratchpad {
     encrypted_data: Bytes,
     /// Monotonically increasing counter to track the number of times this has been updated.
     counter: u64,
-    /// Signature over `Vec<counter>`.extend(Xorname::from_content(encrypted_data).to_vec()) from the owning key.
-    /// Required for scratchpad to be valid.
+    /// Signature over `Vec<counter>`.extend(Xorname::from_content(encrypted_data).to_vec()) from the owning key,
+    /// extended with the expiry if any. Required for scratchpad to be valid.
     signature: Option<Signature>,
+    /// When the scratchpad expires, in seconds since the UNIX epoch, for the nodes to drop it past then.
+    /// Left out of the encoding when not set, for the scratchpads without expiry to be unchanged, see
+    /// [`crate::version::supports_scratchpad_expiry`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
 }
 
 impl Scratchpad {
@@ -44,6 +52,7 @@ impl Scratchpad {
             data_encoding,
             counter: 0,
             signature: None,
+            expires_at: None,
         }
     }
 
@@ -64,22 +73,39 @@ impl Scratchpad {
         self.counter
     }
 
+    /// Returns the expiry, in seconds since the UNIX epoch
+    pub fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    /// Whether the scratchpad has expired by `now`, in seconds since the UNIX epoch.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
     /// Returns the next counter value,
     ///
     /// Encrypts data and updates the signature with provided sk
     pub fn update_and_sign(&mut self, unencrypted_data: Bytes, sk: &SecretKey) -> u64 {
+        self.update_and_sign_with_expiry(unencrypted_data, sk, None)
+    }
+
+    /// Same as `update_and_sign`, with the scratchpad expiring at `expires_at`, in seconds since the UNIX epoch.
+    /// The expiry is signed along with the data, for it not to be changed or stripped by anyone but the owner.
+    pub fn update_and_sign_with_expiry(
+        &mut self,
+        unencrypted_data: Bytes,
+        sk: &SecretKey,
+        expires_at: Option<u64>,
+    ) -> u64 {
         let next_count = self.increment();
 
         let pk = self.owner();
 
         self.encrypted_data = Bytes::from(pk.encrypt(unencrypted_data).to_bytes());
+        self.expires_at = expires_at;
 
-        let encrypted_data_xorname = self.encrypted_data_hash().to_vec();
-
-        let mut bytes_to_sign = self.counter.to_be_bytes().to_vec();
-        bytes_to_sign.extend(encrypted_data_xorname);
-
-        self.signature = Some(sk.sign(&bytes_to_sign));
+        self.signature = Some(sk.sign(self.bytes_for_signing()));
         next_count
     }
 
@@ -87,15 +113,22 @@ impl Scratchpad {
     /// owner's public key.
     pub fn is_valid(&self) -> bool {
         if let Some(signature) = &self.signature {
-            let mut signing_bytes = self.counter.to_be_bytes().to_vec();
-            signing_bytes.extend(self.encrypted_data_hash().to_vec()); // add the count
-
-            self.owner().verify(signature, &signing_bytes)
+            self.owner().verify(signature, self.bytes_for_signing())
         } else {
             false
         }
     }
 
+    /// The counter, the hash of the encrypted data and the expiry if any.
+    fn bytes_for_signing(&self) -> Vec<u8> {
+        let mut bytes = self.counter.to_be_bytes().to_vec();
+        bytes.extend(self.encrypted_data_hash().to_vec());
+        if let Some(expires_at) = self.expires_at {
+            bytes.extend(expires_at.to_be_bytes());
+        }
+        bytes
+    }
+
     /// Returns the encrypted_data.
     pub fn encrypted_data(&self) -> &Bytes {
         &self.encrypted_data
@@ -162,4 +195,57 @@ mod tests {
         scratchpad.update_and_sign(Bytes::from_static(b"data to be encrypted"), &sk);
         assert!(scratchpad.is_valid());
     }
+
+    #[test]
+    fn the_expiry_is_signed_by_the_owner() {
+        let sk = SecretKey::random();
+        let mut scratchpad = Scratchpad::new(sk.public_key(), 42);
+        scratchpad.update_and_sign_with_expiry(Bytes::from_static(b"data"), &sk, Some(100));
+        assert!(scratchpad.is_valid());
+        assert_eq!(scratchpad.expires_at(), Some(100));
+        assert!(!scratchpad.is_expired(99));
+        assert!(scratchpad.is_expired(100));
+
+        let mut nearer = scratchpad.clone();
+        nearer.expires_at = Some(1);
+        assert!(!nearer.is_valid());
+
+        let mut stripped = scratchpad.clone();
+        stripped.expires_at = None;
+        assert!(!stripped.is_valid());
+    }
+
+    #[test]
+    fn scratchpads_without_expiry_keep_their_encoding() {
+        /// The scratchpad as encoded before it could expire.
+        #[derive(Serialize)]
+        struct LegacyScratchpad<'a> {
+            address: &'a ScratchpadAddress,
+            data_encoding: u64,
+            encrypted_data: &'a Bytes,
+            counter: u64,
+            signature: Option<Signature>,
+        }
+
+        let sk = SecretKey::random();
+        let mut scratchpad = Scratchpad::new(sk.public_key(), 42);
+        scratchpad.update_and_sign(Bytes::from_static(b"data"), &sk);
+        let legacy = LegacyScratchpad {
+            address: &scratchpad.address,
+            data_encoding: scratchpad.data_encoding,
+            encrypted_data: &scratchpad.encrypted_data,
+            counter: scratchpad.counter,
+            signature: scratchpad.signature.clone(),
+        };
+        let legacy = rmp_serde::to_vec(&legacy).expect("Failed to encode the legacy scratchpad");
+        assert_eq!(
+            rmp_serde::to_vec(&scratchpad).expect("Failed to encode the scratchpad"),
+            legacy
+        );
+
+        let decoded: Scratchpad =
+            rmp_serde::from_slice(&legacy).expect("Failed to decode the legacy scratchpad");
+        assert_eq!(decoded, scratchpad);
+        assert!(decoded.is_valid());
+    }
 }
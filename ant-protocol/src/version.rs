@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use lazy_static::lazy_static;
-use std::sync::RwLock;
+use std::{fmt, sync::RwLock};
 
 lazy_static! {
     /// The network_id is used to differentiate between different networks.
@@ -83,10 +83,277 @@ pub fn get_truncate_version_str() -> String {
     }
 }
 
+/// The first protocol version whose nodes decode the expiry of the scratchpads, see [`supports_scratchpad_expiry`].
+const SCRATCHPAD_EXPIRY_DECODED_SINCE: (u64, u64) = (0, 4);
+
+/// Whether the scratchpads can carry an expiry on the network, i.e. whether all the peers we talk to, down to the
+/// ones one minor version behind us during a rolling upgrade, decode it. Their older nodes fail to decode the
+/// scratchpads carrying one.
+pub fn supports_scratchpad_expiry() -> bool {
+    let version = get_truncate_version_str();
+    let Some((major, minor)) = version.split_once('.') else {
+        return false;
+    };
+    let (Ok(major), Ok(minor)) = (major.parse(), minor.parse()) else {
+        return false;
+    };
+    all_peers_decode_since((major, minor), SCRATCHPAD_EXPIRY_DECODED_SINCE)
+}
+
+/// Whether the peers of the given version, and the ones one minor version behind it, all run `since` or later.
+/// A new major version only talks to its own peers.
+fn all_peers_decode_since(ours: (u64, u64), since: (u64, u64)) -> bool {
+    let (major, minor) = ours;
+    (major, minor.saturating_sub(1)) >= since
+}
+
+/// The protocol version a peer runs, as in its identify protocol str, e.g. `ant/0.3/1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtocolVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub network_id: u8,
+}
+
+impl ProtocolVersion {
+    /// Parse an identify protocol str, or a req/response one like `/ant/0.3/1`.
+    pub fn parse(protocol_str: &str) -> Option<Self> {
+        let mut parts = protocol_str.trim_start_matches('/').split('/');
+        if parts.next() != Some("ant") {
+            return None;
+        }
+        let (major, minor) = parts.next()?.split_once('.')?;
+        let network_id = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+            network_id,
+        })
+    }
+
+    /// The version released before this one, whose peers can still be talked to during a rolling upgrade.
+    pub fn previous(&self) -> Option<Self> {
+        Some(Self {
+            minor: self.minor.checked_sub(1)?,
+            ..*self
+        })
+    }
+
+    /// The req/response protocol str of the version, see [`REQ_RESPONSE_VERSION_STR`].
+    pub fn req_response_version_str(&self) -> String {
+        format!("/ant/{}.{}/{}", self.major, self.minor, self.network_id)
+    }
+
+    fn is_adjacent_to(&self, other: &Self) -> bool {
+        self.major == other.major && self.minor.abs_diff(other.minor) == 1
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ant/{}.{}/{}", self.major, self.minor, self.network_id)
+    }
+}
+
+/// Why a peer's protocol is not compatible with ours.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProtocolMismatch {
+    #[error("the peer is on network {theirs}, we are on network {ours}")]
+    DifferentNetwork { ours: u8, theirs: u8 },
+    #[error("the network requires protocol {theirs}, we have {ours}")]
+    IncompatibleVersion {
+        ours: ProtocolVersion,
+        theirs: ProtocolVersion,
+    },
+    #[error("the peer runs the unrecognised protocol {0:?}")]
+    Unrecognised(String),
+}
+
+/// Check the identify protocol of a peer against ours.
+///
+/// The peers one minor version apart are accepted with `accept_adjacent`, for the nodes of a network
+/// going through a rolling upgrade to keep talking to each other.
+pub fn check_protocol_compatibility(
+    ours: &str,
+    theirs: &str,
+    accept_adjacent: bool,
+) -> Result<(), ProtocolMismatch> {
+    if ours == theirs {
+        return Ok(());
+    }
+    let (Some(our_version), Some(their_version)) =
+        (ProtocolVersion::parse(ours), ProtocolVersion::parse(theirs))
+    else {
+        return Err(ProtocolMismatch::Unrecognised(theirs.to_string()));
+    };
+    if our_version.network_id != their_version.network_id {
+        return Err(ProtocolMismatch::DifferentNetwork {
+            ours: our_version.network_id,
+            theirs: their_version.network_id,
+        });
+    }
+    if our_version == their_version
+        || (accept_adjacent && our_version.is_adjacent_to(&their_version))
+    {
+        return Ok(());
+    }
+    Err(ProtocolMismatch::IncompatibleVersion {
+        ours: our_version,
+        theirs: their_version,
+    })
+}
+
+/// Separates the agent version of a node from the capabilities it advertises along with it.
+const CAPABILITIES_SEPARATOR: &str = ";caps=";
+
+/// The capabilities a node advertises along with its identify agent version, e.g.
+/// `ant/node/0.3/1;caps=relay-server,archive`, for its peers to route, cache and pick payees by them rather than
+/// probing it. The capabilities a peer doesn't know of are ignored, for new ones to be added over time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeCapabilities {
+    /// Relays the connections to the nodes behind a home network.
+    pub relay_server: bool,
+    /// Its storage was near full when it started.
+    pub storage_near_full: bool,
+    /// Volunteers to hold records beyond its close group responsibility, for the clients to push cold data to.
+    pub archive: bool,
+    /// Behind a home network, only reachable through a relay.
+    pub client_only: bool,
+}
+
+impl NodeCapabilities {
+    const RELAY_SERVER: &'static str = "relay-server";
+    const STORAGE_NEAR_FULL: &'static str = "storage-near-full";
+    const ARCHIVE: &'static str = "archive";
+    const CLIENT_ONLY: &'static str = "client-only";
+
+    /// The agent version advertising the capabilities, left as is if there are none.
+    pub fn agent_version(&self, agent_version: &str) -> String {
+        let flags: Vec<_> = [
+            (self.relay_server, Self::RELAY_SERVER),
+            (self.storage_near_full, Self::STORAGE_NEAR_FULL),
+            (self.archive, Self::ARCHIVE),
+            (self.client_only, Self::CLIENT_ONLY),
+        ]
+        .into_iter()
+        .filter_map(|(is_set, flag)| is_set.then_some(flag))
+        .collect();
+        if flags.is_empty() {
+            agent_version.to_string()
+        } else {
+            format!("{agent_version}{CAPABILITIES_SEPARATOR}{}", flags.join(","))
+        }
+    }
+
+    /// Split the agent version a peer advertises into its version and its capabilities.
+    pub fn parse_agent_version(agent_version: &str) -> (&str, Self) {
+        let mut capabilities = Self::default();
+        let Some((version, flags)) = agent_version.split_once(CAPABILITIES_SEPARATOR) else {
+            return (agent_version, capabilities);
+        };
+        for flag in flags.split(',') {
+            match flag {
+                Self::RELAY_SERVER => capabilities.relay_server = true,
+                Self::STORAGE_NEAR_FULL => capabilities.storage_near_full = true,
+                Self::ARCHIVE => capabilities.archive = true,
+                Self::CLIENT_ONLY => capabilities.client_only = true,
+                _ => {}
+            }
+        }
+        (version, capabilities)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn capabilities_round_trip_through_the_agent_version() {
+        let version = "ant/node/0.3/1";
+        assert_eq!(NodeCapabilities::default().agent_version(version), version);
+        assert_eq!(
+            NodeCapabilities::parse_agent_version(version),
+            (version, NodeCapabilities::default())
+        );
+
+        let capabilities = NodeCapabilities {
+            relay_server: true,
+            archive: true,
+            ..Default::default()
+        };
+        let agent_version = capabilities.agent_version(version);
+        assert_eq!(agent_version, "ant/node/0.3/1;caps=relay-server,archive");
+        assert_eq!(
+            NodeCapabilities::parse_agent_version(&agent_version),
+            (version, capabilities)
+        );
+
+        // The capabilities of newer nodes are skipped over.
+        assert_eq!(
+            NodeCapabilities::parse_agent_version("ant/node/0.3/1;caps=teleport,client-only"),
+            (
+                version,
+                NodeCapabilities {
+                    client_only: true,
+                    ..Default::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn scratchpad_expiry_waits_for_the_adjacent_versions_to_decode_it() {
+        let since = SCRATCHPAD_EXPIRY_DECODED_SINCE;
+        // The nodes one minor version behind can't decode it yet.
+        assert!(!all_peers_decode_since(since, since));
+        assert!(all_peers_decode_since((since.0, since.1 + 1), since));
+        assert!(!all_peers_decode_since((since.0, since.1 - 1), since));
+        assert!(all_peers_decode_since((since.0 + 1, 1), since));
+        assert!(all_peers_decode_since((since.0 + 1, 0), since));
+    }
+
+    #[test]
+    fn adjacent_protocol_versions_are_only_accepted_if_allowed() {
+        let ours = "ant/0.3/1";
+        assert_eq!(check_protocol_compatibility(ours, ours, false), Ok(()));
+        assert_eq!(
+            check_protocol_compatibility(ours, "ant/0.2/1", true),
+            Ok(())
+        );
+        assert_eq!(
+            check_protocol_compatibility(ours, "ant/0.4/1", true),
+            Ok(())
+        );
+
+        let mismatch = check_protocol_compatibility(ours, "ant/0.4/1", false).unwrap_err();
+        assert_eq!(
+            mismatch.to_string(),
+            "the network requires protocol ant/0.4/1, we have ant/0.3/1"
+        );
+        assert!(matches!(
+            check_protocol_compatibility(ours, "ant/1.3/1", true),
+            Err(ProtocolMismatch::IncompatibleVersion { .. })
+        ));
+        assert!(matches!(
+            check_protocol_compatibility(ours, "ant/0.3/2", true),
+            Err(ProtocolMismatch::DifferentNetwork { ours: 1, theirs: 2 })
+        ));
+        assert!(matches!(
+            check_protocol_compatibility(ours, "ipfs/0.1.0", true),
+            Err(ProtocolMismatch::Unrecognised(_))
+        ));
+
+        let version = ProtocolVersion::parse("/ant/0.3/1").unwrap();
+        assert_eq!(
+            version.previous().map(|v| v.req_response_version_str()),
+            Some("/ant/0.2/1".to_string())
+        );
+    }
+
     #[test]
     fn test_print_version_strings() -> Result<(), Box<dyn std::error::Error>> {
         set_network_id(3);
@@ -20,6 +20,6 @@ pub use self::{
     metadata::{Entry, EntryHash},
     permissions::Permissions,
     reg_crdt::RegisterCrdt,
-    register::{Register, SignedRegister},
+    register::{Register, SignedRegister, MAX_REG_ENTRY_SIZE, MAX_REG_NUM_ENTRIES},
     register_op::RegisterOp,
 };
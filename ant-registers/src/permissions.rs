@@ -52,6 +52,14 @@ impl Permissions {
         }
     }
 
+    /// Returns the users allowed to write to this Register, or `None` if anyone can write
+    pub fn writers(&self) -> Option<&BTreeSet<PublicKey>> {
+        match self {
+            Self::AnyoneCanWrite => None,
+            Self::Writers(writers) => Some(writers),
+        }
+    }
+
     /// If this is restricted to a set of users, add a user to the list of users that can write to this Register
     pub fn add_writer(&mut self, user: PublicKey) {
         if let Self::Writers(writers) = self {
@@ -126,6 +126,51 @@ impl RegisterCrdt {
             .collect()
     }
 
+    /// Returns the hashes of the current entries, i.e. the tips of the concurrent branches of the register.
+    pub fn tips(&self) -> BTreeSet<EntryHash> {
+        self.data
+            .read()
+            .hashes()
+            .into_iter()
+            .map(EntryHash)
+            .collect()
+    }
+
+    /// Returns all the entries of the register, along with their corresponding entry hashes and the hashes of their
+    /// children, i.e. the entries they were written atop of.
+    /// Entries come after their children, from the first entries written to the current ones.
+    pub fn history(&self) -> Vec<(EntryHash, Entry, BTreeSet<EntryHash>)> {
+        let mut history = Vec::new();
+        let mut visited = HashSet::new();
+
+        for node in self.data.all_nodes() {
+            // Depth first, adding an entry once all of its children have been added.
+            let mut stack = vec![(node.hash(), false)];
+            while let Some((hash, children_added)) = stack.pop() {
+                let Some(node) = self.data.node(hash) else {
+                    continue;
+                };
+                if children_added {
+                    let children = node.children.iter().copied().map(EntryHash).collect();
+                    history.push((EntryHash(hash), node.value.clone(), children));
+                    continue;
+                }
+                if !visited.insert(hash) {
+                    continue;
+                }
+                stack.push((hash, true));
+                stack.extend(
+                    node.children
+                        .iter()
+                        .filter(|child| !visited.contains(*child))
+                        .map(|child| (*child, false)),
+                );
+            }
+        }
+
+        history
+    }
+
     /// Access the underlying MerkleReg (e.g. for access to history)
     /// NOTE: This API is unstable and may be removed in the future
     pub fn merkle_reg(&self) -> &MerkleReg<Entry> {
@@ -305,4 +350,36 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn entry_history_and_tips() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let address = RegisterAddress {
+            meta: XorName::random(&mut rng),
+            owner: SecretKey::random().public_key(),
+        };
+        let mut crdt = RegisterCrdt::new(address);
+
+        // entry_1 <- entry_2_1 <- entry_3, with entry_2_2 written concurrently atop entry_1
+        let (entry_hash_1, _, _) = crdt.write(vec![0x1], &BTreeSet::new())?;
+        let (entry_hash_2_1, _, _) = crdt.write(vec![0x2, 0x1], &[entry_hash_1].into())?;
+        let (entry_hash_2_2, _, _) = crdt.write(vec![0x2, 0x2], &[entry_hash_1].into())?;
+        let (entry_hash_3, _, _) = crdt.write(vec![0x3], &[entry_hash_2_1].into())?;
+
+        assert_eq!(crdt.tips(), [entry_hash_2_2, entry_hash_3].into());
+
+        let history = crdt.history();
+        assert_eq!(history.len(), 4);
+        let position = |hash| history.iter().position(|(h, ..)| *h == hash);
+        // Entries come after the ones they were written atop of
+        assert_eq!(position(entry_hash_1), Some(0));
+        assert!(position(entry_hash_2_1) < position(entry_hash_3));
+        assert!(position(entry_hash_2_2).is_some());
+
+        let (_, entry_3, children_3) = &history[position(entry_hash_3).unwrap_or_default()];
+        assert_eq!(entry_3, &vec![0x3]);
+        assert_eq!(children_3, &[entry_hash_2_1].into());
+
+        Ok(())
+    }
 }
@@ -14,11 +14,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use xor_name::XorName;
 
-/// Arbitrary maximum size of a register entry.
-const MAX_REG_ENTRY_SIZE: usize = 1024;
+/// Arbitrary maximum size of a register entry, in bytes.
+/// Nodes reject registers holding bigger entries with [`Error::EntryTooBig`].
+pub const MAX_REG_ENTRY_SIZE: usize = 1024;
 
 /// Maximum number of entries of a register.
-const MAX_REG_NUM_ENTRIES: u16 = 1024;
+/// Nodes reject registers holding more entries, even once merged, with [`Error::TooManyEntries`].
+pub const MAX_REG_NUM_ENTRIES: u16 = 1024;
 
 /// A Register on the SAFE Network
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -62,7 +64,7 @@ impl SignedRegister {
     /// Verfies a SignedRegister
     pub fn verify(&self) -> Result<()> {
         let reg_size = self.ops.len();
-        if reg_size >= MAX_REG_NUM_ENTRIES as usize {
+        if reg_size > MAX_REG_NUM_ENTRIES as usize {
             return Err(Error::TooManyEntries(reg_size));
         }
 
@@ -101,6 +103,7 @@ impl SignedRegister {
     /// Merge two SignedRegisters
     pub fn merge(&mut self, other: &Self) -> Result<()> {
         self.register.verify_is_mergeable(&other.register)?;
+        self.verify_merged_size(other)?;
         self.ops.extend(other.ops.clone());
         Ok(())
     }
@@ -110,10 +113,20 @@ impl SignedRegister {
     pub fn verified_merge(&mut self, other: &Self) -> Result<()> {
         self.register.verify_is_mergeable(&other.register)?;
         other.verify()?;
+        self.verify_merged_size(other)?;
         self.ops.extend(other.ops.clone());
         Ok(())
     }
 
+    /// Concurrent writes can each be within the entries cap, but not once merged.
+    fn verify_merged_size(&self, other: &Self) -> Result<()> {
+        let merged_size = self.ops.union(&other.ops).count();
+        if merged_size > MAX_REG_NUM_ENTRIES as usize {
+            return Err(Error::TooManyEntries(merged_size));
+        }
+        Ok(())
+    }
+
     /// Return the address.
     pub fn address(&self) -> &RegisterAddress {
         self.register.address()
@@ -198,10 +211,8 @@ impl Register {
     }
 
     /// Check if a register op is valid for our current register
+    /// The op must be signed by its source, even when anyone can write, so that the source can't be forged.
     pub fn check_register_op(&self, op: &RegisterOp) -> Result<()> {
-        if self.permissions.can_anyone_write() {
-            return Ok(()); // anyone can write, so no need to check the signature
-        }
         self.check_user_permissions(op.source)?;
         op.verify_signature(&op.source)
     }
@@ -316,6 +327,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn ops_with_a_forged_source_are_rejected() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+        let meta: XorName = xor_name::rand::random();
+        let address = RegisterAddress { meta, owner };
+
+        for permissions in [
+            Permissions::new_anyone_can_write(),
+            Permissions::new_with([writer]),
+        ] {
+            let mut signed_reg =
+                create_reg_replica_with(meta, Some(owner_sk.clone()), Some(permissions));
+
+            // An op signed by someone else claiming to be an allowed writer
+            let mut op = generate_random_op(address, &SecretKey::random())?;
+            op.source = writer;
+            let res = signed_reg.add_op(op.clone());
+            assert!(
+                matches!(&res, Err(err) if err == &Error::InvalidSignature),
+                "Unexpected result: {res:?}"
+            );
+
+            // ...is also caught when verifying a register it made its way into
+            let _ = signed_reg.ops.insert(op);
+            assert_eq!(signed_reg.verify(), Err(Error::InvalidSignature));
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn register_query_public_perms() -> eyre::Result<()> {
         let meta = xor_name::rand::random();
@@ -403,6 +447,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn merging_over_max_reg_entries_errors() -> eyre::Result<()> {
+        let meta = xor_name::rand::random();
+        let authority_sk = SecretKey::random();
+        let address = RegisterAddress {
+            meta,
+            owner: authority_sk.public_key(),
+        };
+        let perms = Some(Permissions::new_anyone_can_write());
+
+        // each replica is half full, but their entries differ
+        let mut replica1 = create_reg_replica_with(meta, Some(authority_sk.clone()), perms.clone());
+        let mut replica2 = create_reg_replica_with(meta, Some(authority_sk.clone()), perms);
+        for _ in 0..MAX_REG_NUM_ENTRIES / 2 {
+            replica1.add_op(generate_random_op(address, &authority_sk)?)?;
+            replica2.add_op(generate_random_op(address, &authority_sk)?)?;
+        }
+        replica1.verify()?;
+
+        // a full replica is still valid
+        let mut full_replica = replica1.clone();
+        full_replica.merge(&replica2)?;
+        full_replica.verify()?;
+
+        replica2.add_op(generate_random_op(address, &authority_sk)?)?;
+        assert_eq!(
+            replica1.verified_merge(&replica2),
+            Err(Error::TooManyEntries(MAX_REG_NUM_ENTRIES as usize + 1))
+        );
+        assert_eq!(replica1.ops().len(), MAX_REG_NUM_ENTRIES as usize / 2);
+
+        Ok(())
+    }
+
     // Helpers for tests
     fn gen_reg_replicas(
         authority_sk: Option<SecretKey>,
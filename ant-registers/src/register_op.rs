@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, Entry, Error, RegisterAddress};
+use crate::{error::Result, Entry, EntryHash, Error, RegisterAddress};
 
 use bls::{PublicKey, SecretKey};
 use crdts::merkle_reg::Node as MerkleDagEntry;
@@ -64,6 +64,16 @@ impl RegisterOp {
         self.source
     }
 
+    /// the hash of the entry written by the operation
+    pub fn entry_hash(&self) -> EntryHash {
+        EntryHash(self.crdt_op.hash())
+    }
+
+    /// the entry written by the operation
+    pub fn entry(&self) -> &Entry {
+        &self.crdt_op.value
+    }
+
     /// Check signature of register Op against provided public key
     pub fn verify_signature(&self, pk: &PublicKey) -> Result<()> {
         let bytes = Self::bytes_for_signing(&self.address, &self.crdt_op, &self.source);
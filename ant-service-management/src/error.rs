@@ -38,10 +38,22 @@ pub enum Error {
     RpcNodeRestartError(String),
     #[error("Could not stop node through RPC: {0}")]
     RpcNodeStopError(String),
+    #[error("Could not decommission node through RPC: {0}")]
+    RpcNodeDecommissionError(String),
     #[error("Could not update node through RPC: {0}")]
     RpcNodeUpdateError(String),
     #[error("Could not obtain record addresses through RPC: {0}")]
     RpcRecordAddressError(String),
+    #[error("Could not list records through RPC: {0}")]
+    RpcListRecordsError(String),
+    #[error("Could not obtain peer scores through RPC: {0}")]
+    RpcPeerScoresError(String),
+    #[error("Could not obtain the driver state through RPC: {0}")]
+    RpcDriverStateError(String),
+    #[error("Could not obtain the audit log through RPC: {0}")]
+    RpcAuditLogError(String),
+    #[error("Could not set the faults to inject through RPC: {0}")]
+    RpcSetChaosError(String),
     #[error("Could not find process at '{0}'")]
     ServiceProcessNotFound(String),
     #[error("The service '{0}' does not exists and cannot be removed.")]
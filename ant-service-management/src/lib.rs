@@ -61,6 +61,8 @@ pub enum UpgradeResult {
     NotRequired,
     Upgraded(String, String),
     UpgradedButNotStarted(String, String, String),
+    /// The upgraded service did not start, so the previous binary was put back and started.
+    RolledBack(String, String, String),
     Error(String),
 }
 
@@ -69,6 +71,8 @@ pub struct UpgradeOptions {
     pub auto_restart: bool,
     pub env_variables: Option<Vec<(String, String)>>,
     pub force: bool,
+    /// Put the previous binary back if the upgraded service does not start.
+    pub rollback_on_failure: bool,
     pub start_service: bool,
     pub target_bin_path: PathBuf,
     pub target_version: Version,
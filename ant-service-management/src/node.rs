@@ -423,6 +423,13 @@ pub fn push_arguments_from_peers_args(peers_args: &PeersArgs, args: &mut Vec<OsS
                 .join(","),
         ));
     }
+    if !peers_args.dns_seeds.is_empty() {
+        args.push(OsString::from("--dns-seed"));
+        args.push(OsString::from(peers_args.dns_seeds.join(",")));
+    }
+    if peers_args.skip_contacts_verification {
+        args.push(OsString::from("--skip-contacts-verification"));
+    }
     if peers_args.disable_mainnet_contacts {
         args.push(OsString::from("--testnet"));
     }
@@ -9,16 +9,23 @@
 use crate::error::{Error, Result};
 use ant_protocol::{
     antnode_proto::{
-        ant_node_client::AntNodeClient, NetworkInfoRequest, NodeInfoRequest,
-        RecordAddressesRequest, RestartRequest, StopRequest, UpdateLogLevelRequest, UpdateRequest,
+        ant_node_client::AntNodeClient, AuditLogRequest, DecommissionRequest, DriverStateRequest,
+        ListRecordsRequest, NetworkInfoRequest, NodeInfoRequest, PeerScoresRequest,
+        RecordAddressesRequest, RestartRequest, SetChaosRequest, StopRequest,
+        UpdateLogLevelRequest, UpdateRequest,
     },
+    node_rpc::{rpc_auth_metadata_value, RPC_AUTH_METADATA_KEY},
     CLOSE_GROUP_SIZE,
 };
 use async_trait::async_trait;
 use libp2p::{kad::RecordKey, Multiaddr, PeerId};
-use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf, str::FromStr};
 use tokio::time::Duration;
-use tonic::Request;
+use tonic::{
+    metadata::MetadataValue,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Request,
+};
 use tracing::error;
 
 #[derive(Debug, Clone)]
@@ -30,12 +37,23 @@ pub struct NodeInfo {
     pub version: String,
     pub uptime: Duration,
     pub wallet_balance: u64,
+    pub top_talkers: Vec<PeerBandwidth>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerBandwidth {
+    pub peer_id: PeerId,
+    pub protocol: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct NetworkInfo {
     pub connected_peers: Vec<PeerId>,
     pub listeners: Vec<Multiaddr>,
+    /// Whether the node is reachable from the public internet: `unknown`, `public` or `private`
+    pub reachability: String,
 }
 
 #[derive(Debug, Clone)]
@@ -43,13 +61,86 @@ pub struct RecordAddress {
     pub key: RecordKey,
 }
 
+#[derive(Debug, Clone)]
+pub struct RecordInfo {
+    pub key: RecordKey,
+    pub record_type: String,
+    pub size: u64,
+    /// ilog2 of the distance between the record and the node
+    pub distance: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerScore {
+    pub peer_id: PeerId,
+    pub score: f64,
+    pub is_bad: bool,
+    pub last_issue: String,
+}
+
+/// The sizes of the internal state of a node's networking layer.
+#[derive(Debug, Clone)]
+pub struct DriverState {
+    pub pending_get_closest_peers: u64,
+    pub pending_requests: u64,
+    pub pending_get_record: u64,
+    pub pending_put_record: u64,
+    pub pending_dials: u64,
+    pub replication_pending_keys: u64,
+    pub replication_ongoing_fetches: u64,
+    pub replication_oldest_pending: Duration,
+    pub local_cmd_queue_depth: u64,
+    pub network_cmd_queue_depth: u64,
+    /// The number of peers in each kbucket, keyed by its ilog2 distance
+    pub peers_per_kbucket: BTreeMap<u32, u64>,
+}
+
+/// An entry of the audit log of the records stored and removed by a node.
+#[derive(Debug, Clone)]
+pub struct AuditLogEntry {
+    /// Seconds since the UNIX epoch
+    pub timestamp: u64,
+    pub action: String,
+    /// The record key, in hex
+    pub key: String,
+    pub record_type: Option<String>,
+    pub source: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// The faults a node built with the chaos feature injects on purpose.
+#[derive(Debug, Clone, Default)]
+pub struct ChaosSettings {
+    /// The percentage, 0 to 100, of the inbound requests dropped without a response
+    pub drop_requests_percent: u8,
+    pub replication_delay: Duration,
+    pub corrupt_chunks: bool,
+    pub refuse_quotes: bool,
+}
+
+/// A page of the records held by a node, along with the number of records matching the filter.
+#[derive(Debug, Clone)]
+pub struct RecordsPage {
+    pub records: Vec<RecordInfo>,
+    pub total: u64,
+}
+
 #[async_trait]
 pub trait RpcActions: Sync {
     async fn node_info(&self) -> Result<NodeInfo>;
     async fn network_info(&self) -> Result<NetworkInfo>;
     async fn record_addresses(&self) -> Result<Vec<RecordAddress>>;
+    async fn list_records(
+        &self,
+        record_type: Option<String>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<RecordsPage>;
+    async fn peer_scores(&self) -> Result<Vec<PeerScore>>;
+    async fn driver_state(&self) -> Result<DriverState>;
     async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> Result<()>;
     async fn node_stop(&self, delay_millis: u64) -> Result<()>;
+    async fn node_decommission(&self, timeout_millis: u64) -> Result<()>;
     async fn node_update(&self, delay_millis: u64) -> Result<()>;
     async fn is_node_connected_to_network(&self, timeout: Duration) -> Result<()>;
     async fn update_log_level(&self, log_levels: String) -> Result<()>;
@@ -60,6 +151,8 @@ pub struct RpcClient {
     endpoint: String,
     max_attempts: u8,
     retry_delay: Duration,
+    auth_token: Option<String>,
+    tls_config: Option<ClientTlsConfig>,
 }
 
 impl RpcClient {
@@ -71,6 +164,8 @@ impl RpcClient {
             endpoint: endpoint.to_string(),
             max_attempts: Self::MAX_CONNECTION_RETRY_ATTEMPTS,
             retry_delay: Self::CONNECTION_RETRY_DELAY_SEC,
+            auth_token: None,
+            tls_config: None,
         }
     }
 
@@ -79,6 +174,10 @@ impl RpcClient {
         Self::new(&endpoint)
     }
 
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
     /// Set the maximum number of retry attempts when connecting to the RPC endpoint. Default is 5.
     pub fn set_max_attempts(&mut self, max_retry_attempts: u8) {
         self.max_attempts = max_retry_attempts;
@@ -89,18 +188,37 @@ impl RpcClient {
         self.retry_delay = retry_delay;
     }
 
-    // Connect to the RPC endpoint with retry
-    async fn connect_with_retry(&self) -> Result<AntNodeClient<tonic::transport::Channel>> {
+    /// Set the token presented to the mutating RPCs, for nodes started with an RPC token.
+    pub fn set_auth_token(&mut self, token: String) {
+        self.auth_token = Some(token);
+    }
+
+    /// Connect to the node over TLS, presenting a client certificate if the config has one.
+    pub fn set_tls_config(&mut self, tls_config: ClientTlsConfig) {
+        self.tls_config = Some(tls_config);
+    }
+
+    /// Connect to the RPC endpoint with retry
+    pub async fn connect_with_retry(&self) -> Result<AntNodeClient<Channel>> {
+        let mut endpoint = Endpoint::from_shared(self.endpoint.clone())
+            .map_err(|_| Error::RpcConnectionError(self.endpoint.clone()))?;
+        if let Some(tls_config) = &self.tls_config {
+            endpoint = endpoint.tls_config(tls_config.clone()).map_err(|err| {
+                error!("Invalid TLS config for the RPC endpoint: {err:?}");
+                Error::RpcConnectionError(self.endpoint.clone())
+            })?;
+        }
+
         let mut attempts = 0;
         loop {
             debug!(
                 "Attempting connection to node RPC endpoint at {}...",
                 self.endpoint
             );
-            match AntNodeClient::connect(self.endpoint.clone()).await {
-                Ok(rpc_client) => {
+            match endpoint.connect().await {
+                Ok(channel) => {
                     debug!("Connection successful");
-                    break Ok(rpc_client);
+                    break Ok(AntNodeClient::new(channel));
                 }
                 Err(_) => {
                     attempts += 1;
@@ -116,6 +234,66 @@ impl RpcClient {
             }
         }
     }
+
+    /// Replace the faults the node injects on purpose. Only the nodes built with the chaos feature accept it.
+    pub async fn set_chaos(&self, settings: &ChaosSettings) -> Result<()> {
+        let mut client = self.connect_with_retry().await?;
+        let _response = client
+            .set_chaos(self.authenticated_request(SetChaosRequest {
+                drop_requests_percent: settings.drop_requests_percent as u32,
+                replication_delay_millis: settings.replication_delay.as_millis() as u64,
+                corrupt_chunks: settings.corrupt_chunks,
+                refuse_quotes: settings.refuse_quotes,
+            }))
+            .await
+            .map_err(|e| {
+                error!("Could not set the faults to inject through RPC: {e:?}");
+                Error::RpcSetChaosError(e.to_string())
+            })?;
+        Ok(())
+    }
+
+    /// Retrieve the latest `count` entries of the audit log of the node, oldest first. None are kept by the nodes
+    /// run without an audit log.
+    pub async fn audit_log(&self, count: u64) -> Result<Vec<AuditLogEntry>> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .audit_log(Request::new(AuditLogRequest { count }))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain the audit log through RPC: {e:?}");
+                Error::RpcAuditLogError(e.to_string())
+            })?;
+        let non_empty = |value: String| (!value.is_empty()).then_some(value);
+        let entries = response
+            .into_inner()
+            .entries
+            .into_iter()
+            .map(|entry| AuditLogEntry {
+                timestamp: entry.timestamp,
+                action: entry.action,
+                key: entry.key,
+                record_type: non_empty(entry.record_type),
+                source: non_empty(entry.source),
+                reason: non_empty(entry.reason),
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    /// Wrap the message of a mutating RPC, along with the auth token if one is set.
+    fn authenticated_request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(token) = &self.auth_token {
+            match MetadataValue::from_str(&rpc_auth_metadata_value(token)) {
+                Ok(value) => {
+                    let _ = request.metadata_mut().insert(RPC_AUTH_METADATA_KEY, value);
+                }
+                Err(err) => error!("The RPC auth token is not a valid header value: {err:?}"),
+            }
+        }
+        request
+    }
 }
 
 #[async_trait]
@@ -131,6 +309,15 @@ impl RpcActions for RpcClient {
             })?;
         let node_info_resp = response.get_ref();
         let peer_id = PeerId::from_bytes(&node_info_resp.peer_id)?;
+        let mut top_talkers = vec![];
+        for bandwidth in node_info_resp.top_talkers.iter() {
+            top_talkers.push(PeerBandwidth {
+                peer_id: PeerId::from_bytes(&bandwidth.peer_id)?,
+                protocol: bandwidth.protocol.clone(),
+                bytes_sent: bandwidth.bytes_sent,
+                bytes_received: bandwidth.bytes_received,
+            });
+        }
         let node_info = NodeInfo {
             pid: node_info_resp.pid,
             peer_id,
@@ -139,6 +326,7 @@ impl RpcActions for RpcClient {
             version: node_info_resp.bin_version.clone(),
             uptime: Duration::from_secs(node_info_resp.uptime_secs),
             wallet_balance: node_info_resp.wallet_balance,
+            top_talkers,
         };
         Ok(node_info)
     }
@@ -168,6 +356,7 @@ impl RpcActions for RpcClient {
         Ok(NetworkInfo {
             connected_peers,
             listeners,
+            reachability: network_info.reachability.clone(),
         })
     }
 
@@ -188,10 +377,93 @@ impl RpcActions for RpcClient {
         Ok(record_addresses)
     }
 
+    async fn list_records(
+        &self,
+        record_type: Option<String>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<RecordsPage> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .list_records(Request::new(ListRecordsRequest {
+                record_type: record_type.unwrap_or_default(),
+                offset,
+                limit,
+            }))
+            .await
+            .map_err(|e| {
+                error!("Could not list records through RPC: {e:?}");
+                Error::RpcListRecordsError(e.to_string())
+            })?
+            .into_inner();
+        let records = response
+            .records
+            .into_iter()
+            .map(|record| RecordInfo {
+                key: RecordKey::from(record.address),
+                record_type: record.record_type,
+                size: record.size,
+                distance: record.distance,
+            })
+            .collect();
+        Ok(RecordsPage {
+            records,
+            total: response.total,
+        })
+    }
+
+    async fn peer_scores(&self) -> Result<Vec<PeerScore>> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .peer_scores(Request::new(PeerScoresRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain peer scores through RPC: {e:?}");
+                Error::RpcPeerScoresError(e.to_string())
+            })?;
+        let mut peer_scores = vec![];
+        for peer in response.into_inner().peers {
+            peer_scores.push(PeerScore {
+                peer_id: PeerId::from_bytes(&peer.peer_id)?,
+                score: peer.score,
+                is_bad: peer.is_bad,
+                last_issue: peer.last_issue,
+            });
+        }
+        Ok(peer_scores)
+    }
+
+    async fn driver_state(&self) -> Result<DriverState> {
+        let mut client = self.connect_with_retry().await?;
+        let response = client
+            .driver_state(Request::new(DriverStateRequest {}))
+            .await
+            .map_err(|e| {
+                error!("Could not obtain the driver state through RPC: {e:?}");
+                Error::RpcDriverStateError(e.to_string())
+            })?
+            .into_inner();
+        Ok(DriverState {
+            pending_get_closest_peers: response.pending_get_closest_peers,
+            pending_requests: response.pending_requests,
+            pending_get_record: response.pending_get_record,
+            pending_put_record: response.pending_put_record,
+            pending_dials: response.pending_dials,
+            replication_pending_keys: response.replication_pending_keys,
+            replication_ongoing_fetches: response.replication_ongoing_fetches,
+            replication_oldest_pending: Duration::from_secs(
+                response.replication_oldest_pending_secs,
+            ),
+            local_cmd_queue_depth: response.local_cmd_queue_depth,
+            network_cmd_queue_depth: response.network_cmd_queue_depth,
+            peers_per_kbucket: response.peers_per_kbucket.into_iter().collect(),
+        })
+    }
+
     async fn node_restart(&self, delay_millis: u64, retain_peer_id: bool) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
-            .restart(Request::new(RestartRequest {
+            .restart(self.authenticated_request(RestartRequest {
                 delay_millis,
                 retain_peer_id,
             }))
@@ -206,7 +478,7 @@ impl RpcActions for RpcClient {
     async fn node_stop(&self, delay_millis: u64) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
-            .stop(Request::new(StopRequest { delay_millis }))
+            .stop(self.authenticated_request(StopRequest { delay_millis }))
             .await
             .map_err(|e| {
                 error!("Could not restart node through RPC: {e:?}");
@@ -215,10 +487,22 @@ impl RpcActions for RpcClient {
         Ok(())
     }
 
+    async fn node_decommission(&self, timeout_millis: u64) -> Result<()> {
+        let mut client = self.connect_with_retry().await?;
+        let _response = client
+            .decommission(self.authenticated_request(DecommissionRequest { timeout_millis }))
+            .await
+            .map_err(|e| {
+                error!("Could not decommission node through RPC: {e:?}");
+                Error::RpcNodeDecommissionError(e.to_string())
+            })?;
+        Ok(())
+    }
+
     async fn node_update(&self, delay_millis: u64) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
-            .update(Request::new(UpdateRequest { delay_millis }))
+            .update(self.authenticated_request(UpdateRequest { delay_millis }))
             .await
             .map_err(|e| {
                 error!("Could not update node through RPC: {e:?}");
@@ -273,7 +557,7 @@ impl RpcActions for RpcClient {
     async fn update_log_level(&self, log_levels: String) -> Result<()> {
         let mut client = self.connect_with_retry().await?;
         let _response = client
-            .update_log_level(Request::new(UpdateLogLevelRequest {
+            .update_log_level(self.authenticated_request(UpdateLogLevelRequest {
                 log_level: log_levels,
             }))
             .await
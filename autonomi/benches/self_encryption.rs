@@ -0,0 +1,55 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rand::{thread_rng, Rng};
+use self_encryption::{decrypt_full_set, encrypt};
+
+const SAMPLE_SIZE: usize = 10;
+
+/// The sizes of the data to self-encrypt, in MiB.
+const DATA_SIZES_MB: [usize; 3] = [1, 10, 50];
+
+fn random_data(size: usize) -> Bytes {
+    let mut data = vec![0u8; size];
+    thread_rng().fill(&mut data[..]);
+    Bytes::from(data)
+}
+
+fn self_encryption(c: &mut Criterion) {
+    for size_mb in DATA_SIZES_MB {
+        let data = random_data(size_mb * 1024 * 1024);
+
+        let mut group = c.benchmark_group(format!("self_encryption_{size_mb}MB"));
+        group.sampling_mode(criterion::SamplingMode::Flat);
+        group.sample_size(SAMPLE_SIZE);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        group.bench_function("encrypt", |b| {
+            b.iter_batched(
+                || data.clone(),
+                |data| encrypt(data).expect("Failed to encrypt"),
+                BatchSize::LargeInput,
+            )
+        });
+
+        let (data_map, chunks) = encrypt(data.clone()).expect("Failed to encrypt");
+        group.bench_function("decrypt", |b| {
+            b.iter(|| {
+                let decrypted = decrypt_full_set(&data_map, &chunks).expect("Failed to decrypt");
+                assert_eq!(decrypted.len(), data.len());
+            })
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, self_encryption);
+criterion_main!(benches);
@@ -1,10 +1,12 @@
-from .autonomi_client import Client, Wallet, PaymentOption, VaultSecretKey, UserData, DataMapChunk, encrypt
+from .autonomi_client import Client, Wallet, PaymentOption, VaultSecretKey, RegisterSecretKey, Register, UserData, DataMapChunk, encrypt
 
 __all__ = [
     "Client",
     "Wallet", 
     "PaymentOption",
     "VaultSecretKey",
+    "RegisterSecretKey",
+    "Register",
     "UserData",
     "DataMapChunk",
     "encrypt"
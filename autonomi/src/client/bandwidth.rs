@@ -0,0 +1,156 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::sync::Mutex;
+
+use ant_networking::target_arch::{sleep, Duration, Instant};
+
+use super::data::{CHUNK_DOWNLOAD_BATCH_SIZE, CHUNK_UPLOAD_BATCH_SIZE};
+
+/// Bandwidth limits of a [`crate::Client`], see [`crate::ClientConfig::bandwidth`].
+///
+/// The limits apply to the chunk uploads and downloads of the client. Unset limits leave that direction unthrottled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BandwidthConfig {
+    /// Max number of chunk bytes to upload per second.
+    pub upload_bytes_per_sec: Option<u64>,
+    /// Max number of chunk bytes to download per second.
+    pub download_bytes_per_sec: Option<u64>,
+    /// Max number of chunk uploads in flight at once.
+    ///
+    /// Uploads start at this concurrency and back off when puts time out or fail to reach their quorum. When unset,
+    /// they start at the `CHUNK_UPLOAD_BATCH_SIZE` environment variable, or 8 per CPU core, and may grow past it
+    /// while puts succeed quickly.
+    pub max_concurrent_uploads: Option<usize>,
+    /// Max number of chunk downloads in flight at once.
+    ///
+    /// Defaults to the `CHUNK_DOWNLOAD_BATCH_SIZE` environment variable, or 8 per CPU core.
+    pub max_concurrent_downloads: Option<usize>,
+}
+
+/// Enforces the [`BandwidthConfig`] of a client, shared by all its clones.
+#[derive(Debug, Default)]
+pub(crate) struct Bandwidth {
+    config: BandwidthConfig,
+    upload: Option<RateThrottle>,
+    download: Option<RateThrottle>,
+}
+
+impl Bandwidth {
+    pub(crate) fn new(config: BandwidthConfig) -> Self {
+        Self {
+            config,
+            upload: config.upload_bytes_per_sec.map(RateThrottle::new),
+            download: config.download_bytes_per_sec.map(RateThrottle::new),
+        }
+    }
+
+    /// The number of chunk uploads to have in flight at once.
+    pub(crate) fn upload_concurrency(&self) -> usize {
+        self.config
+            .max_concurrent_uploads
+            .unwrap_or(*CHUNK_UPLOAD_BATCH_SIZE)
+            .max(1)
+    }
+
+    /// The number of chunk downloads to have in flight at once.
+    pub(crate) fn download_concurrency(&self) -> usize {
+        self.config
+            .max_concurrent_downloads
+            .unwrap_or(*CHUNK_DOWNLOAD_BATCH_SIZE)
+            .max(1)
+    }
+
+    /// Wait for our turn to upload `bytes`, if the upload rate is limited.
+    pub(crate) async fn throttle_upload(&self, bytes: usize) {
+        if let Some(throttle) = &self.upload {
+            throttle.consume(bytes).await;
+        }
+    }
+
+    /// Account for `bytes` downloaded, waiting before returning them if the download rate is limited.
+    pub(crate) async fn throttle_download(&self, bytes: usize) {
+        if let Some(throttle) = &self.download {
+            throttle.consume(bytes).await;
+        }
+    }
+}
+
+/// Spaces out transfers so that they average out to at most `bytes_per_sec`.
+///
+/// Each transfer is scheduled once the previous ones are due to have completed at the configured rate, so that
+/// concurrent transfers queue up behind each other rather than all going out at once.
+#[derive(Debug)]
+struct RateThrottle {
+    bytes_per_sec: u64,
+    /// When the transfers scheduled so far are due to have completed.
+    next_free: Mutex<Option<Instant>>,
+}
+
+impl RateThrottle {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.max(1),
+            next_free: Mutex::new(None),
+        }
+    }
+
+    async fn consume(&self, bytes: usize) {
+        let delay = self.schedule(Instant::now(), bytes);
+        if !delay.is_zero() {
+            trace!("Throttling transfer of {bytes} bytes for {delay:?}");
+            sleep(delay).await;
+        }
+    }
+
+    /// Schedule a transfer of `bytes` at `now`, returning how long to wait before it may start.
+    fn schedule(&self, now: Instant, bytes: usize) -> Duration {
+        let transfer_time = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        let mut next_free = self
+            .next_free
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let start = match *next_free {
+            Some(next_free) if next_free > now => next_free,
+            _ => now,
+        };
+        *next_free = Some(start + transfer_time);
+        start - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfers_are_spaced_out_to_the_rate() {
+        let throttle = RateThrottle::new(1000);
+        let now = Instant::now();
+
+        // The first transfer goes out straight away, the next ones wait for the previous ones to be done.
+        assert_eq!(throttle.schedule(now, 500), Duration::ZERO);
+        assert_eq!(throttle.schedule(now, 1000), Duration::from_millis(500));
+        assert_eq!(throttle.schedule(now, 100), Duration::from_millis(1500));
+
+        // Once idle for long enough, transfers go out straight away again.
+        let later = now + Duration::from_secs(5);
+        assert_eq!(throttle.schedule(later, 100), Duration::ZERO);
+    }
+
+    #[test]
+    fn concurrency_defaults_to_the_batch_sizes() {
+        let bandwidth = Bandwidth::new(BandwidthConfig {
+            max_concurrent_uploads: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(bandwidth.upload_concurrency(), 2);
+        assert_eq!(bandwidth.download_concurrency(), *CHUNK_DOWNLOAD_BATCH_SIZE);
+    }
+}
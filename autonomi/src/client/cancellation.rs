@@ -0,0 +1,137 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::future::Future;
+use std::pin::pin;
+
+use ant_networking::target_arch::{sleep, spawn, Duration};
+use futures::future::{select, Either};
+
+pub use tokio_util::sync::CancellationToken;
+
+use super::Client;
+
+/// The operation was cancelled through the token of the client it was run with, or timed out, see
+/// [`Client::with_cancellation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The operation was cancelled")]
+pub struct Cancelled;
+
+/// Run the operation unless or until the token is cancelled.
+async fn until_cancelled<T, E: From<Cancelled>>(
+    token: Option<&CancellationToken>,
+    operation: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let Some(token) = token else {
+        return operation.await;
+    };
+    if token.is_cancelled() {
+        return Err(Cancelled.into());
+    }
+    match select(pin!(operation), pin!(token.cancelled())).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => {
+            debug!("Client operation cancelled");
+            Err(Cancelled.into())
+        }
+    }
+}
+
+/// A token cancelled once `timeout` has elapsed, or once its parent is.
+fn timeout_token(parent: Option<&CancellationToken>, timeout: Duration) -> CancellationToken {
+    let token = parent.map_or_else(CancellationToken::new, CancellationToken::child_token);
+    let timer = token.clone();
+    let _handle = spawn(async move {
+        if let Either::Right(_) = select(pin!(timer.cancelled()), pin!(sleep(timeout))).await {
+            debug!("Cancelling the client operations after their timeout of {timeout:?}");
+            timer.cancel();
+        }
+    });
+    token
+}
+
+impl Client {
+    /// A client on the same connection, whose operations are aborted once the `token` is cancelled, returning a
+    /// `Cancelled` error.
+    ///
+    /// The network queries outstanding are dropped, and no further ones are made. A payment already sent on chain
+    /// is waited on rather than aborted, for its receipt not to be lost, and the chunks stored by a resumable upload
+    /// are kept track of for it to be resumed later on.
+    ///
+    /// ```no_run
+    /// # use autonomi::{client::cancellation::CancellationToken, Client};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::init().await?;
+    /// # let addr = Default::default();
+    /// let token = CancellationToken::new();
+    /// let request = client.with_cancellation(token.clone());
+    /// // e.g. once the HTTP request this download serves is dropped
+    /// token.cancel();
+    /// assert!(request.data_get_public(addr).await.is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            cancellation: Some(token),
+            ..self.clone()
+        }
+    }
+
+    /// A client on the same connection, whose operations are aborted once `timeout` has elapsed from now, or once
+    /// cancelled through the token it already had, see [`Client::with_cancellation`].
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        self.with_cancellation(timeout_token(self.cancellation.as_ref(), timeout))
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Run the operation unless or until the token of the client is cancelled, see [`Client::with_cancellation`].
+    pub(crate) async fn cancellable<T, E: From<Cancelled>>(
+        &self,
+        operation: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        until_cancelled(self.cancellation.as_ref(), operation).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::pending;
+
+    #[tokio::test]
+    async fn operations_are_aborted_once_cancelled_or_timed_out() {
+        assert_eq!(
+            until_cancelled(None, async { Ok::<_, Cancelled>(1) }).await,
+            Ok(1)
+        );
+
+        let parent = CancellationToken::new();
+        let token = timeout_token(Some(&parent), Duration::from_millis(50));
+        assert_eq!(
+            until_cancelled(Some(&token), pending::<Result<(), _>>()).await,
+            Err(Cancelled)
+        );
+        assert!(!parent.is_cancelled());
+
+        // The operations to come aren't even started.
+        let token = timeout_token(Some(&parent), Duration::from_secs(3600));
+        parent.cancel();
+        let started = async { panic!("the operation shouldn't be started") };
+        assert_eq!(
+            until_cancelled::<(), _>(Some(&token), started).await,
+            Err(Cancelled)
+        );
+    }
+}
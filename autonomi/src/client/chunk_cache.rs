@@ -0,0 +1,269 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Client;
+use ant_protocol::storage::Chunk;
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::{fs, io};
+use xor_name::XorName;
+
+/// Where the fetched chunks are kept on disk, and how much of it they may take, see
+/// [`super::ClientConfig::chunk_cache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkCacheConfig {
+    pub dir: PathBuf,
+    /// Once over it, the chunks used the longest ago are dropped first.
+    pub max_bytes: u64,
+}
+
+/// The state of the chunk cache, see [`Client::chunk_cache_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkCacheStats {
+    pub dir: PathBuf,
+    pub chunks: usize,
+    pub bytes: u64,
+    pub max_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheIndex {
+    /// The size and the last use of the chunks.
+    entries: HashMap<XorName, (u64, u64)>,
+    /// The chunks by last use, the oldest first.
+    by_last_use: BTreeMap<u64, XorName>,
+    bytes: u64,
+    next_use: u64,
+}
+
+impl CacheIndex {
+    fn touch(&mut self, name: XorName, size: u64) {
+        let last_use = self.next_use;
+        self.next_use += 1;
+        if let Some((old_size, old_use)) = self.entries.insert(name, (size, last_use)) {
+            let _ = self.by_last_use.remove(&old_use);
+            self.bytes -= old_size;
+        }
+        let _ = self.by_last_use.insert(last_use, name);
+        self.bytes += size;
+    }
+
+    fn remove(&mut self, name: &XorName) -> bool {
+        let Some((size, last_use)) = self.entries.remove(name) else {
+            return false;
+        };
+        let _ = self.by_last_use.remove(&last_use);
+        self.bytes -= size;
+        true
+    }
+}
+
+/// The chunks fetched from the network, kept on disk under their content's address to be read back instead of
+/// fetched again.
+///
+/// A chunk read back is checked against its address, a corrupted one being dropped and fetched again.
+#[derive(Debug)]
+pub(crate) struct ChunkCache {
+    config: ChunkCacheConfig,
+    index: Mutex<CacheIndex>,
+}
+
+impl ChunkCache {
+    /// Open the cache in the dir, indexing the chunks already in it, the least recently written first.
+    pub(crate) fn open(config: ChunkCacheConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        let mut chunks = vec![];
+        for entry in fs::read_dir(&config.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str().and_then(|name| {
+                let bytes: [u8; 32] = hex::decode(name).ok()?.try_into().ok()?;
+                Some(XorName(bytes))
+            }) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            chunks.push((metadata.modified().ok(), name, metadata.len()));
+        }
+        chunks.sort();
+
+        let mut index = CacheIndex::default();
+        for (_modified, name, size) in chunks {
+            index.touch(name, size);
+        }
+        debug!(
+            "Opened the chunk cache at {:?}, holding {} chunks",
+            config.dir,
+            index.entries.len()
+        );
+        let cache = Self {
+            config,
+            index: Mutex::new(index),
+        };
+        // The limit may have been lowered since
+        cache.evict();
+        Ok(cache)
+    }
+
+    fn path(&self, name: &XorName) -> PathBuf {
+        self.config.dir.join(hex::encode(name))
+    }
+
+    pub(crate) fn get(&self, name: &XorName) -> Option<Chunk> {
+        let mut index = self.index.lock().ok()?;
+        let (size, _) = *index.entries.get(name)?;
+        let path = self.path(name);
+        match fs::read(&path) {
+            Ok(value) if XorName::from_content(&value) == *name => {
+                index.touch(*name, size);
+                Some(Chunk::new(Bytes::from(value)))
+            }
+            result => {
+                warn!("Dropping the cached chunk {name:?}, unreadable or corrupted: {result:?}");
+                let _ = index.remove(name);
+                let _ = fs::remove_file(path);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&self, chunk: &Chunk) {
+        let name = *chunk.name();
+        let size = chunk.value().len() as u64;
+        if size > self.config.max_bytes {
+            return;
+        }
+        let Ok(mut index) = self.index.lock() else {
+            return;
+        };
+        if !index.entries.contains_key(&name) {
+            // Write to a temp file first, to never leave a truncated chunk behind
+            let tmp_path = self.config.dir.join(format!("{}.tmp", hex::encode(name)));
+            let written = fs::write(&tmp_path, chunk.value())
+                .and_then(|()| fs::rename(&tmp_path, self.path(&name)));
+            if let Err(err) = written {
+                warn!("Failed to cache the chunk {name:?}: {err}");
+                let _ = fs::remove_file(tmp_path);
+                return;
+            }
+        }
+        index.touch(name, size);
+        drop(index);
+        self.evict();
+    }
+
+    /// Drop the chunks used the longest ago until within the size limit.
+    fn evict(&self) {
+        let Ok(mut index) = self.index.lock() else {
+            return;
+        };
+        while index.bytes > self.config.max_bytes {
+            let Some((_, oldest)) = index.by_last_use.first_key_value().map(|(k, v)| (*k, *v))
+            else {
+                break;
+            };
+            let _ = index.remove(&oldest);
+            if let Err(err) = fs::remove_file(self.path(&oldest)) {
+                warn!("Failed to evict the cached chunk {oldest:?}: {err}");
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ChunkCacheStats {
+        let (chunks, bytes) = self
+            .index
+            .lock()
+            .map(|index| (index.entries.len(), index.bytes))
+            .unwrap_or_default();
+        ChunkCacheStats {
+            dir: self.config.dir.clone(),
+            chunks,
+            bytes,
+            max_bytes: self.config.max_bytes,
+        }
+    }
+
+    pub(crate) fn clear(&self) -> io::Result<()> {
+        let Ok(mut index) = self.index.lock() else {
+            return Ok(());
+        };
+        let names: Vec<_> = index.entries.keys().copied().collect();
+        for name in names {
+            match fs::remove_file(self.path(&name)) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+            let _ = index.remove(&name);
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// The chunks in the disk cache and the space they take, if the cache is enabled, see
+    /// [`super::ClientConfig::chunk_cache`].
+    pub fn chunk_cache_stats(&self) -> Option<ChunkCacheStats> {
+        self.chunk_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Remove all of the chunks from the disk cache, if enabled.
+    pub fn chunk_cache_clear(&self) -> io::Result<()> {
+        match &self.chunk_cache {
+            Some(cache) => cache.clear(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &[u8]) -> Chunk {
+        Chunk::new(Bytes::copy_from_slice(content))
+    }
+
+    #[test]
+    fn chunks_are_kept_within_the_limit_and_checked_on_read() -> io::Result<()> {
+        let dir =
+            std::env::temp_dir().join(format!("autonomi_chunk_cache_{}", rand::random::<u64>()));
+        let config = ChunkCacheConfig {
+            dir: dir.clone(),
+            max_bytes: 10,
+        };
+        let cache = ChunkCache::open(config.clone())?;
+        let (first, second, third) = (chunk(b"first"), chunk(b"other"), chunk(b"third"));
+        cache.insert(&first);
+        cache.insert(&second);
+        assert_eq!(cache.get(first.name()), Some(first.clone()));
+
+        // The chunk used the longest ago goes first
+        cache.insert(&third);
+        assert_eq!(cache.get(second.name()), None);
+        assert_eq!(cache.stats().chunks, 2);
+        assert_eq!(cache.stats().bytes, 10);
+
+        // The cache is found again on disk
+        let reopened = ChunkCache::open(config)?;
+        assert_eq!(reopened.get(third.name()), Some(third.clone()));
+
+        // A corrupted chunk is dropped
+        fs::write(reopened.path(third.name()), b"corrupted")?;
+        assert_eq!(reopened.get(third.name()), None);
+        assert_eq!(reopened.stats().chunks, 1);
+
+        reopened.clear()?;
+        assert_eq!(reopened.stats().bytes, 0);
+        assert_eq!(reopened.get(first.name()), None);
+        let _ = fs::remove_dir_all(dir);
+        Ok(())
+    }
+}
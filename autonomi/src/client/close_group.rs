@@ -0,0 +1,140 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use ant_networking::{
+    target_arch::{Duration, Instant},
+    NetworkError,
+};
+use ant_protocol::NetworkAddress;
+use libp2p::PeerId;
+
+use super::Client;
+
+/// The number of close groups cached at most, the ones looked up the longest ago being dropped first.
+const MAX_CACHED_CLOSE_GROUPS: usize = 1024;
+
+/// The close groups recently looked up, see [`Client::get_closest_peers`].
+#[derive(Debug)]
+pub(crate) struct CloseGroupCache {
+    ttl: Duration,
+    groups: Mutex<HashMap<NetworkAddress, (Instant, Vec<PeerId>)>>,
+}
+
+impl CloseGroupCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            groups: Default::default(),
+        }
+    }
+
+    fn get(&self, addr: &NetworkAddress) -> Option<Vec<PeerId>> {
+        let groups = self.groups.lock().expect("lock poisoned");
+        groups
+            .get(addr)
+            .filter(|(looked_up_at, _)| looked_up_at.elapsed() < self.ttl)
+            .map(|(_, peers)| peers.clone())
+    }
+
+    fn insert(&self, addr: NetworkAddress, peers: Vec<PeerId>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut groups = self.groups.lock().expect("lock poisoned");
+        groups.retain(|_, (looked_up_at, _)| looked_up_at.elapsed() < self.ttl);
+        if groups.len() >= MAX_CACHED_CLOSE_GROUPS {
+            let oldest = groups
+                .iter()
+                .min_by_key(|(_, (looked_up_at, _))| *looked_up_at)
+                .map(|(addr, _)| addr.clone());
+            if let Some(oldest) = oldest {
+                let _ = groups.remove(&oldest);
+            }
+        }
+        let _ = groups.insert(addr, (Instant::now(), peers));
+    }
+}
+
+impl Default for CloseGroupCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60))
+    }
+}
+
+impl Client {
+    /// The peers closest to the address, sorted by their distance to it.
+    ///
+    /// The lookups are cached for [`super::ClientConfig::close_group_ttl`], as close groups change slowly, while a
+    /// lookup asks the network.
+    pub async fn get_closest_peers(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<Vec<PeerId>, NetworkError> {
+        if let Some(peers) = self.close_groups.get(&addr) {
+            trace!("Using the cached close group of {addr:?}");
+            return Ok(peers);
+        }
+
+        let peers = self
+            .network
+            .client_get_all_close_peers_in_range_or_close_group(&addr)
+            .await?;
+        debug!("Looked up the close group of {addr:?}: {peers:?}");
+        self.record_recent_addr(addr.clone());
+        self.close_groups.insert(addr, peers.clone());
+        Ok(peers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::storage::ChunkAddress;
+    use xor_name::XorName;
+
+    fn addr(byte: u8) -> NetworkAddress {
+        NetworkAddress::from_chunk_address(ChunkAddress::new(XorName([byte; 32])))
+    }
+
+    #[test]
+    fn close_groups_are_cached_until_they_expire() {
+        let peers = vec![PeerId::random(), PeerId::random()];
+
+        let cache = CloseGroupCache::new(Duration::from_secs(3600));
+        cache.insert(addr(1), peers.clone());
+        assert_eq!(cache.get(&addr(1)), Some(peers.clone()));
+        assert_eq!(cache.get(&addr(2)), None);
+
+        let disabled = CloseGroupCache::new(Duration::ZERO);
+        disabled.insert(addr(1), peers);
+        assert_eq!(disabled.get(&addr(1)), None);
+    }
+
+    #[test]
+    fn the_oldest_close_group_is_dropped_when_full() {
+        let cache = CloseGroupCache::new(Duration::from_secs(3600));
+        for index in 0..MAX_CACHED_CLOSE_GROUPS as u16 {
+            let mut name = [0; 32];
+            name[..2].copy_from_slice(&index.to_be_bytes());
+            cache.insert(
+                NetworkAddress::from_chunk_address(ChunkAddress::new(XorName(name))),
+                vec![],
+            );
+        }
+        cache.insert(addr(9), vec![]);
+
+        assert_eq!(
+            cache.groups.lock().expect("lock poisoned").len(),
+            MAX_CACHED_CLOSE_GROUPS
+        );
+        assert!(cache.get(&addr(9)).is_some());
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bls::{Ciphertext, PublicKey, SecretKey};
+use bytes::Bytes;
+
+use crate::client::payment::PaymentOption;
+use crate::client::{ClientEvent, UploadSummary};
+use crate::self_encryption::{encrypt, DataMapLevel};
+use crate::Client;
+use ant_evm::Amount;
+use ant_protocol::storage::Chunk;
+
+use super::*;
+
+/// Read access to encrypted data, granted to the holder of a key, see [`Client::data_grant_access`].
+///
+/// It holds the data map encrypted to the recipient's key, so it can be shared in the open: only the recipient can
+/// read the data with it, without needing the owner's key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessToken {
+    /// The address of the encrypted data the access is granted to.
+    pub addr: DataAddr,
+    data_map: Bytes,
+}
+
+impl AccessToken {
+    pub fn to_hex(&self) -> String {
+        hex::encode([self.addr.0.as_slice(), &self.data_map].concat())
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, hex::FromHexError> {
+        let bytes = hex::decode(hex)?;
+        if bytes.len() <= xor_name::XOR_NAME_LEN {
+            return Err(hex::FromHexError::InvalidStringLength);
+        }
+        let (addr, data_map) = bytes.split_at(xor_name::XOR_NAME_LEN);
+        let mut name = [0; xor_name::XOR_NAME_LEN];
+        name.copy_from_slice(addr);
+        Ok(Self {
+            addr: XorName(name),
+            data_map: Bytes::copy_from_slice(data_map),
+        })
+    }
+}
+
+/// The data map, encrypted to the key, as stored on the network or in the access tokens.
+fn encrypt_data_map(data_map: &DataMapChunk, key: &PublicKey) -> Bytes {
+    Bytes::from(key.encrypt(data_map.0.value()).to_bytes())
+}
+
+fn decrypt_data_map(encrypted: &[u8], key: &SecretKey) -> Result<DataMapChunk, GetError> {
+    let data_map = Ciphertext::from_bytes(encrypted)
+        .ok()
+        .and_then(|ciphertext| key.decrypt(&ciphertext))
+        // Decrypting with another key doesn't fail, but gives bytes that aren't a data map.
+        .filter(|data_map| rmp_serde::from_slice::<DataMapLevel>(data_map).is_ok())
+        .ok_or(GetError::DataMapDecryption)?;
+    Ok(DataMapChunk(Chunk::new(Bytes::from(data_map))))
+}
+
+impl Client {
+    /// Upload a piece of private data to the network, along with its data map encrypted to the owner's key, so that
+    /// it can be fetched back from anywhere with the key alone, see [`Client::data_get_encrypted`].
+    ///
+    /// Unlike [`Client::data_put`], the data doesn't rely on its data map being kept secret. To share it with a
+    /// group, the owner can use a key derived for it, e.g. with [`SecretKey::derive_child`], or grant access to
+    /// each member with [`Client::data_grant_access`].
+    ///
+    /// Returns the address of the encrypted data map.
+    pub async fn data_put_encrypted(
+        &self,
+        data: Bytes,
+        owner: &PublicKey,
+        payment_option: PaymentOption,
+    ) -> Result<DataAddr, PutError> {
+        let now = ant_networking::target_arch::Instant::now();
+        let (data_map_chunk, chunks) = encrypt(data, &self.chunking, self.compression)?;
+        debug!("Encryption took: {:.2?}", now.elapsed());
+
+        let encrypted_map_chunk =
+            Chunk::new(encrypt_data_map(&DataMapChunk(data_map_chunk), owner));
+        let map_xor_name = *encrypted_map_chunk.name();
+        info!("Uploading the encrypted data map chunk to the network at: {map_xor_name:?}");
+
+        // Pay for all chunks + the encrypted data map chunk, but the ones stored already
+        let all_chunks: Vec<_> = chunks
+            .iter()
+            .chain(std::iter::once(&encrypted_map_chunk))
+            .collect();
+        let stored = self.chunks_already_stored(&all_chunks).await;
+        let xor_names: Vec<_> = all_chunks
+            .iter()
+            .map(|chunk| *chunk.name())
+            .filter(|name| !stored.contains(name))
+            .collect();
+        info!("Paying for {} addresses", xor_names.len());
+        let receipt = self
+            .pay_for_content_addrs(xor_names.into_iter(), payment_option)
+            .await
+            .inspect_err(|err| error!("Error paying for data: {err:?}"))?;
+
+        debug!("Uploading {} chunks", all_chunks.len());
+        let record_count = all_chunks.len();
+        let mut failed_uploads = self.upload_chunks_with_retries(all_chunks, &receipt).await;
+
+        // Return the last chunk upload error
+        if let Some(last_chunk_fail) = failed_uploads.pop() {
+            tracing::error!(
+                "Error uploading chunk ({:?}): {:?}",
+                last_chunk_fail.0.address(),
+                last_chunk_fail.1
+            );
+            return Err(last_chunk_fail.1);
+        }
+
+        // Reporting
+        let tokens_spent = receipt
+            .values()
+            .map(|(_, cost)| cost.as_atto())
+            .sum::<Amount>();
+
+        let summary = UploadSummary {
+            record_count,
+            tokens_spent,
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
+
+        Ok(map_xor_name)
+    }
+
+    /// Fetch a blob of data uploaded with [`Client::data_put_encrypted`], decrypting its data map with the owner's
+    /// key.
+    pub async fn data_get_encrypted(
+        &self,
+        addr: DataAddr,
+        key: &SecretKey,
+    ) -> Result<Bytes, GetError> {
+        info!("Fetching encrypted data from Data Address: {addr:?}");
+        let data_map = self.encrypted_data_map(addr, key).await?;
+        self.data_get(data_map).await
+    }
+
+    /// Grant the holder of the `recipient` key read access to the data uploaded with
+    /// [`Client::data_put_encrypted`], returning the token for them to fetch it with
+    /// [`Client::data_get_with_access`].
+    ///
+    /// The access can't be revoked, as the recipient can keep the data map once they decrypted it.
+    pub async fn data_grant_access(
+        &self,
+        addr: DataAddr,
+        owner: &SecretKey,
+        recipient: &PublicKey,
+    ) -> Result<AccessToken, GetError> {
+        let data_map = self.encrypted_data_map(addr, owner).await?;
+        debug!("Granting access to the encrypted data at {addr:?}");
+        Ok(AccessToken {
+            addr,
+            data_map: encrypt_data_map(&data_map, recipient),
+        })
+    }
+
+    /// Fetch a blob of data with the token granting access to it, decrypting it with the recipient's key.
+    pub async fn data_get_with_access(
+        &self,
+        token: &AccessToken,
+        key: &SecretKey,
+    ) -> Result<Bytes, GetError> {
+        info!(
+            "Fetching encrypted data at {:?} with an access token",
+            token.addr
+        );
+        let data_map = decrypt_data_map(&token.data_map, key)?;
+        self.data_get(data_map).await
+    }
+
+    async fn encrypted_data_map(
+        &self,
+        addr: DataAddr,
+        key: &SecretKey,
+    ) -> Result<DataMapChunk, GetError> {
+        let encrypted_map_chunk = self.chunk_get(addr).await?;
+        decrypt_data_map(encrypted_map_chunk.value(), key)
+            .inspect_err(|_| error!("The data map at {addr:?} doesn't decrypt with the key"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_maps_only_decrypt_with_the_key_they_are_encrypted_to() {
+        let (data_map_chunk, _chunks) =
+            encrypt(Bytes::from(vec![7; 10 * 1024]), &Default::default(), None)
+                .expect("data to encrypt");
+        let data_map = DataMapChunk(data_map_chunk);
+        let owner = SecretKey::random();
+        let recipient = SecretKey::random();
+
+        let encrypted = encrypt_data_map(&data_map, &owner.public_key());
+        assert_eq!(
+            decrypt_data_map(&encrypted, &owner).expect("data map to decrypt"),
+            data_map
+        );
+        assert!(matches!(
+            decrypt_data_map(&encrypted, &recipient),
+            Err(GetError::DataMapDecryption)
+        ));
+
+        let token = AccessToken {
+            addr: XorName::random(&mut rand::thread_rng()),
+            data_map: encrypt_data_map(&data_map, &recipient.public_key()),
+        };
+        let token = AccessToken::from_hex(&token.to_hex()).expect("token to parse");
+        assert_eq!(
+            decrypt_data_map(&token.data_map, &recipient).expect("data map to decrypt"),
+            data_map
+        );
+        assert!(AccessToken::from_hex(&hex::encode(token.addr)).is_err());
+    }
+}
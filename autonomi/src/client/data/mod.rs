@@ -9,19 +9,25 @@
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::LazyLock;
 
-use ant_evm::{Amount, EvmWalletError};
+use ant_evm::{Amount, EvmWalletError, ProofOfPayment};
 use ant_networking::NetworkError;
+use ant_protocol::messages::PaymentRejection;
 use ant_protocol::storage::Chunk;
-use ant_protocol::NetworkAddress;
+use ant_protocol::{ErrorCode, NetworkAddress};
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use xor_name::XorName;
 
+use crate::client::cancellation::Cancelled;
 use crate::client::payment::PaymentOption;
 use crate::client::{ClientEvent, UploadSummary};
 use crate::{self_encryption::encrypt, Client};
 
+pub use crate::self_encryption::{ChunkingParams, Compression};
+
+pub mod encrypted;
 pub mod public;
+pub mod stream;
 
 /// Number of chunks to upload in parallel.
 ///
@@ -57,9 +63,6 @@ pub static CHUNK_DOWNLOAD_BATCH_SIZE: LazyLock<usize> = LazyLock::new(|| {
     batch_size
 });
 
-/// Number of retries to upload chunks.
-pub(crate) const RETRY_ATTEMPTS: usize = 3;
-
 /// Raw Data Address (points to a DataMap)
 pub type DataAddr = XorName;
 /// Raw Chunk Address (points to a [`Chunk`])
@@ -73,9 +76,9 @@ pub enum PutError {
     #[error("A network error occurred.")]
     Network(#[from] NetworkError),
     #[error("Error occurred during cost estimation.")]
-    CostError(#[from] CostError),
+    CostError(CostError),
     #[error("Error occurred during payment.")]
-    PayError(#[from] PayError),
+    PayError(PayError),
     #[error("Serialization error: {0}")]
     Serialization(String),
     #[error("A wallet error occurred.")]
@@ -86,6 +89,36 @@ pub enum PutError {
     PaymentUnexpectedlyInvalid(NetworkAddress),
     #[error("The payment proof contains no payees.")]
     PayeesMissing,
+    #[error("The payment for {address:?} was rejected by {} payees", rejections.len())]
+    PaymentRejected {
+        address: NetworkAddress,
+        proof: Box<ProofOfPayment>,
+        /// The payees' views of the payment, see [`crate::client::disputes`]
+        rejections: Vec<PaymentRejection>,
+    },
+    #[error("Failed to read the data to upload: {0}")]
+    Read(#[from] std::io::Error),
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+}
+
+// The cancellations are told as such at the top of the upload, rather than as the step they interrupted.
+impl From<CostError> for PutError {
+    fn from(err: CostError) -> Self {
+        match err {
+            CostError::Cancelled(cancelled) => Self::Cancelled(cancelled),
+            err => Self::CostError(err),
+        }
+    }
+}
+
+impl From<PayError> for PutError {
+    fn from(err: PayError) -> Self {
+        match err {
+            PayError::Cancelled(cancelled) => Self::Cancelled(cancelled),
+            err => Self::PayError(err),
+        }
+    }
 }
 
 /// Errors that can occur during the pay operation.
@@ -96,7 +129,18 @@ pub enum PayError {
     #[error("Failed to self-encrypt data.")]
     SelfEncryption(#[from] crate::self_encryption::Error),
     #[error("Cost error: {0:?}")]
-    Cost(#[from] CostError),
+    Cost(CostError),
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+}
+
+impl From<CostError> for PayError {
+    fn from(err: CostError) -> Self {
+        match err {
+            CostError::Cancelled(cancelled) => Self::Cancelled(cancelled),
+            err => Self::Cost(err),
+        }
+    }
 }
 
 /// Errors that can occur during the get operation.
@@ -106,12 +150,16 @@ pub enum GetError {
     InvalidDataMap(rmp_serde::decode::Error),
     #[error("Failed to decrypt data.")]
     Decryption(crate::self_encryption::Error),
+    #[error("Failed to decrypt the data map with the key.")]
+    DataMapDecryption,
     #[error("Failed to deserialize")]
     Deserialization(#[from] rmp_serde::decode::Error),
     #[error("General networking error: {0:?}")]
     Network(#[from] NetworkError),
     #[error("General protocol error: {0:?}")]
     Protocol(#[from] ant_protocol::Error),
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
 }
 
 /// Errors that can occur during the cost calculation.
@@ -129,11 +177,96 @@ pub enum CostError {
     Serialization(String),
     #[error("Market price error: {0:?}")]
     MarketPriceError(#[from] ant_evm::payment_vault::error::Error),
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+}
+
+impl PutError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::SelfEncryption(err) => err.code(),
+            Self::Network(err) => err.code(),
+            Self::CostError(err) => err.code(),
+            Self::PayError(err) => err.code(),
+            Self::Serialization(_) => ErrorCode::Serialization,
+            Self::Wallet(err) => ErrorCode::of_evm_error(err),
+            Self::VaultBadOwner => ErrorCode::InvalidInput,
+            Self::PaymentUnexpectedlyInvalid(_) | Self::PaymentRejected { .. } => {
+                ErrorCode::PaymentRejected
+            }
+            Self::PayeesMissing => ErrorCode::Internal,
+            Self::Read(_) => ErrorCode::Io,
+            Self::Cancelled(_) => ErrorCode::Cancelled,
+        }
+    }
+
+    /// Whether the upload may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+impl PayError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::EvmWalletError(err) => ErrorCode::of_evm_wallet_error(err),
+            Self::SelfEncryption(err) => err.code(),
+            Self::Cost(err) => err.code(),
+            Self::Cancelled(_) => ErrorCode::Cancelled,
+        }
+    }
+
+    /// Whether the payment may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+impl GetError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::InvalidDataMap(_) | Self::Deserialization(_) => ErrorCode::Serialization,
+            Self::Decryption(err) => err.code(),
+            Self::DataMapDecryption => ErrorCode::Encryption,
+            Self::Network(err) => err.code(),
+            Self::Protocol(err) => err.code(),
+            Self::Cancelled(_) => ErrorCode::Cancelled,
+        }
+    }
+
+    /// Whether the download may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+impl CostError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::SelfEncryption(err) => err.code(),
+            Self::CouldNotGetStoreQuote(_) | Self::NotEnoughNodeQuotes(..) => {
+                ErrorCode::QuoteUnavailable
+            }
+            Self::CouldNotGetStoreCosts(err) => err.code(),
+            Self::Serialization(_) => ErrorCode::Serialization,
+            Self::MarketPriceError(err) => ErrorCode::of_payment_vault_error(err),
+            Self::Cancelled(_) => ErrorCode::Cancelled,
+        }
+    }
+
+    /// Whether the cost estimation may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
 }
 
 /// Private data on the network can be accessed with this
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct DataMapChunk(Chunk);
+pub struct DataMapChunk(pub(crate) Chunk);
 
 impl DataMapChunk {
     pub fn to_hex(&self) -> String {
@@ -210,11 +343,18 @@ impl Client {
         payment_option: PaymentOption,
     ) -> Result<DataMapChunk, PutError> {
         let now = ant_networking::target_arch::Instant::now();
-        let (data_map_chunk, chunks) = encrypt(data)?;
+        let (data_map_chunk, chunks) = encrypt(data, &self.chunking, self.compression)?;
         debug!("Encryption took: {:.2?}", now.elapsed());
 
-        // Pay for all chunks
-        let xor_names: Vec<_> = chunks.iter().map(|chunk| *chunk.name()).collect();
+        // Pay for all chunks, but the ones stored already
+        let stored = self
+            .chunks_already_stored(&chunks.iter().collect::<Vec<_>>())
+            .await;
+        let xor_names: Vec<_> = chunks
+            .iter()
+            .map(|chunk| *chunk.name())
+            .filter(|name| !stored.contains(name))
+            .collect();
         info!("Paying for {} addresses", xor_names.len());
         let receipt = self
             .pay_for_content_addrs(xor_names.into_iter(), payment_option)
@@ -241,20 +381,16 @@ impl Client {
         let record_count = chunks.len();
 
         // Reporting
-        if let Some(channel) = self.client_event_sender.as_ref() {
-            let tokens_spent = receipt
-                .values()
-                .map(|(_, cost)| cost.as_atto())
-                .sum::<Amount>();
-
-            let summary = UploadSummary {
-                record_count,
-                tokens_spent,
-            };
-            if let Err(err) = channel.send(ClientEvent::UploadComplete(summary)).await {
-                error!("Failed to send client event: {err:?}");
-            }
-        }
+        let tokens_spent = receipt
+            .values()
+            .map(|(_, cost)| cost.as_atto())
+            .sum::<Amount>();
+
+        let summary = UploadSummary {
+            record_count,
+            tokens_spent,
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
 
         Ok(DataMapChunk(data_map_chunk))
     }
@@ -271,4 +407,27 @@ mod tests {
         let data_map2 = DataMapChunk::from_hex(&hex).expect("Failed to decode hex");
         assert_eq!(data_map, data_map2);
     }
+
+    #[test]
+    fn errors_keep_the_code_of_their_cause() {
+        let quorum_failed = PutError::Network(NetworkError::RecordNotStoredByNodes(
+            NetworkAddress::from_chunk_address(ant_protocol::storage::ChunkAddress::new(
+                XorName::from_content(b"chunk"),
+            )),
+        ));
+        assert_eq!(quorum_failed.code(), ErrorCode::RecordNotStored);
+        assert!(quorum_failed.is_retryable());
+
+        let no_funds = PutError::from(PayError::EvmWalletError(
+            EvmWalletError::InsufficientTokensForQuotes(Amount::from(1), Amount::from(2)),
+        ));
+        assert_eq!(no_funds.code(), ErrorCode::InsufficientFunds);
+        assert!(!no_funds.is_retryable());
+
+        let no_quotes = PutError::from(CostError::NotEnoughNodeQuotes(XorName::default(), 2, 5));
+        assert_eq!(no_quotes.code(), ErrorCode::QuoteUnavailable);
+        assert!(no_quotes.is_retryable());
+
+        assert_eq!(GetError::DataMapDecryption.code(), ErrorCode::Encryption);
+    }
 }
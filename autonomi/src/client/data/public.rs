@@ -11,7 +11,6 @@ use libp2p::kad::Quorum;
 use std::collections::HashSet;
 
 use crate::client::payment::{PaymentOption, Receipt};
-use crate::client::utils::process_tasks_with_max_concurrency;
 use crate::client::{ClientEvent, UploadSummary};
 use crate::{self_encryption::encrypt, Client};
 use ant_evm::{Amount, AttoTokens};
@@ -45,7 +44,7 @@ impl Client {
         payment_option: PaymentOption,
     ) -> Result<DataAddr, PutError> {
         let now = ant_networking::target_arch::Instant::now();
-        let (data_map_chunk, chunks) = encrypt(data)?;
+        let (data_map_chunk, chunks) = encrypt(data, &self.chunking, self.compression)?;
         let data_map_addr = data_map_chunk.address();
         debug!("Encryption took: {:.2?}", now.elapsed());
         info!("Uploading datamap chunk to the network at: {data_map_addr:?}");
@@ -56,6 +55,10 @@ impl Client {
         for chunk in &chunks {
             xor_names.push(*chunk.name());
         }
+        let stored = self
+            .chunks_already_stored(&chunks.iter().chain([&data_map_chunk]).collect::<Vec<_>>())
+            .await;
+        xor_names.retain(|name| !stored.contains(name));
 
         // Pay for all chunks + data map chunk
         info!("Paying for {} addresses", xor_names.len());
@@ -90,20 +93,16 @@ impl Client {
         let record_count = chunks.len() + 1;
 
         // Reporting
-        if let Some(channel) = self.client_event_sender.as_ref() {
-            let tokens_spent = receipt
-                .values()
-                .map(|(_proof, price)| price.as_atto())
-                .sum::<Amount>();
-
-            let summary = UploadSummary {
-                record_count,
-                tokens_spent,
-            };
-            if let Err(err) = channel.send(ClientEvent::UploadComplete(summary)).await {
-                error!("Failed to send client event: {err:?}");
-            }
-        }
+        let tokens_spent = receipt
+            .values()
+            .map(|(_proof, price)| price.as_atto())
+            .sum::<Amount>();
+
+        let summary = UploadSummary {
+            record_count,
+            tokens_spent,
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
 
         Ok(map_xor_name)
     }
@@ -112,25 +111,46 @@ impl Client {
     pub async fn chunk_get(&self, addr: ChunkAddr) -> Result<Chunk, GetError> {
         info!("Getting chunk: {addr:?}");
 
-        let key = NetworkAddress::from_chunk_address(ChunkAddress::new(addr)).to_record_key();
+        if let Some(chunk) = self.chunk_cache.as_ref().and_then(|cache| cache.get(&addr)) {
+            debug!("Got chunk {addr:?} from the chunk cache");
+            self.send_client_event(ClientEvent::ChunkFetched {
+                address: addr,
+                bytes: chunk.value.len(),
+            });
+            return Ok(chunk);
+        }
+
+        let network_addr = NetworkAddress::from_chunk_address(ChunkAddress::new(addr));
+        let key = network_addr.to_record_key();
+        self.record_recent_addr(network_addr);
         debug!("Fetching chunk from network at: {key:?}");
-        let get_cfg = GetRecordCfg {
+        let get_cfg = self.get_cfg.apply(GetRecordCfg {
             get_quorum: Quorum::One,
-            retry_strategy: None,
+            retry_strategy: Some(self.retry_strategy),
             target_record: None,
             expected_holders: HashSet::new(),
             is_register: false,
-        };
+        });
 
         let record = self
-            .network
-            .get_record_from_network(key, &get_cfg)
+            .cancellable(async {
+                Ok::<_, GetError>(self.network.get_record_from_network(key, &get_cfg).await?)
+            })
             .await
-            .inspect_err(|err| error!("Error fetching chunk: {err:?}"))?;
+            .inspect_err(|err| error!("Error fetching chunk: {err:?}"))?
+            .record;
         let header = RecordHeader::from_record(&record)?;
 
         if let RecordKind::Chunk = header.kind {
             let chunk: Chunk = try_deserialize_record(&record)?;
+            if let Some(cache) = &self.chunk_cache {
+                cache.insert(&chunk);
+            }
+            self.bandwidth.throttle_download(chunk.value.len()).await;
+            self.send_client_event(ClientEvent::ChunkFetched {
+                address: addr,
+                bytes: chunk.value.len(),
+            });
             Ok(chunk)
         } else {
             error!(
@@ -144,7 +164,7 @@ impl Client {
     /// Get the estimated cost of storing a piece of data.
     pub async fn data_cost(&self, data: Bytes) -> Result<AttoTokens, CostError> {
         let now = ant_networking::target_arch::Instant::now();
-        let (data_map_chunk, chunks) = encrypt(data)?;
+        let (data_map_chunk, chunks) = encrypt(data, &self.chunking, self.compression)?;
 
         debug!("Encryption took: {:.2?}", now.elapsed());
 
@@ -176,7 +196,7 @@ impl Client {
         Ok(total_cost)
     }
 
-    // Upload chunks and retry failed uploads up to `RETRY_ATTEMPTS` times.
+    // Upload chunks and retry failed uploads up to `upload_cfg.reput_attempts` times.
     pub(crate) async fn upload_chunks_with_retries<'a>(
         &self,
         mut chunks: Vec<&'a Chunk>,
@@ -204,8 +224,9 @@ impl Client {
                         .map_err(|err| (chunk, err))
                 });
             }
-            let uploads =
-                process_tasks_with_max_concurrency(upload_tasks, *CHUNK_UPLOAD_BATCH_SIZE).await;
+            let uploads = self
+                .process_uploads_with_adaptive_concurrency(upload_tasks)
+                .await;
 
             // Check for errors.
             let total_uploads = uploads.len();
@@ -220,19 +241,26 @@ impl Client {
                 return vec![];
             }
 
-            // Max retries reached.
-            if current_attempt > RETRY_ATTEMPTS {
+            // Max retries reached, or no further attempt to make once cancelled.
+            if current_attempt > self.upload_cfg.reput_attempts || self.is_cancelled() {
                 return uploads_failed;
             }
 
             tracing::info!(
-                "Retrying putting {} failed chunks (attempt {current_attempt}/3)",
-                uploads_failed.len()
+                "Retrying putting {} failed chunks (attempt {current_attempt}/{})",
+                uploads_failed.len(),
+                self.upload_cfg.reput_attempts
             );
 
             // Re-iterate over the failed chunks
             chunks = uploads_failed.into_iter().map(|(chunk, _)| chunk).collect();
             current_attempt += 1;
+            for chunk in &chunks {
+                self.send_client_event(ClientEvent::RetryScheduled {
+                    address: *chunk.name(),
+                    attempt: current_attempt,
+                });
+            }
         }
     }
 }
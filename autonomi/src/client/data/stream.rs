@@ -0,0 +1,353 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_evm::{Amount, EvmWallet};
+use ant_protocol::storage::Chunk;
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use self_encryption::{decrypt_range, ChunkInfo, DataMap, EncryptedChunk};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{DataAddr, DataMapChunk, GetError, PutError};
+use crate::client::{ClientEvent, UploadSummary};
+use crate::self_encryption::{Compression, SourceDataMaps, StreamEncryptor};
+use crate::Client;
+
+/// Number of bytes to read from a stream being uploaded at a time.
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+impl Client {
+    /// Stream a blob of (private) data from the network.
+    ///
+    /// The chunks are fetched in order, at most [`crate::client::bandwidth::BandwidthConfig::max_concurrent_downloads`] ahead of the consumer, and each one is
+    /// decrypted as soon as it's next in line. This allows large files to be written to disk or served over the wire
+    /// without holding them in memory.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use autonomi::Client;
+    /// use futures::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::init().await?;
+    /// # let data_map = todo!();
+    /// let mut stream = Box::pin(client.data_stream(data_map).await?);
+    /// while let Some(bytes) = stream.next().await {
+    ///     let _bytes = bytes?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn data_stream(
+        &self,
+        data_map: DataMapChunk,
+    ) -> Result<impl Stream<Item = Result<Bytes, GetError>> + '_, GetError> {
+        info!(
+            "Streaming private data from Data Map {:?}",
+            data_map.0.address()
+        );
+        let source = self.resolve_data_maps(data_map.0.value()).await?;
+        self.stream_from_source(source, 0)
+    }
+
+    /// Stream a blob of data from the network. See [`Client::data_stream`].
+    pub async fn data_stream_public(
+        &self,
+        addr: DataAddr,
+    ) -> Result<impl Stream<Item = Result<Bytes, GetError>> + '_, GetError> {
+        info!("Streaming data from Data Address: {addr:?}");
+        let data_map_chunk = self.chunk_get(addr).await?;
+        let source = self.resolve_data_maps(data_map_chunk.value()).await?;
+        self.stream_from_source(source, 0)
+    }
+
+    /// Stream a blob of data from the network, from the chunk holding the byte at `offset` on. Only the chunks from
+    /// there are fetched, so a range of big data can be served without fetching all of it. Returns the offset of the
+    /// first byte streamed, which can be before `offset`, down to `0` for compressed data that can't be sought.
+    pub async fn data_stream_public_from(
+        &self,
+        addr: DataAddr,
+        offset: u64,
+    ) -> Result<(u64, impl Stream<Item = Result<Bytes, GetError>> + '_), GetError> {
+        info!("Streaming data from Data Address: {addr:?}, from offset {offset}");
+        let data_map_chunk = self.chunk_get(addr).await?;
+        let source = self.resolve_data_maps(data_map_chunk.value()).await?;
+        let (skip, start) = seek_point(&source, offset);
+        Ok((start, self.stream_from_source(source, skip)?))
+    }
+
+    /// Fetch the chunks of the data maps in order with a bounded look ahead, yielding each decrypted, and if need be
+    /// decompressed, one. The first `skip` chunks are left out, e.g. when they have been fetched by an earlier download.
+    pub(crate) fn stream_from_source(
+        &self,
+        source: SourceDataMaps,
+        skip: usize,
+    ) -> Result<impl Stream<Item = Result<Bytes, GetError>> + '_, GetError> {
+        debug!("Streaming encrypted data chunks from {source:?}, skipping {skip}");
+        let SourceDataMaps {
+            data_maps,
+            compression,
+        } = source;
+        let mut decompressor = compression
+            .map(Compression::decompressor)
+            .transpose()
+            .map_err(GetError::Decryption)?;
+
+        let infos: Vec<_> = data_maps
+            .iter()
+            .enumerate()
+            .flat_map(|(segment, data_map)| {
+                data_map
+                    .infos()
+                    .into_iter()
+                    .map(move |info| (segment, info))
+            })
+            .skip(skip)
+            .collect();
+        let stream = stream::iter(infos)
+            .map(move |(segment, info)| async move {
+                let chunk = self.chunk_get(info.dst_hash).await.inspect_err(|err| {
+                    error!("Error fetching chunk {:?}: {err:?}", info.dst_hash)
+                })?;
+                Ok::<_, GetError>((segment, info, chunk.value))
+            })
+            .buffered(self.bandwidth.download_concurrency())
+            .map(move |fetched| {
+                let (segment, info, content) = fetched?;
+                let decrypted = decrypt_chunk(&data_maps[segment], &info, content)?;
+                match decompressor.as_mut() {
+                    Some(decompressor) => decompressor
+                        .decompress(&decrypted)
+                        .map_err(GetError::Decryption),
+                    None => Ok(decrypted),
+                }
+            });
+        Ok(stream)
+    }
+
+    /// Upload (private) data read from `reader` to the network, without holding all of it in memory.
+    ///
+    /// The data is self-encrypted as it's read, and its chunks are paid for and uploaded in batches of
+    /// [`crate::client::bandwidth::BandwidthConfig::max_concurrent_uploads`]. This allows piping data straight from
+    /// other processes or network sources. Like [`Client::data_put`], the [`DataMapChunk`] is returned, not uploaded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use autonomi::Client;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::init().await?;
+    /// # let wallet = todo!();
+    /// let data_map = client.data_put_stream(&b"Hello, World"[..], &wallet).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn data_put_stream(
+        &self,
+        reader: impl AsyncRead + Unpin,
+        wallet: &EvmWallet,
+    ) -> Result<DataMapChunk, PutError> {
+        let (data_map_chunk, summary) = self.upload_from_reader(reader, wallet).await?;
+        self.send_client_event(ClientEvent::UploadComplete(summary));
+        Ok(DataMapChunk(data_map_chunk))
+    }
+
+    /// Upload data read from `reader` to the network, see [`Client::data_put_stream`]. The data map is uploaded too,
+    /// making the data publicly accessible at the returned address.
+    pub async fn data_put_stream_public(
+        &self,
+        reader: impl AsyncRead + Unpin,
+        wallet: &EvmWallet,
+    ) -> Result<DataAddr, PutError> {
+        let (data_map_chunk, mut summary) = self.upload_from_reader(reader, wallet).await?;
+        let map_xor_name = *data_map_chunk.address().xorname();
+        info!("Uploading datamap chunk to the network at: {map_xor_name:?}");
+        summary.tokens_spent += self
+            .pay_and_upload_chunks(std::slice::from_ref(&data_map_chunk), wallet)
+            .await?;
+        summary.record_count += 1;
+
+        self.send_client_event(ClientEvent::UploadComplete(summary));
+        Ok(map_xor_name)
+    }
+
+    /// Self-encrypt the data read from `reader`, paying for and uploading its chunks batch by batch. Returns the
+    /// data map chunk, which isn't uploaded.
+    async fn upload_from_reader(
+        &self,
+        mut reader: impl AsyncRead + Unpin,
+        wallet: &EvmWallet,
+    ) -> Result<(Chunk, UploadSummary), PutError> {
+        let mut encryptor = StreamEncryptor::new(self.chunking, self.compression)?;
+        let mut summary = UploadSummary {
+            record_count: 0,
+            tokens_spent: Amount::ZERO,
+        };
+        let mut batch = vec![];
+        let mut buf = vec![0; READ_BUFFER_SIZE];
+
+        loop {
+            let read = reader.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            batch.extend(encryptor.push(&buf[..read])?);
+
+            if batch.len() >= self.bandwidth.upload_concurrency() {
+                summary.tokens_spent += self.pay_and_upload_chunks(&batch, wallet).await?;
+                summary.record_count += batch.len();
+                batch.clear();
+            }
+        }
+
+        let (data_map_chunk, last_chunks) = encryptor.finish()?;
+        batch.extend(last_chunks);
+        summary.tokens_spent += self.pay_and_upload_chunks(&batch, wallet).await?;
+        summary.record_count += batch.len();
+
+        debug!(
+            "Uploaded {} chunks read from the stream",
+            summary.record_count
+        );
+        Ok((data_map_chunk, summary))
+    }
+
+    /// Pay for and upload the chunks, returning the amount spent.
+    async fn pay_and_upload_chunks(
+        &self,
+        chunks: &[Chunk],
+        wallet: &EvmWallet,
+    ) -> Result<Amount, PutError> {
+        let stored = self
+            .chunks_already_stored(&chunks.iter().collect::<Vec<_>>())
+            .await;
+        let names: Vec<_> = chunks
+            .iter()
+            .map(|chunk| *chunk.name())
+            .filter(|name| !stored.contains(name))
+            .collect();
+        info!("Paying for {} addresses", names.len());
+        let receipt = self
+            .pay(names.into_iter(), wallet)
+            .await
+            .inspect_err(|err| error!("Error paying for data: {err:?}"))?;
+
+        debug!("Uploading {} chunks", chunks.len());
+        let mut failed_uploads = self
+            .upload_chunks_with_retries(chunks.iter().collect(), &receipt)
+            .await;
+
+        // Return the last chunk upload error
+        if let Some(last_chunk_fail) = failed_uploads.pop() {
+            tracing::error!(
+                "Error uploading chunk ({:?}): {:?}",
+                last_chunk_fail.0.address(),
+                last_chunk_fail.1
+            );
+            return Err(last_chunk_fail.1);
+        }
+
+        Ok(receipt.values().map(|(_, cost)| cost.as_atto()).sum())
+    }
+}
+
+/// The number of chunks of the source data before the one holding the byte at `offset`, and the offset the chunk
+/// starts at. Compressed data is only decompressed from its start.
+fn seek_point(source: &SourceDataMaps, offset: u64) -> (usize, u64) {
+    if source.compression.is_some() {
+        return (0, 0);
+    }
+    let mut skip = 0;
+    let mut start = 0;
+    for info in source.data_maps.iter().flat_map(DataMap::infos) {
+        let end = start + info.src_size as u64;
+        if end > offset {
+            break;
+        }
+        skip += 1;
+        start = end;
+    }
+    (skip, start)
+}
+
+/// Decrypt a single chunk of the data map. The keys of a chunk derive from the source hashes of its neighbours, which
+/// are all held by the data map, so chunks can be decrypted one by one.
+fn decrypt_chunk(data_map: &DataMap, info: &ChunkInfo, content: Bytes) -> Result<Bytes, GetError> {
+    let encrypted_chunk = EncryptedChunk {
+        index: info.index,
+        content,
+    };
+    decrypt_range(data_map, &[encrypted_chunk], 0, info.src_size).map_err(|e| {
+        error!("Error decrypting chunk {:?}: {e:?}", info.dst_hash);
+        GetError::Decryption(crate::self_encryption::Error::SelfEncryption(e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, RngCore};
+    use xor_name::XorName;
+
+    #[test]
+    fn chunks_decrypt_one_by_one() -> Result<(), GetError> {
+        let mut data = vec![0u8; 5 * 1024 * 1024];
+        thread_rng().fill_bytes(&mut data);
+        let data = Bytes::from(data);
+
+        let (data_map, encrypted_chunks) = self_encryption::encrypt(data.clone())
+            .map_err(|e| GetError::Decryption(crate::self_encryption::Error::SelfEncryption(e)))?;
+
+        let mut streamed = Vec::with_capacity(data.len());
+        for info in data_map.infos() {
+            let content = encrypted_chunks
+                .iter()
+                .find(|chunk| XorName::from_content(&chunk.content) == info.dst_hash)
+                .map(|chunk| chunk.content.clone())
+                .expect("Chunk of the data map to have been produced");
+            streamed.extend_from_slice(&decrypt_chunk(&data_map, &info, content)?);
+        }
+
+        assert_eq!(Bytes::from(streamed), data);
+        Ok(())
+    }
+
+    #[test]
+    fn streams_seek_to_the_chunk_holding_the_offset() -> Result<(), GetError> {
+        let mut data = vec![0u8; 5 * 1024 * 1024];
+        thread_rng().fill_bytes(&mut data);
+        let (data_map, _) = self_encryption::encrypt(Bytes::from(data))
+            .map_err(|e| GetError::Decryption(crate::self_encryption::Error::SelfEncryption(e)))?;
+        let sizes: Vec<u64> = data_map
+            .infos()
+            .iter()
+            .map(|info| info.src_size as u64)
+            .collect();
+        let source = SourceDataMaps {
+            data_maps: vec![data_map],
+            compression: None,
+        };
+
+        assert_eq!(seek_point(&source, 0), (0, 0));
+        assert_eq!(seek_point(&source, sizes[0] - 1), (0, 0));
+        assert_eq!(seek_point(&source, sizes[0]), (1, sizes[0]));
+        assert_eq!(
+            seek_point(&source, sizes[0] + sizes[1] + 1),
+            (2, sizes[0] + sizes[1])
+        );
+
+        let compressed = SourceDataMaps {
+            compression: Some(Compression::Zstd),
+            ..source
+        };
+        assert_eq!(seek_point(&compressed, sizes[0]), (0, 0));
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Client;
+use ant_evm::payment_vault::verify_data_payment;
+use ant_evm::{Amount, EncodedPeerId, EvmError, ProofOfPayment, QuoteHash, RewardsAddress};
+pub use ant_protocol::messages::{PaymentRejection, PaymentRejectionReason};
+use ant_protocol::NetworkAddress;
+use futures::future::join_all;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+use xor_name::XorName;
+
+/// A payee of a record, with its quote as paid, the payment found on chain for it and its view of the payment if
+/// it rejected it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayeeEvidence {
+    pub payee: EncodedPeerId,
+    pub quote_hash: QuoteHash,
+    pub rewards_address: RewardsAddress,
+    /// The amount found paid for the quote on chain, or why it couldn't be verified
+    pub paid_on_chain: Result<Amount, String>,
+    pub rejection: Option<PaymentRejection>,
+}
+
+/// Everything about the payment of a record, as seen by the client, the chain and the payees, to find out why it
+/// was rejected, e.g. to report it or to pay again for the record, see [`Client::dispute_bundle`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisputeBundle {
+    pub address: NetworkAddress,
+    /// Hash of the proof, see `ProofOfPayment::hash`
+    pub payment_hash: XorName,
+    pub proof: ProofOfPayment,
+    pub payees: Vec<PayeeEvidence>,
+    pub created_at: SystemTime,
+}
+
+impl DisputeBundle {
+    fn new(
+        address: NetworkAddress,
+        proof: &ProofOfPayment,
+        paid_on_chain: Vec<Result<Amount, String>>,
+        rejections: Vec<PaymentRejection>,
+    ) -> Result<Self, EvmError> {
+        let payees = proof
+            .peer_quotes
+            .iter()
+            .zip(paid_on_chain)
+            .map(|((payee, quote), paid_on_chain)| {
+                let node = payee.to_peer_id().ok().map(NetworkAddress::from_peer);
+                PayeeEvidence {
+                    payee: payee.clone(),
+                    quote_hash: quote.hash(),
+                    rewards_address: quote.rewards_address,
+                    paid_on_chain,
+                    rejection: rejections
+                        .iter()
+                        .find(|rejection| Some(&rejection.node) == node.as_ref())
+                        .cloned(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            address,
+            payment_hash: proof.hash()?,
+            proof: proof.clone(),
+            payees,
+            created_at: SystemTime::now(),
+        })
+    }
+
+    /// The payees that rejected the payment, the ones to get new quotes from to pay again.
+    pub fn rejected_by(&self) -> Vec<PeerId> {
+        self.payees
+            .iter()
+            .filter(|evidence| evidence.rejection.is_some())
+            .filter_map(|evidence| evidence.payee.to_peer_id().ok())
+            .collect()
+    }
+}
+
+impl Client {
+    /// Ask the payees of the record for their view of its payment, if they rejected the record put with it.
+    ///
+    /// The nodes only keep their latest rejections, so this is to be asked soon after the put.
+    pub async fn payment_rejections(
+        &self,
+        address: NetworkAddress,
+        proof: &ProofOfPayment,
+    ) -> Result<Vec<PaymentRejection>, EvmError> {
+        Ok(self
+            .network
+            .get_payment_rejections(address, proof.hash()?, &proof.payees())
+            .await)
+    }
+
+    /// Gather the evidence of the payment of a record: its quotes, how each of them was paid on chain, and the
+    /// payees' views of the payment if they rejected it, see [`Client::payment_rejections`].
+    pub async fn dispute_bundle(
+        &self,
+        address: NetworkAddress,
+        proof: &ProofOfPayment,
+    ) -> Result<DisputeBundle, EvmError> {
+        let rejections = self.payment_rejections(address.clone(), proof).await?;
+        let paid_on_chain = join_all(proof.peer_quotes.iter().map(|(_, quote)| async move {
+            let quote_hash = quote.hash();
+            let payment = (
+                quote_hash,
+                quote.quoting_metrics.clone(),
+                quote.rewards_address,
+            );
+            verify_data_payment(&self.evm_network, vec![quote_hash], vec![payment])
+                .await
+                .map_err(|err| err.to_string())
+        }))
+        .await;
+        info!(
+            "Gathered the payment evidence of {address:?}, rejected by {} payees",
+            rejections.len()
+        );
+
+        DisputeBundle::new(address, proof, paid_on_chain, rejections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_evm::PaymentQuote;
+    use ant_protocol::storage::ChunkAddress;
+
+    #[test]
+    fn the_rejections_are_told_along_with_the_payees_they_are_from() {
+        let xor_name = XorName::from_content(b"chunk");
+        let address = NetworkAddress::from_chunk_address(ChunkAddress::new(xor_name));
+        let payees: Vec<_> = (0..3).map(|_| PeerId::random()).collect();
+        let proof = ProofOfPayment {
+            peer_quotes: payees
+                .iter()
+                .map(|peer| {
+                    (
+                        EncodedPeerId::from(*peer),
+                        PaymentQuote::test_dummy(xor_name),
+                    )
+                })
+                .collect(),
+        };
+        let rejection = PaymentRejection {
+            node: NetworkAddress::from_peer(payees[1]),
+            address: address.clone(),
+            payment_hash: proof.hash().expect("Failed to hash the proof"),
+            reason: PaymentRejectionReason::NotPaidOnChain("payment invalid".to_string()),
+            quote_hashes: vec![proof.peer_quotes[1].1.hash()],
+            rewards_address: RewardsAddress::ZERO,
+            timestamp: SystemTime::now(),
+        };
+        let paid_on_chain = vec![
+            Ok(Amount::from(10)),
+            Err("payment invalid".to_string()),
+            Ok(Amount::from(10)),
+        ];
+
+        let bundle = DisputeBundle::new(address, &proof, paid_on_chain, vec![rejection.clone()])
+            .expect("Failed to gather the bundle");
+        assert_eq!(bundle.rejected_by(), vec![payees[1]]);
+        assert_eq!(bundle.payees[1].rejection, Some(rejection));
+        assert!(bundle.payees[1].paid_on_chain.is_err());
+        assert!(bundle.payees[0].rejection.is_none());
+
+        // The bundle is kept or sent as is.
+        let bytes = rmp_serde::to_vec(&bundle).expect("bundle to serialize");
+        let decoded: DisputeBundle = rmp_serde::from_slice(&bytes).expect("bundle to deserialize");
+        assert_eq!(decoded, bundle);
+    }
+}
@@ -44,6 +44,10 @@ pub struct Metadata {
     pub modified: u64,
     /// File size in bytes
     pub size: u64,
+    /// SHA-256 checksum of the file content, checked along with the size when the file is downloaded. `None` for
+    /// the files archived without one.
+    #[serde(default)]
+    pub checksum: Option<[u8; 32]>,
 }
 
 impl Metadata {
@@ -59,6 +63,7 @@ impl Metadata {
             created: now,
             modified: now,
             size,
+            checksum: None,
         }
     }
 }
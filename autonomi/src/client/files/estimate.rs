@@ -0,0 +1,153 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::archive_public::PublicArchive;
+use super::fs::FileCostError;
+use super::fs_public::metadata_from_entry;
+use crate::client::files::get_relative_file_path_from_abs_file_and_folder_path;
+use crate::client::quote::StoreQuote;
+use crate::client::Client;
+use crate::self_encryption::encrypt;
+use ant_evm::{Amount, AttoTokens, RewardsAddress};
+use bytes::Bytes;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use xor_name::XorName;
+
+/// The expected cost of uploading a file or a directory, see [`Client::estimate_cost`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostEstimate {
+    /// The total to pay for the upload.
+    pub total: AttoTokens,
+    /// The number of chunks to pay for, including the data maps and the archive.
+    pub chunks_to_pay: usize,
+    /// The number of chunks already stored on the network, uploaded for free.
+    pub chunks_already_stored: usize,
+    /// How the total splits between the rewards addresses of the nodes quoting to store the chunks.
+    pub payees: HashMap<RewardsAddress, AttoTokens>,
+}
+
+impl CostEstimate {
+    fn from_quotes(quotes: &StoreQuote, chunks: usize) -> Self {
+        let mut payees: HashMap<RewardsAddress, Amount> = HashMap::new();
+        for (_hash, rewards_address, price) in quotes.payments() {
+            if !price.is_zero() {
+                *payees.entry(rewards_address).or_default() += price;
+            }
+        }
+        Self {
+            total: AttoTokens::from_atto(quotes.price()),
+            chunks_to_pay: quotes.len(),
+            chunks_already_stored: chunks.saturating_sub(quotes.len()),
+            payees: payees
+                .into_iter()
+                .map(|(address, amount)| (address, AttoTokens::from_atto(amount)))
+                .collect(),
+        }
+    }
+}
+
+impl Client {
+    /// Estimate the cost of uploading a file or a directory as public data, without paying for it.
+    ///
+    /// The files are self-encrypted locally and the nodes are asked to quote for the resulting chunks, the data maps
+    /// and the archive. The chunks already stored on the network are free. The payments go to the nodes storing the
+    /// chunks, there are no network royalties on top.
+    pub async fn estimate_cost(&self, path: &Path) -> Result<CostEstimate, FileCostError> {
+        let mut archive = PublicArchive::new();
+        let mut content_addrs = HashSet::new();
+
+        for entry in walkdir::WalkDir::new(path) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let file_path = entry.path().to_path_buf();
+            let data = Bytes::from(tokio::fs::read(&file_path).await?);
+            let map_xor_name = self.content_addrs_of(data, &mut content_addrs)?;
+            debug!("Estimating the cost of {file_path:?}, with its data map at {map_xor_name:?}");
+
+            let archive_path =
+                get_relative_file_path_from_abs_file_and_folder_path(&file_path, path);
+            archive.add_file(archive_path, map_xor_name, metadata_from_entry(&entry));
+        }
+        let archive_bytes = Bytes::from(rmp_serde::to_vec(&archive)?);
+        let _archive_addr = self.content_addrs_of(archive_bytes, &mut content_addrs)?;
+
+        info!(
+            "Estimating the cost of {} chunks for {path:?}",
+            content_addrs.len()
+        );
+        let chunks = content_addrs.len();
+        let quotes = self.get_store_quotes(content_addrs.into_iter()).await?;
+        let estimate = CostEstimate::from_quotes(&quotes, chunks);
+        debug!("Cost estimate for {path:?}: {estimate:?}");
+        Ok(estimate)
+    }
+
+    /// Self-encrypt the data, adding the addresses of its chunks and of its data map, which is returned.
+    fn content_addrs_of(
+        &self,
+        data: Bytes,
+        content_addrs: &mut HashSet<XorName>,
+    ) -> Result<XorName, FileCostError> {
+        let (data_map_chunk, chunks) = encrypt(data, &self.chunking, self.compression)?;
+        let map_xor_name = *data_map_chunk.name();
+        content_addrs.extend(chunks.iter().map(|chunk| *chunk.name()));
+        let _ = content_addrs.insert(map_xor_name);
+        Ok(map_xor_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::quote::QuoteForAddress;
+    use ant_evm::PaymentQuote;
+    use libp2p::PeerId;
+
+    #[test]
+    fn the_total_is_split_between_the_paid_nodes() {
+        let quote_from = |rewards_address: RewardsAddress| PaymentQuote {
+            rewards_address,
+            ..PaymentQuote::zero()
+        };
+        let (node_a, node_b) = (
+            RewardsAddress::repeat_byte(1),
+            RewardsAddress::repeat_byte(2),
+        );
+        let quote_for_address = |price_b: u64| {
+            QuoteForAddress(vec![
+                (PeerId::random(), quote_from(node_a), Amount::from(3)),
+                (PeerId::random(), quote_from(node_b), Amount::from(price_b)),
+                (
+                    PeerId::random(),
+                    quote_from(RewardsAddress::repeat_byte(3)),
+                    Amount::ZERO,
+                ),
+            ])
+        };
+        let quotes = StoreQuote(HashMap::from([
+            (XorName([1; 32]), quote_for_address(5)),
+            (XorName([2; 32]), quote_for_address(7)),
+        ]));
+
+        let estimate = CostEstimate::from_quotes(&quotes, 3);
+        assert_eq!(estimate.total, AttoTokens::from_u64(18));
+        assert_eq!(estimate.chunks_to_pay, 2);
+        assert_eq!(estimate.chunks_already_stored, 1);
+        assert_eq!(
+            estimate.payees,
+            HashMap::from([
+                (node_a, AttoTokens::from_u64(6)),
+                (node_b, AttoTokens::from_u64(12)),
+            ])
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+};
+
+use ant_evm::EvmWallet;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use xor_name::XorName;
+
+use super::archive::Metadata;
+use crate::{
+    client::data::{DataAddr, GetError, PutError},
+    Client,
+};
+
+/// The address of a folder on the network. Points to a [`Folder`].
+pub type FolderAddr = XorName;
+
+#[derive(Error, Debug)]
+pub enum FolderError {
+    #[error("Entry not found in folder: {0}")]
+    NotFound(PathBuf),
+    #[error("Entry is not a folder: {0}")]
+    NotAFolder(PathBuf),
+    #[error("Invalid folder path: {0}")]
+    InvalidPath(PathBuf),
+    #[error("Failed to fetch folder: {0}")]
+    Get(#[from] GetError),
+    #[error("Failed to upload folder: {0}")]
+    Put(#[from] PutError),
+}
+
+/// An entry of a [`Folder`], either a file or a nested folder.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FolderEntry {
+    /// A public file, the data map of which is uploaded to the network.
+    File {
+        data_addr: DataAddr,
+        metadata: Metadata,
+    },
+    /// A nested folder, uploaded to the network on its own.
+    Folder { folder_addr: FolderAddr },
+}
+
+/// A single level of a directory tree, mapping entry names to files and to the addresses of nested folders.
+///
+/// Unlike [`crate::client::files::archive_public::PublicArchive`], which holds every file path of a tree in one
+/// flat map, each nested folder is stored separately. Changing a single file only re-uploads the folders on its path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct Folder {
+    entries: BTreeMap<String, FolderEntry>,
+}
+
+impl Folder {
+    /// Create a new empty folder
+    /// Note that this does not upload the folder to the network
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Add a file to the folder, replacing any entry of the same name
+    /// Note that this does not upload the folder to the network
+    pub fn add_file(&mut self, name: String, data_addr: DataAddr, metadata: Metadata) {
+        debug!("Added a new file to the folder, name: {name:?}");
+        let _ = self.entries.insert(
+            name,
+            FolderEntry::File {
+                data_addr,
+                metadata,
+            },
+        );
+    }
+
+    /// Add a nested folder to the folder, replacing any entry of the same name
+    /// Note that this does not upload the folder to the network
+    pub fn add_folder(&mut self, name: String, folder_addr: FolderAddr) {
+        debug!("Added a new nested folder to the folder, name: {name:?}");
+        let _ = self
+            .entries
+            .insert(name, FolderEntry::Folder { folder_addr });
+    }
+
+    /// Remove an entry from the folder
+    /// Note that this does not upload the folder to the network
+    pub fn remove(&mut self, name: &str) -> Result<FolderEntry, FolderError> {
+        self.entries
+            .remove(name)
+            .ok_or(FolderError::NotFound(PathBuf::from(name)))
+    }
+
+    /// Get an entry of the folder
+    pub fn get(&self, name: &str) -> Option<&FolderEntry> {
+        self.entries.get(name)
+    }
+
+    /// List the entries of the folder, ordered by name
+    pub fn list(&self) -> impl Iterator<Item = (&String, &FolderEntry)> {
+        self.entries.iter()
+    }
+
+    /// Deserialize from bytes.
+    pub fn from_bytes(data: Bytes) -> Result<Folder, rmp_serde::decode::Error> {
+        let folder: Folder = rmp_serde::from_slice(&data[..])?;
+
+        Ok(folder)
+    }
+
+    /// Serialize to bytes.
+    pub fn to_bytes(&self) -> Result<Bytes, rmp_serde::encode::Error> {
+        let folder_serialized = rmp_serde::to_vec(&self)?;
+        let folder_serialized = Bytes::from(folder_serialized);
+
+        Ok(folder_serialized)
+    }
+}
+
+impl Client {
+    /// Fetch a folder from the network
+    pub async fn folder_get(&self, addr: FolderAddr) -> Result<Folder, GetError> {
+        let data = self.data_get_public(addr).await?;
+        Ok(Folder::from_bytes(data)?)
+    }
+
+    /// Upload a folder to the network
+    pub async fn folder_put(
+        &self,
+        folder: &Folder,
+        wallet: &EvmWallet,
+    ) -> Result<FolderAddr, PutError> {
+        let bytes = folder
+            .to_bytes()
+            .map_err(|e| PutError::Serialization(format!("Failed to serialize folder: {e:?}")))?;
+        let result = self.data_put_public(bytes, wallet.into()).await;
+        debug!("Uploaded folder {folder:?} to the network and the address is {result:?}");
+        result
+    }
+
+    /// List the entries of the folder at `path`, relative to the root folder. An empty path lists the root itself.
+    pub async fn folder_list(
+        &self,
+        root: FolderAddr,
+        path: &Path,
+    ) -> Result<Vec<(String, FolderEntry)>, FolderError> {
+        let dirs = path_components(path)?;
+        let mut folder = self.folder_get(root).await?;
+        for (depth, dir) in dirs.iter().enumerate() {
+            let folder_addr = match folder.get(dir) {
+                Some(FolderEntry::Folder { folder_addr }) => *folder_addr,
+                Some(FolderEntry::File { .. }) => {
+                    return Err(FolderError::NotAFolder(dirs[..=depth].iter().collect()))
+                }
+                None => return Err(FolderError::NotFound(dirs[..=depth].iter().collect())),
+            };
+            folder = self.folder_get(folder_addr).await?;
+        }
+
+        Ok(folder
+            .entries
+            .into_iter()
+            .collect::<Vec<(String, FolderEntry)>>())
+    }
+
+    /// Add a file at `path` within the tree of the root folder, creating the missing parent folders. Returns the
+    /// address of the new root folder.
+    ///
+    /// Only the folders on the path of the file are uploaded again, the rest of the tree is left untouched.
+    pub async fn folder_add_file(
+        &self,
+        root: FolderAddr,
+        path: &Path,
+        data_addr: DataAddr,
+        metadata: Metadata,
+        wallet: &EvmWallet,
+    ) -> Result<FolderAddr, FolderError> {
+        let (dirs, name) = split_file_path(path)?;
+        let mut folders = self.folders_on_path(root, &dirs, true).await?;
+        if let Some(parent) = folders.last_mut() {
+            parent.add_file(name, data_addr, metadata);
+        }
+        self.put_folders_on_path(folders, &dirs, wallet).await
+    }
+
+    /// Remove the file or folder at `path` within the tree of the root folder. Returns the address of the new root
+    /// folder.
+    ///
+    /// Only the folders on the path of the entry are uploaded again, the rest of the tree is left untouched.
+    pub async fn folder_remove(
+        &self,
+        root: FolderAddr,
+        path: &Path,
+        wallet: &EvmWallet,
+    ) -> Result<FolderAddr, FolderError> {
+        let (dirs, name) = split_file_path(path)?;
+        let mut folders = self.folders_on_path(root, &dirs, false).await?;
+        if let Some(parent) = folders.last_mut() {
+            let _ = parent
+                .remove(&name)
+                .map_err(|_| FolderError::NotFound(path.to_path_buf()))?;
+        }
+        self.put_folders_on_path(folders, &dirs, wallet).await
+    }
+
+    /// Fetch the root folder and every folder down the given directories, in order. Missing folders are created
+    /// empty if `create_missing` is set.
+    async fn folders_on_path(
+        &self,
+        root: FolderAddr,
+        dirs: &[String],
+        create_missing: bool,
+    ) -> Result<Vec<Folder>, FolderError> {
+        let mut folders = vec![self.folder_get(root).await?];
+        for (depth, dir) in dirs.iter().enumerate() {
+            let parent = &folders[depth];
+            let folder = match parent.get(dir) {
+                Some(FolderEntry::Folder { folder_addr }) => self.folder_get(*folder_addr).await?,
+                Some(FolderEntry::File { .. }) => {
+                    return Err(FolderError::NotAFolder(dirs[..=depth].iter().collect()))
+                }
+                None if create_missing => Folder::new(),
+                None => return Err(FolderError::NotFound(dirs[..=depth].iter().collect())),
+            };
+            folders.push(folder);
+        }
+        Ok(folders)
+    }
+
+    /// Upload the updated folders bottom up, linking each one into its parent. Returns the address of the root.
+    async fn put_folders_on_path(
+        &self,
+        mut folders: Vec<Folder>,
+        dirs: &[String],
+        wallet: &EvmWallet,
+    ) -> Result<FolderAddr, FolderError> {
+        let mut folder_addr = None;
+        for depth in (0..folders.len()).rev() {
+            if let Some(child_addr) = folder_addr {
+                folders[depth].add_folder(dirs[depth].clone(), child_addr);
+            }
+            folder_addr = Some(self.folder_put(&folders[depth], wallet).await?);
+        }
+        folder_addr.ok_or(FolderError::InvalidPath(dirs.iter().collect()))
+    }
+}
+
+/// Split a relative path into its directory names.
+fn path_components(path: &Path) -> Result<Vec<String>, FolderError> {
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => name
+                .to_str()
+                .map(str::to_string)
+                .ok_or(FolderError::InvalidPath(path.to_path_buf())),
+            _ => Err(FolderError::InvalidPath(path.to_path_buf())),
+        })
+        .collect()
+}
+
+/// Split a relative path into its parent directory names and the name of the entry itself.
+fn split_file_path(path: &Path) -> Result<(Vec<String>, String), FolderError> {
+    let mut dirs = path_components(path)?;
+    let name = dirs
+        .pop()
+        .ok_or(FolderError::InvalidPath(path.to_path_buf()))?;
+    Ok((dirs, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folder_entries_and_paths() -> Result<(), FolderError> {
+        let mut folder = Folder::new();
+        let file_addr = XorName::random(&mut rand::thread_rng());
+        let nested_addr = XorName::random(&mut rand::thread_rng());
+        folder.add_file("b.txt".to_string(), file_addr, Metadata::new_with_size(1));
+        folder.add_folder("a".to_string(), nested_addr);
+
+        let names = folder
+            .list()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b.txt"]);
+
+        let bytes = folder.to_bytes().expect("Folder to serialize");
+        let deserialized = Folder::from_bytes(bytes).expect("Folder to deserialize");
+        assert_eq!(deserialized, folder);
+
+        assert_eq!(
+            folder.remove("a")?,
+            FolderEntry::Folder {
+                folder_addr: nested_addr
+            }
+        );
+        assert!(matches!(folder.remove("a"), Err(FolderError::NotFound(_))));
+
+        assert_eq!(
+            split_file_path(Path::new("a/b/c.txt"))?,
+            (vec!["a".to_string(), "b".to_string()], "c.txt".to_string())
+        );
+        assert!(matches!(
+            split_file_path(Path::new("../c.txt")),
+            Err(FolderError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            split_file_path(Path::new("")),
+            Err(FolderError::InvalidPath(_))
+        ));
+        Ok(())
+    }
+}
@@ -14,14 +14,20 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::archive::{PrivateArchive, PrivateArchiveAccess};
-use crate::client::data::{CostError, DataMapChunk, GetError, PutError};
+use super::archive::{Metadata, PrivateArchive, PrivateArchiveAccess};
+use crate::client::data::{CostError, DataMapChunk, GetError, PayError, PutError};
 use crate::client::files::get_relative_file_path_from_abs_file_and_folder_path;
 use crate::client::utils::process_tasks_with_max_concurrency;
 use crate::client::Client;
 use ant_evm::EvmWallet;
+use ant_protocol::ErrorCode;
 use bytes::Bytes;
-use std::{path::PathBuf, sync::LazyLock};
+use sha2::{Digest, Sha256};
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tokio::io::AsyncReadExt;
 
 /// Number of files to upload in parallel.
 ///
@@ -49,6 +55,10 @@ pub enum UploadError {
     IoError(#[from] std::io::Error),
     #[error("Failed to upload file")]
     PutError(#[from] PutError),
+    #[error("Failed to pay for file")]
+    PayError(#[from] PayError),
+    #[error("The source file of upload session {0} has changed")]
+    SessionSourceChanged(String),
     #[error("Failed to fetch file")]
     GetError(#[from] GetError),
     #[error("Failed to serialize")]
@@ -64,6 +74,16 @@ pub enum DownloadError {
     GetError(#[from] GetError),
     #[error("IO failure")]
     IoError(#[from] std::io::Error),
+    #[error("Failed to serialize download checkpoint")]
+    Serialization(#[from] rmp_serde::encode::Error),
+    #[error("Downloaded file {path:?} is {got} bytes, while {expected} bytes were uploaded")]
+    SizeMismatch {
+        path: PathBuf,
+        expected: u64,
+        got: u64,
+    },
+    #[error("Downloaded file {0:?} doesn't match the checksum it was uploaded with")]
+    ChecksumMismatch(PathBuf),
 }
 
 /// Errors that can occur during the file cost calculation.
@@ -81,19 +101,72 @@ pub enum FileCostError {
     WalkDir(#[from] walkdir::Error),
 }
 
+impl UploadError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::WalkDir(_) | Self::IoError(_) => ErrorCode::Io,
+            Self::PutError(err) => err.code(),
+            Self::PayError(err) => err.code(),
+            Self::SessionSourceChanged(_) => ErrorCode::InvalidInput,
+            Self::GetError(err) => err.code(),
+            Self::Serialization(_) | Self::Deserialization(_) => ErrorCode::Serialization,
+        }
+    }
+
+    /// Whether the upload may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+impl DownloadError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::GetError(err) => err.code(),
+            Self::IoError(_) => ErrorCode::Io,
+            Self::Serialization(_) => ErrorCode::Serialization,
+            // The file was uploaded corrupted, or the wrong data map was given
+            Self::SizeMismatch { .. } | Self::ChecksumMismatch(_) => ErrorCode::InvalidRecord,
+        }
+    }
+
+    /// Whether the download may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
+impl FileCostError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Cost(err) => err.code(),
+            Self::IoError(_) | Self::WalkDir(_) => ErrorCode::Io,
+            Self::Serialization(_) => ErrorCode::Serialization,
+            Self::SelfEncryption(err) => err.code(),
+        }
+    }
+
+    /// Whether the cost estimation may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+}
+
 impl Client {
     /// Download a private file from network to local file system
+    ///
+    /// The file is streamed to disk as its chunks are fetched, see [`Client::data_stream`]. An interrupted download
+    /// resumes from the chunks already written on the next attempt.
     pub async fn file_download(
         &self,
         data_access: DataMapChunk,
         to_dest: PathBuf,
     ) -> Result<(), DownloadError> {
-        let data = self.data_get(data_access).await?;
-        if let Some(parent) = to_dest.parent() {
-            tokio::fs::create_dir_all(parent).await?;
-            debug!("Created parent directories for {to_dest:?}");
-        }
-        tokio::fs::write(to_dest.clone(), data).await?;
+        let source = self.resolve_data_maps(data_access.0.value()).await?;
+        self.download_source_to_file(source, &to_dest).await?;
         debug!("Downloaded file to {to_dest:?}");
         Ok(())
     }
@@ -105,8 +178,10 @@ impl Client {
         to_dest: PathBuf,
     ) -> Result<(), DownloadError> {
         let archive = self.archive_get(archive_access).await?;
-        for (path, addr, _meta) in archive.iter() {
-            self.file_download(addr.clone(), to_dest.join(path)).await?;
+        for (path, addr, meta) in archive.iter() {
+            let to_dest = to_dest.join(path);
+            self.file_download(addr.clone(), to_dest.clone()).await?;
+            verify_file(&to_dest, meta).await?;
         }
         debug!("Downloaded directory to {to_dest:?}");
         Ok(())
@@ -118,6 +193,29 @@ impl Client {
         &self,
         dir_path: PathBuf,
         wallet: &EvmWallet,
+    ) -> Result<PrivateArchive, UploadError> {
+        self.dir_upload_inner(dir_path, wallet, None).await
+    }
+
+    /// Same as [`Client::dir_upload`], but each file is uploaded with an [`super::upload_session::UploadSession`] persisted under
+    /// `sessions_dir`, see [`Client::file_upload_with_session`].
+    ///
+    /// Uploading the directory again after an interruption carries on from the stored and paid chunks of its files.
+    pub async fn dir_upload_with_sessions(
+        &self,
+        dir_path: PathBuf,
+        wallet: &EvmWallet,
+        sessions_dir: &Path,
+    ) -> Result<PrivateArchive, UploadError> {
+        self.dir_upload_inner(dir_path, wallet, Some(sessions_dir))
+            .await
+    }
+
+    async fn dir_upload_inner(
+        &self,
+        dir_path: PathBuf,
+        wallet: &EvmWallet,
+        sessions_dir: Option<&Path>,
     ) -> Result<PrivateArchive, UploadError> {
         info!("Uploading directory as private: {dir_path:?}");
         let start = tokio::time::Instant::now();
@@ -133,7 +231,14 @@ impl Client {
             let metadata = super::fs_public::metadata_from_entry(&entry);
             let path = entry.path().to_path_buf();
             upload_tasks.push(async move {
-                let file = self.file_upload(path.clone(), wallet).await;
+                let metadata = with_checksum(metadata, &path).await;
+                let file = match sessions_dir {
+                    Some(sessions_dir) => {
+                        self.file_upload_with_session(path.clone(), wallet, sessions_dir)
+                            .await
+                    }
+                    None => self.file_upload(path.clone(), wallet).await,
+                };
                 (path, metadata, file)
             });
         }
@@ -179,7 +284,7 @@ impl Client {
 
     /// Upload a private file to the network.
     /// Reads file, splits into chunks, uploads chunks, uploads datamap, returns [`DataMapChunk`] (pointing to the datamap)
-    async fn file_upload(
+    pub(crate) async fn file_upload(
         &self,
         path: PathBuf,
         wallet: &EvmWallet,
@@ -195,3 +300,88 @@ impl Client {
         Ok(addr)
     }
 }
+
+/// The SHA-256 checksum of the content of the file, see [`Metadata::checksum`].
+pub(crate) async fn file_checksum(path: &Path) -> std::io::Result<[u8; 32]> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// The metadata of the file, along with the checksum of its content unless it fails to read.
+pub(crate) async fn with_checksum(mut metadata: Metadata, path: &Path) -> Metadata {
+    metadata.checksum = file_checksum(path)
+        .await
+        .inspect_err(|err| warn!("Failed to compute the checksum of {path:?}: {err}"))
+        .ok();
+    metadata
+}
+
+/// Check the downloaded file against the size and checksum it was archived with, if it was archived with a
+/// checksum, for the corrupted files and the files fetched from a wrong address not to go unnoticed.
+pub(crate) async fn verify_file(path: &Path, metadata: &Metadata) -> Result<(), DownloadError> {
+    let Some(expected) = metadata.checksum else {
+        return Ok(());
+    };
+    let size = tokio::fs::metadata(path).await?.len();
+    if size != metadata.size {
+        error!(
+            "Downloaded file {path:?} is {size} bytes, while {} bytes were uploaded",
+            metadata.size
+        );
+        return Err(DownloadError::SizeMismatch {
+            path: path.to_path_buf(),
+            expected: metadata.size,
+            got: size,
+        });
+    }
+    if file_checksum(path).await? != expected {
+        error!("Downloaded file {path:?} doesn't match the checksum it was uploaded with");
+        return Err(DownloadError::ChecksumMismatch(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn downloaded_files_are_checked_against_their_checksum() -> eyre::Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("autonomi_checksum_{}", rand::random::<u64>()));
+        tokio::fs::write(&path, b"original content").await?;
+        let metadata = with_checksum(Metadata::new_with_size(16), &path).await;
+        assert!(metadata.checksum.is_some());
+        verify_file(&path, &metadata).await?;
+
+        // Files archived without a checksum aren't checked.
+        verify_file(&path, &Metadata::new_with_size(0)).await?;
+
+        tokio::fs::write(&path, b"tampered content").await?;
+        assert!(matches!(
+            verify_file(&path, &metadata).await,
+            Err(DownloadError::ChecksumMismatch(_))
+        ));
+        tokio::fs::write(&path, b"short").await?;
+        assert!(matches!(
+            verify_file(&path, &metadata).await,
+            Err(DownloadError::SizeMismatch {
+                expected: 16,
+                got: 5,
+                ..
+            })
+        ));
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
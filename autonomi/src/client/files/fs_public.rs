@@ -16,7 +16,7 @@ use crate::client::Client;
 use ant_evm::EvmWallet;
 use ant_networking::target_arch::{Duration, SystemTime};
 use bytes::Bytes;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 impl Client {
     /// Download file from network to local file system
@@ -25,12 +25,9 @@ impl Client {
         data_addr: DataAddr,
         to_dest: PathBuf,
     ) -> Result<(), DownloadError> {
-        let data = self.data_get_public(data_addr).await?;
-        if let Some(parent) = to_dest.parent() {
-            tokio::fs::create_dir_all(parent).await?;
-            debug!("Created parent directories {parent:?} for {to_dest:?}");
-        }
-        tokio::fs::write(to_dest.clone(), data).await?;
+        let data_map_chunk = self.chunk_get(data_addr).await?;
+        let source = self.resolve_data_maps(data_map_chunk.value()).await?;
+        self.download_source_to_file(source, &to_dest).await?;
         debug!("Downloaded file to {to_dest:?} from the network address {data_addr:?}");
         Ok(())
     }
@@ -43,8 +40,10 @@ impl Client {
     ) -> Result<(), DownloadError> {
         let archive = self.archive_get_public(archive_addr).await?;
         debug!("Downloaded archive for the directory from the network at {archive_addr:?}");
-        for (path, addr, _meta) in archive.iter() {
-            self.file_download_public(*addr, to_dest.join(path)).await?;
+        for (path, addr, meta) in archive.iter() {
+            let to_dest = to_dest.join(path);
+            self.file_download_public(*addr, to_dest.clone()).await?;
+            verify_file(&to_dest, meta).await?;
         }
         debug!(
             "All files in the directory downloaded to {:?} from the network address {:?}",
@@ -78,6 +77,7 @@ impl Client {
             let metadata = metadata_from_entry(&entry);
             let path = entry.path().to_path_buf();
             upload_tasks.push(async move {
+                let metadata = with_checksum(metadata, &path).await;
                 let file = self.file_upload_public(path.clone(), wallet).await;
                 (path, metadata, file)
             });
@@ -124,7 +124,7 @@ impl Client {
 
     /// Upload a file to the network.
     /// Reads file, splits into chunks, uploads chunks, uploads datamap, returns DataAddr (pointing to the datamap)
-    async fn file_upload_public(
+    pub(crate) async fn file_upload_public(
         &self,
         path: PathBuf,
         wallet: &EvmWallet,
@@ -141,45 +141,12 @@ impl Client {
     }
 
     /// Get the cost to upload a file/dir to the network.
-    /// quick and dirty implementation, please refactor once files are cleanly implemented
-    pub async fn file_cost(&self, path: &PathBuf) -> Result<ant_evm::AttoTokens, FileCostError> {
-        let mut archive = PublicArchive::new();
-        let mut total_cost = ant_evm::Amount::ZERO;
-
-        for entry in walkdir::WalkDir::new(path) {
-            let entry = entry?;
-
-            if !entry.file_type().is_file() {
-                continue;
-            }
-
-            let path = entry.path().to_path_buf();
-            tracing::info!("Cost for file: {path:?}");
-
-            let data = tokio::fs::read(&path).await?;
-            let file_bytes = Bytes::from(data);
-            let file_cost = self.data_cost(file_bytes.clone()).await?;
-
-            total_cost += file_cost.as_atto();
-
-            // re-do encryption to get the correct map xorname here
-            // this code needs refactor
-            let now = ant_networking::target_arch::Instant::now();
-            let (data_map_chunk, _) = crate::self_encryption::encrypt(file_bytes)?;
-            tracing::debug!("Encryption took: {:.2?}", now.elapsed());
-            let map_xor_name = *data_map_chunk.address().xorname();
-
-            let metadata = metadata_from_entry(&entry);
-            archive.add_file(path, map_xor_name, metadata);
-        }
-
-        let root_serialized = rmp_serde::to_vec(&archive)?;
-
-        let archive_cost = self.data_cost(Bytes::from(root_serialized)).await?;
-
-        total_cost += archive_cost.as_atto();
-        debug!("Total cost for the directory: {total_cost:?}");
-        Ok(total_cost.into())
+    ///
+    /// See [`Client::estimate_cost`] for the chunks it pays for and who gets paid.
+    pub async fn file_cost(&self, path: &Path) -> Result<ant_evm::AttoTokens, FileCostError> {
+        let estimate = self.estimate_cost(path).await?;
+        debug!("Total cost for the directory: {:?}", estimate.total);
+        Ok(estimate.total)
     }
 }
 
@@ -198,6 +165,7 @@ pub(crate) fn metadata_from_entry(entry: &walkdir::DirEntry) -> Metadata {
                 created: 0,
                 modified: 0,
                 size: 0,
+                checksum: None,
             };
         }
     };
@@ -231,5 +199,6 @@ pub(crate) fn metadata_from_entry(entry: &walkdir::DirEntry) -> Metadata {
         created,
         modified,
         size: fs_metadata.len(),
+        checksum: None,
     }
 }
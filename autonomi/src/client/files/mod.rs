@@ -5,10 +5,23 @@ pub mod archive;
 pub mod archive_public;
 #[cfg(feature = "fs")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub mod estimate;
+pub mod folders;
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
 pub mod fs;
 #[cfg(feature = "fs")]
 #[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
 pub mod fs_public;
+#[cfg(feature = "fs")]
+mod partial_download;
+pub mod repair;
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub mod sync;
+#[cfg(feature = "fs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fs")))]
+pub mod upload_session;
 
 #[cfg(feature = "fs")]
 pub(crate) fn get_relative_file_path_from_abs_file_and_folder_path(
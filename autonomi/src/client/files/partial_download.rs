@@ -0,0 +1,183 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::fs::DownloadError;
+use crate::client::Client;
+use crate::self_encryption::SourceDataMaps;
+use futures::StreamExt;
+use self_encryption::{ChunkInfo, DataMap};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use xor_name::XorName;
+
+/// Suffix of the file holding the decrypted prefix of an ongoing download.
+const PARTIAL_FILE_SUFFIX: &str = "partial";
+/// Suffix of the file holding the checkpoint of an ongoing download.
+const CHECKPOINT_FILE_SUFFIX: &str = "partial.state";
+
+/// The progress of a download, persisted next to the partial file after every chunk written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DownloadCheckpoint {
+    /// Identifies the data map being downloaded, so that a partial file of other content is never resumed.
+    data_map_id: XorName,
+    /// The number of chunks decrypted and written to the partial file, in order.
+    chunks_written: usize,
+}
+
+impl Client {
+    /// Download the source data of the data maps to the destination file, resuming from the partial file of an earlier
+    /// interrupted download if there is one. Compressed data is always downloaded from the start, as decompression
+    /// can't pick up half way.
+    ///
+    /// The chunks are decrypted in order and appended to `<dest>.partial`, with the number of chunks written
+    /// checkpointed in `<dest>.partial.state`. The partial file is moved to the destination once complete.
+    pub(crate) async fn download_source_to_file(
+        &self,
+        source: SourceDataMaps,
+        to_dest: &Path,
+    ) -> Result<(), DownloadError> {
+        if let Some(parent) = to_dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+            debug!("Created parent directories for {to_dest:?}");
+        }
+
+        let partial_path = with_suffix(to_dest, PARTIAL_FILE_SUFFIX);
+        let checkpoint_path = with_suffix(to_dest, CHECKPOINT_FILE_SUFFIX);
+        let infos: Vec<_> = source.data_maps.iter().flat_map(DataMap::infos).collect();
+        let data_map_id = data_map_id(&infos);
+
+        let checkpoint = match tokio::fs::read(&checkpoint_path).await {
+            Ok(bytes) if partial_path.exists() && source.compression.is_none() => {
+                rmp_serde::from_slice(&bytes).ok()
+            }
+            _ => None,
+        };
+        let (mut chunks_written, offset) = resume_point(checkpoint, data_map_id, &infos);
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&partial_path)
+            .await?;
+        // Drop anything written past the last checkpoint, it could be a partially written chunk.
+        file.set_len(offset).await?;
+        let _ = file.seek(std::io::SeekFrom::Start(offset)).await?;
+        if chunks_written > 0 {
+            info!(
+                "Resuming download to {to_dest:?} from chunk {chunks_written}/{}",
+                infos.len()
+            );
+        }
+
+        let mut stream = std::pin::pin!(self.stream_from_source(source, chunks_written)?);
+        while let Some(bytes) = stream.next().await {
+            file.write_all(&bytes?).await?;
+            file.flush().await?;
+
+            chunks_written += 1;
+            let checkpoint = DownloadCheckpoint {
+                data_map_id,
+                chunks_written,
+            };
+            tokio::fs::write(&checkpoint_path, rmp_serde::to_vec(&checkpoint)?).await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+
+        tokio::fs::rename(&partial_path, to_dest).await?;
+        tokio::fs::remove_file(&checkpoint_path).await?;
+        Ok(())
+    }
+}
+
+/// Append a suffix to the file name of the path, e.g. `file.txt` to `file.txt.partial`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(".");
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn data_map_id(infos: &[ChunkInfo]) -> XorName {
+    let dst_hashes = infos
+        .iter()
+        .map(|info| info.dst_hash.0.as_slice())
+        .collect::<Vec<_>>();
+    XorName::from_content_parts(&dst_hashes)
+}
+
+/// Returns the number of chunks already written and the length of the decrypted prefix they make up, if the
+/// checkpoint belongs to the data map. Otherwise the download starts over.
+fn resume_point(
+    checkpoint: Option<DownloadCheckpoint>,
+    data_map_id: XorName,
+    infos: &[ChunkInfo],
+) -> (usize, u64) {
+    match checkpoint {
+        Some(checkpoint)
+            if checkpoint.data_map_id == data_map_id
+                && checkpoint.chunks_written <= infos.len() =>
+        {
+            let offset = infos[..checkpoint.chunks_written]
+                .iter()
+                .map(|info| info.src_size as u64)
+                .sum();
+            (checkpoint.chunks_written, offset)
+        }
+        _ => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use rand::{thread_rng, RngCore};
+
+    #[test]
+    fn resume_point_matches_the_data_map() {
+        let mut data = vec![0u8; 4 * 1024 * 1024];
+        thread_rng().fill_bytes(&mut data);
+        let (data_map, _) =
+            self_encryption::encrypt(Bytes::from(data)).expect("Data to be encrypted");
+        let infos = data_map.infos();
+        let data_map_id = data_map_id(&infos);
+
+        assert_eq!(resume_point(None, data_map_id, &infos), (0, 0));
+
+        let checkpoint = DownloadCheckpoint {
+            data_map_id,
+            chunks_written: 2,
+        };
+        let expected_offset = (infos[0].src_size + infos[1].src_size) as u64;
+        assert_eq!(
+            resume_point(Some(checkpoint.clone()), data_map_id, &infos),
+            (2, expected_offset)
+        );
+
+        let other_data_map = DownloadCheckpoint {
+            data_map_id: XorName::random(&mut thread_rng()),
+            ..checkpoint
+        };
+        assert_eq!(
+            resume_point(Some(other_data_map), data_map_id, &infos),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn suffix_is_appended_to_the_file_name() {
+        assert_eq!(
+            with_suffix(Path::new("dir/file.txt"), PARTIAL_FILE_SUFFIX),
+            PathBuf::from("dir/file.txt.partial")
+        );
+    }
+}
@@ -0,0 +1,480 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::archive::{PrivateArchive, PrivateArchiveAccess};
+use super::archive_public::{ArchiveAddr, PublicArchive};
+use crate::client::data::{ChunkAddr, DataAddr, DataMapChunk, GetError, PayError};
+use crate::client::utils::process_tasks_with_max_concurrency;
+use crate::client::Client;
+use crate::self_encryption::DataMapLevel;
+use ant_evm::{AttoTokens, EvmWallet};
+use ant_networking::{GetRecordCfg, GetRecordError, GetRecordOk, NetworkError, PutRecordCfg};
+use ant_protocol::{
+    messages::{Query, QueryResponse, Request, Response},
+    storage::{
+        try_deserialize_record, try_serialize_record, Chunk, ChunkAddress, RecordKind,
+        RetryStrategy,
+    },
+    NetworkAddress, CLOSE_GROUP_SIZE,
+};
+use bytes::Bytes;
+use libp2p::{
+    kad::{Quorum, Record},
+    PeerId,
+};
+use std::collections::HashSet;
+
+/// The number of nodes a chunk is expected on by default, see [`Client::repair`]: a majority of its close group,
+/// which is what the nodes require before they consider a chunk stored.
+pub const DEFAULT_MIN_REPLICAS: usize = CLOSE_GROUP_SIZE / 2 + 1;
+
+/// The number of archivers each chunk is pushed to by default, see [`Client::push_to_archivers`].
+pub const DEFAULT_ARCHIVE_COPIES: usize = 2;
+
+/// The uploads to repair, see [`Client::repair`].
+#[derive(Debug, Clone)]
+pub enum RepairTarget {
+    /// Private data, whose data map is kept locally.
+    Data(DataMapChunk),
+    /// Public data, along with its data map chunk.
+    PublicData(DataAddr),
+    /// A private archive, along with all of its files.
+    Archive(PrivateArchiveAccess),
+    /// A public archive, along with all of its files.
+    PublicArchive(ArchiveAddr),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RepairError {
+    #[error("Failed to fetch the data maps of the data to repair: {0}")]
+    Get(#[from] GetError),
+    #[error("Failed to pay for the chunks to re-upload: {0}")]
+    Pay(#[from] PayError),
+    #[error("Network error: {0}")]
+    Network(#[from] NetworkError),
+    #[error("None of the peers we know of is an archiver")]
+    NoArchivers,
+}
+
+/// The outcome of a [`Client::repair`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The number of chunks checked.
+    pub checked: usize,
+    /// The number of chunks found on at least the minimum of nodes.
+    pub healthy: usize,
+    /// The chunks found on fewer nodes, with their number, paid for and uploaded again. None of the nodes held
+    /// those restored from an archiver.
+    pub repaired: Vec<(ChunkAddr, usize)>,
+    /// The chunks no node returned, not even an archiver. They can only be repaired by uploading the original data
+    /// again.
+    pub lost: Vec<ChunkAddr>,
+    /// The chunks that couldn't be checked or uploaded again, with why.
+    pub failed: Vec<(ChunkAddr, String)>,
+    /// The cost of the chunks uploaded again.
+    pub cost: AttoTokens,
+}
+
+impl RepairReport {
+    /// Whether every chunk was found on enough nodes, or was repaired.
+    pub fn is_healthy(&self) -> bool {
+        self.lost.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// What the copies of a chunk fetched from its close group tell about it.
+#[derive(Debug)]
+enum ChunkReplicas {
+    /// The number of nodes that returned the chunk, with the chunk itself.
+    Found(usize, Box<Chunk>),
+    Lost,
+    Unknown(String),
+}
+
+impl ChunkReplicas {
+    fn from_result(result: Result<GetRecordOk, NetworkError>) -> Self {
+        let (record, holders) = match result {
+            Ok(get_record_ok) => {
+                let holders = get_record_ok.holders().len();
+                (get_record_ok.record, holders)
+            }
+            Err(NetworkError::GetRecordError(GetRecordError::NotEnoughCopies {
+                record,
+                got,
+                ..
+            })) => (record, got),
+            Err(NetworkError::GetRecordError(GetRecordError::RecordNotFound)) => {
+                return Self::Lost;
+            }
+            Err(err) => return Self::Unknown(format!("{err:?}")),
+        };
+        match try_deserialize_record::<Chunk>(&record) {
+            Ok(chunk) => Self::Found(holders, Box::new(chunk)),
+            Err(err) => Self::Unknown(format!("Invalid chunk: {err:?}")),
+        }
+    }
+}
+
+impl Client {
+    /// Check that every chunk of previously uploaded data, or of the files of an archive, is still held by at least
+    /// `min_replicas` nodes, paying for and uploading again the ones held by fewer, e.g. after heavy churn.
+    ///
+    /// The copies are fetched from the whole close group of each chunk, so that the nodes returning it can be
+    /// counted. The chunks none of them returns are restored from the archivers they were pushed to with
+    /// [`Client::push_to_archivers`], if any, and are otherwise reported as lost.
+    pub async fn repair(
+        &self,
+        target: RepairTarget,
+        min_replicas: usize,
+        wallet: &EvmWallet,
+    ) -> Result<RepairReport, RepairError> {
+        let addrs = self.chunk_addrs_of(target).await?;
+        info!(
+            "Checking the replicas of {} chunks, expecting at least {min_replicas} each",
+            addrs.len()
+        );
+
+        let check_tasks: Vec<_> = addrs
+            .iter()
+            .map(|addr| async move { (*addr, self.chunk_replicas(*addr).await) })
+            .collect();
+        let checked =
+            process_tasks_with_max_concurrency(check_tasks, self.bandwidth.download_concurrency())
+                .await;
+
+        let mut report = RepairReport {
+            checked: checked.len(),
+            healthy: 0,
+            repaired: vec![],
+            lost: vec![],
+            failed: vec![],
+            cost: AttoTokens::zero(),
+        };
+        let archivers = self.archivers().await?;
+        let mut to_repair = vec![];
+        for (addr, replicas) in checked {
+            match replicas {
+                ChunkReplicas::Found(holders, _) if holders >= min_replicas => report.healthy += 1,
+                ChunkReplicas::Found(holders, chunk) => {
+                    warn!("Chunk {addr:?} is only held by {holders} nodes");
+                    to_repair.push((*chunk, holders));
+                }
+                ChunkReplicas::Lost => match self.chunk_from_archivers(addr, &archivers).await {
+                    Some(chunk) => {
+                        warn!("Chunk {addr:?} wasn't returned by its close group, restoring it from an archiver");
+                        to_repair.push((chunk, 0));
+                    }
+                    None => {
+                        error!("Chunk {addr:?} wasn't returned by any node");
+                        report.lost.push(addr);
+                    }
+                },
+                ChunkReplicas::Unknown(err) => {
+                    error!("Failed to check the replicas of chunk {addr:?}: {err}");
+                    report.failed.push((addr, err));
+                }
+            }
+        }
+        if to_repair.is_empty() {
+            info!("No chunk to repair: {report:?}");
+            return Ok(report);
+        }
+
+        let receipt = self
+            .pay(to_repair.iter().map(|(chunk, _)| *chunk.name()), wallet)
+            .await?;
+        report.cost = AttoTokens::from_atto(
+            receipt
+                .values()
+                .map(|(_, cost)| cost.as_atto())
+                .sum::<ant_evm::U256>(),
+        );
+
+        let chunks: Vec<_> = to_repair.iter().map(|(chunk, _)| chunk).collect();
+        let failed: HashSet<_> = self
+            .upload_chunks_with_retries(chunks, &receipt)
+            .await
+            .into_iter()
+            .map(|(chunk, err)| {
+                report.failed.push((*chunk.name(), format!("{err:?}")));
+                *chunk.name()
+            })
+            .collect();
+        for (chunk, holders) in to_repair {
+            if failed.contains(chunk.name()) {
+                continue;
+            }
+            if receipt.contains_key(chunk.name()) {
+                report.repaired.push((*chunk.name(), holders));
+            } else {
+                // The nodes wouldn't quote for it, as enough of them told they store it already.
+                debug!("Chunk {:?} is stored already, per the quotes", chunk.name());
+                report.healthy += 1;
+            }
+        }
+
+        info!("Repaired the chunks: {report:?}");
+        Ok(report)
+    }
+
+    /// Push a copy of every chunk of previously uploaded data, or of the files of an archive, to the `copies`
+    /// archivers closest to it, as an extra durability tier for cold data that [`Client::repair`] restores the
+    /// chunks lost by their close group from.
+    ///
+    /// The archivers are the nodes we know of that advertise the archive capability, holding the chunks for free,
+    /// so they might drop them once they run out of room.
+    ///
+    /// Returns the number of chunks pushed to at least one archiver.
+    pub async fn push_to_archivers(
+        &self,
+        target: RepairTarget,
+        copies: usize,
+    ) -> Result<usize, RepairError> {
+        let archivers = self.archivers().await?;
+        if archivers.is_empty() {
+            return Err(RepairError::NoArchivers);
+        }
+        let addrs = self.chunk_addrs_of(target).await?;
+        info!(
+            "Pushing {} chunks to {copies} of the {} archivers we know of",
+            addrs.len(),
+            archivers.len()
+        );
+
+        let push_tasks: Vec<_> = addrs
+            .iter()
+            .map(|addr| {
+                let archivers = &archivers;
+                async move {
+                    let chunk = self
+                        .chunk_get(*addr)
+                        .await
+                        .inspect_err(|err| {
+                            error!("Failed to fetch chunk {addr:?} to archive: {err:?}")
+                        })
+                        .ok()?;
+                    let address = NetworkAddress::from_chunk_address(*chunk.address());
+                    let mut closest = archivers.clone();
+                    closest.sort_by_key(|peer| address.distance(&NetworkAddress::from_peer(*peer)));
+                    closest.truncate(copies);
+
+                    let record = Record {
+                        key: address.to_record_key(),
+                        value: try_serialize_record(&chunk, RecordKind::Chunk)
+                            .ok()?
+                            .to_vec(),
+                        publisher: None,
+                        expires: None,
+                    };
+                    let put_cfg = PutRecordCfg {
+                        put_quorum: Quorum::One,
+                        retry_strategy: None,
+                        use_put_record_to: Some(closest),
+                        verification: None,
+                    };
+                    self.network
+                        .put_record(record, &put_cfg)
+                        .await
+                        .inspect_err(|err| {
+                            error!("Failed to push chunk {addr:?} to archivers: {err:?}")
+                        })
+                        .ok()
+                }
+            })
+            .collect();
+        let pushed =
+            process_tasks_with_max_concurrency(push_tasks, self.bandwidth.upload_concurrency())
+                .await
+                .into_iter()
+                .flatten()
+                .count();
+
+        info!("Pushed {pushed} chunks out of {} to archivers", addrs.len());
+        Ok(pushed)
+    }
+
+    /// The nodes we know of that advertise the archive capability.
+    pub async fn archivers(&self) -> Result<Vec<PeerId>, NetworkError> {
+        Ok(self
+            .network
+            .get_peer_capabilities()
+            .await?
+            .into_iter()
+            .filter_map(|(peer, capabilities)| capabilities.archive.then_some(peer))
+            .collect())
+    }
+
+    /// Fetch the chunk straight from the archivers, which are beyond its close group.
+    async fn chunk_from_archivers(&self, addr: ChunkAddr, archivers: &[PeerId]) -> Option<Chunk> {
+        if archivers.is_empty() {
+            return None;
+        }
+        let address = NetworkAddress::from_chunk_address(ChunkAddress::new(addr));
+        let request = Request::Query(Query::GetReplicatedRecord {
+            requester: NetworkAddress::from_peer(self.network.peer_id()),
+            key: address.clone(),
+        });
+        let responses = self
+            .network
+            .send_and_get_responses(archivers, &request, true)
+            .await;
+        responses.into_values().find_map(|response| {
+            let Ok(Response::Query(QueryResponse::GetReplicatedRecord(Ok((_holder, value))))) =
+                response
+            else {
+                return None;
+            };
+            let record = Record::new(address.to_record_key(), value.to_vec());
+            try_deserialize_record::<Chunk>(&record)
+                .ok()
+                // The archivers aren't trusted more than any node, the chunk has to match its address.
+                .filter(|chunk| *chunk.name() == addr)
+        })
+    }
+
+    /// The addresses of all of the chunks making up the target, including those of its data maps stored on the
+    /// network.
+    async fn chunk_addrs_of(&self, target: RepairTarget) -> Result<Vec<ChunkAddr>, GetError> {
+        let mut addrs = vec![];
+        let data_maps = match target {
+            RepairTarget::Data(data_map) => vec![data_map.0.value],
+            RepairTarget::PublicData(addr) => {
+                addrs.push(addr);
+                vec![self.chunk_get(addr).await?.value]
+            }
+            RepairTarget::Archive(access) => {
+                let archive = PrivateArchive::from_bytes(self.data_get(access.clone()).await?)?;
+                std::iter::once(access.0.value)
+                    .chain(
+                        archive
+                            .iter()
+                            .map(|(_, data_map, _)| data_map.0.value.clone()),
+                    )
+                    .collect()
+            }
+            RepairTarget::PublicArchive(archive_addr) => {
+                let archive = PublicArchive::from_bytes(self.data_get_public(archive_addr).await?)?;
+                let mut data_maps = vec![self.chunk_get(archive_addr).await?.value];
+                addrs.push(archive_addr);
+                for (_, addr, _) in archive.iter() {
+                    data_maps.push(self.chunk_get(*addr).await?.value);
+                    addrs.push(*addr);
+                }
+                data_maps
+            }
+        };
+
+        for data_map in data_maps {
+            addrs.extend(self.data_map_chunk_addrs(&data_map).await?);
+        }
+        // Identical files share their chunks.
+        let mut seen = HashSet::new();
+        addrs.retain(|addr| seen.insert(*addr));
+        Ok(addrs)
+    }
+
+    /// The addresses of the chunks of a wrapped data map, down to the ones of the source data.
+    async fn data_map_chunk_addrs(
+        &self,
+        data_map_bytes: &Bytes,
+    ) -> Result<Vec<ChunkAddr>, GetError> {
+        let mut data_map_level: DataMapLevel = rmp_serde::from_slice(data_map_bytes)
+            .map_err(GetError::InvalidDataMap)
+            .inspect_err(|err| error!("Error deserializing data map: {err:?}"))?;
+        let mut addrs = vec![];
+
+        loop {
+            match data_map_level {
+                DataMapLevel::First(data_map) => {
+                    addrs.extend(data_map.infos().iter().map(|info| info.dst_hash));
+                    break Ok(addrs);
+                }
+                DataMapLevel::Segmented(_, data_maps) => {
+                    for data_map in data_maps {
+                        addrs.extend(data_map.infos().iter().map(|info| info.dst_hash));
+                    }
+                    break Ok(addrs);
+                }
+                DataMapLevel::Compressed(_, level) => data_map_level = *level,
+                DataMapLevel::Additional(data_map) => {
+                    addrs.extend(data_map.infos().iter().map(|info| info.dst_hash));
+                    let data = self.fetch_from_data_map(&data_map).await?;
+                    data_map_level =
+                        rmp_serde::from_slice(&data).map_err(GetError::InvalidDataMap)?;
+                }
+            }
+        }
+    }
+
+    /// Fetch the chunk from all of its close group, counting the nodes that return it.
+    async fn chunk_replicas(&self, addr: ChunkAddr) -> ChunkReplicas {
+        let key = NetworkAddress::from_chunk_address(ChunkAddress::new(addr)).to_record_key();
+        let get_cfg = GetRecordCfg {
+            // Anything less would stop at the first copy, as a chunk is checked against its address.
+            get_quorum: Quorum::All,
+            retry_strategy: Some(RetryStrategy::Quick),
+            target_record: None,
+            expected_holders: HashSet::new(),
+            is_register: false,
+        };
+        ChunkReplicas::from_result(self.network.get_record_from_network(key, &get_cfg).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_record() -> (Chunk, Record) {
+        let chunk = Chunk::new(Bytes::from_static(b"chunk to repair"));
+        let record = Record::new(
+            NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            try_serialize_record(&chunk, RecordKind::Chunk)
+                .expect("chunk to serialize")
+                .to_vec(),
+        );
+        (chunk, record)
+    }
+
+    #[test]
+    fn replicas_are_counted_from_the_copies_returned() {
+        let (chunk, record) = chunk_record();
+        let content_hash = xor_name::XorName::from_content(&record.value);
+
+        let found = ChunkReplicas::from_result(Ok(GetRecordOk {
+            record: record.clone(),
+            provenance: (0..CLOSE_GROUP_SIZE)
+                .map(|_| (PeerId::random(), content_hash))
+                .collect(),
+            completed_early: false,
+            merged: false,
+        }));
+        assert!(
+            matches!(found, ChunkReplicas::Found(holders, found) if holders == CLOSE_GROUP_SIZE && *found == chunk)
+        );
+
+        let under_replicated = ChunkReplicas::from_result(Err(NetworkError::GetRecordError(
+            GetRecordError::NotEnoughCopies {
+                record,
+                expected: CLOSE_GROUP_SIZE,
+                got: 2,
+            },
+        )));
+        assert!(matches!(under_replicated, ChunkReplicas::Found(2, _)));
+
+        let lost = ChunkReplicas::from_result(Err(NetworkError::GetRecordError(
+            GetRecordError::RecordNotFound,
+        )));
+        assert!(matches!(lost, ChunkReplicas::Lost));
+
+        let unknown = ChunkReplicas::from_result(Err(NetworkError::GetRecordError(
+            GetRecordError::QueryTimeout,
+        )));
+        assert!(matches!(unknown, ChunkReplicas::Unknown(_)));
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::archive::{Metadata, PrivateArchive, PrivateArchiveAccess};
+use super::archive_public::{ArchiveAddr, PublicArchive};
+use super::fs::{with_checksum, UploadError, FILE_UPLOAD_BATCH_SIZE};
+use super::fs_public::metadata_from_entry;
+use crate::client::files::get_relative_file_path_from_abs_file_and_folder_path;
+use crate::client::utils::process_tasks_with_max_concurrency;
+use crate::client::Client;
+use ant_evm::EvmWallet;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The changes of a local directory since it was uploaded as an archive, see [`Client::dir_sync_public`].
+///
+/// The paths are the ones in the archive, i.e. relative to the parent of the directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// The files not in the archive, uploaded.
+    pub added: Vec<PathBuf>,
+    /// The files whose size or modification time differ from the archived ones, uploaded again.
+    pub changed: Vec<PathBuf>,
+    /// The archived files not in the directory anymore, left out of the new archive.
+    pub removed: Vec<PathBuf>,
+    /// The number of files kept as they were archived.
+    pub unchanged: usize,
+}
+
+impl SyncSummary {
+    /// Whether the directory is the same as the archive, i.e. nothing was uploaded.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A file of the directory to upload.
+struct LocalFile {
+    path: PathBuf,
+    archive_path: PathBuf,
+    metadata: Metadata,
+}
+
+impl Client {
+    /// Sync a directory with the public archive it was uploaded as, uploading only its new and changed files.
+    ///
+    /// Files are considered changed when their size or modification time differ from the archived ones. The
+    /// archives are immutable, so a new archive is uploaded, without the files removed from the directory, unless
+    /// nothing changed.
+    ///
+    /// Returns the address of the new archive, along with the changes.
+    pub async fn dir_sync_public(
+        &self,
+        dir_path: PathBuf,
+        archive_addr: ArchiveAddr,
+        wallet: &EvmWallet,
+    ) -> Result<(ArchiveAddr, SyncSummary), UploadError> {
+        let old_archive = self.archive_get_public(archive_addr).await?;
+        let archived = old_archive
+            .iter()
+            .map(|(path, _addr, meta)| (path.clone(), meta.clone()))
+            .collect();
+        let (to_upload, summary) = diff_dir(&dir_path, &archived)?;
+        if summary.is_empty() {
+            info!("Directory {dir_path:?} is in sync with the archive at {archive_addr:?}");
+            return Ok((archive_addr, summary));
+        }
+
+        let mut upload_tasks = vec![];
+        for file in to_upload {
+            upload_tasks.push(async move {
+                let metadata = with_checksum(file.metadata, &file.path).await;
+                let addr = self.file_upload_public(file.path, wallet).await;
+                (file.archive_path, metadata, addr)
+            });
+        }
+        let uploads =
+            process_tasks_with_max_concurrency(upload_tasks, *FILE_UPLOAD_BATCH_SIZE).await;
+
+        let mut archive = PublicArchive::new();
+        for (path, addr, meta) in old_archive.iter() {
+            if !summary.removed.contains(path) {
+                archive.add_file(path.clone(), *addr, meta.clone());
+            }
+        }
+        for (path, meta, addr) in uploads {
+            archive.add_file(path, addr?, meta);
+        }
+
+        let new_addr = self.archive_put_public(&archive, wallet).await?;
+        info!("Synced directory {dir_path:?} from the archive at {archive_addr:?} to {new_addr:?}: {summary:?}");
+        Ok((new_addr, summary))
+    }
+
+    /// Same as [`Client::dir_sync_public`] for a private archive.
+    pub async fn dir_sync(
+        &self,
+        dir_path: PathBuf,
+        archive_access: PrivateArchiveAccess,
+        wallet: &EvmWallet,
+    ) -> Result<(PrivateArchiveAccess, SyncSummary), UploadError> {
+        let old_archive = self.archive_get(archive_access.clone()).await?;
+        let archived = old_archive
+            .iter()
+            .map(|(path, _access, meta)| (path.clone(), meta.clone()))
+            .collect();
+        let (to_upload, summary) = diff_dir(&dir_path, &archived)?;
+        if summary.is_empty() {
+            info!("Directory {dir_path:?} is in sync with its private archive");
+            return Ok((archive_access, summary));
+        }
+
+        let mut upload_tasks = vec![];
+        for file in to_upload {
+            upload_tasks.push(async move {
+                let metadata = with_checksum(file.metadata, &file.path).await;
+                let access = self.file_upload(file.path, wallet).await;
+                (file.archive_path, metadata, access)
+            });
+        }
+        let uploads =
+            process_tasks_with_max_concurrency(upload_tasks, *FILE_UPLOAD_BATCH_SIZE).await;
+
+        let mut archive = PrivateArchive::new();
+        for (path, access, meta) in old_archive.iter() {
+            if !summary.removed.contains(path) {
+                archive.add_file(path.clone(), access.clone(), meta.clone());
+            }
+        }
+        for (path, meta, access) in uploads {
+            archive.add_file(path, access?, meta);
+        }
+
+        let new_access = self.archive_put(&archive, wallet.into()).await?;
+        info!("Synced directory {dir_path:?} with its private archive: {summary:?}");
+        Ok((new_access, summary))
+    }
+}
+
+/// Sort the files of the directory out against the archived ones, returning the ones to upload.
+fn diff_dir(
+    dir_path: &Path,
+    archived: &HashMap<PathBuf, Metadata>,
+) -> Result<(Vec<LocalFile>, SyncSummary), walkdir::Error> {
+    let mut summary = SyncSummary::default();
+    let mut to_upload = vec![];
+    let mut local_paths = HashSet::new();
+
+    for entry in walkdir::WalkDir::new(dir_path) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path().to_path_buf();
+        let archive_path = get_relative_file_path_from_abs_file_and_folder_path(&path, dir_path);
+        let metadata = metadata_from_entry(&entry);
+        let _ = local_paths.insert(archive_path.clone());
+
+        match archived.get(&archive_path) {
+            Some(old) if old.size == metadata.size && old.modified == metadata.modified => {
+                summary.unchanged += 1;
+                continue;
+            }
+            Some(_) => summary.changed.push(archive_path.clone()),
+            None => summary.added.push(archive_path.clone()),
+        }
+        to_upload.push(LocalFile {
+            path,
+            archive_path,
+            metadata,
+        });
+    }
+
+    summary.removed = archived
+        .keys()
+        .filter(|path| !local_paths.contains(*path))
+        .cloned()
+        .collect();
+    summary.added.sort();
+    summary.changed.sort();
+    summary.removed.sort();
+    Ok((to_upload, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_new_and_changed_files_are_uploaded() -> eyre::Result<()> {
+        let root = std::env::temp_dir().join(format!("autonomi_sync_{}", rand::random::<u64>()));
+        let dir = root.join("photos");
+        std::fs::create_dir_all(dir.join("2024"))?;
+        std::fs::write(dir.join("same.jpg"), b"same")?;
+        std::fs::write(dir.join("edited.jpg"), b"edited")?;
+        std::fs::write(dir.join("2024/new.jpg"), b"new")?;
+
+        let (files, summary) = diff_dir(&dir, &HashMap::new())?;
+        assert_eq!(summary.added.len(), 3);
+
+        let mut archived: HashMap<_, _> = files
+            .into_iter()
+            .map(|file| (file.archive_path, file.metadata))
+            .collect();
+        let _ = archived.remove(Path::new("photos/2024/new.jpg"));
+        let _ = archived.insert(
+            PathBuf::from("photos/deleted.jpg"),
+            Metadata::new_with_size(1),
+        );
+        if let Some(meta) = archived.get_mut(Path::new("photos/edited.jpg")) {
+            meta.size += 1;
+        }
+
+        let (to_upload, summary) = diff_dir(&dir, &archived)?;
+        std::fs::remove_dir_all(&root)?;
+        assert_eq!(
+            summary,
+            SyncSummary {
+                added: vec![PathBuf::from("photos/2024/new.jpg")],
+                changed: vec![PathBuf::from("photos/edited.jpg")],
+                removed: vec![PathBuf::from("photos/deleted.jpg")],
+                unchanged: 1,
+            }
+        );
+        assert_eq!(to_upload.len(), 2);
+        Ok(())
+    }
+}
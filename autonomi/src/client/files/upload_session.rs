@@ -0,0 +1,367 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::fs::UploadError;
+use crate::client::data::DataMapChunk;
+use crate::client::payment::Receipt;
+use crate::client::Client;
+use crate::self_encryption::encrypt;
+use ant_evm::EvmWallet;
+use ant_protocol::storage::Chunk;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use xor_name::XorName;
+
+/// The name of the directory holding the upload sessions, within the directory given by the caller.
+const UPLOAD_SESSIONS_DIR: &str = "upload_sessions";
+
+/// The progress of a single chunk within an [`UploadSession`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChunkUploadStatus {
+    /// The chunk has not been paid for yet.
+    Unpaid,
+    /// The chunk has been paid for, the proof of payment is held in the session receipt.
+    Paid,
+    /// The chunk has been stored and verified, or was already present on the network.
+    Stored,
+}
+
+/// A persisted record of the progress of a file upload, allowing an interrupted upload to be resumed without
+/// re-uploading the stored chunks or paying again for the paid ones.
+///
+/// The chunk content is not persisted: self-encryption is deterministic, so the chunks are reproduced from the source
+/// file on resumption.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    source: PathBuf,
+    data_map_chunk: DataMapChunk,
+    chunks: BTreeMap<XorName, ChunkUploadStatus>,
+    receipt: Receipt,
+}
+
+impl UploadSession {
+    fn new(source: PathBuf, data_map_chunk: DataMapChunk, chunks: &[Chunk]) -> Self {
+        Self {
+            source,
+            data_map_chunk,
+            chunks: chunks
+                .iter()
+                .map(|chunk| (*chunk.name(), ChunkUploadStatus::Unpaid))
+                .collect(),
+            receipt: Receipt::new(),
+        }
+    }
+
+    /// The id of the session, derived from the data map of the source file.
+    pub fn id(&self) -> String {
+        self.data_map_chunk.address()
+    }
+
+    /// The file being uploaded.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// The status of each chunk of the file.
+    pub fn chunks(&self) -> &BTreeMap<XorName, ChunkUploadStatus> {
+        &self.chunks
+    }
+
+    /// Whether every chunk has been stored.
+    pub fn is_complete(&self) -> bool {
+        self.chunks
+            .values()
+            .all(|status| *status == ChunkUploadStatus::Stored)
+    }
+
+    fn chunks_with_status(&self, status: ChunkUploadStatus) -> Vec<XorName> {
+        self.chunks
+            .iter()
+            .filter(|(_, chunk_status)| **chunk_status == status)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Record the payments made for the unpaid chunks. Chunks that did not need a payment are already on the network.
+    fn mark_paid(&mut self, receipt: Receipt) {
+        for (name, status) in self.chunks.iter_mut() {
+            if *status != ChunkUploadStatus::Unpaid {
+                continue;
+            }
+            *status = if receipt.contains_key(name) {
+                ChunkUploadStatus::Paid
+            } else {
+                ChunkUploadStatus::Stored
+            };
+        }
+        self.receipt.extend(receipt);
+    }
+
+    fn mark_stored(&mut self, name: &XorName) {
+        if let Some(status) = self.chunks.get_mut(name) {
+            *status = ChunkUploadStatus::Stored;
+        }
+    }
+
+    fn session_path(sessions_dir: &Path, session_id: &str) -> PathBuf {
+        sessions_dir.join(UPLOAD_SESSIONS_DIR).join(session_id)
+    }
+
+    /// Load a persisted session from the given directory.
+    pub fn load(sessions_dir: &Path, session_id: &str) -> Result<Self, UploadError> {
+        let bytes = std::fs::read(Self::session_path(sessions_dir, session_id))?;
+        Ok(rmp_serde::from_slice(&bytes)?)
+    }
+
+    /// Load all the sessions persisted in the given directory, e.g. to list the interrupted uploads.
+    ///
+    /// Sessions that fail to load, e.g. written by an incompatible version, are skipped.
+    pub fn list(sessions_dir: &Path) -> Result<Vec<Self>, UploadError> {
+        let dir = sessions_dir.join(UPLOAD_SESSIONS_DIR);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut sessions = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some() {
+                // A temporary file left by an interrupted save
+                continue;
+            }
+            match std::fs::read(&path)
+                .map_err(UploadError::from)
+                .and_then(|bytes| Ok(rmp_serde::from_slice(&bytes)?))
+            {
+                Ok(session) => sessions.push(session),
+                Err(err) => warn!("Skipping the upload session at {path:?}: {err:?}"),
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// The size of the persisted session in the given directory, in bytes.
+    pub fn size_on_disk(&self, sessions_dir: &Path) -> u64 {
+        std::fs::metadata(Self::session_path(sessions_dir, &self.id()))
+            .map(|metadata| metadata.len())
+            .unwrap_or_default()
+    }
+
+    /// Persist the session to the given directory, overwriting the previous state.
+    pub fn save(&self, sessions_dir: &Path) -> Result<(), UploadError> {
+        let path = Self::session_path(sessions_dir, &self.id());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let bytes = rmp_serde::to_vec(self)?;
+        // Write to a temporary file first, so that an interruption can not leave a truncated session behind.
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, bytes)?;
+        std::fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Remove the persisted session from the given directory.
+    pub fn remove(&self, sessions_dir: &Path) -> Result<(), UploadError> {
+        let path = Self::session_path(sessions_dir, &self.id());
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Upload a private file to the network, persisting the progress of the upload under `sessions_dir` (e.g. the
+    /// client data dir) as it goes.
+    ///
+    /// If a session already exists for the file, the upload carries on from where it stopped. The session is removed
+    /// once the upload completes.
+    pub async fn file_upload_with_session(
+        &self,
+        path: PathBuf,
+        wallet: &EvmWallet,
+        sessions_dir: &Path,
+    ) -> Result<DataMapChunk, UploadError> {
+        info!("Uploading file with a session: {path:?}");
+        let (data_map_chunk, chunks) = self.encrypt_file(&path).await?;
+
+        let session_id = data_map_chunk.address();
+        let session = match UploadSession::load(sessions_dir, &session_id) {
+            Ok(session) => {
+                info!("Resuming existing upload session {session_id} for {path:?}");
+                session
+            }
+            Err(_) => UploadSession::new(path, data_map_chunk, &chunks),
+        };
+
+        self.run_upload_session(session, chunks, wallet, sessions_dir)
+            .await
+    }
+
+    /// Resume an interrupted upload from its persisted session, skipping the stored chunks and re-using the payments
+    /// already made.
+    pub async fn file_upload_resume(
+        &self,
+        session_id: &str,
+        wallet: &EvmWallet,
+        sessions_dir: &Path,
+    ) -> Result<DataMapChunk, UploadError> {
+        let session = UploadSession::load(sessions_dir, session_id)?;
+        info!(
+            "Resuming upload session {session_id} for {:?}",
+            session.source
+        );
+
+        let (data_map_chunk, chunks) = self.encrypt_file(&session.source).await?;
+        if data_map_chunk != session.data_map_chunk {
+            error!(
+                "Source file {:?} of upload session {session_id} has changed",
+                session.source
+            );
+            return Err(UploadError::SessionSourceChanged(session_id.to_string()));
+        }
+
+        self.run_upload_session(session, chunks, wallet, sessions_dir)
+            .await
+    }
+
+    async fn encrypt_file(&self, path: &Path) -> Result<(DataMapChunk, Vec<Chunk>), UploadError> {
+        let data = Bytes::from(tokio::fs::read(path).await?);
+        let (data_map_chunk, chunks) = encrypt(data, &self.chunking, self.compression)
+            .map_err(crate::client::data::PutError::from)?;
+        Ok((DataMapChunk(data_map_chunk), chunks))
+    }
+
+    async fn run_upload_session(
+        &self,
+        mut session: UploadSession,
+        chunks: Vec<Chunk>,
+        wallet: &EvmWallet,
+        sessions_dir: &Path,
+    ) -> Result<DataMapChunk, UploadError> {
+        let session_id = session.id();
+
+        let unpaid = session.chunks_with_status(ChunkUploadStatus::Unpaid);
+        if !unpaid.is_empty() {
+            let unpaid_chunks: Vec<_> = chunks
+                .iter()
+                .filter(|chunk| unpaid.contains(chunk.name()))
+                .collect();
+            // The chunks found stored already are left out of the receipt, so marked stored along with the paid ones.
+            let stored = self.chunks_already_stored(&unpaid_chunks).await;
+            info!(
+                "Paying for {} chunks of upload session {session_id}",
+                unpaid.len() - stored.len()
+            );
+            let receipt = self
+                .pay(
+                    unpaid.into_iter().filter(|name| !stored.contains(name)),
+                    wallet,
+                )
+                .await?;
+            session.mark_paid(receipt);
+            session.save(sessions_dir)?;
+        }
+
+        let paid = session.chunks_with_status(ChunkUploadStatus::Paid);
+        let pending_chunks = chunks
+            .iter()
+            .filter(|chunk| paid.contains(chunk.name()))
+            .collect::<Vec<_>>();
+        info!(
+            "Uploading {} chunks of upload session {session_id}, {} already stored",
+            pending_chunks.len(),
+            session.chunks.len() - pending_chunks.len()
+        );
+
+        // Persist the progress after every batch, so that an interruption loses at most a batch worth of uploads.
+        for batch in pending_chunks.chunks(self.bandwidth.upload_concurrency()) {
+            let mut failed_uploads = self
+                .upload_chunks_with_retries(batch.to_vec(), &session.receipt)
+                .await;
+
+            for chunk in batch {
+                if !failed_uploads
+                    .iter()
+                    .any(|(failed, _)| failed.name() == chunk.name())
+                {
+                    session.mark_stored(chunk.name());
+                }
+            }
+            session.save(sessions_dir)?;
+
+            if let Some((chunk, err)) = failed_uploads.pop() {
+                error!(
+                    "Error uploading chunk ({:?}) of upload session {session_id}: {err:?}",
+                    chunk.address()
+                );
+                return Err(err.into());
+            }
+        }
+
+        debug!("Upload session {session_id} completed");
+        session.remove(sessions_dir)?;
+        Ok(session.data_map_chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_evm::{AttoTokens, ProofOfPayment};
+
+    #[test]
+    fn session_tracks_chunk_progress_and_persists() -> Result<(), UploadError> {
+        let chunks = (0..3)
+            .map(|i| Chunk::new(Bytes::from(format!("chunk {i}"))))
+            .collect::<Vec<_>>();
+        let data_map_chunk = DataMapChunk(Chunk::new(Bytes::from_static(b"data map")));
+        let mut session = UploadSession::new("file".into(), data_map_chunk, &chunks);
+        assert_eq!(
+            session.chunks_with_status(ChunkUploadStatus::Unpaid).len(),
+            3
+        );
+
+        // The last chunk needed no payment, it's already on the network.
+        let mut receipt = Receipt::new();
+        for chunk in &chunks[..2] {
+            let proof = ProofOfPayment {
+                peer_quotes: vec![],
+            };
+            let _ = receipt.insert(*chunk.name(), (proof, AttoTokens::zero()));
+        }
+        session.mark_paid(receipt);
+        session.mark_stored(chunks[0].name());
+        assert_eq!(
+            session.chunks_with_status(ChunkUploadStatus::Paid),
+            vec![*chunks[1].name()]
+        );
+        assert!(!session.is_complete());
+
+        let sessions_dir =
+            std::env::temp_dir().join(format!("autonomi_upload_session_{}", rand::random::<u64>()));
+        session.save(&sessions_dir)?;
+        let loaded = UploadSession::load(&sessions_dir, &session.id())?;
+        assert_eq!(loaded.chunks(), session.chunks());
+        assert_eq!(loaded.receipt.len(), 2);
+
+        let listed = UploadSession::list(&sessions_dir)?;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].source(), Path::new("file"));
+        assert!(listed[0].size_on_disk(&sessions_dir) > 0);
+
+        loaded.remove(&sessions_dir)?;
+        assert!(UploadSession::load(&sessions_dir, &session.id()).is_err());
+        assert!(UploadSession::list(&sessions_dir)?.is_empty());
+        let _ = std::fs::remove_dir_all(sessions_dir);
+        Ok(())
+    }
+}
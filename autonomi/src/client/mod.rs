@@ -10,12 +10,29 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub mod address;
+pub mod bandwidth;
+pub mod cancellation;
+pub mod chunk_cache;
+mod close_group;
 pub mod payment;
 pub mod quote;
+mod quote_cache;
+mod read_repair;
+pub mod receipts;
 
 pub mod data;
+pub mod disputes;
 pub mod files;
+pub mod network_stats;
+pub mod pointer;
+pub mod pubsub;
+pub mod record_cfg;
+pub mod scratchpad;
+pub mod session;
 pub mod transactions;
+pub mod transfer_notifications;
+pub mod upload_cfg;
+pub mod wallet_watch;
 
 #[cfg(feature = "external-signer")]
 #[cfg_attr(docsrs, doc(cfg(feature = "external-signer")))]
@@ -32,23 +49,40 @@ pub mod wasm;
 
 // private module with utility functions
 mod rate_limiter;
+mod retry;
+mod upload_window;
 mod utils;
 
 use ant_bootstrap::{BootstrapCacheConfig, BootstrapCacheStore, PeersArgs};
 pub use ant_evm::Amount;
-use ant_evm::EvmNetwork;
+use ant_evm::{AttoTokens, EvmNetwork};
 use ant_networking::{interval, multiaddr_is_global, Network, NetworkBuilder, NetworkEvent};
-use ant_protocol::version::IDENTIFY_PROTOCOL_STR;
+use ant_protocol::{storage::RetryStrategy, version::IDENTIFY_PROTOCOL_STR};
+use bandwidth::{Bandwidth, BandwidthConfig};
+use bytes::Bytes;
+use cancellation::CancellationToken;
+use chunk_cache::{ChunkCache, ChunkCacheConfig};
+use close_group::CloseGroupCache;
+use data::{ChunkingParams, Compression};
 use libp2p::{identity::Keypair, Multiaddr};
+use quote_cache::QuoteCache;
+use record_cfg::{GetCfg, PutCfg};
+use session::{spawn_keep_warm, RecentAddrs};
 use std::{collections::HashSet, sync::Arc, time::Duration};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
+use upload_cfg::UploadCfg;
+use upload_window::UploadWindow;
+use xor_name::XorName;
 
 /// Time before considering the connection timed out.
 pub const CONNECT_TIMEOUT_SECS: u64 = 10;
 
-const CLIENT_EVENT_CHANNEL_SIZE: usize = 100;
+const CLIENT_EVENT_CHANNEL_SIZE: usize = 1000;
+
+const GOSSIP_CHANNEL_SIZE: usize = 100;
 
 // Amount of peers to confirm into our routing table before we consider the client ready.
+pub use ant_protocol::ErrorCode;
 pub use ant_protocol::CLOSE_GROUP_SIZE;
 
 /// Represents a client for the Autonomi network.
@@ -68,8 +102,31 @@ pub use ant_protocol::CLOSE_GROUP_SIZE;
 #[derive(Clone)]
 pub struct Client {
     pub(crate) network: Network,
-    pub(crate) client_event_sender: Arc<Option<mpsc::Sender<ClientEvent>>>,
+    pub(crate) client_event_sender: Option<broadcast::Sender<ClientEvent>>,
     pub(crate) evm_network: EvmNetwork,
+    pub(crate) bandwidth: Arc<Bandwidth>,
+    pub(crate) upload_window: Arc<UploadWindow>,
+    pub(crate) chunking: ChunkingParams,
+    pub(crate) compression: Option<Compression>,
+    pub(crate) upload_cfg: UploadCfg,
+    /// Gossipsub messages received on the topics we're subscribed to, see [`Client::subscribe`].
+    pub(crate) gossip_sender: broadcast::Sender<(String, Bytes)>,
+    /// The addresses whose close groups are kept warm, see [`ClientConfig::keep_warm`].
+    pub(crate) recent_addrs: Arc<RecentAddrs>,
+    /// How the operations failing on the network are retried, see [`ClientConfig::retry_strategy`].
+    pub(crate) retry_strategy: RetryStrategy,
+    /// How the records are fetched, see [`Client::with_get_cfg`].
+    pub(crate) get_cfg: GetCfg,
+    /// How the records other than chunks are put, see [`Client::with_put_cfg`].
+    pub(crate) put_cfg: PutCfg,
+    /// The close groups recently looked up, see [`Client::get_closest_peers`].
+    pub(crate) close_groups: Arc<CloseGroupCache>,
+    /// The quotes reused to pay the same nodes again, see [`ClientConfig::quote_cache`].
+    pub(crate) quote_cache: Arc<QuoteCache>,
+    /// Aborts the operations once cancelled, see [`Client::with_cancellation`].
+    pub(crate) cancellation: Option<CancellationToken>,
+    /// The fetched chunks kept on disk, see [`ClientConfig::chunk_cache`].
+    pub(crate) chunk_cache: Option<Arc<ChunkCache>>,
 }
 
 /// Configuration for [`Client::init_with_config`].
@@ -84,6 +141,55 @@ pub struct ClientConfig {
     ///
     /// If not provided, the client will use the default bootstrap peers.
     pub peers: Option<Vec<Multiaddr>>,
+
+    /// Limits on the bandwidth used for uploading and downloading chunks.
+    ///
+    /// Unlimited by default.
+    pub bandwidth: BandwidthConfig,
+
+    /// How uploaded data is split into chunks.
+    ///
+    /// Defaults to chunks of up to 1 MiB.
+    pub chunking: ChunkingParams,
+
+    /// Compress uploaded data before self-encrypting it, storing it in fewer chunks.
+    ///
+    /// Off by default. Compressed data is decompressed on download regardless of this setting.
+    pub compression: Option<Compression>,
+
+    /// How uploaded chunks are verified and put again on failure.
+    ///
+    /// Defaults to verifying every chunk with a quorum of 2, and putting the failed ones again up to 3 times.
+    pub upload: UploadCfg,
+
+    /// Refresh the routing table toward the close groups of the recently used addresses at this interval, in the
+    /// background, for a long-lived client to keep finding them quickly.
+    ///
+    /// Off by default.
+    pub keep_warm: Option<Duration>,
+
+    /// How the operations failing on the network are retried: fetching quotes, putting records and fetching chunks.
+    ///
+    /// Payments are never retried, as a retry could pay twice. Defaults to [`RetryStrategy::Balanced`].
+    pub retry_strategy: RetryStrategy,
+
+    /// How long the close groups looked up with [`Client::get_closest_peers`] are reused for.
+    ///
+    /// Defaults to a minute. Zero disables the caching.
+    pub close_group_ttl: Duration,
+
+    /// Reuse the quotes of the nodes, until shortly before the expiry they signed, to pay for the other addresses of
+    /// the close groups they're in, instead of asking them for a quote for every address.
+    ///
+    /// On by default. The addresses whose close group is quoted from the cache aren't checked for being stored
+    /// already, see [`UploadCfg::dedup_check`] for that.
+    pub quote_cache: bool,
+
+    /// Keep the fetched chunks on disk, for the data downloaded again to be read from the disk instead of the
+    /// network.
+    ///
+    /// Off by default. The chunks being content-addressed, the cached ones are never outdated.
+    pub chunk_cache: Option<ChunkCacheConfig>,
 }
 
 impl Default for ClientConfig {
@@ -94,6 +200,15 @@ impl Default for ClientConfig {
             #[cfg(not(feature = "local"))]
             local: false,
             peers: None,
+            bandwidth: Default::default(),
+            chunking: Default::default(),
+            compression: None,
+            upload: Default::default(),
+            keep_warm: None,
+            retry_strategy: RetryStrategy::Balanced,
+            close_group_ttl: Duration::from_secs(60),
+            quote_cache: true,
+            chunk_cache: None,
         }
     }
 }
@@ -106,12 +221,46 @@ pub enum ConnectError {
     TimedOut,
 
     /// Same as [`ConnectError::TimedOut`] but with a list of incompatible protocols.
-    #[error("Failed to populate our routing table due to incompatible protocol: {0:?}")]
+    #[error("Failed to populate our routing table, the peers run the incompatible protocols {0:?} while we have {1}. Upgrade to a version compatible with the network.")]
     TimedOutWithIncompatibleProtocol(HashSet<String>, String),
 
     /// An error occurred while bootstrapping the client.
     #[error("Failed to bootstrap the client")]
     Bootstrap(#[from] ant_bootstrap::Error),
+
+    /// The chunk cache dir could not be opened.
+    #[error("Failed to open the chunk cache")]
+    ChunkCache(#[source] std::io::Error),
+}
+
+impl ConnectError {
+    /// The class of the error, see [`ErrorCode`].
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::TimedOut => ErrorCode::NotEnoughPeers,
+            Self::TimedOutWithIncompatibleProtocol(..) => ErrorCode::IncompatibleProtocol,
+            Self::Bootstrap(err) => match err {
+                ant_bootstrap::Error::FailedToParseCacheData
+                | ant_bootstrap::Error::InvalidBootstrapCacheDir
+                | ant_bootstrap::Error::UnsignedContacts
+                | ant_bootstrap::Error::InvalidContactsSignature(_)
+                | ant_bootstrap::Error::InvalidContactsPublicKey(_)
+                | ant_bootstrap::Error::FailedToParseUrl
+                | ant_bootstrap::Error::Json(_) => ErrorCode::InvalidInput,
+                ant_bootstrap::Error::CouldNotObtainDataDir | ant_bootstrap::Error::Io(_) => {
+                    ErrorCode::Io
+                }
+                ant_bootstrap::Error::LockError => ErrorCode::Internal,
+                _ => ErrorCode::ConnectionFailed,
+            },
+            Self::ChunkCache(_) => ErrorCode::Io,
+        }
+    }
+
+    /// Whether connecting may succeed when retried, see [`ErrorCode::is_retryable`].
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
 }
 
 impl Client {
@@ -153,6 +302,7 @@ impl Client {
         Self::init_with_config(ClientConfig {
             local,
             peers: Some(peers),
+            ..Default::default()
         })
         .await
     }
@@ -172,6 +322,12 @@ impl Client {
     /// # }
     /// ```
     pub async fn init_with_config(config: ClientConfig) -> Result<Self, ConnectError> {
+        let chunk_cache = config
+            .chunk_cache
+            .map(ChunkCache::open)
+            .transpose()
+            .map_err(ConnectError::ChunkCache)?
+            .map(Arc::new);
         let (network, event_receiver) = build_client_and_run_swarm(config.local);
 
         let peers_args = PeersArgs {
@@ -198,14 +354,36 @@ impl Client {
 
         // Wait until we have added a few peers to our routing table.
         let (sender, receiver) = futures::channel::oneshot::channel();
-        ant_networking::target_arch::spawn(handle_event_receiver(event_receiver, sender));
+        let (gossip_sender, _) = broadcast::channel(GOSSIP_CHANNEL_SIZE);
+        ant_networking::target_arch::spawn(handle_event_receiver(
+            event_receiver,
+            sender,
+            gossip_sender.clone(),
+        ));
         receiver.await.expect("sender should not close")?;
         debug!("Enough peers were added to our routing table, initialization complete");
 
+        let recent_addrs = Arc::new(RecentAddrs::default());
+        spawn_keep_warm(&network, &recent_addrs, config.keep_warm);
+
         Ok(Self {
             network,
-            client_event_sender: Arc::new(None),
+            client_event_sender: None,
             evm_network: Default::default(),
+            bandwidth: Arc::new(Bandwidth::new(config.bandwidth)),
+            upload_window: Arc::new(UploadWindow::new(&config.bandwidth)),
+            chunking: config.chunking,
+            compression: config.compression,
+            upload_cfg: config.upload,
+            gossip_sender,
+            recent_addrs,
+            retry_strategy: config.retry_strategy,
+            get_cfg: Default::default(),
+            put_cfg: Default::default(),
+            close_groups: Arc::new(CloseGroupCache::new(config.close_group_ttl)),
+            quote_cache: Arc::new(QuoteCache::new(config.quote_cache)),
+            cancellation: None,
+            chunk_cache,
         })
     }
 
@@ -246,7 +424,12 @@ impl Client {
         });
 
         let (sender, receiver) = futures::channel::oneshot::channel();
-        ant_networking::target_arch::spawn(handle_event_receiver(event_receiver, sender));
+        let (gossip_sender, _) = broadcast::channel(GOSSIP_CHANNEL_SIZE);
+        ant_networking::target_arch::spawn(handle_event_receiver(
+            event_receiver,
+            sender,
+            gossip_sender.clone(),
+        ));
 
         receiver.await.expect("sender should not close")?;
         debug!("Client is connected to the network");
@@ -259,24 +442,92 @@ impl Client {
 
         Ok(Self {
             network,
-            client_event_sender: Arc::new(None),
+            client_event_sender: None,
             evm_network: Default::default(),
+            bandwidth: Default::default(),
+            upload_window: Default::default(),
+            chunking: Default::default(),
+            compression: None,
+            upload_cfg: Default::default(),
+            gossip_sender,
+            recent_addrs: Default::default(),
+            retry_strategy: RetryStrategy::Balanced,
+            get_cfg: Default::default(),
+            put_cfg: Default::default(),
+            close_groups: Default::default(),
+            quote_cache: Default::default(),
+            cancellation: None,
+            chunk_cache: None,
         })
     }
 
-    /// Receive events from the client.
-    pub fn enable_client_events(&mut self) -> mpsc::Receiver<ClientEvent> {
+    /// Receive events from the client, e.g. to report the progress of uploads and downloads.
+    ///
+    /// Events are broadcast to every receiver. Events are never waited on, so a receiver falling more than 1000
+    /// events behind misses the oldest ones, see [`broadcast::error::RecvError::Lagged`].
+    pub fn enable_client_events(&mut self) -> broadcast::Receiver<ClientEvent> {
+        if let Some(sender) = &self.client_event_sender {
+            return sender.subscribe();
+        }
         let (client_event_sender, client_event_receiver) =
-            tokio::sync::mpsc::channel(CLIENT_EVENT_CHANNEL_SIZE);
-        self.client_event_sender = Arc::new(Some(client_event_sender));
+            broadcast::channel(CLIENT_EVENT_CHANNEL_SIZE);
+        self.client_event_sender = Some(client_event_sender);
         debug!("All events to the clients are enabled");
 
         client_event_receiver
     }
 
+    /// Broadcast the event, if client events are enabled.
+    pub(crate) fn send_client_event(&self, event: ClientEvent) {
+        if let Some(sender) = &self.client_event_sender {
+            if sender.send(event).is_err() {
+                trace!("Dropped client event as there are no receivers");
+            }
+        }
+    }
+
     pub fn set_evm_network(&mut self, evm_network: EvmNetwork) {
         self.evm_network = evm_network;
     }
+
+    /// Change how the next uploads are verified, see [`ClientConfig::upload`].
+    pub fn set_upload_cfg(&mut self, upload_cfg: UploadCfg) {
+        self.upload_cfg = upload_cfg;
+    }
+
+    /// Change how the next operations are retried, see [`ClientConfig::retry_strategy`].
+    pub fn set_retry_strategy(&mut self, retry_strategy: RetryStrategy) {
+        self.retry_strategy = retry_strategy;
+    }
+
+    /// A client on the same connection, fetching the records as `get_cfg` says, for the operations needing another
+    /// consistency than the default of each record type.
+    ///
+    /// ```no_run
+    /// # use autonomi::client::{record_cfg::GetCfg, Client};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = Client::init().await?;
+    /// # let addr = Default::default();
+    /// // A preview only needs the first copy found
+    /// let data = client.with_get_cfg(GetCfg::quick()).data_get_public(addr).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_get_cfg(&self, get_cfg: GetCfg) -> Self {
+        Self {
+            get_cfg,
+            ..self.clone()
+        }
+    }
+
+    /// A client on the same connection, putting the records other than chunks as `put_cfg` says.
+    pub fn with_put_cfg(&self, put_cfg: PutCfg) -> Self {
+        Self {
+            put_cfg,
+            ..self.clone()
+        }
+    }
 }
 
 fn build_client_and_run_swarm(local: bool) -> (Network, mpsc::Receiver<NetworkEvent>) {
@@ -305,6 +556,7 @@ fn build_client_and_run_swarm(local: bool) -> (Network, mpsc::Receiver<NetworkEv
 async fn handle_event_receiver(
     mut event_receiver: mpsc::Receiver<NetworkEvent>,
     sender: futures::channel::oneshot::Sender<Result<(), ConnectError>>,
+    gossip_sender: broadcast::Sender<(String, Bytes)>,
 ) {
     // We switch this to `None` when we've sent the oneshot 'connect' result.
     let mut sender = Some(sender);
@@ -347,13 +599,17 @@ async fn handle_event_receiver(
                             }
                         }
                     }
-                    NetworkEvent::PeerWithUnsupportedProtocol { their_protocol, .. } => {
-                        tracing::warn!(their_protocol, "Peer with unsupported protocol");
+                    NetworkEvent::PeerWithUnsupportedProtocol { their_protocol, mismatch, .. } => {
+                        tracing::warn!(their_protocol, "Peer with unsupported protocol: {mismatch}");
 
                         if sender.is_some() {
                             unsupported_protocols.push(their_protocol);
                         }
                     }
+                    NetworkEvent::GossipsubMsgReceived { topic, msg } => {
+                        // Nobody listening means nobody is watching the topic anymore
+                        let _ = gossip_sender.send((topic, msg));
+                    }
                     _ => {}
                 }
             }
@@ -363,10 +619,41 @@ async fn handle_event_receiver(
     // TODO: Handle closing of network events sender
 }
 
-/// Events that can be broadcasted by the client.
+/// Events that can be broadcasted by the client, see [`Client::enable_client_events`].
 #[derive(Debug, Clone)]
 pub enum ClientEvent {
+    /// An upload completed, summing up all of its chunks and payments.
     UploadComplete(UploadSummary),
+    /// The number of chunk uploads allowed in flight at once changed, as puts sped up or started failing.
+    UploadConcurrencyChanged(usize),
+    /// A chunk was stored by the nodes paid to store it.
+    ChunkUploaded { address: XorName, bytes: usize },
+    /// A stored chunk was verified to be held by the nodes close to it. Only sent for the chunks
+    /// [`upload_cfg::UploadCfg::verification`] picks.
+    ChunkVerified { address: XorName },
+    /// Storage of records was paid for.
+    PaymentMade {
+        record_count: usize,
+        cost: AttoTokens,
+    },
+    /// Chunks about to be uploaded were found stored already, so weren't paid for, see
+    /// [`upload_cfg::UploadCfg::dedup_check`].
+    ChunksAlreadyStored { count: usize, bytes: usize },
+    /// A chunk was fetched from the network.
+    ChunkFetched { address: XorName, bytes: usize },
+    /// Uploading a chunk failed, and will be retried.
+    RetryScheduled {
+        address: XorName,
+        /// The attempt that is scheduled, starting at 2 for the first retry.
+        attempt: usize,
+    },
+    /// An operation failed on the network, and will be retried, see [`ClientConfig::retry_strategy`].
+    OperationRetried {
+        /// What is retried, e.g. `"store quote"`.
+        operation: &'static str,
+        /// The attempt that is scheduled, starting at 2 for the first retry.
+        attempt: usize,
+    },
 }
 
 /// Summary of an upload operation.
@@ -0,0 +1,232 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{data::CostError, Client};
+use ant_evm::payment_vault::get_market_price;
+use ant_evm::{Amount, QuotingMetrics, U256};
+use ant_networking::NetworkError;
+use ant_protocol::{convert_distance_to_u256, storage::ChunkAddress, NetworkAddress};
+use libp2p::PeerId;
+use std::collections::HashMap;
+use xor_name::XorName;
+
+/// The state of the whole network, extrapolated from the close groups of a sample of addresses, see
+/// [`Client::network_stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkStats {
+    /// The sampled addresses whose close group answered.
+    pub samples: usize,
+    /// The distinct nodes that answered.
+    pub nodes_queried: usize,
+    /// The median of the network sizes estimated by the nodes.
+    pub estimated_network_size: Option<u64>,
+    /// The records the nodes are responsible for, on average.
+    pub average_records_per_node: f64,
+    /// The distinct records stored in the network, `None` if no node could tell the network size.
+    pub estimated_total_records: Option<u64>,
+    /// The nodes holding a copy of a record, on average.
+    pub average_replication: f64,
+    /// The median price quoted to store a record.
+    pub median_price: Amount,
+}
+
+/// A quote of a node of the close group of a sampled address.
+#[derive(Debug, Clone)]
+struct SampledQuote {
+    peer: PeerId,
+    metrics: QuotingMetrics,
+    price: Amount,
+}
+
+impl Client {
+    /// Estimate the size, the content and the prices of the network, from the quotes of the close groups of
+    /// `samples` addresses spread over the keyspace. Nothing is paid nor stored.
+    ///
+    /// The more samples, the closer the estimation, each sample querying a close group for its quotes.
+    pub async fn network_stats(&self, samples: usize) -> Result<NetworkStats, CostError> {
+        let futures = sample_addresses(samples)
+            .into_iter()
+            .map(|content_addr| async move {
+                let addr = NetworkAddress::from_chunk_address(ChunkAddress::new(content_addr));
+                let quotes = match self
+                    .network
+                    .get_store_quote_from_network(addr.clone(), vec![])
+                    .await
+                {
+                    Ok(quotes) if !quotes.is_empty() => quotes,
+                    Ok(_) => return None,
+                    Err(err) => {
+                        warn!("Could not sample the close group of {addr:?}: {err:?}");
+                        return None;
+                    }
+                };
+                let metrics: Vec<_> = quotes
+                    .iter()
+                    .map(|(_, quote)| quote.quoting_metrics.clone())
+                    .collect();
+                let prices = match get_market_price(&self.evm_network, metrics).await {
+                    Ok(prices) => prices,
+                    Err(err) => {
+                        warn!("Could not price the quotes of the close group of {addr:?}: {err:?}");
+                        return None;
+                    }
+                };
+                let quotes = quotes
+                    .into_iter()
+                    .zip(prices)
+                    .map(|((peer, quote), price)| SampledQuote {
+                        peer,
+                        metrics: quote.quoting_metrics,
+                        price,
+                    })
+                    .collect::<Vec<_>>();
+                Some((addr, quotes))
+            });
+        let sampled: Vec<_> = self
+            .cancellable(async { Ok::<_, CostError>(futures::future::join_all(futures).await) })
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let stats = estimate(&sampled).ok_or(CostError::CouldNotGetStoreCosts(
+            NetworkError::NoStoreCostResponses,
+        ))?;
+        info!("Estimated the network stats from {samples} samples: {stats:?}");
+        Ok(stats)
+    }
+}
+
+/// Addresses spread evenly over the keyspace, each at random within its own range.
+fn sample_addresses(samples: usize) -> Vec<XorName> {
+    let mut rng = rand::thread_rng();
+    (0..samples)
+        .map(|i| {
+            let mut addr = XorName::random(&mut rng);
+            let prefix = ((i as u64 * (u16::MAX as u64 + 1)) / samples as u64) as u16;
+            addr.0[..2].copy_from_slice(&prefix.to_be_bytes());
+            addr
+        })
+        .collect()
+}
+
+/// Whether the node holds the records at the address, being within its responsible range. A node without one yet
+/// holds all of the records it's close to.
+fn holds(addr: &NetworkAddress, quote: &SampledQuote) -> bool {
+    let Some(range) = quote.metrics.network_density else {
+        return true;
+    };
+    let distance = convert_distance_to_u256(&NetworkAddress::from_peer(quote.peer).distance(addr));
+    distance <= U256::from_be_bytes(range)
+}
+
+fn median<T: Ord + Copy>(mut values: Vec<T>) -> Option<T> {
+    values.sort();
+    values.get(values.len() / 2).copied()
+}
+
+/// Extrapolate the stats of the network from the sampled close groups.
+///
+/// Every node holds the records of its responsible range, so all the nodes together hold each record as many times
+/// as it is replicated: the records are the records of all the nodes divided by the replication.
+fn estimate(sampled: &[(NetworkAddress, Vec<SampledQuote>)]) -> Option<NetworkStats> {
+    if sampled.is_empty() {
+        return None;
+    }
+
+    // The nodes answering for several samples are counted once
+    let nodes: HashMap<PeerId, &QuotingMetrics> = sampled
+        .iter()
+        .flat_map(|(_, quotes)| quotes.iter().map(|quote| (quote.peer, &quote.metrics)))
+        .collect();
+    let average_records_per_node = nodes
+        .values()
+        .map(|metrics| metrics.close_records_stored as f64)
+        .sum::<f64>()
+        / nodes.len() as f64;
+    let estimated_network_size = median(
+        nodes
+            .values()
+            .filter_map(|metrics| metrics.network_size)
+            .collect(),
+    );
+
+    let average_replication = sampled
+        .iter()
+        .map(|(addr, quotes)| quotes.iter().filter(|quote| holds(addr, quote)).count() as f64)
+        .sum::<f64>()
+        / sampled.len() as f64;
+    let estimated_total_records = estimated_network_size
+        .filter(|_| average_replication > 0.0)
+        .map(|size| (size as f64 * average_records_per_node / average_replication).round() as u64);
+
+    let median_price = median(
+        sampled
+            .iter()
+            .flat_map(|(_, quotes)| quotes.iter().map(|quote| quote.price))
+            .collect(),
+    )
+    .unwrap_or_default();
+
+    Some(NetworkStats {
+        samples: sampled.len(),
+        nodes_queried: nodes.len(),
+        estimated_network_size,
+        average_records_per_node,
+        estimated_total_records,
+        average_replication,
+        median_price,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(records: usize, network_size: u64, price: u64) -> SampledQuote {
+        SampledQuote {
+            peer: PeerId::random(),
+            metrics: QuotingMetrics {
+                close_records_stored: records,
+                network_size: Some(network_size),
+                ..Default::default()
+            },
+            price: Amount::from(price),
+        }
+    }
+
+    #[test]
+    fn stats_are_extrapolated_from_the_close_groups() {
+        let addr = |content: &[u8]| {
+            NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::from_content(content)))
+        };
+        let first = vec![quote(100, 1000, 10), quote(200, 1000, 30)];
+        let mut second = vec![quote(300, 3000, 20), first[0].clone()];
+        // Out of its responsible range, the node doesn't hold the records of the address
+        second[0].metrics.network_density = Some([0; 32]);
+        let stats = estimate(&[(addr(b"first"), first), (addr(b"second"), second)]);
+
+        let stats = stats.expect("samples were given");
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.nodes_queried, 3);
+        assert_eq!(stats.estimated_network_size, Some(1000));
+        assert_eq!(stats.average_records_per_node, 200.0);
+        assert_eq!(stats.average_replication, 1.5);
+        assert_eq!(stats.estimated_total_records, Some(133_333));
+        assert_eq!(stats.median_price, Amount::from(20));
+
+        assert_eq!(estimate(&[]), None);
+    }
+
+    #[test]
+    fn samples_are_spread_over_the_keyspace() {
+        let addrs = sample_addresses(4);
+        let prefixes: Vec<_> = addrs.iter().map(|addr| addr.0[0]).collect();
+        assert_eq!(prefixes, vec![0x00, 0x40, 0x80, 0xc0]);
+    }
+}
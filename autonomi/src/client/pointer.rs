@@ -0,0 +1,235 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::data::PayError;
+use crate::client::Client;
+use crate::client::ClientEvent;
+use crate::client::UploadSummary;
+
+use ant_evm::{Amount, AttoTokens, EvmWallet};
+use ant_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
+pub use ant_protocol::storage::{Pointer, PointerAddress, PointerTarget};
+use ant_protocol::{
+    storage::{try_deserialize_record, try_serialize_record, RecordKind, RetryStrategy},
+    NetworkAddress,
+};
+pub use bls::SecretKey;
+use libp2p::kad::{Quorum, Record};
+use std::collections::HashSet;
+use xor_name::XorName;
+
+use super::data::CostError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PointerError {
+    #[error("Cost error: {0}")]
+    Cost(#[from] CostError),
+    #[error("Network error")]
+    Network(#[from] NetworkError),
+    #[error("Serialization error")]
+    Serialization,
+    #[error("Pointer could not be verified (corrupt)")]
+    FailedVerification,
+    #[error("Payment failure occurred during pointer creation.")]
+    Pay(#[from] PayError),
+    #[error("Pointer already exists at this address: {0:?}")]
+    PointerAlreadyExists(PointerAddress),
+}
+
+impl Client {
+    /// Fetches the latest version of a Pointer from the network.
+    pub async fn pointer_get(&self, address: PointerAddress) -> Result<Pointer, PointerError> {
+        let key = NetworkAddress::from_pointer_address(address).to_record_key();
+        let get_cfg = self.get_cfg.apply(GetRecordCfg {
+            get_quorum: Quorum::Majority,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: HashSet::new(),
+            is_register: false,
+        });
+
+        let (pointer, fetched) = match self.network.get_record_from_network(key, &get_cfg).await {
+            Ok(fetched) => (
+                try_deserialize_record::<Pointer>(&fetched.record)
+                    .map_err(|_| PointerError::Serialization)?,
+                Some(fetched),
+            ),
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord {
+                result_map, ..
+            })) => {
+                debug!("Got multiple pointers for {address:?}, picking the latest version");
+                let pointer = result_map
+                    .values()
+                    .filter_map(|(record, _)| try_deserialize_record::<Pointer>(record).ok())
+                    .filter(|pointer| pointer.verify())
+                    .max_by_key(|pointer| pointer.count())
+                    .ok_or(PointerError::FailedVerification)?;
+                (pointer, None)
+            }
+            Err(err) => {
+                warn!("Failed to fetch pointer {address:?} from network: {err}");
+                return Err(err.into());
+            }
+        };
+
+        if pointer.address() != &address || !pointer.verify() {
+            error!("Pointer fetched from {address:?} failed verification");
+            return Err(PointerError::FailedVerification);
+        }
+        if let Some(fetched) = fetched {
+            self.read_repair(fetched);
+        }
+        Ok(pointer)
+    }
+
+    /// Stores a new Pointer on the network, paying for it.
+    ///
+    /// Use [`Client::pointer_update`] to change what an existing pointer points at.
+    pub async fn pointer_put(
+        &self,
+        pointer: Pointer,
+        wallet: &EvmWallet,
+    ) -> Result<(), PointerError> {
+        let address = *pointer.address();
+
+        // pay for the pointer
+        let xor_name = address.xorname();
+        debug!("Paying for pointer at address: {address:?}");
+        let payment_proofs = self
+            .pay(std::iter::once(xor_name), wallet)
+            .await
+            .inspect_err(|err| {
+                error!("Failed to pay for pointer at address: {address:?} : {err}")
+            })?;
+
+        // make sure the pointer was paid for
+        let (proof, price) = match payment_proofs.get(&xor_name) {
+            Some((proof, price)) => (proof, price),
+            None => {
+                // pointer was skipped, meaning it was already paid for
+                error!("Pointer at address: {address:?} was already paid for");
+                return Err(PointerError::PointerAlreadyExists(address));
+            }
+        };
+
+        // prepare the record for network storage
+        let payees = proof.payees();
+        let record = Record {
+            key: NetworkAddress::from_pointer_address(address).to_record_key(),
+            value: try_serialize_record(&(proof, &pointer), RecordKind::PointerWithPayment)
+                .map_err(|_| PointerError::Serialization)?
+                .to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::Majority,
+            retry_strategy: Some(RetryStrategy::default()),
+            target_record: None,
+            expected_holders: Default::default(),
+            is_register: false,
+        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::All,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: Some(payees),
+                verification: Some((VerificationKind::Crdt, get_cfg)),
+            },
+            &record,
+        );
+
+        // put the record to the network
+        debug!("Storing pointer at address {address:?} to the network");
+        self.network
+            .put_record(record, &put_cfg)
+            .await
+            .inspect_err(|err| {
+                error!("Failed to put record - pointer {address:?} to the network: {err}")
+            })?;
+
+        // send client event
+        let summary = UploadSummary {
+            record_count: 1,
+            tokens_spent: price.as_atto(),
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
+
+        Ok(())
+    }
+
+    /// Points an existing Pointer of the owner at a new target. Updates are free, only the creation is paid for.
+    pub async fn pointer_update(
+        &self,
+        owner: &SecretKey,
+        name: XorName,
+        target: PointerTarget,
+    ) -> Result<Pointer, PointerError> {
+        let address = PointerAddress::new(owner.public_key(), name);
+        let current = self.pointer_get(address).await?;
+        let pointer = Pointer::new(owner, name, current.count() + 1, target);
+
+        let record = Record {
+            key: NetworkAddress::from_pointer_address(address).to_record_key(),
+            value: try_serialize_record(&pointer, RecordKind::Pointer)
+                .map_err(|_| PointerError::Serialization)?
+                .to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::Majority,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: None,
+                verification: Some((
+                    VerificationKind::Crdt,
+                    GetRecordCfg {
+                        get_quorum: Quorum::Majority,
+                        retry_strategy: None,
+                        target_record: None,
+                        expected_holders: HashSet::new(),
+                        is_register: false,
+                    },
+                )),
+            },
+            &record,
+        );
+
+        debug!(
+            "Updating pointer at address {address:?} to version {}",
+            pointer.count()
+        );
+        self.network
+            .put_record(record, &put_cfg)
+            .await
+            .inspect_err(|err| {
+                error!("Failed to update pointer {address:?} on the network: {err}")
+            })?;
+
+        Ok(pointer)
+    }
+
+    /// Get the cost to create a pointer
+    pub async fn pointer_cost(&self, address: PointerAddress) -> Result<AttoTokens, PointerError> {
+        trace!("Getting cost for pointer at {address:?}");
+
+        let store_quote = self
+            .get_store_quotes(std::iter::once(address.xorname()))
+            .await?;
+        let total_cost = AttoTokens::from_atto(
+            store_quote
+                .0
+                .values()
+                .map(|quote| quote.price())
+                .sum::<Amount>(),
+        );
+        debug!("Calculated the cost to create pointer at {address:?} is {total_cost}");
+        Ok(total_cost)
+    }
+}
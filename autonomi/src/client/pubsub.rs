@@ -0,0 +1,183 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::Client;
+
+use ant_networking::MAX_PACKET_SIZE;
+use bls::Signature;
+pub use bls::{PublicKey, SecretKey};
+use bytes::Bytes;
+use futures::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+/// The prefix of the app topics, keeping them apart from the ones the network uses internally.
+const TOPIC_PREFIX: &str = "app/";
+
+/// The bytes of the envelope on top of the payload, leaving room for the gossipsub overhead.
+const ENVELOPE_OVERHEAD: usize = 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PubSubError {
+    #[error("Serialization error")]
+    Serialization,
+    #[error("The message of {0} bytes exceeds the maximum of {1} bytes")]
+    MessageTooLarge(usize, usize),
+}
+
+/// A message published on a topic, signed by its publisher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PubSubMessage {
+    pub topic: String,
+    /// The key the message was signed with, identifying its publisher
+    pub publisher: PublicKey,
+    pub payload: Bytes,
+}
+
+/// The message as sent over gossipsub.
+#[derive(Debug, Serialize, Deserialize)]
+struct SignedEnvelope {
+    publisher: PublicKey,
+    payload: Bytes,
+    /// Messages are deduplicated by content, so a publisher can send the same payload twice.
+    nonce: u64,
+    signature: Signature,
+}
+
+impl SignedEnvelope {
+    fn new(topic: &str, payload: Bytes, secret_key: &SecretKey) -> Self {
+        let nonce = rand::random();
+        let signature = secret_key.sign(Self::bytes_for_signature(topic, &payload, nonce));
+        Self {
+            publisher: secret_key.public_key(),
+            payload,
+            nonce,
+            signature,
+        }
+    }
+
+    /// The topic is signed too, for a message not to be replayed on another topic.
+    fn bytes_for_signature(topic: &str, payload: &[u8], nonce: u64) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(topic.len() + payload.len() + 8);
+        bytes.extend_from_slice(topic.as_bytes());
+        bytes.extend_from_slice(&nonce.to_be_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    /// The message, if the envelope was signed by its publisher for this topic.
+    fn into_verified_message(self, topic: &str) -> Option<PubSubMessage> {
+        let bytes = Self::bytes_for_signature(topic, &self.payload, self.nonce);
+        if !self.publisher.verify(&self.signature, bytes) {
+            return None;
+        }
+        Some(PubSubMessage {
+            topic: topic.to_string(),
+            publisher: self.publisher,
+            payload: self.payload,
+        })
+    }
+}
+
+fn network_topic(topic: &str) -> String {
+    format!("{TOPIC_PREFIX}{topic}")
+}
+
+impl Client {
+    /// Subscribe to a topic, returning a stream of the messages published on it from now on.
+    ///
+    /// Only the messages correctly signed by their publisher are surfaced, along with the publisher's key, as
+    /// anyone can publish on any topic: filter on [`PubSubMessage::publisher`] to only trust some of them.
+    /// Messages are delivered on a best effort basis, through the peers subscribed to the topic. The subscription
+    /// lasts until [`Client::unsubscribe`] is called.
+    pub fn subscribe(&self, topic: &str) -> impl Stream<Item = PubSubMessage> {
+        let msgs = self.gossip_sender.subscribe();
+        let network_topic = network_topic(topic);
+        self.network.subscribe_to_topic(network_topic.clone());
+        info!("Subscribed to topic {topic}");
+
+        let state = (msgs, topic.to_string(), network_topic);
+        stream::unfold(state, |(mut msgs, topic, network_topic)| async move {
+            loop {
+                match msgs.recv().await {
+                    Ok((msg_topic, msg)) if msg_topic == network_topic => {
+                        let message = rmp_serde::from_slice::<SignedEnvelope>(&msg)
+                            .ok()
+                            .and_then(|envelope| envelope.into_verified_message(&topic));
+                        match message {
+                            Some(message) => return Some((message, (msgs, topic, network_topic))),
+                            None => warn!("Dropping an unverifiable msg on topic {topic}"),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Missed {missed} gossipsub msgs while subscribed to {topic}");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Stop receiving the messages of a topic subscribed to with [`Client::subscribe`].
+    pub fn unsubscribe(&self, topic: &str) {
+        self.network.unsubscribe_from_topic(network_topic(topic));
+        info!("Unsubscribed from topic {topic}");
+    }
+
+    /// Publish a message on a topic, signed with the given key, to the peers subscribed to it.
+    pub fn publish(
+        &self,
+        topic: &str,
+        payload: Bytes,
+        secret_key: &SecretKey,
+    ) -> Result<(), PubSubError> {
+        let max_size = MAX_PACKET_SIZE - ENVELOPE_OVERHEAD;
+        if payload.len() > max_size {
+            return Err(PubSubError::MessageTooLarge(payload.len(), max_size));
+        }
+        let envelope = SignedEnvelope::new(topic, payload, secret_key);
+        let msg = rmp_serde::to_vec(&envelope).map_err(|_| PubSubError::Serialization)?;
+        debug!("Publishing a msg of {} bytes on topic {topic}", msg.len());
+        self.network
+            .publish_on_topic(network_topic(topic), Bytes::from(msg));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_messages_signed_for_the_topic_are_verified() {
+        let secret_key = SecretKey::random();
+        let payload = Bytes::from_static(b"hello");
+        let envelope = SignedEnvelope::new("chat", payload.clone(), &secret_key);
+        let bytes = rmp_serde::to_vec(&envelope).expect("envelope to serialize");
+
+        let decoded: SignedEnvelope =
+            rmp_serde::from_slice(&bytes).expect("envelope to deserialize");
+        assert_eq!(
+            decoded.into_verified_message("chat"),
+            Some(PubSubMessage {
+                topic: "chat".to_string(),
+                publisher: secret_key.public_key(),
+                payload: payload.clone(),
+            })
+        );
+
+        let replayed: SignedEnvelope =
+            rmp_serde::from_slice(&bytes).expect("envelope to deserialize");
+        assert_eq!(replayed.into_verified_message("other"), None);
+
+        let mut forged = SignedEnvelope::new("chat", payload, &secret_key);
+        forged.publisher = SecretKey::random().public_key();
+        assert_eq!(forged.into_verified_message("chat"), None);
+    }
+}
@@ -9,13 +9,16 @@
 use super::{data::CostError, Client};
 use crate::client::rate_limiter::RateLimiter;
 use ant_evm::payment_vault::get_market_price;
-use ant_evm::{Amount, EvmNetwork, PaymentQuote, QuotePayment, QuotingMetrics};
+use ant_evm::{Amount, PaymentQuote, QuotePayment, QuotingMetrics};
 use ant_networking::{Network, NetworkError};
 use ant_protocol::{storage::ChunkAddress, NetworkAddress, CLOSE_GROUP_SIZE};
 use libp2p::PeerId;
 use std::collections::HashMap;
 use xor_name::XorName;
 
+/// How many times cheaper or pricier than the median a quote can be before being rejected as an outlier.
+const OUTLIER_PRICE_FACTOR: u64 = 10;
+
 /// A quote for a single address
 pub struct QuoteForAddress(pub(crate) Vec<(PeerId, PaymentQuote, Amount)>);
 
@@ -41,28 +44,60 @@ impl StoreQuote {
         self.0.is_empty()
     }
 
+    /// The payments of the quotes, a quote reused for several addresses being paid once for all of them, as the
+    /// payment vault only records the last payment of a quote hash.
     pub fn payments(&self) -> Vec<QuotePayment> {
-        let mut quote_payments = vec![];
+        let mut quote_payments: Vec<QuotePayment> = vec![];
+        let mut payment_index_of_quote: HashMap<_, usize> = HashMap::new();
         for (_address, quote) in self.0.iter() {
             for (_peer, quote, price) in quote.0.iter() {
-                quote_payments.push((quote.hash(), quote.rewards_address, *price));
+                let quote_hash = quote.hash();
+                match payment_index_of_quote.get(&quote_hash) {
+                    Some(&index) => {
+                        let (_, _, paid) = &mut quote_payments[index];
+                        *paid = paid.saturating_add(*price);
+                    }
+                    None => {
+                        let _ = payment_index_of_quote.insert(quote_hash, quote_payments.len());
+                        quote_payments.push((quote_hash, quote.rewards_address, *price));
+                    }
+                }
             }
         }
         quote_payments
     }
 }
 
+/// The quotes of the close group of an address, either fetched from its nodes or reused from the cache along with
+/// their price.
+enum AddrQuotes {
+    Fetched(Vec<(PeerId, PaymentQuote)>),
+    Cached(Vec<(PeerId, PaymentQuote, Amount)>),
+}
+
 impl Client {
     pub(crate) async fn get_store_quotes(
         &self,
         content_addrs: impl Iterator<Item = XorName>,
     ) -> Result<StoreQuote, CostError> {
-        // get all quotes from nodes
+        // get all quotes from nodes, but the ones cached for the whole close group
         let futures: Vec<_> = content_addrs
             .into_iter()
-            .map(|content_addr| fetch_store_quote_with_retries(&self.network, content_addr))
+            .map(|content_addr| async move {
+                self.record_recent_addr(NetworkAddress::from_chunk_address(ChunkAddress::new(
+                    content_addr,
+                )));
+                if let Some(prices) = self.cached_store_quote(content_addr).await {
+                    return Ok((content_addr, AddrQuotes::Cached(prices)));
+                }
+                let (content_addr, raw_quotes) =
+                    self.fetch_store_quote_with_retries(content_addr).await?;
+                Ok::<_, CostError>((content_addr, AddrQuotes::Fetched(raw_quotes)))
+            })
             .collect();
-        let raw_quotes_per_addr = futures::future::try_join_all(futures).await?;
+        let raw_quotes_per_addr = self
+            .cancellable(futures::future::try_join_all(futures))
+            .await?;
 
         // choose the quotes to pay for each address
         let mut quotes_to_pay_per_addr = HashMap::new();
@@ -70,6 +105,14 @@ impl Client {
         let mut rate_limiter = RateLimiter::new();
 
         for (content_addr, raw_quotes) in raw_quotes_per_addr {
+            let raw_quotes = match raw_quotes {
+                AddrQuotes::Fetched(raw_quotes) => raw_quotes,
+                AddrQuotes::Cached(cached_prices) => {
+                    select_quotes_to_pay(&mut quotes_to_pay_per_addr, content_addr, cached_prices)?;
+                    continue;
+                }
+            };
+
             // FIXME: find better way to deal with paid content addrs and feedback to the user
             // assume that content addr is already paid for and uploaded
             if raw_quotes.is_empty() {
@@ -83,57 +126,88 @@ impl Client {
                 .map(|(_, q)| q.quoting_metrics.clone())
                 .collect();
 
-            let all_prices = get_market_price_with_rate_limiter_and_retries(
-                &self.evm_network,
-                &mut rate_limiter,
-                quoting_metrics.clone(),
-            )
-            .await?;
+            rate_limiter.wait_interval_since_last_request(1000).await;
+            let all_prices = self
+                .cancellable(async {
+                    Ok::<_, CostError>(
+                        self.with_retries(
+                            "quote market price",
+                            self.retry_strategy,
+                            || get_market_price(&self.evm_network, quoting_metrics.clone()),
+                            |result| matches!(result, Err(err) if err.to_string().contains("429")),
+                        )
+                        .await?,
+                    )
+                })
+                .await
+                .inspect_err(|err| error!("Error while fetching quote market price: {err:?}"))?;
 
-            let mut prices: Vec<(PeerId, PaymentQuote, Amount)> = all_prices
+            let prices: Vec<(PeerId, PaymentQuote, Amount)> = all_prices
                 .into_iter()
                 .zip(raw_quotes.into_iter())
                 .map(|(price, (peer, quote))| (peer, quote, price))
                 .collect();
+            self.quote_cache.insert(&prices);
 
-            // sort by price
-            prices.sort_by(|(_, _, price_a), (_, _, price_b)| price_a.cmp(price_b));
-
-            // we need at least 5 valid quotes to pay for the data
-            const MINIMUM_QUOTES_TO_PAY: usize = 5;
-            match &prices[..] {
-                [first, second, third, fourth, fifth, ..] => {
-                    let (p1, q1, _) = first;
-                    let (p2, q2, _) = second;
-
-                    // don't pay for the cheapest 2 quotes but include them
-                    let first = (*p1, q1.clone(), Amount::ZERO);
-                    let second = (*p2, q2.clone(), Amount::ZERO);
-
-                    // pay for the rest
-                    quotes_to_pay_per_addr.insert(
-                        content_addr,
-                        QuoteForAddress(vec![
-                            first,
-                            second,
-                            third.clone(),
-                            fourth.clone(),
-                            fifth.clone(),
-                        ]),
-                    );
-                }
-                _ => {
-                    return Err(CostError::NotEnoughNodeQuotes(
-                        content_addr,
-                        prices.len(),
-                        MINIMUM_QUOTES_TO_PAY,
-                    ));
-                }
-            }
+            select_quotes_to_pay(&mut quotes_to_pay_per_addr, content_addr, prices)?;
         }
 
         Ok(StoreQuote(quotes_to_pay_per_addr))
     }
+
+    /// The cached quotes of the close group of a content address, if every node in it has one to reuse, see
+    /// [`super::ClientConfig::quote_cache`].
+    async fn cached_store_quote(
+        &self,
+        content_addr: XorName,
+    ) -> Option<Vec<(PeerId, PaymentQuote, Amount)>> {
+        if !self.quote_cache.is_enabled() {
+            return None;
+        }
+        let addr = NetworkAddress::from_chunk_address(ChunkAddress::new(content_addr));
+        let peers = self
+            .get_closest_peers(addr)
+            .await
+            .inspect_err(|err| {
+                warn!("Error while looking up the close group of {content_addr:?}: {err:?}")
+            })
+            .ok()?;
+        if peers.len() < CLOSE_GROUP_SIZE {
+            return None;
+        }
+        let prices = self.quote_cache.quotes_of(&peers[..CLOSE_GROUP_SIZE])?;
+        debug!("Reusing the cached quotes of the close group of {content_addr:?}");
+        Some(prices)
+    }
+
+    /// Fetch a store quote for a content address, retrying on errors and on too few quotes.
+    ///
+    /// No quotes at all means the content is already stored, which isn't retried.
+    async fn fetch_store_quote_with_retries(
+        &self,
+        content_addr: XorName,
+    ) -> Result<(XorName, Vec<(PeerId, PaymentQuote)>), CostError> {
+        let quotes = self
+            .with_retries(
+                "store quote",
+                self.retry_strategy,
+                || fetch_store_quote(&self.network, content_addr),
+                |result| match result {
+                    Ok(quotes) if !quotes.is_empty() && quotes.len() < CLOSE_GROUP_SIZE => {
+                        error!("Error while fetching store quote: not enough quotes ({}/{CLOSE_GROUP_SIZE}), quotes {quotes:?}", quotes.len());
+                        true
+                    }
+                    Ok(_) => false,
+                    Err(err) => {
+                        error!("Error while fetching store quote: {err:?}");
+                        true
+                    }
+                },
+            )
+            .await
+            .map_err(|_| CostError::CouldNotGetStoreQuote(content_addr))?;
+        Ok((content_addr, quotes))
+    }
 }
 
 /// Fetch a store quote for a content address.
@@ -149,72 +223,113 @@ async fn fetch_store_quote(
         .await
 }
 
-/// Fetch a store quote for a content address with a retry strategy.
-async fn fetch_store_quote_with_retries(
-    network: &Network,
+/// Choose the quotes to pay for an address among the priced ones of its close group.
+fn select_quotes_to_pay(
+    quotes_to_pay_per_addr: &mut HashMap<XorName, QuoteForAddress>,
     content_addr: XorName,
-) -> Result<(XorName, Vec<(PeerId, PaymentQuote)>), CostError> {
-    let mut retries = 0;
-
-    loop {
-        match fetch_store_quote(network, content_addr).await {
-            Ok(quote) => {
-                if quote.len() < CLOSE_GROUP_SIZE {
-                    retries += 1;
-                    error!("Error while fetching store quote: not enough quotes ({}/{CLOSE_GROUP_SIZE}), retry #{retries}, quotes {quote:?}",
-                        quote.len());
-                    if retries > 2 {
-                        break Err(CostError::CouldNotGetStoreQuote(content_addr));
-                    }
-                }
-                break Ok((content_addr, quote));
-            }
-            Err(err) if retries < 2 => {
-                retries += 1;
-                error!("Error while fetching store quote: {err:?}, retry #{retries}");
-            }
-            Err(err) => {
-                error!(
-                    "Error while fetching store quote: {err:?}, stopping after {retries} retries"
-                );
-                break Err(CostError::CouldNotGetStoreQuote(content_addr));
-            }
+    mut prices: Vec<(PeerId, PaymentQuote, Amount)>,
+) -> Result<(), CostError> {
+    // sort by price
+    prices.sort_by_key(|(_, _, price)| *price);
+    let prices = reject_price_outliers(prices);
+
+    // we need at least 5 valid quotes to pay for the data
+    const MINIMUM_QUOTES_TO_PAY: usize = 5;
+    match &prices[..] {
+        [first, second, third, fourth, fifth, ..] => {
+            let (p1, q1, _) = first;
+            let (p2, q2, _) = second;
+
+            // don't pay for the cheapest 2 quotes but include them
+            let first = (*p1, q1.clone(), Amount::ZERO);
+            let second = (*p2, q2.clone(), Amount::ZERO);
+
+            // pay for the rest
+            quotes_to_pay_per_addr.insert(
+                content_addr,
+                QuoteForAddress(vec![
+                    first,
+                    second,
+                    third.clone(),
+                    fourth.clone(),
+                    fifth.clone(),
+                ]),
+            );
+            Ok(())
         }
-        // Shall have a sleep between retries to avoid choking the network.
-        // This shall be rare to happen though.
-        std::thread::sleep(std::time::Duration::from_secs(5));
+        _ => Err(CostError::NotEnoughNodeQuotes(
+            content_addr,
+            prices.len(),
+            MINIMUM_QUOTES_TO_PAY,
+        )),
     }
 }
 
-async fn get_market_price_with_rate_limiter_and_retries(
-    evm_network: &EvmNetwork,
-    rate_limiter: &mut RateLimiter,
-    quoting_metrics: Vec<QuotingMetrics>,
-) -> Result<Vec<Amount>, ant_evm::payment_vault::error::Error> {
-    const MAX_RETRIES: u64 = 2;
-    let mut retries: u64 = 0;
-    let mut interval_in_ms: u64 = 1000;
-
-    loop {
-        rate_limiter
-            .wait_interval_since_last_request(interval_in_ms)
-            .await;
-
-        match get_market_price(evm_network, quoting_metrics.clone()).await {
-            Ok(amounts) => {
-                break Ok(amounts);
-            }
-            Err(err) => {
-                if err.to_string().contains("429") && retries < MAX_RETRIES {
-                    retries += 1;
-                    interval_in_ms *= retries * 2;
-                    error!("Error while fetching quote market price: {err:?}, retry #{retries}");
-                    continue;
-                } else {
-                    error!("Error while fetching quote market price: {err:?}, stopping after {retries} retries");
-                    break Err(err);
-                }
+/// Drop the quotes priced `OUTLIER_PRICE_FACTOR` times away from the median, for a single node
+/// quoting nonsense not to skew the payment. The prices must be sorted.
+fn reject_price_outliers(
+    prices: Vec<(PeerId, PaymentQuote, Amount)>,
+) -> Vec<(PeerId, PaymentQuote, Amount)> {
+    let median = match prices.get(prices.len() / 2) {
+        Some((_, _, median)) if !median.is_zero() => *median,
+        _ => return prices,
+    };
+    let factor = Amount::from(OUTLIER_PRICE_FACTOR);
+    prices
+        .into_iter()
+        .filter(|(peer, _, price)| {
+            let is_outlier =
+                *price > median.saturating_mul(factor) || price.saturating_mul(factor) < median;
+            if is_outlier {
+                warn!(
+                    "Rejecting the quote of {peer:?} at {price}, far from the median of {median}"
+                );
             }
-        }
+            !is_outlier
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_far_from_the_median_price_are_rejected() {
+        let prices: Vec<_> = [1u64, 95, 100, 100, 105, 120, 5000]
+            .into_iter()
+            .map(|price| (PeerId::random(), PaymentQuote::zero(), Amount::from(price)))
+            .collect();
+
+        let kept: Vec<_> = reject_price_outliers(prices)
+            .into_iter()
+            .map(|(_, _, price)| price)
+            .collect();
+        assert_eq!(
+            kept,
+            [95u64, 100, 100, 105, 120]
+                .into_iter()
+                .map(Amount::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn quotes_reused_across_addresses_are_paid_once() {
+        let mut reused = PaymentQuote::zero();
+        reused.expires_at = Some(reused.timestamp + std::time::Duration::from_secs(3600));
+        let peer = PeerId::random();
+        let quote_for_address =
+            |price: u64| QuoteForAddress(vec![(peer, reused.clone(), Amount::from(price))]);
+        let store_quote = StoreQuote(HashMap::from([
+            (XorName::from_content(b"first"), quote_for_address(10)),
+            (XorName::from_content(b"second"), quote_for_address(20)),
+        ]));
+
+        let payments = store_quote.payments();
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].0, reused.hash());
+        assert_eq!(payments[0].2, Amount::from(30));
+        assert_eq!(store_quote.price(), Amount::from(30));
     }
 }
@@ -0,0 +1,130 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ant_evm::{Amount, PaymentQuote};
+use libp2p::PeerId;
+
+/// How long before their expiry the cached quotes stop being reused, for the payment and the upload to be done by
+/// then.
+const QUOTE_EXPIRY_MARGIN: Duration = Duration::from_secs(10 * 60);
+
+/// The number of payees whose quotes are cached at most, the ones expiring the soonest being dropped first.
+const MAX_CACHED_QUOTES: usize = 4096;
+
+/// The signed quotes of the payees, along with their market price, reused to pay them for other addresses of the
+/// close groups they're in until the quotes expire, see [`super::ClientConfig::quote_cache`].
+///
+/// The nodes tell the price of storing a record from their metrics rather than from the record, and accept a quote
+/// with an expiry they signed for any of the records they're responsible for, see `PaymentQuote::is_payee_scoped`.
+/// Only those quotes are cached, and each of them is paid once for all of the addresses it's reused for, see
+/// [`super::quote::StoreQuote::payments`].
+#[derive(Debug)]
+pub(crate) struct QuoteCache {
+    enabled: bool,
+    quotes: Mutex<HashMap<PeerId, (PaymentQuote, Amount)>>,
+}
+
+impl QuoteCache {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            quotes: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The cached quotes of all of the payees, unless one of them has none to reuse.
+    pub(crate) fn quotes_of(
+        &self,
+        payees: &[PeerId],
+    ) -> Option<Vec<(PeerId, PaymentQuote, Amount)>> {
+        if !self.enabled || payees.is_empty() {
+            return None;
+        }
+        let quotes = self.quotes.lock().expect("lock poisoned");
+        payees
+            .iter()
+            .map(|peer| {
+                quotes
+                    .get(peer)
+                    .filter(|(quote, _)| quote.is_valid_for(QUOTE_EXPIRY_MARGIN))
+                    .map(|(quote, price)| (*peer, quote.clone(), *price))
+            })
+            .collect()
+    }
+
+    pub(crate) fn insert(&self, quotes: &[(PeerId, PaymentQuote, Amount)]) {
+        if !self.enabled {
+            return;
+        }
+        let mut cached = self.quotes.lock().expect("lock poisoned");
+        cached.retain(|_, (quote, _)| quote.is_valid_for(QUOTE_EXPIRY_MARGIN));
+        for (peer, quote, price) in quotes {
+            if quote.expires_at.is_none() || !quote.is_valid_for(QUOTE_EXPIRY_MARGIN) {
+                continue;
+            }
+            if cached.len() >= MAX_CACHED_QUOTES && !cached.contains_key(peer) {
+                let soonest = cached
+                    .iter()
+                    .min_by_key(|(_, (quote, _))| quote.expires_at)
+                    .map(|(peer, _)| *peer);
+                if let Some(soonest) = soonest {
+                    let _ = cached.remove(&soonest);
+                }
+            }
+            let _ = cached.insert(*peer, (quote.clone(), *price));
+        }
+    }
+}
+
+impl Default for QuoteCache {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(valid_for: Duration) -> PaymentQuote {
+        let mut quote = PaymentQuote::zero();
+        quote.expires_at = Some(quote.timestamp + valid_for);
+        quote
+    }
+
+    #[test]
+    fn quotes_are_reused_until_about_to_expire() {
+        let (fresh, expiring, unsigned) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let cache = QuoteCache::new(true);
+        cache.insert(&[
+            (fresh, quote(Duration::from_secs(3600)), Amount::from(10)),
+            (expiring, quote(Duration::from_secs(60)), Amount::from(10)),
+            (unsigned, PaymentQuote::zero(), Amount::from(10)),
+        ]);
+
+        let quotes = cache.quotes_of(&[fresh]).expect("quote to be cached");
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0].2, Amount::from(10));
+        // Any payee without a quote to reuse has all of them fetched again.
+        assert!(cache.quotes_of(&[fresh, expiring]).is_none());
+        assert!(cache.quotes_of(&[fresh, unsigned]).is_none());
+        assert!(cache.quotes_of(&[]).is_none());
+
+        let disabled = QuoteCache::new(false);
+        disabled.insert(&[(fresh, quote(Duration::from_secs(3600)), Amount::from(10))]);
+        assert!(disabled.quotes_of(&[fresh]).is_none());
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_networking::{GetRecordOk, PutRecordCfg};
+use ant_protocol::{NetworkAddress, PrettyPrintRecordKey, CLOSE_GROUP_SIZE};
+use libp2p::{kad::Quorum, PeerId};
+
+use super::Client;
+
+/// The close group members that clearly lack the fetched record: the ones that served another copy, and, if the
+/// fetch asked every member rather than stopping at the quorum, the ones that returned none.
+fn peers_lacking(fetched: &GetRecordOk, close_group: &[PeerId]) -> Vec<PeerId> {
+    let mut peers = fetched.divergent_peers();
+    if !fetched.completed_early {
+        peers.extend(
+            close_group
+                .iter()
+                .take(CLOSE_GROUP_SIZE)
+                .filter(|peer| !fetched.provenance.contains_key(peer)),
+        );
+    }
+    peers
+}
+
+impl Client {
+    /// Put the fetched record back to the close group members lacking it, in the background, if enabled with
+    /// [`super::record_cfg::GetCfg::read_repair`]. The record must have been verified already.
+    ///
+    /// The nodes only take a mutable record for free to update a copy they hold, so the members holding none are
+    /// left to the replication between nodes.
+    pub(crate) fn read_repair(&self, fetched: GetRecordOk) {
+        if !self.get_cfg.read_repair
+            || (fetched.completed_early && fetched.divergent_peers().is_empty())
+        {
+            return;
+        }
+
+        let client = self.clone();
+        let _handle = ant_networking::target_arch::spawn(async move {
+            let pretty_key = PrettyPrintRecordKey::from(&fetched.record.key).into_owned();
+            let close_group = if fetched.completed_early {
+                vec![]
+            } else {
+                let addr = NetworkAddress::from_record_key(&fetched.record.key);
+                client.get_closest_peers(addr).await.unwrap_or_default()
+            };
+            let peers = peers_lacking(&fetched, &close_group);
+            if peers.is_empty() {
+                return;
+            }
+
+            debug!("Read-repairing {pretty_key:?} to {peers:?}");
+            let put_cfg = PutRecordCfg {
+                put_quorum: Quorum::One,
+                retry_strategy: None,
+                use_put_record_to: Some(peers),
+                verification: None,
+            };
+            match client.network.put_record(fetched.record, &put_cfg).await {
+                Ok(repaired) => info!("Read-repaired {pretty_key:?} to {repaired:?}"),
+                Err(err) => warn!("Failed to read-repair {pretty_key:?}: {err}"),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::kad::{Record, RecordKey};
+    use xor_name::XorName;
+
+    #[test]
+    fn only_the_peers_clearly_lacking_the_record_are_repaired() {
+        let record = Record::new(RecordKey::new(b"pointer"), vec![1, 2, 3]);
+        let content_hash = XorName::from_content(&record.value);
+        let close_group: Vec<_> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let (holders, rest) = close_group.split_at(3);
+        let stale = rest[0];
+
+        let mut provenance: ant_networking::RecordProvenance =
+            holders.iter().map(|peer| (*peer, content_hash)).collect();
+        let _ = provenance.insert(stale, XorName::from_content(b"older copy"));
+        let mut fetched = GetRecordOk {
+            record,
+            provenance,
+            completed_early: true,
+            merged: false,
+        };
+
+        // The members that weren't asked may well hold the record.
+        assert_eq!(peers_lacking(&fetched, &close_group), vec![stale]);
+
+        fetched.completed_early = false;
+        let mut lacking = peers_lacking(&fetched, &close_group);
+        lacking.sort();
+        let mut expected = rest.to_vec();
+        expected.sort();
+        assert_eq!(lacking, expected);
+    }
+}
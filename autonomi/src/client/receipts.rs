@@ -0,0 +1,136 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::payment::Receipt;
+use crate::Client;
+use ant_evm::ProofOfPayment;
+use ant_networking::NetworkError;
+pub use ant_protocol::messages::StorageReceipt;
+use ant_protocol::{storage::ChunkAddress, NetworkAddress, CLOSE_GROUP_SIZE};
+use futures::future::try_join_all;
+use std::collections::{HashMap, HashSet};
+use xor_name::XorName;
+
+impl Client {
+    /// Get signed receipts from the close nodes to the record that they store it, paid for with the proof.
+    ///
+    /// Keep them with the proof to later show the record was accepted, see [`verify_storage_receipts`].
+    pub async fn storage_receipts(
+        &self,
+        address: NetworkAddress,
+        proof: &ProofOfPayment,
+    ) -> Result<Vec<StorageReceipt>, NetworkError> {
+        self.network
+            .get_storage_receipts(address, proof.hash()?)
+            .await
+            .inspect_err(|err| error!("Failed to get storage receipts: {err}"))
+    }
+
+    /// Get the storage receipts of all the chunks paid for with the payment receipt of an upload.
+    pub async fn chunk_storage_receipts(
+        &self,
+        receipt: &Receipt,
+    ) -> Result<HashMap<XorName, Vec<StorageReceipt>>, NetworkError> {
+        let receipts = try_join_all(receipt.iter().map(|(xor_name, (proof, _))| async move {
+            let address = NetworkAddress::from_chunk_address(ChunkAddress::new(*xor_name));
+            let receipts = self.storage_receipts(address, proof).await?;
+            Ok::<_, NetworkError>((*xor_name, receipts))
+        }))
+        .await?;
+
+        Ok(receipts.into_iter().collect())
+    }
+}
+
+/// Check that the receipts show that a majority of the close group paid to store the record accepted it.
+pub fn verify_storage_receipts(
+    address: &NetworkAddress,
+    proof: &ProofOfPayment,
+    receipts: &[StorageReceipt],
+) -> bool {
+    let Ok(payment_hash) = proof.hash() else {
+        return false;
+    };
+    let payees: HashSet<_> = proof.payees().into_iter().collect();
+
+    let accepted_by: HashSet<_> = receipts
+        .iter()
+        .filter(|receipt| &receipt.address == address && receipt.payment_hash == payment_hash)
+        .filter(|receipt| receipt.verify())
+        .filter_map(|receipt| receipt.node())
+        .filter(|node| payees.contains(node))
+        .collect();
+
+    // A majority of the close group
+    accepted_by.len() > CLOSE_GROUP_SIZE / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_evm::{EncodedPeerId, PaymentQuote};
+    use libp2p::identity::Keypair;
+    use std::time::SystemTime;
+
+    fn receipt(
+        keypair: &Keypair,
+        address: &NetworkAddress,
+        proof: &ProofOfPayment,
+    ) -> StorageReceipt {
+        let payment_hash = proof.hash().expect("Failed to hash the proof");
+        let node_key = keypair.public().encode_protobuf();
+        let timestamp = SystemTime::now();
+        let bytes = StorageReceipt::bytes_for_signing(address, &payment_hash, &node_key, timestamp);
+
+        StorageReceipt {
+            address: address.clone(),
+            payment_hash,
+            node_key,
+            timestamp,
+            signature: keypair.sign(&bytes).expect("Failed to sign"),
+        }
+    }
+
+    #[test]
+    fn receipts_from_a_majority_of_payees_verify() {
+        let xor_name = XorName::from_content(b"chunk");
+        let address = NetworkAddress::from_chunk_address(ChunkAddress::new(xor_name));
+        let payees: Vec<_> = (0..CLOSE_GROUP_SIZE)
+            .map(|_| Keypair::generate_ed25519())
+            .collect();
+        let proof = ProofOfPayment {
+            peer_quotes: payees
+                .iter()
+                .map(|keypair| {
+                    (
+                        EncodedPeerId::from(keypair.public().to_peer_id()),
+                        PaymentQuote::test_dummy(xor_name),
+                    )
+                })
+                .collect(),
+        };
+
+        let mut receipts: Vec<_> = payees[..2]
+            .iter()
+            .map(|keypair| receipt(keypair, &address, &proof))
+            .collect();
+        assert!(!verify_storage_receipts(&address, &proof, &receipts));
+
+        // Duplicates and receipts of nodes that weren't paid don't count
+        receipts.push(receipt(&payees[0], &address, &proof));
+        receipts.push(receipt(&Keypair::generate_ed25519(), &address, &proof));
+        assert!(!verify_storage_receipts(&address, &proof, &receipts));
+
+        receipts.push(receipt(&payees[2], &address, &proof));
+        assert!(verify_storage_receipts(&address, &proof, &receipts));
+
+        let other_address =
+            NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::from_content(b"other")));
+        assert!(!verify_storage_receipts(&other_address, &proof, &receipts));
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_networking::{GetRecordCfg, PutRecordCfg};
+use ant_protocol::storage::RetryStrategy;
+use libp2p::kad::{Quorum, Record};
+
+/// How records are fetched, see [`crate::Client::with_get_cfg`].
+///
+/// Every setting left to `None` keeps the default of the record type fetched.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GetCfg {
+    /// The number of nodes that have to return the same copy of a record for it to be accepted.
+    ///
+    /// Defaults to a single copy for the chunks, which are checked against their address, and to a majority of
+    /// the close group for the mutable records.
+    pub quorum: Option<Quorum>,
+    /// How the fetches failing on the network are retried.
+    ///
+    /// Defaults to [`crate::ClientConfig::retry_strategy`] for the chunks, and to a single attempt for the mutable
+    /// records.
+    pub retry_strategy: Option<RetryStrategy>,
+    /// Put the mutable records fetched back to the close group members that served a stale copy, or didn't return
+    /// any when every member was asked, healing their replication as they are read.
+    ///
+    /// Off by default. The records are put in the background, without holding the fetch up, see
+    /// [`crate::Client::pointer_get`].
+    pub read_repair: bool,
+}
+
+/// Whether, and how, the records put are fetched back, see [`PutCfg::verification`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PutVerification {
+    /// The put is done as soon as the nodes accept the record.
+    Skip,
+    /// Some copy of the record has to be fetched back, which for the mutable records may be a newer or split one.
+    Stored,
+    /// The very record put has to be fetched back.
+    Matching,
+}
+
+/// How records, other than chunks, are put, see [`crate::Client::with_put_cfg`]. The chunks are verified
+/// according to [`crate::ClientConfig::upload`].
+///
+/// Every setting left to `None` keeps the default of the record type put.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PutCfg {
+    /// The number of nodes that have to accept a record for the put to succeed.
+    ///
+    /// Defaults to all of the paid nodes for the new records, and to a majority of the close group for the updates.
+    pub quorum: Option<Quorum>,
+    /// How the puts failing on the network are retried.
+    ///
+    /// Defaults to [`crate::ClientConfig::retry_strategy`].
+    pub retry_strategy: Option<RetryStrategy>,
+    /// Whether to fetch the record back once put.
+    ///
+    /// Defaults to [`PutVerification::Stored`].
+    pub verification: Option<PutVerification>,
+    /// The number of nodes that have to return the record for the put to be verified.
+    ///
+    /// Defaults to a majority of the close group.
+    pub verification_quorum: Option<Quorum>,
+}
+
+impl GetCfg {
+    /// A fetch accepting the first copy found, without retrying, e.g. for previews.
+    pub fn quick() -> Self {
+        Self {
+            quorum: Some(Quorum::One),
+            retry_strategy: Some(RetryStrategy::None),
+            read_repair: false,
+        }
+    }
+
+    /// A fetch checking the copies of the whole close group, retrying for longer, e.g. for archival.
+    pub fn thorough() -> Self {
+        Self {
+            quorum: Some(Quorum::All),
+            retry_strategy: Some(RetryStrategy::Persistent),
+            read_repair: false,
+        }
+    }
+
+    /// The record type's default `cfg`, with our settings applied.
+    pub(crate) fn apply(&self, mut cfg: GetRecordCfg) -> GetRecordCfg {
+        if let Some(quorum) = self.quorum {
+            cfg.get_quorum = quorum;
+        }
+        if let Some(retry_strategy) = self.retry_strategy {
+            cfg.retry_strategy = Some(retry_strategy);
+        }
+        cfg
+    }
+}
+
+impl PutCfg {
+    /// The record type's default `cfg` for putting `record`, with our settings applied.
+    pub(crate) fn apply(&self, mut cfg: PutRecordCfg, record: &Record) -> PutRecordCfg {
+        if let Some(quorum) = self.quorum {
+            cfg.put_quorum = quorum;
+        }
+        if let Some(retry_strategy) = self.retry_strategy {
+            cfg.retry_strategy = Some(retry_strategy);
+        }
+        match self.verification {
+            Some(PutVerification::Skip) => cfg.verification = None,
+            Some(PutVerification::Matching) => {
+                if let Some((_, get_cfg)) = cfg.verification.as_mut() {
+                    get_cfg.target_record = Some(record.clone());
+                }
+            }
+            Some(PutVerification::Stored) | None => {}
+        }
+        if let (Some(quorum), Some((_, get_cfg))) =
+            (self.verification_quorum, cfg.verification.as_mut())
+        {
+            get_cfg.get_quorum = quorum;
+        }
+        cfg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_networking::VerificationKind;
+    use libp2p::kad::RecordKey;
+
+    fn default_put_cfg() -> PutRecordCfg {
+        PutRecordCfg {
+            put_quorum: Quorum::All,
+            retry_strategy: Some(RetryStrategy::Balanced),
+            use_put_record_to: None,
+            verification: Some((
+                VerificationKind::Crdt,
+                GetRecordCfg {
+                    get_quorum: Quorum::Majority,
+                    retry_strategy: None,
+                    target_record: None,
+                    expected_holders: Default::default(),
+                    is_register: false,
+                },
+            )),
+        }
+    }
+
+    #[test]
+    fn only_the_settings_given_are_applied() {
+        let record = Record::new(RecordKey::new(b"record"), vec![1, 2, 3]);
+
+        let cfg = PutCfg::default().apply(default_put_cfg(), &record);
+        assert!(matches!(cfg.put_quorum, Quorum::All));
+        assert!(matches!(
+            cfg.verification,
+            Some((
+                _,
+                GetRecordCfg {
+                    get_quorum: Quorum::Majority,
+                    target_record: None,
+                    ..
+                }
+            ))
+        ));
+
+        let cfg = PutCfg {
+            quorum: Some(Quorum::One),
+            verification: Some(PutVerification::Matching),
+            verification_quorum: Some(Quorum::All),
+            ..Default::default()
+        }
+        .apply(default_put_cfg(), &record);
+        assert!(matches!(cfg.put_quorum, Quorum::One));
+        let (_, get_cfg) = cfg.verification.expect("verification to be kept");
+        assert!(matches!(get_cfg.get_quorum, Quorum::All));
+        assert_eq!(get_cfg.target_record, Some(record.clone()));
+
+        let cfg = PutCfg {
+            verification: Some(PutVerification::Skip),
+            ..Default::default()
+        }
+        .apply(default_put_cfg(), &record);
+        assert!(cfg.verification.is_none());
+
+        let (_, default_get_cfg) = default_put_cfg().verification.expect("verification");
+        let get_cfg = GetCfg::quick().apply(default_get_cfg);
+        assert!(matches!(get_cfg.get_quorum, Quorum::One));
+        assert!(matches!(get_cfg.retry_strategy, Some(RetryStrategy::None)));
+    }
+}
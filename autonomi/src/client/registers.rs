@@ -8,29 +8,39 @@
 
 #![allow(deprecated)]
 
-use crate::client::data::PayError;
+use crate::client::data::{DataAddr, GetError, PayError, PutError};
 use crate::client::Client;
 use crate::client::ClientEvent;
 use crate::client::UploadSummary;
 
-pub use ant_registers::{Permissions as RegisterPermissions, RegisterAddress};
+pub use ant_registers::{
+    EntryHash as RegisterEntryHash, Permissions as RegisterPermissions, RegisterAddress,
+    MAX_REG_ENTRY_SIZE, MAX_REG_NUM_ENTRIES,
+};
 pub use bls::SecretKey as RegisterSecretKey;
 
 use ant_evm::{Amount, AttoTokens, EvmWallet, EvmWalletError};
 use ant_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
 use ant_protocol::{
+    messages::{register_topic, RegisterNotification},
     storage::{try_deserialize_record, try_serialize_record, RecordKind, RetryStrategy},
     NetworkAddress,
 };
 use ant_registers::Register as BaseRegister;
 use ant_registers::{Permissions, RegisterCrdt, RegisterOp, SignedRegister};
 use bytes::Bytes;
+use futures::{stream, Stream};
 use libp2p::kad::{Quorum, Record};
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
+use tokio::sync::broadcast::error::RecvError;
 use xor_name::XorName;
 
 use super::data::CostError;
 
+/// Marks the register entries holding the address of an entry value stored as public data,
+/// as the value was too big to fit in the entry.
+const DATA_ENTRY_PREFIX: &[u8] = b"autonomi:register-entry-data:";
+
 #[derive(Debug, thiserror::Error)]
 pub enum RegisterError {
     #[error("Cost error: {0}")]
@@ -53,6 +63,45 @@ pub enum RegisterError {
     InvalidQuote,
     #[error("The payment proof contains no payees.")]
     PayeesMissing,
+    #[error("Key {0:?} is not allowed to write to this register")]
+    AccessDenied(bls::PublicKey),
+    #[error("Entry of {size} bytes is too big for a register, max: {max}. Write it with a wallet to store it as data instead.")]
+    EntryTooBig { size: usize, max: usize },
+    #[error("The register is full, it already holds {0} entries")]
+    TooManyEntries(usize),
+    #[error("Failed to store the register entry as data")]
+    EntryPut(#[source] Box<PutError>),
+    #[error("Failed to fetch the register entry stored as data")]
+    EntryGet(#[source] Box<GetError>),
+}
+
+/// Whether the entry value needs storing as data, keeping only its address in the register.
+///
+/// Values starting with the data entry prefix are stored as data as well, so that no entry written
+/// with a wallet holding a raw value is mistaken for one holding the address of its value.
+fn needs_data_entry(value: &[u8]) -> bool {
+    value.len() > MAX_REG_ENTRY_SIZE || value.starts_with(DATA_ENTRY_PREFIX)
+}
+
+fn data_entry(addr: &DataAddr) -> Bytes {
+    [DATA_ENTRY_PREFIX, addr.0.as_slice()].concat().into()
+}
+
+/// The address of the value stored as data, if the entry holds one.
+fn data_entry_addr(entry: &[u8]) -> Option<DataAddr> {
+    let addr: [u8; 32] = entry.strip_prefix(DATA_ENTRY_PREFIX)?.try_into().ok()?;
+    Some(XorName(addr))
+}
+
+/// An entry of a Register's history, see [`Register::history`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegisterEntry {
+    /// The hash identifying the entry in the register.
+    pub hash: RegisterEntryHash,
+    /// The value written.
+    pub value: Bytes,
+    /// The entries this one was written atop of, empty for the first entries of the register.
+    pub children: BTreeSet<RegisterEntryHash>,
 }
 
 #[deprecated(
@@ -81,6 +130,91 @@ impl Register {
             .collect()
     }
 
+    /// Retrieve all the entries ever written to the register, e.g. to undo writes or inspect conflicts.
+    ///
+    /// Entries come after the entries they were written atop of, from the first ones to the current ones.
+    pub fn history(&self) -> Vec<RegisterEntry> {
+        self.crdt_reg
+            .history()
+            .into_iter()
+            .map(|(hash, value, children)| RegisterEntry {
+                hash,
+                value: value.into(),
+                children,
+            })
+            .collect()
+    }
+
+    /// Retrieve a current or past entry of the register by its hash.
+    pub fn entry(&self, hash: RegisterEntryHash) -> Option<RegisterEntry> {
+        let value = self.crdt_reg.get(hash)?;
+        Some(RegisterEntry {
+            hash,
+            value: value.clone().into(),
+            children: self
+                .crdt_reg
+                .children(&hash)
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect(),
+        })
+    }
+
+    /// The hashes of the current entries, i.e. the tips of the branches written concurrently.
+    ///
+    /// Writing to the register merges all branches, the new entry being written atop all of the tips.
+    pub fn tips(&self) -> BTreeSet<RegisterEntryHash> {
+        self.crdt_reg.tips()
+    }
+
+    /// The keys allowed to write to the register, set by its owner at creation.
+    pub fn permissions(&self) -> &RegisterPermissions {
+        self.signed_reg.base_register().permissions()
+    }
+
+    /// Whether the given key may write to the register.
+    pub fn can_write(&self, key: &bls::PublicKey) -> bool {
+        self.permissions().can_write(key)
+    }
+
+    /// Apply the valid ops of a notification we haven't seen yet, returning the entries they wrote.
+    fn apply_notification(&mut self, msg: &[u8]) -> Vec<Bytes> {
+        let notification: RegisterNotification = match rmp_serde::from_slice(msg) {
+            Ok(notification) => notification,
+            Err(err) => {
+                warn!(
+                    "Ignoring malformed notification for register {}: {err}",
+                    self.address()
+                );
+                return vec![];
+            }
+        };
+
+        let mut entries = vec![];
+        for op in notification.ops {
+            if self.crdt_reg.get(op.entry_hash()).is_some() {
+                continue;
+            }
+            if let Err(err) = self.signed_reg.add_op(op.clone()) {
+                warn!(
+                    "Ignoring invalid op notified for register {}: {err}",
+                    self.address()
+                );
+                continue;
+            }
+            let entry = Bytes::from(op.entry().clone());
+            if let Err(err) = self.crdt_reg.apply_op(op) {
+                warn!(
+                    "Failed to apply op notified for register {}: {err}",
+                    self.address()
+                );
+                continue;
+            }
+            entries.push(entry);
+        }
+        entries
+    }
+
     fn new(
         initial_value: Option<Bytes>,
         name: XorName,
@@ -112,7 +246,26 @@ impl Register {
         Ok(register)
     }
 
-    fn write_atop(&mut self, entry: &[u8], owner: &RegisterSecretKey) -> Result<(), RegisterError> {
+    fn write_atop(
+        &mut self,
+        entry: &[u8],
+        writer: &RegisterSecretKey,
+    ) -> Result<(), RegisterError> {
+        let writer_pk = writer.public_key();
+        if !self.can_write(&writer_pk) {
+            return Err(RegisterError::AccessDenied(writer_pk));
+        }
+        if entry.len() > MAX_REG_ENTRY_SIZE {
+            return Err(RegisterError::EntryTooBig {
+                size: entry.len(),
+                max: MAX_REG_ENTRY_SIZE,
+            });
+        }
+        let num_entries = self.signed_reg.ops().len();
+        if num_entries >= MAX_REG_NUM_ENTRIES as usize {
+            return Err(RegisterError::TooManyEntries(num_entries));
+        }
+
         let children: BTreeSet<_> = self.crdt_reg.read().iter().map(|(hash, _)| *hash).collect();
 
         let (_hash, address, crdt_op) = self
@@ -120,9 +273,9 @@ impl Register {
             .write(entry.to_vec(), &children)
             .map_err(RegisterError::Write)?;
 
-        let op = RegisterOp::new(address, crdt_op, owner);
+        let op = RegisterOp::new(address, crdt_op, writer);
 
-        let _ = self.signed_reg.add_op(op);
+        self.signed_reg.add_op(op).map_err(RegisterError::Write)?;
 
         Ok(())
     }
@@ -144,24 +297,26 @@ impl Client {
         let network_address = NetworkAddress::from_register_address(address);
         let key = network_address.to_record_key();
 
-        let get_cfg = GetRecordCfg {
+        let get_cfg = self.get_cfg.apply(GetRecordCfg {
             get_quorum: Quorum::Majority,
             retry_strategy: None,
             target_record: None,
             expected_holders: Default::default(),
             is_register: true,
-        };
-
-        let signed_reg = match self.network.get_record_from_network(key, &get_cfg).await {
-            Ok(record) => {
-                let signed_reg: SignedRegister =
-                    try_deserialize_record(&record).map_err(|_| RegisterError::Serialization)?;
-                signed_reg
-            }
-            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
+        });
+
+        let fetched = match self.network.get_record_from_network(key, &get_cfg).await {
+            Ok(fetched) => fetched,
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord {
+                result_map,
+                provenance,
+            })) => {
                 error!("Got split record error for register at address: {address}. This should've been handled at the network layer");
                 Err(RegisterError::Network(NetworkError::GetRecordError(
-                    GetRecordError::SplitRecord { result_map },
+                    GetRecordError::SplitRecord {
+                        result_map,
+                        provenance,
+                    },
                 )))?
             }
             Err(e) => {
@@ -170,6 +325,9 @@ impl Client {
             }
         };
 
+        let signed_reg: SignedRegister =
+            try_deserialize_record(&fetched.record).map_err(|_| RegisterError::Serialization)?;
+
         // Make sure the fetched record contains valid CRDT operations
         signed_reg
             .verify()
@@ -187,17 +345,80 @@ impl Client {
             crdt_reg,
         };
         debug!("Fetched register {register:?} from the address: {address} in the network");
+        self.read_repair(fetched);
         Ok(register)
     }
 
+    /// Retrieve the current values of the register, like [`Register::values`], fetching the values that were
+    /// stored as data for being too big for a register entry.
+    pub async fn register_values(&self, register: &Register) -> Result<Vec<Bytes>, RegisterError> {
+        let mut values = vec![];
+        for value in register.values() {
+            match data_entry_addr(&value) {
+                Some(addr) => {
+                    debug!(
+                        "Fetching the value of register {} stored as data at {addr:?}",
+                        register.address()
+                    );
+                    let value = self
+                        .data_get_public(addr)
+                        .await
+                        .map_err(|err| RegisterError::EntryGet(Box::new(err)))?;
+                    values.push(value);
+                }
+                None => values.push(value),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Store the value as data if it doesn't fit in a register entry, returning the entry to write.
+    async fn register_entry(
+        &self,
+        value: Bytes,
+        wallet: &EvmWallet,
+    ) -> Result<Bytes, RegisterError> {
+        if !needs_data_entry(&value) {
+            return Ok(value);
+        }
+        debug!(
+            "Storing the register entry of {} bytes as data",
+            value.len()
+        );
+        let addr = self
+            .data_put_public(value, wallet.into())
+            .await
+            .map_err(|err| RegisterError::EntryPut(Box::new(err)))?;
+        Ok(data_entry(&addr))
+    }
+
+    /// Updates a Register on the network with a new value of any size, like [`Client::register_update`].
+    ///
+    /// Values too big for a register entry, see [`MAX_REG_ENTRY_SIZE`], are stored as public data paid with the
+    /// wallet, the entry only holding their address. Use [`Client::register_values`] to read them back.
+    pub async fn register_update_with_wallet(
+        &self,
+        register: Register,
+        new_value: Bytes,
+        writer: RegisterSecretKey,
+        wallet: &EvmWallet,
+    ) -> Result<(), RegisterError> {
+        let entry = self.register_entry(new_value, wallet).await?;
+        self.register_update(register, entry, writer).await
+    }
+
     /// Updates a Register on the network with a new value. This will overwrite existing value(s).
+    ///
+    /// The update is signed with `writer`, which can be the owner or any other key allowed by the register's
+    /// permissions, see [`Client::register_create_with_permissions`].
+    /// Values bigger than [`MAX_REG_ENTRY_SIZE`] are rejected, see [`Client::register_update_with_wallet`] to write them.
     pub async fn register_update(
         &self,
         mut register: Register,
         new_value: Bytes,
-        owner: RegisterSecretKey,
+        writer: RegisterSecretKey,
     ) -> Result<(), RegisterError> {
-        register.write_atop(&new_value, &owner)?;
+        register.write_atop(&new_value, &writer)?;
 
         let signed_register = register.signed_reg.clone();
 
@@ -218,12 +439,15 @@ impl Client {
             expected_holders: Default::default(),
             is_register: true,
         };
-        let put_cfg = PutRecordCfg {
-            put_quorum: Quorum::All,
-            retry_strategy: None,
-            use_put_record_to: None,
-            verification: Some((VerificationKind::Crdt, get_cfg)),
-        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::All,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: None,
+                verification: Some((VerificationKind::Crdt, get_cfg)),
+            },
+            &record,
+        );
 
         // Store the updated register on the network
         self.network
@@ -243,6 +467,59 @@ impl Client {
         Ok(())
     }
 
+    /// Watch a Register for changes, returning a stream of the entries written to it from now on.
+    ///
+    /// The nodes holding the register notify its watchers of the writes they accept, so there is no need to poll
+    /// it. Entries are verified against the register's permissions before being surfaced, and each is only
+    /// surfaced once no matter how many nodes notify of it. The subscription lasts until
+    /// [`Client::unwatch_register`] is called.
+    pub async fn watch_register(
+        &self,
+        address: RegisterAddress,
+    ) -> Result<impl Stream<Item = Bytes>, RegisterError> {
+        let topic = register_topic(&address);
+        let notifications = self.gossip_sender.subscribe();
+        self.network.subscribe_to_topic(topic.clone());
+
+        // Fetching the register also connects us to the nodes holding it, which publish its notifications.
+        let register = self
+            .register_get(address)
+            .await
+            .inspect_err(|err| error!("Failed to fetch watched register {address}: {err}"))?;
+        info!("Watching register {address} on topic {topic}");
+
+        let state = (notifications, register, VecDeque::new());
+        Ok(stream::unfold(
+            state,
+            move |(mut notifications, mut register, mut pending)| {
+                let topic = topic.clone();
+                async move {
+                    loop {
+                        if let Some(entry) = pending.pop_front() {
+                            return Some((entry, (notifications, register, pending)));
+                        }
+                        match notifications.recv().await {
+                            Ok((msg_topic, msg)) if msg_topic == topic => {
+                                pending.extend(register.apply_notification(&msg));
+                            }
+                            Ok(_) => {}
+                            Err(RecvError::Lagged(missed)) => {
+                                warn!("Missed {missed} gossipsub msgs while watching {topic}");
+                            }
+                            Err(RecvError::Closed) => return None,
+                        }
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Stop receiving the notifications of a Register watched with [`Client::watch_register`].
+    pub fn unwatch_register(&self, address: RegisterAddress) {
+        self.network
+            .unsubscribe_from_topic(register_topic(&address));
+    }
+
     /// Get the cost to create a register
     pub async fn register_cost(
         &self,
@@ -298,7 +575,11 @@ impl Client {
 
     /// Creates a new Register with a name and optional initial value and uploads it to the network.
     ///
-    /// Unlike `register_create`, this function allows you to specify the permissions for the register.
+    /// Unlike `register_create`, this function allows you to specify the permissions for the register, e.g. a set of
+    /// writers for a collaborative register, or that anyone can write to it. The owner can always write to it.
+    /// Nodes reject updates signed by anyone else.
+    ///
+    /// An initial value too big for a register entry is stored as data, see [`Client::register_update_with_wallet`].
     pub async fn register_create_with_permissions(
         &self,
         value: Option<Bytes>,
@@ -309,6 +590,10 @@ impl Client {
     ) -> Result<Register, RegisterError> {
         info!("Creating register with name: {name}");
         let name = XorName::from_content_parts(&[name.as_bytes()]);
+        let value = match value {
+            Some(value) => Some(self.register_entry(value, wallet).await?),
+            None => None,
+        };
 
         // Owner can write to the register.
         let register = Register::new(value, name, owner, permissions)?;
@@ -353,12 +638,15 @@ impl Client {
             is_register: true,
         };
 
-        let put_cfg = PutRecordCfg {
-            put_quorum: Quorum::All,
-            retry_strategy: None,
-            use_put_record_to: Some(payees),
-            verification: Some((VerificationKind::Crdt, get_cfg)),
-        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::All,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: Some(payees),
+                verification: Some((VerificationKind::Crdt, get_cfg)),
+            },
+            &record,
+        );
 
         debug!("Storing register at address {address} to the network");
         self.network
@@ -368,16 +656,35 @@ impl Client {
                 error!("Failed to put record - register {address} to the network: {err}")
             })?;
 
-        if let Some(channel) = self.client_event_sender.as_ref() {
-            let summary = UploadSummary {
-                record_count: 1,
-                tokens_spent: price.as_atto(),
-            };
-            if let Err(err) = channel.send(ClientEvent::UploadComplete(summary)).await {
-                error!("Failed to send client event: {err}");
-            }
-        }
+        let summary = UploadSummary {
+            record_count: 1,
+            tokens_spent: price.as_atto(),
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
 
         Ok(register)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_entries_hold_the_address_of_their_value() {
+        let addr = XorName::random(&mut rand::thread_rng());
+        let entry = data_entry(&addr);
+        assert!(entry.len() <= MAX_REG_ENTRY_SIZE);
+        assert_eq!(data_entry_addr(&entry), Some(addr));
+
+        assert_eq!(data_entry_addr(b"a raw value"), None);
+        assert_eq!(data_entry_addr(DATA_ENTRY_PREFIX), None);
+    }
+
+    #[test]
+    fn big_or_prefixed_values_need_a_data_entry() {
+        assert!(!needs_data_entry(&[0; MAX_REG_ENTRY_SIZE]));
+        assert!(needs_data_entry(&[0; MAX_REG_ENTRY_SIZE + 1]));
+        assert!(needs_data_entry(&[DATA_ENTRY_PREFIX, b"raw"].concat()));
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::future::Future;
+
+use ant_networking::target_arch::sleep;
+use ant_protocol::storage::RetryStrategy;
+
+use super::{Client, ClientEvent};
+
+impl Client {
+    /// Run the operation again until it succeeds or the retry strategy gives up, backing off between the attempts.
+    ///
+    /// `should_retry` tells the outcomes worth another attempt, e.g. the errors an idempotent retry can fix. Once the
+    /// strategy gives up, the outcome of the last attempt is returned. Each retry is reported as a
+    /// [`ClientEvent::OperationRetried`].
+    pub(crate) async fn with_retries<T, E, F, Fut>(
+        &self,
+        operation: &'static str,
+        strategy: RetryStrategy,
+        mut attempt: F,
+        should_retry: impl Fn(&Result<T, E>) -> bool,
+    ) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut backoff = strategy.backoff().into_iter();
+        let mut attempt_number = 1;
+        loop {
+            let result = attempt().await;
+            if !should_retry(&result) {
+                return result;
+            }
+            match backoff.next() {
+                Some(Some(delay)) => {
+                    attempt_number += 1;
+                    warn!(
+                        "Retrying {operation} in {delay:?} (attempt {attempt_number}/{})",
+                        strategy.attempts()
+                    );
+                    self.send_client_event(ClientEvent::OperationRetried {
+                        operation,
+                        attempt: attempt_number,
+                    });
+                    sleep(delay).await;
+                }
+                _ => {
+                    warn!("Giving up on {operation} after {attempt_number} attempts");
+                    return result;
+                }
+            }
+        }
+    }
+}
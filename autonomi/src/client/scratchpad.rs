@@ -0,0 +1,262 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::data::PayError;
+use crate::client::Client;
+use crate::client::ClientEvent;
+use crate::client::UploadSummary;
+
+use ant_evm::{Amount, AttoTokens, EvmWallet};
+use ant_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
+pub use ant_protocol::storage::{Scratchpad, ScratchpadAddress, MAX_SCRATCHPAD_SIZE};
+use ant_protocol::{
+    storage::{try_deserialize_record, try_serialize_record, RecordKind, RetryStrategy},
+    Bytes, NetworkAddress,
+};
+pub use bls::{PublicKey, SecretKey};
+use libp2p::kad::{Quorum, Record};
+use std::collections::HashSet;
+
+use super::data::CostError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScratchpadError {
+    #[error("Cost error: {0}")]
+    Cost(#[from] CostError),
+    #[error("Network error")]
+    Network(#[from] NetworkError),
+    #[error("Serialization error")]
+    Serialization,
+    #[error("Scratchpad could not be verified (corrupt)")]
+    FailedVerification,
+    #[error("Payment failure occurred during scratchpad creation.")]
+    Pay(#[from] PayError),
+    #[error("Scratchpad already exists at this address: {0:?}")]
+    ScratchpadAlreadyExists(ScratchpadAddress),
+    #[error("Scratchpad of {size} bytes is bigger than the max of {max} bytes")]
+    TooBig { size: usize, max: usize },
+}
+
+impl Client {
+    /// Fetches the latest version of a Scratchpad from the network.
+    ///
+    /// The data is encrypted to its owner, see [`Scratchpad::decrypt_data`].
+    pub async fn scratchpad_get(
+        &self,
+        address: ScratchpadAddress,
+    ) -> Result<Scratchpad, ScratchpadError> {
+        let key = NetworkAddress::from_scratchpad_address(address).to_record_key();
+        let get_cfg = self.get_cfg.apply(GetRecordCfg {
+            get_quorum: Quorum::Majority,
+            retry_strategy: None,
+            target_record: None,
+            expected_holders: HashSet::new(),
+            is_register: false,
+        });
+
+        let (scratchpad, fetched) = match self.network.get_record_from_network(key, &get_cfg).await
+        {
+            Ok(fetched) => (
+                try_deserialize_record::<Scratchpad>(&fetched.record)
+                    .map_err(|_| ScratchpadError::Serialization)?,
+                Some(fetched),
+            ),
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord {
+                result_map, ..
+            })) => {
+                debug!("Got multiple scratchpads for {address:?}, picking the latest version");
+                let scratchpad = result_map
+                    .values()
+                    .filter_map(|(record, _)| try_deserialize_record::<Scratchpad>(record).ok())
+                    .filter(|scratchpad| scratchpad.is_valid())
+                    .max_by_key(|scratchpad| scratchpad.count())
+                    .ok_or(ScratchpadError::FailedVerification)?;
+                (scratchpad, None)
+            }
+            Err(err) => {
+                warn!("Failed to fetch scratchpad {address:?} from network: {err}");
+                return Err(err.into());
+            }
+        };
+
+        if scratchpad.address() != &address || !scratchpad.is_valid() {
+            error!("Scratchpad fetched from {address:?} failed verification");
+            return Err(ScratchpadError::FailedVerification);
+        }
+        if let Some(fetched) = fetched {
+            self.read_repair(fetched);
+        }
+        Ok(scratchpad)
+    }
+
+    /// Stores a new Scratchpad of the owner on the network, paying for it. The data is encrypted to the owner.
+    ///
+    /// The content type lets apps identify the data they store. Use [`Client::scratchpad_update`] to change the
+    /// data of an existing scratchpad.
+    pub async fn scratchpad_create(
+        &self,
+        owner: &SecretKey,
+        content_type: u64,
+        data: Bytes,
+        wallet: &EvmWallet,
+    ) -> Result<Scratchpad, ScratchpadError> {
+        let mut scratchpad = Scratchpad::new(owner.public_key(), content_type);
+        let _ = scratchpad.update_and_sign(data, owner);
+        check_size(&scratchpad)?;
+        let address = *scratchpad.address();
+
+        // pay for the scratchpad
+        let xor_name = address.xorname();
+        debug!("Paying for scratchpad at address: {address:?}");
+        let payment_proofs = self
+            .pay(std::iter::once(xor_name), wallet)
+            .await
+            .inspect_err(|err| {
+                error!("Failed to pay for scratchpad at address: {address:?} : {err}")
+            })?;
+
+        // make sure the scratchpad was paid for
+        let (proof, price) = match payment_proofs.get(&xor_name) {
+            Some((proof, price)) => (proof, price),
+            None => {
+                // scratchpad was skipped, meaning it was already paid for
+                error!("Scratchpad at address: {address:?} was already paid for");
+                return Err(ScratchpadError::ScratchpadAlreadyExists(address));
+            }
+        };
+
+        // prepare the record for network storage
+        let payees = proof.payees();
+        let record = Record {
+            key: NetworkAddress::from_scratchpad_address(address).to_record_key(),
+            value: try_serialize_record(&(proof, &scratchpad), RecordKind::ScratchpadWithPayment)
+                .map_err(|_| ScratchpadError::Serialization)?
+                .to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let get_cfg = GetRecordCfg {
+            get_quorum: Quorum::Majority,
+            retry_strategy: Some(RetryStrategy::default()),
+            target_record: None,
+            expected_holders: Default::default(),
+            is_register: false,
+        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::All,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: Some(payees),
+                verification: Some((VerificationKind::Crdt, get_cfg)),
+            },
+            &record,
+        );
+
+        // put the record to the network
+        debug!("Storing scratchpad at address {address:?} to the network");
+        self.network
+            .put_record(record, &put_cfg)
+            .await
+            .inspect_err(|err| {
+                error!("Failed to put record - scratchpad {address:?} to the network: {err}")
+            })?;
+
+        // send client event
+        let summary = UploadSummary {
+            record_count: 1,
+            tokens_spent: price.as_atto(),
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
+
+        Ok(scratchpad)
+    }
+
+    /// Replaces the data of an existing Scratchpad of the owner. Updates are free, only the creation is paid for.
+    ///
+    /// Nodes only accept versions with a higher counter than the one they hold, so the latest write wins.
+    pub async fn scratchpad_update(
+        &self,
+        owner: &SecretKey,
+        data: Bytes,
+    ) -> Result<Scratchpad, ScratchpadError> {
+        let address = ScratchpadAddress::new(owner.public_key());
+        let mut scratchpad = self.scratchpad_get(address).await?;
+        let _ = scratchpad.update_and_sign(data, owner);
+        check_size(&scratchpad)?;
+
+        let record = Record {
+            key: NetworkAddress::from_scratchpad_address(address).to_record_key(),
+            value: try_serialize_record(&scratchpad, RecordKind::Scratchpad)
+                .map_err(|_| ScratchpadError::Serialization)?
+                .to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::Majority,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: None,
+                verification: Some((
+                    VerificationKind::Crdt,
+                    GetRecordCfg {
+                        get_quorum: Quorum::Majority,
+                        retry_strategy: None,
+                        target_record: None,
+                        expected_holders: HashSet::new(),
+                        is_register: false,
+                    },
+                )),
+            },
+            &record,
+        );
+
+        debug!(
+            "Updating scratchpad at address {address:?} to version {}",
+            scratchpad.count()
+        );
+        self.network
+            .put_record(record, &put_cfg)
+            .await
+            .inspect_err(|err| {
+                error!("Failed to update scratchpad {address:?} on the network: {err}")
+            })?;
+
+        Ok(scratchpad)
+    }
+
+    /// Get the cost to create a scratchpad
+    pub async fn scratchpad_cost(&self, owner: &PublicKey) -> Result<AttoTokens, ScratchpadError> {
+        let address = ScratchpadAddress::new(*owner);
+        trace!("Getting cost for scratchpad at {address:?}");
+
+        let store_quote = self
+            .get_store_quotes(std::iter::once(address.xorname()))
+            .await?;
+        let total_cost = AttoTokens::from_atto(
+            store_quote
+                .0
+                .values()
+                .map(|quote| quote.price())
+                .sum::<Amount>(),
+        );
+        debug!("Calculated the cost to create scratchpad at {address:?} is {total_cost}");
+        Ok(total_cost)
+    }
+}
+
+fn check_size(scratchpad: &Scratchpad) -> Result<(), ScratchpadError> {
+    let size = scratchpad.payload_size();
+    if size > MAX_SCRATCHPAD_SIZE {
+        return Err(ScratchpadError::TooBig {
+            size,
+            max: MAX_SCRATCHPAD_SIZE,
+        });
+    }
+    Ok(())
+}
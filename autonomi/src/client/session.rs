@@ -0,0 +1,154 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Weak};
+
+use ant_networking::{target_arch::Duration, Network, NetworkError};
+use ant_protocol::{NetworkAddress, CLOSE_GROUP_SIZE};
+use libp2p::PeerId;
+
+use super::Client;
+
+/// The number of recently used addresses whose close groups are kept warm.
+const MAX_RECENT_ADDRS: usize = 32;
+
+/// The state of the connection of a [`Client`] to the network, see [`Client::health`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientHealth {
+    /// The number of peers we currently have a connection to.
+    pub connected_peers: usize,
+    /// The number of peers in our routing table.
+    pub routing_table_peers: usize,
+}
+
+impl ClientHealth {
+    /// Whether the routing table holds at least a close group worth of peers, as after [`Client::init`].
+    pub fn is_connected(&self) -> bool {
+        self.routing_table_peers >= CLOSE_GROUP_SIZE
+    }
+}
+
+/// The addresses recently fetched or uploaded to, most recent last.
+#[derive(Debug, Default)]
+pub(crate) struct RecentAddrs(Mutex<VecDeque<NetworkAddress>>);
+
+impl RecentAddrs {
+    pub(crate) fn record(&self, addr: NetworkAddress) {
+        let mut recent = self.0.lock().expect("lock poisoned");
+        recent.retain(|recent_addr| recent_addr != &addr);
+        recent.push_back(addr);
+        if recent.len() > MAX_RECENT_ADDRS {
+            let _ = recent.pop_front();
+        }
+    }
+
+    fn snapshot(&self) -> Vec<NetworkAddress> {
+        self.0
+            .lock()
+            .expect("lock poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Client {
+    /// The state of our connection to the network.
+    ///
+    /// A client is meant to be long-lived and shared (it's cheap to clone), rather than created per operation, which
+    /// bootstraps a new routing table each time. Services embedding it can check this to decide whether to wait,
+    /// instead of reconnecting.
+    pub async fn health(&self) -> Result<ClientHealth, NetworkError> {
+        let state = self.network.get_swarm_local_state().await?;
+        let kbuckets = self.network.get_kbuckets().await?;
+        Ok(ClientHealth {
+            connected_peers: state.connected_peers.len(),
+            routing_table_peers: kbuckets.values().map(Vec::len).sum(),
+        })
+    }
+
+    /// The peers we currently have a connection to.
+    pub async fn connected_peers(&self) -> Result<Vec<PeerId>, NetworkError> {
+        Ok(self.network.get_swarm_local_state().await?.connected_peers)
+    }
+
+    /// Remember the address as recently used, for its close group to be kept warm.
+    pub(crate) fn record_recent_addr(&self, addr: NetworkAddress) {
+        self.recent_addrs.record(addr);
+    }
+}
+
+/// Refresh the routing table toward the close groups of the recently used addresses, every interval.
+///
+/// Stops once every clone of the client is dropped.
+pub(crate) async fn keep_warm(
+    network: Network,
+    recent_addrs: Weak<RecentAddrs>,
+    interval: Duration,
+) {
+    loop {
+        ant_networking::target_arch::sleep(interval).await;
+        let Some(recent_addrs) = recent_addrs.upgrade() else {
+            debug!("The client was dropped, no longer keeping its close groups warm");
+            return;
+        };
+        let addresses = recent_addrs.snapshot();
+        drop(recent_addrs);
+        if addresses.is_empty() {
+            continue;
+        }
+
+        debug!(
+            "Keeping the close groups of {} addresses warm",
+            addresses.len()
+        );
+        if let Err(err) = network.prewarm(addresses).await {
+            warn!("Failed to keep the close groups warm: {err}");
+        }
+    }
+}
+
+/// Start keeping the close groups of the client warm, if configured to.
+pub(crate) fn spawn_keep_warm(
+    network: &Network,
+    recent_addrs: &Arc<RecentAddrs>,
+    interval: Option<Duration>,
+) {
+    if let Some(interval) = interval {
+        let _handle = ant_networking::target_arch::spawn(keep_warm(
+            network.clone(),
+            Arc::downgrade(recent_addrs),
+            interval,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ant_protocol::storage::ChunkAddress;
+    use xor_name::XorName;
+
+    #[test]
+    fn only_the_most_recent_addrs_are_kept() {
+        let recent = RecentAddrs::default();
+        let addr =
+            |byte: u8| NetworkAddress::from_chunk_address(ChunkAddress::new(XorName([byte; 32])));
+        for byte in 0..40 {
+            recent.record(addr(byte));
+        }
+        recent.record(addr(10));
+
+        let snapshot = recent.snapshot();
+        assert_eq!(snapshot.len(), MAX_RECENT_ADDRS);
+        assert_eq!(snapshot.first(), Some(&addr(8)));
+        assert_eq!(snapshot.last(), Some(&addr(10)));
+        assert_eq!(snapshot.iter().filter(|a| **a == addr(10)).count(), 1);
+    }
+}
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::client::data::PayError;
+use crate::client::utils::process_tasks_with_max_concurrency;
 use crate::client::Client;
 use crate::client::ClientEvent;
 use crate::client::UploadSummary;
@@ -14,19 +15,23 @@ use crate::client::UploadSummary;
 use ant_evm::Amount;
 use ant_evm::AttoTokens;
 pub use ant_protocol::storage::Transaction;
-use ant_protocol::storage::TransactionAddress;
+pub use ant_protocol::storage::TransactionAddress;
 pub use bls::SecretKey;
 
 use ant_evm::{EvmWallet, EvmWalletError};
-use ant_networking::{GetRecordCfg, NetworkError, PutRecordCfg, VerificationKind};
+use ant_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
 use ant_protocol::{
     storage::{try_serialize_record, RecordKind, RetryStrategy},
     NetworkAddress,
 };
 use libp2p::kad::{Quorum, Record};
+use std::collections::{BTreeMap, BTreeSet};
 
 use super::data::CostError;
 
+/// The max number of transactions fetched at once while crawling a transaction DAG.
+const DAG_CRAWL_CONCURRENCY: usize = 16;
+
 #[derive(Debug, thiserror::Error)]
 pub enum TransactionError {
     #[error("Cost error: {0}")]
@@ -47,7 +52,171 @@ pub enum TransactionError {
     TransactionAlreadyExists(TransactionAddress),
 }
 
+/// An issue spotted while crawling a transaction DAG, see [`TransactionDag::faults`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionDagFault {
+    /// Several transactions were found at the address, its owner spent it more than once.
+    DoubleSpend(TransactionAddress),
+    /// A transaction at the address isn't signed by its owner, it was left out of the DAG.
+    InvalidSignature(TransactionAddress),
+    /// The transaction at the address, an output of the parent, doesn't name the parent among its parents.
+    UnknownParent {
+        address: TransactionAddress,
+        parent: TransactionAddress,
+    },
+}
+
+/// The transactions reachable from a transaction by following its outputs, see [`Client::crawl_transaction_dag`].
+#[derive(Debug, Clone, Default)]
+pub struct TransactionDag {
+    /// The valid transactions found at each crawled address.
+    pub transactions: BTreeMap<TransactionAddress, Vec<Transaction>>,
+    /// The outputs holding no transaction yet, i.e. the unspent ones.
+    pub unspent: BTreeSet<TransactionAddress>,
+    /// The outputs left uncrawled for being deeper than the depth of the crawl.
+    pub uncrawled: BTreeSet<TransactionAddress>,
+    /// The issues spotted along the way, a DAG without any is sound.
+    pub faults: Vec<TransactionDagFault>,
+}
+
+impl TransactionDag {
+    /// The addresses spent more than once.
+    pub fn double_spends(&self) -> impl Iterator<Item = &TransactionAddress> {
+        self.faults.iter().filter_map(|fault| match fault {
+            TransactionDagFault::DoubleSpend(address) => Some(address),
+            _ => None,
+        })
+    }
+
+    /// Whether no issue was spotted while crawling.
+    pub fn is_sound(&self) -> bool {
+        self.faults.is_empty()
+    }
+
+    /// The addresses of the outputs of the transactions at the address.
+    pub fn outputs(&self, address: &TransactionAddress) -> BTreeSet<TransactionAddress> {
+        self.transactions
+            .get(address)
+            .into_iter()
+            .flatten()
+            .flat_map(|transaction| transaction.outputs.iter())
+            .map(|(output, _content)| TransactionAddress::from_owner(*output))
+            .collect()
+    }
+
+    /// Add the transactions found at the address, output of the parent unless it's the crawl start,
+    /// returning the addresses of their outputs.
+    fn insert(
+        &mut self,
+        address: TransactionAddress,
+        parent: Option<TransactionAddress>,
+        transactions: Vec<Transaction>,
+    ) -> BTreeSet<TransactionAddress> {
+        let (valid, invalid): (Vec<_>, Vec<_>) = transactions
+            .into_iter()
+            .partition(|transaction| transaction.verify());
+        if !invalid.is_empty() {
+            warn!(
+                "Found {} badly signed transactions at {address:?}",
+                invalid.len()
+            );
+            self.faults
+                .push(TransactionDagFault::InvalidSignature(address));
+        }
+        if valid.is_empty() {
+            return BTreeSet::new();
+        }
+        if valid.len() > 1 {
+            warn!(
+                "Found a double spend of {} transactions at {address:?}",
+                valid.len()
+            );
+            self.faults.push(TransactionDagFault::DoubleSpend(address));
+        }
+        if let Some(parent) = parent {
+            let names_parent = valid.iter().all(|transaction| {
+                transaction
+                    .parents
+                    .iter()
+                    .any(|owner| TransactionAddress::from_owner(*owner) == parent)
+            });
+            if !names_parent {
+                self.faults
+                    .push(TransactionDagFault::UnknownParent { address, parent });
+            }
+        }
+        let _ = self.transactions.insert(address, valid);
+        self.outputs(&address)
+    }
+}
+
 impl Client {
+    /// Crawl the transactions reachable from the one at `from`, following their outputs `depth` levels deep.
+    ///
+    /// Each level is fetched concurrently. Double spends, badly signed transactions and outputs not naming their
+    /// parent are reported as [`TransactionDag::faults`], e.g. for a wallet to verify the provenance of what it
+    /// received.
+    pub async fn crawl_transaction_dag(
+        &self,
+        from: TransactionAddress,
+        depth: usize,
+    ) -> Result<TransactionDag, TransactionError> {
+        let mut dag = TransactionDag::default();
+        let mut level = vec![(from, None)];
+        let mut seen = BTreeSet::from([from]);
+
+        for current_depth in 0..=depth {
+            if level.is_empty() {
+                break;
+            }
+            debug!(
+                "Crawling {} transactions at depth {current_depth} from {from:?}",
+                level.len()
+            );
+            let tasks = level.into_iter().map(|(address, parent)| async move {
+                (
+                    address,
+                    parent,
+                    self.network.get_transactions(address).await,
+                )
+            });
+            let results = process_tasks_with_max_concurrency(tasks, DAG_CRAWL_CONCURRENCY).await;
+
+            let mut next_level = vec![];
+            for (address, parent, result) in results {
+                let transactions = match result {
+                    Ok(transactions) => transactions,
+                    Err(NetworkError::GetRecordError(GetRecordError::RecordNotFound)) => {
+                        let _ = dag.unspent.insert(address);
+                        continue;
+                    }
+                    Err(err) => {
+                        error!("Failed to fetch transaction {address:?} while crawling from {from:?}: {err}");
+                        return Err(err.into());
+                    }
+                };
+                for output in dag.insert(address, parent, transactions) {
+                    if !seen.insert(output) {
+                        continue;
+                    }
+                    if current_depth == depth {
+                        let _ = dag.uncrawled.insert(output);
+                    } else {
+                        next_level.push((output, Some(address)));
+                    }
+                }
+            }
+            level = next_level;
+        }
+
+        info!(
+            "Crawled {} transactions from {from:?}, with {} faults",
+            dag.transactions.len(),
+            dag.faults.len()
+        );
+        Ok(dag)
+    }
+
     /// Fetches a Transaction from the network.
     pub async fn transaction_get(
         &self,
@@ -102,12 +271,15 @@ impl Client {
             expected_holders: Default::default(),
             is_register: false,
         };
-        let put_cfg = PutRecordCfg {
-            put_quorum: Quorum::All,
-            retry_strategy: None,
-            use_put_record_to: Some(payees),
-            verification: Some((VerificationKind::Crdt, get_cfg)),
-        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::All,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: Some(payees),
+                verification: Some((VerificationKind::Crdt, get_cfg)),
+            },
+            &record,
+        );
 
         // put the record to the network
         debug!("Storing transaction at address {address:?} to the network");
@@ -119,15 +291,11 @@ impl Client {
             })?;
 
         // send client event
-        if let Some(channel) = self.client_event_sender.as_ref() {
-            let summary = UploadSummary {
-                record_count: 1,
-                tokens_spent: price.as_atto(),
-            };
-            if let Err(err) = channel.send(ClientEvent::UploadComplete(summary)).await {
-                error!("Failed to send client event: {err}");
-            }
-        }
+        let summary = UploadSummary {
+            record_count: 1,
+            tokens_spent: price.as_atto(),
+        };
+        self.send_client_event(ClientEvent::UploadComplete(summary));
 
         Ok(())
     }
@@ -151,3 +319,84 @@ impl Client {
         Ok(total_cost)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(
+        owner: &SecretKey,
+        parents: &[&SecretKey],
+        outputs: &[&SecretKey],
+    ) -> Transaction {
+        Transaction::new(
+            owner.public_key(),
+            parents.iter().map(|parent| parent.public_key()).collect(),
+            [0; 32],
+            outputs
+                .iter()
+                .map(|output| (output.public_key(), [0; 32]))
+                .collect(),
+            owner,
+        )
+    }
+
+    #[test]
+    fn dag_spots_double_spends_and_unknown_parents() {
+        let root = SecretKey::random();
+        let child = SecretKey::random();
+        let other_child = SecretKey::random();
+        let root_addr = TransactionAddress::from_owner(root.public_key());
+        let child_addr = TransactionAddress::from_owner(child.public_key());
+        let other_child_addr = TransactionAddress::from_owner(other_child.public_key());
+
+        let mut dag = TransactionDag::default();
+        let outputs = dag.insert(
+            root_addr,
+            None,
+            vec![
+                transaction(&root, &[], &[&child]),
+                transaction(&root, &[], &[&other_child]),
+            ],
+        );
+        assert_eq!(outputs, BTreeSet::from([child_addr, other_child_addr]));
+        assert_eq!(dag.double_spends().collect::<Vec<_>>(), vec![&root_addr]);
+
+        let _ = dag.insert(
+            child_addr,
+            Some(root_addr),
+            vec![transaction(&child, &[&root], &[])],
+        );
+        assert_eq!(dag.faults.len(), 1);
+
+        let _ = dag.insert(
+            other_child_addr,
+            Some(root_addr),
+            vec![transaction(&other_child, &[&child], &[])],
+        );
+        assert_eq!(
+            dag.faults.last(),
+            Some(&TransactionDagFault::UnknownParent {
+                address: other_child_addr,
+                parent: root_addr,
+            })
+        );
+    }
+
+    #[test]
+    fn dag_leaves_out_badly_signed_transactions() {
+        let owner = SecretKey::random();
+        let address = TransactionAddress::from_owner(owner.public_key());
+        let mut forged = transaction(&owner, &[], &[&SecretKey::random()]);
+        forged.signature = SecretKey::random().sign(forged.bytes_for_signature());
+
+        let mut dag = TransactionDag::default();
+        assert!(dag.insert(address, None, vec![forged]).is_empty());
+        assert!(!dag.transactions.contains_key(&address));
+        assert_eq!(
+            dag.faults,
+            vec![TransactionDagFault::InvalidSignature(address)]
+        );
+        assert!(!dag.is_sound());
+    }
+}
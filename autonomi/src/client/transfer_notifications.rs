@@ -0,0 +1,163 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::{
+    pubsub::{PubSubError, PublicKey, SecretKey},
+    Client,
+};
+
+use ant_evm::{Amount, EvmWallet, EvmWalletError, RewardsAddress, TxHash};
+use bls::Ciphertext;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// The prefix of the topics the transfer notifications are published on.
+const TRANSFER_TOPIC_PREFIX: &str = "transfers/";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransferNotificationError {
+    #[error("Wallet error: {0:?}")]
+    Wallet(#[from] EvmWalletError),
+    #[error("Failed to publish the notification: {0}")]
+    PubSub(#[from] PubSubError),
+    #[error("Serialization error")]
+    Serialization,
+}
+
+/// A transfer of tokens, as notified to its recipient.
+///
+/// Anyone can publish a notification to any recipient, so it only tells a transfer was made: check the transaction
+/// on chain, or the balance of the wallet, before trusting it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferNotification {
+    pub from: RewardsAddress,
+    pub to: RewardsAddress,
+    pub amount: Amount,
+    pub tx_hash: TxHash,
+    /// Free text from the sender, e.g. what the transfer is for
+    pub note: Option<String>,
+}
+
+/// The topic the notifications to the recipient are published on, derived from their key for only them to know
+/// what they receive, though anyone knowing the key can see that they do.
+pub fn transfer_topic(recipient: &PublicKey) -> String {
+    format!(
+        "{TRANSFER_TOPIC_PREFIX}{}",
+        hex::encode(XorName::from_content(&recipient.to_bytes()))
+    )
+}
+
+fn encrypt_notification(
+    notification: &TransferNotification,
+    recipient: &PublicKey,
+) -> Result<Bytes, TransferNotificationError> {
+    let bytes =
+        rmp_serde::to_vec(notification).map_err(|_| TransferNotificationError::Serialization)?;
+    Ok(Bytes::from(recipient.encrypt(bytes).to_bytes()))
+}
+
+fn decrypt_notification(payload: &[u8], recipient: &SecretKey) -> Option<TransferNotification> {
+    let ciphertext = Ciphertext::from_bytes(payload).ok()?;
+    let bytes = recipient.decrypt(&ciphertext)?;
+    rmp_serde::from_slice(&bytes).ok()
+}
+
+impl Client {
+    /// Transfer the tokens to the wallet at `to`, notifying its owner, who listens with the key `recipient`, through
+    /// [`Client::transfer_notifications`].
+    ///
+    /// The notification only reaches the recipient if they are listening at the time, see [`Client::subscribe`].
+    pub async fn transfer_and_notify(
+        &self,
+        wallet: &EvmWallet,
+        to: RewardsAddress,
+        amount: Amount,
+        recipient: &PublicKey,
+        note: Option<String>,
+    ) -> Result<TxHash, TransferNotificationError> {
+        let tx_hash = wallet
+            .transfer_tokens(to, amount)
+            .await
+            .map_err(EvmWalletError::from)?;
+        info!("Transferred {amount} tokens to {to:?} in tx {tx_hash:?}");
+
+        let notification = TransferNotification {
+            from: wallet.address(),
+            to,
+            amount,
+            tx_hash,
+            note,
+        };
+        self.notify_transfer(&notification, recipient)?;
+        Ok(tx_hash)
+    }
+
+    /// Publish the notification of a transfer, encrypted to the recipient's key, on the topic derived from it.
+    pub fn notify_transfer(
+        &self,
+        notification: &TransferNotification,
+        recipient: &PublicKey,
+    ) -> Result<(), TransferNotificationError> {
+        let payload = encrypt_notification(notification, recipient)?;
+        // The wallet doesn't have a BLS key to sign with, and the notification is checked on chain regardless.
+        self.publish(&transfer_topic(recipient), payload, &SecretKey::random())?;
+        debug!("Notified the transfer in tx {:?}", notification.tx_hash);
+        Ok(())
+    }
+
+    /// Listen for the notifications of the transfers made to the owner of the key, from now on and until
+    /// [`Client::unsubscribe`] is called with its [`transfer_topic`].
+    ///
+    /// The messages that don't decrypt with the key are dropped.
+    pub fn transfer_notifications(
+        &self,
+        recipient: &SecretKey,
+    ) -> impl Stream<Item = TransferNotification> {
+        let recipient = recipient.clone();
+        self.subscribe(&transfer_topic(&recipient.public_key()))
+            .filter_map(move |message| {
+                let notification = decrypt_notification(&message.payload, &recipient);
+                if notification.is_none() {
+                    warn!("Dropping a transfer notification that doesn't decrypt with our key");
+                }
+                futures::future::ready(notification)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_only_decrypt_with_the_recipient_key() {
+        let recipient = SecretKey::random();
+        let notification = TransferNotification {
+            from: RewardsAddress::repeat_byte(1),
+            to: RewardsAddress::repeat_byte(2),
+            amount: Amount::from(42u64),
+            tx_hash: TxHash::repeat_byte(3),
+            note: Some("rent".to_string()),
+        };
+
+        let payload = encrypt_notification(&notification, &recipient.public_key())
+            .expect("notification to encrypt");
+        assert_eq!(
+            decrypt_notification(&payload, &recipient),
+            Some(notification)
+        );
+        assert_eq!(decrypt_notification(&payload, &SecretKey::random()), None);
+
+        assert_ne!(
+            transfer_topic(&recipient.public_key()),
+            transfer_topic(&SecretKey::random().public_key())
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::num::NonZero;
+
+use ant_protocol::storage::RetryStrategy;
+use libp2p::kad::Quorum;
+use rand::Rng;
+
+/// Which of the uploaded chunks are verified to be stored, see [`UploadCfg::verification`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkVerification {
+    /// Every chunk is verified.
+    All,
+    /// A random sample of the given percentage of the chunks is verified, e.g. 10 for one chunk out of ten.
+    Sample(u8),
+    /// The chunks are not verified, only their puts have to reach the storing nodes.
+    None,
+}
+
+/// How chunks are uploaded and then verified, see [`crate::ClientConfig::upload`].
+///
+/// Verifying fewer chunks, or with a smaller quorum, makes uploads faster and cheaper in requests, at the cost of
+/// only finding out later about the chunks that ended up not stored.
+#[derive(Debug, Clone, Copy)]
+pub struct UploadCfg {
+    /// Which chunks to verify once uploaded.
+    ///
+    /// Defaults to [`ChunkVerification::All`].
+    pub verification: ChunkVerification,
+    /// The number of nodes that have to prove storing a chunk for it to be verified.
+    ///
+    /// Defaults to 2.
+    pub verification_quorum: Quorum,
+    /// How long to keep retrying the verification of a chunk, backing off between the attempts.
+    ///
+    /// Defaults to [`RetryStrategy::Balanced`].
+    pub verification_retry_strategy: RetryStrategy,
+    /// The number of times the chunks that failed to upload or verify are put again, before giving up on them.
+    ///
+    /// Defaults to 3.
+    pub reput_attempts: usize,
+    /// Probe which chunks the network already holds before paying, to neither pay for nor upload them again.
+    ///
+    /// Identical content encrypts to identical chunks, so common files are often stored already. Each probe asks
+    /// the close group of the chunk to prove holding it, which costs a request per chunk even when it isn't.
+    ///
+    /// Defaults to `false`.
+    pub dedup_check: bool,
+}
+
+impl Default for UploadCfg {
+    fn default() -> Self {
+        Self {
+            verification: ChunkVerification::All,
+            verification_quorum: Quorum::N(NonZero::new(2).expect("2 is non-zero")),
+            verification_retry_strategy: RetryStrategy::Balanced,
+            reput_attempts: 3,
+            dedup_check: false,
+        }
+    }
+}
+
+impl UploadCfg {
+    /// Whether to verify the next uploaded chunk.
+    pub(crate) fn should_verify(&self, rng: &mut impl Rng) -> bool {
+        match self.verification {
+            ChunkVerification::All => true,
+            ChunkVerification::Sample(percent) => rng.gen_range(0..100) < percent,
+            ChunkVerification::None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn the_sampled_share_of_chunks_is_verified() {
+        let mut rng = thread_rng();
+        let mut verified = |verification| {
+            let cfg = UploadCfg {
+                verification,
+                ..Default::default()
+            };
+            (0..1000).filter(|_| cfg.should_verify(&mut rng)).count()
+        };
+
+        assert_eq!(verified(ChunkVerification::All), 1000);
+        assert_eq!(verified(ChunkVerification::None), 0);
+        assert_eq!(verified(ChunkVerification::Sample(0)), 0);
+        assert_eq!(verified(ChunkVerification::Sample(100)), 1000);
+        let sampled = verified(ChunkVerification::Sample(20));
+        assert!((100..300).contains(&sampled), "{sampled} chunks verified");
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::{future::Future, sync::Mutex};
+
+use ant_networking::{
+    target_arch::{Duration, Instant},
+    GetRecordError, NetworkError,
+};
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use super::{bandwidth::BandwidthConfig, data::PutError, Client, ClientEvent};
+use crate::client::data::CHUNK_UPLOAD_BATCH_SIZE;
+
+/// Puts completing within this time count as fast, growing the window.
+const FAST_PUT_THRESHOLD: Duration = Duration::from_secs(20);
+/// Without a configured max, the window can grow up to this many times its initial size.
+const MAX_WINDOW_FACTOR: usize = 4;
+
+/// How a chunk put went, as far as the upload window is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PutOutcome {
+    /// The put succeeded within `FAST_PUT_THRESHOLD`.
+    Fast,
+    /// The put succeeded, but took its time.
+    Slow,
+    /// The put timed out or failed to reach its quorum, a sign the link or the network is overwhelmed.
+    Congested,
+    /// The put failed for a reason unrelated to load.
+    Failed,
+}
+
+impl PutOutcome {
+    fn new(result: Result<(), &PutError>, elapsed: Duration) -> Self {
+        match result {
+            Ok(()) if elapsed <= FAST_PUT_THRESHOLD => Self::Fast,
+            Ok(()) => Self::Slow,
+            Err(PutError::Network(
+                NetworkError::PutRecordQuorumFailed { .. }
+                | NetworkError::RecordNotStoredByNodes(_)
+                | NetworkError::FailedToVerifyChunkProof(_)
+                | NetworkError::OutboundError(_)
+                | NetworkError::GetRecordError(
+                    GetRecordError::QueryTimeout | GetRecordError::NotEnoughCopies { .. },
+                ),
+            )) => Self::Congested,
+            Err(_) => Self::Failed,
+        }
+    }
+}
+
+/// AIMD controller of the number of chunk uploads in flight at once.
+///
+/// The window grows by one for every window worth of fast puts, and halves on congestion. Once halved, further
+/// congestion is ignored until a window worth of puts has completed, as those were already in flight.
+#[derive(Debug)]
+pub(crate) struct UploadWindow {
+    state: Mutex<WindowState>,
+}
+
+#[derive(Debug)]
+struct WindowState {
+    window: usize,
+    max: usize,
+    /// Fast puts since the window last grew.
+    fast_puts: usize,
+    completions_since_decrease: usize,
+}
+
+impl Default for UploadWindow {
+    fn default() -> Self {
+        Self::new(&BandwidthConfig::default())
+    }
+}
+
+impl UploadWindow {
+    /// Start at the configured concurrency, or at `CHUNK_UPLOAD_BATCH_SIZE`. A configured concurrency is the max.
+    pub(crate) fn new(config: &BandwidthConfig) -> Self {
+        let (initial, max) = match config.max_concurrent_uploads {
+            Some(max) => (max.max(1), max.max(1)),
+            None => {
+                let initial = (*CHUNK_UPLOAD_BATCH_SIZE).max(1);
+                (initial, initial * MAX_WINDOW_FACTOR)
+            }
+        };
+        Self {
+            state: Mutex::new(WindowState {
+                window: initial,
+                max,
+                fast_puts: 0,
+                completions_since_decrease: usize::MAX,
+            }),
+        }
+    }
+
+    /// The number of uploads that may currently be in flight.
+    pub(crate) fn current(&self) -> usize {
+        self.lock().window
+    }
+
+    /// Adjust the window to the outcome of a put. Returns the new window if it changed.
+    fn record(&self, outcome: PutOutcome) -> Option<usize> {
+        let mut state = self.lock();
+        let before = state.window;
+        state.completions_since_decrease = state.completions_since_decrease.saturating_add(1);
+
+        match outcome {
+            PutOutcome::Fast => {
+                state.fast_puts += 1;
+                if state.fast_puts >= state.window {
+                    state.window = (state.window + 1).min(state.max);
+                    state.fast_puts = 0;
+                }
+            }
+            PutOutcome::Congested if state.completions_since_decrease > before => {
+                state.window = (state.window / 2).max(1);
+                state.fast_puts = 0;
+                state.completions_since_decrease = 0;
+            }
+            PutOutcome::Congested | PutOutcome::Slow | PutOutcome::Failed => {}
+        }
+
+        (state.window != before).then_some(state.window)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WindowState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Client {
+    /// Run the upload tasks with as many in flight as the upload window allows, adjusting it as they complete.
+    pub(crate) async fn process_uploads_with_adaptive_concurrency<I, T, C>(
+        &self,
+        tasks: I,
+    ) -> Vec<Result<T, (C, PutError)>>
+    where
+        I: IntoIterator,
+        I::Item: Future<Output = Result<T, (C, PutError)>> + Send,
+    {
+        let mut tasks = tasks.into_iter().map(|task| async move {
+            let start = Instant::now();
+            let result = task.await;
+            (start.elapsed(), result)
+        });
+        let mut futures = FuturesUnordered::new();
+        let mut results = Vec::new();
+
+        loop {
+            while futures.len() < self.upload_window.current() {
+                match tasks.next() {
+                    Some(task) => futures.push(task),
+                    None => break,
+                }
+            }
+            let Some((elapsed, result)) = futures.next().await else {
+                break;
+            };
+
+            let outcome =
+                PutOutcome::new(result.as_ref().map(|_| ()).map_err(|(_, err)| err), elapsed);
+            if let Some(window) = self.upload_window.record(outcome) {
+                debug!("Upload concurrency window changed to {window} after a {outcome:?} put");
+                self.send_client_event(ClientEvent::UploadConcurrencyChanged(window));
+            }
+            results.push(result);
+        }
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(initial: usize) -> UploadWindow {
+        UploadWindow::new(&BandwidthConfig {
+            max_concurrent_uploads: Some(initial),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn window_halves_once_per_window_on_congestion() {
+        let window = window(8);
+        assert_eq!(window.record(PutOutcome::Congested), Some(4));
+        // The other puts in flight at the time don't halve it again.
+        for _ in 0..4 {
+            assert_eq!(window.record(PutOutcome::Congested), None);
+        }
+        assert_eq!(window.record(PutOutcome::Congested), Some(2));
+        assert_eq!(window.record(PutOutcome::Failed), None);
+        assert_eq!(window.current(), 2);
+    }
+
+    #[test]
+    fn window_grows_on_fast_puts_up_to_the_max() {
+        let window = window(4);
+        assert_eq!(window.record(PutOutcome::Congested), Some(2));
+
+        // One more per window worth of fast puts.
+        assert_eq!(window.record(PutOutcome::Fast), None);
+        assert_eq!(window.record(PutOutcome::Fast), Some(3));
+        assert_eq!(window.record(PutOutcome::Slow), None);
+
+        for _ in 0..20 {
+            let _ = window.record(PutOutcome::Fast);
+        }
+        assert_eq!(window.current(), 4);
+    }
+}
@@ -7,25 +7,25 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::client::payment::{receipt_from_store_quotes, Receipt};
-use ant_evm::{EvmWallet, ProofOfPayment};
-use ant_networking::{GetRecordCfg, PutRecordCfg, VerificationKind};
+use ant_evm::{AttoTokens, EvmWallet, ProofOfPayment};
+use ant_networking::PutRecordCfg;
 use ant_protocol::{
     messages::ChunkProof,
     storage::{try_serialize_record, Chunk, RecordKind, RetryStrategy},
 };
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::stream::{FuturesUnordered, StreamExt};
 use libp2p::kad::{Quorum, Record};
 use rand::{thread_rng, Rng};
 use self_encryption::{decrypt_full_set, DataMap, EncryptedChunk};
-use std::{future::Future, num::NonZero};
+use std::{collections::HashSet, future::Future};
 use xor_name::XorName;
 
 use super::{
-    data::{GetError, PayError, PutError, CHUNK_DOWNLOAD_BATCH_SIZE},
-    Client,
+    data::{GetError, PayError, PutError},
+    Client, ClientEvent,
 };
-use crate::self_encryption::DataMapLevel;
+use crate::self_encryption::{DataMapLevel, SourceDataMaps};
 
 impl Client {
     /// Fetch and decrypt all chunks in the data map.
@@ -51,11 +51,13 @@ impl Client {
             });
         }
         debug!("Successfully fetched all the encrypted chunks");
-        let encrypted_chunks =
-            process_tasks_with_max_concurrency(download_tasks, *CHUNK_DOWNLOAD_BATCH_SIZE)
-                .await
-                .into_iter()
-                .collect::<Result<Vec<EncryptedChunk>, GetError>>()?;
+        let encrypted_chunks = process_tasks_with_max_concurrency(
+            download_tasks,
+            self.bandwidth.download_concurrency(),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<Vec<EncryptedChunk>, GetError>>()?;
 
         let data = decrypt_full_set(data_map, &encrypted_chunks).map_err(|e| {
             error!("Error decrypting encrypted_chunks: {e:?}");
@@ -70,26 +72,65 @@ impl Client {
         &self,
         data_map_bytes: &Bytes,
     ) -> Result<Bytes, GetError> {
+        let source = self.resolve_data_maps(data_map_bytes).await?;
+        let data = match source.data_maps.as_slice() {
+            [data_map] => self.fetch_from_data_map(data_map).await?,
+            data_maps => {
+                let mut data = BytesMut::new();
+                for data_map in data_maps {
+                    data.extend_from_slice(&self.fetch_from_data_map(data_map).await?);
+                }
+                data.freeze()
+            }
+        };
+
+        match source.compression {
+            Some(compression) => compression.decompress(&data).map_err(GetError::Decryption),
+            None => Ok(data),
+        }
+    }
+
+    /// Unpack a wrapped data map, fetching any additional levels, down to the data maps of the source data.
+    ///
+    /// That is a single data map, unless the data was chunked into segments with custom [`super::data::ChunkingParams`].
+    pub(crate) async fn resolve_data_maps(
+        &self,
+        data_map_bytes: &Bytes,
+    ) -> Result<SourceDataMaps, GetError> {
         let mut data_map_level: DataMapLevel = rmp_serde::from_slice(data_map_bytes)
             .map_err(GetError::InvalidDataMap)
             .inspect_err(|err| error!("Error deserializing data map: {err:?}"))?;
+        let mut compression = None;
 
         loop {
-            let data_map = match &data_map_level {
-                DataMapLevel::First(map) => map,
-                DataMapLevel::Additional(map) => map,
-            };
-
-            let data = self.fetch_from_data_map(data_map).await?;
-
-            match &data_map_level {
-                DataMapLevel::First(_) => break Ok(data),
-                DataMapLevel::Additional(_) => {
+            match data_map_level {
+                DataMapLevel::First(data_map) => {
+                    break Ok(SourceDataMaps {
+                        data_maps: vec![data_map],
+                        compression,
+                    })
+                }
+                DataMapLevel::Segmented(params, data_maps) => {
+                    debug!(
+                        "Data was chunked into {} segments with {params:?}",
+                        data_maps.len()
+                    );
+                    break Ok(SourceDataMaps {
+                        data_maps,
+                        compression,
+                    });
+                }
+                DataMapLevel::Compressed(algorithm, level) => {
+                    debug!("Data was compressed with {algorithm:?}");
+                    compression = Some(algorithm);
+                    data_map_level = *level;
+                }
+                DataMapLevel::Additional(data_map) => {
+                    let data = self.fetch_from_data_map(&data_map).await?;
                     data_map_level = rmp_serde::from_slice(&data).map_err(|err| {
                         error!("Error deserializing data map: {err:?}");
                         GetError::InvalidDataMap(err)
                     })?;
-                    continue;
                 }
             };
         }
@@ -113,7 +154,7 @@ impl Client {
         let record_kind = RecordKind::ChunkWithPayment;
         let record = Record {
             key: key.clone(),
-            value: try_serialize_record(&(payment, chunk.clone()), record_kind)
+            value: try_serialize_record(&(&payment, chunk), record_kind)
                 .map_err(|e| {
                     PutError::Serialization(format!(
                         "Failed to serialize chunk with payment: {e:?}"
@@ -124,39 +165,133 @@ impl Client {
             expires: None,
         };
 
-        let verification = {
-            let verification_cfg = GetRecordCfg {
-                get_quorum: Quorum::N(NonZero::new(2).expect("2 is non-zero")),
-                retry_strategy: Some(RetryStrategy::Balanced),
-                target_record: None,
-                expected_holders: Default::default(),
-                is_register: false,
-            };
-
-            let stored_on_node = try_serialize_record(&chunk, RecordKind::Chunk)
-                .map_err(|e| PutError::Serialization(format!("Failed to serialize chunk: {e:?}")))?
-                .to_vec();
-            let random_nonce = thread_rng().gen::<u64>();
-            let expected_proof = ChunkProof::new(&stored_on_node, random_nonce);
-
-            Some((
-                VerificationKind::ChunkProof {
-                    expected_proof,
-                    nonce: random_nonce,
-                },
-                verification_cfg,
-            ))
-        };
-
+        // The chunk is verified once stored rather than as part of the put, to report on both steps.
         let put_cfg = PutRecordCfg {
             put_quorum: Quorum::One,
-            retry_strategy: Some(RetryStrategy::Balanced),
+            retry_strategy: Some(self.retry_strategy),
             use_put_record_to: Some(storing_nodes.clone()),
-            verification,
+            verification: None,
         };
-        let payment_upload = Ok(self.network.put_record(record, &put_cfg).await?);
-        debug!("Successfully stored chunk: {chunk:?} to {storing_nodes:?}");
-        payment_upload
+        let stored_on = self
+            .cancellable(async {
+                self.bandwidth.throttle_upload(chunk.value.len()).await;
+                Ok::<_, PutError>(self.network.put_record(record, &put_cfg).await?)
+            })
+            .await?;
+        debug!("Successfully stored chunk: {chunk:?} to {stored_on:?}, out of {storing_nodes:?}");
+        self.send_client_event(ClientEvent::ChunkUploaded {
+            address: *chunk.name(),
+            bytes: chunk.value.len(),
+        });
+
+        if !self.upload_cfg.should_verify(&mut thread_rng()) {
+            debug!("Skipping the verification of chunk {:?}", chunk.address());
+            return Ok(());
+        }
+        let stored_on_node = try_serialize_record(&chunk, RecordKind::Chunk)
+            .map_err(|e| PutError::Serialization(format!("Failed to serialize chunk: {e:?}")))?
+            .to_vec();
+        let random_nonce = thread_rng().gen::<u64>();
+        let expected_proof = ChunkProof::new(&stored_on_node, random_nonce);
+        let verified = self
+            .cancellable(async {
+                Ok::<_, PutError>(
+                    self.network
+                        .verify_chunk_existence(
+                            chunk.network_address(),
+                            random_nonce,
+                            expected_proof,
+                            self.upload_cfg.verification_quorum,
+                            Some(self.upload_cfg.verification_retry_strategy),
+                        )
+                        .await?,
+                )
+            })
+            .await;
+        match verified {
+            Ok(()) => {}
+            Err(err @ PutError::Cancelled(_)) => return Err(err),
+            Err(err) => {
+                // The nodes tell why they rejected the payment only when asked to.
+                let rejections = self
+                    .network
+                    .get_payment_rejections(
+                        chunk.network_address(),
+                        payment.hash()?,
+                        &storing_nodes,
+                    )
+                    .await;
+                if rejections.is_empty() {
+                    return Err(err);
+                }
+                warn!(
+                    "Payment for chunk {:?} rejected by {} payees",
+                    chunk.address(),
+                    rejections.len()
+                );
+                return Err(PutError::PaymentRejected {
+                    address: chunk.network_address(),
+                    proof: Box::new(payment),
+                    rejections,
+                });
+            }
+        }
+        debug!("Verified chunk {:?} to be stored", chunk.address());
+        self.send_client_event(ClientEvent::ChunkVerified {
+            address: *chunk.name(),
+        });
+        Ok(())
+    }
+
+    /// The chunks the network already holds, out of the given ones, if [`super::upload_cfg::UploadCfg::dedup_check`]
+    /// is enabled. They're proven held by the same quorum as the uploads are verified with.
+    pub(crate) async fn chunks_already_stored(&self, chunks: &[&Chunk]) -> HashSet<XorName> {
+        if !self.upload_cfg.dedup_check {
+            return HashSet::new();
+        }
+
+        let probes: Vec<_> = chunks
+            .iter()
+            .copied()
+            .map(|chunk| async move {
+                let stored_on_node = try_serialize_record(chunk, RecordKind::Chunk)
+                    .ok()?
+                    .to_vec();
+                let nonce = thread_rng().gen::<u64>();
+                let expected_proof = ChunkProof::new(&stored_on_node, nonce);
+                self.network
+                    .verify_chunk_existence(
+                        chunk.network_address(),
+                        nonce,
+                        expected_proof,
+                        self.upload_cfg.verification_quorum,
+                        Some(RetryStrategy::None),
+                    )
+                    .await
+                    .ok()
+                    .map(|()| (*chunk.name(), chunk.value.len()))
+            })
+            .collect();
+        let stored: Vec<_> =
+            process_tasks_with_max_concurrency(probes, self.bandwidth.upload_concurrency())
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+
+        if !stored.is_empty() {
+            let bytes = stored.iter().map(|(_, bytes)| bytes).sum();
+            info!(
+                "{} out of {} chunks are stored already, skipping paying for and uploading {bytes} bytes",
+                stored.len(),
+                chunks.len()
+            );
+            self.send_client_event(ClientEvent::ChunksAlreadyStored {
+                count: stored.len(),
+                bytes,
+            });
+        }
+        stored.into_iter().map(|(name, _)| name).collect()
     }
 
     /// Pay for the chunks and get the proof of payment.
@@ -170,7 +305,9 @@ impl Client {
 
         // Make sure nobody else can use the wallet while we are paying
         debug!("Waiting for wallet lock");
-        let lock_guard = wallet.lock().await;
+        let lock_guard = self
+            .cancellable(async { Ok::<_, PayError>(wallet.lock().await) })
+            .await?;
         debug!("Locked wallet");
 
         // TODO: the error might contain some succeeded quote payments as well. These should be returned on err, so that they can be skipped when retrying.
@@ -193,6 +330,13 @@ impl Client {
         );
 
         let receipt = receipt_from_store_quotes(quotes);
+        if !receipt.is_empty() {
+            let cost = receipt.values().map(|(_, price)| price.as_atto()).sum();
+            self.send_client_event(ClientEvent::PaymentMade {
+                record_count: receipt.len(),
+                cost: AttoTokens::from_atto(cost),
+            });
+        }
 
         Ok(receipt)
     }
@@ -17,10 +17,10 @@ use crate::client::data::PutError;
 use crate::client::payment::PaymentOption;
 use crate::client::Client;
 use ant_evm::{Amount, AttoTokens};
-use ant_networking::{GetRecordCfg, GetRecordError, NetworkError, PutRecordCfg, VerificationKind};
-use ant_protocol::storage::{
-    try_serialize_record, RecordKind, RetryStrategy, Scratchpad, ScratchpadAddress,
+use ant_networking::{
+    GetRecordCfg, GetRecordError, GetRecordOk, NetworkError, PutRecordCfg, VerificationKind,
 };
+use ant_protocol::storage::{try_serialize_record, RecordKind, Scratchpad, ScratchpadAddress};
 use ant_protocol::Bytes;
 use ant_protocol::{storage::try_deserialize_record, NetworkAddress};
 use libp2p::kad::{Quorum, Record};
@@ -83,25 +83,27 @@ impl Client {
         info!("Fetching vault from network at {network_address:?}",);
         let scratch_key = network_address.to_record_key();
 
-        let get_cfg = GetRecordCfg {
+        let get_cfg = self.get_cfg.apply(GetRecordCfg {
             get_quorum: Quorum::Majority,
             retry_strategy: None,
             target_record: None,
             expected_holders: HashSet::new(),
             is_register: false,
-        };
+        });
 
         let pad = match self
             .network
             .get_record_from_network(scratch_key.clone(), &get_cfg)
             .await
         {
-            Ok(record) => {
+            Ok(GetRecordOk { record, .. }) => {
                 debug!("Got scratchpad for {scratch_key:?}");
                 try_deserialize_record::<Scratchpad>(&record)
                     .map_err(|_| VaultError::CouldNotDeserializeVaultScratchPad(scratch_address))?
             }
-            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord { result_map })) => {
+            Err(NetworkError::GetRecordError(GetRecordError::SplitRecord {
+                result_map, ..
+            })) => {
                 debug!("Got multiple scratchpads for {scratch_key:?}");
                 let mut pads = result_map
                     .values()
@@ -231,21 +233,24 @@ impl Client {
             }
         };
 
-        let put_cfg = PutRecordCfg {
-            put_quorum: Quorum::Majority,
-            retry_strategy: Some(RetryStrategy::Balanced),
-            use_put_record_to: None,
-            verification: Some((
-                VerificationKind::Crdt,
-                GetRecordCfg {
-                    get_quorum: Quorum::Majority,
-                    retry_strategy: None,
-                    target_record: None,
-                    expected_holders: HashSet::new(),
-                    is_register: false,
-                },
-            )),
-        };
+        let put_cfg = self.put_cfg.apply(
+            PutRecordCfg {
+                put_quorum: Quorum::Majority,
+                retry_strategy: Some(self.retry_strategy),
+                use_put_record_to: None,
+                verification: Some((
+                    VerificationKind::Crdt,
+                    GetRecordCfg {
+                        get_quorum: Quorum::Majority,
+                        retry_strategy: None,
+                        target_record: None,
+                        expected_holders: HashSet::new(),
+                        is_register: false,
+                    },
+                )),
+            },
+            &record,
+        );
 
         debug!("Put record - scratchpad at {scratch_address:?} to the network");
         self.network
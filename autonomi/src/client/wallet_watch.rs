@@ -0,0 +1,168 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::collections::VecDeque;
+
+use ant_evm::{Amount, EvmWatchOnlyWallet};
+use ant_networking::target_arch::{sleep, Duration};
+use futures::{stream, Stream, StreamExt};
+
+use crate::client::{pubsub::SecretKey, transfer_notifications::TransferNotification, Client};
+
+/// The balances of a wallet, as last checked on chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletBalances {
+    pub tokens: Amount,
+    pub gas: Amount,
+}
+
+/// A change to a wallet, see [`Client::watch_wallet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletEvent {
+    /// The balances changed, or were checked for the first time.
+    BalanceChanged(WalletBalances),
+    /// Tokens were received.
+    Deposited(Amount),
+    /// Tokens were spent, e.g. for a payment or a transfer confirmed on chain.
+    Spent(Amount),
+    /// A transfer to the wallet was notified, before it was checked on chain, see
+    /// [`Client::transfer_notifications`].
+    TransferNotified(TransferNotification),
+}
+
+/// What has the balances checked again.
+enum Trigger {
+    Interval,
+    Notification(TransferNotification),
+}
+
+/// The events telling the change from the `previous` balances to the `current` ones.
+fn balance_events(previous: Option<WalletBalances>, current: WalletBalances) -> Vec<WalletEvent> {
+    let Some(previous) = previous else {
+        return vec![WalletEvent::BalanceChanged(current)];
+    };
+    if previous == current {
+        return vec![];
+    }
+    let mut events = vec![];
+    if current.tokens > previous.tokens {
+        events.push(WalletEvent::Deposited(current.tokens - previous.tokens));
+    } else if current.tokens < previous.tokens {
+        events.push(WalletEvent::Spent(previous.tokens - current.tokens));
+    }
+    events.push(WalletEvent::BalanceChanged(current));
+    events
+}
+
+async fn check_balances(wallet: &EvmWatchOnlyWallet) -> Option<WalletBalances> {
+    let balances = async {
+        Ok::<_, ant_evm::EvmWalletError>(WalletBalances {
+            tokens: wallet.balance_of_tokens().await?,
+            gas: wallet.balance_of_gas_tokens().await?,
+        })
+    };
+    balances
+        .await
+        .inspect_err(|err| {
+            warn!(
+                "Error while checking the balances of {:?}: {err:?}",
+                wallet.address()
+            )
+        })
+        .ok()
+}
+
+impl Client {
+    /// Watch the balances of the wallet, checking them on chain at every interval, and whenever a transfer to it is
+    /// notified if the key it's notified to is given, see [`Client::transfer_notifications`].
+    ///
+    /// The first event tells the balances as first checked. The checks that fail are logged and skipped, and the
+    /// stream never ends on its own, the notifications being listened to until it's dropped.
+    pub fn watch_wallet(
+        &self,
+        wallet: EvmWatchOnlyWallet,
+        interval: Duration,
+        recipient: Option<&SecretKey>,
+    ) -> impl Stream<Item = WalletEvent> {
+        let intervals = stream::unfold(true, move |first| async move {
+            if !first {
+                sleep(interval).await;
+            }
+            Some((Trigger::Interval, false))
+        });
+        let triggers = match recipient {
+            Some(recipient) => stream::select(
+                intervals,
+                self.transfer_notifications(recipient)
+                    .map(Trigger::Notification),
+            )
+            .left_stream(),
+            None => intervals.right_stream(),
+        };
+        info!("Watching the wallet {:?}", wallet.address());
+
+        let state = (Box::pin(triggers), wallet, None, VecDeque::new());
+        stream::unfold(
+            state,
+            |(mut triggers, wallet, mut balances, mut events)| async move {
+                loop {
+                    if let Some(event) = events.pop_front() {
+                        return Some((event, (triggers, wallet, balances, events)));
+                    }
+                    if let Trigger::Notification(notification) = triggers.next().await? {
+                        events.push_back(WalletEvent::TransferNotified(notification));
+                    }
+                    if let Some(current) = check_balances(&wallet).await {
+                        events.extend(balance_events(balances, current));
+                        balances = Some(current);
+                    }
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balances(tokens: u64, gas: u64) -> WalletBalances {
+        WalletBalances {
+            tokens: Amount::from(tokens),
+            gas: Amount::from(gas),
+        }
+    }
+
+    #[test]
+    fn balance_changes_are_told_as_deposits_and_spends() {
+        assert_eq!(
+            balance_events(None, balances(10, 1)),
+            vec![WalletEvent::BalanceChanged(balances(10, 1))]
+        );
+        assert!(balance_events(Some(balances(10, 1)), balances(10, 1)).is_empty());
+        assert_eq!(
+            balance_events(Some(balances(10, 1)), balances(15, 1)),
+            vec![
+                WalletEvent::Deposited(Amount::from(5)),
+                WalletEvent::BalanceChanged(balances(15, 1))
+            ]
+        );
+        assert_eq!(
+            balance_events(Some(balances(10, 2)), balances(7, 1)),
+            vec![
+                WalletEvent::Spent(Amount::from(3)),
+                WalletEvent::BalanceChanged(balances(7, 1))
+            ]
+        );
+        // The gas alone being spent doesn't tell a spend of tokens.
+        assert_eq!(
+            balance_events(Some(balances(10, 2)), balances(10, 1)),
+            vec![WalletEvent::BalanceChanged(balances(10, 1))]
+        );
+    }
+}
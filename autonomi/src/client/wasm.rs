@@ -622,6 +622,112 @@ mod vault {
     }
 }
 
+#[cfg(feature = "registers")]
+#[allow(deprecated)]
+mod registers {
+    use super::*;
+    use crate::client::registers::{Register, RegisterAddress};
+    use bytes::Bytes;
+    use wasm_bindgen::{JsError, JsValue};
+
+    /// A register fetched from or created on the network.
+    #[wasm_bindgen(js_name = Register)]
+    pub struct JsRegister(Register);
+
+    #[wasm_bindgen(js_class = Register)]
+    impl JsRegister {
+        /// Returns the hex encoded address of the register.
+        #[wasm_bindgen]
+        pub fn address(&self) -> String {
+            self.0.address().to_hex()
+        }
+
+        /// Returns the current values of the register, as an array of `Uint8Array`. There can be
+        /// multiple values in case the register was updated concurrently.
+        #[wasm_bindgen]
+        pub fn values(&self) -> Result<JsValue, JsError> {
+            Ok(serde_wasm_bindgen::to_value(&self.0.values())?)
+        }
+    }
+
+    #[wasm_bindgen(js_class = Client)]
+    impl JsClient {
+        /// Get the hex encoded address of a register from its name and owner.
+        #[wasm_bindgen(js_name = registerAddress)]
+        pub fn register_address(name: String, owner: &SecretKeyJs) -> String {
+            crate::client::Client::register_address(&name, &owner.0).to_hex()
+        }
+
+        /// Get the cost to create a register.
+        #[wasm_bindgen(js_name = registerCost)]
+        pub async fn register_cost(
+            &self,
+            name: String,
+            owner: &SecretKeyJs,
+        ) -> Result<AttoTokens, JsError> {
+            let cost = self.0.register_cost(name, owner.0.clone()).await?;
+
+            Ok(AttoTokens(cost))
+        }
+
+        /// Create a register with a name and an optional initial value, with the owner as the only writer.
+        ///
+        /// # Example
+        ///
+        /// ```js
+        /// const owner = genSecretKey();
+        /// const register = await client.registerCreate(new Uint8Array([0, 1, 2, 3]), "foo", owner, wallet);
+        /// const registerAddr = register.address();
+        /// ```
+        #[wasm_bindgen(js_name = registerCreate)]
+        pub async fn register_create(
+            &self,
+            value: Option<Vec<u8>>,
+            name: String,
+            owner: &SecretKeyJs,
+            wallet: &JsWallet,
+        ) -> Result<JsRegister, JsError> {
+            let register = self
+                .0
+                .register_create(value.map(Bytes::from), &name, owner.0.clone(), &wallet.0)
+                .await?;
+
+            Ok(JsRegister(register))
+        }
+
+        /// Fetch a register from the network.
+        #[wasm_bindgen(js_name = registerGet)]
+        pub async fn register_get(&self, addr: String) -> Result<JsRegister, JsError> {
+            let addr = RegisterAddress::from_hex(&addr)?;
+            let register = self.0.register_get(addr).await?;
+
+            Ok(JsRegister(register))
+        }
+
+        /// Update a register with a new value, overwriting the existing value(s).
+        ///
+        /// # Example
+        ///
+        /// ```js
+        /// const register = await client.registerGet(registerAddr);
+        /// await client.registerUpdate(register, new Uint8Array([4, 5, 6]), owner);
+        /// ```
+        #[wasm_bindgen(js_name = registerUpdate)]
+        pub async fn register_update(
+            &self,
+            register: &JsRegister,
+            value: Vec<u8>,
+            writer: &SecretKeyJs,
+        ) -> Result<(), JsError> {
+            self.0
+                .register_update(register.0.clone(), Bytes::from(value), writer.0.clone())
+                .await?;
+
+            Ok(())
+        }
+    }
+}
+
 #[cfg(feature = "external-signer")]
 mod external_signer {
     use super::*;
@@ -0,0 +1,570 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! C bindings of the client, for apps written in other languages to embed it, e.g. mobile apps through JNI or
+//! Swift's C interop. A header can be generated from this module with `cbindgen`.
+//!
+//! Every fallible function takes an `error` out-param, set to a message to free with [`autonomi_string_free`] when
+//! it fails. The [`ErrorCode`] of the failure is then told by [`autonomi_last_error_code`], for the apps to retry or
+//! alert on without parsing the message. The handles, strings and bytes returned are owned by the caller and freed with their `_free` function.
+//!
+//! The operations block the calling thread until they complete, reporting their progress through the optional
+//! callback on that same thread.
+
+#![allow(unsafe_code)]
+
+use crate::client::address::{addr_to_str, str_to_addr};
+use crate::client::{Client, ClientEvent, ErrorCode};
+use crate::{get_evm_network_from_env, Bytes, Wallet};
+use futures::future::{select, Either};
+use std::cell::Cell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::future::Future;
+use std::path::PathBuf;
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast::error::RecvError;
+
+/// A client connected to the network, along with the runtime its operations run on.
+pub struct AutonomiClient {
+    runtime: Runtime,
+    inner: Client,
+}
+
+/// A wallet paying for the uploads.
+pub struct AutonomiWallet {
+    inner: Wallet,
+}
+
+/// Bytes owned by the caller, to free with [`autonomi_bytes_free`]. `data` is null on failure.
+#[repr(C)]
+pub struct AutonomiBytes {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// The progress reported to the callback of an operation.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutonomiProgress {
+    /// A chunk of `bytes` was stored.
+    ChunkUploaded = 0,
+    /// A chunk of `bytes` was fetched.
+    ChunkFetched = 1,
+    /// Storage was paid for, `bytes` being the number of records paid for.
+    PaymentMade = 2,
+}
+
+/// Called with the progress of an operation and the `user_data` it was started with.
+pub type AutonomiProgressCallback =
+    Option<extern "C" fn(progress: AutonomiProgress, bytes: usize, user_data: *mut c_void)>;
+
+impl AutonomiProgress {
+    fn from_event(event: &ClientEvent) -> Option<(Self, usize)> {
+        match event {
+            ClientEvent::ChunkUploaded { bytes, .. } => Some((Self::ChunkUploaded, *bytes)),
+            ClientEvent::ChunkFetched { bytes, .. } => Some((Self::ChunkFetched, *bytes)),
+            ClientEvent::PaymentMade { record_count, .. } => {
+                Some((Self::PaymentMade, *record_count))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl AutonomiClient {
+    /// Run the operation to completion, forwarding the progress events of the client to the callback meanwhile.
+    ///
+    /// The events of the operations run concurrently on the same client are forwarded to each of their callbacks.
+    fn run<F: Future>(
+        &self,
+        progress: AutonomiProgressCallback,
+        user_data: *mut c_void,
+        operation: F,
+    ) -> F::Output {
+        let (Some(callback), Some(sender)) = (progress, self.inner.client_event_sender.as_ref())
+        else {
+            return self.runtime.block_on(operation);
+        };
+        let mut events = sender.subscribe();
+
+        self.runtime.block_on(async move {
+            let mut operation = std::pin::pin!(operation);
+            loop {
+                let event = std::pin::pin!(events.recv());
+                match select(operation, event).await {
+                    Either::Left((output, _)) => return output,
+                    Either::Right((event, pending)) => {
+                        operation = pending;
+                        match event {
+                            Ok(event) => {
+                                if let Some((progress, bytes)) =
+                                    AutonomiProgress::from_event(&event)
+                                {
+                                    callback(progress, bytes, user_data);
+                                }
+                            }
+                            Err(RecvError::Lagged(_)) => {}
+                            Err(RecvError::Closed) => return operation.await,
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// An error to report to the caller, along with its class.
+#[derive(Debug)]
+struct FfiError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: ErrorCode, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+// The arguments are the only source of bare messages.
+impl From<String> for FfiError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+}
+
+thread_local! {
+    static LAST_ERROR_CODE: Cell<u16> = const { Cell::new(0) };
+}
+
+fn set_error(error: *mut *mut c_char, err: FfiError) {
+    LAST_ERROR_CODE.with(|code| code.set(err.code.as_u16()));
+    if !error.is_null() {
+        // SAFETY: the caller passes either null or a valid pointer to write the error to.
+        unsafe { *error = into_c_string(err.message) };
+    }
+}
+
+/// The [`ErrorCode`] number of the last error reported on the calling thread, 0 if none was.
+#[no_mangle]
+pub extern "C" fn autonomi_last_error_code() -> u16 {
+    LAST_ERROR_CODE.with(Cell::get)
+}
+
+/// Whether an operation failing with the [`ErrorCode`] number may succeed when retried. False for unknown codes.
+#[no_mangle]
+pub extern "C" fn autonomi_error_code_is_retryable(code: u16) -> bool {
+    ErrorCode::from_u16(code).is_some_and(ErrorCode::is_retryable)
+}
+
+fn into_c_string(string: String) -> *mut c_char {
+    CString::new(string)
+        .unwrap_or_else(|_| c"invalid string".to_owned())
+        .into_raw()
+}
+
+/// Reads a nul terminated UTF-8 string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a nul terminated string outliving the returned reference.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
+    if ptr.is_null() {
+        return Err(format!("{name} is null"));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|err| format!("{name} is not valid UTF-8: {err}"))
+}
+
+/// Reads a handle argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a live handle.
+unsafe fn handle_arg<'a, T>(ptr: *const T, name: &str) -> Result<&'a T, String> {
+    ptr.as_ref().ok_or_else(|| format!("{name} is null"))
+}
+
+/// Connect to the network through the `peers_len` multiaddrs of `peers`. Returns null on failure.
+///
+/// # Safety
+///
+/// `peers` must point to `peers_len` nul terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_client_connect(
+    peers: *const *const c_char,
+    peers_len: usize,
+    error: *mut *mut c_char,
+) -> *mut AutonomiClient {
+    let connect = || -> Result<AutonomiClient, FfiError> {
+        if peers.is_null() && peers_len > 0 {
+            return Err("peers is null".to_string().into());
+        }
+        let peers = (0..peers_len)
+            .map(|i| {
+                str_arg(*peers.add(i), "peer")?
+                    .parse()
+                    .map_err(|err| format!("Invalid multiaddr: {err}"))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let runtime = Runtime::new().map_err(|err| {
+            FfiError::new(
+                ErrorCode::Io,
+                format!("Could not start tokio runtime: {err}"),
+            )
+        })?;
+        let mut inner = runtime
+            .block_on(Client::init_with_peers(peers))
+            .map_err(|err| FfiError::new(err.code(), format!("Failed to connect: {err}")))?;
+        let _ = inner.enable_client_events();
+
+        Ok(AutonomiClient { runtime, inner })
+    };
+
+    match connect() {
+        Ok(client) => Box::into_raw(Box::new(client)),
+        Err(err) => {
+            set_error(error, err);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a client returned by [`autonomi_client_connect`].
+///
+/// # Safety
+///
+/// `client` must be null or a client not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_client_free(client: *mut AutonomiClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Loads the wallet of the hex encoded private key, on the EVM network configured through the environment.
+/// Returns null on failure.
+///
+/// # Safety
+///
+/// `private_key` must be a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_wallet_from_private_key(
+    private_key: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut AutonomiWallet {
+    let load = || -> Result<AutonomiWallet, FfiError> {
+        let private_key = str_arg(private_key, "private_key")?;
+        let network = get_evm_network_from_env()
+            .map_err(|err| format!("Failed to get the EVM network: {err}"))?;
+        let inner = Wallet::new_from_private_key(network, private_key).map_err(|err| {
+            FfiError::new(
+                ErrorCode::of_evm_wallet_error(&err),
+                format!("Invalid private key: {err}"),
+            )
+        })?;
+        Ok(AutonomiWallet { inner })
+    };
+
+    match load() {
+        Ok(wallet) => Box::into_raw(Box::new(wallet)),
+        Err(err) => {
+            set_error(error, err);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a wallet returned by [`autonomi_wallet_from_private_key`].
+///
+/// # Safety
+///
+/// `wallet` must be null or a wallet not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_wallet_free(wallet: *mut AutonomiWallet) {
+    if !wallet.is_null() {
+        drop(Box::from_raw(wallet));
+    }
+}
+
+/// The address of the wallet. Returns null if `wallet` is null.
+///
+/// # Safety
+///
+/// `wallet` must be null or a live wallet.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_wallet_address(wallet: *const AutonomiWallet) -> *mut c_char {
+    match wallet.as_ref() {
+        Some(wallet) => into_c_string(format!("{:?}", wallet.inner.address())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// The balance of payment tokens of the wallet, in atto tokens. Returns null on failure.
+///
+/// # Safety
+///
+/// `client` and `wallet` must be live handles.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_wallet_balance(
+    client: *const AutonomiClient,
+    wallet: *const AutonomiWallet,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let balance = || -> Result<String, FfiError> {
+        let client = handle_arg(client, "client")?;
+        let wallet = handle_arg(wallet, "wallet")?;
+        let balance = client
+            .runtime
+            .block_on(wallet.inner.balance_of_tokens())
+            .map_err(|err| {
+                FfiError::new(
+                    ErrorCode::PaymentFailed,
+                    format!("Failed to get balance: {err}"),
+                )
+            })?;
+        Ok(balance.to_string())
+    };
+
+    match balance() {
+        Ok(balance) => into_c_string(balance),
+        Err(err) => {
+            set_error(error, err);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Uploads `data_len` bytes of `data` publicly, paid with the wallet. Returns the hex address of the data, or null
+/// on failure.
+///
+/// # Safety
+///
+/// `client` and `wallet` must be live handles and `data` must point to `data_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_data_put_public(
+    client: *const AutonomiClient,
+    wallet: *const AutonomiWallet,
+    data: *const u8,
+    data_len: usize,
+    progress: AutonomiProgressCallback,
+    user_data: *mut c_void,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let put = || -> Result<String, FfiError> {
+        let client = handle_arg(client, "client")?;
+        let wallet = handle_arg(wallet, "wallet")?;
+        if data.is_null() && data_len > 0 {
+            return Err("data is null".to_string().into());
+        }
+        let data = if data_len == 0 {
+            Bytes::new()
+        } else {
+            Bytes::copy_from_slice(std::slice::from_raw_parts(data, data_len))
+        };
+
+        let addr = client
+            .run(
+                progress,
+                user_data,
+                client.inner.data_put_public(data, (&wallet.inner).into()),
+            )
+            .map_err(|err| FfiError::new(err.code(), format!("Failed to put data: {err}")))?;
+        Ok(addr_to_str(addr))
+    };
+
+    match put() {
+        Ok(addr) => into_c_string(addr),
+        Err(err) => {
+            set_error(error, err);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Downloads the public data at the hex address. The bytes returned hold null on failure.
+///
+/// # Safety
+///
+/// `client` must be a live handle and `addr` a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_data_get_public(
+    client: *const AutonomiClient,
+    addr: *const c_char,
+    progress: AutonomiProgressCallback,
+    user_data: *mut c_void,
+    error: *mut *mut c_char,
+) -> AutonomiBytes {
+    let get = || -> Result<Bytes, FfiError> {
+        let client = handle_arg(client, "client")?;
+        let addr =
+            str_to_addr(str_arg(addr, "addr")?).map_err(|err| format!("Invalid address: {err}"))?;
+        client
+            .run(progress, user_data, client.inner.data_get_public(addr))
+            .map_err(|err| FfiError::new(err.code(), format!("Failed to get data: {err}")))
+    };
+
+    match get() {
+        Ok(data) => {
+            let data = data.to_vec().into_boxed_slice();
+            let len = data.len();
+            AutonomiBytes {
+                data: Box::into_raw(data).cast(),
+                len,
+            }
+        }
+        Err(err) => {
+            set_error(error, err);
+            AutonomiBytes {
+                data: std::ptr::null_mut(),
+                len: 0,
+            }
+        }
+    }
+}
+
+/// Uploads the file or directory at `path` publicly, paid with the wallet. Returns the hex address of its archive,
+/// or null on failure.
+///
+/// # Safety
+///
+/// `client` and `wallet` must be live handles and `path` a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_dir_upload_public(
+    client: *const AutonomiClient,
+    wallet: *const AutonomiWallet,
+    path: *const c_char,
+    progress: AutonomiProgressCallback,
+    user_data: *mut c_void,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let upload = || -> Result<String, FfiError> {
+        let client = handle_arg(client, "client")?;
+        let wallet = handle_arg(wallet, "wallet")?;
+        let path = PathBuf::from(str_arg(path, "path")?);
+        let archive_addr = client
+            .run(
+                progress,
+                user_data,
+                client
+                    .inner
+                    .dir_and_archive_upload_public(path, &wallet.inner),
+            )
+            .map_err(|err| FfiError::new(err.code(), format!("Failed to upload: {err}")))?;
+        Ok(addr_to_str(archive_addr))
+    };
+
+    match upload() {
+        Ok(addr) => into_c_string(addr),
+        Err(err) => {
+            set_error(error, err);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Downloads the public archive at the hex address to the `dest` directory. Returns whether it succeeded.
+///
+/// # Safety
+///
+/// `client` must be a live handle, `addr` and `dest` nul terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_dir_download_public(
+    client: *const AutonomiClient,
+    addr: *const c_char,
+    dest: *const c_char,
+    progress: AutonomiProgressCallback,
+    user_data: *mut c_void,
+    error: *mut *mut c_char,
+) -> bool {
+    let download = || -> Result<(), FfiError> {
+        let client = handle_arg(client, "client")?;
+        let archive_addr =
+            str_to_addr(str_arg(addr, "addr")?).map_err(|err| format!("Invalid address: {err}"))?;
+        let dest = PathBuf::from(str_arg(dest, "dest")?);
+        client
+            .run(
+                progress,
+                user_data,
+                client.inner.dir_download_public(archive_addr, dest),
+            )
+            .map_err(|err| FfiError::new(err.code(), format!("Failed to download: {err}")))
+    };
+
+    match download() {
+        Ok(()) => true,
+        Err(err) => {
+            set_error(error, err);
+            false
+        }
+    }
+}
+
+/// Frees a string returned by the functions of this module, including the error messages.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this module and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Frees the bytes returned by [`autonomi_data_get_public`].
+///
+/// # Safety
+///
+/// `bytes` must be returned by this module and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn autonomi_bytes_free(bytes: AutonomiBytes) {
+    if !bytes.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            bytes.data, bytes.len,
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_args_are_reported_as_errors() {
+        let mut error = std::ptr::null_mut();
+        let wallet = unsafe { autonomi_wallet_from_private_key(std::ptr::null(), &mut error) };
+        assert!(wallet.is_null());
+        assert!(!error.is_null());
+
+        let message = unsafe { CStr::from_ptr(error) }.to_str().map(str::to_owned);
+        unsafe { autonomi_string_free(error) };
+        assert_eq!(message.as_deref(), Ok("private_key is null"));
+        assert_eq!(autonomi_last_error_code(), ErrorCode::InvalidInput.as_u16());
+        assert!(!autonomi_error_code_is_retryable(autonomi_last_error_code()));
+        assert!(autonomi_error_code_is_retryable(
+            ErrorCode::Timeout.as_u16()
+        ));
+
+        let mut error = std::ptr::null_mut();
+        let bytes = unsafe {
+            autonomi_data_get_public(
+                std::ptr::null(),
+                c"00".as_ptr(),
+                None,
+                std::ptr::null_mut(),
+                &mut error,
+            )
+        };
+        assert!(bytes.data.is_null());
+        assert!(!error.is_null());
+        unsafe { autonomi_string_free(error) };
+    }
+}
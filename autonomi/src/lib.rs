@@ -53,6 +53,7 @@
 //! - `registers`: Operate on register datatype
 //! - `vault`: Operate on Vault datatype
 //! - `full`: All of above
+//! - `ffi`: C bindings of the client, see the `ffi` module
 //! - `local`: Discover local peers using mDNS. Useful for development.
 //! - `loud`: Print debug information to stdout
 
@@ -66,8 +67,10 @@ pub mod client;
 mod self_encryption;
 
 pub use ant_evm::get_evm_network_from_env;
+pub use ant_evm::mnemonic;
 pub use ant_evm::EvmNetwork as Network;
 pub use ant_evm::EvmWallet as Wallet;
+pub use ant_evm::EvmWatchOnlyWallet as WatchOnlyWallet;
 pub use ant_evm::RewardsAddress;
 
 #[doc(no_inline)] // Place this under 'Re-exports' in the docs.
@@ -78,5 +81,8 @@ pub use libp2p::Multiaddr;
 #[doc(inline)]
 pub use client::{files::archive::Metadata, files::archive::PrivateArchive, Client, ClientConfig};
 
+#[cfg(feature = "ffi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ffi")))]
+pub mod ffi;
 #[cfg(feature = "extension-module")]
 mod python;
@@ -1,18 +1,37 @@
 // TODO: Shall be removed once the python binding warnings resolved
 #![allow(non_local_definitions)]
+// Registers are deprecated in favour of transactions, but still exposed to Python
+#![allow(deprecated)]
 
 use crate::client::{
     data::DataMapChunk,
     files::{archive::PrivateArchiveAccess, archive_public::ArchiveAddr},
     payment::PaymentOption as RustPaymentOption,
+    registers::{Register, RegisterAddress, RegisterSecretKey},
     vault::{UserData, VaultSecretKey},
     Client as RustClient,
 };
 use crate::{Bytes, Network, Wallet as RustWallet};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use std::future::Future;
+use std::path::PathBuf;
 use xor_name::XorName;
 
+/// Runs the future made by `future` to completion on a new runtime, releasing the GIL meanwhile for other Python
+/// threads to run, e.g. the event loop of the caller when wrapped in `asyncio.to_thread`.
+fn block_on<F, Fut>(py: Python<'_>, future: F) -> Fut::Output
+where
+    F: FnOnce() -> Fut + Send,
+    Fut: Future,
+    Fut::Output: Send,
+{
+    py.allow_threads(|| {
+        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
+        rt.block_on(future())
+    })
+}
+
 #[pyclass(name = "Client")]
 pub(crate) struct PyClient {
     inner: RustClient,
@@ -21,8 +40,7 @@ pub(crate) struct PyClient {
 #[pymethods]
 impl PyClient {
     #[staticmethod]
-    fn connect(peers: Vec<String>) -> PyResult<Self> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
+    fn connect(py: Python<'_>, peers: Vec<String>) -> PyResult<Self> {
         let peers = peers
             .into_iter()
             .map(|addr| addr.parse())
@@ -31,112 +49,234 @@ impl PyClient {
                 pyo3::exceptions::PyValueError::new_err(format!("Invalid multiaddr: {e}"))
             })?;
 
-        let client = rt
-            .block_on(RustClient::init_with_peers(peers))
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to connect: {e}"))
-            })?;
+        let client = block_on(py, || RustClient::init_with_peers(peers)).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to connect: {e}"))
+        })?;
 
         Ok(Self { inner: client })
     }
 
-    fn data_put(&self, data: Vec<u8>, payment: &PyPaymentOption) -> PyResult<PyDataMapChunk> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let access = rt
-            .block_on(
-                self.inner
-                    .data_put(Bytes::from(data), payment.inner.clone()),
-            )
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to put private data: {e}"))
-            })?;
+    fn data_put(
+        &self,
+        py: Python<'_>,
+        data: Vec<u8>,
+        payment: &PyPaymentOption,
+    ) -> PyResult<PyDataMapChunk> {
+        let access = block_on(py, || {
+            self.inner
+                .data_put(Bytes::from(data), payment.inner.clone())
+        })
+        .map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to put private data: {e}"))
+        })?;
 
         Ok(PyDataMapChunk { inner: access })
     }
 
-    fn data_get(&self, access: &PyDataMapChunk) -> PyResult<Vec<u8>> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let data = rt
-            .block_on(self.inner.data_get(access.inner.clone()))
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to get private data: {e}"))
-            })?;
+    fn data_get(&self, py: Python<'_>, access: &PyDataMapChunk) -> PyResult<Vec<u8>> {
+        let data = block_on(py, || self.inner.data_get(access.inner.clone())).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to get private data: {e}"))
+        })?;
         Ok(data.to_vec())
     }
 
-    fn data_put_public(&self, data: Vec<u8>, payment: &PyPaymentOption) -> PyResult<String> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let addr = rt
-            .block_on(
-                self.inner
-                    .data_put_public(bytes::Bytes::from(data), payment.inner.clone()),
-            )
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to put data: {e}"))
-            })?;
+    fn data_put_public(
+        &self,
+        py: Python<'_>,
+        data: Vec<u8>,
+        payment: &PyPaymentOption,
+    ) -> PyResult<String> {
+        let addr = block_on(py, || {
+            self.inner
+                .data_put_public(bytes::Bytes::from(data), payment.inner.clone())
+        })
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Failed to put data: {e}")))?;
 
         Ok(crate::client::address::addr_to_str(addr))
     }
 
-    fn data_get_public(&self, addr: &str) -> PyResult<Vec<u8>> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
+    fn data_get_public(&self, py: Python<'_>, addr: &str) -> PyResult<Vec<u8>> {
         let addr = crate::client::address::str_to_addr(addr).map_err(|e| {
             pyo3::exceptions::PyValueError::new_err(format!("Invalid address: {e}"))
         })?;
 
-        let data = rt.block_on(self.inner.data_get_public(addr)).map_err(|e| {
+        let data = block_on(py, || self.inner.data_get_public(addr)).map_err(|e| {
             pyo3::exceptions::PyValueError::new_err(format!("Failed to get data: {e}"))
         })?;
 
         Ok(data.to_vec())
     }
 
-    fn vault_cost(&self, key: &PyVaultSecretKey) -> PyResult<String> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let cost = rt
-            .block_on(self.inner.vault_cost(&key.inner))
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to get vault cost: {e}"))
-            })?;
+    /// Upload a file or a directory publicly, returning the address of its archive.
+    fn dir_upload_public(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        wallet: &PyWallet,
+    ) -> PyResult<String> {
+        let archive_addr = block_on(py, || {
+            self.inner
+                .dir_and_archive_upload_public(path, &wallet.inner)
+        })
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Failed to upload: {e}")))?;
+
+        Ok(crate::client::address::addr_to_str(archive_addr))
+    }
+
+    /// Download the files of a public archive to the destination directory.
+    fn dir_download_public(&self, py: Python<'_>, addr: &str, dest: PathBuf) -> PyResult<()> {
+        let archive_addr = crate::client::address::str_to_addr(addr).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid address: {e}"))
+        })?;
+
+        block_on(py, || self.inner.dir_download_public(archive_addr, dest)).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to download: {e}"))
+        })
+    }
+
+    /// Upload a file or a directory privately, returning the access to its archive.
+    fn dir_upload(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        wallet: &PyWallet,
+    ) -> PyResult<PyDataMapChunk> {
+        let archive_access = block_on(py, || {
+            self.inner.dir_and_archive_upload(path, &wallet.inner)
+        })
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Failed to upload: {e}")))?;
+
+        Ok(PyDataMapChunk {
+            inner: archive_access,
+        })
+    }
+
+    /// Download the files of a private archive to the destination directory.
+    fn dir_download(&self, py: Python<'_>, access: &PyDataMapChunk, dest: PathBuf) -> PyResult<()> {
+        block_on(py, || self.inner.dir_download(access.inner.clone(), dest)).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to download: {e}"))
+        })
+    }
+
+    #[staticmethod]
+    fn register_generate_key() -> PyRegisterSecretKey {
+        PyRegisterSecretKey {
+            inner: RustClient::register_generate_key(),
+        }
+    }
+
+    #[pyo3(signature = (value, name, key, wallet))]
+    fn register_create(
+        &self,
+        py: Python<'_>,
+        value: Option<Vec<u8>>,
+        name: &str,
+        key: &PyRegisterSecretKey,
+        wallet: &PyWallet,
+    ) -> PyResult<PyRegister> {
+        let register = block_on(py, || {
+            self.inner.register_create(
+                value.map(Bytes::from),
+                name,
+                key.inner.clone(),
+                &wallet.inner,
+            )
+        })
+        .map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to create register: {e}"))
+        })?;
+
+        Ok(PyRegister { inner: register })
+    }
+
+    fn register_get(&self, py: Python<'_>, addr: &str) -> PyResult<PyRegister> {
+        let addr = RegisterAddress::from_hex(addr).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid register address: {e}"))
+        })?;
+
+        let register = block_on(py, || self.inner.register_get(addr)).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to get register: {e}"))
+        })?;
+
+        Ok(PyRegister { inner: register })
+    }
+
+    /// The values of the register, with the ones too big for a register entry fetched from where they're stored.
+    fn register_values(&self, py: Python<'_>, register: &PyRegister) -> PyResult<Vec<Vec<u8>>> {
+        let values = block_on(py, || self.inner.register_values(&register.inner)).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to get register values: {e}"))
+        })?;
+
+        Ok(values.into_iter().map(|value| value.to_vec()).collect())
+    }
+
+    fn register_update(
+        &self,
+        py: Python<'_>,
+        register: &PyRegister,
+        new_value: Vec<u8>,
+        key: &PyRegisterSecretKey,
+    ) -> PyResult<()> {
+        block_on(py, || {
+            self.inner.register_update(
+                register.inner.clone(),
+                Bytes::from(new_value),
+                key.inner.clone(),
+            )
+        })
+        .map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to update register: {e}"))
+        })
+    }
+
+    fn vault_cost(&self, py: Python<'_>, key: &PyVaultSecretKey) -> PyResult<String> {
+        let cost = block_on(py, || self.inner.vault_cost(&key.inner)).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to get vault cost: {e}"))
+        })?;
         Ok(cost.to_string())
     }
 
     fn write_bytes_to_vault(
         &self,
+        py: Python<'_>,
         data: Vec<u8>,
         payment: &PyPaymentOption,
         key: &PyVaultSecretKey,
         content_type: u64,
     ) -> PyResult<String> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let cost = rt
-            .block_on(self.inner.write_bytes_to_vault(
+        let cost = block_on(py, || {
+            self.inner.write_bytes_to_vault(
                 bytes::Bytes::from(data),
                 payment.inner.clone(),
                 &key.inner,
                 content_type,
-            ))
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to write to vault: {e}"))
-            })?;
+            )
+        })
+        .map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to write to vault: {e}"))
+        })?;
         Ok(cost.to_string())
     }
 
-    fn fetch_and_decrypt_vault(&self, key: &PyVaultSecretKey) -> PyResult<(Vec<u8>, u64)> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let (data, content_type) = rt
-            .block_on(self.inner.fetch_and_decrypt_vault(&key.inner))
+    fn fetch_and_decrypt_vault(
+        &self,
+        py: Python<'_>,
+        key: &PyVaultSecretKey,
+    ) -> PyResult<(Vec<u8>, u64)> {
+        let (data, content_type) = block_on(py, || self.inner.fetch_and_decrypt_vault(&key.inner))
             .map_err(|e| {
                 pyo3::exceptions::PyValueError::new_err(format!("Failed to fetch vault: {e}"))
             })?;
         Ok((data.to_vec(), content_type))
     }
 
-    fn get_user_data_from_vault(&self, key: &PyVaultSecretKey) -> PyResult<PyUserData> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let user_data = rt
-            .block_on(self.inner.get_user_data_from_vault(&key.inner))
-            .map_err(|e| {
+    fn get_user_data_from_vault(
+        &self,
+        py: Python<'_>,
+        key: &PyVaultSecretKey,
+    ) -> PyResult<PyUserData> {
+        let user_data =
+            block_on(py, || self.inner.get_user_data_from_vault(&key.inner)).map_err(|e| {
                 pyo3::exceptions::PyValueError::new_err(format!("Failed to get user data: {e}"))
             })?;
         Ok(PyUserData { inner: user_data })
@@ -144,20 +284,21 @@ impl PyClient {
 
     fn put_user_data_to_vault(
         &self,
+        py: Python<'_>,
         key: &PyVaultSecretKey,
         payment: &PyPaymentOption,
         user_data: &PyUserData,
     ) -> PyResult<String> {
-        let rt = tokio::runtime::Runtime::new().expect("Could not start tokio runtime");
-        let cost = rt
-            .block_on(self.inner.put_user_data_to_vault(
+        let cost = block_on(py, || {
+            self.inner.put_user_data_to_vault(
                 &key.inner,
                 payment.inner.clone(),
                 user_data.inner.clone(),
-            ))
-            .map_err(|e| {
-                pyo3::exceptions::PyValueError::new_err(format!("Failed to put user data: {e}"))
-            })?;
+            )
+        })
+        .map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Failed to put user data: {e}"))
+        })?;
         Ok(cost.to_string())
     }
 }
@@ -250,6 +391,53 @@ impl PyVaultSecretKey {
     }
 }
 
+#[pyclass(name = "RegisterSecretKey")]
+pub(crate) struct PyRegisterSecretKey {
+    inner: RegisterSecretKey,
+}
+
+#[pymethods]
+impl PyRegisterSecretKey {
+    #[new]
+    fn new() -> Self {
+        Self {
+            inner: RegisterSecretKey::random(),
+        }
+    }
+
+    #[staticmethod]
+    fn from_hex(hex_str: &str) -> PyResult<Self> {
+        RegisterSecretKey::from_hex(hex_str)
+            .map(|key| Self { inner: key })
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(format!("Invalid hex key: {e}")))
+    }
+
+    fn to_hex(&self) -> String {
+        self.inner.to_hex()
+    }
+}
+
+#[pyclass(name = "Register")]
+pub(crate) struct PyRegister {
+    inner: Register,
+}
+
+#[pymethods]
+impl PyRegister {
+    fn address(&self) -> String {
+        self.inner.address().to_hex()
+    }
+
+    /// The latest entries of the register, see `Client.register_values` to resolve the ones stored as data.
+    fn values(&self) -> Vec<Vec<u8>> {
+        self.inner
+            .values()
+            .into_iter()
+            .map(|value| value.to_vec())
+            .collect()
+    }
+}
+
 #[pyclass(name = "UserData")]
 pub(crate) struct PyUserData {
     inner: UserData,
@@ -343,6 +531,8 @@ fn autonomi_client_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyWallet>()?;
     m.add_class::<PyPaymentOption>()?;
     m.add_class::<PyVaultSecretKey>()?;
+    m.add_class::<PyRegisterSecretKey>()?;
+    m.add_class::<PyRegister>()?;
     m.add_class::<PyUserData>()?;
     m.add_class::<PyDataMapChunk>()?;
     m.add_function(wrap_pyfunction!(encrypt, m)?)?;
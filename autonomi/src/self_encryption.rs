@@ -6,11 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use ant_protocol::storage::Chunk;
+use ant_protocol::{storage::Chunk, ErrorCode};
 use bytes::{BufMut, Bytes, BytesMut};
 use rayon::prelude::*;
-use self_encryption::{DataMap, MAX_CHUNK_SIZE};
+use self_encryption::{DataMap, MAX_CHUNK_SIZE, MIN_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES};
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 use tracing::debug;
 
 #[derive(Debug, thiserror::Error)]
@@ -19,6 +20,113 @@ pub enum Error {
     Encoding(#[from] rmp_serde::encode::Error),
     #[error(transparent)]
     SelfEncryption(#[from] self_encryption::Error),
+    #[error("Invalid chunking parameters: {0:?}")]
+    InvalidChunkingParams(ChunkingParams),
+    #[error("Failed to compress or decompress data: {0}")]
+    Compression(std::io::Error),
+}
+
+impl Error {
+    pub(crate) fn code(&self) -> ErrorCode {
+        match self {
+            Error::Encoding(_) => ErrorCode::Serialization,
+            Error::SelfEncryption(_) | Error::Compression(_) => ErrorCode::Encryption,
+            Error::InvalidChunkingParams(_) => ErrorCode::InvalidInput,
+        }
+    }
+}
+
+/// Self-encryption splits data into at least this many chunks.
+const MIN_CHUNK_COUNT: usize = 3;
+
+/// Algorithm to compress data with before self-encrypting it, see [`crate::ClientConfig::compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    /// Zstandard, at its default level.
+    Zstd,
+}
+
+impl Compression {
+    fn compress(self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::Zstd => zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(Error::Compression),
+        }
+    }
+
+    /// Decompress the whole of the compressed data.
+    pub(crate) fn decompress(self, data: &[u8]) -> Result<Bytes, Error> {
+        match self {
+            Compression::Zstd => zstd::stream::decode_all(data)
+                .map(Bytes::from)
+                .map_err(Error::Compression),
+        }
+    }
+
+    /// Decompress compressed data piece by piece, e.g. as its chunks are streamed in.
+    pub(crate) fn decompressor(self) -> Result<Decompressor, Error> {
+        match self {
+            Compression::Zstd => zstd::stream::write::Decoder::new(Vec::new())
+                .map(Decompressor)
+                .map_err(Error::Compression),
+        }
+    }
+}
+
+/// Decompresses a stream of compressed data, see [`Compression::decompressor`].
+pub(crate) struct Decompressor(zstd::stream::write::Decoder<'static, Vec<u8>>);
+
+impl Decompressor {
+    /// Feed the next piece of compressed data, returning the data it decompresses to.
+    pub(crate) fn decompress(&mut self, data: &[u8]) -> Result<Bytes, Error> {
+        self.0.write_all(data).map_err(Error::Compression)?;
+        self.0.flush().map_err(Error::Compression)?;
+        Ok(Bytes::from(std::mem::take(self.0.get_mut())))
+    }
+}
+
+/// How data is split into chunks before being self-encrypted, see [`crate::ClientConfig::chunking`].
+///
+/// Data chunked with custom parameters is split into segments that are self-encrypted one by one, with the data maps
+/// of all segments and the parameters recorded in the data map chunk. Downloads decode either kind of data map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkingParams {
+    /// Max size of a chunk, before encryption.
+    ///
+    /// Defaults to, and can't exceed, the 1 MiB chunks that nodes accept.
+    pub max_chunk_size: usize,
+    /// Min number of chunks to split data into, given enough bytes to fill them.
+    ///
+    /// Defaults to, and can't be below, the 3 chunks self-encryption needs.
+    pub min_chunk_count: usize,
+}
+
+impl Default for ChunkingParams {
+    fn default() -> Self {
+        Self {
+            max_chunk_size: *MAX_CHUNK_SIZE,
+            min_chunk_count: MIN_CHUNK_COUNT,
+        }
+    }
+}
+
+impl ChunkingParams {
+    fn validate(&self) -> Result<(), Error> {
+        if !(MIN_CHUNK_SIZE..=*MAX_CHUNK_SIZE).contains(&self.max_chunk_size)
+            || self.min_chunk_count < MIN_CHUNK_COUNT
+        {
+            return Err(Error::InvalidChunkingParams(*self));
+        }
+        Ok(())
+    }
+
+    /// The size of the chunks to split `data_len` bytes into.
+    fn chunk_size(&self, data_len: usize) -> usize {
+        data_len
+            .div_ceil(self.min_chunk_count)
+            .min(self.max_chunk_size)
+            .max(MIN_CHUNK_SIZE)
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -29,31 +137,211 @@ pub(crate) enum DataMapLevel {
     // resulting from chunking up a previous level data map.
     // This happens when that previous level data map was too big to fit in a chunk itself.
     Additional(DataMap),
+    // Holds the data maps to the consecutive segments of the source data, chunked with the given parameters.
+    Segmented(ChunkingParams, Vec<DataMap>),
+    // Holds the level of the data maps to the source data, which was compressed before being self-encrypted.
+    Compressed(Compression, Box<DataMapLevel>),
 }
 
-pub(crate) fn encrypt(data: Bytes) -> Result<(Chunk, Vec<Chunk>), Error> {
-    let (data_map, chunks) = self_encryption::encrypt(data)?;
-    let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+/// The data maps to the source data, once all levels of a data map chunk have been resolved.
+#[derive(Debug)]
+pub(crate) struct SourceDataMaps {
+    /// The data maps of consecutive segments of the source data, a single one unless chunked with custom parameters.
+    pub(crate) data_maps: Vec<DataMap>,
+    /// How the source data was compressed before being self-encrypted, if at all.
+    pub(crate) compression: Option<Compression>,
+}
+
+/// Self-encrypt the data into chunks as per the parameters, after compressing it if asked to, returning the data map
+/// chunk and the chunks. Data that doesn't get any smaller is stored uncompressed.
+pub(crate) fn encrypt(
+    data: Bytes,
+    params: &ChunkingParams,
+    compression: Option<Compression>,
+) -> Result<(Chunk, Vec<Chunk>), Error> {
+    params.validate()?;
 
-    // Transform `EncryptedChunk` into `Chunk`
-    let chunks: Vec<Chunk> = chunks
-        .into_par_iter()
-        .map(|c| Chunk::new(c.content.clone()))
-        .chain(additional_chunks)
-        .collect();
+    let (data, compression) = match compression {
+        Some(compression) => {
+            let compressed = compression.compress(&data)?;
+            debug!(
+                "Compressed {} bytes to {} with {compression:?}",
+                data.len(),
+                compressed.len()
+            );
+            if compressed.len() < data.len() {
+                (Bytes::from(compressed), Some(compression))
+            } else {
+                (data, None)
+            }
+        }
+        None => (data, None),
+    };
+
+    let (data_map, mut chunks) = encrypt_to_level(data, params)?;
+    let data_map = match compression {
+        Some(compression) => DataMapLevel::Compressed(compression, Box::new(data_map)),
+        None => data_map,
+    };
+    let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+    chunks.extend(additional_chunks);
 
     Ok((data_map_chunk, chunks))
 }
 
+/// Self-encrypt the data into chunks as per the parameters, returning the level of the data map(s) to them.
+fn encrypt_to_level(
+    data: Bytes,
+    params: &ChunkingParams,
+) -> Result<(DataMapLevel, Vec<Chunk>), Error> {
+    // Self-encryption splits data of up to 3 max size chunks into 3 chunks, so we split the data into segments of
+    // that many chunks and encrypt them one by one. When a single segment does, the data is encrypted as usual.
+    let chunk_size = params.chunk_size(data.len());
+    let segment_size = MIN_CHUNK_COUNT * chunk_size;
+    if chunk_size >= *MAX_CHUNK_SIZE || data.len() <= segment_size {
+        let (data_map, chunks) = self_encryption::encrypt(data)?;
+        let chunks = chunks
+            .into_par_iter()
+            .map(|c| Chunk::new(c.content.clone()))
+            .collect();
+        return Ok((DataMapLevel::First(data_map), chunks));
+    }
+
+    let mut data_maps = vec![];
+    let mut chunks = vec![];
+    for segment in segments(data, segment_size) {
+        let (data_map, segment_chunks) = self_encryption::encrypt(segment)?;
+        data_maps.push(data_map);
+        chunks.extend(
+            segment_chunks
+                .into_iter()
+                .map(|c| Chunk::new(c.content.clone())),
+        );
+    }
+    debug!(
+        "Encrypted {} segments of {segment_size} bytes into {} chunks",
+        data_maps.len(),
+        chunks.len()
+    );
+
+    Ok((DataMapLevel::Segmented(*params, data_maps), chunks))
+}
+
+/// Split the data into segments of `segment_size`, merging a tail too small to be self-encrypted into the last one.
+fn segments(mut data: Bytes, segment_size: usize) -> Vec<Bytes> {
+    let mut segments = vec![];
+    while data.len() >= segment_size + MIN_ENCRYPTABLE_BYTES {
+        segments.push(data.split_to(segment_size));
+    }
+    segments.push(data);
+    segments
+}
+
+/// Self-encrypts data as it's fed in, a segment of 3 chunks of the max chunk size at a time, so that data of unknown
+/// length can be uploaded without holding it all in memory. See [`ChunkingParams`] on segments.
+///
+/// As the length isn't known up front, [`ChunkingParams::min_chunk_count`] doesn't apply, and compressed data that
+/// doesn't get any smaller is stored compressed regardless.
+pub(crate) struct StreamEncryptor {
+    params: ChunkingParams,
+    compression: Option<(Compression, zstd::stream::write::Encoder<'static, Vec<u8>>)>,
+    /// Data fed in but not yet encrypted.
+    pending: BytesMut,
+    data_maps: Vec<DataMap>,
+}
+
+impl StreamEncryptor {
+    pub(crate) fn new(
+        params: ChunkingParams,
+        compression: Option<Compression>,
+    ) -> Result<Self, Error> {
+        params.validate()?;
+        let compression = compression
+            .map(|compression| match compression {
+                Compression::Zstd => {
+                    zstd::stream::write::Encoder::new(Vec::new(), zstd::DEFAULT_COMPRESSION_LEVEL)
+                        .map(|encoder| (compression, encoder))
+                }
+            })
+            .transpose()
+            .map_err(Error::Compression)?;
+        Ok(Self {
+            params,
+            compression,
+            pending: BytesMut::new(),
+            data_maps: vec![],
+        })
+    }
+
+    /// Feed in the next bytes of the data, returning the chunks of the segments it completes.
+    pub(crate) fn push(&mut self, data: &[u8]) -> Result<Vec<Chunk>, Error> {
+        match &mut self.compression {
+            Some((_, encoder)) => {
+                encoder.write_all(data).map_err(Error::Compression)?;
+                self.pending.extend_from_slice(encoder.get_ref());
+                encoder.get_mut().clear();
+            }
+            None => self.pending.extend_from_slice(data),
+        }
+
+        // Hold back a tail too small to be self-encrypted on its own, in case the data ends after this segment.
+        let segment_size = MIN_CHUNK_COUNT * self.params.max_chunk_size;
+        let mut chunks = vec![];
+        while self.pending.len() >= segment_size + MIN_ENCRYPTABLE_BYTES {
+            let segment = self.pending.split_to(segment_size).freeze();
+            chunks.extend(self.encrypt_segment(segment)?);
+        }
+        Ok(chunks)
+    }
+
+    /// Finish the data, returning the data map chunk and the chunks of the last segment and of the data map.
+    pub(crate) fn finish(mut self) -> Result<(Chunk, Vec<Chunk>), Error> {
+        let compression = match self.compression.take() {
+            Some((compression, encoder)) => {
+                let tail = encoder.finish().map_err(Error::Compression)?;
+                self.pending.extend_from_slice(&tail);
+                Some(compression)
+            }
+            None => None,
+        };
+
+        let segment = std::mem::take(&mut self.pending).freeze();
+        let mut chunks = self.encrypt_segment(segment)?;
+
+        // A single segment is encrypted just like the whole data would be.
+        let data_map = match <[DataMap; 1]>::try_from(self.data_maps) {
+            Ok([data_map]) => DataMapLevel::First(data_map),
+            Err(data_maps) => DataMapLevel::Segmented(self.params, data_maps),
+        };
+        let data_map = match compression {
+            Some(compression) => DataMapLevel::Compressed(compression, Box::new(data_map)),
+            None => data_map,
+        };
+        let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+        chunks.extend(additional_chunks);
+
+        Ok((data_map_chunk, chunks))
+    }
+
+    fn encrypt_segment(&mut self, segment: Bytes) -> Result<Vec<Chunk>, Error> {
+        let (data_map, chunks) = self_encryption::encrypt(segment)?;
+        self.data_maps.push(data_map);
+        Ok(chunks
+            .into_iter()
+            .map(|c| Chunk::new(c.content.clone()))
+            .collect())
+    }
+}
+
 // Produces a chunk out of the first `DataMap`, which is validated for its size.
 // If the chunk is too big, it is self-encrypted and the resulting (additional level) `DataMap` is put into a chunk.
 // The above step is repeated as many times as required until the chunk size is valid.
 // In other words: If the chunk content is too big, it will be
 // self encrypted into additional chunks, and now we have a new `DataMap`
 // which points to all of those additional chunks.. and so on.
-fn pack_data_map(data_map: DataMap) -> Result<(Chunk, Vec<Chunk>), Error> {
+fn pack_data_map(data_map: DataMapLevel) -> Result<(Chunk, Vec<Chunk>), Error> {
     let mut chunks = vec![];
-    let mut chunk_content = wrap_data_map(&DataMapLevel::First(data_map))?;
+    let mut chunk_content = wrap_data_map(&data_map)?;
 
     let (data_map_chunk, additional_chunks) = loop {
         debug!("Max chunk size: {}", *MAX_CHUNK_SIZE);
@@ -92,3 +380,135 @@ fn wrap_data_map(data_map: &DataMapLevel) -> Result<Bytes, rmp_serde::encode::Er
         .inspect_err(|err| error!("Failed to serialize data map: {err:?}"))?;
     Ok(bytes.into_inner().freeze())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, RngCore};
+    use self_encryption::{decrypt_full_set, EncryptedChunk};
+
+    #[test]
+    fn data_is_segmented_into_chunks_of_the_max_size() -> Result<(), Error> {
+        let mut data = vec![0u8; 100_000];
+        thread_rng().fill_bytes(&mut data);
+        let data = Bytes::from(data);
+        let params = ChunkingParams {
+            max_chunk_size: 4096,
+            ..Default::default()
+        };
+
+        let (data_map_chunk, chunks) = encrypt(data.clone(), &params, None)?;
+        let Ok(DataMapLevel::Segmented(recorded, data_maps)) =
+            rmp_serde::from_slice(data_map_chunk.value())
+        else {
+            panic!("Expected a segmented data map");
+        };
+        assert_eq!(recorded, params);
+
+        let mut decrypted = BytesMut::new();
+        for data_map in &data_maps {
+            let infos = data_map.infos();
+            assert!(infos.iter().all(|info| info.src_size <= 4096));
+            let encrypted_chunks: Vec<_> = infos
+                .iter()
+                .map(|info| {
+                    let chunk = chunks
+                        .iter()
+                        .find(|chunk| *chunk.name() == info.dst_hash)
+                        .expect("Chunk of the data map to have been produced");
+                    EncryptedChunk {
+                        index: info.index,
+                        content: chunk.value().clone(),
+                    }
+                })
+                .collect();
+            decrypted.extend_from_slice(&decrypt_full_set(data_map, &encrypted_chunks)?);
+        }
+        assert_eq!(decrypted.freeze(), data);
+
+        // Parameters the chunks of the network can't hold are rejected.
+        let too_large = ChunkingParams {
+            max_chunk_size: *MAX_CHUNK_SIZE + 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            encrypt(data, &too_large, None),
+            Err(Error::InvalidChunkingParams(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn compressible_data_is_recorded_as_compressed() -> Result<(), Error> {
+        let text = "All work and no play makes Jack a dull boy. ".repeat(100_000);
+        let data = Bytes::from(text);
+
+        let (data_map_chunk, chunks) =
+            encrypt(data.clone(), &Default::default(), Some(Compression::Zstd))?;
+        let (_, uncompressed_chunks) = encrypt(data.clone(), &Default::default(), None)?;
+        assert!(chunks.len() < uncompressed_chunks.len());
+
+        let Ok(DataMapLevel::Compressed(Compression::Zstd, _)) =
+            rmp_serde::from_slice(data_map_chunk.value())
+        else {
+            panic!("Expected a compressed data map");
+        };
+
+        // Decompressing piece by piece, as when streaming, gets the data back.
+        let compressed = Compression::Zstd.compress(&data)?;
+        let mut decompressor = Compression::Zstd.decompressor()?;
+        let mut decompressed = BytesMut::new();
+        for piece in compressed.chunks(1000) {
+            decompressed.extend_from_slice(&decompressor.decompress(piece)?);
+        }
+        assert_eq!(decompressed.freeze(), data);
+
+        // Data that doesn't compress is stored as is.
+        let mut random = vec![0u8; 10_000];
+        thread_rng().fill_bytes(&mut random);
+        let (data_map_chunk, _) = encrypt(
+            Bytes::from(random),
+            &Default::default(),
+            Some(Compression::Zstd),
+        )?;
+        assert!(matches!(
+            rmp_serde::from_slice(data_map_chunk.value()),
+            Ok(DataMapLevel::First(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn streamed_data_is_encrypted_segment_by_segment() -> Result<(), Error> {
+        let mut data = vec![0u8; 100_000];
+        thread_rng().fill_bytes(&mut data);
+        let params = ChunkingParams {
+            max_chunk_size: 4096,
+            ..Default::default()
+        };
+
+        let mut encryptor = StreamEncryptor::new(params, None)?;
+        let mut chunks = vec![];
+        for piece in data.chunks(5000) {
+            chunks.extend(encryptor.push(piece)?);
+        }
+        let (data_map_chunk, last_chunks) = encryptor.finish()?;
+        chunks.extend(last_chunks);
+
+        // 8 full segments of 3 chunks and the tail.
+        assert_eq!(chunks.len(), 27);
+        let Ok(DataMapLevel::Segmented(_, data_maps)) =
+            rmp_serde::from_slice(data_map_chunk.value())
+        else {
+            panic!("Expected a segmented data map");
+        };
+        assert_eq!(data_maps.len(), 9);
+
+        // Data fitting in a single segment is encrypted like it is in one go.
+        let small = Bytes::from(data[..10_000].to_vec());
+        let mut encryptor = StreamEncryptor::new(params, None)?;
+        assert!(encryptor.push(&small)?.is_empty());
+        assert_eq!(encryptor.finish()?, encrypt(small, &params, None)?);
+        Ok(())
+    }
+}
@@ -13,6 +13,7 @@ use ant_logging::LogBuilder;
 use autonomi::Client;
 use bytes::Bytes;
 use eyre::Result;
+use futures::StreamExt;
 use rand::Rng;
 use std::time::Duration;
 use test_utils::evm::get_funded_wallet;
@@ -61,5 +62,56 @@ async fn register() -> Result<()> {
     let register = client.register_get(*register.address()).await.unwrap();
     assert_eq!(register.values(), vec![Bytes::from(vec![5, 6, 7, 8])]);
 
+    // The initial value is still in the history, below the updated one
+    let history = register.history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].value, Bytes::from(vec![1, 2, 3, 4]));
+    assert_eq!(history[1].children, [history[0].hash].into());
+    assert_eq!(register.tips(), [history[1].hash].into());
+    assert_eq!(register.entry(history[0].hash).as_ref(), Some(&history[0]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn register_watch() -> Result<()> {
+    let _log_appender_guard = LogBuilder::init_single_threaded_tokio_test("register_watch", false);
+
+    let client = Client::init_local().await?;
+    let wallet = get_funded_wallet();
+    let key = bls::SecretKey::random();
+
+    let rand_name: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect();
+    let register = client
+        .register_create(
+            Some(vec![1, 2, 3, 4].into()),
+            &rand_name,
+            key.clone(),
+            &wallet,
+        )
+        .await?;
+
+    sleep(Duration::from_secs(10)).await;
+
+    // A separate client watches the register for changes
+    let watcher = Client::init_local().await?;
+    let entries = watcher.watch_register(*register.address()).await?;
+    futures::pin_mut!(entries);
+
+    let register = client.register_get(*register.address()).await?;
+    client
+        .register_update(register.clone(), vec![5, 6, 7, 8].into(), key)
+        .await?;
+
+    // The nodes holding the register notify the watcher of the new entry
+    let entry = tokio::time::timeout(Duration::from_secs(30), entries.next()).await?;
+    assert_eq!(entry, Some(Bytes::from(vec![5, 6, 7, 8])));
+
+    watcher.unwatch_register(*register.address());
+
     Ok(())
 }
@@ -0,0 +1,50 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ant_logging::LogBuilder;
+use autonomi::Client;
+use bytes::Bytes;
+use eyre::Result;
+use std::time::Duration;
+use test_utils::evm::get_funded_wallet;
+use tokio::time::sleep;
+
+#[tokio::test]
+async fn scratchpad_put_and_update() -> Result<()> {
+    let _log_appender_guard = LogBuilder::init_single_threaded_tokio_test("scratchpad", false);
+
+    let client = Client::init_local().await?;
+    let wallet = get_funded_wallet();
+    let key = bls::SecretKey::random();
+
+    let scratchpad = client
+        .scratchpad_create(&key, 42, Bytes::from("first"), &wallet)
+        .await?;
+    let address = *scratchpad.address();
+
+    sleep(Duration::from_secs(5)).await;
+
+    let fetched = client.scratchpad_get(address).await?;
+    assert_eq!(fetched.count(), 1);
+    assert_eq!(fetched.data_encoding(), 42);
+    assert_eq!(fetched.decrypt_data(&key)?, Bytes::from("first"));
+
+    // The latest write wins
+    let updated = client
+        .scratchpad_update(&key, Bytes::from("second"))
+        .await?;
+    assert_eq!(updated.count(), 2);
+
+    sleep(Duration::from_secs(5)).await;
+
+    let fetched = client.scratchpad_get(address).await?;
+    assert_eq!(fetched.count(), 2);
+    assert_eq!(fetched.decrypt_data(&key)?, Bytes::from("second"));
+
+    Ok(())
+}
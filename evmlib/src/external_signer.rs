@@ -6,13 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::common::{Address, Amount, Calldata, QuoteHash, QuotePayment, U256};
+use crate::common::{Address, Amount, Calldata, QuoteHash, QuotePayment, TxHash, U256};
 use crate::contract::network_token::{self, NetworkToken};
 use crate::contract::payment_vault::MAX_TRANSFERS_PER_TRANSACTION;
 use crate::utils::http_provider;
 use crate::Network;
+use alloy::network::TransactionBuilder;
+use alloy::providers::{PendingTransactionError, Provider};
+use alloy::rpc::types::TransactionRequest;
+use alloy::transports::{RpcError, TransportErrorKind};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -20,6 +25,90 @@ pub enum Error {
     NetworkTokenContract(#[from] network_token::Error),
     #[error("Data payments contract error: {0}")]
     DataPaymentsContract(#[from] crate::contract::payment_vault::error::Error),
+    #[error(transparent)]
+    Rpc(#[from] RpcError<TransportErrorKind>),
+    #[error("Pending transaction error: {0}")]
+    PendingTransaction(#[from] PendingTransactionError),
+    #[error("Failed to sign transaction: {0}")]
+    Signing(String),
+}
+
+/// Signs transactions for an address whose secret key may live outside of this process, e.g. on a hardware
+/// device or with a remote signing service.
+pub trait Signer {
+    /// The address the transactions are sent from.
+    fn address(&self) -> Address;
+
+    /// Sign a transaction prepared with [`prepare_transaction`], returning it EIP-2718 encoded.
+    fn sign_transaction(
+        &self,
+        tx: TransactionRequest,
+    ) -> impl Future<Output = Result<Vec<u8>, Error>> + Send;
+}
+
+/// Prepare a transaction from the calldata (input, to), filling in the nonce, gas limit, fees and chain id of the
+/// sender.
+///
+/// The transaction can be serialized, signed elsewhere and broadcast with [`broadcast_signed_transaction`].
+pub async fn prepare_transaction(
+    network: &Network,
+    from: Address,
+    (calldata, to): (Calldata, Address),
+) -> Result<TransactionRequest, Error> {
+    let provider = http_provider(network.rpc_url().clone());
+    let mut tx = TransactionRequest::default()
+        .with_from(from)
+        .with_to(to)
+        .with_input(calldata);
+
+    let nonce = provider.get_transaction_count(from).await?;
+    let chain_id = provider.get_chain_id().await?;
+    let fees = provider.estimate_eip1559_fees(None).await?;
+    tx.set_nonce(nonce);
+    tx.set_chain_id(chain_id);
+    tx.set_max_fee_per_gas(fees.max_fee_per_gas);
+    tx.set_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+
+    let gas_limit = provider.estimate_gas(&tx).await?;
+    tx.set_gas_limit(gas_limit);
+
+    Ok(tx)
+}
+
+/// Broadcast an EIP-2718 encoded signed transaction and wait for it to be confirmed.
+pub async fn broadcast_signed_transaction(
+    network: &Network,
+    signed_tx: &[u8],
+) -> Result<TxHash, Error> {
+    let provider = http_provider(network.rpc_url().clone());
+    let tx_hash = provider
+        .send_raw_transaction(signed_tx)
+        .await?
+        .watch()
+        .await?;
+    Ok(tx_hash)
+}
+
+/// Prepare, sign and broadcast the transaction of the calldata (input, to) with the signer.
+pub async fn send_with_signer<S: Signer>(
+    signer: &S,
+    network: &Network,
+    calldata: (Calldata, Address),
+) -> Result<TxHash, Error> {
+    let tx = prepare_transaction(network, signer.address(), calldata).await?;
+    let signed_tx = signer.sign_transaction(tx).await?;
+    broadcast_signed_transaction(network, &signed_tx).await
+}
+
+/// Transfer payment tokens from the signer's address to the receiver.
+pub async fn transfer_tokens_with_signer<S: Signer>(
+    signer: &S,
+    network: &Network,
+    receiver: Address,
+    amount: U256,
+) -> Result<TxHash, Error> {
+    let calldata = transfer_tokens_calldata(network, receiver, amount);
+    send_with_signer(signer, network, calldata).await
 }
 
 /// Approve an address / smart contract to spend this wallet's payment tokens.
@@ -22,6 +22,7 @@ pub mod contract;
 pub mod cryptography;
 #[cfg(feature = "external-signer")]
 pub mod external_signer;
+pub mod mnemonic;
 pub mod quoting_metrics;
 pub mod testnet;
 pub mod utils;
@@ -0,0 +1,169 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use alloy::hex::ToHexExt;
+use alloy::signers::k256::ecdsa::SigningKey;
+use alloy::signers::k256::elliptic_curve::PrimeField;
+use alloy::signers::k256::{FieldBytes, Scalar};
+pub use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha512;
+
+/// The BIP44 derivation path of the first Ethereum account, as used by most wallets.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(#[from] bip39::Error),
+    #[error("Mnemonics must have 12 or 24 words, not {0}")]
+    InvalidWordCount(usize),
+    #[error("Invalid derivation path: {0}")]
+    InvalidDerivationPath(String),
+    #[error("The derived key is invalid, try another derivation path")]
+    InvalidDerivedKey,
+}
+
+/// Generate a new random mnemonic of 12 or 24 words.
+pub fn random_mnemonic(word_count: usize) -> Result<Mnemonic, Error> {
+    let mut entropy = match word_count {
+        12 => vec![0u8; 16],
+        24 => vec![0u8; 32],
+        _ => return Err(Error::InvalidWordCount(word_count)),
+    };
+    rand::thread_rng().fill_bytes(&mut entropy);
+    Ok(Mnemonic::from_entropy(&entropy)?)
+}
+
+/// Derive the hex-encoded private key at the BIP32 derivation path (e.g. [`DEFAULT_DERIVATION_PATH`]) from the
+/// mnemonic phrase and its optional passphrase.
+pub fn private_key_from_mnemonic(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: &str,
+) -> Result<String, Error> {
+    let mnemonic = Mnemonic::parse(phrase)?;
+    let seed = mnemonic.to_seed(passphrase);
+    let key = derive_key(&seed, &parse_derivation_path(derivation_path)?)?;
+    Ok(key.to_bytes().encode_hex_with_prefix())
+}
+
+fn parse_derivation_path(path: &str) -> Result<Vec<u32>, Error> {
+    let invalid = || Error::InvalidDerivationPath(path.to_string());
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(invalid());
+    }
+
+    segments
+        .map(|segment| {
+            let (index, offset) = match segment.strip_suffix('\'') {
+                Some(index) => (index, HARDENED_OFFSET),
+                None => (segment, 0),
+            };
+            let index: u32 = index.parse().map_err(|_| invalid())?;
+            if index >= HARDENED_OFFSET {
+                return Err(invalid());
+            }
+            Ok(index + offset)
+        })
+        .collect()
+}
+
+/// BIP32 derivation of the private key at the path of child indexes.
+fn derive_key(seed: &[u8], path: &[u32]) -> Result<SigningKey, Error> {
+    let (mut key, mut chain_code) = split_key(hmac_sha512(b"Bitcoin seed", &[seed]))?;
+
+    for index in path {
+        let mut data = Vec::with_capacity(37);
+        if *index >= HARDENED_OFFSET {
+            data.push(0);
+            data.extend_from_slice(&key.to_bytes());
+        } else {
+            data.extend_from_slice(&key.verifying_key().to_sec1_bytes());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, child_chain_code) = split_key(hmac_sha512(&chain_code, &[&data]))?;
+        let child = *tweak.as_nonzero_scalar().as_ref() + *key.as_nonzero_scalar().as_ref();
+        key = SigningKey::from_bytes(&child.to_bytes()).map_err(|_| Error::InvalidDerivedKey)?;
+        chain_code = child_chain_code;
+    }
+
+    Ok(key)
+}
+
+/// Split the HMAC output into the private key (left half) and the chain code (right half).
+fn split_key(output: [u8; 64]) -> Result<(SigningKey, [u8; 32]), Error> {
+    let key_bytes = FieldBytes::clone_from_slice(&output[..32]);
+    // Keys out of the curve order are invalid, rather than reduced
+    if Option::<Scalar>::from(Scalar::from_repr(key_bytes)).is_none() {
+        return Err(Error::InvalidDerivedKey);
+    }
+    let key = SigningKey::from_bytes(&key_bytes).map_err(|_| Error::InvalidDerivedKey)?;
+
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&output[32..]);
+    Ok((key, chain_code))
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for data in data {
+        mac.update(data);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn test_private_key_from_mnemonic_matches_other_wallets() {
+        // The first account of the well known development mnemonic
+        let private_key =
+            private_key_from_mnemonic(TEST_MNEMONIC, "", DEFAULT_DERIVATION_PATH).unwrap();
+        assert_eq!(
+            private_key,
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+
+        let second_account =
+            private_key_from_mnemonic(TEST_MNEMONIC, "", "m/44'/60'/0'/0/1").unwrap();
+        assert_eq!(
+            second_account,
+            "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d"
+        );
+    }
+
+    #[test]
+    fn test_random_mnemonic_word_counts() {
+        assert_eq!(random_mnemonic(12).unwrap().word_count(), 12);
+        assert_eq!(random_mnemonic(24).unwrap().word_count(), 24);
+        assert!(matches!(
+            random_mnemonic(13),
+            Err(Error::InvalidWordCount(13))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_derivation_paths_are_rejected() {
+        for path in ["", "44'/60'", "m/x", "m/2147483648"] {
+            assert!(matches!(
+                private_key_from_mnemonic(TEST_MNEMONIC, "", path),
+                Err(Error::InvalidDerivationPath(_))
+            ));
+        }
+    }
+}
@@ -33,6 +33,8 @@ pub enum Error {
     #[error("Private key is invalid")]
     PrivateKeyInvalid,
     #[error(transparent)]
+    Mnemonic(#[from] crate::mnemonic::Error),
+    #[error(transparent)]
     RpcError(#[from] RpcError<TransportErrorKind>),
     #[error("Network token contract error: {0}")]
     NetworkTokenContract(#[from] network_token::Error),
@@ -68,6 +70,19 @@ impl Wallet {
         Ok(Self::new(network, wallet))
     }
 
+    /// Creates a new Wallet from the key at the BIP32 derivation path (e.g. [`crate::mnemonic::DEFAULT_DERIVATION_PATH`])
+    /// of a BIP39 mnemonic phrase and its optional passphrase.
+    pub fn new_from_mnemonic(
+        network: Network,
+        phrase: &str,
+        passphrase: &str,
+        derivation_path: &str,
+    ) -> Result<Self, Error> {
+        let private_key =
+            crate::mnemonic::private_key_from_mnemonic(phrase, passphrase, derivation_path)?;
+        Self::new_from_private_key(network, &private_key)
+    }
+
     /// Returns the address of this wallet.
     pub fn address(&self) -> Address {
         wallet_address(&self.wallet)
@@ -145,6 +160,108 @@ impl Wallet {
         let signer: PrivateKeySigner = LocalSigner::random();
         signer.to_bytes().encode_hex_with_prefix()
     }
+
+    /// Returns a watch-only view of this wallet, without its private key.
+    pub fn watch_only(&self) -> WatchOnlyWallet {
+        WatchOnlyWallet::new(self.network.clone(), self.address())
+    }
+}
+
+/// Signs with the in-process private key of the wallet.
+#[cfg(feature = "external-signer")]
+impl crate::external_signer::Signer for Wallet {
+    fn address(&self) -> Address {
+        Wallet::address(self)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: TransactionRequest,
+    ) -> Result<Vec<u8>, crate::external_signer::Error> {
+        use alloy::network::eip2718::Encodable2718;
+
+        let envelope = tx
+            .build(&self.wallet)
+            .await
+            .map_err(|err| crate::external_signer::Error::Signing(err.to_string()))?;
+        Ok(envelope.encoded_2718())
+    }
+}
+
+/// A wallet holding only an address, never a private key.
+///
+/// It can check balances and allowances, and build the calldata of unsigned transactions for an external signer to
+/// sign and submit.
+#[derive(Clone, Debug)]
+pub struct WatchOnlyWallet {
+    address: Address,
+    network: Network,
+}
+
+impl WatchOnlyWallet {
+    /// Creates a new WatchOnlyWallet watching the given address on the specific Network.
+    pub fn new(network: Network, address: Address) -> Self {
+        Self { address, network }
+    }
+
+    /// Returns the address of this wallet.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the `Network` of this wallet.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Returns the raw balance of payment tokens for this wallet.
+    pub async fn balance_of_tokens(&self) -> Result<U256, network_token::Error> {
+        balance_of_tokens(self.address, &self.network).await
+    }
+
+    /// Returns the raw balance of gas tokens for this wallet.
+    pub async fn balance_of_gas_tokens(&self) -> Result<U256, network_token::Error> {
+        balance_of_gas_tokens(self.address, &self.network).await
+    }
+
+    /// See how many tokens of this wallet may be spent by the spender.
+    pub async fn token_allowance(&self, spender: Address) -> Result<U256, network_token::Error> {
+        token_allowance(&self.network, self.address, spender).await
+    }
+
+    /// Build an unsigned transfer of a raw amount of payment tokens to another address.
+    ///
+    /// Returns the transaction calldata (input, to).
+    #[cfg(feature = "external-signer")]
+    pub fn transfer_tokens_calldata(
+        &self,
+        to: Address,
+        amount: U256,
+    ) -> (crate::common::Calldata, Address) {
+        crate::external_signer::transfer_tokens_calldata(&self.network, to, amount)
+    }
+
+    /// Build an unsigned approval for an address / smart contract to spend this wallet's payment tokens.
+    ///
+    /// Returns the transaction calldata (input, to).
+    #[cfg(feature = "external-signer")]
+    pub fn approve_to_spend_tokens_calldata(
+        &self,
+        spender: Address,
+        amount: U256,
+    ) -> (crate::common::Calldata, Address) {
+        crate::external_signer::approve_to_spend_tokens_calldata(&self.network, spender, amount)
+    }
+
+    /// Build the unsigned batch payments of quotes, along with the approval they need.
+    #[cfg(feature = "external-signer")]
+    pub fn pay_for_quotes_calldata<I: IntoIterator<Item = QuotePayment>>(
+        &self,
+        quote_payments: I,
+    ) -> Result<crate::external_signer::PayForQuotesCalldataReturnType, crate::external_signer::Error>
+    {
+        crate::external_signer::pay_for_quotes_calldata(&self.network, quote_payments)
+    }
 }
 
 /// Generate an EthereumWallet with a random private key.
@@ -376,6 +493,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_watch_only_wallet_has_the_wallet_address() {
+        let wallet = Wallet::new_with_random_wallet(Default::default());
+        let watch_only = wallet.watch_only();
+        assert_eq!(watch_only.address(), wallet.address());
+        assert_eq!(watch_only.network(), wallet.network());
+    }
+
+    #[cfg(feature = "external-signer")]
+    #[tokio::test]
+    async fn test_wallet_signs_prepared_transactions() {
+        use crate::external_signer::Signer;
+        use alloy::network::eip2718::Decodable2718;
+        use alloy::network::TransactionBuilder;
+        use alloy::rpc::types::TransactionRequest;
+
+        let wallet = Wallet::new_with_random_wallet(Default::default());
+        let tx = TransactionRequest::default()
+            .with_from(wallet.address())
+            .with_to(address!("0000000000000000000000000000000000000001"))
+            .with_nonce(0)
+            .with_chain_id(1)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(1_000_000_000)
+            .with_max_priority_fee_per_gas(1_000_000);
+
+        let signed_tx = wallet.sign_transaction(tx).await.unwrap();
+        let envelope = <Ethereum as alloy::network::Network>::TxEnvelope::decode_2718(
+            &mut signed_tx.as_slice(),
+        )
+        .unwrap();
+        let signature = envelope.as_eip1559().unwrap().signature();
+        assert_eq!(
+            signature
+                .recover_address_from_prehash(&envelope.signature_hash())
+                .unwrap(),
+            wallet.address()
+        );
+    }
+
     #[tokio::test]
     async fn test_transfer_gas_tokens() {
         let testnet = Testnet::new().await;
@@ -3,7 +3,8 @@ use crate::connection_mode::ConnectionMode;
 use ant_bootstrap::PeersArgs;
 use ant_evm::{EvmNetwork, RewardsAddress};
 use ant_node_manager::{
-    add_services::config::PortRange, config::get_node_registry_path, VerbosityLevel,
+    add_services::config::PortRange, config::get_node_registry_path,
+    upgrade_schedule::UpgradeSchedule, VerbosityLevel,
 };
 use ant_releases::{self, AntReleaseRepoActions, ReleaseType};
 use ant_service_management::NodeRegistry;
@@ -102,7 +103,8 @@ impl NodeManagement {
 /// Stop the specified services
 async fn stop_nodes(services: Vec<String>, action_sender: UnboundedSender<Action>) {
     if let Err(err) =
-        ant_node_manager::cmd::node::stop(None, vec![], services, VerbosityLevel::Minimal).await
+        ant_node_manager::cmd::node::stop(1, true, None, vec![], services, VerbosityLevel::Minimal)
+            .await
     {
         error!("Error while stopping services {err:?}");
         send_action(
@@ -218,13 +220,17 @@ pub struct UpgradeNodesArgs {
 
 async fn upgrade_nodes(args: UpgradeNodesArgs) {
     if let Err(err) = ant_node_manager::cmd::node::upgrade(
+        1,
         args.connection_timeout_s,
+        true,
         args.do_not_start,
         args.custom_bin_path,
         args.force,
         args.fixed_interval,
         args.peer_ids,
         args.provided_env_variables,
+        false,
+        UpgradeSchedule::default(),
         args.service_names,
         args.url,
         args.version,